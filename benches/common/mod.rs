@@ -0,0 +1,31 @@
+//! Shared benchmark helpers.
+use std::{env, fs, path::Path, process::Command};
+
+/// Assemble a RISC-V source of the `benches` directory into a relocatable object.
+///
+/// Uses `llvm-mc` (override its path with the `LLVM_MC` environment variable), writing the object to the
+/// target temporary directory.
+///
+/// Arguments:
+/// - `source`: Assembly file name (e.g. `dispatch.s`).
+///
+/// Returns the object file contents.
+pub fn assemble(source: &str) -> Vec<u8> {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches")
+        .join(source);
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(source)
+        .with_extension("o");
+
+    let status = Command::new(env::var_os("LLVM_MC").unwrap_or_else(|| "llvm-mc".into()))
+        .args(["-triple=riscv32", "-mattr=+m,+a,+c,-relax", "-filetype=obj"])
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .status()
+        .expect("failed to run llvm-mc (set LLVM_MC to its path)");
+    assert!(status.success(), "failed to assemble {}", input.display());
+
+    fs::read(output).unwrap()
+}
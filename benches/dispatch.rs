@@ -0,0 +1,67 @@
+//! Interpreter dispatch benchmarks.
+//!
+//! Run with `cargo bench --features test-utils`.
+//! The workload is assembled from `dispatch.s` with `llvm-mc`.
+mod common;
+
+use core::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use embive::{
+    interpreter::{memory::SliceMemory, ExitReason, Interpreter, State},
+    test_utils::run_riscv_suite,
+    transpiler::{link_objects, Config, LinkedImage},
+};
+
+/// Expected `a0` after the dispatch loop (check `dispatch.s`).
+const DISPATCH_CHECKSUM: i32 = 0x515b_fd3d;
+
+/// Run the dispatch loop, returning the final `a0`.
+///
+/// Arguments:
+/// - `window`: Fetch instructions through the code window fast path.
+fn run_dispatch(image: &LinkedImage<'_>, entry: u32, window: bool) -> i32 {
+    let mut ram = [0; 4];
+    let mut memory = SliceMemory::new(&image.code, &mut ram);
+    let mut interpreter = Interpreter::new(&mut memory, 0);
+    interpreter.program_counter = entry;
+    if window {
+        interpreter.set_code_window(Some(&image.code));
+    }
+
+    assert_eq!(
+        interpreter.run(),
+        Ok(State::Halted {
+            reason: ExitReason::Ebreak
+        })
+    );
+    interpreter.registers.cpu.a0()
+}
+
+fn dispatch(c: &mut Criterion) {
+    let object = common::assemble("dispatch.s");
+    let image = link_objects(&[&object], &Config::default()).unwrap();
+    let entry = image.symbol("_start").unwrap();
+
+    // Results must not change with the register file layout (nor the fetch path)
+    assert_eq!(run_dispatch(&image, entry, false), DISPATCH_CHECKSUM);
+    assert_eq!(run_dispatch(&image, entry, true), DISPATCH_CHECKSUM);
+
+    c.bench_function("dispatch_loop", |b| {
+        b.iter(|| run_dispatch(black_box(&image), black_box(entry), false))
+    });
+    c.bench_function("dispatch_loop_code_window", |b| {
+        b.iter(|| run_dispatch(black_box(&image), black_box(entry), true))
+    });
+}
+
+fn riscv_tests(c: &mut Criterion) {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/riscv/rv32ui");
+
+    c.bench_function("riscv_tests_rv32ui", |b| {
+        b.iter(|| run_riscv_suite(black_box(dir)).unwrap())
+    });
+}
+
+criterion_group!(benches, dispatch, riscv_tests);
+criterion_main!(benches);
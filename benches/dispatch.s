@@ -0,0 +1,32 @@
+# Dispatch benchmark: register-heavy loop, with writes to x0 (hints, discarded results).
+# Assembled by the benchmarks with llvm-mc (check common/mod.rs).
+    .text
+    .globl _start
+_start:
+    li a0, 0
+    li a1, 10000
+    la a2, buffer
+.Lloop:
+    addi a0, a0, 3
+    xor a3, a0, a1
+    slli a4, a3, 2
+    add a0, a0, a4
+    srli a0, a0, 1
+    sw a0, 0(a2)
+    lw a5, 0(a2)
+    sub a0, a4, a5
+    add a0, a0, a1
+    addi zero, a0, 1
+    add zero, a0, a1
+    lw zero, 0(a2)
+    c.mv a3, a0
+    c.add a3, a1
+    c.andi a3, 15
+    c.or a0, a3
+    addi a1, a1, -1
+    bnez a1, .Lloop
+    ebreak
+
+    .bss
+buffer:
+    .word 0
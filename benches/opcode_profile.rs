@@ -0,0 +1,156 @@
+//! Embive opcode profile of the benchmark workloads.
+//!
+//! Counts the executed instructions per Embive opcode (lowest 5 bits), to order the
+//! speed-optimized dispatch (`dispatch-speed` feature, check `src/interpreter/decode_execute.rs`).
+//!
+//! Run with `cargo bench --features test-utils --bench opcode_profile`.
+mod common;
+
+use core::num::NonZeroI32;
+use std::{fs, path::Path, vec::Vec};
+
+use embive::{
+    interpreter::{
+        memory::{MemoryType, SliceMemory, RAM_OFFSET},
+        Error, Interpreter, State, SYSCALL_ARGS,
+    },
+    transpiler::{link_objects, transpile_elf, Config},
+};
+
+/// Number of Embive opcodes.
+const OPCODES: usize = 32;
+
+/// Executed instructions per opcode, with the name of each opcode.
+struct Profile {
+    counts: [u64; OPCODES],
+    names: [Option<String>; OPCODES],
+}
+
+impl Profile {
+    fn new() -> Self {
+        Profile {
+            counts: [0; OPCODES],
+            names: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Run a guest to completion, counting each executed instruction.
+    fn run(&mut self, interpreter: &mut Interpreter<'_, SliceMemory<'_>>) {
+        let mut syscall = |nr: i32, args: &[i32; SYSCALL_ARGS], memory: &mut SliceMemory<'_>| {
+            Ok::<_, Error>(match nr {
+                // Add two numbers (`app.elf`)
+                1 => Ok(args[0] + args[1]),
+                // Load from RAM (`app.elf`)
+                2 => Ok(i32::load(memory, args[0] as u32)?),
+                // riscv-tests exit (followed by `ebreak`)
+                93 => Ok(0),
+                _ => Err(NonZeroI32::new(2).unwrap()),
+            })
+        };
+
+        loop {
+            let instruction = interpreter.fetch().unwrap();
+            let opcode = (u32::from(instruction) & 0x1F) as usize;
+            self.counts[opcode] += 1;
+            self.names[opcode].get_or_insert_with(|| {
+                let debug = format!("{instruction:?}");
+                debug.split('(').next().unwrap_or_default().into()
+            });
+
+            match interpreter.step().unwrap() {
+                State::Running
+                | State::Yielded
+                | State::Breakpoint
+                | State::SyscallPending
+                | State::Hooked(_) => {}
+                State::Called => interpreter.syscall(&mut syscall).unwrap(),
+                State::Waiting => interpreter.interrupt(10).unwrap(),
+                State::Halted { .. } | State::Panicked { .. } => break,
+            }
+        }
+    }
+
+    /// Profile a transpiled ELF file, executed from RAM (riscv-tests) or code.
+    fn elf(&mut self, elf: &[u8], from_ram: bool) {
+        let mut image = vec![0; 64 * 1024];
+        transpile_elf(elf, &mut image).unwrap();
+
+        let (code, mut ram) = match from_ram {
+            true => (Vec::new(), image),
+            false => (image, vec![0; 64 * 1024]),
+        };
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        if from_ram {
+            interpreter.program_counter = RAM_OFFSET;
+        }
+        self.run(&mut interpreter);
+    }
+
+    /// Print the opcodes sorted by frequency.
+    fn print(&self, title: &str) {
+        let total: u64 = self.counts.iter().sum();
+        let mut opcodes: Vec<usize> = (0..OPCODES).filter(|&i| self.counts[i] > 0).collect();
+        opcodes.sort_by_key(|&i| core::cmp::Reverse(self.counts[i]));
+
+        println!("\n{title} (total: {total})");
+        for opcode in opcodes {
+            println!(
+                "{opcode:>4} {:<16} {:>10} {:>6.2}%",
+                self.names[opcode].as_deref().unwrap_or("?"),
+                self.counts[opcode],
+                self.counts[opcode] as f64 * 100.0 / total as f64
+            );
+        }
+    }
+}
+
+fn main() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut workloads = Vec::new();
+
+    let mut app = Profile::new();
+    app.elf(&fs::read(root.join("tests/app.elf")).unwrap(), false);
+    app.print("app.elf");
+    workloads.push(app);
+
+    let mut dispatch = Profile::new();
+    let object = common::assemble("dispatch.s");
+    let image = link_objects(&[&object], &Config::default()).unwrap();
+    let mut ram = [0; 4];
+    let mut memory = SliceMemory::new(&image.code, &mut ram);
+    let mut interpreter = Interpreter::new(&mut memory, 0);
+    interpreter.program_counter = image.symbol("_start").unwrap();
+    dispatch.run(&mut interpreter);
+    // Synthetic loop, not included in the ranking
+    dispatch.print("dispatch.s");
+
+    for suite in ["rv32ui", "rv32uc", "rv32um", "rv32ua"] {
+        let mut profile = Profile::new();
+        let mut tests: Vec<_> = fs::read_dir(root.join("tests/riscv").join(suite))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "elf"))
+            .collect();
+        tests.sort();
+        for test in tests {
+            profile.elf(&fs::read(test).unwrap(), true);
+        }
+        profile.print(suite);
+        workloads.push(profile);
+    }
+
+    // Each workload has the same weight, regardless of its length
+    let mut ranking = Profile::new();
+    for profile in &workloads {
+        let total: u64 = profile.counts.iter().sum();
+        for opcode in 0..OPCODES {
+            // Parts per million of the workload
+            ranking.counts[opcode] += profile.counts[opcode] * 1_000_000 / total;
+            if ranking.names[opcode].is_none() {
+                ranking.names[opcode].clone_from(&profile.names[opcode]);
+            }
+        }
+    }
+    ranking.print("ranking (average share per workload)");
+}
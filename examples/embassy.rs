@@ -11,7 +11,6 @@ use core::num::NonZeroI32;
 use embive::{
     interpreter::{
         memory::{Memory, MemoryType, SliceMemory},
-        registers::CPURegister,
         Interpreter, State, SYSCALL_ARGS,
     },
     transpiler::transpile_elf,
@@ -78,35 +77,38 @@ async fn main(spawner: Spawner) {
     // Run it until ebreak, triggering an interrupt after every wfi
     loop {
         match interpreter.run().unwrap() {
-            State::Running => {
-                // Yield to other tasks after instruction limit
+            State::Running | State::Yielded => {
+                // Yield to other tasks after instruction limit (or a guest yield)
                 info!("Yielding...");
                 yield_now().await;
             }
             State::Called => interpreter.syscall_async(&mut syscall).await.unwrap(),
-            State::Waiting => interpreter.interrupt(10).unwrap(),
-            State::Halted => break,
+            // Syscalls are awaited, never deferred
+            State::SyscallPending => unreachable!(),
+            State::Waiting => match interpreter.wait_timeout() {
+                Some(ticks) => {
+                    // Guest is sleeping, a real host would arm a timer here (e.g. `embassy_time::Timer`)
+                    info!("Sleeping for {ticks} ticks...");
+                    yield_now().await;
+                }
+                None => interpreter.interrupt(10).unwrap(),
+            },
+            State::Breakpoint => info!("Breakpoint hit, resuming..."),
+            State::Hooked(id) => info!("Hook {id} hit, resuming..."),
+            State::Halted { .. } => break,
+            State::Panicked { msg_ptr, len } => {
+                panic!(
+                    "Guest panicked: {}",
+                    interpreter.panic_message(msg_ptr, len).unwrap()
+                )
+            }
         }
     }
 
     // Code does "10 + 20" using syscalls (load from ram and add numbers)
     // Check the result (Ok(30)) (A0 = 0, A1 = 30)
-    assert_eq!(
-        interpreter
-            .registers
-            .cpu
-            .get(CPURegister::A0 as u8)
-            .unwrap(),
-        0
-    );
-    assert_eq!(
-        interpreter
-            .registers
-            .cpu
-            .get(CPURegister::A1 as u8)
-            .unwrap(),
-        30
-    );
+    assert_eq!(interpreter.registers.cpu.a0(), 0);
+    assert_eq!(interpreter.registers.cpu.a1(), 30);
 
     info!("Interpreter halted!");
     std::process::exit(0);
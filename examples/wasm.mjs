@@ -0,0 +1,26 @@
+// Runs the WebAssembly example (`examples/wasm.rs`) with Node.js, as a browser-based simulator would.
+//
+// Usage: node examples/wasm.mjs <wasm.wasm> <binary.elf>
+import { readFileSync } from "node:fs";
+
+const [wasmPath, elfPath] = process.argv.slice(2);
+const { instance } = await WebAssembly.instantiate(readFileSync(wasmPath));
+const { memory, sim_input, sim_load, sim_step, sim_result } = instance.exports;
+
+// Copy the ELF file into the module memory and load it
+// (`memory.buffer` is read after `sim_input`, as allocating may grow the memory)
+const elf = readFileSync(elfPath);
+const input = sim_input(elf.length);
+new Uint8Array(memory.buffer, input, elf.length).set(elf);
+if (sim_load() !== 0) throw new Error("couldn't load the ELF file");
+
+// Step the guest, yielding to the event loop in between (the host owns the clock)
+const start = performance.now();
+for (;;) {
+  const state = sim_step(BigInt(Math.floor(performance.now() - start)));
+  if (state === 2) break;
+  if (state < 0) throw new Error(`interpreter error: ${state}`);
+  await new Promise((resolve) => setTimeout(resolve, state === 1 ? 1 : 0));
+}
+
+console.log(`Guest halted, result: ${sim_result()}`);
@@ -0,0 +1,166 @@
+//! WebAssembly Example
+//!
+//! Shows how to run the Embive interpreter inside a WebAssembly module (e.g. a browser-based device simulator).
+//! The host owns the event loop and the clock: it passes the ELF file through the module memory and drives
+//! the guest in slices of instructions, injecting the current time (in ticks) on every step.
+//! No filesystem, clock or thread is used by the module.
+//!
+//! Example:
+//! -> Build the module with
+//!    `cargo build --release --example wasm --target wasm32-unknown-unknown --no-default-features --features interpreter,transpiler,alloc`
+//! -> Run it with `node examples/wasm.mjs target/wasm32-unknown-unknown/release/examples/wasm.wasm tests/app.elf`
+use std::cell::RefCell;
+use std::num::NonZeroI32;
+
+use embive::{
+    interpreter::{
+        memory::{Memory, MemoryType, SplitMemory},
+        Interpreter, State, SYSCALL_ARGS,
+    },
+    transpiler::transpile_elf_vec,
+};
+
+type WasmMemory = SplitMemory<Vec<u8>, Vec<u8>>;
+
+/// Instructions executed per step before yielding to the host.
+const INSTRUCTION_LIMIT: u32 = 1000;
+/// Guest RAM size, in bytes.
+const RAM_SIZE: usize = 4096;
+
+/// Step result: guest is still running.
+const STEP_RUNNING: i32 = 0;
+/// Step result: guest is sleeping, call again after some time.
+const STEP_WAITING: i32 = 1;
+/// Step result: guest halted (check `sim_result`).
+const STEP_HALTED: i32 = 2;
+/// Error: no program loaded (or loaded twice).
+const ERROR_NOT_LOADED: i32 = -1;
+/// Error: transpilation failed.
+const ERROR_TRANSPILER: i32 = -2;
+/// Error: interpreter error (or guest panic).
+const ERROR_INTERPRETER: i32 = -3;
+
+/// Running guest, with the tick it should be woken up at (if sleeping).
+struct Simulator {
+    interpreter: Interpreter<'static, WasmMemory>,
+    wake_at: Option<u64>,
+}
+
+thread_local! {
+    // WebAssembly modules are single-threaded, the state lives for the whole module instance
+    static INPUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static SIMULATOR: RefCell<Option<Simulator>> = const { RefCell::new(None) };
+}
+
+// A simple syscall implementation (same as the other examples)
+fn syscall<M: Memory>(
+    nr: i32,
+    args: &[i32; SYSCALL_ARGS],
+    memory: &mut M,
+) -> Result<Result<i32, NonZeroI32>, ()> {
+    Ok(match nr {
+        // Add two numbers (arg[0] + arg[1])
+        1 => Ok(args[0] + args[1]),
+        // Load from RAM (arg[0])
+        2 => match i32::load(memory, args[0] as u32) {
+            Ok(val) => Ok(val),
+            Err(_) => Err(1.try_into().unwrap()), // Error loading
+        },
+        _ => Err(2.try_into().unwrap()), // Not implemented
+    })
+}
+
+/// Get a buffer for the ELF file, of `len` bytes, to be filled by the host before calling `sim_load`.
+#[no_mangle]
+pub extern "C" fn sim_input(len: usize) -> *mut u8 {
+    INPUT.with_borrow_mut(|input| {
+        input.clear();
+        input.resize(len, 0);
+        input.as_mut_ptr()
+    })
+}
+
+/// Transpile the ELF file (check `sim_input`) and load it.
+///
+/// The memory lives as long as the module instance, so only one program can be loaded
+/// (instantiate the module again to load another one).
+#[no_mangle]
+pub extern "C" fn sim_load() -> i32 {
+    SIMULATOR.with_borrow_mut(|simulator| {
+        if simulator.is_some() {
+            return ERROR_NOT_LOADED;
+        }
+
+        let code = match INPUT.with_borrow_mut(|input| transpile_elf_vec(input)) {
+            Ok(code) => code,
+            Err(_) => return ERROR_TRANSPILER,
+        };
+        let memory = Box::leak(Box::new(SplitMemory::new(code, vec![0; RAM_SIZE])));
+        *simulator = Some(Simulator {
+            interpreter: Interpreter::new(memory, INSTRUCTION_LIMIT),
+            wake_at: None,
+        });
+        0
+    })
+}
+
+/// Run the guest for up to `INSTRUCTION_LIMIT` instructions.
+///
+/// `now` is the host time, in ticks (e.g. milliseconds), used to wake up sleeping guests.
+#[no_mangle]
+pub extern "C" fn sim_step(now: u64) -> i32 {
+    SIMULATOR.with_borrow_mut(|simulator| {
+        let Some(Simulator {
+            interpreter,
+            wake_at,
+        }) = simulator
+        else {
+            return ERROR_NOT_LOADED;
+        };
+
+        // Keep sleeping until the deadline
+        if let Some(deadline) = *wake_at {
+            if now < deadline {
+                return STEP_WAITING;
+            }
+            *wake_at = None;
+        }
+
+        loop {
+            match interpreter.run() {
+                Ok(State::Running | State::Yielded) => return STEP_RUNNING,
+                Ok(State::Called) => {
+                    if interpreter.syscall(&mut syscall).is_err() {
+                        return ERROR_INTERPRETER;
+                    }
+                }
+                Ok(State::Waiting) => match interpreter.wait_timeout() {
+                    Some(ticks) => {
+                        *wake_at = Some(now.saturating_add(ticks));
+                        return STEP_WAITING;
+                    }
+                    None => {
+                        if interpreter.interrupt(10).is_err() {
+                            return ERROR_INTERPRETER;
+                        }
+                    }
+                },
+                Ok(State::Breakpoint | State::Hooked(_)) => {}
+                Ok(State::Halted { .. }) => return STEP_HALTED,
+                // Syscalls are handled right away, never deferred
+                Ok(State::Panicked { .. } | State::SyscallPending) | Err(_) => {
+                    return ERROR_INTERPRETER
+                }
+            }
+        }
+    })
+}
+
+/// Get the guest result (`a1`), after it halted.
+#[no_mangle]
+pub extern "C" fn sim_result() -> i32 {
+    SIMULATOR.with_borrow(|simulator| match simulator {
+        Some(simulator) => simulator.interpreter.registers.cpu.a1(),
+        None => ERROR_NOT_LOADED,
+    })
+}
@@ -0,0 +1,345 @@
+/* Embive C API, generated with cbindgen (`cbindgen --config ffi/cbindgen.toml --output ffi/include/embive.h`). */
+
+#ifndef EMBIVE_H
+#define EMBIVE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Error code returned by the C API.
+ */
+typedef enum EmbiveError {
+  /**
+   * Success.
+   */
+  EMBIVE_ERROR_OK,
+  /**
+   * A required pointer is `NULL`.
+   */
+  EMBIVE_ERROR_NULL_POINTER,
+  /**
+   * Invalid argument (e.g. register index).
+   */
+  EMBIVE_ERROR_INVALID_ARGUMENT,
+  /**
+   * Memory access out of bounds.
+   */
+  EMBIVE_ERROR_INVALID_MEMORY_ADDRESS,
+  /**
+   * Interrupts are not enabled by the interpreted code.
+   */
+  EMBIVE_ERROR_INTERRUPT_NOT_ENABLED,
+  /**
+   * No syscall callback (check [`embive_set_syscall`]).
+   */
+  EMBIVE_ERROR_NO_SYSCALL_CALLBACK,
+  /**
+   * Other interpreter error (e.g. invalid instruction).
+   */
+  EMBIVE_ERROR_INTERPRETER,
+  /**
+   * Transpilation failed.
+   */
+  EMBIVE_ERROR_TRANSPILER,
+} EmbiveError;
+
+/**
+ * Interpreter state after running (check [`State`]).
+ */
+typedef enum EmbiveState {
+  /**
+   * Instruction limit reached, call [`embive_run`] to continue running.
+   */
+  EMBIVE_STATE_RUNNING,
+  /**
+   * Syscall, call [`embive_syscall`] to handle it and then [`embive_run`] to continue running.
+   */
+  EMBIVE_STATE_CALLED,
+  /**
+   * Waiting for an interrupt (check [`embive_interrupt`]).
+   */
+  EMBIVE_STATE_WAITING,
+  /**
+   * Interpreted code halted (`ebreak`, exit syscall or instruction budget).
+   */
+  EMBIVE_STATE_HALTED,
+  /**
+   * Interpreted code stopped at a breakpoint.
+   */
+  EMBIVE_STATE_BREAKPOINT,
+  /**
+   * Interpreted code panicked.
+   */
+  EMBIVE_STATE_PANICKED,
+  /**
+   * Interpreted code yielded voluntarily (yield syscall), call [`embive_run`] to continue running.
+   */
+  EMBIVE_STATE_YIELDED,
+} EmbiveState;
+
+/**
+ * Interpreter memory (code + RAM), passed to the syscall callback.
+ */
+typedef struct EmbiveMemory EmbiveMemory;
+
+/**
+ * Interpreter storage (2048 bytes), to be allocated by the caller (e.g. a `static` or stack variable).
+ *
+ * The size is fixed (part of the ABI), reserving room for the interpreter to grow across versions and features.
+ * The size actually used is returned by [`embive_interpreter_size`], firmware linking against a prebuilt library can
+ * check it at runtime.
+ *
+ * Must not be moved after [`embive_interpreter_init`].
+ */
+typedef struct EmbiveInterpreter {
+  uint64_t _storage[256];
+} EmbiveInterpreter;
+
+/**
+ * Syscall callback.
+ *
+ * Arguments:
+ * - `nr`: Syscall number.
+ * - `args`: Syscall arguments ([`SYSCALL_ARGS`] values).
+ * - `memory`: Interpreter memory (check [`embive_memory_load`] and [`embive_memory_store`]).
+ * - `user_data`: User data passed to [`embive_set_syscall`].
+ * - `value`: Syscall return value (`a1`), on success.
+ *
+ * Returns:
+ * - `0`: Success (`value` is returned to the interpreted code).
+ * - Other: Error code returned to the interpreted code (`a0`).
+ */
+typedef int32_t (*EmbiveSyscallFn)(int32_t nr,
+                                   const int32_t *args,
+                                   struct EmbiveMemory *memory,
+                                   void *user_data,
+                                   int32_t *value);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Get the size of the interpreter storage actually used, in bytes.
+ *
+ * Always less than or equal to `sizeof(EmbiveInterpreter)` for a matching header, a larger size means the header and
+ * the library don't match.
+ */
+size_t embive_interpreter_size(void);
+
+/**
+ * Initialize an interpreter.
+ *
+ * Arguments:
+ * - `interpreter`: Interpreter storage (must not be moved afterwards).
+ * - `code`: Code buffer (Embive image), mapped to address `0x00000000`.
+ * - `code_len`: Code buffer length, in bytes.
+ * - `ram`: RAM buffer, mapped to [`crate::interpreter::memory::RAM_OFFSET`].
+ * - `ram_len`: RAM buffer length, in bytes.
+ * - `instruction_limit`: Instructions executed per [`embive_run`] (`0` for no limit).
+ *
+ * # Safety
+ * The code and RAM buffers must be valid (and not accessed elsewhere) for as long as the interpreter is used.
+ */
+enum EmbiveError embive_interpreter_init(struct EmbiveInterpreter *interpreter,
+                                         const uint8_t *code,
+                                         size_t code_len,
+                                         uint8_t *ram,
+                                         size_t ram_len,
+                                         uint32_t instruction_limit);
+
+/**
+ * Load a new Embive image, resetting the interpreter (the RAM is kept).
+ *
+ * Arguments:
+ * - `interpreter`: Initialized interpreter.
+ * - `code`: Code buffer (Embive image).
+ * - `code_len`: Code buffer length, in bytes.
+ *
+ * # Safety
+ * The code buffer must be valid for as long as the interpreter is used.
+ */
+enum EmbiveError embive_load(struct EmbiveInterpreter *interpreter,
+                             const uint8_t *code,
+                             size_t code_len);
+
+/**
+ * Reset the interpreter (program counter, registers and pending state).
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+enum EmbiveError embive_reset(struct EmbiveInterpreter *interpreter);
+
+/**
+ * Run the interpreter until it stops (check [`EmbiveState`]).
+ *
+ * Arguments:
+ * - `interpreter`: Initialized interpreter.
+ * - `state`: Interpreter state, on success.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`], `state` must be valid for writes.
+ */
+enum EmbiveError embive_run(struct EmbiveInterpreter *interpreter,
+                            enum EmbiveState *state);
+
+/**
+ * Set the syscall callback, used by [`embive_syscall`].
+ *
+ * Arguments:
+ * - `interpreter`: Initialized interpreter.
+ * - `callback`: Syscall callback (`NULL` to remove it).
+ * - `user_data`: Opaque pointer passed to the callback.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+enum EmbiveError embive_set_syscall(struct EmbiveInterpreter *interpreter,
+                                    EmbiveSyscallFn callback,
+                                    void *user_data);
+
+/**
+ * Handle the pending syscall ([`EmbiveState::Called`]) with the syscall callback.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+enum EmbiveError embive_syscall(struct EmbiveInterpreter *interpreter);
+
+/**
+ * Trigger an interrupt (check [`crate::interpreter::Interpreter::interrupt`]).
+ *
+ * Arguments:
+ * - `interpreter`: Initialized interpreter.
+ * - `value`: Value passed to the interrupt handler.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+enum EmbiveError embive_interrupt(struct EmbiveInterpreter *interpreter, int32_t value);
+
+/**
+ * Read a CPU register.
+ *
+ * Arguments:
+ * - `interpreter`: Initialized interpreter.
+ * - `index`: Register index (`0` to `31`, e.g. `10` for `a0`).
+ * - `value`: Register value, on success.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`], `value` must be valid for writes.
+ */
+enum EmbiveError embive_get_register(struct EmbiveInterpreter *interpreter,
+                                     uint8_t index,
+                                     int32_t *value);
+
+/**
+ * Write a CPU register.
+ *
+ * Arguments:
+ * - `interpreter`: Initialized interpreter.
+ * - `index`: Register index (`0` to `31`, e.g. `10` for `a0`).
+ * - `value`: Register value.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+enum EmbiveError embive_set_register(struct EmbiveInterpreter *interpreter,
+                                     uint8_t index,
+                                     int32_t value);
+
+/**
+ * Read the program counter.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`], `value` must be valid for writes.
+ */
+enum EmbiveError embive_get_pc(struct EmbiveInterpreter *interpreter,
+                               uint32_t *value);
+
+/**
+ * Write the program counter.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+enum EmbiveError embive_set_pc(struct EmbiveInterpreter *interpreter, uint32_t value);
+
+/**
+ * Get the interpreter memory (check [`embive_memory_load`] and [`embive_memory_store`]).
+ *
+ * Returns `NULL` if `interpreter` is `NULL`. The memory must not be used while the interpreter is running.
+ *
+ * # Safety
+ * `interpreter` must be initialized with [`embive_interpreter_init`].
+ */
+struct EmbiveMemory *embive_interpreter_memory(struct EmbiveInterpreter *interpreter);
+
+/**
+ * Read bytes from the interpreter memory (code or RAM).
+ *
+ * Arguments:
+ * - `memory`: Interpreter memory.
+ * - `address`: Start address.
+ * - `buffer`: Output buffer.
+ * - `len`: Number of bytes to read.
+ *
+ * # Safety
+ * `memory` must come from [`embive_interpreter_memory`] or the syscall callback, `buffer` must be valid
+ * for writes of `len` bytes.
+ */
+enum EmbiveError embive_memory_load(struct EmbiveMemory *memory,
+                                    uint32_t address,
+                                    uint8_t *buffer,
+                                    size_t len);
+
+/**
+ * Write bytes to the interpreter memory (RAM only).
+ *
+ * Arguments:
+ * - `memory`: Interpreter memory.
+ * - `address`: Start address.
+ * - `data`: Data to write.
+ * - `len`: Number of bytes to write.
+ *
+ * # Safety
+ * `memory` must come from [`embive_interpreter_memory`] or the syscall callback, `data` must be valid
+ * for reads of `len` bytes.
+ */
+enum EmbiveError embive_memory_store(struct EmbiveMemory *memory,
+                                     uint32_t address,
+                                     const uint8_t *data,
+                                     size_t len);
+
+#if defined(EMBIVE_TRANSPILER)
+/**
+ * Transpile a RISC-V ELF file to an Embive image.
+ *
+ * Arguments:
+ * - `elf`: ELF file.
+ * - `elf_len`: ELF file length, in bytes.
+ * - `output`: Output buffer (Embive image).
+ * - `output_len`: Output buffer length, in bytes.
+ * - `size`: Embive image size, in bytes, on success.
+ *
+ * # Safety
+ * `elf` must be valid for reads of `elf_len` bytes, `output` for writes of `output_len` bytes
+ * and `size` for writes.
+ */
+enum EmbiveError embive_transpile_elf(const uint8_t *elf,
+                                      size_t elf_len,
+                                      uint8_t *output,
+                                      size_t output_len,
+                                      size_t *size);
+#endif
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* EMBIVE_H */
@@ -0,0 +1,14 @@
+//! C bindings for Embive.
+//!
+//! Static library exporting the [`embive::ffi`] API, declared in `include/embive.h`.
+#![no_std]
+
+pub use embive::ffi::*;
+
+/// The interpreter never panics, a panic can only come from a bug (halt).
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo<'_>) -> ! {
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,88 @@
+/* Smoke test of the C API: runs `tests/app.elf` (10 + 20 using syscalls), as the Rust examples do. */
+#include <stdio.h>
+#include <string.h>
+
+#include "embive.h"
+
+#define RAM_OFFSET 0x80000000u
+
+static uint8_t elf[64 * 1024];
+static uint8_t code[16 * 1024];
+static uint8_t ram[4 * 1024];
+static EmbiveInterpreter interpreter;
+
+static int32_t handle_syscall(int32_t nr, const int32_t *args, EmbiveMemory *memory, void *user_data, int32_t *value) {
+    int32_t *calls = user_data;
+    uint8_t bytes[4];
+
+    (*calls)++;
+    switch (nr) {
+    case 1: /* Add two numbers (arg[0] + arg[1]) */
+        *value = args[0] + args[1];
+        return 0;
+    case 2: /* Load from RAM (arg[0]) */
+        if (embive_memory_load(memory, (uint32_t)args[0], bytes, sizeof(bytes)) != EMBIVE_ERROR_OK) {
+            return 1; /* Error loading */
+        }
+        *value = (int32_t)((uint32_t)bytes[0] | (uint32_t)bytes[1] << 8 | (uint32_t)bytes[2] << 16 | (uint32_t)bytes[3] << 24);
+        return 0;
+    default:
+        return 2; /* Not implemented */
+    }
+}
+
+#define CHECK(expr)                                                \
+    do {                                                           \
+        if (!(expr)) {                                             \
+            fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #expr); \
+            return 1;                                              \
+        }                                                          \
+    } while (0)
+
+int main(int argc, char **argv) {
+    FILE *file;
+    size_t elf_len, code_len;
+    EmbiveState state = EMBIVE_STATE_RUNNING;
+    int32_t calls = 0, a0, a1;
+
+    CHECK(argc == 2);
+    CHECK(embive_interpreter_size() <= sizeof(EmbiveInterpreter));
+    CHECK((file = fopen(argv[1], "rb")) != NULL);
+    elf_len = fread(elf, 1, sizeof(elf), file);
+    fclose(file);
+
+    CHECK(embive_transpile_elf(elf, elf_len, code, sizeof(code), &code_len) == EMBIVE_ERROR_OK);
+    CHECK(embive_interpreter_init(&interpreter, code, code_len, ram, sizeof(ram), 10) == EMBIVE_ERROR_OK);
+    CHECK(embive_set_syscall(&interpreter, handle_syscall, &calls) == EMBIVE_ERROR_OK);
+
+    /* Run it until ebreak, triggering an interrupt after every wfi */
+    while (state != EMBIVE_STATE_HALTED) {
+        CHECK(embive_run(&interpreter, &state) == EMBIVE_ERROR_OK);
+        switch (state) {
+        case EMBIVE_STATE_CALLED:
+            CHECK(embive_syscall(&interpreter) == EMBIVE_ERROR_OK);
+            break;
+        case EMBIVE_STATE_WAITING:
+            CHECK(embive_interrupt(&interpreter, 10) == EMBIVE_ERROR_OK);
+            break;
+        case EMBIVE_STATE_PANICKED:
+            CHECK(!"guest panicked");
+            break;
+        default:
+            break;
+        }
+    }
+
+    /* Code does "10 + 20" using syscalls (load from ram and add numbers) */
+    CHECK(embive_get_register(&interpreter, 10, &a0) == EMBIVE_ERROR_OK);
+    CHECK(embive_get_register(&interpreter, 11, &a1) == EMBIVE_ERROR_OK);
+    CHECK(a0 == 0 && a1 == 30);
+    CHECK(calls > 0);
+
+    /* Out of bounds accesses are reported */
+    CHECK(embive_memory_store(embive_interpreter_memory(&interpreter), RAM_OFFSET + sizeof(ram), (const uint8_t *)"x", 1) == EMBIVE_ERROR_INVALID_MEMORY_ADDRESS);
+    CHECK(embive_run(NULL, &state) == EMBIVE_ERROR_NULL_POINTER);
+
+    printf("Guest halted, result: %d (%d syscalls)\n", a1, calls);
+    return 0;
+}
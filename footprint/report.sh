@@ -0,0 +1,114 @@
+#!/bin/sh
+# Static memory footprint report of the Embive interpreter.
+#
+# Builds the footprint firmware (`size` profile) for each feature set of the matrix, printing its
+# `.text`/`.rodata` sizes, and attributes the minimal build to source modules:
+# - `.text`: bytes of code generated for each source file (inlined code included), from the line tables.
+# - `.rodata`: constants (e.g. dispatch tables) referenced from each source file (x86_64 hosts only).
+#
+# Fails if the minimal interpreter (`.text` + `.rodata`) is over `BUDGET` bytes.
+#
+# Requires `llvm-size`, `llvm-dwarfdump` and `llvm-objdump` (`llvm-tools`).
+set -eu
+
+cd "$(dirname "$0")"
+ROOT=$(cd .. && pwd)
+BIN=target/size/embive-footprint
+BUDGET=${BUDGET:-10240}
+TOP=${TOP:-15}
+
+# Section size (in bytes) of the built firmware
+section() {
+    llvm-size -A "$BIN" | awk -v name="$1" '$1 == name { print $2 }'
+}
+
+echo "| Features                   | .text | .rodata | Total |"
+echo "|----------------------------|-------|---------|-------|"
+for features in "" "dispatch-speed" "peripherals" "peripherals,dispatch-speed"; do
+    cargo build --quiet --profile size --features "$features"
+    text=$(section .text)
+    rodata=$(section .rodata)
+    printf "| %-26s | %5d | %7d | %5d |\n" "${features:-(minimal)}" "$text" "$rodata" $((text + rodata))
+done
+
+cargo build --quiet --profile size
+text=$(section .text)
+rodata=$(section .rodata)
+
+echo
+echo ".text per source file (minimal, top $TOP):"
+llvm-dwarfdump --debug-line "$BIN" | awk -v root="$ROOT/" '
+    function hex(s,    i, n) {
+        n = 0
+        s = tolower(substr(s, 3))
+        for (i = 1; i <= length(s); i++) n = n * 16 + index("0123456789abcdef", substr(s, i, 1)) - 1
+        return n
+    }
+    /^debug_line\[/ { delete dirs; delete files; prev = -1 }
+    /^include_directories\[/ { split($0, d, "\""); sub(/^include_directories\[ */, ""); dirs[$1 + 0] = d[2] }
+    /^file_names\[/ { sub(/^file_names\[ */, ""); index_ = $1 + 0 }
+    /^ +name: / { split($0, n, "\""); name = n[2] }
+    /^ +dir_index: / {
+        path = ($2 + 0 > 0 ? dirs[$2 + 0] "/" : "") name
+        sub(root, "", path)
+        sub(/^.*\/library\//, "library/", path)
+        files[index_] = path
+    }
+    /^0x/ {
+        address = hex($1)
+        if (prev >= 0) size[files[file]] += address - prev
+        prev = ($0 ~ /end_sequence/) ? -1 : address
+        file = $4
+    }
+    END { for (f in size) if (size[f] > 0) printf "%7d  %s\n", size[f], f }
+' | sort -rn | head -n "$TOP"
+
+echo
+echo ".rodata per referencing source file (minimal):"
+llvm-objdump -h "$BIN" | awk '$2 == ".rodata" { print $4, $3 }' | {
+    read -r start length
+    llvm-objdump -d -l --no-show-raw-insn "$BIN" | awk -v root="$ROOT/" -v start="0x$start" -v length_="0x$length" '
+        function hex(s,    i, n) {
+            n = 0
+            s = tolower(substr(s, 3))
+            for (i = 1; i <= length(s); i++) n = n * 16 + index("0123456789abcdef", substr(s, i, 1)) - 1
+            return n
+        }
+        BEGIN { begin = hex(start); end = begin + hex(length_) }
+        /^; \// {
+            file = $2
+            sub(/:[0-9]+$/, "", file)
+            sub(root, "", file)
+            sub(/^.*\/library\//, "library/", file)
+        }
+        /# 0x[0-9a-f]+/ {
+            match($0, /# 0x[0-9a-f]+/)
+            address = hex(substr($0, RSTART + 2, RLENGTH - 2))
+            if (address >= begin && address < end && !(address in owner)) owner[address] = file
+        }
+        END {
+            # Each constant spans up to the next referenced one
+            count = 0
+            for (a in owner) addresses[++count] = a + 0
+            for (i = 2; i <= count; i++) {
+                a = addresses[i]
+                for (j = i - 1; j > 0 && addresses[j] > a; j--) addresses[j + 1] = addresses[j]
+                addresses[j + 1] = a
+            }
+            for (i = 1; i <= count; i++) {
+                next_ = (i < count) ? addresses[i + 1] : end
+                size[owner[addresses[i]]] += next_ - addresses[i]
+            }
+            if (count > 0 && addresses[1] > begin) size["(unreferenced)"] += addresses[1] - begin
+            for (f in size) printf "%7d  %s\n", size[f], f
+        }
+    '
+} | sort -rn
+
+total=$((text + rodata))
+echo
+echo "Minimal interpreter: $total bytes (budget: $BUDGET bytes)"
+if [ "$total" -gt "$BUDGET" ]; then
+    echo "Over budget by $((total - BUDGET)) bytes"
+    exit 1
+fi
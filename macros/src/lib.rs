@@ -0,0 +1,91 @@
+//! Build-time transpilation for Embive.
+//!
+//! [`include_riscv!`] transpiles a RISC-V ELF file while compiling the host, embedding the Embive image
+//! as a static. No transpilation is done on the device: the image is read from flash, without a RAM copy.
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::{memory::SliceMemory, Interpreter};
+//!
+//! static CODE: &[u8] = embive_macros::include_riscv!("../tests/app.elf");
+//!
+//! let mut ram = [0; 1024];
+//! let mut memory = SliceMemory::new(CODE, &mut ram);
+//! let mut interpreter = Interpreter::new(&mut memory, 0);
+//! ```
+use std::path::PathBuf;
+
+use proc_macro::{Literal, TokenStream, TokenTree};
+
+/// Transpile a RISC-V ELF file at build time, expanding to the Embive image (`&'static [u8; N]`).
+///
+/// The path is relative to the crate root (`CARGO_MANIFEST_DIR`), as the calling file isn't known on stable Rust.
+/// The crate is rebuilt when the ELF file changes.
+///
+/// Arguments:
+/// - `path`: String literal with the path to the RISC-V ELF file.
+///
+/// Transpilation errors (and unreadable files) are reported as compile errors.
+#[proc_macro]
+pub fn include_riscv(input: TokenStream) -> TokenStream {
+    match include_riscv_impl(input) {
+        Ok(output) => output,
+        Err(msg) => compile_error(&msg),
+    }
+}
+
+/// Implementation of [`include_riscv!`].
+///
+/// Returns:
+/// - `Ok(TokenStream)`: The Embive image expression.
+/// - `Err(String)`: Error message.
+fn include_riscv_impl(input: TokenStream) -> Result<TokenStream, String> {
+    let path = parse_path(input)?;
+
+    let root = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| "include_riscv!: CARGO_MANIFEST_DIR is not set".to_string())?;
+    let path = PathBuf::from(root).join(path);
+
+    let elf = std::fs::read(&path)
+        .map_err(|e| format!("include_riscv!: couldn't read {}: {e}", path.display()))?;
+    let image = embive::transpiler::transpile_elf_vec(&elf)
+        .map_err(|e| format!("include_riscv!: couldn't transpile {}: {e}", path.display()))?;
+
+    // `include_bytes!` makes Cargo track the ELF file, so the image is regenerated when it changes
+    let path = Literal::string(&path.to_string_lossy());
+    let image = Literal::byte_string(&image);
+    format!("{{ const _: &[u8] = include_bytes!({path}); {image} }}")
+        .parse()
+        .map_err(|e| format!("include_riscv!: {e}"))
+}
+
+/// Parse the macro input (a single string literal).
+///
+/// Returns:
+/// - `Ok(String)`: The path.
+/// - `Err(String)`: Error message.
+fn parse_path(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter();
+    let path = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        // Path passed through another macro (e.g. `$path:literal`)
+        (Some(TokenTree::Group(group)), None) => return parse_path(group.stream()),
+        _ => String::new(),
+    };
+
+    match path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+    {
+        Some(path) if !path.contains('\\') => Ok(path.to_string()),
+        _ => Err("include_riscv!: expected a string literal path (without escapes)".to_string()),
+    }
+}
+
+/// Expand to a `compile_error!` with a message.
+fn compile_error(msg: &str) -> TokenStream {
+    // Unwrap is safe because the input is a valid macro invocation
+    format!("compile_error!({})", Literal::string(msg))
+        .parse()
+        .unwrap()
+}
@@ -0,0 +1,17 @@
+use embive::transpiler::transpile_elf_vec;
+use embive_macros::include_riscv;
+
+static APP: &[u8] = include_riscv!("../tests/app.elf");
+
+#[test]
+fn test_include_riscv() {
+    let elf = include_bytes!("../../tests/app.elf");
+    assert_eq!(APP, transpile_elf_vec(elf).unwrap());
+}
+
+#[test]
+fn test_include_riscv_const() {
+    const IMAGE: &[u8] = include_riscv!("../tests/test.elf");
+    let elf = include_bytes!("../../tests/test.elf");
+    assert_eq!(IMAGE, transpile_elf_vec(elf).unwrap());
+}
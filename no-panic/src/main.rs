@@ -0,0 +1,72 @@
+//! Panic-never check for the Embive interpreter.
+//!
+//! Any panic path left in the interpreter after optimization references `embive_interpreter_panicked`,
+//! which is never defined: the release build fails to link, listing the functions that can panic.
+#![no_std]
+#![no_main]
+
+use core::{hint::black_box, num::NonZeroI32, panic::PanicInfo};
+
+use embive::interpreter::{
+    memory::{Memory, MemoryType, SliceMemory},
+    Error, Interpreter, State, SYSCALL_ARGS,
+};
+
+const MAX_INSTRUCTIONS: u32 = 2048;
+const CODE_SIZE: usize = 1024;
+const RAM_SIZE: usize = 1024;
+
+fn syscall<M: Memory>(
+    nr: i32,
+    args: &[i32; SYSCALL_ARGS],
+    memory: &mut M,
+) -> Result<Result<i32, NonZeroI32>, Error> {
+    Ok(match nr {
+        1 => Ok(args[0].wrapping_add(args[1])),
+        2 => Ok(i32::load(memory, args[0] as u32)?),
+        _ => Err(NonZeroI32::MIN),
+    })
+}
+
+/// Run the guest code (never executed, the check is done at link time).
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    black_box(run());
+    loop {}
+}
+
+fn run() -> i32 {
+    // Guest code, unknown to the optimizer
+    let code = black_box(&[0; CODE_SIZE][..]);
+    let mut ram = [0; RAM_SIZE];
+    let mut memory = SliceMemory::new(code, &mut ram);
+    let mut interpreter = Interpreter::new(&mut memory, black_box(MAX_INSTRUCTIONS));
+
+    loop {
+        let result = match interpreter.run() {
+            Ok(State::Running | State::Yielded) => Ok(()),
+            Ok(State::Called) => interpreter.syscall(&mut syscall),
+            Ok(State::Waiting) => interpreter.interrupt(black_box(0)),
+            Ok(State::Panicked { msg_ptr, len }) => {
+                interpreter.panic_message(msg_ptr, len).map(|_| ())
+            }
+            Ok(State::Breakpoint | State::SyscallPending | State::Hooked(_)) => Ok(()),
+            Ok(State::Halted { .. }) | Err(_) => break,
+        };
+
+        if result.is_err() {
+            break;
+        }
+    }
+
+    interpreter.registers.cpu.a0()
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    extern "C" {
+        fn embive_interpreter_panicked() -> !;
+    }
+
+    unsafe { embive_interpreter_panicked() }
+}
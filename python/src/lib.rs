@@ -0,0 +1,324 @@
+//! Python bindings for Embive.
+//!
+//! Exposes the interpreter, its memory and the transpiler to Python, so guest test scenarios can be
+//! scripted (e.g. from pytest) instead of writing Rust harnesses.
+use std::{fmt::Display, mem::ManuallyDrop, num::NonZeroI32, ptr::NonNull};
+
+use embive::interpreter::{
+    memory::{Memory as _, MemoryType, SplitMemory, RAM_OFFSET},
+    State as InterpreterState, SYSCALL_ARGS,
+};
+use pyo3::{create_exception, exceptions::PyRuntimeError, prelude::*, types::PyBytes};
+
+/// Guest memory, owned by the interpreter.
+type GuestMemory = SplitMemory<Vec<u8>, Vec<u8>>;
+
+create_exception!(
+    embive,
+    EmbiveError,
+    PyRuntimeError,
+    "Interpreter or transpiler error."
+);
+create_exception!(
+    embive,
+    SyscallError,
+    pyo3::exceptions::PyException,
+    "Raised by a syscall callback to return an error code (non-zero `int` argument) to the guest."
+);
+
+/// Map an Embive error to a Python exception.
+fn error(error: impl Display) -> PyErr {
+    EmbiveError::new_err(error.to_string())
+}
+
+/// Interpreter state (check `embive::interpreter::State`).
+#[pyclass(eq, eq_int, module = "embive")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    /// Instruction limit reached, call `run` to continue.
+    Running,
+    /// Syscall pending, handle it (`syscall`, `complete_syscall`) and call `run` to continue.
+    Called,
+    /// Waiting for an interrupt (`interrupt`).
+    Waiting,
+    /// Guest halted (check `exit_code`).
+    Halted,
+    /// Guest stopped at a breakpoint.
+    Breakpoint,
+    /// Guest panicked (check `panic_message`).
+    Panicked,
+    /// Guest yielded voluntarily (yield syscall), call `run` to continue.
+    Yielded,
+}
+
+/// Transpile a RISC-V ELF file to an Embive image.
+///
+/// Arguments:
+/// - `elf`: ELF file contents.
+///
+/// Returns the Embive image, raising `EmbiveError` on failure.
+#[pyfunction]
+fn transpile_elf<'py>(py: Python<'py>, elf: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let image = embive::transpiler::transpile_elf_vec(elf).map_err(error)?;
+    Ok(PyBytes::new(py, &image))
+}
+
+/// Embive interpreter, owning its memory (code and RAM).
+#[pyclass(unsendable, module = "embive")]
+pub struct Interpreter {
+    /// Interpreter, borrowing `memory` (dropped first).
+    interpreter: ManuallyDrop<embive::interpreter::Interpreter<'static, GuestMemory>>,
+    /// Owned memory (from `Box::into_raw`).
+    memory: NonNull<GuestMemory>,
+    /// Panic message location (`msg_ptr`, `len`) of the last run.
+    panic: Option<(u32, u32)>,
+    /// Exit code (exit syscall) of the last run.
+    exit_code: Option<i32>,
+}
+
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        // SAFETY: The interpreter is dropped before the memory it borrows, which was allocated in `new`.
+        unsafe {
+            ManuallyDrop::drop(&mut self.interpreter);
+            drop(Box::from_raw(self.memory.as_ptr()));
+        }
+    }
+}
+
+#[pymethods]
+impl Interpreter {
+    /// Create an interpreter.
+    ///
+    /// Arguments:
+    /// - `code`: Embive image (check `transpile_elf`), mapped to address `0`.
+    /// - `ram_size`: RAM size, in bytes (mapped to `RAM_OFFSET`).
+    /// - `instruction_limit`: Instructions executed per `run` (`0` for no limit).
+    #[new]
+    #[pyo3(signature = (code, ram_size = 4096, instruction_limit = 0))]
+    fn new(code: Vec<u8>, ram_size: usize, instruction_limit: u32) -> Self {
+        let memory = Box::into_raw(Box::new(SplitMemory::new(code, vec![0; ram_size])));
+        // SAFETY: The memory is only freed on drop, after the interpreter.
+        let interpreter =
+            embive::interpreter::Interpreter::new(unsafe { &mut *memory }, instruction_limit);
+        Self {
+            interpreter: ManuallyDrop::new(interpreter),
+            // Unwrap is safe because `Box::into_raw` never returns a null pointer
+            memory: NonNull::new(memory).unwrap(),
+            panic: None,
+            exit_code: None,
+        }
+    }
+
+    /// Run the interpreter until it stops, returning its `State`.
+    fn run(&mut self) -> PyResult<State> {
+        let state = self.interpreter.run().map_err(error)?;
+        self.panic = None;
+        self.exit_code = None;
+        Ok(match state {
+            InterpreterState::Running => State::Running,
+            // Syscalls are not deferred from Python
+            InterpreterState::Called | InterpreterState::SyscallPending => State::Called,
+            InterpreterState::Waiting => State::Waiting,
+            InterpreterState::Halted { reason } => {
+                self.exit_code = reason.code();
+                State::Halted
+            }
+            // Hooks are not set from Python
+            InterpreterState::Breakpoint | InterpreterState::Hooked(_) => State::Breakpoint,
+            InterpreterState::Yielded => State::Yielded,
+            InterpreterState::Panicked { msg_ptr, len } => {
+                self.panic = Some((msg_ptr, len));
+                State::Panicked
+            }
+        })
+    }
+
+    /// Reset the interpreter (program counter, registers and pending state). The RAM is kept.
+    fn reset(&mut self) {
+        self.interpreter.reset();
+        self.panic = None;
+        self.exit_code = None;
+    }
+
+    /// Get the pending syscall, as `(nr, args)`, or `None`.
+    fn pending_syscall(&self) -> Option<(i32, [i32; SYSCALL_ARGS])> {
+        self.interpreter.pending_syscall()
+    }
+
+    /// Complete the pending syscall, returning `value` to the guest.
+    fn complete_syscall(&mut self, value: i32) -> PyResult<()> {
+        self.interpreter.complete_syscall(Ok(value)).map_err(error)
+    }
+
+    /// Reject the pending syscall, returning the error `code` (non-zero) to the guest.
+    fn reject_syscall(&mut self, code: i32) -> PyResult<()> {
+        let code = NonZeroI32::new(code).ok_or_else(|| error("error code must be non-zero"))?;
+        self.interpreter.reject_syscall(code).map_err(error)
+    }
+
+    /// Handle the pending syscall with `callback(nr, args, memory)`.
+    ///
+    /// The callback returns the value for the guest (`int`), or raises `SyscallError(code)` to return an error.
+    /// Other exceptions are propagated (the syscall stays pending).
+    fn syscall(slf: &Bound<'_, Self>, callback: &Bound<'_, PyAny>) -> PyResult<()> {
+        let py = slf.py();
+        let (nr, args) = slf
+            .borrow()
+            .interpreter
+            .pending_syscall()
+            .ok_or_else(|| error("no pending syscall"))?;
+
+        // No borrow is held while the callback runs, so it can use the interpreter (e.g. its memory)
+        let memory = Memory {
+            interpreter: slf.clone().unbind(),
+        };
+        match callback.call1((nr, args.to_vec(), memory)) {
+            Ok(value) => {
+                let value = value.extract::<i32>()?;
+                slf.borrow_mut().complete_syscall(value)
+            }
+            Err(e) if e.is_instance_of::<SyscallError>(py) => {
+                let code = e.value(py).getattr("args")?.get_item(0)?.extract::<i32>()?;
+                slf.borrow_mut().reject_syscall(code)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Trigger an interrupt, with `value` passed to the guest handler.
+    fn interrupt(&mut self, value: i32) -> PyResult<()> {
+        self.interpreter.interrupt(value).map_err(error)
+    }
+
+    /// Read a CPU register (`0` to `31`, e.g. `10` for `a0`).
+    fn get_register(&self, index: u8) -> PyResult<i32> {
+        self.interpreter.registers.cpu.get(index).map_err(error)
+    }
+
+    /// Write a CPU register (`0` to `31`, e.g. `10` for `a0`).
+    fn set_register(&mut self, index: u8, value: i32) -> PyResult<()> {
+        self.interpreter
+            .registers
+            .cpu
+            .set(index, value)
+            .map_err(error)
+    }
+
+    /// Program counter.
+    #[getter]
+    fn get_pc(&self) -> u32 {
+        self.interpreter.program_counter
+    }
+
+    #[setter]
+    fn set_pc(&mut self, value: u32) {
+        self.interpreter.program_counter = value;
+    }
+
+    /// Instructions executed per `run` (`0` for no limit).
+    #[getter]
+    fn get_instruction_limit(&self) -> u32 {
+        self.interpreter.instruction_limit
+    }
+
+    #[setter]
+    fn set_instruction_limit(&mut self, value: u32) {
+        self.interpreter.instruction_limit = value;
+    }
+
+    /// Sleep requested by the guest, in ticks (`None` if waiting for an interrupt).
+    #[getter]
+    fn wait_timeout(&self) -> Option<u64> {
+        self.interpreter.wait_timeout()
+    }
+
+    /// Guest memory (code and RAM).
+    #[getter]
+    fn memory(slf: &Bound<'_, Self>) -> Memory {
+        Memory {
+            interpreter: slf.clone().unbind(),
+        }
+    }
+
+    /// Exit code of the last run (`None` if the guest didn't exit through the exit syscall).
+    fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Panic message of the last run (`None` if the guest didn't panic).
+    fn panic_message(&mut self) -> PyResult<Option<String>> {
+        let Some((msg_ptr, len)) = self.panic else {
+            return Ok(None);
+        };
+        let msg = self
+            .interpreter
+            .panic_message(msg_ptr, len)
+            .map_err(error)?;
+        Ok(Some(msg.to_string()))
+    }
+}
+
+/// View of the interpreter memory (code at address `0`, RAM at `RAM_OFFSET`).
+#[pyclass(unsendable, module = "embive")]
+pub struct Memory {
+    interpreter: Py<Interpreter>,
+}
+
+#[pymethods]
+impl Memory {
+    /// Read `size` bytes at `address`.
+    fn load<'py>(
+        &self,
+        py: Python<'py>,
+        address: u32,
+        size: usize,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let mut interpreter = self.interpreter.borrow_mut(py);
+        let bytes = interpreter
+            .interpreter
+            .memory
+            .load_bytes(address, size)
+            .map_err(error)?;
+        Ok(PyBytes::new(py, bytes))
+    }
+
+    /// Write `data` at `address` (RAM only).
+    fn store(&self, py: Python<'_>, address: u32, data: &[u8]) -> PyResult<()> {
+        let mut interpreter = self.interpreter.borrow_mut(py);
+        interpreter
+            .interpreter
+            .memory
+            .store_bytes(address, data)
+            .map_err(error)
+    }
+
+    /// Read a little-endian `u32` at `address`.
+    fn load_u32(&self, py: Python<'_>, address: u32) -> PyResult<u32> {
+        let mut interpreter = self.interpreter.borrow_mut(py);
+        u32::load(&mut *interpreter.interpreter.memory, address).map_err(error)
+    }
+
+    /// Write a little-endian `u32` at `address` (RAM only).
+    fn store_u32(&self, py: Python<'_>, address: u32, value: u32) -> PyResult<()> {
+        let mut interpreter = self.interpreter.borrow_mut(py);
+        value
+            .store(&mut *interpreter.interpreter.memory, address)
+            .map_err(error)
+    }
+}
+
+/// Embive Python module.
+#[pymodule]
+#[pyo3(name = "embive")]
+fn embive_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Interpreter>()?;
+    m.add_class::<Memory>()?;
+    m.add_class::<State>()?;
+    m.add_function(wrap_pyfunction!(transpile_elf, m)?)?;
+    m.add("EmbiveError", m.py().get_type::<EmbiveError>())?;
+    m.add("SyscallError", m.py().get_type::<SyscallError>())?;
+    m.add("RAM_OFFSET", RAM_OFFSET)?;
+    m.add("SYSCALL_ARGS", SYSCALL_ARGS)?;
+    Ok(())
+}
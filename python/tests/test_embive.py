@@ -0,0 +1,117 @@
+"""Guest scenarios driven from Python (run with `pytest python/tests`, after `maturin develop`)."""
+from pathlib import Path
+
+import pytest
+
+import embive
+
+APP_ELF = Path(__file__).parents[2] / "tests" / "app.elf"
+
+
+def app_syscall(nr, args, memory):
+    """Syscalls of `tests/app.elf` (same as the Rust examples)."""
+    if nr == 1:
+        # Add two numbers (arg[0] + arg[1])
+        return args[0] + args[1]
+    if nr == 2:
+        # Load from RAM (arg[0])
+        try:
+            return memory.load_u32(args[0] & 0xFFFFFFFF)
+        except embive.EmbiveError:
+            raise embive.SyscallError(1)
+    raise embive.SyscallError(2)
+
+
+def run(interpreter, syscall):
+    """Run until the guest halts, handling syscalls and interrupting on `wfi`."""
+    while True:
+        state = interpreter.run()
+        if state == embive.State.Called:
+            interpreter.syscall(syscall)
+        elif state == embive.State.Waiting:
+            interpreter.interrupt(10)
+        elif state == embive.State.Halted:
+            return
+        else:
+            assert state == embive.State.Running
+
+
+def test_app():
+    code = embive.transpile_elf(APP_ELF.read_bytes())
+    interpreter = embive.Interpreter(code, ram_size=4096, instruction_limit=10)
+
+    run(interpreter, app_syscall)
+
+    # Code does "10 + 20" using syscalls (load from ram and add numbers)
+    assert interpreter.get_register(10) == 0
+    assert interpreter.get_register(11) == 30
+
+
+def test_syscall_error():
+    code = embive.transpile_elf(APP_ELF.read_bytes())
+    interpreter = embive.Interpreter(code)
+    assert interpreter.run() == embive.State.Called
+
+    def failing_syscall(nr, args, memory):
+        raise embive.SyscallError(7)
+
+    interpreter.syscall(failing_syscall)
+    assert interpreter.get_register(10) == 7
+
+
+def test_syscall_exception_propagates():
+    code = embive.transpile_elf(APP_ELF.read_bytes())
+    interpreter = embive.Interpreter(code)
+    assert interpreter.run() == embive.State.Called
+
+    def broken_syscall(nr, args, memory):
+        raise ValueError("broken")
+
+    with pytest.raises(ValueError):
+        interpreter.syscall(broken_syscall)
+
+    # Syscall is still pending
+    nr, args = interpreter.pending_syscall()
+    assert len(args) == embive.SYSCALL_ARGS
+    interpreter.complete_syscall(0)
+    assert interpreter.pending_syscall() is None
+
+
+def test_memory():
+    # Code: ebreak (already transpiled)
+    interpreter = embive.Interpreter(bytes([0x1F, 0x00, 0x10, 0x00]), ram_size=16)
+    memory = interpreter.memory
+
+    memory.store(embive.RAM_OFFSET, b"\x01\x02\x03\x04")
+    assert memory.load(embive.RAM_OFFSET, 4) == b"\x01\x02\x03\x04"
+    assert memory.load_u32(embive.RAM_OFFSET) == 0x04030201
+    memory.store_u32(embive.RAM_OFFSET + 4, 0xDEADBEEF)
+    assert memory.load(embive.RAM_OFFSET + 4, 4) == b"\xef\xbe\xad\xde"
+    assert memory.load(0, 4) == b"\x1f\x00\x10\x00"
+
+    with pytest.raises(embive.EmbiveError):
+        memory.load(embive.RAM_OFFSET + 16, 1)
+    with pytest.raises(embive.EmbiveError):
+        memory.store(0, b"\x00")
+
+    assert interpreter.run() == embive.State.Halted
+    assert interpreter.exit_code() is None
+
+
+def test_registers():
+    interpreter = embive.Interpreter(bytes([0x1F, 0x00, 0x10, 0x00]))
+    interpreter.set_register(10, -5)
+    assert interpreter.get_register(10) == -5
+    with pytest.raises(embive.EmbiveError):
+        interpreter.get_register(32)
+
+    interpreter.pc = 4
+    assert interpreter.pc == 4
+    interpreter.reset()
+    assert interpreter.pc == 0
+    assert interpreter.get_register(10) == 0
+
+
+def test_transpile_error():
+    with pytest.raises(embive.EmbiveError):
+        embive.transpile_elf(b"not an elf")
@@ -0,0 +1,380 @@
+//! Embive Runner
+//!
+//! Reference host: loads a RISC-V ELF file, transpiles it and runs it, mapping stdio syscalls to the process.
+//!
+//! Usage: `embive-run [OPTIONS] <binary.elf>` (check `embive-run --help`).
+//!
+//! Host syscalls:
+//! - `write` (`64`): `fd` (`1`: stdout, `2`: stderr), buffer, length. Returns the number of bytes written.
+//! - `read` (`63`): `fd` (`0`: stdin), buffer, length. Returns the number of bytes read.
+//! - `exit` (`93`): exit status, stops the guest.
+//! - `uart_write`/`uart_read` (check [`embive::protocol`]): same as `write` to stdout and `read` from stdin.
+//! - `log` (check [`embive::interpreter::guest_log`]): records are printed to stderr.
+//!
+//! The exit status is the `exit` syscall argument (Linux or Embive, check [`embive::protocol::EXIT_SYSCALL`]),
+//! or `a0` when the guest halts (`ebreak`).
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::num::NonZeroI32;
+use std::process::ExitCode;
+
+use embive::interpreter::{
+    memory::{Memory, SliceMemory},
+    syscall_trace::SyscallTracer,
+    trace::{TraceFormat, Tracer},
+    Config, Error, ExitReason, Interpreter, State, LOG_SYSCALL, SYSCALL_ARGS,
+};
+use embive::protocol::{UART_READ, UART_WRITE};
+use embive::transpiler::transpile_elf_vec;
+
+const USAGE: &str = "\
+Usage: embive-run [OPTIONS] <binary.elf>
+
+Transpile and run a RISC-V ELF file, mapping stdio syscalls to the process.
+
+Options:
+  --ram <SIZE>        RAM size, in bytes (`K`/`M` suffixes, default: 64K)
+  --limit <COUNT>     Stop after COUNT instructions (default: no limit)
+  --trace[=FORMAT]    Trace executed instructions to stderr (`spike` or `json`, default: spike)
+  --strace            Trace syscalls to stderr
+  --gdb <PORT>        Wait for a GDB client on localhost:PORT (`debugger` feature)
+  -h, --help          Print this help
+";
+
+/// Linux `read` syscall number.
+const READ_SYSCALL: i32 = 63;
+/// Linux `write` syscall number.
+const WRITE_SYSCALL: i32 = 64;
+/// Linux `exit` syscall number.
+const EXIT_SYSCALL: i32 = 93;
+
+/// Bad file descriptor error code (`EBADF`).
+const ERROR_BAD_FD: NonZeroI32 = error_code(9);
+/// Invalid address error code (`EFAULT`).
+const ERROR_FAULT: NonZeroI32 = error_code(14);
+/// I/O error code (`EIO`).
+const ERROR_IO: NonZeroI32 = error_code(5);
+/// Unknown syscall error code (`ENOSYS`).
+const ERROR_NO_SYSCALL: NonZeroI32 = error_code(38);
+
+/// Error code (checked to be non-zero at compile time).
+const fn error_code(code: i32) -> NonZeroI32 {
+    match NonZeroI32::new(code) {
+        Some(code) => code,
+        None => panic!("error codes must be non-zero"),
+    }
+}
+
+/// Command line options.
+#[derive(Debug)]
+struct Options {
+    elf: String,
+    ram_size: usize,
+    limit: Option<u32>,
+    trace: Option<TraceFormat>,
+    strace: bool,
+    gdb: Option<u16>,
+}
+
+impl Options {
+    /// Parse the command line arguments.
+    ///
+    /// Returns:
+    /// - `Ok(Some(Options))`: Parsed options.
+    /// - `Ok(None)`: Help requested.
+    /// - `Err(String)`: Invalid arguments.
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut elf = None;
+        let mut options = Options {
+            elf: String::new(),
+            ram_size: 64 * 1024,
+            limit: None,
+            trace: None,
+            strace: false,
+            gdb: None,
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("missing value for {name}"));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--ram" => options.ram_size = parse_size(&value("--ram")?)?,
+                "--limit" => options.limit = Some(parse_number(&value("--limit")?)?),
+                "--gdb" => options.gdb = Some(parse_number(&value("--gdb")?)?),
+                "--trace" | "--trace=spike" => options.trace = Some(TraceFormat::Spike),
+                "--trace=json" => options.trace = Some(TraceFormat::JsonLines),
+                "--strace" => options.strace = true,
+                _ if arg.starts_with('-') => return Err(format!("unknown option: {arg}")),
+                _ if elf.is_none() => elf = Some(arg),
+                _ => return Err(format!("unexpected argument: {arg}")),
+            }
+        }
+
+        options.elf = elf.ok_or("missing ELF file")?;
+        if options.gdb.is_some()
+            && (options.trace.is_some() || options.strace || options.limit.is_some())
+        {
+            return Err("--gdb can't be combined with --trace, --strace or --limit".into());
+        }
+        Ok(Some(options))
+    }
+}
+
+/// Parse a decimal or hexadecimal (`0x`) number.
+fn parse_number<T: TryFrom<u64>>(value: &str) -> Result<T, String> {
+    let number = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    number
+        .ok()
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or(format!("invalid number: {value}"))
+}
+
+/// Parse a size, with an optional `K` (KiB) or `M` (MiB) suffix.
+fn parse_size(value: &str) -> Result<usize, String> {
+    let (number, unit) = match value.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&value[..i], 1024),
+        Some((i, 'M' | 'm')) => (&value[..i], 1024 * 1024),
+        _ => (value, 1),
+    };
+    parse_number::<usize>(number)?
+        .checked_mul(unit)
+        .ok_or(format!("invalid size: {value}"))
+}
+
+/// Host side of the guest stdio (and exit status).
+#[derive(Debug, Default)]
+struct Host {
+    exit_status: Option<i32>,
+}
+
+impl Host {
+    /// Handle a guest syscall.
+    fn syscall<M: Memory>(
+        &mut self,
+        nr: i32,
+        args: &[i32; SYSCALL_ARGS],
+        memory: &mut M,
+    ) -> Result<Result<i32, NonZeroI32>, Error> {
+        let (address, len) = (args[1] as u32, args[2] as u32 as usize);
+        Ok(match nr {
+            WRITE_SYSCALL => match args[0] {
+                1 => write(&mut io::stdout(), memory, address, len),
+                2 => write(&mut io::stderr(), memory, address, len),
+                _ => Err(ERROR_BAD_FD),
+            },
+            READ_SYSCALL => match args[0] {
+                0 => read(memory, address, len),
+                _ => Err(ERROR_BAD_FD),
+            },
+            UART_WRITE => write(&mut io::stdout(), memory, args[0] as u32, args[1] as usize),
+            UART_READ => read(memory, args[0] as u32, args[1] as usize),
+            EXIT_SYSCALL => {
+                self.exit_status = Some(args[0]);
+                Ok(0)
+            }
+            _ => Err(ERROR_NO_SYSCALL),
+        })
+    }
+}
+
+/// Write guest memory to a stream.
+fn write<W: Write, M: Memory>(
+    stream: &mut W,
+    memory: &mut M,
+    address: u32,
+    len: usize,
+) -> Result<i32, NonZeroI32> {
+    let data = memory.load_bytes(address, len).map_err(|_| ERROR_FAULT)?;
+    stream
+        .write_all(data)
+        .and_then(|_| stream.flush())
+        .map_err(|_| ERROR_IO)?;
+    Ok(len as i32)
+}
+
+/// Read stdin to guest memory.
+fn read<M: Memory>(memory: &mut M, address: u32, len: usize) -> Result<i32, NonZeroI32> {
+    let buffer = memory.mut_bytes(address, len).map_err(|_| ERROR_FAULT)?;
+    let len = io::stdin().read(buffer).map_err(|_| ERROR_IO)?;
+    Ok(len as i32)
+}
+
+/// Print the log records of a pending log syscall to stderr.
+fn print_logs<M: Memory>(interpreter: &mut Interpreter<'_, M>) -> Result<(), Error> {
+    if let Some(records) = interpreter.log_records()? {
+        for record in records {
+            match record {
+                Ok(record) => eprintln!(
+                    "[{:?} {:#06x}] {}",
+                    record.level,
+                    record.module,
+                    record.message_str()
+                ),
+                Err(e) => eprintln!("embive-run: {e}"),
+            }
+        }
+    }
+    interpreter.complete_syscall(Ok(0))
+}
+
+/// Trace sink writing to stderr.
+struct TraceSink<W: Write>(W);
+
+impl<W: Write> fmt::Write for TraceSink<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Run the guest until it exits, returning its exit status.
+fn run(options: &Options, memory: &mut SliceMemory<'_>) -> Result<i32, String> {
+    let config = Config::default().with_max_instructions(options.limit.map_or(0, u64::from));
+    let code = memory.code();
+    let mut interpreter = Interpreter::with_config(memory, 0, config);
+    interpreter.set_code_window(Some(code));
+    let mut sink = options
+        .trace
+        .map(|format| (format, TraceSink(io::BufWriter::new(io::stderr()))));
+    let mut strace = options.strace.then(|| TraceSink(io::stderr()));
+    let mut host = Host::default();
+
+    loop {
+        let state = match &mut sink {
+            Some((format, sink)) => Tracer::new(*format, sink)
+                .run(&mut interpreter)
+                .map_err(|e| e.to_string())?,
+            None => interpreter.run().map_err(|e| e.to_string())?,
+        };
+
+        match state {
+            // Voluntary yield (no other guests)
+            State::Running | State::Yielded => {}
+            State::Called
+                if interpreter.pending_syscall().map(|(nr, _)| nr) == Some(LOG_SYSCALL) =>
+            {
+                print_logs(&mut interpreter).map_err(|e| e.to_string())?
+            }
+            State::Called => {
+                let mut syscall = |nr, args: &_, memory: &mut _| host.syscall(nr, args, memory);
+                match &mut strace {
+                    Some(sink) => SyscallTracer::new(sink)
+                        .syscall(&mut interpreter, &mut syscall)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())?,
+                    None => interpreter
+                        .syscall(&mut syscall)
+                        .map_err(|e| e.to_string())?,
+                }
+            }
+            // No interrupt source, `wfi` is a no-op (sleeps end immediately)
+            // Syscalls are handled right away, never deferred (and no hooks are set)
+            State::Waiting | State::Breakpoint | State::SyscallPending | State::Hooked(_) => {}
+            State::Halted { reason } => match reason {
+                ExitReason::Ebreak => return Ok(interpreter.registers.cpu.a0()),
+                ExitReason::ExitSyscall(code) => return Ok(code),
+                ExitReason::InstructionLimit => {
+                    return Err(format!(
+                        "instruction limit reached (pc: {:#010x})",
+                        interpreter.program_counter
+                    ))
+                }
+                #[cfg(feature = "resource-limits")]
+                ExitReason::ResourceLimit(resource) => {
+                    return Err(format!(
+                        "resource limit reached: {resource:?} (pc: {:#010x})",
+                        interpreter.program_counter
+                    ))
+                }
+            },
+            State::Panicked { msg_ptr, len } => {
+                let msg = interpreter
+                    .panic_message(msg_ptr, len)
+                    .map_err(|e| e.to_string())?;
+                return Err(format!("guest panicked: {msg}"));
+            }
+        }
+
+        if let Some(status) = host.exit_status {
+            return Ok(status);
+        }
+    }
+}
+
+/// Run the guest under a GDB server.
+#[cfg(feature = "debugger")]
+fn run_gdb(port: u16, memory: &mut SliceMemory<'_>) -> Result<i32, String> {
+    use embive::interpreter::Debugger;
+    use gdbstub::stub::{DisconnectReason, GdbStub};
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+
+    let mut host = Host::default();
+    let syscall = |nr: i32, args: &[i32; SYSCALL_ARGS], memory: &mut SliceMemory<'_>| {
+        host.syscall(nr, args, memory)
+    };
+    let mut debugger: Debugger<'_, _, TcpStream, _> = Debugger::new(memory, syscall);
+
+    eprintln!("embive-run: waiting for a GDB client on localhost:{port}...");
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).map_err(|e| e.to_string())?;
+    let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+
+    let mut buffer = [0; 4096];
+    let gdb = GdbStub::builder(stream)
+        .with_packet_buffer(&mut buffer)
+        .build()
+        .map_err(|e| e.to_string())?;
+    match gdb.run_blocking::<Debugger<'_, _, TcpStream, _>>(&mut debugger) {
+        Ok(DisconnectReason::TargetExited(code)) => Ok(code.into()),
+        Ok(reason) => Err(format!("debugging ended: {reason:?}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(not(feature = "debugger"))]
+fn run_gdb(_port: u16, _memory: &mut SliceMemory<'_>) -> Result<i32, String> {
+    Err("--gdb requires the `debugger` feature".into())
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprint!("embive-run: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let code = match std::fs::read(&options.elf)
+        .map_err(|e| e.to_string())
+        .and_then(|elf| transpile_elf_vec(&elf).map_err(|e| e.to_string()))
+    {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("embive-run: couldn't load {}: {e}", options.elf);
+            return ExitCode::from(2);
+        }
+    };
+    let mut ram = vec![0; options.ram_size];
+    let mut memory = SliceMemory::new(&code, &mut ram);
+
+    let result = match options.gdb {
+        Some(port) => run_gdb(port, &mut memory),
+        None => run(&options, &mut memory),
+    };
+    match result {
+        Ok(status) => {
+            eprintln!("embive-run: exit status {status}");
+            ExitCode::from(status as u8)
+        }
+        Err(e) => {
+            eprintln!("embive-run: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
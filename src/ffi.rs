@@ -0,0 +1,704 @@
+//! C FFI Module
+//!
+//! Stable C API to embed Embive in C firmware (check the `ffi/` crate for the static library and header).
+//!
+//! No allocation is done: the firmware provides the interpreter storage ([`EmbiveInterpreter`]),
+//! the code and the RAM buffers, which must outlive the interpreter.
+//!
+//! All functions return an [`EmbiveError`], checking pointers for `NULL` (but not for validity).
+use core::{
+    ffi::c_void,
+    mem::{align_of, size_of},
+    num::NonZeroI32,
+    ptr::{addr_of_mut, null_mut},
+    slice,
+};
+
+use crate::interpreter::{
+    memory::{Memory, SliceMemory},
+    Error, Interpreter, State, SYSCALL_ARGS,
+};
+
+/// Interpreter storage (2048 bytes), to be allocated by the caller (e.g. a `static` or stack variable).
+///
+/// The size is fixed (part of the ABI), reserving room for the interpreter to grow across versions and features.
+/// The size actually used is returned by [`embive_interpreter_size`], firmware linking against a prebuilt library can
+/// check it at runtime.
+///
+/// Must not be moved after [`embive_interpreter_init`].
+#[repr(C)]
+pub struct EmbiveInterpreter {
+    _storage: [u64; 256],
+}
+
+/// Interpreter memory (code + RAM), passed to the syscall callback.
+pub struct EmbiveMemory(SliceMemory<'static>);
+
+/// Interpreter state after running (check [`State`]).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbiveState {
+    /// Instruction limit reached, call [`embive_run`] to continue running.
+    Running,
+    /// Syscall, call [`embive_syscall`] to handle it and then [`embive_run`] to continue running.
+    Called,
+    /// Waiting for an interrupt (check [`embive_interrupt`]).
+    Waiting,
+    /// Interpreted code halted (`ebreak`, exit syscall or instruction budget).
+    Halted,
+    /// Interpreted code stopped at a breakpoint.
+    Breakpoint,
+    /// Interpreted code panicked.
+    Panicked,
+    /// Interpreted code yielded voluntarily (yield syscall), call [`embive_run`] to continue running.
+    Yielded,
+}
+
+/// Error code returned by the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbiveError {
+    /// Success.
+    Ok,
+    /// A required pointer is `NULL`.
+    NullPointer,
+    /// Invalid argument (e.g. register index).
+    InvalidArgument,
+    /// Memory access out of bounds.
+    InvalidMemoryAddress,
+    /// Interrupts are not enabled by the interpreted code.
+    InterruptNotEnabled,
+    /// No syscall callback (check [`embive_set_syscall`]).
+    NoSyscallCallback,
+    /// Other interpreter error (e.g. invalid instruction).
+    Interpreter,
+    /// Transpilation failed.
+    Transpiler,
+}
+
+impl From<Error> for EmbiveError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::InvalidMemoryAddress { .. }
+            | Error::InvalidMemoryAccessLength(_)
+            | Error::MemoryFault { .. } => EmbiveError::InvalidMemoryAddress,
+            Error::InvalidCPURegister(_) => EmbiveError::InvalidArgument,
+            Error::InterruptNotEnabled => EmbiveError::InterruptNotEnabled,
+            Error::NoSyscallFunction => EmbiveError::NoSyscallCallback,
+            _ => EmbiveError::Interpreter,
+        }
+    }
+}
+
+impl From<State> for EmbiveState {
+    fn from(state: State) -> Self {
+        match state {
+            State::Running => EmbiveState::Running,
+            // Syscalls are not deferred through the C API
+            State::Called | State::SyscallPending => EmbiveState::Called,
+            State::Waiting => EmbiveState::Waiting,
+            State::Halted { .. } => EmbiveState::Halted,
+            // Hooks are not exposed, can't be hit
+            State::Breakpoint | State::Hooked(_) => EmbiveState::Breakpoint,
+            State::Panicked { .. } => EmbiveState::Panicked,
+            State::Yielded => EmbiveState::Yielded,
+        }
+    }
+}
+
+/// Syscall callback.
+///
+/// Arguments:
+/// - `nr`: Syscall number.
+/// - `args`: Syscall arguments ([`SYSCALL_ARGS`] values).
+/// - `memory`: Interpreter memory (check [`embive_memory_load`] and [`embive_memory_store`]).
+/// - `user_data`: User data passed to [`embive_set_syscall`].
+/// - `value`: Syscall return value (`a1`), on success.
+///
+/// Returns:
+/// - `0`: Success (`value` is returned to the interpreted code).
+/// - Other: Error code returned to the interpreted code (`a0`).
+pub type EmbiveSyscallFn = Option<
+    unsafe extern "C" fn(
+        nr: i32,
+        args: *const i32,
+        memory: *mut EmbiveMemory,
+        user_data: *mut c_void,
+        value: *mut i32,
+    ) -> i32,
+>;
+
+impl Memory for EmbiveMemory {
+    #[inline]
+    fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+        self.0.load_bytes(address, len)
+    }
+
+    #[inline]
+    fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+        self.0.mut_bytes(address, len)
+    }
+
+    #[inline]
+    fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.0.store_bytes(address, data)
+    }
+}
+
+/// Contents of the interpreter storage.
+///
+/// The interpreter borrows the memory from the same storage, so fields are only accessed through
+/// raw pointers (never as a whole).
+struct Inner {
+    interpreter: Interpreter<'static, EmbiveMemory>,
+    memory: EmbiveMemory,
+    syscall: EmbiveSyscallFn,
+    user_data: *mut c_void,
+}
+
+const _: () = assert!(size_of::<Inner>() <= size_of::<EmbiveInterpreter>());
+const _: () = assert!(align_of::<Inner>() <= align_of::<EmbiveInterpreter>());
+
+/// Get the interpreter from its storage.
+///
+/// # Safety
+/// `interpreter` must be `NULL` or initialized with [`embive_interpreter_init`].
+unsafe fn interpreter<'a>(
+    interpreter: *mut EmbiveInterpreter,
+) -> Option<&'a mut Interpreter<'static, EmbiveMemory>> {
+    let inner = interpreter.cast::<Inner>();
+    if inner.is_null() {
+        return None;
+    }
+    Some(&mut *addr_of_mut!((*inner).interpreter))
+}
+
+/// Build a slice from a C buffer (`NULL` is only accepted for empty buffers).
+///
+/// # Safety
+/// `ptr` must be valid for reads of `len` bytes, for the lifetime `'a`.
+unsafe fn buffer<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+/// Build a mutable slice from a C buffer (`NULL` is only accepted for empty buffers).
+///
+/// # Safety
+/// `ptr` must be valid for reads and writes of `len` bytes, for the lifetime `'a`.
+unsafe fn buffer_mut<'a>(ptr: *mut u8, len: usize) -> Option<&'a mut [u8]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Some(&mut []),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts_mut(ptr, len)),
+    }
+}
+
+/// Map a result to an error code.
+fn error(result: Result<(), Error>) -> EmbiveError {
+    match result {
+        Ok(()) => EmbiveError::Ok,
+        Err(error) => error.into(),
+    }
+}
+
+/// Get the size of the interpreter storage actually used, in bytes.
+///
+/// Always less than or equal to `sizeof(EmbiveInterpreter)` for a matching header, a larger size means the header and
+/// the library don't match.
+#[no_mangle]
+pub extern "C" fn embive_interpreter_size() -> usize {
+    size_of::<Inner>()
+}
+
+/// Initialize an interpreter.
+///
+/// Arguments:
+/// - `interpreter`: Interpreter storage (must not be moved afterwards).
+/// - `code`: Code buffer (Embive image), mapped to address `0x00000000`.
+/// - `code_len`: Code buffer length, in bytes.
+/// - `ram`: RAM buffer, mapped to [`crate::interpreter::memory::RAM_OFFSET`].
+/// - `ram_len`: RAM buffer length, in bytes.
+/// - `instruction_limit`: Instructions executed per [`embive_run`] (`0` for no limit).
+///
+/// # Safety
+/// The code and RAM buffers must be valid (and not accessed elsewhere) for as long as the interpreter is used.
+#[no_mangle]
+pub unsafe extern "C" fn embive_interpreter_init(
+    interpreter: *mut EmbiveInterpreter,
+    code: *const u8,
+    code_len: usize,
+    ram: *mut u8,
+    ram_len: usize,
+    instruction_limit: u32,
+) -> EmbiveError {
+    let inner = interpreter.cast::<Inner>();
+    let (false, Some(code), Some(ram)) = (
+        inner.is_null(),
+        buffer(code, code_len),
+        buffer_mut(ram, ram_len),
+    ) else {
+        return EmbiveError::NullPointer;
+    };
+
+    let memory = addr_of_mut!((*inner).memory);
+    memory.write(EmbiveMemory(SliceMemory::new(code, ram)));
+    addr_of_mut!((*inner).interpreter).write(Interpreter::new(&mut *memory, instruction_limit));
+    addr_of_mut!((*inner).syscall).write(None);
+    addr_of_mut!((*inner).user_data).write(null_mut());
+    EmbiveError::Ok
+}
+
+/// Load a new Embive image, resetting the interpreter (the RAM is kept).
+///
+/// Arguments:
+/// - `interpreter`: Initialized interpreter.
+/// - `code`: Code buffer (Embive image).
+/// - `code_len`: Code buffer length, in bytes.
+///
+/// # Safety
+/// The code buffer must be valid for as long as the interpreter is used.
+#[no_mangle]
+pub unsafe extern "C" fn embive_load(
+    interpreter: *mut EmbiveInterpreter,
+    code: *const u8,
+    code_len: usize,
+) -> EmbiveError {
+    let (Some(interpreter), Some(code)) = (self::interpreter(interpreter), buffer(code, code_len))
+    else {
+        return EmbiveError::NullPointer;
+    };
+
+    interpreter.memory.0.set_code(code);
+    interpreter.reset();
+    EmbiveError::Ok
+}
+
+/// Reset the interpreter (program counter, registers and pending state).
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_reset(interpreter: *mut EmbiveInterpreter) -> EmbiveError {
+    let Some(interpreter) = self::interpreter(interpreter) else {
+        return EmbiveError::NullPointer;
+    };
+
+    interpreter.reset();
+    EmbiveError::Ok
+}
+
+/// Run the interpreter until it stops (check [`EmbiveState`]).
+///
+/// Arguments:
+/// - `interpreter`: Initialized interpreter.
+/// - `state`: Interpreter state, on success.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`], `state` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn embive_run(
+    interpreter: *mut EmbiveInterpreter,
+    state: *mut EmbiveState,
+) -> EmbiveError {
+    let (Some(interpreter), Some(state)) = (self::interpreter(interpreter), state.as_mut()) else {
+        return EmbiveError::NullPointer;
+    };
+
+    match interpreter.run() {
+        Ok(new_state) => {
+            *state = new_state.into();
+            EmbiveError::Ok
+        }
+        Err(error) => error.into(),
+    }
+}
+
+/// Set the syscall callback, used by [`embive_syscall`].
+///
+/// Arguments:
+/// - `interpreter`: Initialized interpreter.
+/// - `callback`: Syscall callback (`NULL` to remove it).
+/// - `user_data`: Opaque pointer passed to the callback.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_set_syscall(
+    interpreter: *mut EmbiveInterpreter,
+    callback: EmbiveSyscallFn,
+    user_data: *mut c_void,
+) -> EmbiveError {
+    let inner = interpreter.cast::<Inner>();
+    if inner.is_null() {
+        return EmbiveError::NullPointer;
+    }
+
+    addr_of_mut!((*inner).syscall).write(callback);
+    addr_of_mut!((*inner).user_data).write(user_data);
+    EmbiveError::Ok
+}
+
+/// Handle the pending syscall ([`EmbiveState::Called`]) with the syscall callback.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_syscall(interpreter: *mut EmbiveInterpreter) -> EmbiveError {
+    let inner = interpreter.cast::<Inner>();
+    let Some(interpreter) = self::interpreter(interpreter) else {
+        return EmbiveError::NullPointer;
+    };
+    let Some(callback) = *addr_of_mut!((*inner).syscall) else {
+        return EmbiveError::NoSyscallCallback;
+    };
+    let user_data = *addr_of_mut!((*inner).user_data);
+
+    let mut syscall = |nr: i32, args: &[i32; SYSCALL_ARGS], memory: &mut EmbiveMemory| {
+        let mut value = 0;
+        let code = callback(nr, args.as_ptr(), memory, user_data, &mut value);
+        Ok::<_, Error>(NonZeroI32::new(code).map_or(Ok(value), Err))
+    };
+    error(interpreter.syscall(&mut syscall))
+}
+
+/// Trigger an interrupt (check [`crate::interpreter::Interpreter::interrupt`]).
+///
+/// Arguments:
+/// - `interpreter`: Initialized interpreter.
+/// - `value`: Value passed to the interrupt handler.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_interrupt(
+    interpreter: *mut EmbiveInterpreter,
+    value: i32,
+) -> EmbiveError {
+    let Some(interpreter) = self::interpreter(interpreter) else {
+        return EmbiveError::NullPointer;
+    };
+
+    error(interpreter.interrupt(value))
+}
+
+/// Read a CPU register.
+///
+/// Arguments:
+/// - `interpreter`: Initialized interpreter.
+/// - `index`: Register index (`0` to `31`, e.g. `10` for `a0`).
+/// - `value`: Register value, on success.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`], `value` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn embive_get_register(
+    interpreter: *mut EmbiveInterpreter,
+    index: u8,
+    value: *mut i32,
+) -> EmbiveError {
+    let (Some(interpreter), Some(value)) = (self::interpreter(interpreter), value.as_mut()) else {
+        return EmbiveError::NullPointer;
+    };
+
+    error(interpreter.registers.cpu.get(index).map(|reg| *value = reg))
+}
+
+/// Write a CPU register.
+///
+/// Arguments:
+/// - `interpreter`: Initialized interpreter.
+/// - `index`: Register index (`0` to `31`, e.g. `10` for `a0`).
+/// - `value`: Register value.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_set_register(
+    interpreter: *mut EmbiveInterpreter,
+    index: u8,
+    value: i32,
+) -> EmbiveError {
+    let Some(interpreter) = self::interpreter(interpreter) else {
+        return EmbiveError::NullPointer;
+    };
+
+    error(interpreter.registers.cpu.set(index, value))
+}
+
+/// Read the program counter.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`], `value` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn embive_get_pc(
+    interpreter: *mut EmbiveInterpreter,
+    value: *mut u32,
+) -> EmbiveError {
+    let (Some(interpreter), Some(value)) = (self::interpreter(interpreter), value.as_mut()) else {
+        return EmbiveError::NullPointer;
+    };
+
+    *value = interpreter.program_counter;
+    EmbiveError::Ok
+}
+
+/// Write the program counter.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_set_pc(
+    interpreter: *mut EmbiveInterpreter,
+    value: u32,
+) -> EmbiveError {
+    let Some(interpreter) = self::interpreter(interpreter) else {
+        return EmbiveError::NullPointer;
+    };
+
+    interpreter.program_counter = value;
+    EmbiveError::Ok
+}
+
+/// Get the interpreter memory (check [`embive_memory_load`] and [`embive_memory_store`]).
+///
+/// Returns `NULL` if `interpreter` is `NULL`. The memory must not be used while the interpreter is running.
+///
+/// # Safety
+/// `interpreter` must be initialized with [`embive_interpreter_init`].
+#[no_mangle]
+pub unsafe extern "C" fn embive_interpreter_memory(
+    interpreter: *mut EmbiveInterpreter,
+) -> *mut EmbiveMemory {
+    match self::interpreter(interpreter) {
+        Some(interpreter) => &mut *interpreter.memory,
+        None => null_mut(),
+    }
+}
+
+/// Read bytes from the interpreter memory (code or RAM).
+///
+/// Arguments:
+/// - `memory`: Interpreter memory.
+/// - `address`: Start address.
+/// - `buffer`: Output buffer.
+/// - `len`: Number of bytes to read.
+///
+/// # Safety
+/// `memory` must come from [`embive_interpreter_memory`] or the syscall callback, `buffer` must be valid
+/// for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn embive_memory_load(
+    memory: *mut EmbiveMemory,
+    address: u32,
+    buffer: *mut u8,
+    len: usize,
+) -> EmbiveError {
+    let (Some(memory), Some(buffer)) = (memory.as_mut(), buffer_mut(buffer, len)) else {
+        return EmbiveError::NullPointer;
+    };
+
+    error(
+        memory
+            .load_bytes(address, len)
+            .map(|bytes| buffer.copy_from_slice(bytes)),
+    )
+}
+
+/// Write bytes to the interpreter memory (RAM only).
+///
+/// Arguments:
+/// - `memory`: Interpreter memory.
+/// - `address`: Start address.
+/// - `data`: Data to write.
+/// - `len`: Number of bytes to write.
+///
+/// # Safety
+/// `memory` must come from [`embive_interpreter_memory`] or the syscall callback, `data` must be valid
+/// for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn embive_memory_store(
+    memory: *mut EmbiveMemory,
+    address: u32,
+    data: *const u8,
+    len: usize,
+) -> EmbiveError {
+    let (Some(memory), Some(data)) = (memory.as_mut(), buffer(data, len)) else {
+        return EmbiveError::NullPointer;
+    };
+
+    error(memory.store_bytes(address, data))
+}
+
+/// Transpile a RISC-V ELF file to an Embive image.
+///
+/// Arguments:
+/// - `elf`: ELF file.
+/// - `elf_len`: ELF file length, in bytes.
+/// - `output`: Output buffer (Embive image).
+/// - `output_len`: Output buffer length, in bytes.
+/// - `size`: Embive image size, in bytes, on success.
+///
+/// # Safety
+/// `elf` must be valid for reads of `elf_len` bytes, `output` for writes of `output_len` bytes
+/// and `size` for writes.
+#[cfg(feature = "transpiler")]
+#[no_mangle]
+pub unsafe extern "C" fn embive_transpile_elf(
+    elf: *const u8,
+    elf_len: usize,
+    output: *mut u8,
+    output_len: usize,
+    size: *mut usize,
+) -> EmbiveError {
+    let (Some(elf), Some(output), Some(size)) = (
+        buffer(elf, elf_len),
+        buffer_mut(output, output_len),
+        size.as_mut(),
+    ) else {
+        return EmbiveError::NullPointer;
+    };
+
+    match crate::transpiler::transpile_elf(elf, output) {
+        Ok(len) => {
+            *size = len;
+            EmbiveError::Ok
+        }
+        Err(_) => EmbiveError::Transpiler,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use super::*;
+
+    /// Code: ecall, ebreak (already transpiled)
+    const CODE: [u8; 8] = [0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x10, 0x00];
+
+    unsafe extern "C" fn syscall(
+        nr: i32,
+        args: *const i32,
+        memory: *mut EmbiveMemory,
+        user_data: *mut c_void,
+        value: *mut i32,
+    ) -> i32 {
+        *user_data.cast::<i32>() += 1;
+        let data = (nr + *args).to_le_bytes();
+        let ram = crate::interpreter::memory::RAM_OFFSET;
+        if embive_memory_store(memory, ram, data.as_ptr(), data.len()) != EmbiveError::Ok {
+            return 1;
+        }
+        *value = nr * 2;
+        0
+    }
+
+    #[test]
+    fn test_ffi_run() {
+        let mut storage = MaybeUninit::<EmbiveInterpreter>::uninit();
+        let interpreter = storage.as_mut_ptr();
+        let mut ram = [0; 16];
+        let mut calls = 0i32;
+        let mut state = EmbiveState::Running;
+        unsafe {
+            assert_eq!(
+                embive_interpreter_init(interpreter, CODE.as_ptr(), 0, ram.as_mut_ptr(), 16, 0),
+                EmbiveError::Ok
+            );
+            assert_eq!(
+                embive_load(interpreter, CODE.as_ptr(), CODE.len()),
+                EmbiveError::Ok
+            );
+            assert_eq!(embive_syscall(interpreter), EmbiveError::NoSyscallCallback);
+            assert_eq!(
+                embive_set_syscall(interpreter, Some(syscall), (&mut calls as *mut i32).cast()),
+                EmbiveError::Ok
+            );
+            assert_eq!(embive_set_register(interpreter, 17, 3), EmbiveError::Ok);
+            assert_eq!(embive_set_register(interpreter, 10, 4), EmbiveError::Ok);
+
+            assert_eq!(embive_run(interpreter, &mut state), EmbiveError::Ok);
+            assert_eq!(state, EmbiveState::Called);
+            assert_eq!(embive_syscall(interpreter), EmbiveError::Ok);
+            assert_eq!(embive_run(interpreter, &mut state), EmbiveError::Ok);
+            assert_eq!(state, EmbiveState::Halted);
+
+            let mut value = 0;
+            assert_eq!(
+                embive_get_register(interpreter, 11, &mut value),
+                EmbiveError::Ok
+            );
+            assert_eq!(value, 6);
+            assert_eq!(
+                embive_get_register(interpreter, 32, &mut value),
+                EmbiveError::InvalidArgument
+            );
+            let mut pc = 0;
+            assert_eq!(embive_get_pc(interpreter, &mut pc), EmbiveError::Ok);
+            assert_eq!(pc, 8);
+
+            let memory = embive_interpreter_memory(interpreter);
+            let mut data = [0; 4];
+            let ram_offset = crate::interpreter::memory::RAM_OFFSET;
+            assert_eq!(
+                embive_memory_load(memory, ram_offset, data.as_mut_ptr(), 4),
+                EmbiveError::Ok
+            );
+            assert_eq!(i32::from_le_bytes(data), 7);
+            assert_eq!(
+                embive_memory_load(memory, ram_offset + 16, data.as_mut_ptr(), 4),
+                EmbiveError::InvalidMemoryAddress
+            );
+        }
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_ffi_null() {
+        let mut state = EmbiveState::Running;
+        unsafe {
+            assert_eq!(embive_run(null_mut(), &mut state), EmbiveError::NullPointer);
+            assert_eq!(
+                embive_interpreter_init(null_mut(), CODE.as_ptr(), 8, null_mut(), 0, 0),
+                EmbiveError::NullPointer
+            );
+            assert!(embive_interpreter_memory(null_mut()).is_null());
+        }
+    }
+
+    #[test]
+    fn test_ffi_size() {
+        // Fixed storage, with room to grow
+        assert_eq!(size_of::<EmbiveInterpreter>(), 2048);
+        assert!(embive_interpreter_size() <= size_of::<EmbiveInterpreter>());
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_ffi_transpile() {
+        let elf = include_bytes!("../tests/app.elf");
+        let mut output = [0; 4096];
+        let mut size = 0;
+        unsafe {
+            assert_eq!(
+                embive_transpile_elf(
+                    elf.as_ptr(),
+                    elf.len(),
+                    output.as_mut_ptr(),
+                    output.len(),
+                    &mut size
+                ),
+                EmbiveError::Ok
+            );
+            assert_ne!(size, 0);
+            assert_eq!(
+                embive_transpile_elf(elf.as_ptr(), elf.len(), output.as_mut_ptr(), 4, &mut size),
+                EmbiveError::Transpiler
+            );
+        }
+    }
+}
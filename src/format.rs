@@ -243,6 +243,48 @@ impl Format for TypeU {
     }
 }
 
+/// UL-Type Instruction Format (U-Type with a load-immediate flag, used by `lui`)
+///
+/// The flag (Embive only, bit 5) marks a load-immediate: the 32-bit immediate is the next word,
+/// making it a single 8-byte instruction (check [`crate::instruction::embive::Lui`]).
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TypeUL {
+    /// Destination Register
+    pub rd: u8,
+    /// Immediate Value (upper 20 bits)
+    pub imm: i32,
+    /// Load-Immediate Flag
+    pub li: bool,
+}
+
+impl Format for TypeUL {
+    const SIZE: Size = Size::Word;
+
+    #[inline(always)]
+    fn from_riscv(inst: u32) -> Self {
+        TypeUL {
+            rd: ((inst >> 7) & 0b1_1111) as u8,
+            imm: (inst & (0b1111_1111_1111_1111_1111 << 12)) as i32,
+            li: false,
+        }
+    }
+
+    #[inline(always)]
+    fn from_embive(inst: u32) -> Self {
+        TypeUL {
+            li: (inst >> 5) & 0b1 != 0,
+            ..Self::from_riscv(inst)
+        }
+    }
+
+    #[inline(always)]
+    fn to_embive(self) -> u32 {
+        ((self.li as u32) << 5)
+            | ((self.rd as u32) << 7)
+            | (self.imm as u32 & (0b1111_1111_1111_1111_1111 << 12))
+    }
+}
+
 /// J-Type Instruction Format
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct TypeJ {
@@ -904,6 +946,19 @@ mod tests {
         assert_eq!(parsed.rd, 3);
     }
 
+    #[test]
+    fn test_type_ul() {
+        let inst = 0b11110000001000001111000110110111; // lui x3, -65009
+        let mut parsed = TypeUL::from_riscv(inst);
+        test_from_to(parsed);
+        assert!(!parsed.li);
+
+        parsed.li = true;
+        test_from_to(parsed);
+        assert_eq!(parsed.to_embive() & 0b110_0000, 0b10_0000);
+        assert_eq!(parsed.imm, -65009 << 12);
+    }
+
     #[test]
     fn test_type_j_negative() {
         let inst = 0b10101100001100011011000111101111; // jal x3, -935230
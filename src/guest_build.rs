@@ -0,0 +1,420 @@
+//! Guest Build Module
+//!
+//! Drives building guest Rust crates for RISC-V (`riscv32imac-unknown-none-elf` by default), transpiles
+//! them and generates Rust constants for inclusion, so hosts and integration tests don't need hand-written
+//! Makefiles to produce their guest images.
+//!
+//! Example (host `build.rs`, guest crate in `guest/` with an `app` binary):
+//! ```no_run
+//! use embive::guest_build::GuestBuild;
+//!
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! GuestBuild::new("guest", "app")
+//!     .symbol("interrupt_handler")
+//!     .build()
+//!     .unwrap()
+//!     .write(out_dir, "APP")
+//!     .unwrap();
+//!
+//! println!("cargo:rerun-if-changed=guest");
+//! ```
+//!
+//! The host then includes the generated constants (`APP: &[u8]`, `APP_INTERRUPT_HANDLER: u32`):
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/app.rs"));
+//! ```
+//!
+//! The guest crate is built from its own directory, so its Cargo configuration (e.g. `.cargo/config.toml`
+//! with the linker script) is used. The RISC-V target must be installed (`rustup target add`).
+use core::fmt::{Display, Formatter, Write};
+use std::{
+    borrow::ToOwned,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::transpiler::{self, symbol, transpile_elf_vec_with_config, Config};
+
+/// Default guest target.
+pub const DEFAULT_GUEST_TARGET: &str = "riscv32imac-unknown-none-elf";
+
+/// Default guest build profile.
+pub const DEFAULT_GUEST_PROFILE: &str = "release";
+
+/// Guest Build Error
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GuestBuildError {
+    /// Failed to run Cargo, or to read/write a file.
+    Io(io::Error),
+    /// Cargo failed to build the guest. The exit status is provided.
+    Cargo(ExitStatus),
+    /// Failed to transpile the guest.
+    Transpiler(transpiler::Error),
+    /// Symbol not found in the guest ELF. The symbol name is provided.
+    MissingSymbol(String),
+}
+
+impl core::error::Error for GuestBuildError {}
+
+impl Display for GuestBuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GuestBuildError::Io(e) => write!(f, "I/O error: {e}"),
+            GuestBuildError::Cargo(status) => write!(f, "failed to build guest: cargo {status}"),
+            GuestBuildError::Transpiler(e) => write!(f, "failed to transpile guest: {e}"),
+            GuestBuildError::MissingSymbol(name) => write!(f, "symbol `{name}` not found"),
+        }
+    }
+}
+
+impl From<io::Error> for GuestBuildError {
+    fn from(e: io::Error) -> Self {
+        GuestBuildError::Io(e)
+    }
+}
+
+impl From<transpiler::Error> for GuestBuildError {
+    fn from(e: transpiler::Error) -> Self {
+        GuestBuildError::Transpiler(e)
+    }
+}
+
+/// Guest Build Builder
+///
+/// Builds a guest binary with Cargo, then transpiles it (check [`GuestBuild::build`]).
+#[derive(Debug, Clone)]
+pub struct GuestBuild {
+    manifest_dir: PathBuf,
+    bin: String,
+    target: String,
+    profile: String,
+    features: Vec<String>,
+    target_dir: Option<PathBuf>,
+    config: Config<'static>,
+    symbols: Vec<String>,
+}
+
+impl GuestBuild {
+    /// Create a guest build.
+    ///
+    /// Arguments:
+    /// - `manifest_dir`: Directory of the guest crate (containing its `Cargo.toml`).
+    /// - `bin`: Name of the guest binary.
+    pub fn new<P: AsRef<Path>>(manifest_dir: P, bin: &str) -> Self {
+        GuestBuild {
+            manifest_dir: manifest_dir.as_ref().to_path_buf(),
+            bin: bin.to_owned(),
+            target: DEFAULT_GUEST_TARGET.to_owned(),
+            profile: DEFAULT_GUEST_PROFILE.to_owned(),
+            features: Vec::new(),
+            target_dir: None,
+            config: Config::default(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Set the target triple. Default: [`DEFAULT_GUEST_TARGET`].
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = target.to_owned();
+        self
+    }
+
+    /// Set the build profile (e.g. `dev`). Default: [`DEFAULT_GUEST_PROFILE`].
+    pub fn profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_owned();
+        self
+    }
+
+    /// Enable a feature of the guest crate.
+    pub fn feature(mut self, feature: &str) -> Self {
+        self.features.push(feature.to_owned());
+        self
+    }
+
+    /// Set the target directory. Default: `target` in the guest crate directory.
+    ///
+    /// The target directory of the host isn't shared, as a build script can't build into it while Cargo holds its lock.
+    pub fn target_dir<P: AsRef<Path>>(mut self, target_dir: P) -> Self {
+        self.target_dir = Some(target_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the transpiler configuration. Default: [`Config::default`].
+    pub fn config(mut self, config: Config<'static>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Export the address of a guest symbol (e.g. an exported function) as a constant.
+    pub fn symbol(mut self, name: &str) -> Self {
+        self.symbols.push(name.to_owned());
+        self
+    }
+
+    /// Get the path of the guest ELF (once built).
+    pub fn elf_path(&self) -> PathBuf {
+        let mut path = self.output_dir();
+        path.push(&self.target);
+        path.push(profile_dir(&self.profile));
+        path.push(&self.bin);
+        path
+    }
+
+    /// Build the guest with Cargo, then transpile it.
+    ///
+    /// Returns:
+    /// - `Ok(GuestImage)`: Success, returns the transpiled guest.
+    /// - `Err(GuestBuildError)`: Failed to build or transpile the guest.
+    pub fn build(&self) -> Result<GuestImage, GuestBuildError> {
+        let elf_path = self.elf_path();
+
+        let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+        command
+            .current_dir(&self.manifest_dir)
+            .args(["build", "--bin", &self.bin])
+            .args(["--target", &self.target])
+            .args(["--profile", &self.profile])
+            .arg("--target-dir")
+            .arg(self.output_dir())
+            // Host flags (set by Cargo for build scripts) don't apply to the guest
+            .env_remove("CARGO_ENCODED_RUSTFLAGS")
+            .env_remove("RUSTC_WORKSPACE_WRAPPER");
+        if !self.features.is_empty() {
+            command.args(["--features", &self.features.join(",")]);
+        }
+
+        let status = command.status()?;
+        if !status.success() {
+            return Err(GuestBuildError::Cargo(status));
+        }
+
+        let symbols: Vec<&str> = self.symbols.iter().map(String::as_str).collect();
+        GuestImage::from_elf_with_config(elf_path, &symbols, &self.config)
+    }
+
+    /// Get the target directory of the guest build.
+    fn output_dir(&self) -> PathBuf {
+        self.target_dir
+            .clone()
+            .unwrap_or_else(|| self.manifest_dir.join("target"))
+    }
+}
+
+/// Transpiled Guest Image
+///
+/// Embive image of a guest ELF, with the addresses of its exported symbols.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuestImage {
+    elf_path: PathBuf,
+    code: Vec<u8>,
+    symbols: Vec<(String, u32)>,
+}
+
+impl GuestImage {
+    /// Transpile a guest ELF file (e.g. a prebuilt fixture).
+    ///
+    /// Arguments:
+    /// - `elf_path`: Path of the RISC-V ELF file.
+    /// - `symbols`: Symbols to export (check [`GuestBuild::symbol`]).
+    ///
+    /// Returns:
+    /// - `Ok(GuestImage)`: Success, returns the transpiled guest.
+    /// - `Err(GuestBuildError)`: Failed to read or transpile the ELF, or a symbol wasn't found.
+    pub fn from_elf<P: AsRef<Path>>(
+        elf_path: P,
+        symbols: &[&str],
+    ) -> Result<Self, GuestBuildError> {
+        Self::from_elf_with_config(elf_path, symbols, &Config::default())
+    }
+
+    /// Transpile a guest ELF file, with a custom transpiler configuration.
+    /// Same as [`GuestImage::from_elf`].
+    pub fn from_elf_with_config<P: AsRef<Path>>(
+        elf_path: P,
+        symbols: &[&str],
+        config: &Config<'_>,
+    ) -> Result<Self, GuestBuildError> {
+        let elf = fs::read(elf_path.as_ref())?;
+        let code = transpile_elf_vec_with_config(&elf, config)?;
+        let symbols = symbols
+            .iter()
+            .map(|&name| match symbol(&elf, name)? {
+                Some(address) => Ok((name.to_owned(), address)),
+                None => Err(GuestBuildError::MissingSymbol(name.to_owned())),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(GuestImage {
+            elf_path: elf_path.as_ref().to_path_buf(),
+            code,
+            symbols,
+        })
+    }
+
+    /// Get the path of the guest ELF.
+    pub fn elf_path(&self) -> &Path {
+        &self.elf_path
+    }
+
+    /// Get the transpiled code (Embive format).
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Get the exported symbols (name and address).
+    pub fn symbols(&self) -> &[(String, u32)] {
+        &self.symbols
+    }
+
+    /// Generate the Rust constants of the guest.
+    ///
+    /// - `{name}: &[u8]`: The transpiled code, included from `code_path`.
+    /// - `{name}_{SYMBOL}: u32`: The address of each exported symbol (uppercase, non-alphanumeric characters replaced by `_`).
+    ///
+    /// Arguments:
+    /// - `name`: Name of the code constant (e.g. `APP`).
+    /// - `code_path`: Path of the transpiled code file.
+    pub fn rust_source<P: AsRef<Path>>(&self, name: &str, code_path: P) -> String {
+        let mut source = String::new();
+        // Unwraps are safe because writing to a string doesn't fail
+        writeln!(
+            source,
+            "// Generated by embive::guest_build from {}",
+            self.elf_path.display()
+        )
+        .unwrap();
+        writeln!(
+            source,
+            "pub static {name}: &[u8] = include_bytes!({:?});",
+            code_path.as_ref().display().to_string()
+        )
+        .unwrap();
+        for (symbol, address) in &self.symbols {
+            let symbol: String = symbol
+                .chars()
+                .map(|c| match c.is_ascii_alphanumeric() {
+                    true => c.to_ascii_uppercase(),
+                    false => '_',
+                })
+                .collect();
+            writeln!(source, "pub const {name}_{symbol}: u32 = {address:#010x};").unwrap();
+        }
+
+        source
+    }
+
+    /// Write the transpiled code (`{name}.bin`) and its Rust constants (`{name}.rs`, check [`GuestImage::rust_source`])
+    /// to a directory (e.g. `OUT_DIR`). File names are lowercase.
+    ///
+    /// Arguments:
+    /// - `dir`: The output directory.
+    /// - `name`: Name of the code constant (e.g. `APP`).
+    ///
+    /// Returns:
+    /// - `Ok(PathBuf)`: Success, returns the path of the Rust file.
+    /// - `Err(GuestBuildError)`: Failed to write the files.
+    pub fn write<P: AsRef<Path>>(&self, dir: P, name: &str) -> Result<PathBuf, GuestBuildError> {
+        let file_name = name.to_ascii_lowercase();
+        let code_path = dir.as_ref().join(std::format!("{file_name}.bin"));
+        let source_path = dir.as_ref().join(std::format!("{file_name}.rs"));
+
+        fs::write(&code_path, &self.code)?;
+        fs::write(&source_path, self.rust_source(name, &code_path))?;
+        Ok(source_path)
+    }
+}
+
+/// Get the output directory of a Cargo profile (`dev` and `test` build to `debug`, `bench` to `release`).
+fn profile_dir(profile: &str) -> &str {
+    match profile {
+        "dev" | "test" => "debug",
+        "bench" => "release",
+        profile => profile,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_ELF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/app.elf");
+
+    #[test]
+    fn test_elf_path() {
+        let build = GuestBuild::new("guest", "app");
+        assert_eq!(
+            build.elf_path(),
+            Path::new("guest/target/riscv32imac-unknown-none-elf/release/app")
+        );
+
+        let build = build
+            .target("riscv32imc-unknown-none-elf")
+            .profile("dev")
+            .target_dir("out");
+        assert_eq!(
+            build.elf_path(),
+            Path::new("out/riscv32imc-unknown-none-elf/debug/app")
+        );
+    }
+
+    #[test]
+    fn test_from_elf() {
+        let image = GuestImage::from_elf(APP_ELF, &["interrupt_handler"]).unwrap();
+        let elf = fs::read(APP_ELF).unwrap();
+
+        assert_eq!(
+            image.code(),
+            transpile_elf_vec_with_config(&elf, &Config::default()).unwrap()
+        );
+        assert_eq!(
+            image.symbols(),
+            &[(
+                "interrupt_handler".to_owned(),
+                symbol(&elf, "interrupt_handler").unwrap().unwrap()
+            )]
+        );
+
+        assert!(matches!(
+            GuestImage::from_elf(APP_ELF, &["missing"]),
+            Err(GuestBuildError::MissingSymbol(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            GuestImage::from_elf("missing.elf", &[]),
+            Err(GuestBuildError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_write() {
+        let image = GuestImage::from_elf(APP_ELF, &["_code_entry"]).unwrap();
+        let dir = env::temp_dir().join(std::format!("embive-guest-build-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let source_path = image.write(&dir, "APP").unwrap();
+        let code_path = dir.join("app.bin");
+        assert_eq!(source_path, dir.join("app.rs"));
+        assert_eq!(fs::read(&code_path).unwrap(), image.code());
+
+        let source = fs::read_to_string(&source_path).unwrap();
+        assert!(source.contains(&std::format!(
+            "pub static APP: &[u8] = include_bytes!({:?});",
+            code_path.display().to_string()
+        )));
+        assert!(source.contains(&std::format!(
+            "pub const APP__CODE_ENTRY: u32 = {:#010x};",
+            image.symbols()[0].1
+        )));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_error() {
+        let build = GuestBuild::new("missing-guest-dir", "app");
+        assert!(matches!(build.build(), Err(GuestBuildError::Io(_))));
+    }
+}
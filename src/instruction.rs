@@ -10,18 +10,48 @@ mod riscv_macro;
 pub use embive::Instruction;
 
 /// Embive Instruction
+///
+/// Embive instruction representation, for external tooling (e.g. disassemblers, analyzers, linters):
+/// - [`embive::Instruction`]: Raw instruction word, as stored in transpiled code.
+/// - [`embive::Decoded`]: Decoded instruction, one variant per opcode wrapping its struct.
+/// - Per-opcode structs (e.g. [`embive::OpImm`]), wrapping their format (e.g. [`embive::TypeI`]) with the
+///   operation selectors (e.g. [`embive::OpImm::ADDI_FUNC`]), check [`embive::InstructionImpl`].
+///
+/// Example:
+/// ```
+/// use embive::instruction::embive::{Decoded, Instruction, InstructionImpl, OpImm, TypeI};
+///
+/// // addi a0, a0, 1
+/// let addi = OpImm(TypeI {
+///     rd_rs2: 10,
+///     rs1: 10,
+///     imm: 1,
+///     func: OpImm::ADDI_FUNC,
+/// });
+/// let instruction = Instruction::from(Decoded::OpImm(addi));
+/// assert_eq!(instruction.size(), 4);
+///
+/// // Back from the raw word
+/// let raw = u32::from(instruction);
+/// assert_eq!(raw, addi.encode() | OpImm::opcode() as u32);
+/// assert_eq!(Instruction::from(raw).decode(), Some(Decoded::OpImm(addi)));
+/// assert_eq!(OpImm::decode(raw), addi);
+/// ```
 #[cfg(any(feature = "transpiler", feature = "interpreter"))]
-pub(crate) mod embive {
+pub mod embive {
     use super::embive_macro::instructions;
-    use crate::format::{
-        Format, Size, TypeB, TypeCB1, TypeCB2, TypeCB4, TypeCI1, TypeCI2, TypeCI3, TypeCI4,
-        TypeCI5, TypeCIW, TypeCJ, TypeCL, TypeCR, TypeCS, TypeCSS, TypeI, TypeJ, TypeR, TypeU,
+    use crate::format::Format;
+    #[doc(inline)]
+    pub use crate::format::{
+        Size, TypeB, TypeCB1, TypeCB2, TypeCB4, TypeCI1, TypeCI2, TypeCI3, TypeCI4, TypeCI5,
+        TypeCIW, TypeCJ, TypeCL, TypeCR, TypeCS, TypeCSS, TypeI, TypeJ, TypeR, TypeU, TypeUL,
     };
 
     /// Embive Instruction Struct
     ///
     /// This struct wraps a raw embive instruction (u32)
     /// with a custom implementation for the Debug trait.
+    /// Check [`Instruction::decode`] for the decoded instruction.
     #[derive(Clone, Copy, PartialEq)]
     #[repr(transparent)]
     pub struct Instruction(u32);
@@ -40,6 +70,41 @@ pub(crate) mod embive {
         }
     }
 
+    impl Instruction {
+        /// Instruction size, in bytes (2 for compressed instructions, 8 for load-immediates, check [`Lui`],
+        /// 4 otherwise).
+        ///
+        /// Only the opcode (lowest 5 bits) and the load-immediate flag (bit 5) are needed, so the size can be
+        /// found from the first byte.
+        #[inline(always)]
+        pub fn size(&self) -> u32 {
+            if (self.0 & 0x1F) <= CSwsp::opcode() as u32 {
+                Size::Half as u32
+            } else if self.0 & 0x3F == (1 << 5) | Lui::opcode() as u32 {
+                Lui::LOAD_IMMEDIATE_SIZE
+            } else {
+                Size::Word as u32
+            }
+        }
+
+        /// Decode the instruction.
+        ///
+        /// Returns:
+        /// - `Some(Decoded)`: The decoded instruction.
+        /// - `None`: Invalid instruction.
+        #[inline(always)]
+        pub fn decode(&self) -> Option<Decoded> {
+            Decoded::decode(self.0)
+        }
+    }
+
+    impl From<Decoded> for Instruction {
+        #[inline(always)]
+        fn from(decoded: Decoded) -> Self {
+            Self(decoded.encode())
+        }
+    }
+
     impl core::fmt::Debug for Instruction {
         fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             match decode_instruction!(self.0, fmt, (f)) {
@@ -50,18 +115,21 @@ pub(crate) mod embive {
     }
 
     /// Embive Instruction Trait
-    #[allow(dead_code)]
+    ///
+    /// Implemented by the per-opcode structs, to encode and decode them (raw Embive words).
     pub trait InstructionImpl {
-        /// Instruction Opcode
+        /// Instruction Opcode (lowest 5 bits)
         fn opcode() -> u8;
 
         /// Instruction size in bytes
         fn size() -> Size;
 
-        /// Encode instruction to u32 (Embive Format)
+        /// Encode instruction to u32 (Embive Format), without the opcode (check [`InstructionImpl::opcode`])
         fn encode(&self) -> u32;
 
         /// Decode instruction from u32 (Embive Format)
+        ///
+        /// The opcode isn't checked, check [`Decoded::decode`] for any instruction.
         fn decode(inst: u32) -> Self;
     }
 
@@ -115,7 +183,11 @@ pub(crate) mod embive {
                 SW_FUNC = 7;
             }
         };
-        28 => Lui: TypeU = {};
+        28 => Lui: TypeUL = {
+            u32: {
+                LOAD_IMMEDIATE_SIZE = 8;
+            }
+        };
         29 => OpImm: TypeI = {
             u8: {
                 ADDI_FUNC = 0;
@@ -159,6 +231,7 @@ pub(crate) mod embive {
                 AMOMAX_FUNC = 26;
                 AMOMINU_FUNC = 27;
                 AMOMAXU_FUNC = 28;
+                CUSTOM_FUNC = 512;
             }
         };
         31 => SystemMiscMem: TypeI = {
@@ -168,6 +241,7 @@ pub(crate) mod embive {
                 FENCEI_IMM = 2;
                 WFI_IMM = 3;
                 MRET_IMM = 4;
+                SFENCEVMA_IMM = 5;
             },
             u8: {
                 MISC_FUNC = 0;
@@ -204,3 +278,42 @@ pub(crate) mod riscv {
     instruction!(Store, 0b010_0011);
     instruction!(System, 0b111_0011);
 }
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::embive::Instruction;
+    use crate::transpiler::transpile_raw;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut code = [
+            0x13, 0x05, 0x15, 0x00, // addi   a0, a0, 1
+            0xb7, 0x55, 0x34, 0x12, // lui    a1, 0x12345
+            0x23, 0xa2, 0xb6, 0x00, // sw     a1, 4(a3)
+            0x33, 0x85, 0xa5, 0x02, // mul    a0, a1, a0
+            0xe3, 0x1e, 0xb5, 0xfe, // bne    a0, a1, -4
+            0x05, 0x05, // c.addi a0, 1
+            0x82, 0x80, // c.jr   ra
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut offset = 0;
+        while offset < code.len() {
+            let mut bytes = [0; 4];
+            let len = (code.len() - offset).min(4);
+            bytes[..len].copy_from_slice(&code[offset..offset + len]);
+            let size = Instruction::from(u32::from_le_bytes(bytes)).size() as usize;
+            bytes[size..].fill(0);
+            let raw = u32::from_le_bytes(bytes);
+
+            // Raw word -> decoded -> raw word
+            let decoded = Instruction::from(raw).decode().unwrap();
+            assert_eq!(decoded.size() as usize, size);
+            assert_eq!(decoded.opcode() as u32, raw & 0x1F);
+            assert_eq!(decoded.encode(), raw);
+            assert_eq!(Instruction::from(decoded), Instruction::from(raw));
+            offset += size;
+        }
+    }
+}
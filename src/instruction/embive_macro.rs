@@ -14,7 +14,7 @@ macro_rules! instruction {
         crate::instruction::embive_macro::instruction!($name, $opcode, $format);
     };
     ($name:ident, $opcode:expr, $format:ty) => {
-        /// Embive Instruction
+        #[doc = concat!("Embive `", stringify!($name), "` Instruction (opcode ", stringify!($opcode), ")")]
         #[derive(Debug, Clone, Copy, PartialEq)]
         pub struct $name (pub $format);
 
@@ -27,7 +27,7 @@ macro_rules! instruction {
 
             /// Instruction size
             #[inline(always)]
-            fn size() -> crate::format::Size {
+            fn size() -> crate::instruction::embive::Size {
                 <$format>::SIZE
             }
 
@@ -77,6 +77,65 @@ macro_rules! instructions {
         }
 
         pub(crate) use decode_instruction;
+
+        /// Decoded Embive Instruction
+        ///
+        /// One variant per opcode, named after its struct.
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum Decoded {
+            $(
+                #[doc = concat!("Check [`", stringify!($name), "`]")]
+                $name($name),
+            )*
+        }
+
+        impl Decoded {
+            /// Decode an instruction from u32 (Embive Format), `None` if invalid.
+            #[inline(always)]
+            pub fn decode(inst: u32) -> Option<Self> {
+                use crate::instruction::embive::InstructionImpl;
+
+                match (inst & 0x1F) {
+                    $(
+                        $opcode => Some(Self::$name($name::decode(inst))),
+                    )*
+                    _ => None,
+                }
+            }
+
+            /// Encode the instruction to u32 (Embive Format, including the opcode).
+            pub fn encode(&self) -> u32 {
+                use crate::instruction::embive::InstructionImpl;
+
+                match self {
+                    $(
+                        Self::$name(inst) => inst.encode() | $name::opcode() as u32,
+                    )*
+                }
+            }
+
+            /// Instruction opcode (lowest 5 bits).
+            pub fn opcode(&self) -> u8 {
+                use crate::instruction::embive::InstructionImpl;
+
+                match self {
+                    $(
+                        Self::$name(_) => $name::opcode(),
+                    )*
+                }
+            }
+
+            /// Instruction size.
+            pub fn size(&self) -> crate::instruction::embive::Size {
+                use crate::instruction::embive::InstructionImpl;
+
+                match self {
+                    $(
+                        Self::$name(_) => $name::size(),
+                    )*
+                }
+            }
+        }
     };
 }
 
@@ -2,38 +2,192 @@
 //!
 //! This module contains the Embive interpreter, which is responsible for executing the interpreted code.
 //! It uses the Embive instruction set and provides a simple interface for running and debugging the code.
+mod builder;
+pub mod cache;
+mod call;
+mod capabilities;
+#[cfg(feature = "compression")]
+pub mod compression;
+mod config;
+pub mod coverage;
+mod custom;
 #[cfg(feature = "debugger")]
 mod debugger;
 mod decode_execute;
+pub mod diff;
 mod error;
+pub mod guest_log;
+pub mod heatmap;
+#[cfg(feature = "hooks")]
+mod hooks;
+pub mod integrity;
+#[cfg(feature = "interrupt-queue")]
+mod interrupt_queue;
+pub mod jump_table;
+#[cfg(feature = "interrupt-latency")]
+mod latency;
+pub mod loader;
 pub mod memory;
+#[cfg(feature = "mmu")]
+pub mod mmu;
+#[cfg(feature = "state-observer")]
+mod observer;
+pub mod ota;
+#[cfg(feature = "peripherals")]
+pub mod peripherals;
+mod permissions;
+#[cfg(feature = "pmp")]
+pub mod pmp;
+mod policy;
+pub mod privilege;
+pub mod profiler;
+pub(crate) mod random;
 pub mod registers;
+#[cfg(feature = "resource-limits")]
+mod resources;
+mod runner;
+mod scheduler;
+pub mod snapshot;
 mod state;
+mod stepping;
+mod syscall;
+pub mod syscall_trace;
+#[cfg(feature = "interrupt-queue")]
+pub mod timer_wheel;
+#[cfg(feature = "timing")]
+pub mod timing;
+pub mod trace;
 mod utils;
+pub mod watch;
+pub mod watchdog;
 
-use core::num::NonZeroI32;
+use core::{num::NonZeroI32, task::Poll};
 
-use decode_execute::decode_execute;
-use memory::{Memory, MemoryType};
-use registers::{CPURegister, Registers};
+use decode_execute::{decode_execute, memory_access};
+use memory::{Memory, RAM_OFFSET};
+use registers::{CPURegister, CSOperation, CustomCSRHandler, Registers};
 
 #[doc(inline)]
-pub use error::Error;
+pub use builder::{BuildError, InterpreterBuilder};
 #[doc(inline)]
-pub use state::State;
+pub use call::{CALL_ARGS, CALL_RETURN_ADDRESS, CALL_RETURN_PC};
+#[doc(inline)]
+pub use capabilities::HostCapabilities;
+#[doc(inline)]
+pub use config::{Config, EbreakMode, MachineIds};
+#[doc(inline)]
+pub use custom::{CustomInstruction, CustomInstructionHandler};
+#[doc(inline)]
+pub use error::{Error, MemoryAccess};
+#[doc(inline)]
+pub use permissions::SyscallPermission;
+#[doc(inline)]
+pub use policy::{InstructionPolicy, PolicyClass, POLICY_CLASSES};
+#[doc(inline)]
+pub use privilege::Privilege;
+#[doc(inline)]
+pub use random::{RngProvider, RANDOM_ERROR_INVALID_ADDRESS, RANDOM_ERROR_UNAVAILABLE};
+#[doc(inline)]
+pub use runner::{Runner, SyscallHandler};
+#[doc(inline)]
+pub use scheduler::{Scheduler, Task};
+#[doc(inline)]
+pub use state::{ExitReason, State};
+#[doc(inline)]
+pub use syscall::{extended_syscall_args, SyscallRet, EXTENDED_SYSCALL_REGISTER_ARGS};
 
 #[cfg(feature = "debugger")]
 #[doc(inline)]
-pub use debugger::Debugger;
+pub use debugger::{Checkpoint, DebugStop, Debugger, History};
+
+#[cfg(feature = "hooks")]
+#[doc(inline)]
+pub use hooks::{ReturnValue, HOOK_CAPACITY};
+#[cfg(feature = "hooks")]
+#[doc(inline)]
+pub use runner::HookHandler;
+
+#[cfg(feature = "interrupt-queue")]
+#[doc(inline)]
+pub use interrupt_queue::INTERRUPT_QUEUE_CAPACITY;
 
-use crate::instruction::embive::Instruction;
-use utils::{likely, unlikely};
+#[cfg(feature = "interrupt-latency")]
+#[doc(inline)]
+pub use latency::{InterruptLatency, LatencyStats};
+
+#[cfg(feature = "resource-limits")]
+#[doc(inline)]
+pub use resources::{Resource, ResourceLimits, ResourceUsage};
+
+#[cfg(feature = "state-observer")]
+#[doc(inline)]
+pub use observer::{StateObserver, StateTransition};
+
+use crate::{instruction::embive::Instruction, protocol};
+use utils::{likely, unlikely, utf8_prefix};
 
 /// Embive Custom Interrupt Code
-pub const EMBIVE_INTERRUPT_CODE: u32 = 16;
+pub const EMBIVE_INTERRUPT_CODE: u32 = protocol::INTERRUPT_CODE;
 
 /// Number of syscall arguments
-pub const SYSCALL_ARGS: usize = 7;
+pub const SYSCALL_ARGS: usize = protocol::SYSCALL_ARGS;
+
+/// Panic syscall number.
+///
+/// Syscall numbers below 0 are reserved for Embive. When the interpreted code calls this syscall,
+/// the interpreter returns [`State::Panicked`] instead of [`State::Called`], with the panic message
+/// address (`a0`) and length (`a1`).
+pub const PANIC_SYSCALL: i32 = protocol::PANIC_SYSCALL;
+
+/// Sleep syscall number.
+///
+/// The interpreted code requests to be woken up after a timeout, in host-defined ticks (`a0` low, `a1` high).
+/// The interpreter returns [`State::Waiting`] instead of [`State::Called`], with the timeout available through
+/// [`Interpreter::wait_timeout`]. The host can arm a timer and call [`Interpreter::run`] when it expires
+/// (or trigger an interrupt before it). Returns 0 (`a0`) to the interpreted code.
+pub const SLEEP_SYSCALL: i32 = protocol::SLEEP_SYSCALL;
+
+/// Log syscall number.
+///
+/// The interpreted code sends a batch of log records (check [`guest_log`]), with the records address (`a0`)
+/// and length in bytes (`a1`). Handled by the host as a regular syscall ([`State::Called`]), decoding the records
+/// with [`Interpreter::log_records`].
+pub const LOG_SYSCALL: i32 = protocol::LOG_SYSCALL;
+
+/// Random syscall number.
+///
+/// The interpreted code requests random bytes (e.g. a `getrandom` backend), with the buffer address (`a0`)
+/// and length in bytes (`a1`). Handled by the interpreter, without returning to the host: the buffer is filled
+/// by the RNG provider (check [`Interpreter::set_rng_provider`]), or by a deterministic generator if a seed is
+/// configured (check [`Config::rng_seed`]). Returns the error code (`a0`, 0 on success, check
+/// [`RANDOM_ERROR_UNAVAILABLE`] and [`RANDOM_ERROR_INVALID_ADDRESS`]) and the number of bytes written (`a1`).
+pub const RANDOM_SYSCALL: i32 = protocol::RANDOM_SYSCALL;
+
+/// Capabilities syscall number.
+///
+/// The interpreted code queries the host capabilities (ABI version, enabled extensions, memory sizes and host
+/// syscalls, check [`protocol::CAPABILITIES_SYSCALL`] for the layout), with the buffer address (`a0`) and length
+/// in bytes (`a1`). Handled by the interpreter, without returning to the host: the capabilities are set with
+/// [`Interpreter::set_host_capabilities`]. Returns the error code (`a0`, 0 on success) and the capabilities size (`a1`).
+pub const CAPABILITIES_SYSCALL: i32 = protocol::CAPABILITIES_SYSCALL;
+
+/// Yield syscall number.
+///
+/// The interpreted code gives control back to the host voluntarily (e.g. a cooperative scheduler in the guest).
+/// The interpreter returns [`State::Yielded`] instead of [`State::Called`], so hosts can tell voluntary yields from
+/// preemption ([`State::Running`] at the instruction limit). Returns the host backpressure (`a1`, check
+/// [`Interpreter::set_yield_backpressure`]) to the interpreted code.
+pub const YIELD_SYSCALL: i32 = protocol::YIELD_SYSCALL;
+
+/// Exit syscall number.
+///
+/// The interpreted code halts with an exit code (`a0`). The interpreter returns [`State::Halted`] instead of
+/// [`State::Called`], with [`ExitReason::ExitSyscall`] as the reason. The interpreted code doesn't resume.
+pub const EXIT_SYSCALL: i32 = protocol::EXIT_SYSCALL;
+
+/// Syscall error returned to the interpreted code (`a0`) when a host syscall isn't permitted
+/// (check [`Interpreter::set_syscall_permissions`]).
+pub const SYSCALL_ERROR_PERMISSION_DENIED: i32 = protocol::SYSCALL_ERROR_PERMISSION_DENIED;
 
 /// Embive Interpreter Struct
 #[derive(Debug)]
@@ -49,6 +203,69 @@ pub struct Interpreter<'a, M: Memory> {
     pub instruction_limit: u32,
     /// Memory reservation for atomic operations (addr, value).
     pub(crate) memory_reservation: Option<(u32, i32)>,
+    /// Thread-local storage block base address.
+    pub(crate) tls_base: Option<u32>,
+    /// Interpreter configuration.
+    pub(crate) config: Config,
+    /// Custom instruction handler.
+    pub(crate) custom_instruction_handler: Option<CustomInstructionHandler<M>>,
+    /// Custom CSR handler (check [`Interpreter::set_custom_csr_handler`]).
+    pub(crate) custom_csr_handler: registers::CustomCSRHandlerRef<'a>,
+    /// Instructions consumed outside of the interpreted code, deducted from the next instruction limit.
+    pub(crate) instruction_debt: u64,
+    /// Syscall pending (`ecall` executed, not yet handled).
+    pub(crate) syscall_pending: bool,
+    /// Pending syscall deferred by the host (check [`Interpreter::defer_syscall`]).
+    pub(crate) syscall_deferred: bool,
+    /// Timeout requested by the interpreted code while waiting (check [`SLEEP_SYSCALL`]).
+    pub(crate) wait_timeout: Option<u64>,
+    /// Entropy source of the interpreted code (check [`RANDOM_SYSCALL`]).
+    pub(crate) rng_provider: Option<RngProvider>,
+    /// Deterministic generator state (check [`Config::rng_seed`]).
+    pub(crate) rng_state: u64,
+    /// Host capabilities reported to the interpreted code (check [`CAPABILITIES_SYSCALL`]).
+    pub(crate) host_capabilities: HostCapabilities,
+    /// Allowed host syscalls (check [`Interpreter::set_syscall_permissions`]).
+    pub(crate) syscall_permissions: Option<&'a mut [SyscallPermission]>,
+    /// Instruction fetch fast path (check [`Interpreter::set_code_window`]).
+    pub(crate) code_window: Option<&'a [u8]>,
+    /// Original RISC-V code, for illegal instruction trap values (check [`Interpreter::set_source_image`]).
+    pub(crate) source_image: Option<&'a [u8]>,
+    /// Syscalls issued since the last call to [`Interpreter::run`].
+    #[cfg(feature = "syscall-flood")]
+    pub(crate) syscalls_in_run: u32,
+    /// Consecutive syscalls issued less than [`Config::syscall_min_interval`] instructions apart.
+    #[cfg(feature = "syscall-flood")]
+    pub(crate) syscall_burst: u32,
+    /// Instructions retired at the last syscall.
+    #[cfg(feature = "syscall-flood")]
+    pub(crate) last_syscall: u64,
+    /// Host backpressure returned to the interpreted code on yield (check [`YIELD_SYSCALL`]).
+    pub(crate) yield_backpressure: u32,
+    /// Interrupts queued by the host (check [`Interpreter::queue_interrupt`]).
+    #[cfg(feature = "interrupt-queue")]
+    pub(crate) interrupt_queue: interrupt_queue::InterruptQueue,
+    /// Host hooks at guest addresses (check `Interpreter::add_hook`).
+    #[cfg(feature = "hooks")]
+    pub(crate) hooks: hooks::Hooks,
+    /// Interrupt latency tracking (check `Interpreter::interrupt_latency`).
+    #[cfg(feature = "interrupt-latency")]
+    pub(crate) latency: latency::LatencyTracker,
+    /// Resource usage tracking (check `Interpreter::resource_usage`).
+    #[cfg(feature = "resource-limits")]
+    pub(crate) resources: resources::ResourceTracker,
+    /// State observer (check [`StateObserver`]).
+    #[cfg(feature = "state-observer")]
+    pub(crate) state_observer: Option<StateObserver<M>>,
+    /// Last state reported to the state observer.
+    #[cfg(feature = "state-observer")]
+    pub(crate) observed_state: State,
+    /// Address translation (check [`mmu`]).
+    #[cfg(feature = "mmu")]
+    pub(crate) mmu: mmu::Mmu,
+    /// Timing model (check [`timing`]).
+    #[cfg(feature = "timing")]
+    pub(crate) timing_model: timing::TimingModelRef<'a>,
 }
 
 impl<'a, M: Memory> Interpreter<'a, M> {
@@ -58,24 +275,341 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     /// - `memory`: System memory (code + RAM).
     /// - `instruction_limit`: Execution will yield when the instruction limit is reached (0 means no limit).
     pub fn new(memory: &'a mut M, instruction_limit: u32) -> Self {
+        Self::with_config(memory, instruction_limit, Config::default())
+    }
+
+    /// Create a new interpreter with a custom configuration.
+    ///
+    /// Arguments:
+    /// - `memory`: System memory (code + RAM).
+    /// - `instruction_limit`: Execution will yield when the instruction limit is reached (0 means no limit).
+    /// - `config`: Interpreter configuration (check [`Config`]).
+    pub fn with_config(memory: &'a mut M, instruction_limit: u32, config: Config) -> Self {
         // Create the interpreter
-        Interpreter {
+        let mut interpreter = Interpreter {
             program_counter: 0,
             registers: Default::default(),
             memory,
             instruction_limit,
             memory_reservation: None,
+            tls_base: None,
+            config,
+            custom_instruction_handler: None,
+            custom_csr_handler: Default::default(),
+            instruction_debt: 0,
+            syscall_pending: false,
+            syscall_deferred: false,
+            wait_timeout: None,
+            rng_provider: None,
+            rng_state: 0,
+            host_capabilities: HostCapabilities::default(),
+            syscall_permissions: None,
+            code_window: None,
+            source_image: None,
+            #[cfg(feature = "syscall-flood")]
+            syscalls_in_run: 0,
+            #[cfg(feature = "syscall-flood")]
+            syscall_burst: 0,
+            #[cfg(feature = "syscall-flood")]
+            last_syscall: 0,
+            yield_backpressure: 0,
+            #[cfg(feature = "interrupt-queue")]
+            interrupt_queue: Default::default(),
+            #[cfg(feature = "hooks")]
+            hooks: Default::default(),
+            #[cfg(feature = "interrupt-latency")]
+            latency: Default::default(),
+            #[cfg(feature = "resource-limits")]
+            resources: Default::default(),
+            #[cfg(feature = "state-observer")]
+            state_observer: None,
+            #[cfg(feature = "state-observer")]
+            observed_state: State::Running,
+            #[cfg(feature = "mmu")]
+            mmu: Default::default(),
+            #[cfg(feature = "timing")]
+            timing_model: Default::default(),
+        };
+
+        // Reflect the enabled extensions
+        interpreter.reset_registers();
+        interpreter.reset_rng();
+
+        interpreter
+    }
+
+    /// Create an interpreter builder, validating the configuration on build (check [`InterpreterBuilder`]).
+    ///
+    /// Arguments:
+    /// - `memory`: System memory (code + RAM).
+    pub fn builder(memory: &'a mut M) -> InterpreterBuilder<'a, M> {
+        InterpreterBuilder::new(memory)
+    }
+
+    /// Get the interpreter configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Get the system memory (e.g. to read guest RAM between calls to [`Interpreter::run`]).
+    pub fn memory(&self) -> &M {
+        self.memory
+    }
+
+    /// Get the system memory (mutable, e.g. to write guest RAM between calls to [`Interpreter::run`]).
+    pub fn memory_mut(&mut self) -> &mut M {
+        self.memory
+    }
+
+    /// Release the system memory borrow, consuming the interpreter.
+    ///
+    /// Returns the memory with the interpreter lifetime (the execution state is dropped,
+    /// check [`crate::interpreter::snapshot`] to keep it).
+    pub fn into_memory(self) -> &'a mut M {
+        self.memory
+    }
+
+    /// Set the instruction limit (0 means no limit).
+    ///
+    /// Takes effect on the next call to [`Interpreter::run`]. If changed while running
+    /// (e.g. from a custom instruction handler), the current run keeps the previous limit.
+    ///
+    /// Arguments:
+    /// - `instruction_limit`: Execution will yield when the instruction limit is reached (0 means no limit).
+    pub fn set_instruction_limit(&mut self, instruction_limit: u32) {
+        self.instruction_limit = instruction_limit;
+    }
+
+    /// Set the interpreter configuration, keeping the current execution state.
+    ///
+    /// Takes effect on the next executed instruction (also if changed while running):
+    /// - Disabled extensions result in [`Error::IllegalInstruction`], `misa` and the machine IDs are updated.
+    /// - Virtual time is recomputed from the instructions retired since the last reset,
+    ///   so changing the ratio rescales the current time (check [`Interpreter::time`]).
+    /// - The deterministic generator is reseeded (check [`Config::rng_seed`]).
+    ///
+    /// Arguments:
+    /// - `config`: Interpreter configuration (check [`Config`]).
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+        self.registers
+            .control_status
+            .set_misa_extensions(config.misa_extensions());
+        self.registers
+            .control_status
+            .set_instructions_per_tick(config.instructions_per_tick);
+        self.registers
+            .control_status
+            .set_machine_ids(config.machine_ids);
+        self.reset_rng();
+    }
+
+    /// Set the custom instruction handler (check [`CustomInstructionHandler`]).
+    ///
+    /// Custom instructions are generated by the transpiler for unsupported RISC-V instructions
+    /// (check [`crate::transpiler::Config::with_custom_handler`]).
+    ///
+    /// Arguments:
+    /// - `handler`: Custom instruction handler (`None` makes custom instructions invalid, [`Error::InvalidInstruction`]).
+    pub fn set_custom_instruction_handler(&mut self, handler: Option<CustomInstructionHandler<M>>) {
+        self.custom_instruction_handler = handler;
+    }
+
+    /// Set the custom CSR handler (check [`CustomCSRHandler`]).
+    ///
+    /// The handler is called when the interpreted code accesses a custom CSR, allowing the host to
+    /// provide values (e.g. tick count, device ID) without a syscall round-trip. It is kept on reset.
+    /// Reads of read-only registers (`0xFC0..=0xFFF`) are also forwarded to the handler. Writes to them by the
+    /// interpreted code (as to any read-only CSR) are illegal instructions, raised before reaching the handler.
+    ///
+    /// Arguments:
+    /// - `handler`: Custom CSR handler (`None` disables custom CSRs, returning [`Error::InvalidCSRegister`]).
+    pub fn set_custom_csr_handler(&mut self, handler: Option<&'a mut dyn CustomCSRHandler>) {
+        self.custom_csr_handler = registers::CustomCSRHandlerRef(handler);
+    }
+
+    /// Execute a control and status register operation, including custom CSRs (check
+    /// [`Interpreter::set_custom_csr_handler`]).
+    ///
+    /// Arguments:
+    /// - `op`: The operation to execute (`None` means only read the register).
+    /// - `addr`: The address of the register (from 0 to 4095).
+    ///
+    /// Returns:
+    /// - `Ok(u32)`: The register value prior to the operation.
+    /// - `Err(Error)`: The register address is invalid or not supported.
+    #[inline]
+    pub fn csr_operation(&mut self, op: Option<CSOperation>, addr: u16) -> Result<u32, Error> {
+        if registers::is_custom_csr(addr) {
+            return match self.custom_csr_handler.0.as_mut() {
+                Some(handler) => handler.operation(addr, op),
+                None => Err(Error::InvalidCSRegister(addr)),
+            };
         }
+
+        let value = self.registers.control_status.operation(op, addr)?;
+        if op.is_some() && addr == registers::MIP_ADDR {
+            // Memory-mapped software interrupt register (e.g. CLINT `msip`)
+            self.sync_software_interrupt();
+        }
+
+        Ok(value)
     }
 
     /// Reset the interpreter:
     /// - Program counter is reset to 0.
     /// - CPU Registers are reset to 0.
+    /// - Control and status registers are reset (`misa` and the machine IDs reflect the configuration).
+    /// - Virtual time is reset to 0.
     /// - Memory reservation is cleared.
+    /// - Thread-local storage base is cleared (call [`Interpreter::init_tls`] again if needed).
+    /// - Instruction debt is cleared (check [`Interpreter::consume_instructions`]).
+    /// - Address translation is disabled and its cache flushed (`mmu` feature).
+    /// - The deterministic generator is reseeded (check [`Config::rng_seed`]), replaying the same random bytes.
+    /// - The custom CSR handler is kept (check [`Interpreter::set_custom_csr_handler`]).
+    /// - Host hooks are kept, and fire again (`hooks` feature).
+    /// - Interrupt latency statistics are cleared (`interrupt-latency` feature).
+    /// - Resource usage is cleared (`resource-limits` feature).
+    /// - Syscall permissions are kept, quotas aren't refilled (check [`Interpreter::set_syscall_permissions`]).
+    /// - The code window is kept (check [`Interpreter::set_code_window`]).
+    /// - The source image is kept (check [`Interpreter::set_source_image`]).
     pub fn reset(&mut self) {
         self.program_counter = 0;
-        self.registers = Default::default();
+        self.reset_registers();
         self.memory_reservation = None;
+        self.tls_base = None;
+        self.instruction_debt = 0;
+        self.syscall_pending = false;
+        self.syscall_deferred = false;
+        self.wait_timeout = None;
+        #[cfg(feature = "syscall-flood")]
+        {
+            self.syscall_burst = 0;
+        }
+        #[cfg(feature = "interrupt-queue")]
+        self.clear_queued_interrupts();
+        #[cfg(feature = "hooks")]
+        self.hooks.rearm();
+        #[cfg(feature = "interrupt-latency")]
+        {
+            self.latency = Default::default();
+        }
+        #[cfg(feature = "resource-limits")]
+        {
+            self.resources = Default::default();
+        }
+        self.reset_rng();
+        #[cfg(feature = "mmu")]
+        self.mmu.flush();
+    }
+
+    /// Reset the registers to their default values, according to the configuration.
+    fn reset_registers(&mut self) {
+        self.registers = Default::default();
+        self.registers
+            .control_status
+            .set_misa_extensions(self.config.misa_extensions());
+        self.registers
+            .control_status
+            .set_instructions_per_tick(self.config.instructions_per_tick);
+        self.registers
+            .control_status
+            .set_machine_ids(self.config.machine_ids);
+        self.sync_software_interrupt();
+    }
+
+    /// Initialize a thread-local storage (TLS) block for this interpreter.
+    ///
+    /// The initialization image (`.tdata`) is copied to `base`, the remaining bytes of the block (`.tbss`)
+    /// are zeroed, and the thread pointer (`tp`) is set to `base`. Each interpreter should have its own
+    /// TLS block, so multiple instances running the same code don't share thread-locals.
+    ///
+    /// Arguments:
+    /// - `base`: Address of the TLS block (RAM), must be aligned to the TLS segment alignment.
+    /// - `template_address`: Address of the TLS initialization image (code or RAM).
+    /// - `template_size`: Size of the TLS initialization image, in bytes.
+    /// - `size`: Total size of the TLS block, in bytes (`template_size` is clamped to it).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, TLS block initialized and `tp` set.
+    /// - `Err(Error)`: Failed to copy/zero the TLS block (address out of bounds).
+    pub fn init_tls(
+        &mut self,
+        base: u32,
+        template_address: u32,
+        template_size: u32,
+        size: u32,
+    ) -> Result<(), Error> {
+        let template_size = template_size.min(size);
+        let mut buffer = [0u8; 32];
+        let mut offset = 0;
+
+        while offset < size {
+            let mut len = (size - offset).min(buffer.len() as u32);
+
+            if offset < template_size {
+                // Copy from the initialization image
+                len = len.min(template_size - offset);
+                let data = self
+                    .memory
+                    .load_bytes(template_address.wrapping_add(offset), len as usize)?;
+                buffer[..len as usize].copy_from_slice(data);
+            } else {
+                // Zero-initialized data
+                buffer[..len as usize].fill(0);
+            }
+
+            self.memory
+                .store_bytes(base.wrapping_add(offset), &buffer[..len as usize])?;
+            offset += len;
+        }
+
+        // Set thread pointer
+        self.registers.cpu.inner[CPURegister::TP as usize] = base as i32;
+        self.tls_base = Some(base);
+
+        Ok(())
+    }
+
+    /// Get the thread-local storage (TLS) block base address, if initialized.
+    ///
+    /// Returns:
+    /// - `Some(u32)`: TLS block base address (set by [`Interpreter::init_tls`]).
+    /// - `None`: TLS block not initialized.
+    pub fn tls_base(&self) -> Option<u32> {
+        self.tls_base
+    }
+
+    /// Get the current virtual time (deterministic, advanced by instruction count or timing model cycles,
+    /// check the `timing` module, `timing` feature).
+    ///
+    /// The same value is exposed to the interpreted code through the `time` CSR.
+    /// Useful for driving emulated timers (e.g. the `peripherals` feature timer).
+    ///
+    /// Returns:
+    /// - `Some(u64)`: Ticks since the last reset (check [`Config::instructions_per_tick`]).
+    /// - `None`: Virtual time is disabled.
+    pub fn time(&self) -> Option<u64> {
+        self.registers.control_status.time()
+    }
+
+    /// Consume instruction budget on behalf of the interpreted code.
+    ///
+    /// Used to account for work done by the host (e.g. expensive syscalls or custom instructions), so the
+    /// interpreted code can't bypass metering. The consumed instructions are deducted from the next
+    /// [`Interpreter::run`] calls (possibly yielding immediately) and advance the virtual time.
+    /// Without an instruction limit, only the virtual time is affected.
+    ///
+    /// Arguments:
+    /// - `count`: Number of instructions to consume.
+    pub fn consume_instructions(&mut self, count: u32) {
+        self.instruction_debt = self.instruction_debt.saturating_add(count as u64);
+        self.registers.control_status.retire_many(count as u64);
+    }
+
+    /// Get the instruction debt (instructions consumed by the host, not yet deducted from the instruction limit).
+    pub fn instruction_debt(&self) -> u64 {
+        self.instruction_debt
     }
 
     /// Run the interpreter, executing the code.
@@ -84,10 +618,63 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     /// - `Ok(State)`: Success, current state (check [`State`]).
     /// - `Err(Error)`: Failed to run.
     pub fn run(&mut self) -> Result<State, Error> {
+        // Deferred syscalls must be completed first
+        if unlikely(self.syscall_deferred) {
+            #[cfg(feature = "state-observer")]
+            self.observe_state(Ok(State::SyscallPending));
+            return Ok(State::SyscallPending);
+        }
+
+        // Resuming (e.g. after a syscall)
+        #[cfg(feature = "state-observer")]
+        self.observe_state(Ok(State::Running));
+
+        let result = self.run_code();
+        #[cfg(feature = "state-observer")]
+        self.observe_state(result.as_ref().copied());
+
+        result
+    }
+
+    /// Run the interpreted code, until it stops or the instruction limit is reached.
+    #[inline(always)]
+    fn run_code(&mut self) -> Result<State, Error> {
+        // Unhandled syscalls and wait timeouts are discarded
+        self.syscall_pending = false;
+        self.wait_timeout = None;
+        #[cfg(feature = "syscall-flood")]
+        {
+            self.syscalls_in_run = 0;
+        }
+        #[cfg(feature = "resource-limits")]
+        self.reset_run_resource_usage();
+
         // Check if there is an instruction limit
-        if likely(self.instruction_limit > 0) {
-            // Run the interpreter with an instruction limit
-            for _ in 0..self.instruction_limit {
+        let limited = likely(self.instruction_limit > 0);
+        let budget = if limited {
+            // Deduct instructions consumed by the host (e.g. syscalls)
+            let debt = self.instruction_debt.min(self.instruction_limit as u64);
+            self.instruction_debt -= debt;
+            self.instruction_limit - debt as u32
+        } else {
+            self.instruction_debt = 0;
+            u32::MAX
+        };
+
+        // A single loop for both cases, so the (inlined) interpreter core is only emitted once
+        loop {
+            // Halt when the instruction budget is exhausted
+            let (steps, capped) = match self.remaining_instructions() {
+                Some(0) => {
+                    return Ok(State::Halted {
+                        reason: ExitReason::InstructionLimit,
+                    })
+                }
+                Some(remaining) if remaining <= budget as u64 => (remaining as u32, true),
+                _ => (budget, false),
+            };
+
+            for _ in 0..steps {
                 // Step through the program
                 let state = self.step()?;
 
@@ -97,19 +684,22 @@ impl<'a, M: Memory> Interpreter<'a, M> {
                 }
             }
 
-            // Yield after the instruction limit (still running)
-            return Ok(State::Running);
+            if limited && !capped {
+                // Yield after the instruction limit (still running)
+                return Ok(State::Running);
+            }
         }
+    }
 
-        // No instruction limit
-        loop {
-            // Step through the program
-            let state = self.step()?;
-
-            if unlikely(state != State::Running) {
-                // Stop running
-                return Ok(state);
-            }
+    /// Get the number of instructions left in the instruction budget (check [`Config::max_instructions`]).
+    ///
+    /// Returns:
+    /// - `Some(u64)`: Instructions left (`0` once exhausted).
+    /// - `None`: No instruction budget.
+    pub fn remaining_instructions(&self) -> Option<u64> {
+        match self.config.max_instructions {
+            0 => None,
+            max => Some(max.saturating_sub(self.registers.control_status.instructions_retired())),
         }
     }
 
@@ -120,21 +710,297 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     /// - `Err(Error)`: Failed to execute.
     #[inline(always)]
     pub fn step(&mut self) -> Result<State, Error> {
-        // Fetch next instruction
-        let data = self.fetch()?;
+        // Take pending (software and queued) interrupts before the next instruction
+        if let Some(state) = self.poll_interrupts() {
+            return Ok(state);
+        }
+
+        // Stop at host hooks, before executing the hooked instruction
+        #[cfg(feature = "hooks")]
+        if unlikely(!self.hooks.is_empty()) {
+            if let Some(id) = self.hooks.hit(self.program_counter) {
+                return Ok(State::Hooked(id));
+            }
+        }
+
+        // Fetch next instruction (errors may trap, e.g. in user mode)
+        let data = match self.fetch() {
+            Ok(data) => data,
+            Err(error) => return self.trap_error(error),
+        };
+        let pc = self.program_counter;
+
+        // Timing model inputs are read before execution (registers may be overwritten)
+        #[cfg(feature = "timing")]
+        let timed = unlikely(self.timing_model.is_some());
+        #[cfg(feature = "timing")]
+        let data_address = match timed {
+            true => self.timing_data_address(data),
+            false => None,
+        };
 
         // Decode and execute the instruction
-        decode_execute(self, data)
+        let state = match decode_execute(self, data) {
+            Ok(state) => state,
+            Err(error) => return self.trap_error(error.in_instruction(pc, memory_access(data))),
+        };
+
+        // Advance virtual time (memory stalls, e.g. cache misses, are charged to the instruction)
+        #[cfg(feature = "timing")]
+        {
+            let stall = self.memory.take_stall_cycles();
+            match timed {
+                true => self.retire_timed(pc, data, data_address, stall),
+                false if likely(stall == 0) => self.registers.control_status.retire(),
+                false => self
+                    .registers
+                    .control_status
+                    .retire_cost(stall.saturating_add(1)),
+            }
+        }
+        #[cfg(not(feature = "timing"))]
+        self.registers.control_status.retire();
+
+        Ok(state)
+    }
+
+    /// Set the host backpressure, returned to the interpreted code when it yields (check [`YIELD_SYSCALL`]).
+    ///
+    /// Host-defined hint, e.g. the number of other guests ready to run: `0` lets the guest continue,
+    /// higher values ask it to yield more often.
+    ///
+    /// Arguments:
+    /// - `backpressure`: Host backpressure. Default: `0`.
+    pub fn set_yield_backpressure(&mut self, backpressure: u32) {
+        self.yield_backpressure = backpressure;
+    }
+
+    /// Take a pending software interrupt, then a queued interrupt (`interrupt-queue` feature), if enabled
+    /// (check [`Interpreter::raise_software_interrupt`]).
+    ///
+    /// Returns:
+    /// - `None`: Continue running.
+    /// - `Some(State)`: Interrupt limit reached, interrupts stay pending (`resource-limits` feature).
+    #[inline(always)]
+    pub(crate) fn poll_interrupts(&mut self) -> Option<State> {
+        if unlikely(self.registers.control_status.mip_software)
+            && self.registers.control_status.software_interrupt_ready()
+        {
+            #[cfg(feature = "resource-limits")]
+            if let Some(state) = self.use_resource(Resource::Interrupts, 1) {
+                return Some(state);
+            }
+            self.registers
+                .control_status
+                .take_software_interrupt(&mut self.program_counter);
+
+            // Traps invalidate the memory reservation
+            self.memory_reservation = None;
+            #[cfg(feature = "interrupt-latency")]
+            self.latency_software_entry();
+        }
+
+        #[cfg(feature = "interrupt-queue")]
+        if unlikely(!self.interrupt_queue.is_empty()) {
+            return self.deliver_queued_interrupt();
+        }
+
+        None
+    }
+
+    /// Invalidate the memory reservation (check [`Config::reservation_granule`]) if a store overlaps it.
+    ///
+    /// Arguments:
+    /// - `address`: Store address.
+    /// - `len`: Store length, in bytes.
+    #[inline(always)]
+    pub(crate) fn invalidate_reservation(&mut self, address: u32, len: u32) {
+        if let Some((reserved, _)) = self.memory_reservation {
+            let mask = self.config.reservation_mask();
+            let set = reserved & mask;
+            if address & mask == set || address.wrapping_add(len - 1) & mask == set {
+                self.memory_reservation = None;
+            }
+        }
+    }
+
+    /// Translate the address of a data access (check [`mmu`]) and check its memory protection (check [`pmp`]).
+    /// Without the `mmu` and `pmp` features, this is the identity.
+    ///
+    /// Arguments:
+    /// - `address`: Address of the access.
+    /// - `len`: Access width, in bytes.
+    /// - `access`: Kind of access (load reservations are loads).
+    #[cfg_attr(not(any(feature = "mmu", feature = "pmp")), allow(unused_variables))]
+    #[inline(always)]
+    pub(crate) fn translate(
+        &mut self,
+        address: u32,
+        len: u32,
+        access: MemoryAccess,
+    ) -> Result<u32, Error> {
+        #[cfg(feature = "mmu")]
+        let physical = self.translate_virtual(address, len, access)?;
+        #[cfg(not(feature = "mmu"))]
+        let physical = address;
+
+        #[cfg(feature = "pmp")]
+        self.check_access(address, physical, len, access)?;
+
+        Ok(physical)
     }
 
     /// Fetch the next instruction from the program counter.
     ///
     /// Returns:
     /// - `Ok(Instruction)`: The instruction that was fetched.
-    /// - `Err(Error)`: The program counter is out of bounds.
+    /// - `Err(Error)`: The program counter is out of bounds, or in RAM with RAM execution disabled
+    ///   ([`Error::ExecuteFault`], check [`Config::ram_execution`]), or not mapped (`mmu` feature), or not
+    ///   executable ([`Error::AccessFault`], `pmp` feature).
     #[inline(always)]
     pub fn fetch(&mut self) -> Result<Instruction, Error> {
-        u32::load(self.memory, self.program_counter).map(Instruction::from)
+        self.fetch_parcels(false)
+    }
+
+    /// Fetch a 32-bit operand of the instruction at the program counter (e.g. the immediate of a load-immediate,
+    /// check [`crate::instruction::embive::Lui`]), with the same checks as [`Interpreter::fetch`].
+    ///
+    /// Arguments:
+    /// - `offset`: Operand offset from the program counter.
+    ///
+    /// Returns:
+    /// - `Ok(u32)`: The operand that was fetched.
+    /// - `Err(Error)`: Same as [`Interpreter::fetch`], at the operand address.
+    #[inline(never)]
+    pub(crate) fn fetch_operand(&mut self, offset: u32) -> Result<u32, Error> {
+        let pc = self.program_counter;
+        self.program_counter = pc.wrapping_add(offset);
+        let operand = self.fetch_parcels(true).map(u32::from);
+        self.program_counter = pc;
+
+        operand
+    }
+
+    /// Fetch from the program counter (check [`Interpreter::fetch`]).
+    ///
+    /// Arguments:
+    /// - `word`: Always fetch (and check) 4 bytes, instead of finding the size from the instruction.
+    #[cfg_attr(not(any(feature = "mmu", feature = "pmp")), allow(unused_variables))]
+    #[inline(always)]
+    fn fetch_parcels(&mut self, word: bool) -> Result<Instruction, Error> {
+        // Virtual program counter
+        #[cfg(feature = "mmu")]
+        if unlikely(self.registers.control_status.satp() & mmu::SATP_MODE_SV32 != 0) {
+            return self.fetch_translated(word);
+        }
+
+        // Code region is always executable, RAM only if allowed
+        self.check_ram_execution()?;
+
+        // Fast path: read straight from the code window, without going through the memory
+        let instruction = match self.window_fetch() {
+            Some(instruction) => instruction,
+            None => self
+                .memory
+                .load_u32(self.program_counter)
+                .map(Instruction::from)
+                .map_err(|error| match error {
+                    Error::InvalidMemoryAddress { .. } => {
+                        Error::InvalidProgramCounter(self.program_counter)
+                    }
+                    error => error,
+                })?,
+        };
+
+        // Memory protection applies to each instruction parcel (2 bytes)
+        #[cfg(feature = "pmp")]
+        self.check_fetch_parcels(word || instruction.size() != 2)?;
+
+        Ok(instruction)
+    }
+
+    /// Check if the program counter is executable: always in the code region, in RAM only if allowed
+    /// (check [`Config::ram_execution`]).
+    #[inline(always)]
+    fn check_ram_execution(&self) -> Result<(), Error> {
+        if unlikely(!self.config.ram_execution && self.program_counter >= RAM_OFFSET) {
+            return Err(Error::ExecuteFault(self.program_counter));
+        }
+
+        Ok(())
+    }
+
+    /// Check the memory protection of each parcel (2 bytes) of the instruction at the program counter
+    /// (check [`pmp`]).
+    ///
+    /// Arguments:
+    /// - `word`: The instruction has two parcels (not compressed).
+    #[cfg(feature = "pmp")]
+    #[inline(always)]
+    fn check_fetch_parcels(&self, word: bool) -> Result<(), Error> {
+        let pc = self.program_counter;
+        self.check_fetch(pc, pc)?;
+        if word {
+            self.check_fetch(pc.wrapping_add(2), pc.wrapping_add(2))?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the next instruction from the code window, if it holds all of its 4 bytes
+    /// (check [`Interpreter::set_code_window`]).
+    #[inline(always)]
+    fn window_fetch(&self) -> Option<Instruction> {
+        let pc = self.program_counter as usize;
+        let bytes = self.code_window?.get(pc..pc.wrapping_add(4))?;
+        let bytes = <[u8; 4]>::try_from(bytes).ok()?;
+
+        Some(Instruction::from(u32::from_le_bytes(bytes)))
+    }
+
+    /// Set the code window, a fast path for instruction fetches.
+    ///
+    /// Instructions within the window are read straight from it, skipping the memory ([`Memory::load_u32`]),
+    /// instructions outside of it (e.g. RAM, or the last 2-byte instruction) still go through the memory.
+    /// The window is borrowed immutably, so it can't change while set. Checks (RAM execution, `pmp` and `mmu`
+    /// features) still apply, but memory wrappers don't see these fetches (e.g.
+    /// [`heatmap::HeatmapMemory`] doesn't record them).
+    ///
+    /// Arguments:
+    /// - `code`: Code mapped at address `0`, the same bytes the memory holds (e.g. [`memory::SliceMemory::code`]).
+    ///   `None` disables the fast path.
+    pub fn set_code_window(&mut self, code: Option<&'a [u8]>) {
+        self.code_window = code;
+    }
+
+    /// Set the source image, the original RISC-V code of the code region (before transpilation).
+    ///
+    /// Transpilation keeps the instruction sizes and addresses, so the original instruction bits can be read back
+    /// from it: illegal instruction traps report them in `mtval` (check [`privilege`]), as the privileged spec
+    /// recommends. Without a source image (or for instructions outside of it, e.g. in RAM), `mtval` is `0`.
+    ///
+    /// Arguments:
+    /// - `code`: Original code mapped at address `0` (e.g. the raw RISC-V binary, or
+    ///   [`crate::transpiler::Config::with_source_image`] for ELF files). `None` reports `0`.
+    pub fn set_source_image(&mut self, code: Option<&'a [u8]>) {
+        self.source_image = code;
+    }
+
+    /// Decode the panic message of a [`State::Panicked`] state from the interpreted code memory.
+    ///
+    /// Invalid UTF-8 sequences are not supported, the message is truncated at the first invalid byte.
+    ///
+    /// Arguments:
+    /// - `msg_ptr`: Address of the panic message.
+    /// - `len`: Length of the panic message, in bytes.
+    ///
+    /// Returns:
+    /// - `Ok(&str)`: The panic message.
+    /// - `Err(Error)`: The message address is out of bounds.
+    pub fn panic_message(&mut self, msg_ptr: u32, len: u32) -> Result<&str, Error> {
+        let bytes = self.memory.load_bytes(msg_ptr, len as usize)?;
+
+        Ok(utf8_prefix(bytes))
     }
 
     /// Execute an interrupt as configured by the interpreted code.
@@ -152,7 +1018,8 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     ///
     /// Returns:
     /// - `Ok(())`: Success, interrupt executed.
-    /// - `Err(Error)`: Interrupt not enabled by interpreted code.
+    /// - `Err(Error)`: Interrupt not enabled by interpreted code, or interrupt limit reached
+    ///   (`Error::ResourceLimit`, `resource-limits` feature).
     pub fn interrupt(&mut self, value: i32) -> Result<(), Error> {
         // Check if interrupt is enabled
         if unlikely(!self.registers.control_status.interrupt_enabled()) {
@@ -160,42 +1027,65 @@ impl<'a, M: Memory> Interpreter<'a, M> {
             return Err(Error::InterruptNotEnabled);
         }
 
+        #[cfg(feature = "resource-limits")]
+        if self.use_resource(Resource::Interrupts, 1).is_some() {
+            return Err(Error::ResourceLimit(Resource::Interrupts));
+        }
+
+        let now = self.registers.control_status.instructions_retired();
+        self.interrupt_trap(value, Some(now));
+
+        Ok(())
+    }
+
+    /// Trap to the interrupt handler (interrupts must be enabled).
+    ///
+    /// Arguments:
+    /// - `value`: Value to be passed to the interrupt handler (through `mtval` CSR).
+    /// - `raised`: Instructions retired when the interrupt was injected (`interrupt-latency` feature).
+    #[cfg_attr(not(feature = "interrupt-latency"), allow(unused_variables))]
+    pub(crate) fn interrupt_trap(&mut self, value: i32, raised: Option<u64>) {
         // Set interrupt
         self.registers.control_status.set_interrupt();
 
-        // Trap to the interrupt handler
+        // Trap to the interrupt handler (invalidating the memory reservation)
         self.registers
             .control_status
-            .trap_entry(&mut self.program_counter, value);
-
-        Ok(())
+            .interrupt_entry(&mut self.program_counter, value);
+        self.memory_reservation = None;
+        #[cfg(feature = "interrupt-latency")]
+        self.latency_trap_entry(raised);
     }
 
     /// Get the syscall arguments.
     #[inline(always)]
     fn syscall_arguments(&mut self) -> (i32, &[i32; SYSCALL_ARGS], &mut M) {
         // Syscall Arguments
-        let args = self.registers.cpu.inner[CPURegister::A0 as usize..]
-            .first_chunk()
-            // Unwrap is safe because the slice is guaranteed to have more than SYSCALL_ARGS elements.
-            .unwrap();
+        let args = self.registers.cpu.syscall_args();
 
         // Syscall Number
-        let nr = self.registers.cpu.inner[CPURegister::A7 as usize];
+        let nr = self.registers.cpu.a7();
 
         (nr, args, self.memory)
     }
 
     /// Set the syscall result.
     #[inline(always)]
-    fn syscall_result(&mut self, result: Result<i32, NonZeroI32>) {
+    fn syscall_result<R: Into<SyscallRet>>(&mut self, result: Result<R, NonZeroI32>) {
+        self.syscall_pending = false;
+        self.syscall_deferred = false;
+
         match result {
             Ok(value) => {
                 // Clear error code
                 self.registers.cpu.inner[CPURegister::A0 as usize] = 0;
 
                 // Set return value
-                self.registers.cpu.inner[CPURegister::A1 as usize] = value;
+                let (low, high) = value.into().registers();
+                self.registers.cpu.inner[CPURegister::A1 as usize] = low;
+                if let Some(high) = high {
+                    self.registers.cpu.inner[CPURegister::A2 as usize] = high;
+                }
             }
             Err(error) => {
                 // Set error code
@@ -207,6 +1097,124 @@ impl<'a, M: Memory> Interpreter<'a, M> {
         }
     }
 
+    /// Get the timeout requested by the interpreted code (after [`State::Waiting`]).
+    ///
+    /// Returns:
+    /// - `Some(u64)`: Wake up after this many ticks (host-defined unit, check [`SLEEP_SYSCALL`]).
+    ///   The host can arm a timer instead of polling, then call [`Interpreter::run`] when it expires.
+    /// - `None`: No timeout, waiting for an interrupt (`wfi`), or not waiting.
+    pub fn wait_timeout(&self) -> Option<u64> {
+        self.wait_timeout
+    }
+
+    /// Raise a machine software interrupt (`mip.MSIP`).
+    ///
+    /// The interrupt is taken before the next executed instruction (e.g. right after the syscall returns,
+    /// when raised while handling a syscall), as soon as it is enabled by the interpreted code
+    /// (CSRs `mstatus.MIE` and `mie.MSIE`). The trap handler (`mtvec`) receives `mcause` 3 (interrupt bit set)
+    /// and the value in `mtval`.
+    ///
+    /// The interrupt stays pending until `mip.MSIP` is cleared, by the interpreted code (usually in the
+    /// trap handler, before `mret`) or by [`Interpreter::clear_software_interrupt`].
+    ///
+    /// Arguments:
+    /// - `value`: Value to be passed to the interrupt handler (through `mtval` CSR).
+    pub fn raise_software_interrupt(&mut self, value: i32) {
+        #[cfg(feature = "interrupt-latency")]
+        self.latency_software_raised();
+        self.registers.control_status.set_software_interrupt(value);
+        self.sync_software_interrupt();
+    }
+
+    /// Clear a pending machine software interrupt (`mip.MSIP`, check [`Interpreter::raise_software_interrupt`]).
+    pub fn clear_software_interrupt(&mut self) {
+        self.registers.control_status.mip_software = false;
+        self.sync_software_interrupt();
+    }
+
+    /// Synchronize `mip.MSIP` with the memory-mapped software interrupt register, if any (e.g. CLINT `msip`,
+    /// check [`Memory::sync_software_interrupt`]).
+    ///
+    /// Only needed when either side is written: after word stores, `mip` CSR writes, host raises and clears,
+    /// resets and snapshot restores.
+    #[inline(always)]
+    pub(crate) fn sync_software_interrupt(&mut self) {
+        let control_status = &mut self.registers.control_status;
+        control_status.mip_software = self
+            .memory
+            .sync_software_interrupt(control_status.mip_software);
+    }
+
+    /// Get the pending syscall (after [`State::Called`]), without handling it.
+    ///
+    /// Allows routing syscalls to different subsystems, before committing to handle it
+    /// ([`Interpreter::syscall`], [`Interpreter::complete_syscall`]) or bouncing it back ([`Interpreter::reject_syscall`]).
+    ///
+    /// Returns:
+    /// - `Some((i32, [i32; SYSCALL_ARGS]))`: Syscall number (`a7`) and arguments (`a0` to `a6`).
+    /// - `None`: No syscall pending (already handled, or [`Interpreter::run`] was called again).
+    pub fn pending_syscall(&self) -> Option<(i32, [i32; SYSCALL_ARGS])> {
+        if !self.syscall_pending {
+            return None;
+        }
+
+        Some((self.registers.cpu.a7(), *self.registers.cpu.syscall_args()))
+    }
+
+    /// Defer the pending syscall, to complete it later (e.g. from another thread or task servicing guest I/O).
+    ///
+    /// The interpreter stays in [`State::SyscallPending`]: [`Interpreter::run`] doesn't execute any code
+    /// until the syscall is completed ([`Interpreter::complete_syscall`], [`Interpreter::reject_syscall`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, syscall deferred.
+    /// - `Err(Error)`: No syscall pending ([`Error::NoPendingSyscall`]).
+    pub fn defer_syscall(&mut self) -> Result<(), Error> {
+        if !self.syscall_pending {
+            return Err(Error::NoPendingSyscall);
+        }
+
+        self.syscall_deferred = true;
+        Ok(())
+    }
+
+    /// Check if the pending syscall was deferred (check [`Interpreter::defer_syscall`]).
+    pub fn syscall_deferred(&self) -> bool {
+        self.syscall_deferred
+    }
+
+    /// Complete the pending syscall with a result (check [`Interpreter::pending_syscall`]).
+    ///
+    /// Arguments:
+    /// - `result`: Mapped to the value (`a1`/`a2`) and error (`a0`) returned to the interpreted code.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, result returned to the interpreted code.
+    /// - `Err(Error)`: No syscall pending ([`Error::NoPendingSyscall`]).
+    pub fn complete_syscall<R: Into<SyscallRet>>(
+        &mut self,
+        result: Result<R, NonZeroI32>,
+    ) -> Result<(), Error> {
+        if !self.syscall_pending {
+            return Err(Error::NoPendingSyscall);
+        }
+
+        self.syscall_result(result);
+        Ok(())
+    }
+
+    /// Reject the pending syscall, returning an error code to the interpreted code (check [`Interpreter::pending_syscall`]).
+    ///
+    /// Arguments:
+    /// - `error`: Error code returned to the interpreted code (`a0`).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, error returned to the interpreted code.
+    /// - `Err(Error)`: No syscall pending ([`Error::NoPendingSyscall`]).
+    pub fn reject_syscall(&mut self, error: NonZeroI32) -> Result<(), Error> {
+        self.complete_syscall::<i32>(Err(error))
+    }
+
     /// Handle a system call.
     ///
     /// System calls are triggered by the `ecall` instruction.
@@ -214,7 +1222,7 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     /// - `a7`: Syscall number.
     /// - `a0` to `a6`: Arguments.
     /// - `a0`: Return error code.
-    /// - `a1`: Return value.
+    /// - `a1`: Return value (`a1` low and `a2` high for 64-bit values, check [`SyscallRet`]).
     ///
     /// Arguments:
     /// - `function`: System call function (FnMut closure):
@@ -224,12 +1232,14 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     ///         - `Memory`: System Memory (code + RAM).
     ///
     ///     - Returns:
-    ///         - `Result<Result<i32, NonZeroI32>, E>`:
+    ///         - `Result<Result<R, NonZeroI32>, E>`:
     ///             - Outer `Result`: Ok(()) if the syscall was successful, Err(E) if an internal error occurred. Errors are returned to the calling code.
-    ///             - Inner `Result`: Mapped to the value (`a1`) and error (`a0`) returned to the interpreted code.
-    pub fn syscall<F, E>(&mut self, function: &mut F) -> Result<(), E>
+    ///             - Inner `Result`: Mapped to the value (`a1`/`a2`) and error (`a0`) returned to the interpreted code.
+    ///               The value can be any type convertible to [`SyscallRet`] (e.g. `i32`, `i64`).
+    pub fn syscall<F, E, R>(&mut self, function: &mut F) -> Result<(), E>
     where
-        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<i32, NonZeroI32>, E>,
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<R, NonZeroI32>, E>,
+        R: Into<SyscallRet>,
     {
         // Get syscall arguments
         let (nr, args, memory) = self.syscall_arguments();
@@ -243,6 +1253,67 @@ impl<'a, M: Memory> Interpreter<'a, M> {
         Ok(())
     }
 
+    /// Handle a system call, or defer it (check [`Interpreter::defer_syscall`]).
+    ///
+    /// Same as [`Interpreter::syscall`], but the syscall function can return [`Poll::Pending`] when the result
+    /// is not available yet (e.g. guest I/O serviced by another executor). The syscall is then completed later,
+    /// with [`Interpreter::complete_syscall`].
+    ///
+    /// Arguments:
+    /// - `function`: System call function (FnMut closure), returning `Result<Poll<Result<R, NonZeroI32>>, E>`.
+    ///
+    /// Returns:
+    /// - `Ok(Poll::Ready(()))`: Success, result returned to the interpreted code.
+    /// - `Ok(Poll::Pending)`: Syscall deferred, the interpreter stays in [`State::SyscallPending`].
+    /// - `Err(E)`: Internal error of the syscall function.
+    pub fn syscall_deferrable<F, E, R>(&mut self, function: &mut F) -> Result<Poll<()>, E>
+    where
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Poll<Result<R, NonZeroI32>>, E>,
+        R: Into<SyscallRet>,
+    {
+        // Get syscall arguments
+        let (nr, args, memory) = self.syscall_arguments();
+
+        // Call the syscall function
+        match function(nr, args, memory)? {
+            Poll::Ready(result) => {
+                // Set the syscall result
+                self.syscall_result(result);
+                Ok(Poll::Ready(()))
+            }
+            Poll::Pending => {
+                self.syscall_deferred = true;
+                Ok(Poll::Pending)
+            }
+        }
+    }
+
+    /// Handle a system call, consuming instruction budget (check [`Interpreter::consume_instructions`]).
+    ///
+    /// Same as [`Interpreter::syscall`], but the syscall function also returns a synthetic cost
+    /// (number of instructions), so guests can't bypass metering by doing heavy work through host calls.
+    ///
+    /// Arguments:
+    /// - `function`: System call function (FnMut closure), returning `Result<(Result<R, NonZeroI32>, u32), E>`
+    ///   (syscall result and cost).
+    pub fn syscall_with_cost<F, E, R>(&mut self, function: &mut F) -> Result<(), E>
+    where
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<(Result<R, NonZeroI32>, u32), E>,
+        R: Into<SyscallRet>,
+    {
+        // Get syscall arguments
+        let (nr, args, memory) = self.syscall_arguments();
+
+        // Call the syscall function
+        let (result, cost) = function(nr, args, memory)?;
+
+        // Set the syscall result
+        self.syscall_result(result);
+        self.consume_instructions(cost);
+
+        Ok(())
+    }
+
     /// Handle a system call asynchronously.
     ///
     /// System calls are triggered by the `ecall` instruction.
@@ -250,7 +1321,7 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     /// - `a7`: Syscall number.
     /// - `a0` to `a6`: Arguments.
     /// - `a0`: Return error code.
-    /// - `a1`: Return value.
+    /// - `a1`: Return value (`a1` low and `a2` high for 64-bit values, check [`SyscallRet`]).
     ///
     /// Arguments:
     /// - `function`: System call function (AsyncFnMut closure):
@@ -260,13 +1331,15 @@ impl<'a, M: Memory> Interpreter<'a, M> {
     ///         - `Memory`: System Memory (code + RAM).
     ///
     ///     - Returns:
-    ///         - `Result<Result<i32, NonZeroI32>, E>`:
+    ///         - `Result<Result<R, NonZeroI32>, E>`:
     ///             - Outer `Result`: Ok(()) if the syscall was successful, Err(E) if an internal error occurred. Errors are returned to the calling code.
-    ///             - Inner `Result`: Mapped to the value (`a1`) and error (`a0`) returned to the interpreted code.
+    ///             - Inner `Result`: Mapped to the value (`a1`/`a2`) and error (`a0`) returned to the interpreted code.
+    ///               The value can be any type convertible to [`SyscallRet`] (e.g. `i32`, `i64`).
     #[cfg(feature = "async")]
-    pub async fn syscall_async<F, E>(&mut self, function: &mut F) -> Result<(), E>
+    pub async fn syscall_async<F, E, R>(&mut self, function: &mut F) -> Result<(), E>
     where
-        F: AsyncFnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<i32, NonZeroI32>, E>,
+        F: AsyncFnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<R, NonZeroI32>, E>,
+        R: Into<SyscallRet>,
     {
         // Get syscall arguments
         let (nr, args, memory) = self.syscall_arguments();
@@ -279,6 +1352,33 @@ impl<'a, M: Memory> Interpreter<'a, M> {
 
         Ok(())
     }
+
+    /// Handle a system call asynchronously, consuming instruction budget (check [`Interpreter::consume_instructions`]).
+    ///
+    /// Same as [`Interpreter::syscall_async`], but the syscall function also returns a synthetic cost
+    /// (number of instructions), so guests can't bypass metering by doing heavy work through host calls.
+    ///
+    /// Arguments:
+    /// - `function`: System call function (AsyncFnMut closure), returning `Result<(Result<R, NonZeroI32>, u32), E>`
+    ///   (syscall result and cost).
+    #[cfg(feature = "async")]
+    pub async fn syscall_async_with_cost<F, E, R>(&mut self, function: &mut F) -> Result<(), E>
+    where
+        F: AsyncFnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<(Result<R, NonZeroI32>, u32), E>,
+        R: Into<SyscallRet>,
+    {
+        // Get syscall arguments
+        let (nr, args, memory) = self.syscall_arguments();
+
+        // Call the syscall function
+        let (result, cost) = function(nr, args, memory).await?;
+
+        // Set the syscall result
+        self.syscall_result(result);
+        self.consume_instructions(cost);
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -342,21 +1442,149 @@ mod tests {
         interpreter.syscall(&mut syscall).unwrap();
 
         // Check the result (Ok(0))
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0).unwrap(), 0);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_syscall_i64() {
+        // Code: ecall (already transpiled)
+        let code = [0x1f, 0x00, 0x00, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.inner[CPURegister::A0 as usize] = 5;
+
+        interpreter
+            .syscall(&mut |_, _, _| Ok::<_, Error>(Ok(SyscallRet::I64(-(1 << 40)))))
+            .unwrap();
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(0));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(0));
         assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A0 as u8)
-                .unwrap(),
-            0
+            interpreter.registers.cpu.get(CPURegister::A2),
+            Ok(-(1 << 8))
         );
+
+        // 64-bit values can also be returned directly
+        interpreter
+            .syscall(&mut |_, _, _| Ok::<_, Error>(Ok(u64::MAX)))
+            .unwrap();
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(-1));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A2), Ok(-1));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_syscall_with_cost() {
+        let mut code = [
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_instructions_per_tick(1);
+        let mut interpreter = Interpreter::with_config(&mut memory, 2, config);
+
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter
+            .syscall_with_cost(&mut |_, _, _| Ok::<_, Error>((Ok(1), 3)))
+            .unwrap();
+        assert_eq!(interpreter.instruction_debt(), 3);
+        assert_eq!(interpreter.time(), Some(4));
+
+        // Whole budget consumed by the syscall
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.program_counter, 4);
+        assert_eq!(interpreter.instruction_debt(), 1);
+
+        // Only one instruction left
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.program_counter, 8);
+        assert_eq!(interpreter.instruction_debt(), 0);
+
         assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A1 as u8)
-                .unwrap(),
-            0
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+    }
+
+    #[test]
+    fn test_pending_syscall() {
+        // Code: ecall, ecall (already transpiled)
+        let code = [0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.inner[CPURegister::A0 as usize] = 10;
+        interpreter.registers.cpu.inner[CPURegister::A7 as usize] = 3;
+
+        assert_eq!(interpreter.pending_syscall(), None);
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        assert_eq!(
+            interpreter.pending_syscall(),
+            Some((3, [10, 0, 0, 0, 0, 0, 0]))
+        );
+
+        let error = NonZeroI32::new(-2).unwrap();
+        assert_eq!(interpreter.reject_syscall(error), Ok(()));
+        assert_eq!(interpreter.pending_syscall(), None);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(-2));
+        assert_eq!(
+            interpreter.reject_syscall(error),
+            Err(Error::NoPendingSyscall)
+        );
+
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        assert_eq!(interpreter.complete_syscall(Ok(7)), Ok(()));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(0));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(7));
+        assert_eq!(interpreter.pending_syscall(), None);
+    }
+
+    #[test]
+    fn test_deferred_syscall() {
+        // Code: ecall, ecall, ebreak (already transpiled)
+        let code = [
+            0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x10, 0x00,
+        ];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.inner[CPURegister::A0 as usize] = 20;
+        assert_eq!(interpreter.defer_syscall(), Err(Error::NoPendingSyscall));
+
+        // Serviced by another thread
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let result = interpreter.syscall_deferrable(&mut |_, args, _| {
+            let (sender, value) = (sender.clone(), args[0]);
+            std::thread::spawn(move || sender.send(value + 1).unwrap());
+            Ok::<_, Error>(Poll::<Result<i32, NonZeroI32>>::Pending)
+        });
+        assert_eq!(result, Ok(Poll::Pending));
+        assert!(interpreter.syscall_deferred());
+
+        // Nothing runs until completion
+        assert_eq!(interpreter.run(), Ok(State::SyscallPending));
+        assert_eq!(interpreter.program_counter, 4);
+        let value = receiver.recv().unwrap();
+        assert_eq!(interpreter.complete_syscall(Ok(value)), Ok(()));
+        assert!(!interpreter.syscall_deferred());
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(21));
+
+        // Handled right away
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        let result = interpreter
+            .syscall_deferrable(&mut |_, _, _| Ok::<_, Error>(Poll::Ready(Ok::<_, NonZeroI32>(5))));
+        assert_eq!(result, Ok(Poll::Ready(())));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(5));
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
         );
     }
 
@@ -377,27 +1605,13 @@ mod tests {
         let mut interpreter = Interpreter::new(&mut memory, 0);
         let state = interpreter.run().unwrap();
 
-        // Host Called (syscall)
-        assert_eq!(state, State::Called);
-        interpreter.syscall(&mut syscall).unwrap();
-
-        // Check the result (Err(1))
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A0 as u8)
-                .unwrap(),
-            1
-        );
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A1 as u8)
-                .unwrap(),
-            0
-        );
+        // Host Called (syscall)
+        assert_eq!(state, State::Called);
+        interpreter.syscall(&mut syscall).unwrap();
+
+        // Check the result (Err(1))
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0).unwrap(), 1);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1).unwrap(), 0);
     }
 
     #[cfg(feature = "transpiler")]
@@ -429,22 +1643,8 @@ mod tests {
         interpreter.syscall(&mut syscall).unwrap();
 
         // Check the result (Ok(-1))
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A0 as u8)
-                .unwrap(),
-            0
-        );
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A1 as u8)
-                .unwrap(),
-            -1
-        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0).unwrap(), 0);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1).unwrap(), -1);
     }
 
     #[cfg(feature = "transpiler")]
@@ -477,22 +1677,108 @@ mod tests {
         interpreter.syscall(&mut syscall).unwrap();
 
         // Check the result (Err(-1))
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0).unwrap(), -1);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1).unwrap(), 0);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_panic() {
+        let mut code = [
+            0x93, 0x08, 0xf0, 0xff, // li   a7, -1     (Panic syscall)
+            0x37, 0x05, 0x00, 0x80, // lui  a0, 0x80000 (Message address)
+            0x93, 0x05, 0x60, 0x00, // li   a1, 6      (Message length)
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut ram = *b"oops!\xFF";
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // Guest panicked
+        let state = interpreter.run().unwrap();
         assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A0 as u8)
-                .unwrap(),
-            -1
+            state,
+            State::Panicked {
+                msg_ptr: memory::RAM_OFFSET,
+                len: 6
+            }
         );
+        assert_eq!(interpreter.program_counter, 4 * 4);
+
+        // Decode message (invalid UTF-8 is truncated)
         assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::A1 as u8)
-                .unwrap(),
-            0
+            interpreter.panic_message(memory::RAM_OFFSET, 6),
+            Ok("oops!")
         );
+        assert!(interpreter.panic_message(memory::RAM_OFFSET, 7).is_err());
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_compressed_disabled() {
+        let mut code = [
+            0x05, 0x45, // c.li a0, 1
+            0x02, 0x90, // c.ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_c_extension(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        let result = interpreter.run();
+        assert_eq!(result, Err(Error::IllegalInstruction(0)));
+        assert_eq!(interpreter.program_counter, 0);
+    }
+
+    #[test]
+    fn test_config_misa() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let config = Config::default()
+            .with_m_extension(false)
+            .with_c_extension(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        assert_eq!(interpreter.config(), &config);
+
+        let misa = interpreter
+            .registers
+            .control_status
+            .operation(None, 0x301) // MISA
+            .unwrap();
+        assert_eq!(misa, 0x4000_0101); // RV32IA
+
+        // Reset keeps the configured extensions
+        interpreter.reset();
+        let misa = interpreter
+            .registers
+            .control_status
+            .operation(None, 0x301) // MISA
+            .unwrap();
+        assert_eq!(misa, 0x4000_0101); // RV32IA
+    }
+
+    #[test]
+    fn test_config_machine_ids() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let config = Config::default().with_machine_ids(1, 2, 3, 4);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        let ids = |interpreter: &mut Interpreter<'_, SliceMemory<'_>>| {
+            [0xF11, 0xF12, 0xF13, 0xF14] // MVENDORID, MARCHID, MIMPID, MHARTID
+                .map(|addr| interpreter.registers.control_status.operation(None, addr))
+        };
+        assert_eq!(ids(&mut interpreter), [Ok(1), Ok(2), Ok(3), Ok(4)]);
+
+        // Reset keeps the configured machine IDs
+        interpreter.reset();
+        assert_eq!(ids(&mut interpreter), [Ok(1), Ok(2), Ok(3), Ok(4)]);
+
+        // Reconfiguring updates them
+        interpreter.set_config(Config::default().with_machine_ids(5, 6, 7, 8));
+        assert_eq!(ids(&mut interpreter), [Ok(5), Ok(6), Ok(7), Ok(8)]);
     }
 
     #[test]
@@ -525,10 +1811,112 @@ mod tests {
 
         // Run the interpreter again
         let result = interpreter.run();
-        assert_eq!(result, Ok(State::Halted));
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
         assert_eq!(interpreter.program_counter, 4 * 4);
     }
 
+    #[test]
+    fn test_reconfigure() {
+        // Code: c.nop, c.nop, c.nop, ebreak (already transpiled)
+        let code = [0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        interpreter.set_instruction_limit(1);
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.program_counter, 2);
+
+        // Execution state is kept
+        interpreter.set_config(Config::default().with_instructions_per_tick(1));
+        assert_eq!(interpreter.program_counter, 2);
+        assert_eq!(interpreter.time(), Some(1));
+
+        interpreter.set_instruction_limit(0);
+        interpreter.set_config(Config::default().with_c_extension(false));
+        assert_eq!(interpreter.time(), None);
+        assert_eq!(interpreter.run(), Err(Error::IllegalInstruction(2)));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_memory_fault() {
+        let mut code = [
+            0x37, 0x05, 0x00, 0x80, // lui a0, 0x80000
+            0x83, 0x25, 0x05, 0x00, // lw  a1, 0(a0)
+            0x23, 0x00, 0xb5, 0x00, // sb  a1, 0(a0)
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let result = interpreter.run();
+        assert_eq!(
+            result,
+            Err(Error::MemoryFault {
+                pc: 4,
+                access: MemoryAccess::Load,
+                address: 0x80000000,
+                len: 4,
+            })
+        );
+
+        // Skip the load
+        interpreter.program_counter = 8;
+        let result = interpreter.run();
+        assert_eq!(
+            result,
+            Err(Error::MemoryFault {
+                pc: 8,
+                access: MemoryAccess::Store,
+                address: 0x80000000,
+                len: 1,
+            })
+        );
+
+        // Fetch out of bounds
+        interpreter.program_counter = 12;
+        assert_eq!(interpreter.run(), Err(Error::InvalidProgramCounter(12)));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_virtual_time() {
+        let mut code = [
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x73, 0x25, 0x10, 0xc0, // rdtime  a0
+            0xf3, 0x25, 0x10, 0xc8, // rdtimeh a1
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_instructions_per_tick(2);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        let result = interpreter.run();
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(1));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(0));
+        assert_eq!(interpreter.time(), Some(3));
+
+        // Virtual time is reset
+        interpreter.reset();
+        assert_eq!(interpreter.time(), Some(0));
+    }
+
     #[cfg(feature = "transpiler")]
     #[test]
     fn test_instruction_limit_zero() {
@@ -545,7 +1933,12 @@ mod tests {
 
         // Run the interpreter
         let result = interpreter.run();
-        assert_eq!(result, Ok(State::Halted));
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
         assert_eq!(interpreter.program_counter, 4 * 4);
     }
 
@@ -574,19 +1967,14 @@ mod tests {
         // Run the interpreter
         let result = interpreter.run();
         assert_eq!(result, Ok(State::Waiting));
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::SP as u8)
-                .unwrap(),
-            55
-        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::SP).unwrap(), 55);
 
-        // interrupt
+        // interrupt (traps invalidate the memory reservation)
+        interpreter.memory_reservation = Some((RAM_OFFSET, 0));
         let result = interpreter.interrupt(1024);
         assert_eq!(result, Ok(()));
         assert_eq!(interpreter.program_counter, 40);
+        assert_eq!(interpreter.memory_reservation, None);
         assert!(
             interpreter
                 .registers
@@ -607,23 +1995,356 @@ mod tests {
 
         // Run the interpreter again
         let result = interpreter.run();
-        assert_eq!(result, Ok(State::Halted));
         assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::SP as u8)
-                .unwrap(),
-            22
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::SP).unwrap(), 22);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::GP).unwrap(), 55);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_software_interrupt() {
+        let mut code = [
+            0x93, 0x00, 0x80, 0x00, // li    ra, 8
+            0x73, 0x90, 0x00, 0x30, // csrw  mstatus, ra
+            0x93, 0x00, 0x80, 0x00, // li    ra, 8
+            0x73, 0x90, 0x40, 0x30, // csrw  mie, ra
+            0x93, 0x00, 0x40, 0x02, // li    ra, 36
+            0x73, 0x90, 0x50, 0x30, // csrw  mtvec, ra
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x93, 0x01, 0x70, 0x03, // li    gp, 55
+            0x73, 0x00, 0x10, 0x00, // ebreak
+            0x73, 0x21, 0x30, 0x34, // csrr  sp, mtval
+            0x73, 0x70, 0x44, 0x34, // csrci mip, 8
+            0x73, 0x00, 0x20, 0x30, // mret
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter.complete_syscall(Ok(0)).unwrap();
+        interpreter.raise_software_interrupt(7);
+
+        // Interrupt is taken right after the syscall returns
+        assert_eq!(interpreter.step(), Ok(State::Running));
+        assert_eq!(interpreter.program_counter, 40);
+        assert_eq!(interpreter.registers.cpu.sp(), 7);
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
         );
+        assert_eq!(interpreter.registers.cpu.gp(), 55);
         assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::GP as u8)
-                .unwrap(),
-            55
+            interpreter.registers.control_status.operation(None, 0x342), // MCAUSE
+            Ok(0x8000_0003)
+        );
+    }
+
+    #[test]
+    fn test_code_patching() {
+        // ebreak (already transpiled)
+        let mut ram = [0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.program_counter = memory::RAM_OFFSET;
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Host patches the code (wfi), no invalidation needed
+        interpreter.program_counter = memory::RAM_OFFSET;
+        interpreter
+            .memory
+            .store_u32(memory::RAM_OFFSET, 0x0030001f)
+            .unwrap();
+        assert_eq!(interpreter.run(), Ok(State::Waiting));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_memory_access() {
+        let mut code = [
+            0x37, 0x05, 0x00, 0x80, // lui  a0, 0x80000
+            0x85, 0x05, // addi a1, a1, 1
+            0x0c, 0xc1, // sw   a1, 0(a0)
+            0xf5, 0xbf, // j    -4
+            0x00, 0x00, // padding
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 4];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 3);
+
+        // Guest RAM is accessible between runs
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.memory().ram(), &[1, 0, 0, 0]);
+        interpreter.memory_mut().ram_mut()[1] = 0xFF;
+        assert_eq!(interpreter.memory().ram(), &[1, 0xFF, 0, 0]);
+
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.memory().ram(), &[2, 0, 0, 0]);
+
+        let memory = interpreter.into_memory();
+        assert_eq!(memory.ram(), &[2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_ram_execution() {
+        // ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x10, 0x00];
+        let mut ram = code;
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let config = Config::default().with_ram_execution(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        // Code region is always executable
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        interpreter.program_counter = memory::RAM_OFFSET;
+        assert_eq!(
+            interpreter.run(),
+            Err(Error::ExecuteFault(memory::RAM_OFFSET))
+        );
+
+        interpreter.set_config(Config::default());
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+    }
+
+    #[test]
+    fn test_code_window() {
+        // Code: ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x10, 0x00];
+        let ebreak = Instruction::from(0x0010_001f);
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::builder(&mut memory)
+            .code_window(&code)
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.fetch(), Ok(ebreak));
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Fetched from the window, not the memory
+        let window = [0x13, 0x00, 0x00, 0x00];
+        interpreter.set_code_window(Some(&window));
+        interpreter.program_counter = 0;
+        assert_eq!(interpreter.fetch(), Ok(Instruction::from(0x13)));
+
+        // Falls back to the memory outside of the window
+        interpreter.set_code_window(Some(&window[..2]));
+        assert_eq!(interpreter.fetch(), Ok(ebreak));
+        interpreter.program_counter = 4;
+        assert_eq!(interpreter.fetch(), Err(Error::InvalidProgramCounter(4)));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_max_instructions() {
+        let mut code = [
+            0x6f, 0x00, 0x00, 0x00, // j .
+        ];
+        transpile_raw(&mut code).unwrap();
+        let halted = Ok(State::Halted {
+            reason: ExitReason::InstructionLimit,
+        });
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_max_instructions(10);
+        let mut interpreter = Interpreter::with_config(&mut memory, 4, config);
+
+        // Preempted until the budget is exhausted
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.remaining_instructions(), Some(2));
+        assert_eq!(interpreter.run(), halted);
+        assert_eq!(interpreter.run(), halted);
+        let control_status = &interpreter.registers.control_status;
+        assert_eq!(control_status.instructions_retired(), 10);
+
+        // Without an instruction limit, instructions consumed by the host count
+        interpreter.reset();
+        interpreter.set_instruction_limit(0);
+        interpreter.consume_instructions(3);
+        assert_eq!(interpreter.run(), halted);
+        let control_status = &interpreter.registers.control_status;
+        assert_eq!(control_status.instructions_retired(), 10);
+
+        interpreter.set_config(Config::default());
+        assert_eq!(interpreter.remaining_instructions(), None);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_ebreak_mode() {
+        let mut code = [
+            0x73, 0x00, 0x10, 0x00, // ebreak
+            0x02, 0x90, // c.ebreak
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_ebreak(EbreakMode::Break);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        // Breakpoints can be resumed
+        assert_eq!(interpreter.run(), Ok(State::Breakpoint));
+        assert_eq!(interpreter.program_counter, 4);
+        assert_eq!(interpreter.run(), Ok(State::Breakpoint));
+        assert_eq!(interpreter.program_counter, 6);
+
+        interpreter.set_config(Config::default());
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_custom_csr_handler() {
+        let mut code = [
+            0x73, 0x25, 0x00, 0x7c, // csrr a0, 0x7C0
+            0x73, 0x90, 0x05, 0x7c, // csrw 0x7C0, a1
+            0x73, 0x90, 0x05, 0xfc, // csrw 0xFC0, a1 (read-only)
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut value = 42;
+        let mut handler = |addr: u16, op: Option<CSOperation>| match addr {
+            0x7C0 | 0xFC0 => {
+                let ret = value;
+                if let Some(CSOperation::Write(new)) = op {
+                    value = new;
+                }
+                Ok(ret)
+            }
+            _ => Err(Error::InvalidCSRegister(addr)),
+        };
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        assert_eq!(
+            interpreter.csr_operation(None, 0x7C0),
+            Err(Error::InvalidCSRegister(0x7C0))
+        );
+        interpreter.set_custom_csr_handler(Some(&mut handler));
+
+        // Writes to read-only CSRs don't reach the handler
+        interpreter.registers.cpu.set_a1(7);
+        assert_eq!(interpreter.run(), Err(Error::IllegalInstruction(8)));
+        assert_eq!(interpreter.registers.cpu.a0(), 42);
+
+        // Reset keeps the handler (and its state)
+        interpreter.reset();
+        interpreter.registers.cpu.set_a1(9);
+        assert_eq!(interpreter.run(), Err(Error::IllegalInstruction(8)));
+        assert_eq!(interpreter.registers.cpu.a0(), 7);
+        assert_eq!(interpreter.csr_operation(None, 0xFC0), Ok(9));
+
+        interpreter.set_custom_csr_handler(None);
+        assert_eq!(
+            interpreter.csr_operation(None, 0x7C0),
+            Err(Error::InvalidCSRegister(0x7C0))
+        );
+        assert_eq!(value, 9);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_sleep() {
+        let mut code = [
+            0x93, 0x08, 0xe0, 0xff, // li    a7, -2
+            0x13, 0x05, 0x40, 0x06, // li    a0, 100
+            0x93, 0x05, 0x10, 0x00, // li    a1, 1
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x73, 0x00, 0x50, 0x10, // wfi
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(interpreter.wait_timeout(), None);
+        assert_eq!(interpreter.run(), Ok(State::Waiting));
+        assert_eq!(interpreter.wait_timeout(), Some((1 << 32) | 100));
+        assert_eq!(interpreter.registers.cpu.a0(), 0);
+        assert_eq!(interpreter.program_counter, 16);
+
+        // Timeout expired, plain wfi has no timeout
+        assert_eq!(interpreter.run(), Ok(State::Waiting));
+        assert_eq!(interpreter.wait_timeout(), None);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_tls() {
+        let elf = include_bytes!("../tests/tls.elf");
+        let mut code = [0; 64];
+        let mut ram = [0xFF; 64];
+
+        let size = crate::transpiler::transpile_elf(elf, &mut code).unwrap();
+        let tls = crate::transpiler::tls_segment(elf).unwrap().unwrap();
+
+        let mut memory = SliceMemory::new(&code[..size], &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // Allocate the TLS block at the start of RAM
+        let base = memory::RAM_OFFSET + 16;
+        interpreter
+            .init_tls(base, tls.template_address, tls.template_size, tls.size)
+            .unwrap();
+        assert_eq!(interpreter.tls_base(), Some(base));
+
+        let result = interpreter.run();
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
         );
+
+        // Thread-local counter (41 + 1) and zero-initialized thread-local
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1).unwrap(), 42);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A3).unwrap(), 0);
+
+        // Reset clears the TLS base
+        interpreter.reset();
+        assert_eq!(interpreter.tls_base(), None);
+
+        // Memory around the TLS block is untouched
+        assert_eq!(ram[15], 0xFF);
+        assert_eq!(ram[16..20], 42i32.to_le_bytes());
+        assert_eq!(ram[28], 0xFF);
     }
 
     #[test]
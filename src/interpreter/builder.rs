@@ -0,0 +1,332 @@
+//! Builder Module
+//!
+//! Validated construction of an [`Interpreter`], so misconfiguration is reported before running any code.
+use core::fmt::{self, Display, Formatter};
+
+use super::{
+    memory::Memory, registers::CPURegister, Config, Error, Interpreter, SyscallPermission,
+};
+use crate::protocol::{abi_compatible, ABI_VERSION};
+
+/// Stack pointer alignment, in bytes (RISC-V calling convention).
+const STACK_ALIGNMENT: u32 = 16;
+
+/// Interpreter Build Error
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// Program counter is outside the code region (no instruction can be fetched). The program counter is provided.
+    ProgramCounterOutOfBounds(u32),
+    /// Program counter is not aligned to the instruction size (2 bytes, or 4 bytes without the C extension).
+    /// The program counter is provided.
+    MisalignedProgramCounter(u32),
+    /// Stack pointer is outside the RAM region (no word below it is writable). The stack pointer is provided.
+    StackPointerOutOfBounds(u32),
+    /// Stack pointer is not 16-byte aligned. The stack pointer is provided.
+    MisalignedStackPointer(u32),
+    /// Failed to initialize the thread-local storage block (check [`Interpreter::init_tls`]).
+    Tls(Error),
+    /// Image ABI version not supported by the host (check [`InterpreterBuilder::abi_version`]).
+    /// The image ABI version is provided (`None` if unversioned).
+    IncompatibleAbi(Option<u32>),
+}
+
+impl core::error::Error for BuildError {}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::ProgramCounterOutOfBounds(pc) => {
+                write!(f, "program counter {pc:#010x} is outside the code region")
+            }
+            BuildError::MisalignedProgramCounter(pc) => {
+                write!(f, "program counter {pc:#010x} is misaligned")
+            }
+            BuildError::StackPointerOutOfBounds(sp) => {
+                write!(f, "stack pointer {sp:#010x} is outside the RAM region")
+            }
+            BuildError::MisalignedStackPointer(sp) => {
+                write!(
+                    f,
+                    "stack pointer {sp:#010x} is not {STACK_ALIGNMENT}-byte aligned"
+                )
+            }
+            BuildError::Tls(error) => write!(f, "failed to initialize TLS: {error}"),
+            BuildError::IncompatibleAbi(Some(version)) => {
+                write!(
+                    f,
+                    "image ABI version {version} is not supported (host: {ABI_VERSION})"
+                )
+            }
+            BuildError::IncompatibleAbi(None) => write!(f, "image has no ABI version"),
+        }
+    }
+}
+
+/// Embive Interpreter Builder
+///
+/// Created by [`Interpreter::builder`]. Validates the configuration on [`InterpreterBuilder::build`]:
+/// - The program counter must be aligned and point to fetchable code.
+/// - The stack pointer (if set) must be 16-byte aligned, with the word below it inside RAM.
+/// - The thread-local storage block (if set) must be initialized successfully.
+/// - The image ABI version (if set) must be supported by the host.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::{SliceMemory, RAM_OFFSET}, BuildError, Interpreter};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut ram = [0; 64];
+/// let mut memory = SliceMemory::new(&code, &mut ram);
+///
+/// // Stack pointer after the end of RAM
+/// let result = Interpreter::builder(&mut memory)
+///     .stack_pointer(RAM_OFFSET + 128)
+///     .build();
+/// assert_eq!(result.err(), Some(BuildError::StackPointerOutOfBounds(RAM_OFFSET + 128)));
+///
+/// let interpreter = Interpreter::builder(&mut memory)
+///     .instruction_limit(100)
+///     .stack_pointer(RAM_OFFSET + 64)
+///     .build()
+///     .unwrap();
+/// assert_eq!(interpreter.instruction_limit, 100);
+/// ```
+#[derive(Debug)]
+pub struct InterpreterBuilder<'a, M: Memory> {
+    memory: &'a mut M,
+    program_counter: u32,
+    stack_pointer: Option<u32>,
+    instruction_limit: u32,
+    config: Config,
+    tls: Option<(u32, u32, u32, u32)>,
+    abi_version: Option<Option<u32>>,
+    syscall_permissions: Option<&'a mut [SyscallPermission]>,
+    code_window: Option<&'a [u8]>,
+}
+
+impl<'a, M: Memory> InterpreterBuilder<'a, M> {
+    /// Create a new builder (check [`Interpreter::builder`]).
+    pub(crate) fn new(memory: &'a mut M) -> Self {
+        InterpreterBuilder {
+            memory,
+            program_counter: 0,
+            stack_pointer: None,
+            instruction_limit: 0,
+            config: Config::default(),
+            tls: None,
+            abi_version: None,
+            syscall_permissions: None,
+            code_window: None,
+        }
+    }
+
+    /// Set the entry point (initial program counter). Default: `0`.
+    pub fn program_counter(mut self, program_counter: u32) -> Self {
+        self.program_counter = program_counter;
+        self
+    }
+
+    /// Set the initial stack pointer (`sp`). Default: not set (0).
+    pub fn stack_pointer(mut self, stack_pointer: u32) -> Self {
+        self.stack_pointer = Some(stack_pointer);
+        self
+    }
+
+    /// Set the instruction limit (0 means no limit). Default: `0`.
+    pub fn instruction_limit(mut self, instruction_limit: u32) -> Self {
+        self.instruction_limit = instruction_limit;
+        self
+    }
+
+    /// Set the interpreter configuration (check [`Config`]). Default: [`Config::default`].
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Initialize a thread-local storage block (check [`Interpreter::init_tls`]). Default: not set.
+    pub fn tls(mut self, base: u32, template_address: u32, template_size: u32, size: u32) -> Self {
+        self.tls = Some((base, template_address, template_size, size));
+        self
+    }
+
+    /// Check the ABI version of the image before running it. Default: not checked.
+    ///
+    /// Old images calling removed (or renumbered) syscalls are rejected with [`BuildError::IncompatibleAbi`],
+    /// as well as unversioned images (transpiled without the ABI version trailer).
+    ///
+    /// Arguments:
+    /// - `version`: Image ABI version (check [`crate::protocol::image_abi_version`]).
+    pub fn abi_version(mut self, version: Option<u32>) -> Self {
+        self.abi_version = Some(version);
+        self
+    }
+
+    /// Restrict the host syscalls to an allowlist, with optional quotas (check [`Interpreter::set_syscall_permissions`]).
+    /// Default: all syscalls allowed.
+    pub fn syscall_permissions(mut self, permissions: &'a mut [SyscallPermission]) -> Self {
+        self.syscall_permissions = Some(permissions);
+        self
+    }
+
+    /// Fetch instructions straight from the code, a fast path (check [`Interpreter::set_code_window`]).
+    /// Default: not set.
+    pub fn code_window(mut self, code: &'a [u8]) -> Self {
+        self.code_window = Some(code);
+        self
+    }
+
+    /// Validate the configuration and build the interpreter.
+    ///
+    /// Returns:
+    /// - `Ok(Interpreter)`: Success, interpreter ready to run.
+    /// - `Err(BuildError)`: Invalid configuration (check [`BuildError`]).
+    pub fn build(self) -> Result<Interpreter<'a, M>, BuildError> {
+        if let Some(version) = self.abi_version {
+            if !version.is_some_and(abi_compatible) {
+                return Err(BuildError::IncompatibleAbi(version));
+            }
+        }
+
+        let pc = self.program_counter;
+        let alignment = if self.config.c_extension { 2 } else { 4 };
+        if pc % alignment != 0 {
+            return Err(BuildError::MisalignedProgramCounter(pc));
+        }
+
+        let mut interpreter =
+            Interpreter::with_config(self.memory, self.instruction_limit, self.config);
+        interpreter.program_counter = pc;
+        interpreter.set_syscall_permissions(self.syscall_permissions);
+        interpreter.set_code_window(self.code_window);
+
+        if interpreter.fetch().is_err() {
+            return Err(BuildError::ProgramCounterOutOfBounds(pc));
+        }
+
+        if let Some(sp) = self.stack_pointer {
+            if sp % STACK_ALIGNMENT != 0 {
+                return Err(BuildError::MisalignedStackPointer(sp));
+            }
+
+            // The stack grows downwards, the stack pointer may be the end of RAM
+            if interpreter.memory.mut_bytes(sp.wrapping_sub(4), 4).is_err() {
+                return Err(BuildError::StackPointerOutOfBounds(sp));
+            }
+
+            interpreter.registers.cpu.inner[CPURegister::SP as usize] = sp as i32;
+        }
+
+        if let Some((base, template_address, template_size, size)) = self.tls {
+            interpreter
+                .init_tls(base, template_address, template_size, size)
+                .map_err(BuildError::Tls)?;
+        }
+
+        Ok(interpreter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::{SliceMemory, RAM_OFFSET};
+
+    // Code: ebreak, ebreak (already transpiled)
+    const CODE: [u8; 8] = [0x1f, 0x00, 0x10, 0x00, 0x1f, 0x00, 0x10, 0x00];
+
+    #[test]
+    fn test_build() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+
+        let interpreter = Interpreter::builder(&mut memory)
+            .program_counter(4)
+            .stack_pointer(RAM_OFFSET + 32)
+            .instruction_limit(10)
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.program_counter, 4);
+        assert_eq!(interpreter.instruction_limit, 10);
+        assert_eq!(
+            interpreter.registers.cpu.get(CPURegister::SP),
+            Ok((RAM_OFFSET + 32) as i32)
+        );
+    }
+
+    #[test]
+    fn test_abi_version() {
+        let mut memory = SliceMemory::new(&CODE, &mut []);
+        let result = Interpreter::builder(&mut memory)
+            .abi_version(Some(ABI_VERSION))
+            .build();
+        assert!(result.is_ok());
+
+        let result = Interpreter::builder(&mut memory)
+            .abi_version(Some(ABI_VERSION + 1))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::IncompatibleAbi(Some(ABI_VERSION + 1)))
+        );
+
+        let result = Interpreter::builder(&mut memory).abi_version(None).build();
+        assert_eq!(result.err(), Some(BuildError::IncompatibleAbi(None)));
+    }
+
+    #[test]
+    fn test_program_counter() {
+        let mut memory = SliceMemory::new(&CODE, &mut []);
+        let result = Interpreter::builder(&mut memory).program_counter(8).build();
+        assert_eq!(result.err(), Some(BuildError::ProgramCounterOutOfBounds(8)));
+
+        let result = Interpreter::builder(&mut memory).program_counter(1).build();
+        assert_eq!(result.err(), Some(BuildError::MisalignedProgramCounter(1)));
+
+        let result = Interpreter::builder(&mut memory)
+            .program_counter(2)
+            .config(Config::default().with_c_extension(false))
+            .build();
+        assert_eq!(result.err(), Some(BuildError::MisalignedProgramCounter(2)));
+    }
+
+    #[test]
+    fn test_stack_pointer() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+
+        let result = Interpreter::builder(&mut memory)
+            .stack_pointer(RAM_OFFSET + 8)
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::MisalignedStackPointer(RAM_OFFSET + 8))
+        );
+
+        let result = Interpreter::builder(&mut memory)
+            .stack_pointer(RAM_OFFSET)
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::StackPointerOutOfBounds(RAM_OFFSET))
+        );
+    }
+
+    #[test]
+    fn test_tls() {
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+
+        let result = Interpreter::builder(&mut memory)
+            .tls(RAM_OFFSET + 8, 0, 4, 16)
+            .build();
+        assert!(matches!(result.err(), Some(BuildError::Tls(_))));
+
+        let interpreter = Interpreter::builder(&mut memory)
+            .tls(RAM_OFFSET + 8, 0, 4, 8)
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.tls_base(), Some(RAM_OFFSET + 8));
+    }
+}
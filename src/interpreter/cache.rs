@@ -0,0 +1,420 @@
+//! Cache Module
+//!
+//! Deterministic data cache model, to study guest algorithms under memory latency pressure (worst-case testing).
+//! A [`CacheMemory`] wraps the interpreter memory, modeling a set-associative cache in front of a memory region:
+//! accesses missing the cache stall for extra cycles, charged to the instruction in virtual time with the `timing`
+//! feature (check the `timing` module and [`Memory::take_stall_cycles`]). Without it, stalls are only counted in
+//! the statistics.
+//!
+//! Lines are replaced pseudo-randomly, from a seed (check [`CacheConfig::with_seed`]), so runs are reproducible.
+use super::{
+    memory::{Memory, RAM_OFFSET},
+    random::splitmix64,
+    Error,
+};
+
+/// Embive Cache Configuration
+///
+/// Example:
+/// ```
+/// use embive::interpreter::cache::CacheConfig;
+///
+/// // 2-way set associative, 16-byte lines, 20 cycles per miss
+/// let config = CacheConfig::new()
+///     .with_ways(2)
+///     .with_line_size(16)
+///     .with_miss_penalty(20);
+/// assert_eq!(config.line_size, 16);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheConfig {
+    /// Start address of the cached region. Default: [`RAM_OFFSET`].
+    pub base: u32,
+    /// Size of the cached region, in bytes (accesses outside of it never stall). Default: the whole RAM region.
+    pub size: u32,
+    /// Line size, in bytes (rounded up to a power of two). Default: `32`.
+    pub line_size: u32,
+    /// Number of ways (lines per set, `1` is direct-mapped). Clamped to the number of lines. Default: `1`.
+    pub ways: usize,
+    /// Extra cost of a miss, in cycles. Default: `10`.
+    pub miss_penalty: u32,
+    /// Seed of the line replacement. Default: `0`.
+    pub seed: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheConfig {
+    /// Create a new cache configuration (direct-mapped RAM cache, 32-byte lines, 10 cycles per miss).
+    pub const fn new() -> Self {
+        CacheConfig {
+            base: RAM_OFFSET,
+            size: 0u32.wrapping_sub(RAM_OFFSET),
+            line_size: 32,
+            ways: 1,
+            miss_penalty: 10,
+            seed: 0,
+        }
+    }
+
+    /// Set the cached region.
+    ///
+    /// Arguments:
+    /// - `base`: Start address of the region.
+    /// - `size`: Size of the region, in bytes.
+    pub const fn with_region(mut self, base: u32, size: u32) -> Self {
+        self.base = base;
+        self.size = size;
+        self
+    }
+
+    /// Set the line size, in bytes (rounded up to a power of two).
+    pub const fn with_line_size(mut self, line_size: u32) -> Self {
+        self.line_size = line_size;
+        self
+    }
+
+    /// Set the number of ways (lines per set).
+    pub const fn with_ways(mut self, ways: usize) -> Self {
+        self.ways = ways;
+        self
+    }
+
+    /// Set the extra cost of a miss, in cycles.
+    pub const fn with_miss_penalty(mut self, miss_penalty: u32) -> Self {
+        self.miss_penalty = miss_penalty;
+        self
+    }
+
+    /// Set the seed of the line replacement.
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Cache statistics.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of accesses hitting the cache.
+    pub hits: u64,
+    /// Number of accesses missing the cache.
+    pub misses: u64,
+    /// Stall cycles of the misses.
+    pub stall_cycles: u64,
+}
+
+/// Cache Memory
+///
+/// Wraps a [`Memory`], modeling a cache of `N` lines (check [`CacheConfig`]) in front of a memory region.
+/// Misses allocate a line (loads and stores alike), replacing a free line of the set or a pseudo-random one.
+///
+/// Only typed accesses (`load_u8` to `store_u32`, as done by the guest instructions) are modeled, by their start
+/// address. Instruction fetches also go through `load_u32`, so code executed from the cached region
+/// (check [`super::Config::ram_execution`]) goes through the cache. Host bulk accesses (e.g. syscall buffers)
+/// are not modeled.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{
+///     cache::{CacheConfig, CacheMemory},
+///     memory::SliceMemory,
+///     Interpreter,
+/// };
+///
+/// // Code: li a0, 42; lui a1, 0x80000; sw a0, 0(a1); sw a0, 4(a1); ebreak (already transpiled)
+/// let code = [
+///     0x1d, 0x28, 0xa0, 0x02, 0x9c, 0x05, 0x00, 0x80, 0x9b, 0xab, 0x05, 0x00, 0x9b, 0xab, 0x45, 0x00,
+///     0x1f, 0x00, 0x10, 0x00,
+/// ];
+/// let mut ram = [0; 8];
+/// let config = CacheConfig::new().with_miss_penalty(20);
+/// let mut memory = CacheMemory::<_, 4>::new(SliceMemory::new(&code, &mut ram), config);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+/// interpreter.run().unwrap();
+///
+/// // First store misses, second one hits (same line), stalls are charged in virtual time with the `timing` feature
+/// #[cfg(feature = "timing")]
+/// assert_eq!(interpreter.registers.control_status.cycles(), 5 + 20);
+/// assert_eq!((memory.stats().hits, memory.stats().misses), (1, 1));
+/// assert_eq!(memory.stats().stall_cycles, 20);
+/// ```
+#[derive(Debug)]
+pub struct CacheMemory<M: Memory, const N: usize> {
+    memory: M,
+    config: CacheConfig,
+    /// Cached line numbers (address / line size), `ways` consecutive lines per set.
+    lines: [Option<u32>; N],
+    /// Line size, as a shift.
+    line_shift: u32,
+    /// Ways per set (`1..=N`, or `0` without lines).
+    ways: usize,
+    /// Replacement generator state.
+    rng_state: u64,
+    /// Stall cycles not taken by the interpreter yet.
+    pending: u32,
+    stats: CacheStats,
+}
+
+impl<M: Memory, const N: usize> CacheMemory<M, N> {
+    /// Create a new cache memory, with a cold (empty) cache.
+    ///
+    /// Arguments:
+    /// - `memory`: The wrapped memory.
+    /// - `config`: The cache configuration.
+    pub fn new(memory: M, config: CacheConfig) -> Self {
+        let line_size = config.line_size.max(1).checked_next_power_of_two();
+        CacheMemory {
+            memory,
+            config,
+            lines: [None; N],
+            line_shift: line_size.map_or(31, u32::trailing_zeros),
+            ways: config.ways.max(1).min(N),
+            rng_state: config.seed,
+            pending: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Get the wrapped memory.
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Get the wrapped memory (mutable).
+    pub fn memory_mut(&mut self) -> &mut M {
+        &mut self.memory
+    }
+
+    /// Get the cache configuration.
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// Get the cache statistics.
+    pub fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    /// Clear the cache statistics.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Invalidate all lines (cold cache) and restart the replacement from the seed, for reproducible runs.
+    pub fn flush(&mut self) {
+        self.lines = [None; N];
+        self.rng_state = self.config.seed;
+        self.pending = 0;
+    }
+
+    /// Consume the cache memory, returning the wrapped memory.
+    pub fn into_inner(self) -> M {
+        self.memory
+    }
+
+    /// Model an access.
+    ///
+    /// Arguments:
+    /// - `address`: Start address of the access.
+    #[inline]
+    fn access(&mut self, address: u32) {
+        if address.wrapping_sub(self.config.base) >= self.config.size || self.ways == 0 {
+            return;
+        }
+
+        let line = address >> self.line_shift;
+        let sets = N / self.ways;
+        let start = (line as usize % sets) * self.ways;
+        let set = &mut self.lines[start..start + self.ways];
+        if set.contains(&Some(line)) {
+            self.stats.hits += 1;
+            return;
+        }
+
+        // Miss: allocate a free line, or replace a pseudo-random one
+        let victim = match set.iter().position(Option::is_none) {
+            Some(index) => index,
+            None => (splitmix64(&mut self.rng_state) % self.ways as u64) as usize,
+        };
+        set[victim] = Some(line);
+
+        self.stats.misses += 1;
+        self.stats.stall_cycles += self.config.miss_penalty as u64;
+        self.pending = self.pending.saturating_add(self.config.miss_penalty);
+    }
+}
+
+impl<M: Memory, const N: usize> Memory for CacheMemory<M, N> {
+    #[inline]
+    fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+        self.memory.load_bytes(address, len)
+    }
+
+    #[inline]
+    fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+        self.memory.mut_bytes(address, len)
+    }
+
+    #[inline]
+    fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.memory.store_bytes(address, data)
+    }
+
+    #[inline]
+    fn load_u8(&mut self, address: u32) -> Result<u8, Error> {
+        self.access(address);
+        self.memory.load_u8(address)
+    }
+
+    #[inline]
+    fn load_u16(&mut self, address: u32) -> Result<u16, Error> {
+        self.access(address);
+        self.memory.load_u16(address)
+    }
+
+    #[inline]
+    fn load_u32(&mut self, address: u32) -> Result<u32, Error> {
+        self.access(address);
+        self.memory.load_u32(address)
+    }
+
+    #[inline]
+    fn store_u8(&mut self, address: u32, value: u8) -> Result<(), Error> {
+        self.access(address);
+        self.memory.store_u8(address, value)
+    }
+
+    #[inline]
+    fn store_u16(&mut self, address: u32, value: u16) -> Result<(), Error> {
+        self.access(address);
+        self.memory.store_u16(address, value)
+    }
+
+    #[inline]
+    fn store_u32(&mut self, address: u32, value: u32) -> Result<(), Error> {
+        self.access(address);
+        self.memory.store_u32(address, value)
+    }
+
+    #[inline]
+    fn copy_from_guest(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        self.memory.copy_from_guest(address, buffer)
+    }
+
+    #[inline]
+    fn copy_to_guest(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.memory.copy_to_guest(address, data)
+    }
+
+    #[inline]
+    fn take_stall_cycles(&mut self) -> u32 {
+        let stall = core::mem::take(&mut self.pending);
+        stall.saturating_add(self.memory.take_stall_cycles())
+    }
+
+    #[inline]
+    fn sync_software_interrupt(&mut self, pending: bool) -> bool {
+        self.memory.sync_software_interrupt(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::SliceMemory;
+    #[cfg(feature = "timing")]
+    use crate::interpreter::{
+        timing::{CostTable, InstructionKind},
+        ExitReason, Interpreter, State,
+    };
+
+    /// Code: li a0, 42; lui a1, 0x80000; sw a0, 0(a1); sw a0, 4(a1); ebreak (already transpiled)
+    const CODE: [u8; 20] = [
+        0x1d, 0x28, 0xa0, 0x02, 0x9c, 0x05, 0x00, 0x80, 0x9b, 0xab, 0x05, 0x00, 0x9b, 0xab, 0x45,
+        0x00, 0x1f, 0x00, 0x10, 0x00,
+    ];
+
+    #[test]
+    fn test_cache() {
+        let mut ram = [0; 256];
+        let config = CacheConfig::new().with_line_size(16).with_ways(2);
+        let mut memory = CacheMemory::<_, 4>::new(SliceMemory::new(&CODE, &mut ram), config);
+
+        // Code region isn't cached
+        memory.load_u32(0).unwrap();
+        assert_eq!(memory.take_stall_cycles(), 0);
+
+        // 2 sets of 2 lines: lines 0, 2 and 4 share a set
+        memory.store_u32(RAM_OFFSET, 1).unwrap();
+        memory.load_u8(RAM_OFFSET + 15).unwrap();
+        memory.load_u8(RAM_OFFSET + 16).unwrap();
+        assert_eq!(memory.take_stall_cycles(), 20);
+        assert_eq!(memory.take_stall_cycles(), 0);
+        memory.load_u16(RAM_OFFSET + 32).unwrap();
+        memory.load_u16(RAM_OFFSET + 64).unwrap();
+        assert_eq!(
+            memory.stats(),
+            &CacheStats {
+                hits: 1,
+                misses: 4,
+                stall_cycles: 40
+            }
+        );
+
+        // Host bulk accesses aren't modeled
+        memory.copy_to_guest(RAM_OFFSET + 128, &[1, 2]).unwrap();
+        assert_eq!(memory.stats().misses, 4);
+
+        // Cold cache
+        memory.flush();
+        memory.reset_stats();
+        memory.load_u32(RAM_OFFSET).unwrap();
+        assert_eq!(memory.stats().misses, 1);
+    }
+
+    #[test]
+    fn test_replacement_seed() {
+        let mut misses = [0; 2];
+        for misses in misses.iter_mut() {
+            let config = CacheConfig::new().with_ways(4).with_seed(7);
+            let mut ram = [0; 1024];
+            let mut memory = CacheMemory::<_, 4>::new(SliceMemory::new(&[], &mut ram), config);
+            for address in (0..1024).step_by(32).cycle().take(256) {
+                memory.load_u32(RAM_OFFSET + address).unwrap();
+            }
+            *misses = memory.stats().misses;
+        }
+
+        // Reproducible
+        assert_eq!(misses[0], misses[1]);
+        assert!(misses[0] > 4);
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn test_interpreter() {
+        let mut ram = [0; 8];
+        let config = CacheConfig::new().with_line_size(4).with_miss_penalty(3);
+        let mut memory = CacheMemory::<_, 2>::new(SliceMemory::new(&CODE, &mut ram), config);
+        let mut costs = CostTable::new().with_cost(InstructionKind::Store, 2);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.set_timing_model(Some(&mut costs));
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Both stores miss (different lines), on top of the timing model cost
+        let control_status = &interpreter.registers.control_status;
+        assert_eq!(control_status.instructions_retired(), 5);
+        assert_eq!(control_status.cycles(), 3 + 2 * (2 + 3));
+    }
+}
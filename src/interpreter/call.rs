@@ -0,0 +1,248 @@
+//! Call Module
+//!
+//! Calling individual guest functions from the host (RISC-V calling convention).
+use core::num::NonZeroI32;
+
+use super::{
+    memory::Memory, registers::CPURegister, Error, Interpreter, State, SyscallRet, SYSCALL_ARGS,
+};
+
+/// Maximum number of arguments passed in registers (`a0` to `a7`).
+pub const CALL_ARGS: usize = 8;
+
+/// Return address set for called functions (misaligned, not a function address).
+/// Execution stops when the return lands on [`CALL_RETURN_PC`].
+pub const CALL_RETURN_ADDRESS: u32 = crate::protocol::CALL_RETURN_ADDRESS;
+
+/// Program counter after returning to [`CALL_RETURN_ADDRESS`] (reserved, check [`crate::protocol::CALL_RETURN_PC`]).
+pub const CALL_RETURN_PC: u32 = crate::protocol::CALL_RETURN_PC;
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Call a guest function, returning its result (`a0`).
+    ///
+    /// The interpreter state is saved before the call and restored afterwards (also on errors), so it can be used
+    /// between runs: program counter, CPU registers, memory reservation, pending syscall and the machine state
+    /// (privilege mode, `mstatus`, `mepc`, `mcause`, `mtval`, pending interrupts and the other CSRs, except the
+    /// instruction and cycle counters). Memory, queued interrupts (`interrupt-queue` feature) and other host-side
+    /// state changed by the function aren't restored.
+    ///
+    /// The function runs for at most [`Interpreter::instruction_limit`] instructions (0 means no limit, check
+    /// [`Interpreter::set_instruction_limit`]), so a function that never returns can't hang the host.
+    ///
+    /// The stack pointer (`sp`) must point to a valid stack (check [`super::InterpreterBuilder::stack_pointer`]),
+    /// and other global state (e.g. `gp`, `tp`) must be already set up (e.g. by running the guest initialization code).
+    ///
+    /// Arguments:
+    /// - `address`: Address of the function.
+    /// - `args`: Function arguments (`a0` to `a7`, up to [`CALL_ARGS`]).
+    ///
+    /// Returns:
+    /// - `Ok(i32)`: Success, function result (`a0`).
+    /// - `Err(Error)`: Too many arguments ([`Error::TooManyArguments`]), the function stopped
+    ///   before returning ([`Error::CallInterrupted`], e.g. syscall, or [`State::Running`] at the instruction
+    ///   limit) or failed to execute.
+    pub fn call(&mut self, address: u32, args: &[i32]) -> Result<i32, Error> {
+        self.call_with_syscall(address, args, &mut |_, _, _| {
+            Err::<Result<i32, NonZeroI32>, _>(Error::CallInterrupted(State::Called))
+        })
+    }
+
+    /// Call a guest function, handling syscalls, and return its result (`a0`).
+    ///
+    /// Same as [`Interpreter::call`], but syscalls done by the function are handled (check [`Interpreter::syscall`]).
+    ///
+    /// Arguments:
+    /// - `address`: Address of the function.
+    /// - `args`: Function arguments (`a0` to `a7`, up to [`CALL_ARGS`]).
+    /// - `function`: System call function (FnMut closure, check [`Interpreter::syscall`]).
+    ///
+    /// Returns:
+    /// - `Ok(i32)`: Success, function result (`a0`).
+    /// - `Err(E)`: Interpreter error (check [`Interpreter::call`]) or syscall function error.
+    pub fn call_with_syscall<F, E, R>(
+        &mut self,
+        address: u32,
+        args: &[i32],
+        function: &mut F,
+    ) -> Result<i32, E>
+    where
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<R, NonZeroI32>, E>,
+        R: Into<SyscallRet>,
+        E: From<Error>,
+    {
+        if args.len() > CALL_ARGS {
+            return Err(Error::TooManyArguments(args.len()).into());
+        }
+
+        // Save the interpreter state
+        let program_counter = self.program_counter;
+        let registers = self.registers.cpu;
+        let control_status = self.registers.control_status;
+        let memory_reservation = self.memory_reservation.take();
+        let syscall_pending = self.syscall_pending;
+        let syscall_deferred = core::mem::take(&mut self.syscall_deferred);
+
+        // Set up the call (arguments and return address)
+        let a0 = CPURegister::A0 as usize;
+        self.registers.cpu.inner[a0..a0 + args.len()].copy_from_slice(args);
+        self.registers.cpu.set_ra(CALL_RETURN_ADDRESS as i32);
+        self.program_counter = address;
+
+        let result = self.call_inner(function);
+
+        // Restore the interpreter state
+        self.program_counter = program_counter;
+        self.registers.cpu = registers;
+        self.registers
+            .control_status
+            .restore_call_state(&control_status);
+        self.sync_software_interrupt();
+        #[cfg(feature = "mmu")]
+        self.mmu.flush();
+        self.memory_reservation = memory_reservation;
+        self.syscall_pending = syscall_pending;
+        self.syscall_deferred = syscall_deferred;
+
+        result
+    }
+
+    /// Run until the called function returns, or the instruction limit is reached.
+    fn call_inner<F, E, R>(&mut self, function: &mut F) -> Result<i32, E>
+    where
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<R, NonZeroI32>, E>,
+        R: Into<SyscallRet>,
+        E: From<Error>,
+    {
+        let mut executed = 0;
+        while self.program_counter != CALL_RETURN_PC {
+            if self.instruction_limit > 0 && executed == self.instruction_limit {
+                return Err(Error::CallInterrupted(State::Running).into());
+            }
+            executed += 1;
+
+            match self.step()? {
+                State::Running => {}
+                State::Called => self.syscall(function)?,
+                state => return Err(Error::CallInterrupted(state).into()),
+            }
+        }
+
+        Ok(self.registers.cpu.a0())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::SliceMemory;
+
+    #[cfg(feature = "transpiler")]
+    use crate::{
+        interpreter::{memory::RAM_OFFSET, ExitReason},
+        transpiler::transpile_raw,
+    };
+
+    #[cfg(feature = "transpiler")]
+    fn code() -> [u8; 20] {
+        let mut code = [
+            0x73, 0x00, 0x10, 0x00, // ebreak           (main)
+            0x33, 0x05, 0xb5, 0x00, // add  a0, a0, a1 (add)
+            0x67, 0x80, 0x00, 0x00, // ret
+            0x73, 0x00, 0x00, 0x00, // ecall           (syscall)
+            0x67, 0x80, 0x00, 0x00, // ret
+        ];
+        transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_call() {
+        let code = code();
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 2);
+        interpreter.registers.cpu.set_sp((RAM_OFFSET + 16) as i32);
+        interpreter.registers.cpu.set_a0(1);
+
+        assert_eq!(interpreter.call(4, &[10, 20]), Ok(30));
+
+        // State is restored
+        assert_eq!(interpreter.program_counter, 0);
+        assert_eq!(interpreter.registers.cpu.a0(), 1);
+        assert_eq!(interpreter.registers.cpu.a1(), 0);
+        assert_eq!(interpreter.registers.cpu.ra(), 0);
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_call_syscall() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(
+            interpreter.call(12, &[]),
+            Err(Error::CallInterrupted(State::Called))
+        );
+        assert_eq!(interpreter.program_counter, 0);
+
+        let result = interpreter
+            .call_with_syscall(12, &[5], &mut |_, args, _| Ok::<_, Error>(Ok(args[0] * 2)));
+        // Error code (a0) is the function result
+        assert_eq!(result, Ok(0));
+        assert_eq!(interpreter.program_counter, 0);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_call_limit() {
+        let mut code = [
+            0x73, 0x10, 0x15, 0x34, // csrw mepc, a0   (set_mepc)
+            0x67, 0x80, 0x00, 0x00, // ret
+            0x6f, 0x00, 0x00, 0x00, // j    0          (spin)
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 100);
+
+        // Functions that never return are stopped at the instruction limit
+        assert_eq!(
+            interpreter.call(8, &[]),
+            Err(Error::CallInterrupted(State::Running))
+        );
+        assert_eq!(interpreter.program_counter, 0);
+        assert_eq!(
+            interpreter.registers.control_status.instructions_retired(),
+            100
+        );
+
+        // Machine state is restored
+        assert_eq!(interpreter.call(0, &[0x40]), Ok(0x40));
+        assert_eq!(
+            interpreter.registers.control_status.operation(None, 0x341),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test_call_args() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(
+            interpreter.call(0, &[0; CALL_ARGS + 1]),
+            Err(Error::TooManyArguments(CALL_ARGS + 1))
+        );
+        assert_eq!(
+            interpreter.call(0, &[0; CALL_ARGS]),
+            Err(Error::InvalidProgramCounter(0))
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Capabilities Module
+//!
+//! Host capabilities reported to the interpreted code (check [`super::CAPABILITIES_SYSCALL`]), so guests can
+//! check for syscalls, extensions and memory sizes at startup instead of failing on the first missing one.
+use super::{memory::Memory, Interpreter};
+use crate::protocol::{ABI_VERSION, CAPABILITIES_ERROR_INVALID_ADDRESS, CAPABILITIES_HEADER_SIZE};
+
+/// Host Capabilities
+///
+/// Reported to the interpreted code with the host ABI version and the enabled extensions
+/// (check [`Interpreter::set_host_capabilities`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HostCapabilities {
+    /// Code size, in bytes (`0` if unknown).
+    pub code_size: u32,
+    /// RAM size, in bytes (`0` if unknown).
+    pub ram_size: u32,
+    /// Host syscall numbers (reserved syscalls aren't listed).
+    pub syscalls: &'static [i32],
+}
+
+impl HostCapabilities {
+    /// Get the size of the capabilities reported to the interpreted code, in bytes.
+    pub fn size(&self) -> usize {
+        CAPABILITIES_HEADER_SIZE + self.syscalls.len() * 4
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Set the host capabilities reported to the interpreted code (check [`super::CAPABILITIES_SYSCALL`]).
+    ///
+    /// Arguments:
+    /// - `capabilities`: Host capabilities. Default: unknown sizes and no host syscalls.
+    pub fn set_host_capabilities(&mut self, capabilities: HostCapabilities) {
+        self.host_capabilities = capabilities;
+    }
+
+    /// Handle [`super::CAPABILITIES_SYSCALL`]: fill the buffer (`a0`, length `a1`), returning the error (`a0`)
+    /// and the capabilities size (`a1`).
+    pub(crate) fn capabilities_syscall(&mut self) {
+        let address = self.registers.cpu.a0() as u32;
+        let len = self.registers.cpu.a1() as u32 as usize;
+        let capabilities = self.host_capabilities;
+        let header = [
+            ABI_VERSION,
+            self.registers.control_status.misa(),
+            capabilities.code_size,
+            capabilities.ram_size,
+            capabilities.syscalls.len() as u32,
+        ];
+
+        let error = match self.memory.mut_bytes(address, len) {
+            Ok(buffer) => {
+                let words = header
+                    .into_iter()
+                    .chain(capabilities.syscalls.iter().map(|&nr| nr as u32));
+                for (chunk, word) in buffer.chunks_mut(4).zip(words) {
+                    chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+                }
+                0
+            }
+            Err(_) => CAPABILITIES_ERROR_INVALID_ADDRESS,
+        };
+
+        self.registers.cpu.set_a0(error);
+        self.registers.cpu.set_a1(if error == 0 {
+            capabilities.size() as i32
+        } else {
+            0
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        registers::MISA_M,
+        Config, ExitReason, State, CAPABILITIES_SYSCALL,
+    };
+
+    /// Code: ecall, ebreak (already transpiled)
+    const CODE: [u8; 8] = [0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x10, 0x00];
+
+    fn capabilities(
+        interpreter: &mut Interpreter<'_, SliceMemory<'_>>,
+        address: u32,
+        len: i32,
+    ) -> (i32, i32) {
+        interpreter.reset();
+        interpreter.registers.cpu.set_a7(CAPABILITIES_SYSCALL);
+        interpreter.registers.cpu.set_a0(address as i32);
+        interpreter.registers.cpu.set_a1(len);
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        (
+            interpreter.registers.cpu.a0(),
+            interpreter.registers.cpu.a1(),
+        )
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let config = Config::default().with_m_extension(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        interpreter.set_host_capabilities(HostCapabilities {
+            code_size: CODE.len() as u32,
+            ram_size: 32,
+            syscalls: &[1, 2],
+        });
+
+        assert_eq!(capabilities(&mut interpreter, RAM_OFFSET, 32), (0, 28));
+        let words: [u32; 7] = core::array::from_fn(|i| {
+            u32::from_le_bytes(ram_word(&mut interpreter, RAM_OFFSET + i as u32 * 4))
+        });
+        assert_eq!(words[0], ABI_VERSION);
+        assert_eq!(words[1] & MISA_M, 0);
+        assert_eq!(words[2..], [8, 32, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_capabilities_partial() {
+        let mut ram = [0xFF; 8];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // Buffer smaller than the capabilities, the size is still reported
+        assert_eq!(
+            capabilities(&mut interpreter, RAM_OFFSET, 6),
+            (0, CAPABILITIES_HEADER_SIZE as i32)
+        );
+        assert_eq!(
+            ram_word(&mut interpreter, RAM_OFFSET),
+            ABI_VERSION.to_le_bytes()
+        );
+        assert_eq!(
+            ram_word(&mut interpreter, RAM_OFFSET + 4)[2..],
+            [0xFF, 0xFF]
+        );
+
+        assert_eq!(
+            capabilities(&mut interpreter, RAM_OFFSET + 4, 8),
+            (CAPABILITIES_ERROR_INVALID_ADDRESS, 0)
+        );
+    }
+
+    fn ram_word(interpreter: &mut Interpreter<'_, SliceMemory<'_>>, address: u32) -> [u8; 4] {
+        let mut word = [0; 4];
+        word.copy_from_slice(interpreter.memory.load_bytes(address, 4).unwrap());
+        word
+    }
+}
@@ -0,0 +1,408 @@
+//! Compression Module
+//!
+//! Compressed images (check [`IMAGE_FLAG_COMPRESSED`](super::loader::IMAGE_FLAG_COMPRESSED)), reducing
+//! over-the-air payloads. Images are compressed when packaged ([`compress`]) and decompressed as they are
+//! received ([`Decompressor`]), straight into their final buffer: matches are copied from the data already
+//! decompressed, so no window memory is needed.
+//!
+//! Format (byte-oriented LZ77):
+//! - Decompressed size (little-endian `u32`).
+//! - Tokens, until the decompressed size is reached:
+//!   - `0x00..=0x7F`: run of `token + 1` literal bytes, which follow.
+//!   - `0x80..=0xFF`: match of `(token & 0x7F) + MIN_MATCH` bytes, copied from a distance (little-endian `u16`,
+//!     at least 1) back, which follows.
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::compression::{compress, max_compressed_size, Decompressor};
+//!
+//! let image = [0x01, 0x00].repeat(64); // c.nop (already transpiled)
+//! let mut compressed = vec![0; max_compressed_size(image.len())];
+//! let size = compress(&image, &mut compressed).unwrap();
+//! assert!(size < image.len());
+//!
+//! // Decompress, in chunks (e.g. as received)
+//! let mut code = [0; 128];
+//! let mut decompressor = Decompressor::new();
+//! for chunk in compressed[..size].chunks(3) {
+//!     decompressor.write(chunk, &mut code).unwrap();
+//! }
+//! assert_eq!(decompressor.finish(), Ok(image.len()));
+//! assert_eq!(code[..], image[..]);
+//! ```
+use core::fmt::{self, Display, Formatter};
+
+/// Minimum match length, in bytes.
+pub const MIN_MATCH: usize = 3;
+
+/// Maximum match length, in bytes.
+pub const MAX_MATCH: usize = 0x7F + MIN_MATCH;
+
+/// Maximum match distance, in bytes.
+pub const MAX_DISTANCE: usize = u16::MAX as usize;
+
+/// Maximum literal run length, in bytes.
+const MAX_LITERALS: usize = 0x80;
+
+/// Compressor hash table size (log2).
+const HASH_BITS: u32 = 12;
+
+/// Compression Error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressionError {
+    /// Output buffer too small. The needed size, in bytes, is provided.
+    BufferTooSmall(usize),
+    /// Invalid compressed data (bad distance or data past the decompressed size).
+    Corrupted,
+    /// Compressed data ended before the decompressed size was reached.
+    Truncated,
+}
+
+impl core::error::Error for CompressionError {}
+
+impl Display for CompressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Get the maximum compressed size of some data (worst case, incompressible data).
+///
+/// Arguments:
+/// - `size`: Data size, in bytes.
+///
+/// Returns the compressed buffer size needed, in bytes.
+pub const fn max_compressed_size(size: usize) -> usize {
+    4 + size + size.div_ceil(MAX_LITERALS)
+}
+
+/// Compressed data writer.
+struct Writer<'a> {
+    output: &'a mut [u8],
+    len: usize,
+    needed: usize,
+}
+
+impl Writer<'_> {
+    /// Append bytes to the output.
+    fn push(&mut self, bytes: &[u8]) -> Result<(), CompressionError> {
+        let end = self.len + bytes.len();
+        self.output
+            .get_mut(self.len..end)
+            .ok_or(CompressionError::BufferTooSmall(self.needed))?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Append literal runs.
+    fn literals(&mut self, data: &[u8]) -> Result<(), CompressionError> {
+        for run in data.chunks(MAX_LITERALS) {
+            self.push(&[(run.len() - 1) as u8])?;
+            self.push(run)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hash the next [`MIN_MATCH`] bytes.
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+    (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Compress data (e.g. a transpiled image, when packaging it).
+///
+/// Matches are searched greedily through a small hash table (kept on the stack), favoring speed
+/// and simplicity; decompression doesn't depend on how matches were found.
+///
+/// Arguments:
+/// - `input`: Data to compress (up to 4 GiB).
+/// - `output`: Compressed data buffer (check [`max_compressed_size`]).
+///
+/// Returns:
+/// - `Ok(usize)`: Success, compressed size (in bytes).
+/// - `Err(CompressionError)`: Output buffer too small ([`CompressionError::BufferTooSmall`]).
+pub fn compress(input: &[u8], output: &mut [u8]) -> Result<usize, CompressionError> {
+    let mut writer = Writer {
+        output,
+        len: 0,
+        needed: max_compressed_size(input.len()),
+    };
+    writer.push(&(input.len() as u32).to_le_bytes())?;
+
+    // Last position (plus one) of each hash, 0 if none
+    let mut table = [0u32; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut position = 0;
+    while position + MIN_MATCH <= input.len() {
+        let key = hash(&input[position..]);
+        let candidate = table[key] as usize;
+        table[key] = position as u32 + 1;
+
+        if candidate != 0 && position - (candidate - 1) <= MAX_DISTANCE {
+            let candidate = candidate - 1;
+            let length = input[candidate..]
+                .iter()
+                .zip(&input[position..])
+                .take(MAX_MATCH)
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if length >= MIN_MATCH {
+                writer.literals(&input[literal_start..position])?;
+                writer.push(&[0x80 | (length - MIN_MATCH) as u8])?;
+                writer.push(&((position - candidate) as u16).to_le_bytes())?;
+
+                // Index the matched positions
+                for index in position + 1..(position + length).min(input.len() + 1 - MIN_MATCH) {
+                    table[hash(&input[index..])] = index as u32 + 1;
+                }
+                position += length;
+                literal_start = position;
+                continue;
+            }
+        }
+        position += 1;
+    }
+    writer.literals(&input[literal_start..])?;
+
+    Ok(writer.len)
+}
+
+/// Decompressor stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    /// Receiving the decompressed size (bytes received).
+    Size(usize),
+    /// Waiting for a token.
+    Token,
+    /// Copying literals (bytes left).
+    Literals(usize),
+    /// Receiving a match distance (match length, low byte if received).
+    Distance(usize, Option<u8>),
+}
+
+/// Streaming Decompressor
+///
+/// Decompresses data in parts (of any size, e.g. as received) straight into the destination buffer,
+/// without any other buffering (a few bytes of state).
+#[derive(Debug, Clone)]
+pub struct Decompressor {
+    stage: Stage,
+    size_bytes: [u8; 4],
+    size: usize,
+    position: usize,
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decompressor {
+    /// Create a new decompressor.
+    pub const fn new() -> Self {
+        Decompressor {
+            stage: Stage::Size(0),
+            size_bytes: [0; 4],
+            size: 0,
+            position: 0,
+        }
+    }
+
+    /// Get the decompressed size, once received.
+    pub fn size(&self) -> Option<usize> {
+        match self.stage {
+            Stage::Size(_) => None,
+            _ => Some(self.size),
+        }
+    }
+
+    /// Get the number of bytes decompressed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Decompress the next part of the compressed data.
+    ///
+    /// The decompressor should be dropped after an error.
+    ///
+    /// Arguments:
+    /// - `input`: Compressed data.
+    /// - `output`: Destination buffer (the same for every part).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, data decompressed.
+    /// - `Err(CompressionError)`: Destination buffer too small or corrupted data.
+    pub fn write(&mut self, mut input: &[u8], output: &mut [u8]) -> Result<(), CompressionError> {
+        while let Some((&byte, rest)) = input.split_first() {
+            match self.stage {
+                Stage::Size(received) => {
+                    self.size_bytes[received] = byte;
+                    input = rest;
+                    if received + 1 < self.size_bytes.len() {
+                        self.stage = Stage::Size(received + 1);
+                        continue;
+                    }
+
+                    self.size = u32::from_le_bytes(self.size_bytes) as usize;
+                    if self.size > output.len() {
+                        return Err(CompressionError::BufferTooSmall(self.size));
+                    }
+                    self.stage = Stage::Token;
+                }
+                Stage::Token => {
+                    if self.position == self.size {
+                        return Err(CompressionError::Corrupted);
+                    }
+                    input = rest;
+                    self.stage = match byte {
+                        0x00..=0x7F => Stage::Literals(byte as usize + 1),
+                        _ => Stage::Distance((byte & 0x7F) as usize + MIN_MATCH, None),
+                    };
+                }
+                Stage::Literals(left) => {
+                    let count = left.min(input.len());
+                    let end = self.position + count;
+                    if end > self.size {
+                        return Err(CompressionError::Corrupted);
+                    }
+                    output
+                        .get_mut(self.position..end)
+                        .ok_or(CompressionError::BufferTooSmall(self.size))?
+                        .copy_from_slice(&input[..count]);
+                    input = &input[count..];
+                    self.position = end;
+                    self.stage = match left - count {
+                        0 => Stage::Token,
+                        left => Stage::Literals(left),
+                    };
+                }
+                Stage::Distance(length, None) => {
+                    input = rest;
+                    self.stage = Stage::Distance(length, Some(byte));
+                }
+                Stage::Distance(length, Some(low)) => {
+                    input = rest;
+                    let distance = u16::from_le_bytes([low, byte]) as usize;
+                    let end = self.position + length;
+                    if distance == 0 || distance > self.position || end > self.size {
+                        return Err(CompressionError::Corrupted);
+                    }
+                    if end > output.len() {
+                        return Err(CompressionError::BufferTooSmall(self.size));
+                    }
+
+                    // Byte by byte, as matches may overlap themselves (repeating patterns)
+                    for index in self.position..end {
+                        output[index] = output[index - distance];
+                    }
+                    self.position = end;
+                    self.stage = Stage::Token;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish decompressing, checking that all the data was received.
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, decompressed size (in bytes), at the start of the destination buffer.
+    /// - `Err(CompressionError)`: Compressed data truncated ([`CompressionError::Truncated`]).
+    pub fn finish(&self) -> Result<usize, CompressionError> {
+        match self.stage {
+            Stage::Token if self.position == self.size => Ok(self.size),
+            _ => Err(CompressionError::Truncated),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compress and decompress data in chunks, returning the compressed size.
+    fn roundtrip(data: &[u8], chunk_size: usize) -> usize {
+        let mut compressed = [0; 1024];
+        let size = compress(data, &mut compressed).unwrap();
+        assert!(size <= max_compressed_size(data.len()));
+
+        let mut output = [0; 512];
+        let mut decompressor = Decompressor::new();
+        for chunk in compressed[..size].chunks(chunk_size) {
+            decompressor.write(chunk, &mut output).unwrap();
+        }
+        assert_eq!(decompressor.finish(), Ok(data.len()));
+        assert_eq!(output[..data.len()], *data);
+        size
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        assert_eq!(roundtrip(&[], 1), 4);
+        assert_eq!(roundtrip(&[1, 2], 1), 7);
+
+        // Repeating pattern (overlapping match)
+        let pattern = [0x13, 0x05, 0x10, 0x00].repeat(100);
+        for chunk_size in 1..8 {
+            assert!(roundtrip(&pattern, chunk_size) < 32);
+        }
+
+        // Incompressible data (worst case)
+        let mut noise = [0u8; 300];
+        let mut seed = 1u32;
+        for byte in noise.iter_mut() {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *byte = (seed >> 16) as u8;
+        }
+        assert!(roundtrip(&noise, 7) <= max_compressed_size(noise.len()));
+    }
+
+    #[test]
+    fn test_compress_buffer_too_small() {
+        let mut output = [0; 6];
+        assert_eq!(
+            compress(&[1, 2, 3], &mut output),
+            Err(CompressionError::BufferTooSmall(8))
+        );
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        let mut output = [0; 8];
+
+        // Decompressed size bigger than the buffer
+        assert_eq!(
+            Decompressor::new().write(&[9, 0, 0, 0], &mut output),
+            Err(CompressionError::BufferTooSmall(9))
+        );
+
+        // Match distance past the start
+        assert_eq!(
+            Decompressor::new().write(&[4, 0, 0, 0, 0x00, 0xAA, 0x80, 2, 0], &mut output),
+            Err(CompressionError::Corrupted)
+        );
+
+        // Data past the decompressed size
+        assert_eq!(
+            Decompressor::new().write(&[1, 0, 0, 0, 0x01, 0xAA, 0xBB], &mut output),
+            Err(CompressionError::Corrupted)
+        );
+
+        // Truncated
+        let mut decompressor = Decompressor::new();
+        decompressor
+            .write(&[4, 0, 0, 0, 0x00, 0xAA, 0x80, 1], &mut output)
+            .unwrap();
+        assert_eq!(decompressor.size(), Some(4));
+        assert_eq!(decompressor.position(), 1);
+        assert_eq!(decompressor.finish(), Err(CompressionError::Truncated));
+        decompressor.write(&[0], &mut output).unwrap();
+        assert_eq!(decompressor.finish(), Ok(4));
+        assert_eq!(output[..4], [0xAA; 4]);
+    }
+}
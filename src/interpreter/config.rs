@@ -0,0 +1,372 @@
+//! Interpreter Configuration Module
+
+#[cfg(feature = "resource-limits")]
+use super::ResourceLimits;
+use super::{
+    registers::{MIMPID_DEFAULT, MISA_A, MISA_C, MISA_M, MISA_U},
+    InstructionPolicy,
+};
+
+/// `ebreak` instruction behavior (check [`Config::with_ebreak`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum EbreakMode {
+    /// Halt the execution ([`super::State::Halted`]).
+    #[default]
+    Halt,
+    /// Stop at a breakpoint ([`super::State::Breakpoint`]), execution can be resumed.
+    /// Allows distinguishing `ebreak` instructions planted as breakpoints from an intentional exit (e.g. exit syscall).
+    Break,
+    /// Take a breakpoint exception (`mcause` [`super::privilege::BREAKPOINT`], `mtval` is the `ebreak` address),
+    /// handled by the interpreted code (e.g. a debug monitor).
+    Trap,
+}
+
+/// Machine information CSRs (check [`Config::with_machine_ids`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MachineIds {
+    /// Vendor ID (`mvendorid`). Default: `0` (non-commercial implementation).
+    pub vendor: u32,
+    /// Architecture ID (`marchid`). Default: `0` (not assigned).
+    pub architecture: u32,
+    /// Implementation ID (`mimpid`). Default: crate version (`major << 16 | minor << 8 | patch`).
+    pub implementation: u32,
+    /// Hardware thread ID (`mhartid`). Default: `0`.
+    pub hart: u32,
+}
+
+impl Default for MachineIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MachineIds {
+    /// Create the default machine IDs.
+    pub const fn new() -> Self {
+        MachineIds {
+            vendor: 0,
+            architecture: 0,
+            implementation: MIMPID_DEFAULT,
+            hart: 0,
+        }
+    }
+}
+
+/// Embive Interpreter Configuration
+///
+/// Allows disabling RISC-V extensions at runtime (independent of compile-time features).
+/// Instructions from a disabled extension result in [`super::Error::IllegalInstruction`],
+/// and the `misa` CSR reflects the enabled extensions.
+///
+/// Also configures the deterministic virtual time source (`time` CSR), advanced by instruction count,
+/// whether code can be executed from RAM, the `ebreak` behavior, the deterministic entropy seed, the
+/// instruction budget and the denied instruction classes.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::Config;
+///
+/// // RV32IC profile (no multiply/divide, no atomics)
+/// let config = Config::default()
+///     .with_m_extension(false)
+///     .with_a_extension(false);
+/// assert!(config.c_extension);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct Config {
+    /// Execute M extension instructions (multiply/divide). Default: `true`.
+    pub m_extension: bool,
+    /// Execute A extension instructions (atomics). Default: `true`.
+    pub a_extension: bool,
+    /// Execute C extension instructions (compressed). Default: `true`.
+    pub c_extension: bool,
+    /// Virtual time ratio, in instructions per tick (check [`super::Interpreter::time`]). With a timing model,
+    /// in cycles per tick (`timing` feature). Default: `0` (virtual time disabled).
+    pub instructions_per_tick: u32,
+    /// Allow fetching instructions from the RAM region ([`super::memory::RAM_OFFSET`]). Default: `true`.
+    ///
+    /// When disabled, only the code region is executable (W^X), and fetching from RAM results in
+    /// [`super::Error::ExecuteFault`].
+    pub ram_execution: bool,
+    /// `ebreak` instruction behavior (check [`EbreakMode`]). Default: [`EbreakMode::Halt`].
+    pub ebreak: EbreakMode,
+    /// Deterministic seed of the interpreted code entropy source (check [`super::RANDOM_SYSCALL`]).
+    /// Default: `None` (random bytes come from the RNG provider).
+    ///
+    /// When set, random bytes are generated by a non-cryptographic generator, reproducible for replays and tests.
+    pub rng_seed: Option<u64>,
+    /// Size of the reservation set of `lr.w`/`sc.w`, in bytes (rounded up to a power of two, at least `4`).
+    /// Default: `4` (the reserved word).
+    ///
+    /// Any store to the reservation set (e.g. to another word of a 16-byte granule) invalidates the reservation,
+    /// so a following `sc.w` fails.
+    pub reservation_granule: u32,
+    /// Syscall flood detection: maximum number of consecutive syscalls (`ecall`) issued less than
+    /// [`Config::syscall_min_interval`] instructions apart. Default: `0` (disabled).
+    ///
+    /// When exceeded, running fails with [`super::Error::SyscallFlood`], so hosts can throttle abusive guests.
+    #[cfg(feature = "syscall-flood")]
+    pub syscall_burst_limit: u32,
+    /// Syscall flood detection: minimum number of instructions between two syscalls for them not to count as
+    /// a burst (check [`Config::syscall_burst_limit`]). Default: `0`.
+    #[cfg(feature = "syscall-flood")]
+    pub syscall_min_interval: u32,
+    /// Implement user mode (U-mode) next to machine mode, reported by `misa`. Default: `false`.
+    ///
+    /// Machine-mode code (e.g. a kernel) enters user mode with `mret` (`mstatus.MPP` = 0). In user mode,
+    /// `ecall`, privileged instructions and CSRs, and errors of the interpreted code trap to machine mode
+    /// instead of stopping the interpreter (check [`super::Privilege`]).
+    pub user_mode: bool,
+    /// Deliver errors of machine-mode code (invalid and illegal instructions, access faults) as exception traps
+    /// to `mtvec`, like in user mode. Default: `false` (errors stop the interpreter).
+    ///
+    /// For guests that handle their own faults, e.g. firmware emulating unsupported instructions or recovering
+    /// from bad accesses in its trap handler, as on a bare-metal core without user mode.
+    /// Errors of the trap handler entry (the program counter is `mtvec`) are still returned, instead of trapping
+    /// forever.
+    pub machine_traps: bool,
+    /// Instruction budget: halt after this many instructions retired since the last reset, including the ones
+    /// consumed by the host (check [`super::Interpreter::consume_instructions`]). Default: `0` (no budget).
+    ///
+    /// When exhausted, running returns [`super::State::Halted`] with [`super::ExitReason::InstructionLimit`], unlike the
+    /// per-run instruction limit (preemption, [`super::State::Running`]).
+    pub max_instructions: u64,
+    /// Instruction classes denied at runtime (check [`InstructionPolicy`]). Default: all allowed.
+    ///
+    /// Executing a denied instruction fails with [`super::Error::InstructionDenied`], e.g. to restrict plugin tiers.
+    pub instruction_policy: InstructionPolicy,
+    /// Hard limits on the lifetime resource usage (check [`ResourceLimits`]). Default: no limits.
+    ///
+    /// When reached, running returns [`super::State::Halted`] with [`super::ExitReason::ResourceLimit`].
+    #[cfg(feature = "resource-limits")]
+    pub resource_limits: ResourceLimits,
+    /// Machine information CSRs, read-only to the interpreted code (check [`MachineIds`]).
+    pub machine_ids: MachineIds,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Config {
+    /// Create a new configuration with all extensions enabled.
+    pub const fn new() -> Self {
+        Config {
+            m_extension: true,
+            a_extension: true,
+            c_extension: true,
+            instructions_per_tick: 0,
+            ram_execution: true,
+            ebreak: EbreakMode::Halt,
+            rng_seed: None,
+            reservation_granule: 4,
+            #[cfg(feature = "syscall-flood")]
+            syscall_burst_limit: 0,
+            #[cfg(feature = "syscall-flood")]
+            syscall_min_interval: 0,
+            user_mode: false,
+            machine_traps: false,
+            max_instructions: 0,
+            instruction_policy: InstructionPolicy::new(),
+            #[cfg(feature = "resource-limits")]
+            resource_limits: ResourceLimits::new(),
+            machine_ids: MachineIds::new(),
+        }
+    }
+
+    /// Enable or disable the M extension (multiply/divide).
+    pub const fn with_m_extension(mut self, enabled: bool) -> Self {
+        self.m_extension = enabled;
+        self
+    }
+
+    /// Enable or disable the A extension (atomics).
+    pub const fn with_a_extension(mut self, enabled: bool) -> Self {
+        self.a_extension = enabled;
+        self
+    }
+
+    /// Enable or disable the C extension (compressed).
+    pub const fn with_c_extension(mut self, enabled: bool) -> Self {
+        self.c_extension = enabled;
+        self
+    }
+
+    /// Set the virtual time ratio, in instructions per tick (0 disables virtual time).
+    pub const fn with_instructions_per_tick(mut self, instructions_per_tick: u32) -> Self {
+        self.instructions_per_tick = instructions_per_tick;
+        self
+    }
+
+    /// Allow or deny fetching instructions from the RAM region.
+    pub const fn with_ram_execution(mut self, enabled: bool) -> Self {
+        self.ram_execution = enabled;
+        self
+    }
+
+    /// Set the `ebreak` instruction behavior (check [`EbreakMode`]).
+    pub const fn with_ebreak(mut self, mode: EbreakMode) -> Self {
+        self.ebreak = mode;
+        self
+    }
+
+    /// Set the deterministic seed of the interpreted code entropy source (`None` uses the RNG provider).
+    pub const fn with_rng_seed(mut self, seed: Option<u64>) -> Self {
+        self.rng_seed = seed;
+        self
+    }
+
+    /// Set the size of the `lr.w`/`sc.w` reservation set, in bytes (rounded up to a power of two, at least `4`).
+    pub const fn with_reservation_granule(mut self, granule: u32) -> Self {
+        self.reservation_granule = granule;
+        self
+    }
+
+    /// Set the syscall flood detection limits (a `burst_limit` of `0` disables it).
+    ///
+    /// Arguments:
+    /// - `burst_limit`: Maximum number of consecutive syscalls issued less than `min_interval` instructions apart.
+    /// - `min_interval`: Minimum number of instructions between two syscalls for them not to count as a burst.
+    #[cfg(feature = "syscall-flood")]
+    pub const fn with_syscall_flood_limit(mut self, burst_limit: u32, min_interval: u32) -> Self {
+        self.syscall_burst_limit = burst_limit;
+        self.syscall_min_interval = min_interval;
+        self
+    }
+
+    /// Enable or disable user mode (check [`Config::user_mode`]).
+    pub const fn with_user_mode(mut self, enabled: bool) -> Self {
+        self.user_mode = enabled;
+        self
+    }
+
+    /// Enable or disable machine-mode exception traps (check [`Config::machine_traps`]).
+    pub const fn with_machine_traps(mut self, enabled: bool) -> Self {
+        self.machine_traps = enabled;
+        self
+    }
+
+    /// Set the machine information CSRs (check [`Config::machine_ids`]).
+    ///
+    /// Arguments:
+    /// - `vendor`: Vendor ID (`mvendorid`).
+    /// - `architecture`: Architecture ID (`marchid`).
+    /// - `implementation`: Implementation ID (`mimpid`).
+    /// - `hart`: Hardware thread ID (`mhartid`).
+    pub const fn with_machine_ids(
+        mut self,
+        vendor: u32,
+        architecture: u32,
+        implementation: u32,
+        hart: u32,
+    ) -> Self {
+        self.machine_ids = MachineIds {
+            vendor,
+            architecture,
+            implementation,
+            hart,
+        };
+        self
+    }
+
+    /// Set the instruction budget (check [`Config::max_instructions`], `0` disables it).
+    pub const fn with_max_instructions(mut self, max_instructions: u64) -> Self {
+        self.max_instructions = max_instructions;
+        self
+    }
+
+    /// Set the instruction policy (check [`Config::instruction_policy`]).
+    pub const fn with_instruction_policy(mut self, policy: InstructionPolicy) -> Self {
+        self.instruction_policy = policy;
+        self
+    }
+
+    /// Set the resource limits (check [`Config::resource_limits`]).
+    #[cfg(feature = "resource-limits")]
+    pub const fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    /// Get the address mask of the reservation set (check [`Config::reservation_granule`]).
+    pub(crate) const fn reservation_mask(&self) -> u32 {
+        let granule = if self.reservation_granule < 4 {
+            4
+        } else {
+            self.reservation_granule
+        };
+
+        match granule.checked_next_power_of_two() {
+            Some(granule) => !(granule - 1),
+            None => 1 << 31,
+        }
+    }
+
+    /// Get the `misa` extension bits for this configuration.
+    pub(crate) const fn misa_extensions(&self) -> u32 {
+        let mut extensions = 0;
+        if self.m_extension {
+            extensions |= MISA_M;
+        }
+        if self.a_extension {
+            extensions |= MISA_A;
+        }
+        if self.c_extension {
+            extensions |= MISA_C;
+        }
+        if self.user_mode {
+            extensions |= MISA_U;
+        }
+        extensions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_misa_extensions() {
+        assert_eq!(
+            Config::default().misa_extensions(),
+            MISA_M | MISA_A | MISA_C
+        );
+        assert_eq!(
+            Config::default()
+                .with_m_extension(false)
+                .with_c_extension(false)
+                .misa_extensions(),
+            MISA_A
+        );
+        assert_eq!(
+            Config::default().with_user_mode(true).misa_extensions(),
+            MISA_M | MISA_A | MISA_C | MISA_U
+        );
+    }
+
+    #[test]
+    fn test_reservation_mask() {
+        assert_eq!(Config::default().reservation_mask(), !0x3);
+        assert_eq!(
+            Config::default()
+                .with_reservation_granule(0)
+                .reservation_mask(),
+            !0x3
+        );
+        assert_eq!(
+            Config::default()
+                .with_reservation_granule(12)
+                .reservation_mask(),
+            !0xF
+        );
+        assert_eq!(
+            Config::default()
+                .with_reservation_granule(u32::MAX)
+                .reservation_mask(),
+            1 << 31
+        );
+    }
+}
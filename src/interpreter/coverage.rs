@@ -0,0 +1,271 @@
+//! Coverage Module
+//!
+//! Execution coverage of guest code: a bitmap keyed by program counter, with one bit per
+//! 2-byte instruction slot (instructions are at least 2-byte aligned).
+//! Bitmaps of multiple runs (with the same layout) can be merged (check [`Coverage::merge`]).
+use core::fmt::{self, Display, Formatter, Write};
+
+use super::{memory::Memory, Error, ExitReason, Interpreter, State};
+
+/// Get the bitmap size, in bytes, needed to cover a code region (check [`Coverage::new`]).
+///
+/// Arguments:
+/// - `code_size`: Size of the code region, in bytes.
+pub const fn coverage_bitmap_size(code_size: usize) -> usize {
+    code_size.div_ceil(16)
+}
+
+/// Coverage bitmaps have different layouts (base address or size) and can't be merged.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MergeError;
+
+impl core::error::Error for MergeError {}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "coverage bitmaps have different layouts")
+    }
+}
+
+/// Embive Coverage Collector
+///
+/// Runs an interpreter, recording every executed instruction address in a user-supplied bitmap.
+/// Addresses outside of the bitmap range are ignored.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{coverage::{coverage_bitmap_size, Coverage}, memory::SliceMemory, ExitReason, Interpreter, State};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// let mut bitmap = [0; coverage_bitmap_size(4)];
+/// let mut coverage = Coverage::new(0, &mut bitmap);
+/// assert_eq!(coverage.run(&mut interpreter), Ok(State::Halted { reason: ExitReason::Ebreak }));
+///
+/// assert!(coverage.is_covered(0));
+/// assert_eq!(coverage.addresses().collect::<Vec<_>>(), [0]);
+/// ```
+#[derive(Debug)]
+pub struct Coverage<'c> {
+    base: u32,
+    bitmap: &'c mut [u8],
+}
+
+impl<'c> Coverage<'c> {
+    /// Create a new coverage collector.
+    ///
+    /// Arguments:
+    /// - `base`: Start address of the covered region (e.g. `0` for the code region).
+    /// - `bitmap`: Coverage bitmap, one bit per 2 bytes of code (check [`coverage_bitmap_size`]).
+    ///   Can be zeroed or hold the coverage of a previous run.
+    pub fn new(base: u32, bitmap: &'c mut [u8]) -> Self {
+        Coverage { base, bitmap }
+    }
+
+    /// Get the start address of the covered region.
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// Get the coverage bitmap (bit `n` of byte `i` is address `base + (i * 8 + n) * 2`).
+    pub fn bitmap(&self) -> &[u8] {
+        self.bitmap
+    }
+
+    /// Get the bit position of an address, if in range.
+    fn position(&self, address: u32) -> Option<(usize, u8)> {
+        let slot = (address.checked_sub(self.base)? / 2) as usize;
+        (slot / 8 < self.bitmap.len()).then_some((slot / 8, 1 << (slot % 8)))
+    }
+
+    /// Record an executed instruction address.
+    ///
+    /// Arguments:
+    /// - `address`: Instruction address (program counter).
+    #[inline]
+    pub fn record(&mut self, address: u32) {
+        if let Some((index, mask)) = self.position(address) {
+            self.bitmap[index] |= mask;
+        }
+    }
+
+    /// Check if an instruction address was executed.
+    ///
+    /// Arguments:
+    /// - `address`: Instruction address.
+    pub fn is_covered(&self, address: u32) -> bool {
+        self.position(address)
+            .is_some_and(|(index, mask)| self.bitmap[index] & mask != 0)
+    }
+
+    /// Get the number of executed instruction addresses.
+    pub fn covered_count(&self) -> usize {
+        self.bitmap
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterate over the executed instruction addresses, in ascending order.
+    pub fn addresses(&self) -> impl Iterator<Item = u32> + '_ {
+        self.bitmap
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, byte)| {
+                (0..8)
+                    .filter(move |bit| byte & (1 << bit) != 0)
+                    .map(move |bit| self.base + ((index * 8 + bit) * 2) as u32)
+            })
+    }
+
+    /// Merge the coverage of another run (same base address and bitmap size).
+    ///
+    /// Arguments:
+    /// - `other`: Coverage to merge into this one.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, coverage merged.
+    /// - `Err(MergeError)`: The bitmaps have different layouts.
+    pub fn merge(&mut self, other: &Coverage<'_>) -> Result<(), MergeError> {
+        if self.base != other.base || self.bitmap.len() != other.bitmap.len() {
+            return Err(MergeError);
+        }
+
+        for (byte, other) in self.bitmap.iter_mut().zip(other.bitmap.iter()) {
+            *byte |= other;
+        }
+        Ok(())
+    }
+
+    /// Export the executed instruction addresses, one per line (`0x<address>`).
+    ///
+    /// Arguments:
+    /// - `sink`: The output sink.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, coverage written.
+    /// - `Err(fmt::Error)`: Failed to write to the sink.
+    pub fn write<W: Write>(&self, sink: &mut W) -> fmt::Result {
+        for address in self.addresses() {
+            writeln!(sink, "0x{address:08x}")?;
+        }
+        Ok(())
+    }
+
+    /// Step through a single instruction, recording it.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(Error)`: Failed to execute the instruction.
+    pub fn step<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+    ) -> Result<State, Error> {
+        // Record the trap handler instruction, if an interrupt is taken
+        interpreter.poll_interrupts();
+
+        let pc = interpreter.program_counter;
+        let state = interpreter.step()?;
+        self.record(pc);
+
+        Ok(state)
+    }
+
+    /// Run the interpreter, recording every executed instruction (check [`Interpreter::run`]).
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(Error)`: Failed to execute.
+    pub fn run<M: Memory>(&mut self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
+        let mut count = 0;
+
+        loop {
+            if interpreter.remaining_instructions() == Some(0) {
+                // Halt when the instruction budget is exhausted
+                return Ok(State::Halted {
+                    reason: ExitReason::InstructionLimit,
+                });
+            }
+
+            let state = self.step(interpreter)?;
+            if state != State::Running {
+                return Ok(state);
+            }
+
+            count += 1;
+            if interpreter.instruction_limit > 0 && count >= interpreter.instruction_limit {
+                // Yield after the instruction limit (still running)
+                return Ok(State::Running);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "transpiler")]
+    use crate::{interpreter::memory::SliceMemory, transpiler::transpile_raw};
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_coverage() {
+        let mut code = [
+            0x13, 0x05, 0x10, 0x00, // li   a0, 1
+            0x05, 0x05, // addi a0, a0, 1 (compressed)
+            0x11, 0xa0, // j    4 (skip next)
+            0x01, 0x00, // nop (not executed)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let mut bitmap = [0; coverage_bitmap_size(14)];
+        let mut coverage = Coverage::new(0, &mut bitmap);
+        assert_eq!(
+            coverage.run(&mut interpreter),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        assert_eq!(coverage.covered_count(), 4);
+        assert!(coverage.is_covered(6));
+        assert!(!coverage.is_covered(8));
+        assert!(!coverage.is_covered(100));
+
+        let mut output = String::new();
+        coverage.write(&mut output).unwrap();
+        assert_eq!(output, "0x00000000\n0x00000004\n0x00000006\n0x0000000a\n");
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first = [0; 2];
+        let mut first = Coverage::new(0x100, &mut first);
+        first.record(0x100);
+        first.record(0x11e);
+        first.record(0x120); // Out of range
+
+        let mut second = [0; 2];
+        let mut second = Coverage::new(0x100, &mut second);
+        second.record(0x102);
+        second.record(0x11e);
+
+        first.merge(&second).unwrap();
+        assert_eq!(first.bitmap(), [0b0000_0011, 0b1000_0000]);
+        assert_eq!(first.addresses().collect::<Vec<_>>(), [0x100, 0x102, 0x11e]);
+
+        let mut other = [0; 2];
+        assert_eq!(first.merge(&Coverage::new(0, &mut other)), Err(MergeError));
+        assert_eq!(
+            first.merge(&Coverage::new(0x100, &mut [0])),
+            Err(MergeError)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Custom Instruction Module
+//!
+//! Host-side execution of custom Embive instructions (check [`crate::transpiler::CustomInstruction`]),
+//! allowing accelerator emulation (e.g. vendor DSP instructions) without a syscall round-trip.
+use crate::format::{Format, TypeR};
+use crate::instruction::embive::OpAmo;
+
+use super::{Error, Interpreter, State};
+
+/// Custom instruction handler function.
+///
+/// Called when the interpreter executes a custom Embive instruction.
+/// The program counter is advanced to the next instruction after the handler returns successfully.
+///
+/// Arguments:
+/// - `&mut Interpreter`: The interpreter (registers, memory, etc.).
+/// - `u32`: The raw Embive instruction (check [`CustomInstruction::decode`]).
+///
+/// Returns:
+/// - `Ok(State)`: Instruction executed, the interpreter state (usually [`State::Running`]).
+/// - `Err(Error)`: Failed to execute the instruction (usually [`Error::InvalidInstruction`]).
+pub type CustomInstructionHandler<M> = fn(&mut Interpreter<'_, M>, u32) -> Result<State, Error>;
+
+/// Decoded custom Embive instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CustomInstruction {
+    /// Custom instruction ID.
+    pub id: u16,
+    /// Destination register.
+    pub rd: u8,
+    /// Source register 1.
+    pub rs1: u8,
+    /// Source register 2.
+    pub rs2: u8,
+}
+
+impl CustomInstruction {
+    /// Decode a raw custom Embive instruction (as received by a [`CustomInstructionHandler`]).
+    ///
+    /// Arguments:
+    /// - `raw`: The raw Embive instruction.
+    pub fn decode(raw: u32) -> Self {
+        let inst = TypeR::from_embive(raw);
+        CustomInstruction {
+            id: inst.func.wrapping_sub(OpAmo::CUSTOM_FUNC),
+            rd: inst.rd,
+            rs1: inst.rs1,
+            rs2: inst.rs2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::embive::InstructionImpl;
+
+    #[test]
+    fn test_decode() {
+        let raw = OpAmo(TypeR {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+            func: OpAmo::CUSTOM_FUNC + 7,
+        })
+        .encode()
+            | OpAmo::opcode() as u32;
+
+        assert_eq!(
+            CustomInstruction::decode(raw),
+            CustomInstruction {
+                id: 7,
+                rd: 1,
+                rs1: 2,
+                rs2: 3,
+            }
+        );
+    }
+}
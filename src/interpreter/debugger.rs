@@ -1,5 +1,6 @@
 //! Embive Debugger
 mod gdb;
+mod history;
 
 use core::{marker::PhantomData, num::NonZeroI32};
 
@@ -10,15 +11,43 @@ use gdbstub::{
         run_blocking::{self, BlockingEventLoop},
         SingleThreadStopReason,
     },
+    target::ext::base::reverse_exec::ReplayLogPosition,
 };
 
-use super::{memory::Memory, Error, Interpreter, State, SYSCALL_ARGS};
+use super::{
+    decode_execute::is_call, memory::Memory, Error, ExitReason, Interpreter, State, SYSCALL_ARGS,
+};
+
+#[doc(inline)]
+pub use history::{Checkpoint, History};
 
 /// Debugger Execution Mode
 #[derive(Debug, PartialEq)]
 enum ExecMode {
     Step,
     Run,
+    ReverseStep,
+    ReverseRun,
+}
+
+/// Reason a debugger run primitive stopped (check [`Debugger::run_until`]).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DebugStop {
+    /// Reached the target (address, next instruction or return address).
+    Reached,
+    /// Stopped at a breakpoint: a debugger breakpoint at the program counter, or `ebreak` with
+    /// [`super::EbreakMode::Break`].
+    Breakpoint,
+    /// Interpreted code stopped ([`State::Halted`], [`State::Hooked`] or [`State::Panicked`]).
+    Stopped(State),
+}
+
+/// Map a stopped interpreter state to a [`DebugStop`].
+fn stop(state: State) -> DebugStop {
+    match state {
+        State::Breakpoint => DebugStop::Breakpoint,
+        state => DebugStop::Stopped(state),
+    }
 }
 
 /// A debugger based on gdbstub for the embive interpreter.
@@ -29,6 +58,12 @@ enum ExecMode {
 /// - `C`: Connection type
 /// - `F`: Syscall function type
 /// - `N`: Maximum number of breakpoints
+///
+/// Reverse execution (`reverse-stepi`, `reverse-continue`) is supported when an execution history is set
+/// (check [`Debugger::with_history`]).
+///
+/// Host-side tooling can also drive the interpreter directly with [`Debugger::run_until`], [`Debugger::step_over`]
+/// and [`Debugger::finish`], without a GDB connection.
 #[derive(Debug)]
 pub struct Debugger<
     'a,
@@ -41,6 +76,7 @@ pub struct Debugger<
     breakpoints: [Option<u32>; N],
     exec_mode: ExecMode,
     syscall_fn: F,
+    history: Option<History<'a>>,
     _conn: PhantomData<C>,
 }
 
@@ -72,9 +108,188 @@ impl<
             breakpoints: [None; N],
             exec_mode: ExecMode::Run,
             syscall_fn,
+            history: None,
             _conn: PhantomData,
         }
     }
+
+    /// Set the execution history, enabling reverse execution (check [`History`]).
+    pub fn with_history(mut self, history: History<'a>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Get the interpreter being debugged (e.g. to inspect registers and memory).
+    pub fn interpreter(&mut self) -> &mut Interpreter<'a, M> {
+        &mut self.interpreter
+    }
+
+    /// Run until the program counter reaches an address (temporary breakpoint).
+    ///
+    /// At least one instruction is executed. Syscalls are handled by the syscall function, and `wfi` is resumed
+    /// with an interrupt (value `0`), as when running from GDB.
+    ///
+    /// Arguments:
+    /// - `pc`: Address to stop at.
+    ///
+    /// Returns:
+    /// - `Ok(DebugStop)`: Success, why execution stopped (the address, a breakpoint, or the end of the code).
+    /// - `Err(Error)`: Failed to execute.
+    pub fn run_until(&mut self, pc: u32) -> Result<DebugStop, Error> {
+        self.run_to(pc, None)
+    }
+
+    /// Execute the next instruction, running over calls (`jal`/`jalr` linking a return address).
+    ///
+    /// Calls run until they return to the next instruction (recursive calls included, the stack pointer
+    /// must be back to its current value).
+    ///
+    /// Returns:
+    /// - `Ok(DebugStop)`: Success, why execution stopped (the next instruction, a breakpoint, or the end of the code).
+    /// - `Err(Error)`: Failed to execute.
+    pub fn step_over(&mut self) -> Result<DebugStop, Error> {
+        let pc = self.interpreter.program_counter;
+        match self.interpreter.fetch() {
+            Ok(instruction) if is_call(instruction) => {
+                let sp = self.interpreter.registers.cpu.sp() as u32;
+                self.run_to(pc.wrapping_add(instruction.size()), Some(sp))
+            }
+            // Other instructions (and fetch errors) are stepped
+            _ => Ok(self.execute()?.map_or(DebugStop::Reached, stop)),
+        }
+    }
+
+    /// Run until the current function returns to its caller.
+    ///
+    /// The return address is taken from `ra`, so it must still hold it (e.g. at the function entry, or in a leaf
+    /// function). Recursive calls returning to the same address are skipped (the stack pointer must be back to at
+    /// least its current value).
+    ///
+    /// Returns:
+    /// - `Ok(DebugStop)`: Success, why execution stopped (the return address, a breakpoint, or the end of the code).
+    /// - `Err(Error)`: Failed to execute.
+    pub fn finish(&mut self) -> Result<DebugStop, Error> {
+        let cpu = &self.interpreter.registers.cpu;
+        self.run_to(cpu.ra() as u32, Some(cpu.sp() as u32))
+    }
+
+    /// Run until the program counter reaches an address with the stack pointer at or above a frame.
+    ///
+    /// Arguments:
+    /// - `pc`: Address to stop at.
+    /// - `frame`: Lowest stack pointer (`None` for any frame).
+    fn run_to(&mut self, pc: u32, frame: Option<u32>) -> Result<DebugStop, Error> {
+        loop {
+            if let Some(state) = self.execute()? {
+                return Ok(stop(state));
+            }
+
+            let program_counter = self.interpreter.program_counter;
+            // The stack grows down, deeper frames (recursive calls) have a lower stack pointer.
+            let sp = self.interpreter.registers.cpu.sp() as u32;
+            if program_counter == pc
+                && frame.map_or(true, |frame| sp.wrapping_sub(frame) as i32 >= 0)
+            {
+                return Ok(DebugStop::Reached);
+            }
+            if self.breakpoints.contains(&Some(program_counter)) {
+                return Ok(DebugStop::Breakpoint);
+            }
+        }
+    }
+
+    /// Execute a single instruction, recording the execution history and handling syscalls and `wfi`.
+    ///
+    /// Returns:
+    /// - `Ok(Some(State))`: Execution stopped ([`State::Halted`], [`State::Breakpoint`], [`State::Hooked`]
+    ///   or [`State::Panicked`]).
+    /// - `Ok(None)`: Execution can continue.
+    /// - `Err(Error)`: Failed to execute.
+    fn execute(&mut self) -> Result<Option<State>, Error> {
+        // Checkpoint the execution, if due.
+        if let Some(history) = self.history.as_mut() {
+            history.record(&mut self.interpreter)?;
+        }
+
+        // Run a single instruction.
+        let state = self.interpreter.step()?;
+        if let Some(history) = self.history.as_mut() {
+            history.advance();
+        }
+
+        match state {
+            // Syscalls are handled right away, never deferred
+            State::Running | State::Yielded | State::SyscallPending => {}
+            State::Called => {
+                self.interpreter.syscall(&mut self.syscall_fn)?;
+
+                // Syscalls are not replayed, checkpoint their result.
+                if let Some(history) = self.history.as_mut() {
+                    history.checkpoint(&mut self.interpreter)?;
+                }
+            }
+            State::Waiting => self.interpreter.interrupt(0)?,
+            State::Halted { .. }
+            | State::Breakpoint
+            | State::Hooked(_)
+            | State::Panicked { .. } => return Ok(Some(state)),
+        }
+
+        Ok(None)
+    }
+
+    /// Step backwards (check [`History::reverse_step`]).
+    ///
+    /// Returns:
+    /// - `Ok(u64)`: Success, number of steps reversed (0 without an execution history).
+    /// - `Err(Error)`: Failed to restore or replay.
+    pub fn reverse_step(&mut self, count: u64) -> Result<u64, Error> {
+        match self.history.as_mut() {
+            Some(history) => history.reverse_step(&mut self.interpreter, count),
+            None => Ok(0),
+        }
+    }
+
+    /// Run backwards to the most recent step at a program counter (check [`History::run_backwards_to`]).
+    ///
+    /// Returns:
+    /// - `Ok(true)`: Success, stopped at the program counter.
+    /// - `Ok(false)`: Program counter not found in the execution history (or no history), the state is unchanged.
+    /// - `Err(Error)`: Failed to restore or replay.
+    pub fn run_backwards_to(&mut self, pc: u32) -> Result<bool, Error> {
+        match self.history.as_mut() {
+            Some(history) => history.run_backwards_to(&mut self.interpreter, pc),
+            None => Ok(false),
+        }
+    }
+
+    /// Execute the current reverse execution mode.
+    fn reverse(&mut self) -> Result<SingleThreadStopReason<u32>, Error> {
+        let begin = SingleThreadStopReason::ReplayLog {
+            tid: None,
+            pos: ReplayLogPosition::Begin,
+        };
+        let Some(history) = self.history.as_mut() else {
+            return Ok(begin);
+        };
+
+        if self.exec_mode == ExecMode::ReverseStep {
+            return Ok(match history.reverse_step(&mut self.interpreter, 1)? {
+                0 => begin,
+                _ => SingleThreadStopReason::DoneStep,
+            });
+        }
+
+        // Run backwards until a breakpoint, or the start of the history
+        let breakpoints = &self.breakpoints;
+        if history
+            .run_backwards_until(&mut self.interpreter, |pc| breakpoints.contains(&Some(pc)))?
+        {
+            return Ok(SingleThreadStopReason::SwBreak(()));
+        }
+        history.reverse_step(&mut self.interpreter, u64::MAX)?;
+        Ok(begin)
+    }
 }
 
 impl<
@@ -98,28 +313,46 @@ impl<
             <Self::Connection as gdbstub::conn::Connection>::Error,
         >,
     > {
+        if matches!(
+            target.exec_mode,
+            ExecMode::ReverseStep | ExecMode::ReverseRun
+        ) {
+            return target
+                .reverse()
+                .map(run_blocking::Event::TargetStopped)
+                .map_err(run_blocking::WaitForStopReasonError::Target);
+        }
+
         let mut cycles = 0;
         loop {
-            // Run a single instruction.
-            match target
-                .interpreter
-                .step()
-                .map_err(run_blocking::WaitForStopReasonError::Target)?
-            {
-                State::Running => (),
-                State::Halted => {
+            let state = target
+                .execute()
+                .map_err(run_blocking::WaitForStopReasonError::Target)?;
+
+            match state {
+                None => (),
+                Some(State::Halted {
+                    reason: ExitReason::ExitSyscall(code),
+                }) => {
+                    return Ok(run_blocking::Event::TargetStopped(
+                        SingleThreadStopReason::Exited(code as u8),
+                    ))
+                }
+                Some(State::Halted { .. }) => {
                     return Ok(run_blocking::Event::TargetStopped(
                         SingleThreadStopReason::Terminated(Signal::SIGSTOP),
                     ))
                 }
-                State::Called => target
-                    .interpreter
-                    .syscall(&mut target.syscall_fn)
-                    .map_err(run_blocking::WaitForStopReasonError::Target)?,
-                State::Waiting => target
-                    .interpreter
-                    .interrupt(0)
-                    .map_err(run_blocking::WaitForStopReasonError::Target)?,
+                Some(State::Breakpoint | State::Hooked(_)) => {
+                    return Ok(run_blocking::Event::TargetStopped(
+                        SingleThreadStopReason::SwBreak(()),
+                    ))
+                }
+                Some(_) => {
+                    return Ok(run_blocking::Event::TargetStopped(
+                        SingleThreadStopReason::Terminated(Signal::SIGABRT),
+                    ))
+                }
             }
 
             // Check for breakpoints at the current program counter.
@@ -157,3 +390,129 @@ impl<
         Ok(Some(SingleThreadStopReason::Signal(Signal::SIGINT)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::SliceMemory;
+
+    #[cfg(feature = "transpiler")]
+    use crate::transpiler::transpile_raw;
+
+    /// Connection placeholder (the run primitives don't use GDB).
+    struct NoConnection;
+
+    impl gdbstub::conn::Connection for NoConnection {
+        type Error = ();
+
+        fn write(&mut self, _byte: u8) -> Result<(), ()> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    impl ConnectionExt for NoConnection {
+        fn read(&mut self) -> Result<u8, ()> {
+            Err(())
+        }
+
+        fn peek(&mut self) -> Result<Option<u8>, ()> {
+            Ok(None)
+        }
+    }
+
+    type SyscallFn<'a> = fn(
+        i32,
+        &[i32; SYSCALL_ARGS],
+        &mut SliceMemory<'a>,
+    ) -> Result<Result<i32, NonZeroI32>, Error>;
+
+    #[cfg(feature = "transpiler")]
+    fn code() -> [u8; 32] {
+        let mut code = [
+            0x13, 0x05, 0x10, 0x00, // li   a0, 1
+            0xef, 0x00, 0xc0, 0x00, // jal  ra, 12 (call)
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x73, 0x00, 0x10, 0x00, // ebreak
+            0x13, 0x01, 0x01, 0xff, // addi sp, sp, -16 (function)
+            0x13, 0x05, 0xa5, 0x00, // addi a0, a0, 10
+            0x13, 0x01, 0x01, 0x01, // addi sp, sp, 16
+            0x67, 0x80, 0x00, 0x00, // ret
+        ];
+        transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_step_over() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut debugger: Debugger<'_, _, NoConnection, SyscallFn<'_>, 1> =
+            Debugger::new(&mut memory, |_, _, _| Ok(Ok(0)));
+
+        // Not a call
+        assert_eq!(debugger.step_over(), Ok(DebugStop::Reached));
+        assert_eq!(debugger.interpreter().program_counter, 4);
+
+        // Over the call
+        assert_eq!(debugger.step_over(), Ok(DebugStop::Reached));
+        assert_eq!(debugger.interpreter().program_counter, 8);
+        assert_eq!(debugger.interpreter().registers.cpu.a0(), 11);
+
+        assert_eq!(debugger.step_over(), Ok(DebugStop::Reached));
+        assert_eq!(
+            debugger.step_over(),
+            Ok(DebugStop::Stopped(State::Halted {
+                reason: ExitReason::Ebreak
+            }))
+        );
+        assert_eq!(debugger.interpreter().registers.cpu.a0(), 12);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_run_until_finish() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut debugger: Debugger<'_, _, NoConnection, SyscallFn<'_>, 1> =
+            Debugger::new(&mut memory, |_, _, _| Ok(Ok(0)));
+
+        // Into the function, then back to the caller
+        assert_eq!(debugger.run_until(0x14), Ok(DebugStop::Reached));
+        assert_eq!(debugger.interpreter().registers.cpu.sp(), -16);
+        debugger.interpreter().registers.cpu.set_ra(8);
+        assert_eq!(debugger.finish(), Ok(DebugStop::Reached));
+        assert_eq!(debugger.interpreter().program_counter, 8);
+        assert_eq!(debugger.interpreter().registers.cpu.a0(), 11);
+
+        // Never reached
+        assert_eq!(
+            debugger.run_until(0x100),
+            Ok(DebugStop::Stopped(State::Halted {
+                reason: ExitReason::Ebreak
+            }))
+        );
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_breakpoint_in_call() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut debugger: Debugger<'_, _, NoConnection, SyscallFn<'_>, 1> =
+            Debugger::new(&mut memory, |_, _, _| Ok(Ok(0)));
+        debugger.breakpoints[0] = Some(0x18);
+
+        assert_eq!(debugger.step_over(), Ok(DebugStop::Reached));
+        assert_eq!(debugger.step_over(), Ok(DebugStop::Breakpoint));
+        assert_eq!(debugger.interpreter().program_counter, 0x18);
+
+        // ra still holds the return address (leaf function)
+        assert_eq!(debugger.finish(), Ok(DebugStop::Reached));
+        assert_eq!(debugger.interpreter().program_counter, 8);
+    }
+}
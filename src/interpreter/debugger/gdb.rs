@@ -8,6 +8,7 @@ use gdbstub::{
     target::{
         ext::{
             base::{
+                reverse_exec::{ReverseCont, ReverseContOps, ReverseStep, ReverseStepOps},
                 single_register_access::{SingleRegisterAccess, SingleRegisterAccessOps},
                 singlethread::{
                     SingleThreadBase, SingleThreadResume, SingleThreadResumeOps,
@@ -23,7 +24,11 @@ use gdbstub::{
 use gdbstub_arch::riscv::{reg, Riscv32};
 
 use super::{Debugger, ExecMode};
-use crate::interpreter::{memory::Memory, registers::CSOperation, Error, SYSCALL_ARGS};
+use crate::interpreter::{
+    memory::{Memory, MemoryEndianness},
+    registers::CSOperation,
+    Error, SYSCALL_ARGS,
+};
 
 /// Base target implementation
 impl<
@@ -66,7 +71,8 @@ impl<
     }
 
     fn write_registers(&mut self, regs: &reg::RiscvCoreRegs<u32>) -> TargetResult<(), Self> {
-        for (i, reg) in regs.x.iter().enumerate() {
+        // Register x0 is hardwired to zero
+        for (i, reg) in regs.x.iter().enumerate().skip(1) {
             self.interpreter.registers.cpu.inner[i] = *reg as i32;
         }
 
@@ -137,6 +143,11 @@ impl<
     > SwBreakpoint for Debugger<'_, M, C, F, N>
 {
     fn add_sw_breakpoint(&mut self, addr: u32, _kind: usize) -> TargetResult<bool, Self> {
+        // Breakpoints in the middle of an instruction would never be hit
+        if self.interpreter.check_instruction_start(addr).is_err() {
+            return Err(TargetError::NonFatal);
+        }
+
         match self.breakpoints.iter().position(|b| b.is_none()) {
             Some(i) => {
                 self.breakpoints[i] = Some(addr);
@@ -174,6 +185,22 @@ impl<
     fn support_single_step(&mut self) -> Option<SingleThreadSingleStepOps<'_, Self>> {
         Some(self)
     }
+
+    #[inline(always)]
+    fn support_reverse_step(&mut self) -> Option<ReverseStepOps<'_, (), Self>> {
+        match self.history {
+            Some(_) => Some(self),
+            None => None,
+        }
+    }
+
+    #[inline(always)]
+    fn support_reverse_cont(&mut self) -> Option<ReverseContOps<'_, (), Self>> {
+        match self.history {
+            Some(_) => Some(self),
+            None => None,
+        }
+    }
 }
 
 // Single thread single step implementation
@@ -190,6 +217,34 @@ impl<
     }
 }
 
+// Reverse step implementation
+impl<
+        M: Memory,
+        C: ConnectionExt,
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<i32, NonZeroI32>, Error>,
+        const N: usize,
+    > ReverseStep<()> for Debugger<'_, M, C, F, N>
+{
+    fn reverse_step(&mut self, _tid: ()) -> Result<(), Self::Error> {
+        self.exec_mode = ExecMode::ReverseStep;
+        Ok(())
+    }
+}
+
+// Reverse continue implementation
+impl<
+        M: Memory,
+        C: ConnectionExt,
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<i32, NonZeroI32>, Error>,
+        const N: usize,
+    > ReverseCont<()> for Debugger<'_, M, C, F, N>
+{
+    fn reverse_cont(&mut self) -> Result<(), Self::Error> {
+        self.exec_mode = ExecMode::ReverseRun;
+        Ok(())
+    }
+}
+
 // Single register access implementation
 impl<
         M: Memory,
@@ -211,7 +266,7 @@ impl<
         match reg_id {
             reg::id::RiscvRegId::Pc => {
                 let pc = self.interpreter.program_counter;
-                buf[0..4].copy_from_slice(&pc.to_le_bytes());
+                buf[0..4].copy_from_slice(&pc.to_guest_bytes());
             }
             reg::id::RiscvRegId::Gpr(i) => {
                 let reg = self
@@ -220,7 +275,7 @@ impl<
                     .cpu
                     .get(i)
                     .map_err(TargetError::Fatal)?;
-                buf[0..4].copy_from_slice(&reg.to_le_bytes());
+                buf[0..4].copy_from_slice(&reg.to_guest_bytes());
             }
             reg::id::RiscvRegId::Fpr(i) => {
                 return Err(TargetError::Fatal(Error::InvalidCPURegister(i)))
@@ -228,11 +283,9 @@ impl<
             reg::id::RiscvRegId::Csr(i) => {
                 let csr = self
                     .interpreter
-                    .registers
-                    .control_status
-                    .operation(None, i)
+                    .csr_operation(None, i)
                     .map_err(TargetError::Fatal)?;
-                buf[0..4].copy_from_slice(&csr.to_le_bytes());
+                buf[0..4].copy_from_slice(&csr.to_guest_bytes());
             }
             _ => return Err(TargetError::NonFatal),
         }
@@ -253,27 +306,22 @@ impl<
         let mut pad_buf = [0; 4];
         pad_buf[..buf.len()].copy_from_slice(buf);
 
-        let val = u32::from_le_bytes(pad_buf);
+        let val = u32::from_guest_bytes(pad_buf);
 
         match reg_id {
             reg::id::RiscvRegId::Pc => self.interpreter.program_counter = val,
-            reg::id::RiscvRegId::Gpr(i) => {
-                let reg = self
-                    .interpreter
-                    .registers
-                    .cpu
-                    .get_mut(i)
-                    .map_err(TargetError::Fatal)?;
-                *reg = val as i32;
-            }
+            reg::id::RiscvRegId::Gpr(i) => self
+                .interpreter
+                .registers
+                .cpu
+                .set(i, val as i32)
+                .map_err(TargetError::Fatal)?,
             reg::id::RiscvRegId::Fpr(i) => {
                 return Err(TargetError::Fatal(Error::InvalidCPURegister(i)))
             }
             reg::id::RiscvRegId::Csr(i) => {
                 self.interpreter
-                    .registers
-                    .control_status
-                    .operation(Some(CSOperation::Write(val)), i)
+                    .csr_operation(Some(CSOperation::Write(val)), i)
                     .map_err(TargetError::Fatal)?;
             }
             _ => return Err(TargetError::NonFatal),
@@ -0,0 +1,391 @@
+//! Execution History Module
+//!
+//! Time-travel debugging through periodic checkpoints: earlier states are reconstructed by restoring
+//! the nearest checkpoint and replaying the interpreted code up to the requested step.
+use crate::interpreter::{
+    memory::{Memory, RAM_OFFSET},
+    registers::Registers,
+    Error, Interpreter, State,
+};
+
+/// Execution checkpoint (interpreter state at a given step, check [`History`]).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Checkpoint {
+    step: u64,
+    program_counter: u32,
+    registers: Registers,
+    memory_reservation: Option<(u32, i32)>,
+    rng_state: u64,
+}
+
+impl Checkpoint {
+    /// Get the step (number of instructions executed) of the checkpoint.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+}
+
+/// Execution History
+///
+/// Records checkpoints (registers and RAM) every `interval` steps, and after every syscall, in user-supplied buffers.
+/// When full, the oldest checkpoint is dropped, so the history covers the most recent execution.
+///
+/// Replaying is deterministic, as syscalls are never replayed (a checkpoint follows each of them)
+/// and waiting states are resumed with an interrupt, as done by the [`super::Debugger`].
+/// Changes not done by the interpreted code (e.g. memory written through GDB) are not recorded.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{Checkpoint, History};
+///
+/// // Up to 8 checkpoints of 1 KiB of RAM, every 1000 instructions
+/// let mut checkpoints = [Checkpoint::default(); 8];
+/// let mut ram = [0; 8 * 1024];
+/// let history = History::new(&mut checkpoints, &mut ram, 1024, 1000);
+/// assert_eq!(history.capacity(), 8);
+/// ```
+#[derive(Debug)]
+pub struct History<'h> {
+    checkpoints: &'h mut [Checkpoint],
+    ram: &'h mut [u8],
+    ram_size: usize,
+    interval: u64,
+    capacity: usize,
+    len: usize,
+    next: usize,
+    steps: u64,
+}
+
+impl<'h> History<'h> {
+    /// Create a new execution history.
+    ///
+    /// Arguments:
+    /// - `checkpoints`: Checkpoint buffer.
+    /// - `ram`: RAM snapshot buffer, `ram_size` bytes per checkpoint.
+    /// - `ram_size`: Size of the RAM to snapshot (from [`RAM_OFFSET`]), in bytes.
+    /// - `interval`: Steps between checkpoints (longer intervals use less memory, but replay more instructions).
+    pub fn new(
+        checkpoints: &'h mut [Checkpoint],
+        ram: &'h mut [u8],
+        ram_size: usize,
+        interval: u64,
+    ) -> Self {
+        let capacity = match ram_size {
+            0 => checkpoints.len(),
+            size => checkpoints.len().min(ram.len() / size),
+        };
+
+        History {
+            checkpoints,
+            ram,
+            ram_size,
+            interval: interval.max(1),
+            capacity,
+            len: 0,
+            next: 0,
+            steps: 0,
+        }
+    }
+
+    /// Get the maximum number of checkpoints.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of recorded checkpoints.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if no checkpoint was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the current step (number of instructions executed).
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Get the oldest reachable step, if any.
+    pub fn oldest(&self) -> Option<u64> {
+        self.len
+            .checked_sub(1)
+            .map(|index| self.checkpoints[self.slot(index)].step)
+    }
+
+    /// Get the slot of a checkpoint (`0` is the newest).
+    fn slot(&self, index: usize) -> usize {
+        (self.next + self.capacity - 1 - index) % self.capacity
+    }
+
+    /// Record a checkpoint of the current state (replacing the oldest one, if full).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    pub(crate) fn checkpoint<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+    ) -> Result<(), Error> {
+        if self.capacity == 0 || (self.len > 0 && self.checkpoints[self.slot(0)].step == self.steps)
+        {
+            return Ok(());
+        }
+
+        let slot = self.next;
+        let ram = interpreter.memory.load_bytes(RAM_OFFSET, self.ram_size)?;
+        self.ram[slot * self.ram_size..(slot + 1) * self.ram_size].copy_from_slice(ram);
+        self.checkpoints[slot] = Checkpoint {
+            step: self.steps,
+            program_counter: interpreter.program_counter,
+            registers: interpreter.registers,
+            memory_reservation: interpreter.memory_reservation,
+            rng_state: interpreter.rng_state,
+        };
+
+        self.next = (self.next + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+        Ok(())
+    }
+
+    /// Record a checkpoint if due, before executing a step.
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    pub(crate) fn record<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+    ) -> Result<(), Error> {
+        if self.steps % self.interval == 0 {
+            self.checkpoint(interpreter)?;
+        }
+        Ok(())
+    }
+
+    /// Account for an executed step.
+    pub(crate) fn advance(&mut self) {
+        self.steps += 1;
+    }
+
+    /// Restore a checkpoint, dropping the newer ones (their timeline will be executed again).
+    fn restore<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        index: usize,
+    ) -> Result<(), Error> {
+        let slot = self.slot(index);
+        let checkpoint = self.checkpoints[slot];
+        interpreter.memory.store_bytes(
+            RAM_OFFSET,
+            &self.ram[slot * self.ram_size..(slot + 1) * self.ram_size],
+        )?;
+        interpreter.program_counter = checkpoint.program_counter;
+        interpreter.registers = checkpoint.registers;
+        interpreter.memory_reservation = checkpoint.memory_reservation;
+        interpreter.rng_state = checkpoint.rng_state;
+        interpreter.syscall_pending = false;
+        interpreter.syscall_deferred = false;
+        interpreter.wait_timeout = None;
+
+        self.next = (slot + 1) % self.capacity;
+        self.len -= index;
+        self.steps = checkpoint.step;
+        Ok(())
+    }
+
+    /// Replay steps from the current state (no syscalls are expected).
+    fn replay<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        count: u64,
+    ) -> Result<(), Error> {
+        for _ in 0..count {
+            self.record(interpreter)?;
+            if interpreter.step()? == State::Waiting {
+                interpreter.interrupt(0)?;
+            }
+            self.advance();
+        }
+        Ok(())
+    }
+
+    /// Go back to an earlier step (restoring the nearest checkpoint and replaying up to it).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    /// - `step`: Step to go back to (not older than [`History::oldest`]).
+    fn go_to<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        step: u64,
+    ) -> Result<(), Error> {
+        // Callers never go back further than the oldest checkpoint
+        let Some(index) =
+            (0..self.len).find(|&index| self.checkpoints[self.slot(index)].step <= step)
+        else {
+            return Ok(());
+        };
+        self.restore(interpreter, index)?;
+        self.replay(interpreter, step.saturating_sub(self.steps))
+    }
+
+    /// Step backwards.
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    /// - `count`: Number of steps to go back.
+    ///
+    /// Returns:
+    /// - `Ok(u64)`: Success, number of steps reversed (limited by the oldest checkpoint).
+    /// - `Err(Error)`: Failed to restore or replay.
+    pub fn reverse_step<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        count: u64,
+    ) -> Result<u64, Error> {
+        let Some(oldest) = self.oldest() else {
+            return Ok(0);
+        };
+
+        let current = self.steps;
+        let target = current.saturating_sub(count).max(oldest).min(current);
+        if target < current {
+            self.go_to(interpreter, target)?;
+        }
+        Ok(current - target)
+    }
+
+    /// Run backwards until the program counter matches a condition (e.g. a breakpoint).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    /// - `stop`: Condition, called with the program counter of each earlier step.
+    ///
+    /// Returns:
+    /// - `Ok(true)`: Success, stopped at the most recent matching step.
+    /// - `Ok(false)`: No matching step in the history, the state is unchanged.
+    /// - `Err(Error)`: Failed to restore or replay.
+    pub fn run_backwards_until<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        mut stop: impl FnMut(u32) -> bool,
+    ) -> Result<bool, Error> {
+        let current = self.steps;
+        let mut end = current;
+
+        // Search each segment between checkpoints, newest first
+        for index in 0..self.len {
+            let start = self.checkpoints[self.slot(index)].step;
+            if start >= end {
+                continue;
+            }
+
+            let mut found = None;
+            let checkpoints = self.len;
+            self.restore(interpreter, index)?;
+            for step in start..end {
+                if stop(interpreter.program_counter) {
+                    found = Some(step);
+                }
+                if step + 1 < end {
+                    self.replay(interpreter, 1)?;
+                }
+            }
+            // Keep the newer checkpoints (same timeline)
+            self.len = checkpoints;
+            self.next = (self.next + index) % self.capacity;
+
+            if let Some(step) = found {
+                self.go_to(interpreter, step)?;
+                return Ok(true);
+            }
+            end = start;
+        }
+
+        // Not found, go back to the current step
+        if self.steps != current {
+            self.go_to(interpreter, current)?;
+        }
+        Ok(false)
+    }
+
+    /// Run backwards to the most recent step at a program counter (check [`History::run_backwards_until`]).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    /// - `pc`: Program counter to stop at.
+    pub fn run_backwards_to<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        pc: u32,
+    ) -> Result<bool, Error> {
+        self.run_backwards_until(interpreter, |address| address == pc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::SliceMemory;
+
+    #[cfg(feature = "transpiler")]
+    use crate::transpiler::transpile_raw;
+
+    /// Run forward, as done by the debugger.
+    fn run<M: Memory>(history: &mut History<'_>, interpreter: &mut Interpreter<'_, M>, count: u64) {
+        for _ in 0..count {
+            history.record(interpreter).unwrap();
+            interpreter.step().unwrap();
+            history.advance();
+        }
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_reverse_step() {
+        let mut code = [
+            0x37, 0x05, 0x00, 0x80, // lui  a0, 0x80000
+            0x85, 0x05, // addi a1, a1, 1
+            0x0c, 0xc1, // sw   a1, 0(a0)
+            0xf5, 0xbf, // j    -4
+            0x00, 0x00, // padding
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 4];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let mut checkpoints = [Checkpoint::default(); 3];
+        let mut snapshots = [0; 12];
+        let mut history = History::new(&mut checkpoints, &mut snapshots, 4, 4);
+
+        // 1 + 10 loop iterations (a1 = 10)
+        run(&mut history, &mut interpreter, 31);
+        assert_eq!(interpreter.registers.cpu.a1(), 10);
+        assert_eq!(history.oldest(), Some(20));
+
+        // Back to the end of the 7th iteration
+        assert_eq!(history.reverse_step(&mut interpreter, 9), Ok(9));
+        assert_eq!(history.steps(), 22);
+        assert_eq!(interpreter.program_counter, 4);
+        assert_eq!(interpreter.registers.cpu.a1(), 7);
+        assert_eq!(interpreter.memory.load_u32(RAM_OFFSET), Ok(7));
+
+        // Limited by the oldest checkpoint
+        assert_eq!(history.reverse_step(&mut interpreter, 100), Ok(2));
+        assert_eq!(history.steps(), 20);
+
+        // Forward again, then back to the last store
+        run(&mut history, &mut interpreter, 11);
+        assert_eq!(interpreter.registers.cpu.a1(), 10);
+        assert_eq!(history.run_backwards_to(&mut interpreter, 6), Ok(true));
+        assert_eq!(history.steps(), 29);
+        assert_eq!(interpreter.memory.load_u32(RAM_OFFSET), Ok(9));
+        assert_eq!(interpreter.registers.cpu.a1(), 10);
+
+        // Not in the history, state unchanged
+        assert_eq!(history.run_backwards_to(&mut interpreter, 0), Ok(false));
+        assert_eq!(history.steps(), 29);
+        assert_eq!(interpreter.program_counter, 6);
+    }
+}
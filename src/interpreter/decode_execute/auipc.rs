@@ -1,6 +1,5 @@
 use crate::instruction::embive::Auipc;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::Execute;
@@ -8,12 +7,12 @@ use super::Execute;
 impl<M: Memory> Execute<M> for Auipc {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
-        // rd = 0 means its a HINT instruction, just ignore it.
-        if likely(self.0.rd != 0) {
-            // Load the immediate value + pc into the register.
-            let reg = interpreter.registers.cpu.get_mut(self.0.rd)?;
-            *reg = interpreter.program_counter.wrapping_add_signed(self.0.imm) as i32;
-        }
+        // Load the immediate value + pc into the register.
+        // rd = 0 means its a HINT instruction, the write is discarded.
+        interpreter.registers.cpu.set(
+            self.0.rd,
+            interpreter.program_counter.wrapping_add_signed(self.0.imm) as i32,
+        )?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
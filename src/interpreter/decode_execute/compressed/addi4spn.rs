@@ -15,9 +15,11 @@ impl<M: Memory> Execute<M> for CAddi4spn {
         }
 
         // Load the immediate value + sp into the register.
-        let sp = interpreter.registers.cpu.get(CPURegister::SP as u8)?;
-        let reg = interpreter.registers.cpu.get_mut(self.0.rd)?;
-        *reg = sp.wrapping_add(self.0.imm);
+        let sp = interpreter.registers.cpu.get(CPURegister::SP)?;
+        interpreter
+            .registers
+            .cpu
+            .set(self.0.rd, sp.wrapping_add(self.0.imm))?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
@@ -42,11 +44,7 @@ mod tests {
     fn test_caddi4spn() {
         let mut memory = SliceMemory::new(&[], &mut []);
         let mut interpreter = Interpreter::new(&mut memory, 0);
-        *interpreter
-            .registers
-            .cpu
-            .get_mut(CPURegister::SP as u8)
-            .unwrap() = 0x1;
+        *interpreter.registers.cpu.get_mut(CPURegister::SP).unwrap() = 0x1;
         let caddi4spn = TypeCIW { rd: 10, imm: 0x100 };
 
         let result = CAddi4spn::decode(caddi4spn.to_embive()).execute(&mut interpreter);
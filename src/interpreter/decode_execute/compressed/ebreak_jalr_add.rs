@@ -1,6 +1,5 @@
 use crate::instruction::embive::CEbreakJalrAdd;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::registers::CPURegister;
 use crate::interpreter::utils::unlikely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
@@ -12,32 +11,31 @@ impl<M: Memory> Execute<M> for CEbreakJalrAdd {
         if unlikely(self.0.rs2 == 0) {
             if unlikely(self.0.rd_rs1 == 0) {
                 // Ebreak
-                // Go to next instruction
-                interpreter.program_counter = interpreter
-                    .program_counter
-                    .wrapping_add(Self::size() as u32);
-
-                // Halt the interpreter
-                return Ok(State::Halted);
+                // Halt the interpreter (or stop at a breakpoint, or trap)
+                return interpreter.ebreak(Self::size() as u32);
             } else {
                 // Jalr
                 let rs1 = interpreter.registers.cpu.get(self.0.rd_rs1)?;
 
                 // Load pc + instruction size into the return address register.
-                let ra = interpreter.registers.cpu.get_mut(CPURegister::RA as u8)?;
-                *ra = interpreter
-                    .program_counter
-                    .wrapping_add(Self::size() as u32) as i32;
-
-                // Set the program counter to the new address.
-                interpreter.program_counter = rs1 as u32;
+                interpreter.registers.cpu.set_ra(
+                    interpreter
+                        .program_counter
+                        .wrapping_add(Self::size() as u32) as i32,
+                );
+
+                // Set the program counter to the new address (bit 0 cleared).
+                interpreter.program_counter = rs1 as u32 & !1;
             }
         } else {
+            let rs1 = interpreter.registers.cpu.get(self.0.rd_rs1)?;
             let rs2 = interpreter.registers.cpu.get(self.0.rs2)?;
 
             // Add
-            let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs1)?;
-            *rd = rd.wrapping_add(rs2);
+            interpreter
+                .registers
+                .cpu
+                .set(self.0.rd_rs1, rs1.wrapping_add(rs2))?;
 
             // Go to next instruction
             interpreter.program_counter = interpreter
@@ -54,7 +52,7 @@ mod tests {
     use crate::{
         format::{Format, TypeCR},
         instruction::embive::InstructionImpl,
-        interpreter::memory::SliceMemory,
+        interpreter::{memory::SliceMemory, registers::CPURegister, ExitReason},
     };
 
     use super::*;
@@ -66,7 +64,12 @@ mod tests {
         let ebreak = TypeCR { rd_rs1: 0, rs2: 0 };
 
         let result = CEbreakJalrAdd::decode(ebreak.to_embive()).execute(&mut interpreter);
-        assert_eq!(result, Ok(State::Halted));
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
         assert_eq!(interpreter.program_counter, 0x2);
     }
 
@@ -80,14 +83,7 @@ mod tests {
 
         let result = CEbreakJalrAdd::decode(jalr.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::RA as u8)
-                .unwrap(),
-            0x2
-        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::RA).unwrap(), 0x2);
         assert_eq!(interpreter.program_counter, 0x4);
     }
 
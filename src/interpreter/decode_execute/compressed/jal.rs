@@ -1,6 +1,5 @@
 use crate::instruction::embive::CJal;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::registers::CPURegister;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::super::Execute;
@@ -9,10 +8,11 @@ impl<M: Memory> Execute<M> for CJal {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Load pc + instruction size into the return address register.
-        let ra = interpreter.registers.cpu.get_mut(CPURegister::RA as u8)?;
-        *ra = interpreter
-            .program_counter
-            .wrapping_add(Self::size() as u32) as i32;
+        interpreter.registers.cpu.set_ra(
+            interpreter
+                .program_counter
+                .wrapping_add(Self::size() as u32) as i32,
+        );
 
         // Set the program counter to the new address.
         interpreter.program_counter = interpreter.program_counter.wrapping_add_signed(self.0.imm);
@@ -26,7 +26,7 @@ mod tests {
     use crate::{
         format::{Format, TypeCJ},
         instruction::embive::InstructionImpl,
-        interpreter::memory::SliceMemory,
+        interpreter::{memory::SliceMemory, registers::CPURegister},
     };
 
     use super::*;
@@ -39,14 +39,7 @@ mod tests {
 
         let result = CJal::decode(jal.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
-        assert_eq!(
-            interpreter
-                .registers
-                .cpu
-                .get(CPURegister::RA as u8)
-                .unwrap(),
-            0x2
-        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::RA).unwrap(), 0x2);
         assert_eq!(interpreter.program_counter, 0xc);
     }
 }
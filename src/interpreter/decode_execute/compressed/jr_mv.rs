@@ -11,13 +11,11 @@ impl<M: Memory> Execute<M> for CJrMv {
             // JR (Jump Register)
             let rd_rs1 = interpreter.registers.cpu.get(self.0.rd_rs1)?;
 
-            interpreter.program_counter = rd_rs1 as u32;
+            interpreter.program_counter = rd_rs1 as u32 & !1;
         } else {
             // MV (Move)
             let rs2 = interpreter.registers.cpu.get(self.0.rs2)?;
-            let rd_rs1 = interpreter.registers.cpu.get_mut(self.0.rd_rs1)?;
-
-            *rd_rs1 = rs2;
+            interpreter.registers.cpu.set(self.0.rd_rs1, rs2)?;
 
             // Go to next instruction
             interpreter.program_counter = interpreter
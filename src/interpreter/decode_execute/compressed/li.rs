@@ -1,6 +1,5 @@
 use crate::instruction::embive::CLi;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::super::Execute;
@@ -9,10 +8,7 @@ impl<M: Memory> Execute<M> for CLi {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Load the immediate value into the register.
-        if likely(self.0.rd_rs1 != 0) {
-            let rs1 = interpreter.registers.cpu.get_mut(self.0.rd_rs1)?;
-            *rs1 = self.0.imm;
-        }
+        interpreter.registers.cpu.set(self.0.rd_rs1, self.0.imm)?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
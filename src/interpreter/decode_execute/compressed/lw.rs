@@ -1,9 +1,6 @@
 use crate::instruction::embive::CLw;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::{
-    memory::{Memory, MemoryType},
-    Error, Interpreter, State,
-};
+use crate::interpreter::{memory::Memory, Error, Interpreter, MemoryAccess, State};
 
 use super::super::Execute;
 
@@ -13,11 +10,11 @@ impl<M: Memory> Execute<M> for CLw {
         // Load word from memory
         let rs1 = interpreter.registers.cpu.get(self.0.rs1)?;
         let address = (rs1 as u32).wrapping_add(self.0.imm as u32);
+        let address = interpreter.translate(address, 4, MemoryAccess::Load)?;
 
-        let result = i32::load(interpreter.memory, address)?;
+        let result = interpreter.memory.load_u32(address)? as i32;
         // Store the result in the destination register
-        let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-        *rd = result;
+        interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
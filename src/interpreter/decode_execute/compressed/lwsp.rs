@@ -1,10 +1,7 @@
 use crate::instruction::embive::CLwsp;
 use crate::instruction::embive::InstructionImpl;
 use crate::interpreter::registers::CPURegister;
-use crate::interpreter::{
-    memory::{Memory, MemoryType},
-    Error, Interpreter, State,
-};
+use crate::interpreter::{memory::Memory, Error, Interpreter, MemoryAccess, State};
 
 use super::super::Execute;
 
@@ -12,13 +9,13 @@ impl<M: Memory> Execute<M> for CLwsp {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Load word from memory (sp + imm)
-        let sp = interpreter.registers.cpu.get(CPURegister::SP as u8)?;
+        let sp = interpreter.registers.cpu.get(CPURegister::SP)?;
         let address = (sp as u32).wrapping_add(self.0.imm as u32);
+        let address = interpreter.translate(address, 4, MemoryAccess::Load)?;
 
-        let result = i32::load(interpreter.memory, address)?;
+        let result = interpreter.memory.load_u32(address)? as i32;
         // Store the result in the destination register
-        let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs1)?;
-        *rd = result;
+        interpreter.registers.cpu.set(self.0.rd_rs1, result)?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
@@ -57,11 +54,7 @@ mod tests {
             rd_rs1: 1,
             imm: 0x4,
         };
-        *interpreter
-            .registers
-            .cpu
-            .get_mut(CPURegister::SP as u8)
-            .unwrap() = get_ram_addr();
+        *interpreter.registers.cpu.get_mut(CPURegister::SP).unwrap() = get_ram_addr();
 
         let result = CLwsp::decode(lwsp.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
@@ -1,6 +1,5 @@
 use crate::instruction::embive::COr;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::super::Execute;
@@ -9,12 +8,9 @@ impl<M: Memory> Execute<M> for COr {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Or operation
-        if likely(self.0.rd_rs1 != 0) {
-            let rs2 = interpreter.registers.cpu.get(self.0.rs2)?;
-            let rs1 = interpreter.registers.cpu.get_mut(self.0.rd_rs1)?;
-
-            *rs1 |= rs2;
-        }
+        let rs1 = interpreter.registers.cpu.get(self.0.rd_rs1)?;
+        let rs2 = interpreter.registers.cpu.get(self.0.rs2)?;
+        interpreter.registers.cpu.set(self.0.rd_rs1, rs1 | rs2)?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
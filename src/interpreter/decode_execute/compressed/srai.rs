@@ -1,6 +1,5 @@
 use crate::instruction::embive::CSrai;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::super::Execute;
@@ -9,10 +8,11 @@ impl<M: Memory> Execute<M> for CSrai {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Arithmetic right shift
-        if likely(self.0.rd_rs1 != 0) {
-            let rs1 = interpreter.registers.cpu.get_mut(self.0.rd_rs1)?;
-            *rs1 = rs1.wrapping_shr(self.0.imm as u32);
-        }
+        let rs1 = interpreter.registers.cpu.get(self.0.rd_rs1)?;
+        interpreter
+            .registers
+            .cpu
+            .set(self.0.rd_rs1, rs1.wrapping_shr(self.0.imm as u32))?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
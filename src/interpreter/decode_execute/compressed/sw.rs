@@ -1,9 +1,8 @@
 use crate::instruction::embive::CSw;
 use crate::instruction::embive::InstructionImpl;
-use crate::interpreter::{
-    memory::{Memory, MemoryType},
-    Error, Interpreter, State,
-};
+#[cfg(feature = "resource-limits")]
+use crate::interpreter::Resource;
+use crate::interpreter::{memory::Memory, Error, Interpreter, MemoryAccess, State};
 
 use super::super::Execute;
 
@@ -13,9 +12,17 @@ impl<M: Memory> Execute<M> for CSw {
         // Store word on memory
         let rs1 = interpreter.registers.cpu.get(self.0.rs1)?;
         let address = (rs1 as u32).wrapping_add(self.0.imm as u32);
+        let address = interpreter.translate(address, 4, MemoryAccess::Store)?;
 
         let rs2 = interpreter.registers.cpu.get(self.0.rd_rs2)?;
-        rs2.store(interpreter.memory, address)?;
+        #[cfg(feature = "resource-limits")]
+        if let Some(state) = interpreter.use_resource(Resource::BytesWritten, 4) {
+            // Write limit reached, not executed
+            return Ok(state);
+        }
+        interpreter.invalidate_reservation(address, 4);
+        interpreter.memory.store_u32(address, rs2 as u32)?;
+        interpreter.sync_software_interrupt();
 
         // Go to next instruction
         interpreter.program_counter = interpreter
@@ -52,7 +59,7 @@ mod tests {
             imm: 0x4,
         };
         *interpreter.registers.cpu.get_mut(9).unwrap() = get_ram_addr();
-        *interpreter.registers.cpu.get_mut(8).unwrap() = i32::from_le(0x78563412);
+        *interpreter.registers.cpu.get_mut(8).unwrap() = 0x78563412;
 
         let result = CSw::decode(lw.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
@@ -1,10 +1,9 @@
 use crate::instruction::embive::CSwsp;
 use crate::instruction::embive::InstructionImpl;
 use crate::interpreter::registers::CPURegister;
-use crate::interpreter::{
-    memory::{Memory, MemoryType},
-    Error, Interpreter, State,
-};
+#[cfg(feature = "resource-limits")]
+use crate::interpreter::Resource;
+use crate::interpreter::{memory::Memory, Error, Interpreter, MemoryAccess, State};
 
 use super::super::Execute;
 
@@ -12,11 +11,19 @@ impl<M: Memory> Execute<M> for CSwsp {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Store word to memory (sp + imm)
-        let sp = interpreter.registers.cpu.get(CPURegister::SP as u8)?;
+        let sp = interpreter.registers.cpu.get(CPURegister::SP)?;
         let address = (sp as u32).wrapping_add(self.0.imm as u32);
+        let address = interpreter.translate(address, 4, MemoryAccess::Store)?;
 
         let rs2 = interpreter.registers.cpu.get(self.0.rs2)?;
-        rs2.store(interpreter.memory, address)?;
+        #[cfg(feature = "resource-limits")]
+        if let Some(state) = interpreter.use_resource(Resource::BytesWritten, 4) {
+            // Write limit reached, not executed
+            return Ok(state);
+        }
+        interpreter.invalidate_reservation(address, 4);
+        interpreter.memory.store_u32(address, rs2 as u32)?;
+        interpreter.sync_software_interrupt();
 
         // Go to next instruction
         interpreter.program_counter = interpreter
@@ -49,12 +56,8 @@ mod tests {
         let mut interpreter = Interpreter::new(&mut memory, 0);
         let swsp = TypeCSS { rs2: 1, imm: 0x4 };
 
-        *interpreter
-            .registers
-            .cpu
-            .get_mut(CPURegister::SP as u8)
-            .unwrap() = get_ram_addr();
-        *interpreter.registers.cpu.get_mut(1).unwrap() = i32::from_le(0x78563412);
+        *interpreter.registers.cpu.get_mut(CPURegister::SP).unwrap() = get_ram_addr();
+        *interpreter.registers.cpu.get_mut(1).unwrap() = 0x78563412;
 
         let result = CSwsp::decode(swsp.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
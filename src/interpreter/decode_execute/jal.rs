@@ -1,6 +1,5 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::Jal;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::Execute;
@@ -9,12 +8,12 @@ impl<M: Memory> Execute<M> for Jal {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         // Load pc + instruction size into the destination register.
-        if likely(self.0.rd != 0) {
-            let reg = interpreter.registers.cpu.get_mut(self.0.rd)?;
-            *reg = interpreter
+        interpreter.registers.cpu.set(
+            self.0.rd,
+            interpreter
                 .program_counter
-                .wrapping_add(Self::size() as u32) as i32;
-        }
+                .wrapping_add(Self::size() as u32) as i32,
+        )?;
 
         // Set the program counter to the new address.
         interpreter.program_counter = interpreter.program_counter.wrapping_add_signed(self.0.imm);
@@ -1,6 +1,5 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::Jalr;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::Execute;
@@ -12,15 +11,15 @@ impl<M: Memory> Execute<M> for Jalr {
         let rs1 = interpreter.registers.cpu.get(self.0.rs1)?;
 
         // Load pc + instruction size into the destination register (if not unconditional).
-        if likely(self.0.rd_rs2 != 0) {
-            let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-            *rd = interpreter
+        interpreter.registers.cpu.set(
+            self.0.rd_rs2,
+            interpreter
                 .program_counter
-                .wrapping_add(Self::size() as u32) as i32;
-        }
+                .wrapping_add(Self::size() as u32) as i32,
+        )?;
 
-        // Set the program counter to the new address.
-        interpreter.program_counter = (rs1 as u32).wrapping_add_signed(self.0.imm);
+        // Set the program counter to the new address (bit 0 cleared).
+        interpreter.program_counter = (rs1 as u32).wrapping_add_signed(self.0.imm) & !1;
 
         // Continue execution
         Ok(State::Running)
@@ -1,9 +1,8 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::LoadStore;
-use crate::interpreter::{
-    memory::{Memory, MemoryType},
-    Error, Interpreter, State,
-};
+#[cfg(feature = "resource-limits")]
+use crate::interpreter::Resource;
+use crate::interpreter::{memory::Memory, Error, Interpreter, MemoryAccess, State};
 
 use super::Execute;
 
@@ -15,49 +14,65 @@ impl<M: Memory> Execute<M> for LoadStore {
         let address = (rs1 as u32).wrapping_add_signed(self.0.imm);
         match self.0.func {
             Self::LB_FUNC => {
-                let result = i8::load(interpreter.memory, address)? as i32;
+                let address = interpreter.translate(address, 1, MemoryAccess::Load)?;
+                let result = interpreter.memory.load_u8(address)? as i8 as i32;
                 // Store the result in the destination register
-                let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-                *rd = result;
+                interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
             }
             Self::LH_FUNC => {
-                let result = i16::load(interpreter.memory, address)? as i32;
+                let address = interpreter.translate(address, 2, MemoryAccess::Load)?;
+                let result = interpreter.memory.load_u16(address)? as i16 as i32;
                 // Store the result in the destination register
-                let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-                *rd = result;
+                interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
             }
             Self::LW_FUNC => {
-                let result = i32::load(interpreter.memory, address)?;
+                let address = interpreter.translate(address, 4, MemoryAccess::Load)?;
+                let result = interpreter.memory.load_u32(address)? as i32;
                 // Store the result in the destination register
-                let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-                *rd = result;
+                interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
             }
             Self::LBU_FUNC => {
-                let result = u8::load(interpreter.memory, address)? as i32;
+                let address = interpreter.translate(address, 1, MemoryAccess::Load)?;
+                let result = interpreter.memory.load_u8(address)? as i32;
                 // Store the result in the destination register
-                let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-                *rd = result;
+                interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
             }
             Self::LHU_FUNC => {
-                let result = u16::load(interpreter.memory, address)? as i32;
+                let address = interpreter.translate(address, 2, MemoryAccess::Load)?;
+                let result = interpreter.memory.load_u16(address)? as i32;
                 // Store the result in the destination register
-                let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-                *rd = result;
+                interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
             }
             Self::SB_FUNC => {
-                let address = (rs1 as u32).wrapping_add_signed(self.0.imm);
+                let address = interpreter.translate(address, 1, MemoryAccess::Store)?;
                 let rs2 = interpreter.registers.cpu.get(self.0.rd_rs2)?;
-                (rs2 as u8).store(interpreter.memory, address)?;
+                #[cfg(feature = "resource-limits")]
+                if let Some(state) = interpreter.use_resource(Resource::BytesWritten, 1) {
+                    return Ok(state);
+                }
+                interpreter.invalidate_reservation(address, 1);
+                interpreter.memory.store_u8(address, rs2 as u8)?;
             }
             Self::SH_FUNC => {
-                let address = (rs1 as u32).wrapping_add_signed(self.0.imm);
+                let address = interpreter.translate(address, 2, MemoryAccess::Store)?;
                 let rs2 = interpreter.registers.cpu.get(self.0.rd_rs2)?;
-                (rs2 as u16).store(interpreter.memory, address)?;
+                #[cfg(feature = "resource-limits")]
+                if let Some(state) = interpreter.use_resource(Resource::BytesWritten, 2) {
+                    return Ok(state);
+                }
+                interpreter.invalidate_reservation(address, 2);
+                interpreter.memory.store_u16(address, rs2 as u16)?;
             }
             Self::SW_FUNC => {
-                let address = (rs1 as u32).wrapping_add_signed(self.0.imm);
+                let address = interpreter.translate(address, 4, MemoryAccess::Store)?;
                 let rs2 = interpreter.registers.cpu.get(self.0.rd_rs2)?;
-                rs2.store(interpreter.memory, address)?;
+                #[cfg(feature = "resource-limits")]
+                if let Some(state) = interpreter.use_resource(Resource::BytesWritten, 4) {
+                    return Ok(state);
+                }
+                interpreter.invalidate_reservation(address, 4);
+                interpreter.memory.store_u32(address, rs2 as u32)?;
+                interpreter.sync_software_interrupt();
             }
             _ => return Err(Error::InvalidInstruction(interpreter.program_counter)),
         };
@@ -105,6 +120,27 @@ mod tests {
         assert_eq!(interpreter.program_counter, LoadStore::size() as u32);
     }
 
+    #[test]
+    fn test_lw_zero_register() {
+        let mut ram = [0x12; 4];
+
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let lw = TypeI {
+            rd_rs2: 0,
+            rs1: 2,
+            imm: 0x0,
+            func: LoadStore::LW_FUNC,
+        };
+        *interpreter.registers.cpu.get_mut(2).unwrap() = get_ram_addr();
+
+        // The load is still performed, but its result is discarded
+        let result = LoadStore::decode(lw.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Ok(State::Running));
+        assert_eq!(interpreter.registers.cpu.get(0), Ok(0));
+        assert_eq!(interpreter.program_counter, LoadStore::size() as u32);
+    }
+
     #[test]
     fn test_lb_negative() {
         let mut ram = [0x0; 2];
@@ -336,7 +372,7 @@ mod tests {
         };
 
         *interpreter.registers.cpu.get_mut(1).unwrap() = get_ram_addr();
-        *interpreter.registers.cpu.get_mut(2).unwrap() = i32::from_le(0x1234);
+        *interpreter.registers.cpu.get_mut(2).unwrap() = 0x1234;
 
         let result = LoadStore::decode(store.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
@@ -357,7 +393,7 @@ mod tests {
         };
 
         *interpreter.registers.cpu.get_mut(1).unwrap() = get_ram_addr();
-        *interpreter.registers.cpu.get_mut(2).unwrap() = i32::from_le(0x12345678);
+        *interpreter.registers.cpu.get_mut(2).unwrap() = 0x12345678;
 
         let result = LoadStore::decode(store.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
@@ -1,6 +1,6 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::Lui;
-use crate::interpreter::utils::likely;
+use crate::interpreter::utils::unlikely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::Execute;
@@ -8,13 +8,14 @@ use super::Execute;
 impl<M: Memory> Execute<M> for Lui {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
-        if likely(self.0.rd != 0) {
-            // rd = 0 means its a HINT instruction, just ignore it.
-            // Load the immediate value into the register.
-            let reg = interpreter.registers.cpu.get_mut(self.0.rd)?;
-            *reg = self.0.imm;
+        if unlikely(self.0.li) {
+            return load_immediate(interpreter, self.0.rd);
         }
 
+        // Load the immediate value into the register.
+        // rd = 0 means its a HINT instruction, the write is discarded.
+        interpreter.registers.cpu.set(self.0.rd, self.0.imm)?;
+
         // Go to next instruction
         interpreter.program_counter = interpreter
             .program_counter
@@ -25,10 +26,32 @@ impl<M: Memory> Execute<M> for Lui {
     }
 }
 
+/// Execute a load-immediate (`lui` with the load-immediate flag, emitted by the transpiler for `lui` + `addi`
+/// pairs, check [`crate::transpiler::Config::with_load_immediate`]).
+///
+/// The full 32-bit immediate is the word after the instruction, fetched with the same checks as an instruction.
+///
+/// Arguments:
+/// - `interpreter`: Embive interpreter.
+/// - `rd`: Destination register.
+#[inline(never)]
+fn load_immediate<M: Memory>(interpreter: &mut Interpreter<'_, M>, rd: u8) -> Result<State, Error> {
+    let imm = interpreter.fetch_operand(Lui::size() as u32)?;
+    interpreter.registers.cpu.set(rd, imm as i32)?;
+
+    // Go to next instruction
+    interpreter.program_counter = interpreter
+        .program_counter
+        .wrapping_add(Lui::LOAD_IMMEDIATE_SIZE);
+
+    // Continue execution
+    Ok(State::Running)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        format::{Format, TypeU},
+        format::{Format, TypeUL},
         instruction::embive::InstructionImpl,
         interpreter::memory::SliceMemory,
     };
@@ -40,11 +63,45 @@ mod tests {
         let mut memory = SliceMemory::new(&[], &mut []);
         let mut interpreter = Interpreter::new(&mut memory, 0);
         interpreter.program_counter = 0x1;
-        let lui = TypeU { rd: 1, imm: 0x1000 };
+        let lui = TypeUL {
+            rd: 1,
+            imm: 0x1000,
+            li: false,
+        };
 
         let result = Lui::decode(lui.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Running));
         assert_eq!(*interpreter.registers.cpu.get_mut(1).unwrap(), 0x1000);
         assert_eq!(interpreter.program_counter, 0x1 + Lui::size() as u32);
     }
+
+    #[test]
+    fn test_lui_load_immediate() {
+        let lui = Lui(TypeUL {
+            rd: 1,
+            imm: 0x1234_5000,
+            li: true,
+        });
+
+        let mut code = [0; 8];
+        code[..4].copy_from_slice(&(lui.encode() | Lui::opcode() as u32).to_le_bytes());
+        code[4..].copy_from_slice(&0x1234_4F00u32.to_le_bytes());
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // Single 8-byte instruction
+        assert_eq!(interpreter.fetch().unwrap().size(), 8);
+        let result = lui.execute(&mut interpreter);
+        assert_eq!(result, Ok(State::Running));
+        assert_eq!(interpreter.registers.cpu.get(1), Ok(0x1234_4F00));
+        assert_eq!(interpreter.program_counter, 8);
+
+        // Missing immediate
+        let mut memory = SliceMemory::new(&code[..4], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let result = lui.execute(&mut interpreter);
+        assert_eq!(result, Err(Error::InvalidProgramCounter(4)));
+        assert_eq!(interpreter.registers.cpu.get(1), Ok(0));
+        assert_eq!(interpreter.program_counter, 0);
+    }
 }
@@ -1,16 +1,32 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::OpAmo;
-use crate::interpreter::utils::likely;
-use crate::interpreter::{
-    memory::{Memory, MemoryType},
-    Error, Interpreter, State,
-};
+use crate::interpreter::utils::unlikely;
+#[cfg(feature = "resource-limits")]
+use crate::interpreter::Resource;
+use crate::interpreter::{memory::Memory, Error, Interpreter, MemoryAccess, State};
 
 use super::Execute;
 
 impl<M: Memory> Execute<M> for OpAmo {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
+        // Check if the M / A extensions are enabled
+        if unlikely(self.0.func >= Self::MUL_FUNC) {
+            if unlikely(self.0.func >= Self::CUSTOM_FUNC) {
+                return execute_custom(self, interpreter);
+            }
+
+            let enabled = if self.0.func < Self::LR_FUNC {
+                interpreter.config.m_extension
+            } else {
+                interpreter.config.a_extension
+            };
+
+            if unlikely(!enabled) {
+                return Err(Error::IllegalInstruction(interpreter.program_counter));
+            }
+        }
+
         let rs1 = interpreter.registers.cpu.get(self.0.rs1)?;
         let rs2 = interpreter.registers.cpu.get(self.0.rs2)?;
 
@@ -60,13 +76,29 @@ impl<M: Memory> Execute<M> for OpAmo {
                 }
             } // Remu (Remainder, unsigned)
             _ => {
-                // Atomic operations
-                let value = i32::load(interpreter.memory, rs1 as u32)?;
+                // Atomic operations (load reservations are loads, at the physical address)
+                let access = match self.0.func {
+                    Self::LR_FUNC => MemoryAccess::Load,
+                    _ => MemoryAccess::Atomic,
+                };
+                let address = interpreter.translate(rs1 as u32, 4, access)?;
+                let value = interpreter.memory.load_u32(address)? as i32;
+
+                if (Self::AMOSWAP_FUNC..=Self::AMOMAXU_FUNC).contains(&self.0.func) {
+                    #[cfg(feature = "resource-limits")]
+                    if let Some(state) = interpreter.use_resource(Resource::BytesWritten, 4) {
+                        // Write limit reached, not executed
+                        return Ok(state);
+                    }
 
-                match self.0.func {
+                    // Read-modify-write stores invalidate the memory reservation
+                    interpreter.invalidate_reservation(address, 4);
+                }
+
+                let result = match self.0.func {
                     Self::LR_FUNC => {
                         // Load Reserved (rd = mem[rs1])
-                        interpreter.memory_reservation = Some((rs1 as u32, value)); // Reserve memory
+                        interpreter.memory_reservation = Some((address, value)); // Reserve memory
                         value
                     }
                     Self::SC_FUNC => {
@@ -74,8 +106,18 @@ impl<M: Memory> Execute<M> for OpAmo {
                         let ret;
                         match interpreter.memory_reservation.take() {
                             Some((addr, old_value)) => {
-                                if addr == rs1 as u32 && value == old_value {
-                                    rs2.store(interpreter.memory, addr)?;
+                                // Stores to the reservation set and traps already invalidated it,
+                                // the value check covers host writes
+                                if addr == address && value == old_value {
+                                    #[cfg(feature = "resource-limits")]
+                                    if let Some(state) =
+                                        interpreter.use_resource(Resource::BytesWritten, 4)
+                                    {
+                                        // Write limit reached, not executed (reservation kept)
+                                        interpreter.memory_reservation = Some((addr, old_value));
+                                        return Ok(state);
+                                    }
+                                    interpreter.memory.store_u32(addr, rs2 as u32)?;
                                     ret = 0;
                                 } else {
                                     // Value has changed or address is different
@@ -91,64 +133,75 @@ impl<M: Memory> Execute<M> for OpAmo {
                     }
                     Self::AMOSWAP_FUNC => {
                         // Atomic Swap (rd = mem[rs1]; mem[rs1] = rs2)
-                        rs2.store(interpreter.memory, rs1 as u32)?;
+                        interpreter.memory.store_u32(address, rs2 as u32)?;
                         value
                     }
                     Self::AMOADD_FUNC => {
                         // Atomic Add (rd = mem[rs1]; mem[rs1] += rs2)
-                        value
-                            .wrapping_add(rs2)
-                            .store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, value.wrapping_add(rs2) as u32)?;
                         value
                     }
                     Self::AMOXOR_FUNC => {
                         // Atomic Xor (rd = mem[rs1]; mem[rs1] ^= rs2)
-                        (value ^ rs2).store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, (value ^ rs2) as u32)?;
                         value
                     }
                     Self::AMOAND_FUNC => {
                         // Atomic And (rd = mem[rs1]; mem[rs1] &= rs2)
-                        (value & rs2).store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, (value & rs2) as u32)?;
                         value
                     }
                     Self::AMOOR_FUNC => {
                         // Atomic Or (rd = mem[rs1]; mem[rs1] |= rs2)
-                        (value | rs2).store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, (value | rs2) as u32)?;
                         value
                     }
                     Self::AMOMIN_FUNC => {
                         // Atomic Min (rd = mem[rs1]; mem[rs1] = min(mem[rs1], rs2))
-                        value.min(rs2).store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, value.min(rs2) as u32)?;
                         value
                     }
                     Self::AMOMAX_FUNC => {
                         // Atomic Max (rd = max(mem[rs1], rs2))
-                        value.max(rs2).store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, value.max(rs2) as u32)?;
                         value
                     }
                     Self::AMOMINU_FUNC => {
                         // Atomic Min Unsigned (rd = minu(mem[rs1], rs2))
-                        (value as u32)
-                            .min(rs2 as u32)
-                            .store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, (value as u32).min(rs2 as u32))?;
                         value
                     }
                     Self::AMOMAXU_FUNC => {
                         // Atomic Max Unsigned (rd = maxu(mem[rs1], rs2))
-                        (value as u32)
-                            .max(rs2 as u32)
-                            .store(interpreter.memory, rs1 as u32)?;
+                        interpreter
+                            .memory
+                            .store_u32(address, (value as u32).max(rs2 as u32))?;
                         value
                     }
                     _ => return Err(Error::InvalidInstruction(interpreter.program_counter)),
-                }
+                };
+
+                // Stores may have written a memory-mapped software interrupt register
+                interpreter.sync_software_interrupt();
+                result
             }
         };
 
-        if likely(self.0.rd != 0) {
-            let rd = interpreter.registers.cpu.get_mut(self.0.rd)?;
-            *rd = result;
-        }
+        interpreter.registers.cpu.set(self.0.rd, result)?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
@@ -159,12 +212,35 @@ impl<M: Memory> Execute<M> for OpAmo {
     }
 }
 
+/// Execute a custom instruction through the host handler.
+#[cold]
+fn execute_custom<M: Memory>(
+    inst: &OpAmo,
+    interpreter: &mut Interpreter<'_, M>,
+) -> Result<State, Error> {
+    let handler = interpreter
+        .custom_instruction_handler
+        .ok_or(Error::InvalidInstruction(interpreter.program_counter))?;
+
+    let state = handler(interpreter, inst.encode() | OpAmo::opcode() as u32)?;
+
+    // Go to next instruction
+    interpreter.program_counter = interpreter
+        .program_counter
+        .wrapping_add(OpAmo::size() as u32);
+
+    Ok(state)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        format::{Format, TypeR},
-        instruction::embive::InstructionImpl,
-        interpreter::memory::{SliceMemory, RAM_OFFSET},
+        format::{Format, TypeI, TypeR},
+        instruction::embive::{InstructionImpl, LoadStore},
+        interpreter::{
+            memory::{SliceMemory, RAM_OFFSET},
+            Config,
+        },
     };
 
     use super::*;
@@ -607,6 +683,68 @@ mod tests {
         assert_eq!(interpreter.program_counter, OpAmo::size() as u32);
     }
 
+    #[test]
+    fn test_mul_disabled() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let config = crate::interpreter::Config::default().with_m_extension(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        let op = TypeR {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+            func: OpAmo::MUL_FUNC,
+        };
+        *interpreter.registers.cpu.get_mut(2).unwrap() = 10;
+        *interpreter.registers.cpu.get_mut(3).unwrap() = 20;
+
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Err(Error::IllegalInstruction(0)));
+        assert_eq!(*interpreter.registers.cpu.get_mut(1).unwrap(), 0);
+        assert_eq!(interpreter.program_counter, 0);
+
+        // Base instructions are still available
+        let op = TypeR {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+            func: OpAmo::ADD_FUNC,
+        };
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Ok(State::Running));
+        assert_eq!(*interpreter.registers.cpu.get_mut(1).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_amoadd_disabled() {
+        let mut ram = [0; 4];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let config = crate::interpreter::Config::default().with_a_extension(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        let op = TypeR {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+            func: OpAmo::AMOADD_FUNC,
+        };
+        *interpreter.registers.cpu.get_mut(2).unwrap() = RAM_OFFSET as i32;
+        *interpreter.registers.cpu.get_mut(3).unwrap() = 20;
+
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Err(Error::IllegalInstruction(0)));
+        assert_eq!(interpreter.program_counter, 0);
+
+        // M extension is still available
+        let op = TypeR {
+            rd: 1,
+            rs1: 3,
+            rs2: 3,
+            func: OpAmo::MUL_FUNC,
+        };
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Ok(State::Running));
+        assert_eq!(*interpreter.registers.cpu.get_mut(1).unwrap(), 400);
+    }
+
     #[test]
     fn test_mul_negative() {
         let mut memory = SliceMemory::new(&[], &mut []);
@@ -1096,4 +1234,127 @@ mod tests {
         assert_eq!(*interpreter.registers.cpu.get_mut(1).unwrap(), -14);
         assert_eq!(i32::from_le_bytes(ram), -14);
     }
+
+    #[test]
+    fn test_custom() {
+        fn handler(
+            interpreter: &mut Interpreter<'_, SliceMemory<'_>>,
+            raw: u32,
+        ) -> Result<State, Error> {
+            let inst = crate::interpreter::CustomInstruction::decode(raw);
+            match inst.id {
+                // Multiply-accumulate
+                5 => {
+                    let rs1 = interpreter.registers.cpu.get(inst.rs1)?;
+                    let rs2 = interpreter.registers.cpu.get(inst.rs2)?;
+                    let rd = interpreter.registers.cpu.get_mut(inst.rd)?;
+                    *rd = rd.wrapping_add(rs1.wrapping_mul(rs2));
+                    Ok(State::Running)
+                }
+                _ => Err(Error::InvalidInstruction(interpreter.program_counter)),
+            }
+        }
+
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let op = TypeR {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+            func: OpAmo::CUSTOM_FUNC + 5,
+        };
+        *interpreter.registers.cpu.get_mut(1).unwrap() = 1;
+        *interpreter.registers.cpu.get_mut(2).unwrap() = 6;
+        *interpreter.registers.cpu.get_mut(3).unwrap() = 7;
+
+        // No handler
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Err(Error::InvalidInstruction(0)));
+
+        interpreter.set_custom_instruction_handler(Some(handler));
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Ok(State::Running));
+        assert_eq!(interpreter.registers.cpu.get(1), Ok(43));
+        assert_eq!(interpreter.program_counter, 4);
+
+        // Unknown custom instruction
+        let op = TypeR {
+            func: OpAmo::CUSTOM_FUNC,
+            ..op
+        };
+        let result = OpAmo::decode(op.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Err(Error::InvalidInstruction(4)));
+        assert_eq!(interpreter.program_counter, 4);
+    }
+
+    fn lr_sc(interpreter: &mut Interpreter<'_, SliceMemory<'_>>, func: u16, address: u32) -> i32 {
+        let amo = TypeR {
+            rd: 1,
+            rs1: 3,
+            rs2: 2,
+            func,
+        };
+        *interpreter.registers.cpu.get_mut(2).unwrap() = 7;
+        *interpreter.registers.cpu.get_mut(3).unwrap() = address as i32;
+
+        let result = OpAmo::decode(amo.to_embive()).execute(interpreter);
+        assert_eq!(result, Ok(State::Running));
+        interpreter.registers.cpu.get(1).unwrap()
+    }
+
+    fn store(interpreter: &mut Interpreter<'_, SliceMemory<'_>>, func: u8, address: u32) {
+        let store = TypeI {
+            rd_rs2: 0,
+            rs1: 4,
+            imm: 0,
+            func,
+        };
+        *interpreter.registers.cpu.get_mut(4).unwrap() = address as i32;
+
+        let result = LoadStore::decode(store.to_embive()).execute(interpreter);
+        assert_eq!(result, Ok(State::Running));
+    }
+
+    #[test]
+    fn test_sc_reservation_rules() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // Store to the reserved word (same value) invalidates the reservation
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET);
+        store(&mut interpreter, LoadStore::SW_FUNC, RAM_OFFSET);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET), 1);
+
+        // Store overlapping the reserved word
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET + 4);
+        store(&mut interpreter, LoadStore::SB_FUNC, RAM_OFFSET + 7);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET + 4), 1);
+
+        // AMO to the reserved word
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET);
+        lr_sc(&mut interpreter, OpAmo::AMOOR_FUNC, RAM_OFFSET);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET), 1);
+
+        // SC to another address fails, and consumes the reservation
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET + 4), 1);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET), 1);
+
+        // Store outside the reservation set (4-byte granule)
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET);
+        store(&mut interpreter, LoadStore::SW_FUNC, RAM_OFFSET + 4);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET), 0);
+        assert_eq!(interpreter.memory.load_u32(RAM_OFFSET), Ok(7));
+
+        // Store to another word of a 16-byte granule
+        interpreter.set_config(Config::default().with_reservation_granule(16));
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET);
+        store(&mut interpreter, LoadStore::SH_FUNC, RAM_OFFSET + 12);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET), 1);
+
+        lr_sc(&mut interpreter, OpAmo::LR_FUNC, RAM_OFFSET);
+        store(&mut interpreter, LoadStore::SW_FUNC, RAM_OFFSET + 16);
+        assert_eq!(lr_sc(&mut interpreter, OpAmo::SC_FUNC, RAM_OFFSET), 0);
+    }
 }
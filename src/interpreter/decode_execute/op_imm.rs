@@ -1,6 +1,5 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::OpImm;
-use crate::interpreter::utils::likely;
 use crate::interpreter::{memory::Memory, Error, Interpreter, State};
 
 use super::Execute;
@@ -11,29 +10,28 @@ impl<M: Memory> Execute<M> for OpImm {
         let rs1 = interpreter.registers.cpu.get(self.0.rs1)?;
         let imm = self.0.imm;
 
-        if likely(self.0.rd_rs2 != 0) {
-            // rd = 0 means its a HINT instruction, just ignore it.
-            let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-            *rd = match self.0.func {
-                Self::ADDI_FUNC => rs1.wrapping_add(imm),
-                Self::SLLI_FUNC => rs1.wrapping_shl(imm as u32 & 0b11111),
-                Self::SLTI_FUNC => (rs1 < imm) as u8 as i32,
-                Self::SLTIU_FUNC => ((rs1 as u32) < (imm as u32)) as u8 as i32,
-                Self::XORI_FUNC => rs1 ^ imm,
-                Self::SRLI_SRAI_FUNC => {
-                    if (imm & (0b1 << 10)) != 0 {
-                        // Sra (Arithmetic shift right, fill with sign bit)
-                        rs1.wrapping_shr(imm as u32 & 0b11111)
-                    } else {
-                        // Srl (Logical shift right, fill with zero)
-                        (rs1 as u32).wrapping_shr(imm as u32 & 0b11111) as i32
-                    }
+        let result = match self.0.func {
+            Self::ADDI_FUNC => rs1.wrapping_add(imm),
+            Self::SLLI_FUNC => rs1.wrapping_shl(imm as u32 & 0b11111),
+            Self::SLTI_FUNC => (rs1 < imm) as u8 as i32,
+            Self::SLTIU_FUNC => ((rs1 as u32) < (imm as u32)) as u8 as i32,
+            Self::XORI_FUNC => rs1 ^ imm,
+            Self::SRLI_SRAI_FUNC => {
+                if (imm & (0b1 << 10)) != 0 {
+                    // Sra (Arithmetic shift right, fill with sign bit)
+                    rs1.wrapping_shr(imm as u32 & 0b11111)
+                } else {
+                    // Srl (Logical shift right, fill with zero)
+                    (rs1 as u32).wrapping_shr(imm as u32 & 0b11111) as i32
                 }
-                Self::ORI_FUNC => rs1 | imm,
-                Self::ANDI_FUNC => rs1 & imm,
-                _ => return Err(Error::InvalidInstruction(interpreter.program_counter)),
-            };
-        }
+            }
+            Self::ORI_FUNC => rs1 | imm,
+            Self::ANDI_FUNC => rs1 & imm,
+            _ => return Err(Error::InvalidInstruction(interpreter.program_counter)),
+        };
+
+        // rd = 0 means its a HINT instruction, the write is discarded.
+        interpreter.registers.cpu.set(self.0.rd_rs2, result)?;
 
         // Go to next instruction
         interpreter.program_counter = interpreter
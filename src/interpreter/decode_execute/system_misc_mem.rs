@@ -1,27 +1,119 @@
 use crate::instruction::embive::InstructionImpl;
 use crate::instruction::embive::SystemMiscMem;
-use crate::interpreter::utils::likely;
-use crate::interpreter::{memory::Memory, registers::CSOperation, Error, Interpreter, State};
+use crate::interpreter::registers::CPURegister;
+use crate::interpreter::utils::{likely, unlikely};
+use crate::interpreter::{
+    memory::Memory, privilege::ECALL_FROM_USER, registers::CSOperation, Error, ExitReason,
+    Interpreter, State, CAPABILITIES_SYSCALL, EXIT_SYSCALL, PANIC_SYSCALL, RANDOM_SYSCALL,
+    SLEEP_SYSCALL, SYSCALL_ERROR_PERMISSION_DENIED, YIELD_SYSCALL,
+};
+
+#[cfg(feature = "resource-limits")]
+use crate::interpreter::Resource;
 
 use super::Execute;
 
+/// CSR address bits holding the lowest privilege level allowed to access it (0 for user-level CSRs).
+const CSR_PRIVILEGE: u16 = 0b11 << 8;
+/// CSR address bits marking read-only CSRs (when all set).
+const CSR_READ_ONLY: u16 = 0b11 << 10;
+
 impl<M: Memory> Execute<M> for SystemMiscMem {
     #[inline(always)]
     fn execute(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
         let ret = if likely(self.0.func == Self::MISC_FUNC) {
             match self.0.imm {
-                Self::ECALL_IMM => Ok(State::Called),  // Syscall (ecall)
-                Self::EBREAK_IMM => Ok(State::Halted), // Halt the execution (ebreak)
+                Self::ECALL_IMM if unlikely(interpreter.registers.control_status.user_mode()) => {
+                    // Environment call from user mode (trap to the machine mode kernel)
+                    return interpreter.exception(ECALL_FROM_USER, 0);
+                }
+                Self::WFI_IMM | Self::MRET_IMM | Self::SFENCEVMA_IMM
+                    if unlikely(interpreter.registers.control_status.user_mode()) =>
+                {
+                    // Privileged instruction
+                    return Err(Error::IllegalInstruction(interpreter.program_counter));
+                }
+                Self::ECALL_IMM => {
+                    #[cfg(feature = "resource-limits")]
+                    if let Some(state) = interpreter.use_resource(Resource::Syscalls, 1) {
+                        // Syscall limit reached, not executed
+                        return Ok(state);
+                    }
+
+                    // Environment calls are traps, invalidating the memory reservation
+                    interpreter.memory_reservation = None;
+                    #[cfg(feature = "syscall-flood")]
+                    interpreter.count_syscall()?;
+
+                    let cpu = &interpreter.registers.cpu;
+                    if unlikely(cpu.inner[CPURegister::A7 as usize] == PANIC_SYSCALL) {
+                        // Guest panic (message address and length)
+                        Ok(State::Panicked {
+                            msg_ptr: cpu.inner[CPURegister::A0 as usize] as u32,
+                            len: cpu.inner[CPURegister::A1 as usize] as u32,
+                        })
+                    } else if unlikely(cpu.inner[CPURegister::A7 as usize] == EXIT_SYSCALL) {
+                        // Guest exit (exit code)
+                        Ok(State::Halted {
+                            reason: ExitReason::ExitSyscall(cpu.inner[CPURegister::A0 as usize]),
+                        })
+                    } else if unlikely(cpu.inner[CPURegister::A7 as usize] == SLEEP_SYSCALL) {
+                        // Guest sleep (timeout in ticks, a0 low and a1 high)
+                        let low = cpu.inner[CPURegister::A0 as usize] as u32 as u64;
+                        let high = cpu.inner[CPURegister::A1 as usize] as u32 as u64;
+                        interpreter.wait_timeout = Some((high << 32) | low);
+                        interpreter.registers.cpu.set_a0(0);
+                        Ok(State::Waiting)
+                    } else if unlikely(cpu.inner[CPURegister::A7 as usize] == YIELD_SYSCALL) {
+                        // Guest yield (returns the host backpressure)
+                        let backpressure = interpreter.yield_backpressure as i32;
+                        interpreter.registers.cpu.set_a0(0);
+                        interpreter.registers.cpu.inner[CPURegister::A1 as usize] = backpressure;
+                        Ok(State::Yielded)
+                    } else if unlikely(cpu.inner[CPURegister::A7 as usize] == RANDOM_SYSCALL) {
+                        // Guest entropy request (buffer address and length)
+                        interpreter.random_syscall();
+                        Ok(State::Running)
+                    } else if unlikely(cpu.inner[CPURegister::A7 as usize] == CAPABILITIES_SYSCALL)
+                    {
+                        // Guest capabilities query (buffer address and length)
+                        interpreter.capabilities_syscall();
+                        Ok(State::Running)
+                    } else if unlikely(interpreter.syscall_permissions.is_some())
+                        && !interpreter.syscall_permitted(cpu.inner[CPURegister::A7 as usize])
+                    {
+                        // Host syscall not permitted (or over quota), never reaches the host
+                        interpreter
+                            .registers
+                            .cpu
+                            .set_a0(SYSCALL_ERROR_PERMISSION_DENIED);
+                        interpreter.registers.cpu.set_a1(0);
+                        Ok(State::Running)
+                    } else {
+                        interpreter.syscall_pending = true;
+                        Ok(State::Called) // Syscall (ecall)
+                    }
+                }
+                Self::EBREAK_IMM => return interpreter.ebreak(Self::size() as u32), // Halt, break or trap
                 Self::FENCEI_IMM => {
-                    // Fencing isn't applicable to this implementation.
-                    // This is a nop.
+                    // Instructions are fetched from memory every time (no decoded cache),
+                    // so code writes are always visible. This is a nop.
                     Ok(State::Running)
                 }
                 Self::WFI_IMM => Ok(State::Waiting), // Wait for interrupt (wfi)
+                Self::SFENCEVMA_IMM => {
+                    // Flush the whole translation cache (the virtual address in rs1 is ignored).
+                    // Without the `mmu` feature there is no address translation, this is a nop.
+                    #[cfg(feature = "mmu")]
+                    interpreter.mmu.flush();
+                    Ok(State::Running)
+                }
                 Self::MRET_IMM => {
                     // Return from machine-mode trap
                     interpreter.program_counter =
                         interpreter.registers.control_status.trap_return();
+                    #[cfg(feature = "interrupt-latency")]
+                    interpreter.latency_trap_return();
                     return Ok(State::Running); // Do not increment the program counter
                 }
                 _ => return Err(Error::InvalidInstruction(interpreter.program_counter)),
@@ -67,16 +159,27 @@ impl<M: Memory> Execute<M> for SystemMiscMem {
                 _ => return Err(Error::InvalidInstruction(interpreter.program_counter)),
             };
 
-            let res = interpreter
-                .registers
-                .control_status
-                .operation(op, (self.0.imm & 0b1111_1111_1111) as u16)?;
+            let addr = (self.0.imm & 0b1111_1111_1111) as u16;
+            if unlikely(
+                interpreter.registers.control_status.user_mode() && addr & CSR_PRIVILEGE != 0,
+            ) {
+                // Privileged CSR (not a user-level one)
+                return Err(Error::IllegalInstruction(interpreter.program_counter));
+            }
+            if unlikely(addr & CSR_READ_ONLY == CSR_READ_ONLY && op.is_some()) {
+                // Write to a read-only CSR (e.g. `mvendorid`, `time`)
+                return Err(Error::IllegalInstruction(interpreter.program_counter));
+            }
+            let res = interpreter.csr_operation(op, addr)?;
 
-            if self.0.rd_rs2 != 0 {
-                let rd = interpreter.registers.cpu.get_mut(self.0.rd_rs2)?;
-                *rd = res as i32;
+            // Address space changed, flush the translation cache
+            #[cfg(feature = "mmu")]
+            if addr == crate::interpreter::mmu::SATP_ADDR && op.is_some() {
+                interpreter.mmu.flush();
             }
 
+            interpreter.registers.cpu.set(self.0.rd_rs2, res as i32)?;
+
             Ok(State::Running)
         };
 
@@ -95,7 +198,7 @@ mod tests {
     use crate::{
         format::{Format, TypeI},
         instruction::embive::InstructionImpl,
-        interpreter::memory::SliceMemory,
+        interpreter::memory::{SliceMemory, RAM_OFFSET},
     };
 
     #[test]
@@ -110,7 +213,12 @@ mod tests {
         };
 
         let result = SystemMiscMem::decode(misc_mem.to_embive()).execute(&mut interpreter);
-        assert_eq!(result, Ok(State::Halted));
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
         assert_eq!(interpreter.program_counter, SystemMiscMem::size() as u32);
     }
 
@@ -126,10 +234,87 @@ mod tests {
             imm: SystemMiscMem::ECALL_IMM,
             func: SystemMiscMem::MISC_FUNC,
         };
+        interpreter.memory_reservation = Some((RAM_OFFSET, 0));
 
         let result = SystemMiscMem::decode(misc_mem.to_embive()).execute(&mut interpreter);
         assert_eq!(result, Ok(State::Called));
         assert_eq!(interpreter.program_counter, SystemMiscMem::size() as u32);
+        assert_eq!(interpreter.memory_reservation, None);
+    }
+
+    #[test]
+    fn test_ecall_yield() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.set_yield_backpressure(3);
+        *interpreter.registers.cpu.get_mut(17).unwrap() = YIELD_SYSCALL; // a7
+        *interpreter.registers.cpu.get_mut(10).unwrap() = -1; // a0
+
+        let misc_mem = TypeI {
+            rd_rs2: 0,
+            rs1: 0,
+            imm: SystemMiscMem::ECALL_IMM,
+            func: SystemMiscMem::MISC_FUNC,
+        };
+
+        let result = SystemMiscMem::decode(misc_mem.to_embive()).execute(&mut interpreter);
+        assert_eq!(result, Ok(State::Yielded));
+        assert_eq!(interpreter.program_counter, SystemMiscMem::size() as u32);
+        assert_eq!(interpreter.pending_syscall(), None);
+        assert_eq!(interpreter.registers.cpu.a0(), 0);
+        assert_eq!(interpreter.registers.cpu.get(11), Ok(3));
+    }
+
+    #[test]
+    fn test_ecall_exit() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        *interpreter.registers.cpu.get_mut(17).unwrap() = EXIT_SYSCALL; // a7
+        *interpreter.registers.cpu.get_mut(10).unwrap() = -3; // a0
+
+        let misc_mem = TypeI {
+            rd_rs2: 0,
+            rs1: 0,
+            imm: SystemMiscMem::ECALL_IMM,
+            func: SystemMiscMem::MISC_FUNC,
+        };
+
+        let result = SystemMiscMem::decode(misc_mem.to_embive()).execute(&mut interpreter);
+        assert_eq!(
+            result,
+            Ok(State::Halted {
+                reason: ExitReason::ExitSyscall(-3)
+            })
+        );
+        assert_eq!(ExitReason::ExitSyscall(-3).code(), Some(-3));
+        assert_eq!(ExitReason::Ebreak.code(), None);
+        assert_eq!(interpreter.pending_syscall(), None);
+    }
+
+    #[test]
+    fn test_ecall_panic() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        *interpreter.registers.cpu.get_mut(17).unwrap() = PANIC_SYSCALL; // a7
+        *interpreter.registers.cpu.get_mut(10).unwrap() = 0x1234; // a0
+        *interpreter.registers.cpu.get_mut(11).unwrap() = 10; // a1
+
+        let misc_mem = TypeI {
+            rd_rs2: 0,
+            rs1: 0,
+            imm: SystemMiscMem::ECALL_IMM,
+            func: SystemMiscMem::MISC_FUNC,
+        };
+
+        let result = SystemMiscMem::decode(misc_mem.to_embive()).execute(&mut interpreter);
+        assert_eq!(
+            result,
+            Ok(State::Panicked {
+                msg_ptr: 0x1234,
+                len: 10
+            })
+        );
+        assert_eq!(interpreter.program_counter, SystemMiscMem::size() as u32);
     }
 
     #[test]
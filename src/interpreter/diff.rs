@@ -0,0 +1,366 @@
+//! State Diff Module
+//!
+//! Structured differences between two interpreter states, for test assertions and golden-state debugging:
+//! - Registers: [`Registers::diff`] and [`CPURegisters::diff`] yield a [`RegisterChange`] per differing register.
+//! - Memory: [`diff_bytes`] and [`diff_memory`] yield a [`MemoryChange`] per contiguous range of differing bytes.
+//!
+//! Changes implement [`core::fmt::Display`] (register ABI names, hexadecimal values).
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::{
+//!     diff::{diff_bytes, MemoryChange, RegisterChange},
+//!     registers::{CPURegister, Registers},
+//! };
+//!
+//! let old = Registers::default();
+//! let mut new = old;
+//! new.cpu.set(CPURegister::A0, 42).unwrap();
+//!
+//! let mut changes = old.diff(&new);
+//! assert_eq!(changes.next(), Some(RegisterChange::Cpu { register: 10, old: 0, new: 42 }));
+//! assert_eq!(changes.next(), None);
+//!
+//! let changes: Vec<_> = diff_bytes(0x100, &[1, 2, 3, 4], &[1, 0, 0, 4]).collect();
+//! assert_eq!(changes, [MemoryChange { address: 0x101, old: &[2, 3], new: &[0, 0] }]);
+//! ```
+use core::fmt;
+
+use super::{
+    memory::Memory,
+    registers::{CPURegisters, Registers, CSR_SNAPSHOT_WORDS},
+    Error,
+};
+
+/// ABI names of the CPU registers, by index.
+const CPU_REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Names of the control and status fields, by snapshot word (check [`crate::interpreter::snapshot`]).
+const CSR_NAMES: [&str; CSR_SNAPSHOT_WORDS] = [
+    "mtvec",
+    "mscratch",
+    "mepc",
+    "mcause",
+    "mtval",
+    "status",
+    "software value",
+    "instret",
+    "instreth",
+    "satp",
+    "cycle",
+    "cycleh",
+    #[cfg(feature = "pmp")]
+    "pmpcfg0",
+    #[cfg(feature = "pmp")]
+    "pmpcfg1",
+    #[cfg(feature = "pmp")]
+    "pmpcfg2",
+    #[cfg(feature = "pmp")]
+    "pmpcfg3",
+    #[cfg(feature = "pmp")]
+    "pmpaddr0",
+    #[cfg(feature = "pmp")]
+    "pmpaddr1",
+    #[cfg(feature = "pmp")]
+    "pmpaddr2",
+    #[cfg(feature = "pmp")]
+    "pmpaddr3",
+    #[cfg(feature = "pmp")]
+    "pmpaddr4",
+    #[cfg(feature = "pmp")]
+    "pmpaddr5",
+    #[cfg(feature = "pmp")]
+    "pmpaddr6",
+    #[cfg(feature = "pmp")]
+    "pmpaddr7",
+    #[cfg(feature = "pmp")]
+    "pmpaddr8",
+    #[cfg(feature = "pmp")]
+    "pmpaddr9",
+    #[cfg(feature = "pmp")]
+    "pmpaddr10",
+    #[cfg(feature = "pmp")]
+    "pmpaddr11",
+    #[cfg(feature = "pmp")]
+    "pmpaddr12",
+    #[cfg(feature = "pmp")]
+    "pmpaddr13",
+    #[cfg(feature = "pmp")]
+    "pmpaddr14",
+    #[cfg(feature = "pmp")]
+    "pmpaddr15",
+    "misa",
+];
+
+/// A register that differs between two states.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RegisterChange {
+    /// CPU register.
+    Cpu {
+        /// Register index (from `0` to `31`, check [`super::registers::CPURegister`]).
+        register: u8,
+        /// Old value.
+        old: i32,
+        /// New value.
+        new: i32,
+    },
+    /// Control and status register (machine state, as saved by [`crate::interpreter::snapshot`]).
+    ControlStatus {
+        /// CSR name (`status` holds the interrupt flags, privilege mode and `mstatus` bits, packed).
+        name: &'static str,
+        /// Old value.
+        old: u32,
+        /// New value.
+        new: u32,
+    },
+}
+
+impl fmt::Display for RegisterChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RegisterChange::Cpu { register, old, new } => write!(
+                f,
+                "{}: {old:#010x} ({old}) -> {new:#010x} ({new})",
+                CPU_REGISTER_NAMES[register as usize]
+            ),
+            RegisterChange::ControlStatus { name, old, new } => {
+                write!(f, "{name}: {old:#010x} -> {new:#010x}")
+            }
+        }
+    }
+}
+
+/// A contiguous range of bytes that differs between two memory states.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MemoryChange<'a> {
+    /// Start address of the range.
+    pub address: u32,
+    /// Old bytes.
+    pub old: &'a [u8],
+    /// New bytes (same length as `old`).
+    pub new: &'a [u8],
+}
+
+impl MemoryChange<'_> {
+    /// Get the range size, in bytes.
+    pub fn len(&self) -> usize {
+        self.old.len()
+    }
+
+    /// Check if the range is empty (never true for yielded changes).
+    pub fn is_empty(&self) -> bool {
+        self.old.is_empty()
+    }
+}
+
+impl fmt::Display for MemoryChange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}:", self.address)?;
+        for byte in self.old {
+            write!(f, " {byte:02x}")?;
+        }
+        write!(f, " ->")?;
+        for byte in self.new {
+            write!(f, " {byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl CPURegisters {
+    /// Compare with another CPU state.
+    ///
+    /// Arguments:
+    /// - `other`: New state.
+    ///
+    /// Returns an iterator over the differing registers ([`RegisterChange::Cpu`]), in index order.
+    pub fn diff(&self, other: &CPURegisters) -> impl Iterator<Item = RegisterChange> {
+        let (old, new) = (self.inner, other.inner);
+        (0..old.len()).filter_map(move |index| {
+            (old[index] != new[index]).then_some(RegisterChange::Cpu {
+                register: index as u8,
+                old: old[index],
+                new: new[index],
+            })
+        })
+    }
+}
+
+impl Registers {
+    /// Compare with another register state (check [`crate::interpreter::diff`]).
+    ///
+    /// Host settings (machine IDs, custom CSR handler, virtual time ratio) aren't compared.
+    ///
+    /// Arguments:
+    /// - `other`: New state.
+    ///
+    /// Returns an iterator over the differing CPU registers, then the differing control and status registers.
+    pub fn diff(&self, other: &Registers) -> impl Iterator<Item = RegisterChange> {
+        let (old, new) = (
+            self.control_status.snapshot(),
+            other.control_status.snapshot(),
+        );
+        let control_status = (0..CSR_SNAPSHOT_WORDS).filter_map(move |index| {
+            (old[index] != new[index]).then_some(RegisterChange::ControlStatus {
+                name: CSR_NAMES[index],
+                old: old[index],
+                new: new[index],
+            })
+        });
+
+        self.cpu.diff(&other.cpu).chain(control_status)
+    }
+}
+
+/// Compare two memory states.
+///
+/// Only the common length of `old` and `new` is compared.
+///
+/// Arguments:
+/// - `address`: Start address of both states.
+/// - `old`: Old bytes.
+/// - `new`: New bytes.
+///
+/// Returns an iterator over the contiguous ranges of differing bytes, in ascending address order.
+pub fn diff_bytes<'a>(
+    address: u32,
+    old: &'a [u8],
+    new: &'a [u8],
+) -> impl Iterator<Item = MemoryChange<'a>> {
+    let len = old.len().min(new.len());
+    let mut index = 0;
+
+    core::iter::from_fn(move || {
+        // Find the next differing byte
+        index += (index..len).position(|i| old[i] != new[i])?;
+        let start = index;
+
+        // Merge the following differing bytes
+        while index < len && old[index] != new[index] {
+            index += 1;
+        }
+
+        Some(MemoryChange {
+            address: address.wrapping_add(start as u32),
+            old: &old[start..index],
+            new: &new[start..index],
+        })
+    })
+}
+
+/// Compare expected bytes against guest memory (code or RAM).
+///
+/// Arguments:
+/// - `memory`: Guest memory.
+/// - `address`: Start address of the range.
+/// - `expected`: Expected bytes (old state of the changes).
+///
+/// Returns:
+/// - `Ok(impl Iterator<Item = MemoryChange>)`: The differing ranges (check [`diff_bytes`]), `new` is the guest memory.
+/// - `Err(Error)`: The memory range is out of bounds.
+pub fn diff_memory<'a, M: Memory>(
+    memory: &'a mut M,
+    address: u32,
+    expected: &'a [u8],
+) -> Result<impl Iterator<Item = MemoryChange<'a>>, Error> {
+    let actual = memory.load_bytes(address, expected.len())?;
+    Ok(diff_bytes(address, expected, actual))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        registers::{CPURegister, CSOperation},
+    };
+
+    #[test]
+    fn test_register_diff() {
+        let old = Registers::default();
+        let mut new = old;
+        new.cpu.set(CPURegister::SP, -4).unwrap();
+        new.cpu.set(CPURegister::T6, 1).unwrap();
+        new.control_status
+            .operation(Some(CSOperation::Write(0x100)), 0x305)
+            .unwrap();
+
+        let mut changes = old.diff(&new);
+        let change = changes.next().unwrap();
+        assert_eq!(
+            change,
+            RegisterChange::Cpu {
+                register: 2,
+                old: 0,
+                new: -4
+            }
+        );
+        assert_eq!(format!("{change}"), "sp: 0x00000000 (0) -> 0xfffffffc (-4)");
+        assert_eq!(
+            changes.next(),
+            Some(RegisterChange::Cpu {
+                register: 31,
+                old: 0,
+                new: 1
+            })
+        );
+        let change = changes.next().unwrap();
+        assert_eq!(format!("{change}"), "mtvec: 0x00000000 -> 0x00000100");
+        assert_eq!(changes.next(), None);
+
+        assert_eq!(new.diff(&new).next(), None);
+    }
+
+    #[test]
+    fn test_bytes_diff() {
+        let old = [0, 1, 2, 3, 4, 5, 6, 7];
+        let new = [9, 1, 2, 9, 9, 5, 6, 9, 9];
+        let changes: Vec<_> = diff_bytes(RAM_OFFSET, &old, &new).collect();
+        assert_eq!(
+            changes,
+            [
+                MemoryChange {
+                    address: RAM_OFFSET,
+                    old: &[0],
+                    new: &[9]
+                },
+                MemoryChange {
+                    address: RAM_OFFSET + 3,
+                    old: &[3, 4],
+                    new: &[9, 9]
+                },
+                MemoryChange {
+                    address: RAM_OFFSET + 7,
+                    old: &[7],
+                    new: &[9]
+                },
+            ]
+        );
+        assert_eq!(changes[1].len(), 2);
+        assert_eq!(format!("{}", changes[1]), "0x80000003: 03 04 -> 09 09");
+        assert_eq!(diff_bytes(0, &old, &old).next(), None);
+    }
+
+    #[test]
+    fn test_memory_diff() {
+        let mut ram = [0, 0, 0x2a, 0];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let changes: Vec<_> = diff_memory(&mut memory, RAM_OFFSET, &[0; 4])
+            .unwrap()
+            .collect();
+        assert_eq!(
+            changes,
+            [MemoryChange {
+                address: RAM_OFFSET + 2,
+                old: &[0],
+                new: &[0x2a]
+            }]
+        );
+
+        assert!(diff_memory(&mut memory, RAM_OFFSET, &[0; 5]).is_err());
+    }
+}
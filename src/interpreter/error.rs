@@ -2,15 +2,59 @@
 
 use core::fmt::{Display, Formatter, Result};
 
+#[cfg(feature = "resource-limits")]
+use super::Resource;
+use super::{PolicyClass, State};
+
+/// Memory access kind of a [`Error::MemoryFault`].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum MemoryAccess {
+    /// Load instruction (`lb`, `lh`, `lw`, `lbu`, `lhu`, `c.lw`, `c.lwsp`).
+    Load,
+    /// Store instruction (`sb`, `sh`, `sw`, `c.sw`, `c.swsp`).
+    Store,
+    /// Atomic instruction (`lr.w`, `sc.w`, `amo*.w`).
+    Atomic,
+}
+
+impl Display for MemoryAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            MemoryAccess::Load => write!(f, "load"),
+            MemoryAccess::Store => write!(f, "store"),
+            MemoryAccess::Atomic => write!(f, "atomic"),
+        }
+    }
+}
+
 /// Embive Interpreter Error
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
-    /// Memory address is out of bounds. The memory address is provided.
-    InvalidMemoryAddress(u32),
+    /// Memory access is out of bounds (raised by the [`crate::interpreter::memory::Memory`] implementation).
+    InvalidMemoryAddress {
+        /// Start address of the access.
+        address: u32,
+        /// Access width, in bytes.
+        len: usize,
+    },
     /// Memory access length is invalid. The length is provided.
     InvalidMemoryAccessLength(usize),
-    /// Program counter is out of bounds. The program counter is provided.
+    /// Memory access by the interpreted code is out of bounds.
+    MemoryFault {
+        /// Program counter of the faulting instruction.
+        pc: u32,
+        /// Kind of access.
+        access: MemoryAccess,
+        /// Start address of the access.
+        address: u32,
+        /// Access width, in bytes.
+        len: usize,
+    },
+    /// Program counter is out of bounds (instruction fetch failed). The program counter is provided.
     InvalidProgramCounter(u32),
+    /// Instruction fetch from RAM is not allowed (check [`crate::interpreter::Config::ram_execution`]). The program counter is provided.
+    ExecuteFault(u32),
     /// Instruction is invalid. The program counter is provided.
     InvalidInstruction(u32),
     /// Control and Status Register is invalid or not supported. The CSR address is provided.
@@ -19,16 +63,238 @@ pub enum Error {
     InvalidCPURegister(u8),
     /// Instruction is illegal. The program counter is provided.
     IllegalInstruction(u32),
+    /// Instruction class denied by the host (check [`crate::interpreter::Config::instruction_policy`]).
+    /// Returned to the host, also in user mode (no trap).
+    InstructionDenied {
+        /// Program counter of the denied instruction.
+        pc: u32,
+        /// Denied instruction class.
+        class: PolicyClass,
+    },
     /// Interrupt not enabled by interpreted code (CSR `mie` bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`]).
     InterruptNotEnabled,
+    /// Interrupt queue is full (check [`crate::interpreter::INTERRUPT_QUEUE_CAPACITY`]).
+    #[cfg(feature = "interrupt-queue")]
+    InterruptQueueFull,
+    /// All hooks are in use (check [`crate::interpreter::HOOK_CAPACITY`]).
+    #[cfg(feature = "hooks")]
+    HookTableFull,
     /// No syscall function is set.
     NoSyscallFunction,
+    /// No syscall is pending (check [`crate::interpreter::Interpreter::pending_syscall`]).
+    NoPendingSyscall,
+    /// Too many arguments for a function call (check [`crate::interpreter::CALL_ARGS`]). The number of arguments is provided.
+    TooManyArguments(usize),
+    /// Called function stopped before returning (check [`crate::interpreter::Interpreter::call`]). The state is provided.
+    CallInterrupted(State),
+    /// Address is in the middle of an instruction (check [`crate::interpreter::Interpreter::check_instruction_start`]).
+    NotInstructionStart {
+        /// Checked address.
+        address: u32,
+        /// Start address of the instruction containing it.
+        start: u32,
+    },
+    /// Interpreter stayed too long in a state (check [`crate::interpreter::watchdog::Watchdog`]). The state is provided.
+    WatchdogTimeout(State),
+    /// All timers of a timer wheel are in use (check [`crate::interpreter::timer_wheel::TimerWheel`]).
+    #[cfg(feature = "interrupt-queue")]
+    TimerWheelFull,
+    /// All watches of a watch list are in use (check [`crate::interpreter::watch::WatchList`]).
+    WatchListFull,
+    /// Code region modified since it was hashed (check [`crate::interpreter::integrity::CodeIntegrity`]).
+    /// The start address of the modified page is provided.
+    CodeModified(u32),
+    /// RAM execution is enabled, executed code can't be verified (check [`crate::interpreter::integrity::CodeIntegrity::run`]).
+    RamExecutionEnabled,
+    /// The interpreted code issued too many syscalls in a tight loop (check [`crate::interpreter::Config::syscall_burst_limit`]).
+    /// The program counter of the `ecall` is provided, running again executes it.
+    #[cfg(feature = "syscall-flood")]
+    SyscallFlood(u32),
+    /// Interrupt not taken, the resource limit was reached (check [`crate::interpreter::Config::resource_limits`]).
+    #[cfg(feature = "resource-limits")]
+    ResourceLimit(Resource),
+    /// No valid mapping for a virtual address (check [`crate::interpreter::mmu`]).
+    /// Delivered to the interpreted code as a trap while running, only returned by direct calls (e.g. [`crate::interpreter::Interpreter::fetch`]).
+    #[cfg(feature = "mmu")]
+    PageFault {
+        /// Exception code (`mcause`, e.g. [`crate::interpreter::mmu::LOAD_PAGE_FAULT`]).
+        code: u32,
+        /// Faulting virtual address.
+        address: u32,
+    },
+    /// Physical memory protection violation (check [`crate::interpreter::pmp`]).
+    /// Delivered to the interpreted code as a trap while running, only returned by direct calls (e.g. [`crate::interpreter::Interpreter::fetch`]).
+    #[cfg(feature = "pmp")]
+    AccessFault {
+        /// Exception code (`mcause`, e.g. [`crate::interpreter::privilege::LOAD_ACCESS_FAULT`]).
+        code: u32,
+        /// Faulting (virtual) address.
+        address: u32,
+    },
+}
+
+impl Error {
+    /// Add the faulting instruction context to a memory error.
+    ///
+    /// Arguments:
+    /// - `pc`: Program counter of the instruction.
+    /// - `access`: Kind of access done by the instruction.
+    #[cold]
+    pub(crate) fn in_instruction(self, pc: u32, access: MemoryAccess) -> Self {
+        match self {
+            Error::InvalidMemoryAddress { address, len } => Error::MemoryFault {
+                pc,
+                access,
+                address,
+                len,
+            },
+            error => error,
+        }
+    }
 }
 
 impl core::error::Error for Error {}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{self:?}")
+        match self {
+            Error::InvalidMemoryAddress { address, len } => write!(
+                f,
+                "{len}-byte memory access at {address:#010x} is out of bounds"
+            ),
+            Error::InvalidMemoryAccessLength(len) => {
+                write!(f, "invalid memory access length ({len} bytes)")
+            }
+            Error::MemoryFault {
+                pc,
+                access,
+                address,
+                len,
+            } => write!(
+                f,
+                "{len}-byte {access} at {address:#010x} is out of bounds (pc: {pc:#010x})"
+            ),
+            Error::InvalidProgramCounter(pc) => write!(
+                f,
+                "program counter {pc:#010x} is outside the code region (bad jump or entry point?)"
+            ),
+            Error::ExecuteFault(pc) => write!(
+                f,
+                "instruction fetch from RAM at {pc:#010x} is not allowed (RAM execution disabled)"
+            ),
+            Error::InvalidInstruction(pc) => write!(
+                f,
+                "invalid instruction at {pc:#010x} (was the code transpiled?)"
+            ),
+            Error::InvalidCSRegister(addr) => {
+                write!(f, "unsupported control and status register {addr:#05x}")
+            }
+            Error::InvalidCPURegister(index) => write!(f, "invalid CPU register x{index}"),
+            Error::IllegalInstruction(pc) => write!(
+                f,
+                "illegal instruction at {pc:#010x} (extension disabled in the configuration?)"
+            ),
+            Error::InstructionDenied { pc, class } => write!(
+                f,
+                "instruction at {pc:#010x} denied by the instruction policy ({class:?})"
+            ),
+            Error::InterruptNotEnabled => write!(
+                f,
+                "interrupt not enabled by the interpreted code (mstatus.MIE and mie)"
+            ),
+            #[cfg(feature = "interrupt-queue")]
+            Error::InterruptQueueFull => write!(
+                f,
+                "interrupt queue is full ({} interrupts pending)",
+                super::INTERRUPT_QUEUE_CAPACITY
+            ),
+            #[cfg(feature = "hooks")]
+            Error::HookTableFull => {
+                write!(f, "hook table is full ({} hooks set)", super::HOOK_CAPACITY)
+            }
+            Error::NoSyscallFunction => write!(f, "no syscall function set"),
+            Error::NoPendingSyscall => write!(f, "no syscall pending (state is not Called)"),
+            Error::TooManyArguments(count) => write!(
+                f,
+                "too many function arguments ({count}, at most {} are supported)",
+                super::CALL_ARGS
+            ),
+            Error::CallInterrupted(state) => {
+                write!(f, "called function stopped before returning ({state:?})")
+            }
+            Error::NotInstructionStart { address, start } => write!(
+                f,
+                "address {address:#010x} is in the middle of the instruction at {start:#010x}"
+            ),
+            Error::WatchdogTimeout(state) => write!(f, "watchdog timeout ({state:?})"),
+            #[cfg(feature = "interrupt-queue")]
+            Error::TimerWheelFull => write!(f, "timer wheel is full"),
+            Error::WatchListFull => write!(f, "watch list is full"),
+            Error::CodeModified(address) => {
+                write!(f, "code page at {address:#010x} was modified")
+            }
+            Error::RamExecutionEnabled => write!(
+                f,
+                "RAM execution is enabled, executed code can't be verified (strict W^X)"
+            ),
+            #[cfg(feature = "syscall-flood")]
+            Error::SyscallFlood(pc) => {
+                write!(f, "syscall flood at {pc:#010x} (ecall in a tight loop)")
+            }
+            #[cfg(feature = "resource-limits")]
+            Error::ResourceLimit(resource) => write!(f, "resource limit reached: {resource:?}"),
+            #[cfg(feature = "mmu")]
+            Error::PageFault { code, address } => {
+                let kind = match *code {
+                    super::mmu::INSTRUCTION_PAGE_FAULT => "instruction",
+                    super::mmu::LOAD_PAGE_FAULT => "load",
+                    _ => "store",
+                };
+                write!(f, "{kind} page fault at {address:#010x}")
+            }
+            #[cfg(feature = "pmp")]
+            Error::AccessFault { code, address } => {
+                let kind = match *code {
+                    super::privilege::INSTRUCTION_ACCESS_FAULT => "instruction",
+                    super::privilege::LOAD_ACCESS_FAULT => "load",
+                    _ => "store",
+                };
+                write!(
+                    f,
+                    "{kind} access fault at {address:#010x} (memory protection)"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_instruction() {
+        let error = Error::InvalidMemoryAddress {
+            address: 0x8000_0010,
+            len: 4,
+        };
+        let fault = error.in_instruction(0x20, MemoryAccess::Store);
+        assert_eq!(
+            fault,
+            Error::MemoryFault {
+                pc: 0x20,
+                access: MemoryAccess::Store,
+                address: 0x8000_0010,
+                len: 4,
+            }
+        );
+        assert_eq!(
+            fault.to_string(),
+            "4-byte store at 0x80000010 is out of bounds (pc: 0x00000020)"
+        );
+
+        // Other errors are unchanged
+        let error = Error::InvalidInstruction(0x20).in_instruction(0x20, MemoryAccess::Load);
+        assert_eq!(error, Error::InvalidInstruction(0x20));
     }
 }
@@ -0,0 +1,270 @@
+//! Guest Log Module
+//!
+//! Structured log transport from the interpreted code to the host. Instead of printing one character per syscall,
+//! the guest encodes a batch of log records in its memory and sends it with a single syscall ([`LOG_SYSCALL`]).
+//!
+//! Record format (little-endian, no alignment):
+//! - Level (1 byte, check [`LogLevel`]).
+//! - Module ID (2 bytes, guest-defined).
+//! - Message length (2 bytes).
+//! - Message (UTF-8 bytes).
+use core::fmt::{self, Display, Formatter};
+
+use super::{memory::Memory, utils::utf8_prefix, Error, Interpreter, LOG_SYSCALL};
+
+/// Size of a log record header (level, module ID and message length), in bytes.
+pub const LOG_RECORD_HEADER_SIZE: usize = crate::protocol::LOG_RECORD_HEADER_SIZE;
+
+/// Guest Log Level
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[repr(u8)]
+pub enum LogLevel {
+    /// Error.
+    Error = 1,
+    /// Warning.
+    Warn = 2,
+    /// Information.
+    Info = 3,
+    /// Debug.
+    Debug = 4,
+    /// Trace.
+    Trace = 5,
+}
+
+impl TryFrom<u8> for LogLevel {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        match value {
+            1 => Ok(LogLevel::Error),
+            2 => Ok(LogLevel::Warn),
+            3 => Ok(LogLevel::Info),
+            4 => Ok(LogLevel::Debug),
+            5 => Ok(LogLevel::Trace),
+            _ => Err(value),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// Malformed log record (invalid level or truncated record). The record offset in the batch is provided.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LogDecodeError(pub usize);
+
+impl core::error::Error for LogDecodeError {}
+
+impl Display for LogDecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed log record at offset {}", self.0)
+    }
+}
+
+/// Guest Log Record
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LogRecord<'m> {
+    /// Log level.
+    pub level: LogLevel,
+    /// Module ID (guest-defined).
+    pub module: u16,
+    /// Message bytes.
+    pub message: &'m [u8],
+}
+
+impl<'m> LogRecord<'m> {
+    /// Get the message as a string.
+    ///
+    /// Invalid UTF-8 sequences are not supported, the message is truncated at the first invalid byte.
+    pub fn message_str(&self) -> &'m str {
+        utf8_prefix(self.message)
+    }
+
+    /// Encode the record (e.g. by guest code or tests).
+    ///
+    /// Arguments:
+    /// - `buffer`: Output buffer.
+    ///
+    /// Returns:
+    /// - `Some(usize)`: Success, number of bytes written.
+    /// - `None`: The buffer is too small, or the message is longer than `u16::MAX` bytes.
+    pub fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+        let len = u16::try_from(self.message.len()).ok()?;
+        let size = LOG_RECORD_HEADER_SIZE + self.message.len();
+        let buffer = buffer.get_mut(..size)?;
+
+        buffer[0] = self.level as u8;
+        buffer[1..3].copy_from_slice(&self.module.to_le_bytes());
+        buffer[3..5].copy_from_slice(&len.to_le_bytes());
+        buffer[LOG_RECORD_HEADER_SIZE..].copy_from_slice(self.message);
+        Some(size)
+    }
+
+    /// Forward the record to the [`log`] crate (target `embive::guest`, message prefixed by the module ID).
+    #[cfg(feature = "log")]
+    pub fn forward(&self) {
+        log::log!(
+            target: "embive::guest",
+            log::Level::from(self.level),
+            "[{}] {}",
+            self.module,
+            self.message_str()
+        );
+    }
+}
+
+/// Guest Log Records Iterator
+///
+/// Decodes a batch of log records. Iteration stops after the first malformed record.
+#[derive(Debug, Clone)]
+pub struct LogRecords<'m> {
+    bytes: &'m [u8],
+    offset: usize,
+}
+
+impl<'m> LogRecords<'m> {
+    /// Create a new log records iterator.
+    ///
+    /// Arguments:
+    /// - `bytes`: Encoded log records.
+    pub fn new(bytes: &'m [u8]) -> Self {
+        LogRecords { bytes, offset: 0 }
+    }
+}
+
+impl<'m> Iterator for LogRecords<'m> {
+    type Item = Result<LogRecord<'m>, LogDecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = &self.bytes[self.offset..];
+        if bytes.is_empty() {
+            return None;
+        }
+
+        let record = bytes
+            .split_first_chunk::<LOG_RECORD_HEADER_SIZE>()
+            .and_then(|([level, module @ .., len_low, len_high], rest)| {
+                let len = u16::from_le_bytes([*len_low, *len_high]) as usize;
+                Some(LogRecord {
+                    level: LogLevel::try_from(*level).ok()?,
+                    module: u16::from_le_bytes(*module),
+                    message: rest.get(..len)?,
+                })
+            });
+
+        match record {
+            Some(record) => {
+                self.offset += LOG_RECORD_HEADER_SIZE + record.message.len();
+                Some(Ok(record))
+            }
+            None => {
+                // Stop iterating
+                let offset = self.offset;
+                self.offset = self.bytes.len();
+                Some(Err(LogDecodeError(offset)))
+            }
+        }
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Decode the log records of a pending [`LOG_SYSCALL`] from the interpreted code memory.
+    ///
+    /// The syscall is not completed, call [`Interpreter::complete_syscall`] after consuming the records.
+    ///
+    /// Returns:
+    /// - `Ok(Some(LogRecords))`: The log records sent by the interpreted code.
+    /// - `Ok(None)`: The pending syscall is not [`LOG_SYSCALL`] (or no syscall is pending).
+    /// - `Err(Error)`: The records address is out of bounds.
+    pub fn log_records(&mut self) -> Result<Option<LogRecords<'_>>, Error> {
+        match self.pending_syscall() {
+            Some((LOG_SYSCALL, args)) => {
+                let bytes = self.memory.load_bytes(args[0] as u32, args[1] as usize)?;
+                Ok(Some(LogRecords::new(bytes)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::{SliceMemory, RAM_OFFSET};
+
+    #[test]
+    fn test_log_records() {
+        let mut bytes = [0; 32];
+        let first = LogRecord {
+            level: LogLevel::Info,
+            module: 0x102,
+            message: b"hello",
+        };
+        let second = LogRecord {
+            level: LogLevel::Error,
+            module: 7,
+            message: b"",
+        };
+        let mut len = first.encode(&mut bytes).unwrap();
+        assert_eq!(
+            bytes[..len],
+            [3, 0x02, 0x01, 5, 0, b'h', b'e', b'l', b'l', b'o']
+        );
+        len += second.encode(&mut bytes[len..]).unwrap();
+        assert_eq!(len, 15);
+
+        let mut records = LogRecords::new(&bytes[..len]);
+        assert_eq!(records.next(), Some(Ok(first)));
+        assert_eq!(records.next(), Some(Ok(second)));
+        assert_eq!(records.next(), None);
+        assert_eq!(first.message_str(), "hello");
+
+        // Truncated message
+        let mut records = LogRecords::new(&bytes[..len - 7]);
+        assert_eq!(records.next(), Some(Err(LogDecodeError(0))));
+        assert_eq!(records.next(), None);
+
+        // Invalid level
+        bytes[len - LOG_RECORD_HEADER_SIZE] = 0;
+        let mut records = LogRecords::new(&bytes[..len]);
+        assert!(records.next().unwrap().is_ok());
+        assert_eq!(records.next(), Some(Err(LogDecodeError(10))));
+    }
+
+    #[test]
+    fn test_interpreter_log_records() {
+        let mut ram = [0; 16];
+        let record = LogRecord {
+            level: LogLevel::Warn,
+            module: 1,
+            message: b"low battery",
+        };
+        let len = record.encode(&mut ram).unwrap();
+
+        // Code: ecall (already transpiled)
+        let code = [0x1f, 0x00, 0x00, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        assert_eq!(interpreter.log_records().map(|r| r.is_none()), Ok(true));
+
+        interpreter.registers.cpu.set_a7(LOG_SYSCALL);
+        interpreter.registers.cpu.set_a0(RAM_OFFSET as i32);
+        interpreter.registers.cpu.set_a1(len as i32);
+        interpreter.run().unwrap();
+
+        let mut records = interpreter.log_records().unwrap().unwrap();
+        assert_eq!(records.next(), Some(Ok(record)));
+        assert_eq!(records.next(), None);
+        interpreter.complete_syscall(Ok(0)).unwrap();
+    }
+}
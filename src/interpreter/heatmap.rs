@@ -0,0 +1,472 @@
+//! Heatmap Module
+//!
+//! Memory access heatmap of guest code: loads and stores counted per address bucket, in
+//! user-supplied counters, to find the hot regions of a run (check [`Heatmap::hot_regions`]).
+//!
+//! Accesses are recorded by wrapping the interpreter memory in a [`HeatmapMemory`].
+use core::fmt::{self, Write};
+
+use super::{memory::Memory, Error};
+
+/// Get the number of buckets needed to cover a memory region (check [`Heatmap::new`]).
+///
+/// Arguments:
+/// - `size`: Size of the region, in bytes.
+/// - `bucket_size`: Size of each bucket, in bytes.
+pub const fn heatmap_bucket_count(size: usize, bucket_size: usize) -> usize {
+    size.div_ceil(bucket_size)
+}
+
+/// Access counters of a heatmap bucket (saturating).
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct BucketCount {
+    /// Number of loads.
+    pub loads: u32,
+    /// Number of stores (atomic read-modify-write operations count as a load and a store).
+    pub stores: u32,
+}
+
+impl BucketCount {
+    /// Get the total number of accesses (loads and stores).
+    pub fn total(&self) -> u64 {
+        self.loads as u64 + self.stores as u64
+    }
+}
+
+/// A hot region: contiguous buckets with at least the requested number of accesses each.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct HotRegion {
+    /// Start address of the region.
+    pub start: u32,
+    /// End address of the region (exclusive).
+    pub end: u32,
+    /// Number of loads in the region.
+    pub loads: u64,
+    /// Number of stores in the region.
+    pub stores: u64,
+}
+
+impl HotRegion {
+    /// Get the region size, in bytes.
+    pub fn size(&self) -> u32 {
+        self.end - self.start
+    }
+
+    /// Get the total number of accesses (loads and stores).
+    pub fn total(&self) -> u64 {
+        self.loads + self.stores
+    }
+}
+
+/// Embive Memory Heatmap
+///
+/// Access counters of a memory region, one [`BucketCount`] per `bucket_size` bytes.
+/// An access is recorded in the bucket of its start address; addresses outside of the region
+/// are ignored.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{
+///     heatmap::{heatmap_bucket_count, BucketCount, Heatmap, HeatmapMemory},
+///     memory::{Memory, SliceMemory, RAM_OFFSET},
+/// };
+///
+/// let mut ram = [0; 256];
+/// let mut buckets = [BucketCount::default(); heatmap_bucket_count(256, 64)];
+/// let mut memory = HeatmapMemory::new(
+///     SliceMemory::new(&[], &mut ram),
+///     Heatmap::new(RAM_OFFSET, 64, &mut buckets),
+/// );
+///
+/// // Guest accesses (done by the interpreter)
+/// memory.store_u32(RAM_OFFSET + 0x84, 1).unwrap();
+/// memory.load_u32(RAM_OFFSET + 0x84).unwrap();
+///
+/// let region = memory.heatmap().hot_regions(1).next().unwrap();
+/// assert_eq!((region.start, region.end), (RAM_OFFSET + 0x80, RAM_OFFSET + 0xC0));
+/// assert_eq!((region.loads, region.stores), (1, 1));
+/// ```
+#[derive(Debug)]
+pub struct Heatmap<'h> {
+    base: u32,
+    bucket_size: u32,
+    buckets: &'h mut [BucketCount],
+}
+
+impl<'h> Heatmap<'h> {
+    /// Create a new memory heatmap.
+    ///
+    /// Arguments:
+    /// - `base`: Start address of the region (e.g. [`super::memory::RAM_OFFSET`] for RAM).
+    /// - `bucket_size`: Size of each bucket, in bytes (`0` is handled as `1`).
+    /// - `buckets`: Bucket counters (check [`heatmap_bucket_count`]).
+    ///   Can be zeroed or hold the counters of a previous run.
+    pub fn new(base: u32, bucket_size: u32, buckets: &'h mut [BucketCount]) -> Self {
+        Heatmap {
+            base,
+            bucket_size: bucket_size.max(1),
+            buckets,
+        }
+    }
+
+    /// Get the start address of the region.
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// Get the bucket size, in bytes.
+    pub fn bucket_size(&self) -> u32 {
+        self.bucket_size
+    }
+
+    /// Get the bucket counters (bucket `i` starts at address `base + i * bucket_size`).
+    pub fn buckets(&self) -> &[BucketCount] {
+        self.buckets
+    }
+
+    /// Get the counters of the bucket holding an address, if in range.
+    ///
+    /// Arguments:
+    /// - `address`: Memory address.
+    pub fn bucket(&self, address: u32) -> Option<BucketCount> {
+        self.index(address).map(|index| self.buckets[index])
+    }
+
+    /// Reset all bucket counters.
+    pub fn reset(&mut self) {
+        self.buckets.fill(BucketCount::default());
+    }
+
+    /// Get the bucket index of an address, if in range.
+    fn index(&self, address: u32) -> Option<usize> {
+        let index = (address.checked_sub(self.base)? / self.bucket_size) as usize;
+        (index < self.buckets.len()).then_some(index)
+    }
+
+    /// Get the start address of a bucket.
+    fn bucket_start(&self, index: usize) -> u32 {
+        self.base
+            .saturating_add((index as u32).saturating_mul(self.bucket_size))
+    }
+
+    /// Record a load.
+    ///
+    /// Arguments:
+    /// - `address`: Start address of the access.
+    #[inline]
+    pub fn record_load(&mut self, address: u32) {
+        if let Some(index) = self.index(address) {
+            let bucket = &mut self.buckets[index];
+            bucket.loads = bucket.loads.saturating_add(1);
+        }
+    }
+
+    /// Record a store.
+    ///
+    /// Arguments:
+    /// - `address`: Start address of the access.
+    #[inline]
+    pub fn record_store(&mut self, address: u32) {
+        if let Some(index) = self.index(address) {
+            let bucket = &mut self.buckets[index];
+            bucket.stores = bucket.stores.saturating_add(1);
+        }
+    }
+
+    /// Get the total number of recorded accesses (loads and stores).
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().map(BucketCount::total).sum()
+    }
+
+    /// Iterate over the hot regions, in ascending address order.
+    ///
+    /// Contiguous buckets with at least `min_accesses` accesses each are merged in a single region.
+    ///
+    /// Arguments:
+    /// - `min_accesses`: Minimum number of accesses of a hot bucket (`0` is handled as `1`).
+    pub fn hot_regions(&self, min_accesses: u64) -> impl Iterator<Item = HotRegion> + '_ {
+        let min_accesses = min_accesses.max(1);
+        let mut index = 0;
+
+        core::iter::from_fn(move || {
+            // Find the next hot bucket
+            index += self.buckets[index..]
+                .iter()
+                .position(|bucket| bucket.total() >= min_accesses)?;
+
+            let mut region = HotRegion {
+                start: self.bucket_start(index),
+                end: 0,
+                loads: 0,
+                stores: 0,
+            };
+
+            // Merge the following hot buckets
+            while let Some(bucket) = self.buckets.get(index) {
+                if bucket.total() < min_accesses {
+                    break;
+                }
+
+                region.loads += bucket.loads as u64;
+                region.stores += bucket.stores as u64;
+                index += 1;
+            }
+
+            region.end = self.bucket_start(index);
+            Some(region)
+        })
+    }
+
+    /// Report the hot regions, one per line (`0x<start>-0x<end> <size> loads=<n> stores=<n>`).
+    ///
+    /// Arguments:
+    /// - `sink`: The output sink.
+    /// - `min_accesses`: Minimum number of accesses of a hot bucket (check [`Heatmap::hot_regions`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, report written.
+    /// - `Err(fmt::Error)`: Failed to write to the sink.
+    pub fn write<W: Write>(&self, sink: &mut W, min_accesses: u64) -> fmt::Result {
+        for region in self.hot_regions(min_accesses) {
+            writeln!(
+                sink,
+                "0x{:08x}-0x{:08x} {} loads={} stores={}",
+                region.start,
+                region.end,
+                region.size(),
+                region.loads,
+                region.stores
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Heatmap Memory
+///
+/// Wraps a [`Memory`], recording the guest loads and stores in a [`Heatmap`].
+///
+/// Only typed accesses (`load_u8` to `store_u32`, as done by the guest instructions) are recorded.
+/// Instruction fetches also go through `load_u32`, so code executed from the heatmap region
+/// (check [`super::Config::ram_execution`]) is counted as loads.
+/// Host bulk accesses (e.g. syscall buffers) are not recorded.
+#[derive(Debug)]
+pub struct HeatmapMemory<'h, M: Memory> {
+    memory: M,
+    heatmap: Heatmap<'h>,
+}
+
+impl<'h, M: Memory> HeatmapMemory<'h, M> {
+    /// Create a new heatmap memory.
+    ///
+    /// Arguments:
+    /// - `memory`: The wrapped memory.
+    /// - `heatmap`: The heatmap to record the accesses in.
+    pub fn new(memory: M, heatmap: Heatmap<'h>) -> Self {
+        HeatmapMemory { memory, heatmap }
+    }
+
+    /// Get the wrapped memory.
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Get the wrapped memory (mutable).
+    pub fn memory_mut(&mut self) -> &mut M {
+        &mut self.memory
+    }
+
+    /// Get the heatmap.
+    pub fn heatmap(&self) -> &Heatmap<'h> {
+        &self.heatmap
+    }
+
+    /// Get the heatmap (mutable).
+    pub fn heatmap_mut(&mut self) -> &mut Heatmap<'h> {
+        &mut self.heatmap
+    }
+
+    /// Consume the heatmap memory, returning the wrapped memory and the heatmap.
+    pub fn into_parts(self) -> (M, Heatmap<'h>) {
+        (self.memory, self.heatmap)
+    }
+}
+
+impl<M: Memory> Memory for HeatmapMemory<'_, M> {
+    #[inline]
+    fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+        self.memory.load_bytes(address, len)
+    }
+
+    #[inline]
+    fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+        self.memory.mut_bytes(address, len)
+    }
+
+    #[inline]
+    fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.memory.store_bytes(address, data)
+    }
+
+    #[inline]
+    fn load_u8(&mut self, address: u32) -> Result<u8, Error> {
+        self.heatmap.record_load(address);
+        self.memory.load_u8(address)
+    }
+
+    #[inline]
+    fn load_u16(&mut self, address: u32) -> Result<u16, Error> {
+        self.heatmap.record_load(address);
+        self.memory.load_u16(address)
+    }
+
+    #[inline]
+    fn load_u32(&mut self, address: u32) -> Result<u32, Error> {
+        self.heatmap.record_load(address);
+        self.memory.load_u32(address)
+    }
+
+    #[inline]
+    fn store_u8(&mut self, address: u32, value: u8) -> Result<(), Error> {
+        self.heatmap.record_store(address);
+        self.memory.store_u8(address, value)
+    }
+
+    #[inline]
+    fn store_u16(&mut self, address: u32, value: u16) -> Result<(), Error> {
+        self.heatmap.record_store(address);
+        self.memory.store_u16(address, value)
+    }
+
+    #[inline]
+    fn store_u32(&mut self, address: u32, value: u32) -> Result<(), Error> {
+        self.heatmap.record_store(address);
+        self.memory.store_u32(address, value)
+    }
+
+    #[inline]
+    fn copy_from_guest(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        self.memory.copy_from_guest(address, buffer)
+    }
+
+    #[inline]
+    fn copy_to_guest(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.memory.copy_to_guest(address, data)
+    }
+
+    #[inline]
+    fn take_stall_cycles(&mut self) -> u32 {
+        self.memory.take_stall_cycles()
+    }
+
+    #[inline]
+    fn sync_software_interrupt(&mut self, pending: bool) -> bool {
+        self.memory.sync_software_interrupt(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "transpiler")]
+    use crate::{
+        interpreter::{
+            memory::{SliceMemory, RAM_OFFSET},
+            ExitReason, Interpreter, State,
+        },
+        transpiler::transpile_raw,
+    };
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_heatmap_run() {
+        let mut code = [
+            0x37, 0x05, 0x00, 0x80, // lui  a0, 0x80000 (RAM)
+            0x93, 0x05, 0x30, 0x00, // li   a1, 3
+            0x0c, 0xc1, // sw   a1, 0(a0) (compressed)
+            0x10, 0x41, // lw   a2, 0(a0) (compressed)
+            0xfd, 0x15, // addi a1, a1, -1 (compressed)
+            0xed, 0xfd, // bnez a1, -6 (compressed)
+            0x23, 0x08, 0xb5, 0x0e, // sb   a1, 240(a0)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 256];
+        let mut buckets = [BucketCount::default(); heatmap_bucket_count(256, 16)];
+        let mut memory = HeatmapMemory::new(
+            SliceMemory::new(&code, &mut ram),
+            Heatmap::new(RAM_OFFSET, 16, &mut buckets),
+        );
+
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Instruction fetches (code region) are not in the heatmap
+        let heatmap = memory.heatmap();
+        assert_eq!(heatmap.total(), 7);
+        assert_eq!(
+            heatmap.bucket(RAM_OFFSET),
+            Some(BucketCount {
+                loads: 3,
+                stores: 3
+            })
+        );
+        assert_eq!(
+            heatmap.bucket(RAM_OFFSET + 0xF0),
+            Some(BucketCount {
+                loads: 0,
+                stores: 1
+            })
+        );
+
+        let mut output = String::new();
+        heatmap.write(&mut output, 2).unwrap();
+        assert_eq!(output, "0x80000000-0x80000010 16 loads=3 stores=3\n");
+    }
+
+    #[test]
+    fn test_hot_regions() {
+        let mut buckets = [BucketCount::default(); 6];
+        let mut heatmap = Heatmap::new(0x100, 0x10, &mut buckets);
+
+        for _ in 0..3 {
+            heatmap.record_load(0x100);
+            heatmap.record_store(0x11f);
+            heatmap.record_load(0x150);
+        }
+        heatmap.record_load(0x130);
+        heatmap.record_store(0x0ff); // Out of range
+        heatmap.record_store(0x160); // Out of range
+
+        assert_eq!(heatmap.total(), 10);
+        assert_eq!(
+            heatmap.hot_regions(3).collect::<Vec<_>>(),
+            [
+                HotRegion {
+                    start: 0x100,
+                    end: 0x120,
+                    loads: 3,
+                    stores: 3
+                },
+                HotRegion {
+                    start: 0x150,
+                    end: 0x160,
+                    loads: 3,
+                    stores: 0
+                }
+            ]
+        );
+        assert_eq!(heatmap.hot_regions(0).count(), 3);
+        assert_eq!(heatmap.hot_regions(4).count(), 0);
+
+        heatmap.reset();
+        assert_eq!(heatmap.total(), 0);
+        assert_eq!(heatmap.bucket(0x160), None);
+    }
+}
@@ -0,0 +1,384 @@
+//! Hooks Module
+//!
+//! Host hooks stop the interpreter when the program counter reaches a guest address, before executing the
+//! instruction there, without patching the guest code (check [`Interpreter::add_hook`]). The host can then:
+//! - Resume: running again executes the hooked instruction (the hook fires again the next time it is reached).
+//! - Redirect: set [`Interpreter::program_counter`] before running again.
+//! - Stub the guest function out: [`Interpreter::return_from_hook`] returns to the caller with a host result
+//!   (e.g. replacing a crypto routine with a host implementation).
+//!
+//! Hooks at a function entry can intercept the call (check [`Interpreter::intercept`]): the arguments are read
+//! per the C ABI (`a0` to `a7`, 64-bit values in register pairs), the function body is skipped and the return
+//! value is written (`a0`, and `a1` for 64-bit values) before resuming at the return address (`ra`).
+//!
+//! Hooks (and their check before each instruction) are only compiled with the `hooks` feature.
+use super::{memory::Memory, Error, Interpreter, CALL_ARGS};
+
+/// Maximum number of hooks (check [`Interpreter::add_hook`]).
+pub const HOOK_CAPACITY: usize = 8;
+
+/// Return value of an intercepted function (check [`Interpreter::return_from_hook`]), per the C ABI.
+///
+/// Intercepting functions can return any type that converts into [`ReturnValue`] (e.g. `i32`, `u64`, `()`).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReturnValue {
+    /// No value (`void` function), registers are left untouched.
+    Void,
+    /// Signed 32-bit value (`a0`).
+    I32(i32),
+    /// Unsigned 32-bit value (`a0`).
+    U32(u32),
+    /// Signed 64-bit value (`a0` low, `a1` high).
+    I64(i64),
+    /// Unsigned 64-bit value (`a0` low, `a1` high).
+    U64(u64),
+}
+
+impl From<()> for ReturnValue {
+    fn from(_: ()) -> Self {
+        ReturnValue::Void
+    }
+}
+
+impl From<i32> for ReturnValue {
+    fn from(value: i32) -> Self {
+        ReturnValue::I32(value)
+    }
+}
+
+impl From<u32> for ReturnValue {
+    fn from(value: u32) -> Self {
+        ReturnValue::U32(value)
+    }
+}
+
+impl From<i64> for ReturnValue {
+    fn from(value: i64) -> Self {
+        ReturnValue::I64(value)
+    }
+}
+
+impl From<u64> for ReturnValue {
+    fn from(value: u64) -> Self {
+        ReturnValue::U64(value)
+    }
+}
+
+/// Hooked addresses and their ids.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Hooks {
+    entries: [(u32, u32); HOOK_CAPACITY],
+    len: usize,
+    /// Hooked address being resumed (its hook doesn't fire again before executing it).
+    resumed: Option<u32>,
+}
+
+impl Hooks {
+    /// Check if no hook is set.
+    #[inline(always)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the hooks (address and id), in insertion order.
+    fn entries(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.entries.iter().take(self.len).copied()
+    }
+
+    /// Set a hook, replacing the id of an already hooked address.
+    fn insert(&mut self, address: u32, id: u32) -> Result<(), Error> {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .take(self.len)
+            .find(|(hooked, _)| *hooked == address)
+        {
+            entry.1 = id;
+            return Ok(());
+        }
+
+        let entry = self.entries.get_mut(self.len).ok_or(Error::HookTableFull)?;
+        *entry = (address, id);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the hook at an address, returning its id.
+    fn remove(&mut self, address: u32) -> Option<u32> {
+        let (_, id) = self.entries().find(|&(hooked, _)| hooked == address)?;
+
+        // Keep the insertion order of the remaining hooks
+        let mut entries = [(0, 0); HOOK_CAPACITY];
+        let remaining = self.entries().filter(|&(hooked, _)| hooked != address);
+        for (entry, remaining) in entries.iter_mut().zip(remaining) {
+            *entry = remaining;
+        }
+        self.entries = entries;
+        self.len -= 1;
+        Some(id)
+    }
+
+    /// Check if the instruction at `pc` is hooked, returning the hook id.
+    /// Hooks fire once: the returned hook doesn't fire again when resuming.
+    pub(crate) fn hit(&mut self, pc: u32) -> Option<u32> {
+        if self.resumed.take() == Some(pc) {
+            return None;
+        }
+
+        let (_, id) = self.entries().find(|&(hooked, _)| hooked == pc)?;
+        self.resumed = Some(pc);
+        Some(id)
+    }
+
+    /// Forget the hooked address being resumed.
+    pub(crate) fn rearm(&mut self) {
+        self.resumed = None;
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Set a hook at a guest address: [`Interpreter::run`] returns [`super::State::Hooked`] with the hook id
+    /// when the program counter reaches it, before executing the instruction there.
+    ///
+    /// Running again executes the hooked instruction, unless the host redirects the execution
+    /// ([`Interpreter::program_counter`] or [`Interpreter::return_from_hook`]).
+    /// Hooks are kept across [`Interpreter::reset`].
+    ///
+    /// Arguments:
+    /// - `address`: Guest address (start of an instruction, check [`Interpreter::check_instruction_start`]).
+    /// - `id`: Host-defined hook id. Replaces the id of an already hooked address.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, hook set.
+    /// - `Err(Error)`: No hook left ([`Error::HookTableFull`], check [`HOOK_CAPACITY`]).
+    pub fn add_hook(&mut self, address: u32, id: u32) -> Result<(), Error> {
+        self.hooks.insert(address, id)
+    }
+
+    /// Remove the hook at a guest address (check [`Interpreter::add_hook`]).
+    ///
+    /// Returns:
+    /// - `Some(u32)`: Id of the removed hook.
+    /// - `None`: Address not hooked.
+    pub fn remove_hook(&mut self, address: u32) -> Option<u32> {
+        self.hooks.remove(address)
+    }
+
+    /// Get the hooks (address and id), in insertion order.
+    pub fn hooks(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.hooks.entries()
+    }
+
+    /// Remove all hooks (check [`Interpreter::add_hook`]).
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    /// Return from a hooked guest function without executing it, as if it returned `value`:
+    /// sets `a0` (and `a1` for 64-bit values) and jumps to the return address (`ra`).
+    ///
+    /// Only meaningful for hooks at a function entry (other registers can be set beforehand).
+    ///
+    /// Arguments:
+    /// - `value`: Function result (check [`ReturnValue`]).
+    pub fn return_from_hook(&mut self, value: impl Into<ReturnValue>) {
+        let cpu = &mut self.registers.cpu;
+        match value.into() {
+            ReturnValue::Void => {}
+            ReturnValue::I32(value) => cpu.set_a0(value),
+            ReturnValue::U32(value) => cpu.set_a0(value as i32),
+            ReturnValue::I64(value) => {
+                cpu.set_a0(value as i32);
+                cpu.set_a1((value >> 32) as i32);
+            }
+            ReturnValue::U64(value) => {
+                cpu.set_a0(value as i32);
+                cpu.set_a1((value >> 32) as i32);
+            }
+        }
+        self.program_counter = cpu.ra() as u32;
+        self.hooks.rearm();
+    }
+
+    /// Get the arguments of an intercepted guest function (`a0` to `a7`, per the C ABI).
+    ///
+    /// 64-bit arguments are passed in (even-aligned) register pairs, low word first.
+    /// Further arguments are on the stack (`sp`).
+    ///
+    /// Only meaningful for hooks at a function entry (check [`Interpreter::add_hook`]).
+    pub fn hook_args(&self) -> [i32; CALL_ARGS] {
+        let cpu = &self.registers.cpu;
+        [
+            cpu.a0(),
+            cpu.a1(),
+            cpu.a2(),
+            cpu.a3(),
+            cpu.a4(),
+            cpu.a5(),
+            cpu.a6(),
+            cpu.a7(),
+        ]
+    }
+
+    /// Intercept a hooked guest function, replacing it with a host function: the host function is called with
+    /// the arguments (check [`Interpreter::hook_args`]) and the guest memory (e.g. to access pointer arguments),
+    /// then the guest function returns its result without being executed (check [`Interpreter::return_from_hook`]).
+    ///
+    /// Only meaningful for hooks at a function entry (check [`Interpreter::add_hook`]).
+    ///
+    /// Example:
+    /// ```
+    /// use embive::interpreter::{memory::SliceMemory, Error, Interpreter, State};
+    ///
+    /// // Code: ebreak, ebreak (already transpiled), the first one hooked as a function
+    /// let code = [0x1f, 0x00, 0x10, 0x00, 0x1f, 0x00, 0x10, 0x00];
+    /// let mut memory = SliceMemory::new(&code, &mut []);
+    /// let mut interpreter = Interpreter::new(&mut memory, 0);
+    /// interpreter.add_hook(0, 0).unwrap();
+    ///
+    /// // Arguments and return address (the second ebreak, halting)
+    /// interpreter.registers.cpu.set_a0(6);
+    /// interpreter.registers.cpu.set_a1(7);
+    /// interpreter.registers.cpu.set_ra(4);
+    ///
+    /// assert_eq!(interpreter.run(), Ok(State::Hooked(0)));
+    /// interpreter
+    ///     .intercept(|args, _memory| Ok::<_, Error>(args[0] * args[1]))
+    ///     .unwrap();
+    /// assert!(matches!(interpreter.run(), Ok(State::Halted { .. })));
+    /// assert_eq!(interpreter.registers.cpu.a0(), 42);
+    /// ```
+    ///
+    /// Arguments:
+    /// - `function`: Host function (FnOnce closure), returning the function result (check [`ReturnValue`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, the guest function returned.
+    /// - `Err(E)`: Host function error, the guest function was not skipped.
+    pub fn intercept<F, R, E>(&mut self, function: F) -> Result<(), E>
+    where
+        F: FnOnce(&[i32; CALL_ARGS], &mut M) -> Result<R, E>,
+        R: Into<ReturnValue>,
+    {
+        let args = self.hook_args();
+        let value = function(&args, self.memory)?;
+        self.return_from_hook(value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "transpiler")]
+    use crate::interpreter::{memory::SliceMemory, registers::CPURegister, State};
+    #[cfg(feature = "transpiler")]
+    use crate::transpiler::transpile_raw;
+
+    #[test]
+    fn test_hooks_table() {
+        let mut hooks = Hooks::default();
+        hooks.insert(0x10, 1).unwrap();
+        hooks.insert(0x20, 2).unwrap();
+        hooks.insert(0x10, 3).unwrap();
+        assert!(hooks.entries().eq([(0x10, 3), (0x20, 2)]));
+
+        assert_eq!(hooks.remove(0x10), Some(3));
+        assert_eq!(hooks.remove(0x10), None);
+        assert!(hooks.entries().eq([(0x20, 2)]));
+
+        for address in 0..HOOK_CAPACITY as u32 - 1 {
+            hooks.insert(address, 0).unwrap();
+        }
+        assert_eq!(hooks.insert(0x20, 4), Ok(()));
+        assert_eq!(hooks.insert(0x30, 0), Err(Error::HookTableFull));
+
+        // Fires once per visit
+        assert_eq!(hooks.hit(0x20), Some(4));
+        assert_eq!(hooks.hit(0x20), None);
+        assert_eq!(hooks.hit(0x24), None);
+        assert_eq!(hooks.hit(0x20), Some(4));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_hook_resume() {
+        let mut code = [
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.add_hook(0, 7).unwrap();
+        interpreter.add_hook(4, 8).unwrap();
+
+        assert_eq!(interpreter.run(), Ok(State::Hooked(7)));
+        assert_eq!(interpreter.program_counter, 0);
+        assert_eq!(interpreter.run(), Ok(State::Hooked(8)));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(1));
+
+        // Hooks are kept across resets
+        interpreter.reset();
+        assert_eq!(interpreter.run(), Ok(State::Hooked(7)));
+        assert_eq!(interpreter.remove_hook(4), Some(8));
+        assert!(matches!(interpreter.run(), Ok(State::Halted { .. })));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_return_from_hook() {
+        let mut code = [
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.add_hook(0, 1).unwrap();
+
+        // Stub the "function" at 0, returning to the ebreak
+        interpreter.registers.cpu.set(CPURegister::RA, 4).unwrap();
+        assert_eq!(interpreter.run(), Ok(State::Hooked(1)));
+        interpreter.return_from_hook(42);
+        assert!(matches!(interpreter.run(), Ok(State::Halted { .. })));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(42));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_intercept() {
+        let mut code = [
+            0x13, 0x05, 0x60, 0x00, // li   a0, 6
+            0x93, 0x05, 0x70, 0x00, // li   a1, 7
+            0xef, 0x00, 0x80, 0x00, // jal  ra, 8 (add)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+            0x33, 0x05, 0xb5, 0x00, // add  a0, a0, a1 (add)
+            0x67, 0x80, 0x00, 0x00, // ret
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.add_hook(0x10, 0).unwrap();
+
+        // Replace the function (64-bit result)
+        assert_eq!(interpreter.run(), Ok(State::Hooked(0)));
+        assert_eq!(interpreter.hook_args()[..2], [6, 7]);
+        interpreter
+            .intercept(|args, _memory| Ok::<_, Error>(-((args[0] * args[1]) as i64)))
+            .unwrap();
+        assert!(matches!(interpreter.run(), Ok(State::Halted { .. })));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(-42));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(-1));
+
+        // Errors don't skip the function
+        interpreter.reset();
+        assert_eq!(interpreter.run(), Ok(State::Hooked(0)));
+        assert_eq!(
+            interpreter.intercept(|_, _| Err::<(), _>(Error::NoSyscallFunction)),
+            Err(Error::NoSyscallFunction)
+        );
+        assert!(matches!(interpreter.run(), Ok(State::Halted { .. })));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(13));
+    }
+}
@@ -0,0 +1,243 @@
+//! Code Integrity Module
+//!
+//! Code region integrity checks: page hashes (CRC-32) taken right after loading, then verified before each run
+//! or periodically, page by page. Detects writes to the guest code (e.g. through bugs in host glue sharing the
+//! code buffer), and provides tamper evidence for audits (check [`CodeIntegrity::digest`]).
+//!
+//! CRC-32 detects corruption, not an attacker able to recompute the hashes: sign the image to authenticate it
+//! (check [`super::loader`]). Code executed from RAM can't be verified, so [`CodeIntegrity::run`] requires strict
+//! W^X (RAM execution disabled, check [`super::Config::ram_execution`]).
+use super::{memory::Memory, snapshot::crc32_update, Error, Interpreter, State};
+
+/// Embive Code Integrity
+///
+/// Hashes of up to `P` pages of the code region (starting at address `0`).
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{
+///     integrity::CodeIntegrity,
+///     memory::SliceMemory,
+///     Config, Error, Interpreter,
+/// };
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let integrity = CodeIntegrity::<16>::new(&mut memory, code.len() as u32, 256).unwrap();
+/// println!("code digest: {:#010x}", integrity.digest());
+///
+/// // Strict W^X
+/// let config = Config::default().with_ram_execution(false);
+/// let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+/// assert!(integrity.run(&mut interpreter).is_ok());
+///
+/// // Code modified behind the interpreter's back
+/// let other = [0x13, 0x00, 0x00, 0x00];
+/// let mut memory = SliceMemory::new(&other, &mut []);
+/// assert_eq!(integrity.verify(&mut memory), Err(Error::CodeModified(0)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeIntegrity<const P: usize> {
+    /// Page hashes (CRC-32), `pages` in use.
+    hashes: [u32; P],
+    pages: usize,
+    /// Size of the code region, in bytes.
+    size: u32,
+    page_size: u32,
+    /// CRC-32 of the whole code region.
+    digest: u32,
+    /// Next page checked by [`CodeIntegrity::verify_next`].
+    cursor: usize,
+}
+
+impl<const P: usize> CodeIntegrity<P> {
+    /// Hash the code region (right after loading it).
+    ///
+    /// Arguments:
+    /// - `memory`: System memory.
+    /// - `size`: Size of the code region, in bytes.
+    /// - `page_size`: Page size, in bytes. Raised if needed so the code fits in `P` pages.
+    ///
+    /// Returns:
+    /// - `Ok(CodeIntegrity)`: Success, code hashed.
+    /// - `Err(Error)`: Failed to read the code region (or `P` is `0`).
+    pub fn new<M: Memory>(memory: &mut M, size: u32, page_size: u32) -> Result<Self, Error> {
+        if P == 0 {
+            return Err(Error::InvalidMemoryAccessLength(size as usize));
+        }
+
+        let page_size = page_size.max(1).max(size.div_ceil(P as u32));
+        let mut integrity = CodeIntegrity {
+            hashes: [0; P],
+            pages: size.div_ceil(page_size) as usize,
+            size,
+            page_size,
+            digest: !0,
+            cursor: 0,
+        };
+
+        for page in 0..integrity.pages {
+            let bytes = integrity.page_bytes(memory, page)?;
+            integrity.digest = crc32_update(integrity.digest, bytes);
+            integrity.hashes[page] = !crc32_update(!0, bytes);
+        }
+        integrity.digest = !integrity.digest;
+
+        Ok(integrity)
+    }
+
+    /// Get the CRC-32 of the whole code region, when hashed (e.g. to record in audit logs).
+    pub fn digest(&self) -> u32 {
+        self.digest
+    }
+
+    /// Get the page size, in bytes.
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Get the page hashes (page `i` starts at address `i * page_size`).
+    pub fn hashes(&self) -> &[u32] {
+        &self.hashes[..self.pages]
+    }
+
+    /// Get the bytes of a page.
+    fn page_bytes<'m, M: Memory>(&self, memory: &'m mut M, page: usize) -> Result<&'m [u8], Error> {
+        let start = page as u32 * self.page_size;
+        let len = self.page_size.min(self.size - start);
+        memory.load_bytes(start, len as usize)
+    }
+
+    /// Verify a page.
+    ///
+    /// Arguments:
+    /// - `memory`: System memory.
+    /// - `page`: Page index (out of range pages are ignored).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, page unchanged.
+    /// - `Err(Error)`: Page modified ([`Error::CodeModified`]), or failed to read it.
+    pub fn verify_page<M: Memory>(&self, memory: &mut M, page: usize) -> Result<(), Error> {
+        let Some(&hash) = self.hashes().get(page) else {
+            return Ok(());
+        };
+
+        match !crc32_update(!0, self.page_bytes(memory, page)?) == hash {
+            true => Ok(()),
+            false => Err(Error::CodeModified(page as u32 * self.page_size)),
+        }
+    }
+
+    /// Verify all pages.
+    ///
+    /// Arguments:
+    /// - `memory`: System memory.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, code unchanged.
+    /// - `Err(Error)`: A page was modified ([`Error::CodeModified`], first one), or failed to read it.
+    pub fn verify<M: Memory>(&self, memory: &mut M) -> Result<(), Error> {
+        (0..self.pages).try_for_each(|page| self.verify_page(memory, page))
+    }
+
+    /// Verify the next page, in turn (periodic checks, spreading the cost of [`CodeIntegrity::verify`]).
+    ///
+    /// Arguments:
+    /// - `memory`: System memory.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, page unchanged.
+    /// - `Err(Error)`: Page modified ([`Error::CodeModified`]), or failed to read it.
+    pub fn verify_next<M: Memory>(&mut self, memory: &mut M) -> Result<(), Error> {
+        let page = self.cursor;
+        self.cursor = match page + 1 {
+            next if next < self.pages => next,
+            _ => 0,
+        };
+        self.verify_page(memory, page)
+    }
+
+    /// Verify all pages, then run the interpreter (check [`Interpreter::run`]).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`Interpreter::run`]).
+    /// - `Err(Error)`: RAM execution is enabled ([`Error::RamExecutionEnabled`]), code modified
+    ///   ([`Error::CodeModified`]), or failed to run.
+    pub fn run<M: Memory>(&self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
+        if interpreter.config().ram_execution {
+            return Err(Error::RamExecutionEnabled);
+        }
+
+        self.verify(interpreter.memory)?;
+        interpreter.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, Config, ExitReason};
+
+    #[test]
+    fn test_pages() {
+        let mut code = [0u8; 10];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let integrity = CodeIntegrity::<4>::new(&mut memory, 10, 4).unwrap();
+        assert_eq!(integrity.page_size(), 4);
+        assert_eq!(integrity.hashes().len(), 3);
+        assert_eq!(integrity.digest(), !crc32_update(!0, &code));
+        assert_eq!(integrity.verify(&mut memory), Ok(()));
+
+        // Page size raised to fit
+        let integrity = CodeIntegrity::<2>::new(&mut memory, 10, 1).unwrap();
+        assert_eq!(integrity.page_size(), 5);
+        assert_eq!(
+            CodeIntegrity::<0>::new(&mut memory, 10, 1),
+            Err(Error::InvalidMemoryAccessLength(10))
+        );
+
+        // Last (partial) page modified
+        let mut integrity = CodeIntegrity::<4>::new(&mut memory, 10, 4).unwrap();
+        code[9] = 1;
+        let mut memory = SliceMemory::new(&code, &mut []);
+        assert_eq!(integrity.verify(&mut memory), Err(Error::CodeModified(8)));
+        assert_eq!(integrity.verify_page(&mut memory, 1), Ok(()));
+        assert_eq!(integrity.verify_page(&mut memory, 3), Ok(()));
+
+        // Round robin
+        assert_eq!(integrity.verify_next(&mut memory), Ok(()));
+        assert_eq!(integrity.verify_next(&mut memory), Ok(()));
+        assert_eq!(
+            integrity.verify_next(&mut memory),
+            Err(Error::CodeModified(8))
+        );
+        assert_eq!(integrity.verify_next(&mut memory), Ok(()));
+    }
+
+    #[test]
+    fn test_run() {
+        // Code: ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let integrity = CodeIntegrity::<1>::new(&mut memory, 4, 64).unwrap();
+
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        assert_eq!(
+            integrity.run(&mut interpreter),
+            Err(Error::RamExecutionEnabled)
+        );
+
+        let config = Config::default().with_ram_execution(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        assert_eq!(
+            integrity.run(&mut interpreter),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+    }
+}
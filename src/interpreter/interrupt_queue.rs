@@ -0,0 +1,214 @@
+//! Interrupt Queue Module
+//!
+//! Interrupts queued by the host ([`Interpreter::queue_interrupt`]) are delivered as soon as the interpreted code
+//! enables them (check [`Interpreter::interrupt`]), at the start of the next instruction, so they are not lost
+//! (or rejected with [`Error::InterruptNotEnabled`]) while the guest is still setting up its trap handler:
+//! - Coalescing: queueing a value already pending doesn't add an interrupt (the highest priority is kept).
+//! - Priorities: higher priorities are delivered first, equal priorities in queueing order.
+//!
+//! Traps clear `mstatus.MIE`, so the next queued interrupt is delivered once the handler returns (`mret`).
+//!
+//! The queue (and its check before each instruction) is only compiled with the `interrupt-queue` feature.
+#[cfg(feature = "resource-limits")]
+use super::Resource;
+use super::{memory::Memory, Error, Interpreter, State};
+
+/// Maximum number of queued interrupts (check [`Interpreter::queue_interrupt`]).
+pub const INTERRUPT_QUEUE_CAPACITY: usize = 8;
+
+/// Pending interrupts (value, priority and instructions retired when queued), in queueing order.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct InterruptQueue {
+    entries: [(i32, u8, u64); INTERRUPT_QUEUE_CAPACITY],
+    len: usize,
+}
+
+impl InterruptQueue {
+    /// Check if no interrupt is queued.
+    #[inline(always)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the queued interrupts, in queueing order.
+    fn entries(&self) -> impl Iterator<Item = (i32, u8, u64)> + '_ {
+        self.entries.iter().take(self.len).copied()
+    }
+
+    /// Queue an interrupt, coalescing it with a pending one of the same value (keeping its queueing time).
+    fn push(&mut self, value: i32, priority: u8, queued: u64) -> Result<(), Error> {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .take(self.len)
+            .find(|(pending, ..)| *pending == value)
+        {
+            entry.1 = entry.1.max(priority);
+            return Ok(());
+        }
+
+        let entry = self
+            .entries
+            .get_mut(self.len)
+            .ok_or(Error::InterruptQueueFull)?;
+        *entry = (value, priority, queued);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the next interrupt to deliver (highest priority, oldest first), with its queueing time.
+    fn pop(&mut self) -> Option<(i32, u64)> {
+        let mut next: Option<(usize, i32, u8, u64)> = None;
+        for (index, (value, priority, queued)) in self.entries().enumerate() {
+            if next.map_or(true, |(_, _, highest, _)| priority > highest) {
+                next = Some((index, value, priority, queued));
+            }
+        }
+        let (index, value, _, queued) = next?;
+
+        // Keep the queueing order of the remaining interrupts
+        let mut entries = [(0, 0, 0); INTERRUPT_QUEUE_CAPACITY];
+        let remaining = self
+            .entries()
+            .enumerate()
+            .filter(|&(other, _)| other != index);
+        for (entry, (_, remaining)) in entries.iter_mut().zip(remaining) {
+            *entry = remaining;
+        }
+        self.entries = entries;
+        self.len -= 1;
+        Some((value, queued))
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Queue an interrupt, delivered as soon as the interpreted code enables interrupts (check [`Interpreter::interrupt`]),
+    /// at the start of the next instruction.
+    ///
+    /// Higher priorities are delivered first (equal priorities in queueing order), one at a time: traps clear
+    /// `mstatus.MIE`, so the next queued interrupt is delivered once the handler returns (`mret`).
+    ///
+    /// Unlike [`Interpreter::interrupt`], this never fails because the interpreted code is not ready yet:
+    /// the interrupt stays queued (across calls to [`Interpreter::run`]) until delivered, or until
+    /// [`Interpreter::clear_queued_interrupts`] or [`Interpreter::reset`] is called.
+    ///
+    /// Arguments:
+    /// - `value`: Value to be passed to the interrupt handler (through `mtval` CSR). Values already queued are coalesced.
+    /// - `priority`: Delivery priority (higher first).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, interrupt queued (or coalesced).
+    /// - `Err(Error)`: Queue is full ([`Error::InterruptQueueFull`], check [`INTERRUPT_QUEUE_CAPACITY`]).
+    pub fn queue_interrupt(&mut self, value: i32, priority: u8) -> Result<(), Error> {
+        let queued = self.registers.control_status.instructions_retired();
+        self.interrupt_queue.push(value, priority, queued)
+    }
+
+    /// Get the queued interrupts (value and priority), in queueing order.
+    pub fn queued_interrupts(&self) -> impl Iterator<Item = (i32, u8)> + '_ {
+        self.interrupt_queue
+            .entries()
+            .map(|(value, priority, _)| (value, priority))
+    }
+
+    /// Drop all queued interrupts (check [`Interpreter::queue_interrupt`]).
+    pub fn clear_queued_interrupts(&mut self) {
+        self.interrupt_queue = InterruptQueue::default();
+    }
+
+    /// Deliver the next queued interrupt, if enabled by the interpreted code.
+    ///
+    /// Returns:
+    /// - `None`: Continue running.
+    /// - `Some(State)`: Interrupt limit reached, the interrupt stays queued.
+    #[cold]
+    pub(crate) fn deliver_queued_interrupt(&mut self) -> Option<State> {
+        if !self.registers.control_status.interrupt_enabled() {
+            return None;
+        }
+
+        // Kept queued when the interrupt limit is reached
+        #[cfg(feature = "resource-limits")]
+        if let Some(state) = self.use_resource(Resource::Interrupts, 1) {
+            return Some(state);
+        }
+
+        if let Some((value, queued)) = self.interrupt_queue.pop() {
+            self.interrupt_trap(value, Some(queued));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "transpiler")]
+    use crate::interpreter::{
+        memory::SliceMemory,
+        registers::{CPURegister, CSOperation},
+        State,
+    };
+
+    #[test]
+    fn test_queue_order() {
+        let mut queue = InterruptQueue::default();
+        queue.push(1, 0, 0).unwrap();
+        queue.push(2, 5, 1).unwrap();
+        queue.push(3, 5, 2).unwrap();
+        queue.push(1, 7, 3).unwrap();
+        assert!(queue.entries().eq([(1, 7, 0), (2, 5, 1), (3, 5, 2)]));
+
+        assert_eq!(queue.pop(), Some((1, 0)));
+        assert_eq!(queue.pop(), Some((2, 1)));
+        assert_eq!(queue.pop(), Some((3, 2)));
+        assert_eq!(queue.pop(), None);
+
+        for value in 0..INTERRUPT_QUEUE_CAPACITY as i32 {
+            queue.push(value, 0, 0).unwrap();
+        }
+        assert_eq!(queue.push(0, 1, 0), Ok(()));
+        assert_eq!(queue.push(-1, 0, 0), Err(Error::InterruptQueueFull));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_delivery() {
+        let mut code = [
+            0x93, 0x00, 0x80, 0x00, // li   ra, 8
+            0x73, 0x90, 0x00, 0x30, // csrw mstatus, ra (MIE)
+            0x93, 0x00, 0x00, 0x80, // li   ra, -2048
+            0x73, 0x90, 0x40, 0x30, // csrw mie, ra (Embive interrupt)
+            0x6f, 0x00, 0x00, 0x00, // j    . (loop)
+            0x13, 0x06, 0x16, 0x00, // addi a2, a2, 1 (handler)
+            0x73, 0x00, 0x20, 0x30, // mret
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 1);
+        let control_status = &mut interpreter.registers.control_status;
+        control_status
+            .operation(Some(CSOperation::Write(0x14)), 0x305)
+            .unwrap();
+
+        // Queued before the guest enables interrupts
+        assert_eq!(interpreter.interrupt(0), Err(Error::InterruptNotEnabled));
+        interpreter.queue_interrupt(10, 0).unwrap();
+        interpreter.queue_interrupt(20, 1).unwrap();
+        interpreter.queue_interrupt(10, 0).unwrap();
+
+        // Delivered once enabled, by priority, one handler at a time
+        let mut values = Vec::new();
+        for _ in 0..16 {
+            assert_eq!(interpreter.run(), Ok(State::Running));
+            if interpreter.program_counter == 0x18 {
+                let control_status = &mut interpreter.registers.control_status;
+                values.push(control_status.operation(None, 0x343).unwrap());
+            }
+        }
+        assert_eq!(values, [20, 10]);
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A2), Ok(2));
+        assert_eq!(interpreter.queued_interrupts().next(), None);
+    }
+}
@@ -0,0 +1,319 @@
+//! Jump Table Module
+//!
+//! Linking of two-part guests: a resident runtime image (e.g. standard library and helpers, cached in flash)
+//! at address `0`, and a small app image at [`APP_OFFSET`](crate::protocol::APP_OFFSET) calling the runtime through a jump table.
+//! App updates only carry the app image, and the runtime can be rebuilt (its functions moved) without
+//! rebuilding the apps, as long as its exports are kept.
+//!
+//! The runtime exports its functions through an export table ([`ExportTable`]) and the app imports them through
+//! the slots of its import table, filled by [`resolve_imports`] (or [`ImageLoader::exports`]) when loading it.
+//! Both tables are trailers of their image (check [`crate::protocol`] for the format).
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::jump_table::{resolve_imports, ExportTable};
+//! use embive::protocol::{
+//!     link_table_size, symbol_hash, write_link_table, EXPORT_TABLE_MAGIC, IMPORT_TABLE_MAGIC,
+//! };
+//!
+//! // Runtime image, exporting `add` at address 0x100
+//! let mut runtime = [0; 0x100 + link_table_size(1)];
+//! write_link_table(EXPORT_TABLE_MAGIC, &[(symbol_hash("add"), 0x100)], &mut runtime[0x100..]);
+//!
+//! // App image, importing `add` (slot at offset 8)
+//! let mut app = [0; 4 + link_table_size(1)];
+//! write_link_table(IMPORT_TABLE_MAGIC, &[(symbol_hash("add"), 0)], &mut app[4..]);
+//!
+//! let exports = ExportTable::parse(&runtime).unwrap();
+//! assert_eq!(resolve_imports(&mut app, &exports), Ok(1));
+//! assert_eq!(app[8..12], 0x100u32.to_le_bytes());
+//! ```
+//!
+//! [`ImageLoader::exports`]: super::loader::ImageLoader::exports
+use core::fmt::{self, Display, Formatter};
+
+use crate::protocol::{
+    image_abi_version, ABI_TRAILER_SIZE, EXPORT_TABLE_MAGIC, IMPORT_TABLE_MAGIC, LINK_ENTRY_SIZE,
+};
+
+/// Link Error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkError {
+    /// The image has no export or import table (magic mismatch).
+    MissingTable,
+    /// The table entry count doesn't fit in the image.
+    InvalidTable,
+    /// An import isn't exported by the runtime. The import index and name hash are provided.
+    UnresolvedImport {
+        /// Import index.
+        index: usize,
+        /// Import name hash (check [`crate::protocol::symbol_hash`]).
+        hash: u32,
+    },
+}
+
+impl core::error::Error for LinkError {}
+
+impl Display for LinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Locate the entries of a link table trailer.
+///
+/// Arguments:
+/// - `image`: The image, ending with the table (and optionally the ABI version trailer).
+/// - `magic`: The table magic.
+///
+/// Returns:
+/// - `Ok((usize, usize))`: Offset of the entries in the image, and entry count.
+/// - `Err(LinkError)`: No table, or invalid entry count.
+fn table_offset(image: &[u8], magic: [u8; 4]) -> Result<(usize, usize), LinkError> {
+    let end = match image_abi_version(image) {
+        Some(_) => image.len() - ABI_TRAILER_SIZE,
+        None => image.len(),
+    };
+    let Some((_, [c0, c1, c2, c3, m0, m1, m2, m3])) = image[..end].split_last_chunk::<8>() else {
+        return Err(LinkError::MissingTable);
+    };
+    if [*m0, *m1, *m2, *m3] != magic {
+        return Err(LinkError::MissingTable);
+    }
+
+    let count = u32::from_le_bytes([*c0, *c1, *c2, *c3]) as usize;
+    let offset = count
+        .checked_mul(LINK_ENTRY_SIZE)
+        .and_then(|size| (end - 8).checked_sub(size))
+        .ok_or(LinkError::InvalidTable)?;
+    Ok((offset, count))
+}
+
+/// Read a link table entry (name hash and address or slot).
+fn entry(bytes: &[u8]) -> (u32, u32) {
+    let word = |index: usize| {
+        u32::from_le_bytes([
+            bytes[index],
+            bytes[index + 1],
+            bytes[index + 2],
+            bytes[index + 3],
+        ])
+    };
+    (word(0), word(4))
+}
+
+/// Runtime Export Table
+///
+/// Functions exported by a runtime image (name hash and address), parsed from its trailer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportTable<'a> {
+    entries: &'a [u8],
+}
+
+impl<'a> ExportTable<'a> {
+    /// Parse the export table of a runtime image.
+    ///
+    /// Arguments:
+    /// - `runtime`: The runtime image (transpiled), ending with the export table.
+    ///
+    /// Returns:
+    /// - `Ok(ExportTable)`: Success, the export table.
+    /// - `Err(LinkError)`: No export table, or invalid entry count.
+    pub fn parse(runtime: &'a [u8]) -> Result<Self, LinkError> {
+        let (offset, count) = table_offset(runtime, EXPORT_TABLE_MAGIC)?;
+        Ok(ExportTable {
+            entries: &runtime[offset..offset + count * LINK_ENTRY_SIZE],
+        })
+    }
+
+    /// Get the number of exports.
+    pub fn len(&self) -> usize {
+        self.entries.len() / LINK_ENTRY_SIZE
+    }
+
+    /// Check if the table has no exports.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the exports (name hash and address).
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + 'a {
+        self.entries.chunks_exact(LINK_ENTRY_SIZE).map(entry)
+    }
+
+    /// Get the address of an export.
+    ///
+    /// Arguments:
+    /// - `hash`: The export name hash (check [`crate::protocol::symbol_hash`]).
+    ///
+    /// Returns:
+    /// - `Some(u32)`: The export address.
+    /// - `None`: Not exported by the runtime.
+    pub fn address(&self, hash: u32) -> Option<u32> {
+        self.iter()
+            .find(|&(export, _)| export == hash)
+            .map(|(_, address)| address)
+    }
+}
+
+/// Resolve the import table of an app image, filling its slots with the runtime export addresses.
+///
+/// Arguments:
+/// - `app`: The app image (transpiled), ending with the import table.
+/// - `exports`: The runtime export table.
+///
+/// Returns:
+/// - `Ok(usize)`: Success, the number of imports resolved.
+/// - `Err(LinkError)`: No import table, invalid entry count or unresolved import (the slots are left as is).
+pub fn resolve_imports(app: &mut [u8], exports: &ExportTable<'_>) -> Result<usize, LinkError> {
+    let (offset, count) = table_offset(app, IMPORT_TABLE_MAGIC)?;
+    let imports = &mut app[offset..offset + count * LINK_ENTRY_SIZE];
+
+    // Check all the imports first, so the image is left untouched on failure
+    for (index, import) in imports.chunks_exact(LINK_ENTRY_SIZE).enumerate() {
+        let (hash, _) = entry(import);
+        if exports.address(hash).is_none() {
+            return Err(LinkError::UnresolvedImport { index, hash });
+        }
+    }
+
+    for import in imports.chunks_exact_mut(LINK_ENTRY_SIZE) {
+        let (hash, _) = entry(import);
+        if let Some(address) = exports.address(hash) {
+            import[4..].copy_from_slice(&address.to_le_bytes());
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{abi_trailer, link_table_size, symbol_hash, write_link_table};
+
+    /// Build an image with a link table trailer (and optionally the ABI version trailer).
+    fn image(magic: [u8; 4], entries: &[(u32, u32)], trailer: bool) -> [u8; 48] {
+        let mut image = [0x13; 48];
+        let size = link_table_size(entries.len());
+        let end = if trailer {
+            image[40..].copy_from_slice(&abi_trailer());
+            40
+        } else {
+            48
+        };
+        write_link_table(magic, entries, &mut image[end - size..end]).unwrap();
+        image
+    }
+
+    #[test]
+    fn test_export_table() {
+        let exports = [(symbol_hash("add"), 0x10), (symbol_hash("sub"), 0x20)];
+        for trailer in [false, true] {
+            let runtime = image(EXPORT_TABLE_MAGIC, &exports, trailer);
+            let table = ExportTable::parse(&runtime).unwrap();
+            assert_eq!(table.len(), 2);
+            assert!(!table.is_empty());
+            assert!(table.iter().eq(exports));
+            assert_eq!(table.address(symbol_hash("sub")), Some(0x20));
+            assert_eq!(table.address(symbol_hash("mul")), None);
+        }
+
+        let app = image(IMPORT_TABLE_MAGIC, &exports, false);
+        assert_eq!(ExportTable::parse(&app), Err(LinkError::MissingTable));
+        assert_eq!(ExportTable::parse(&[0; 4]), Err(LinkError::MissingTable));
+
+        let mut runtime = image(EXPORT_TABLE_MAGIC, &exports, false);
+        runtime[40] = 6;
+        assert_eq!(ExportTable::parse(&runtime), Err(LinkError::InvalidTable));
+    }
+
+    #[test]
+    fn test_resolve_imports() {
+        let runtime = image(
+            EXPORT_TABLE_MAGIC,
+            &[(symbol_hash("add"), 0x10), (symbol_hash("sub"), 0x20)],
+            true,
+        );
+        let exports = ExportTable::parse(&runtime).unwrap();
+
+        let imports = [(symbol_hash("sub"), 0), (symbol_hash("add"), 0)];
+        let mut app = image(IMPORT_TABLE_MAGIC, &imports, false);
+        assert_eq!(resolve_imports(&mut app, &exports), Ok(2));
+        assert_eq!(
+            app[24..40],
+            image(
+                IMPORT_TABLE_MAGIC,
+                &[(symbol_hash("sub"), 0x20), (symbol_hash("add"), 0x10)],
+                false
+            )[24..40]
+        );
+
+        let imports = [(symbol_hash("add"), 0), (symbol_hash("mul"), 0)];
+        let mut app = image(IMPORT_TABLE_MAGIC, &imports, false);
+        assert_eq!(
+            resolve_imports(&mut app, &exports),
+            Err(LinkError::UnresolvedImport {
+                index: 1,
+                hash: symbol_hash("mul")
+            })
+        );
+        assert_eq!(app, image(IMPORT_TABLE_MAGIC, &imports, false));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_two_part_guest() {
+        use crate::interpreter::{memory::RegionMemory, ExitReason, Interpreter, State};
+        use crate::protocol::APP_OFFSET;
+
+        // Runtime: `add` at address 0
+        let mut runtime = [0; 8 + link_table_size(1)];
+        runtime[..8].copy_from_slice(&[
+            0x33, 0x05, 0xb5, 0x00, // add    a0, a0, a1
+            0x67, 0x80, 0x00, 0x00, // ret
+        ]);
+        crate::transpiler::transpile_raw(&mut runtime[..8]).unwrap();
+        write_link_table(
+            EXPORT_TABLE_MAGIC,
+            &[(symbol_hash("add"), 0)],
+            &mut runtime[8..],
+        );
+
+        // App: calls `add` through its import slot (APP_OFFSET + 28)
+        let mut app = [0; 24 + link_table_size(1)];
+        app[..24].copy_from_slice(&[
+            0x13, 0x05, 0x20, 0x00, // li     a0, 2
+            0x93, 0x05, 0x30, 0x00, // li     a1, 3
+            0xb7, 0x02, 0x00, 0x40, // lui    t0, 0x40000
+            0x83, 0xa2, 0xc2, 0x01, // lw     t0, 28(t0)
+            0xe7, 0x80, 0x02, 0x00, // jalr   t0
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ]);
+        crate::transpiler::transpile_raw(&mut app[..24]).unwrap();
+        write_link_table(
+            IMPORT_TABLE_MAGIC,
+            &[(symbol_hash("add"), 0)],
+            &mut app[24..],
+        );
+
+        let exports = ExportTable::parse(&runtime).unwrap();
+        assert_eq!(resolve_imports(&mut app, &exports), Ok(1));
+
+        let mut ram = [0; 4];
+        let mut memory: RegionMemory<'_, 3> = RegionMemory::builder()
+            .code(&runtime)
+            .app(&app)
+            .ram(&mut ram)
+            .build()
+            .unwrap();
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.program_counter = APP_OFFSET;
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.cpu.a0(), 5);
+    }
+}
@@ -0,0 +1,196 @@
+//! Interrupt Latency Module
+//!
+//! Interrupt latency counters, in instructions retired (check
+//! [`super::registers::CSRegisters::instructions_retired`]), to characterize guest interrupt handling
+//! (e.g. RTOS critical sections and handlers) in CI performance gates:
+//! - Entry latency: from the injection ([`Interpreter::interrupt`], [`Interpreter::queue_interrupt`] or
+//!   [`Interpreter::raise_software_interrupt`]) to the trap entry. Direct interrupts always enter right away.
+//! - Handler latency: from the trap entry to `mret` (instructions executed by the handler, `mret` excluded).
+//!   With nested interrupts, only the innermost handler is measured.
+//!
+//! Only compiled with the `interrupt-latency` feature, the counters are updated on each trap entry and `mret`.
+use super::{memory::Memory, Interpreter};
+
+/// Latency statistics, in instructions retired.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of samples.
+    pub count: u64,
+    /// Sum of all samples.
+    pub total: u64,
+    /// Lowest sample (`0` without samples).
+    pub min: u64,
+    /// Highest sample.
+    pub max: u64,
+}
+
+impl LatencyStats {
+    /// Add a sample.
+    fn record(&mut self, sample: u64) {
+        self.min = match self.count {
+            0 => sample,
+            _ => self.min.min(sample),
+        };
+        self.max = self.max.max(sample);
+        self.total = self.total.saturating_add(sample);
+        self.count += 1;
+    }
+
+    /// Get the average sample, rounded down (`None` without samples).
+    pub fn average(&self) -> Option<u64> {
+        self.total.checked_div(self.count)
+    }
+}
+
+/// Interrupt latency statistics (check [`Interpreter::interrupt_latency`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptLatency {
+    /// From the interrupt injection to the trap entry.
+    pub entry: LatencyStats,
+    /// From the trap entry to the handler return (`mret`).
+    pub handler: LatencyStats,
+}
+
+/// Interrupt latency tracking.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct LatencyTracker {
+    stats: InterruptLatency,
+    /// Instructions retired when the pending software interrupt was raised by the host.
+    software_raised: Option<u64>,
+    /// Instructions retired at the last interrupt trap entry, until `mret`.
+    handler_entered: Option<u64>,
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Get the interrupt latency statistics, since creation or the last [`Interpreter::reset_interrupt_latency`].
+    pub fn interrupt_latency(&self) -> &InterruptLatency {
+        &self.latency.stats
+    }
+
+    /// Clear the interrupt latency statistics (interrupts being delivered or handled are still measured).
+    pub fn reset_interrupt_latency(&mut self) {
+        self.latency.stats = InterruptLatency::default();
+    }
+
+    /// Record the injection of a software interrupt, unless one is already pending.
+    pub(crate) fn latency_software_raised(&mut self) {
+        if !self.registers.control_status.mip_software {
+            self.latency.software_raised =
+                Some(self.registers.control_status.instructions_retired());
+        }
+    }
+
+    /// Record an interrupt trap entry.
+    ///
+    /// Arguments:
+    /// - `raised`: Instructions retired when the interrupt was injected (`None` if unknown, e.g. raised by the guest).
+    #[cold]
+    pub(crate) fn latency_trap_entry(&mut self, raised: Option<u64>) {
+        let now = self.registers.control_status.instructions_retired();
+        if let Some(raised) = raised {
+            self.latency.stats.entry.record(now.wrapping_sub(raised));
+        }
+        self.latency.handler_entered = Some(now);
+    }
+
+    /// Record a software interrupt trap entry.
+    #[cold]
+    pub(crate) fn latency_software_entry(&mut self) {
+        let raised = self.latency.software_raised.take();
+        self.latency_trap_entry(raised);
+    }
+
+    /// Record a trap return (`mret`), ending the current interrupt handler, if any.
+    #[inline(always)]
+    pub(crate) fn latency_trap_return(&mut self) {
+        if let Some(entered) = self.latency.handler_entered.take() {
+            let now = self.registers.control_status.instructions_retired();
+            self.latency.stats.handler.record(now.wrapping_sub(entered));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    #[cfg(feature = "interrupt-queue")]
+    use crate::{
+        interpreter::{memory::SliceMemory, registers::CSOperation, State, EMBIVE_INTERRUPT_CODE},
+        transpiler::transpile_raw,
+    };
+
+    #[test]
+    fn test_stats() {
+        let mut stats = LatencyStats::default();
+        assert_eq!(stats.average(), None);
+        for sample in [4, 2, 9] {
+            stats.record(sample);
+        }
+        assert_eq!(
+            stats,
+            LatencyStats {
+                count: 3,
+                total: 15,
+                min: 2,
+                max: 9
+            }
+        );
+        assert_eq!(stats.average(), Some(5));
+    }
+
+    #[cfg(feature = "interrupt-queue")]
+    #[test]
+    fn test_interrupt_latency() {
+        let mut code = [
+            0x13, 0x00, 0x00, 0x00, // 0: nop (main loop)
+            0x6f, 0xf0, 0xdf, 0xff, // 4: j 0
+            0x13, 0x00, 0x00, 0x00, // 8: nop (handler)
+            0x13, 0x00, 0x00, 0x00, // 12: nop
+            0x73, 0x00, 0x20, 0x30, // 16: mret
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 10);
+
+        // Handler at 8, interrupts enabled
+        let control_status = &mut interpreter.registers.control_status;
+        control_status
+            .operation(Some(CSOperation::Write(8)), 0x305)
+            .unwrap();
+        control_status
+            .operation(Some(CSOperation::Write(1 << EMBIVE_INTERRUPT_CODE)), 0x304)
+            .unwrap();
+
+        // Queued while disabled: delivered once enabled
+        interpreter.queue_interrupt(1, 0).unwrap();
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0x8)), 0x300)
+            .unwrap();
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.interrupt_latency().entry.max, 10);
+        assert_eq!(interpreter.interrupt_latency().handler.count, 1);
+        assert_eq!(interpreter.interrupt_latency().handler.max, 2);
+
+        // Direct interrupt: enters right away
+        interpreter.interrupt(2).unwrap();
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        let latency = interpreter.interrupt_latency();
+        assert_eq!(latency.entry.count, 2);
+        assert_eq!(latency.entry.min, 0);
+        assert_eq!(latency.handler.count, 2);
+        assert_eq!(latency.handler.average(), Some(2));
+
+        // mret outside of a handler isn't measured
+        interpreter.reset_interrupt_latency();
+        interpreter.program_counter = 16;
+        interpreter.instruction_limit = 1;
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(
+            interpreter.interrupt_latency(),
+            &InterruptLatency::default()
+        );
+    }
+}
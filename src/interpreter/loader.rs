@@ -0,0 +1,753 @@
+//! Loader Module
+//!
+//! Authenticated (and optionally encrypted) image loading, e.g. for plugin binaries delivered over the air.
+//! Images are wrapped in a container ([`ImageHeader`], body and signature), streamed into a host buffer
+//! (which becomes the interpreter code) and only handed out once the signature is verified.
+//!
+//! Container format:
+//! - Header ([`IMAGE_HEADER_SIZE`] bytes): magic ([`IMAGE_MAGIC`]), flags ([`IMAGE_FLAG_ENCRYPTED`],
+//!   [`IMAGE_FLAG_COMPRESSED`]), body size (as stored) and signature size (little-endian `u32`s).
+//! - Body: the transpiled image (compressed if [`IMAGE_FLAG_COMPRESSED`] is set, then encrypted if
+//!   [`IMAGE_FLAG_ENCRYPTED`] is set).
+//! - Signature, over the header and the body as stored (encrypt-then-sign).
+//!
+//! Cryptography is left to the host ([`ImageVerifier`] and [`ImageDecryptor`]), so any algorithm
+//! (and hardware accelerator) can be used.
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::loader::{ImageHeader, ImageLoader, ImageVerifier};
+//!
+//! // Toy verifier (use a real signature scheme, e.g. Ed25519)
+//! struct Sum(u8);
+//!
+//! impl ImageVerifier for Sum {
+//!     fn update(&mut self, data: &[u8]) {
+//!         self.0 = data.iter().fold(self.0, |sum, byte| sum.wrapping_add(*byte));
+//!     }
+//!
+//!     fn verify(&mut self, signature: &[u8]) -> bool {
+//!         signature == [self.0]
+//!     }
+//! }
+//!
+//! // Build the container
+//! let body = [0x1f, 0x00, 0x10, 0x00]; // ebreak (already transpiled)
+//! let header = ImageHeader { flags: 0, body_size: 4, signature_size: 1 }.to_bytes();
+//! let mut signer = Sum(0);
+//! signer.update(&header);
+//! signer.update(&body);
+//! let mut image = header.to_vec();
+//! image.extend_from_slice(&body);
+//! image.push(signer.0);
+//!
+//! // Load it, in chunks (e.g. as received)
+//! let mut code = [0; 64];
+//! let mut verifier = Sum(0);
+//! let mut loader = ImageLoader::new(&mut code).verifier(&mut verifier);
+//! for chunk in image.chunks(3) {
+//!     loader.write(chunk).unwrap();
+//! }
+//! assert_eq!(loader.finish().unwrap(), body);
+//! ```
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "compression")]
+use super::compression::{CompressionError, Decompressor};
+use super::jump_table::{resolve_imports, ExportTable, LinkError};
+
+/// Image container magic.
+pub const IMAGE_MAGIC: [u8; 4] = *b"EMBI";
+
+/// Image container header size, in bytes.
+pub const IMAGE_HEADER_SIZE: usize = 16;
+
+/// Image flag: the body is encrypted (check [`ImageDecryptor`]).
+pub const IMAGE_FLAG_ENCRYPTED: u32 = 1 << 0;
+
+/// Image flag: the body is compressed (check [`compression`](super::compression), requires the
+/// `compression` feature).
+pub const IMAGE_FLAG_COMPRESSED: u32 = 1 << 1;
+
+/// Supported image flags.
+const IMAGE_FLAGS_SUPPORTED: u32 = IMAGE_FLAG_ENCRYPTED
+    | if cfg!(feature = "compression") {
+        IMAGE_FLAG_COMPRESSED
+    } else {
+        0
+    };
+
+/// Compressed body decryption chunk size, in bytes (stack buffer).
+#[cfg(feature = "compression")]
+const DECRYPT_CHUNK_SIZE: usize = 64;
+
+/// Image container header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageHeader {
+    /// Image flags ([`IMAGE_FLAG_ENCRYPTED`], [`IMAGE_FLAG_COMPRESSED`]).
+    pub flags: u32,
+    /// Body (transpiled image, as stored) size, in bytes.
+    pub body_size: u32,
+    /// Signature size, in bytes.
+    pub signature_size: u32,
+}
+
+impl ImageHeader {
+    /// Parse an image container header.
+    ///
+    /// Arguments:
+    /// - `bytes`: Header bytes.
+    ///
+    /// Returns:
+    /// - `Ok(ImageHeader)`: Success, parsed header.
+    /// - `Err(LoadError)`: Invalid magic or unsupported flags.
+    pub fn parse(bytes: &[u8; IMAGE_HEADER_SIZE]) -> Result<Self, LoadError> {
+        let word = |index: usize| {
+            u32::from_le_bytes([
+                bytes[index * 4],
+                bytes[index * 4 + 1],
+                bytes[index * 4 + 2],
+                bytes[index * 4 + 3],
+            ])
+        };
+        if bytes[..4] != IMAGE_MAGIC {
+            return Err(LoadError::InvalidMagic);
+        }
+
+        let header = ImageHeader {
+            flags: word(1),
+            body_size: word(2),
+            signature_size: word(3),
+        };
+        if header.flags & !IMAGE_FLAGS_SUPPORTED != 0 {
+            return Err(LoadError::UnsupportedFlags(header.flags));
+        }
+        Ok(header)
+    }
+
+    /// Encode the image container header (e.g. when packaging an image).
+    pub fn to_bytes(&self) -> [u8; IMAGE_HEADER_SIZE] {
+        let mut bytes = [0; IMAGE_HEADER_SIZE];
+        bytes[..4].copy_from_slice(&IMAGE_MAGIC);
+        bytes[4..8].copy_from_slice(&self.flags.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.body_size.to_le_bytes());
+        bytes[12..].copy_from_slice(&self.signature_size.to_le_bytes());
+        bytes
+    }
+
+    /// Check if the body is encrypted.
+    pub fn encrypted(&self) -> bool {
+        self.flags & IMAGE_FLAG_ENCRYPTED != 0
+    }
+
+    /// Check if the body is compressed.
+    pub fn compressed(&self) -> bool {
+        self.flags & IMAGE_FLAG_COMPRESSED != 0
+    }
+
+    /// Get the size of the body (as stored) and signature, in bytes.
+    ///
+    /// This is the loader buffer size needed, unless the body is compressed (then the decompressed
+    /// body size plus the signature size is needed).
+    pub fn payload_size(&self) -> usize {
+        self.body_size as usize + self.signature_size as usize
+    }
+}
+
+/// Image Load Error
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    /// Not an image container (magic mismatch).
+    InvalidMagic,
+    /// Image flags aren't supported. The flags are provided.
+    UnsupportedFlags(u32),
+    /// Body and signature don't fit in the loader buffer. The needed size, in bytes, is provided.
+    BufferTooSmall(usize),
+    /// Image is encrypted, but no decryptor is set (check [`ImageLoader::decryptor`]).
+    NoDecryptor,
+    /// Failed to decrypt the image (returned by [`ImageDecryptor::decrypt`]).
+    Decryption,
+    /// Failed to decompress the image (corrupted compressed body).
+    Decompression,
+    /// Image ended before the signature.
+    Truncated,
+    /// Data written after the signature.
+    TrailingData,
+    /// Signature verification failed.
+    InvalidSignature,
+    /// Failed to resolve the app import table (check [`ImageLoader::exports`]).
+    Link(LinkError),
+}
+
+impl core::error::Error for LoadError {}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Image signature verifier (e.g. hash and public key check).
+pub trait ImageVerifier {
+    /// Feed signed data (the header, then the body as stored), in order.
+    ///
+    /// Arguments:
+    /// - `data`: Signed data.
+    fn update(&mut self, data: &[u8]);
+
+    /// Verify the signature of all the data fed.
+    ///
+    /// Arguments:
+    /// - `signature`: Image signature.
+    ///
+    /// Returns `true` if the signature is valid.
+    fn verify(&mut self, signature: &[u8]) -> bool;
+}
+
+/// Image body decryptor (e.g. a stream cipher or a block cipher in counter mode).
+pub trait ImageDecryptor {
+    /// Decrypt part of the body, in place. Parts are decrypted in order.
+    ///
+    /// Arguments:
+    /// - `offset`: Offset of the data in the body, in bytes.
+    /// - `data`: Encrypted data, to be replaced by the decrypted data.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, the data was decrypted.
+    /// - `Err(LoadError)`: Decryption failed ([`LoadError::Decryption`]).
+    fn decrypt(&mut self, offset: usize, data: &mut [u8]) -> Result<(), LoadError>;
+}
+
+/// Image Loader
+///
+/// Streams an image container into a host buffer, feeding the verifier and decrypting the body on the fly,
+/// so the container never needs to be fully buffered. The body is only returned (by [`ImageLoader::finish`])
+/// once the signature is verified; on failure, it is zeroed.
+///
+/// Compressed bodies ([`IMAGE_FLAG_COMPRESSED`]) are decompressed on the fly (after decryption) into the
+/// start of the buffer, with the signature kept at its end.
+///
+/// Without a verifier ([`ImageLoader::verifier`]), signatures aren't checked.
+pub struct ImageLoader<'l> {
+    buffer: &'l mut [u8],
+    verifier: Option<&'l mut (dyn ImageVerifier + 'l)>,
+    decryptor: Option<&'l mut (dyn ImageDecryptor + 'l)>,
+    exports: Option<&'l ExportTable<'l>>,
+    header_bytes: [u8; IMAGE_HEADER_SIZE],
+    header: Option<ImageHeader>,
+    received: usize,
+    #[cfg(feature = "compression")]
+    decompressor: Decompressor,
+}
+
+impl fmt::Debug for ImageLoader<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageLoader")
+            .field("buffer", &self.buffer.len())
+            .field("verifier", &self.verifier.is_some())
+            .field("decryptor", &self.decryptor.is_some())
+            .field("exports", &self.exports)
+            .field("header", &self.header)
+            .field("received", &self.received)
+            .finish()
+    }
+}
+
+impl<'l> ImageLoader<'l> {
+    /// Create a new loader, without verifier nor decryptor.
+    ///
+    /// Arguments:
+    /// - `buffer`: Destination buffer, holding the (decompressed) body, later used as code, and the
+    ///   signature (check [`ImageHeader::payload_size`]).
+    pub fn new(buffer: &'l mut [u8]) -> Self {
+        ImageLoader {
+            buffer,
+            verifier: None,
+            decryptor: None,
+            exports: None,
+            header_bytes: [0; IMAGE_HEADER_SIZE],
+            header: None,
+            received: 0,
+            #[cfg(feature = "compression")]
+            decompressor: Decompressor::new(),
+        }
+    }
+
+    /// Set the signature verifier (images are rejected unless their signature is valid).
+    pub fn verifier(mut self, verifier: &'l mut (dyn ImageVerifier + 'l)) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Set the body decryptor, for encrypted images ([`IMAGE_FLAG_ENCRYPTED`]).
+    pub fn decryptor(mut self, decryptor: &'l mut (dyn ImageDecryptor + 'l)) -> Self {
+        self.decryptor = Some(decryptor);
+        self
+    }
+
+    /// Set the runtime export table, for app images of two-part guests (check [`crate::interpreter::jump_table`]).
+    ///
+    /// The app import table is resolved once the signature is verified (the signature covers the unresolved image).
+    pub fn exports(mut self, exports: &'l ExportTable<'l>) -> Self {
+        self.exports = Some(exports);
+        self
+    }
+
+    /// Get the image header, once received.
+    pub fn header(&self) -> Option<ImageHeader> {
+        self.header
+    }
+
+    /// Write the next part of the image container (any size).
+    ///
+    /// The loader should be dropped after an error.
+    ///
+    /// Arguments:
+    /// - `data`: Image container data.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, data loaded.
+    /// - `Err(LoadError)`: Invalid header, buffer too small, decryption or decompression failed or trailing data.
+    pub fn write(&mut self, mut data: &[u8]) -> Result<(), LoadError> {
+        while !data.is_empty() {
+            let Some(header) = self.header else {
+                let count = data.len().min(IMAGE_HEADER_SIZE - self.received);
+                self.header_bytes[self.received..self.received + count]
+                    .copy_from_slice(&data[..count]);
+                self.received += count;
+                data = &data[count..];
+
+                if self.received == IMAGE_HEADER_SIZE {
+                    self.header = Some(self.parse_header()?);
+                }
+                continue;
+            };
+
+            let offset = self.received - IMAGE_HEADER_SIZE;
+            let count = data.len().min(header.payload_size() - offset);
+            if count == 0 {
+                return Err(LoadError::TrailingData);
+            }
+            let (part, rest) = data.split_at(count);
+            self.received += count;
+            data = rest;
+
+            // Body part (signed as stored, then decrypted and decompressed)
+            let body_size = header.body_size as usize;
+            let (body, signature) = part.split_at(count.min(body_size.saturating_sub(offset)));
+            if !body.is_empty() {
+                if let Some(verifier) = self.verifier.as_mut() {
+                    verifier.update(body);
+                }
+                if header.compressed() {
+                    self.decompress(&header, offset, body)?;
+                } else {
+                    let stored = &mut self.buffer[offset..offset + body.len()];
+                    stored.copy_from_slice(body);
+                    if header.encrypted() {
+                        if let Some(decryptor) = self.decryptor.as_mut() {
+                            decryptor.decrypt(offset, stored)?;
+                        }
+                    }
+                }
+            }
+
+            // Signature part
+            if !signature.is_empty() {
+                let start = Self::signature_start(&header, self.buffer.len()) + offset + body.len()
+                    - body_size;
+                self.buffer[start..start + signature.len()].copy_from_slice(signature);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse and check the received header, feeding it to the verifier.
+    fn parse_header(&mut self) -> Result<ImageHeader, LoadError> {
+        let header = ImageHeader::parse(&self.header_bytes)?;
+        if header.compressed() {
+            // The decompressed size is checked once received
+            if header.signature_size as usize > self.buffer.len() {
+                return Err(LoadError::BufferTooSmall(header.signature_size as usize));
+            }
+        } else if header.payload_size() > self.buffer.len() {
+            return Err(LoadError::BufferTooSmall(header.payload_size()));
+        }
+        if header.encrypted() && self.decryptor.is_none() {
+            return Err(LoadError::NoDecryptor);
+        }
+
+        if let Some(verifier) = self.verifier.as_mut() {
+            verifier.update(&self.header_bytes);
+        }
+        Ok(header)
+    }
+
+    /// Get the signature offset in the buffer (after the body, or at the end of the buffer if compressed).
+    fn signature_start(header: &ImageHeader, buffer_size: usize) -> usize {
+        if header.compressed() {
+            buffer_size - header.signature_size as usize
+        } else {
+            header.body_size as usize
+        }
+    }
+
+    /// Decrypt (if needed) and decompress part of a compressed body.
+    #[cfg(feature = "compression")]
+    fn decompress(
+        &mut self,
+        header: &ImageHeader,
+        mut offset: usize,
+        body: &[u8],
+    ) -> Result<(), LoadError> {
+        let signature_size = header.signature_size as usize;
+        let error = |error| match error {
+            CompressionError::BufferTooSmall(size) => {
+                LoadError::BufferTooSmall(size + signature_size)
+            }
+            _ => LoadError::Decompression,
+        };
+        let end = self.buffer.len() - signature_size;
+        let output = &mut self.buffer[..end];
+
+        let Some(decryptor) = self.decryptor.as_mut().filter(|_| header.encrypted()) else {
+            return self.decompressor.write(body, output).map_err(error);
+        };
+        let mut scratch = [0; DECRYPT_CHUNK_SIZE];
+        for chunk in body.chunks(DECRYPT_CHUNK_SIZE) {
+            let data = &mut scratch[..chunk.len()];
+            data.copy_from_slice(chunk);
+            decryptor.decrypt(offset, data)?;
+            self.decompressor.write(data, output).map_err(error)?;
+            offset += chunk.len();
+        }
+        Ok(())
+    }
+
+    /// Compressed bodies aren't supported (rejected by [`ImageHeader::parse`]).
+    #[cfg(not(feature = "compression"))]
+    fn decompress(&mut self, _: &ImageHeader, _: usize, _: &[u8]) -> Result<(), LoadError> {
+        Err(LoadError::UnsupportedFlags(IMAGE_FLAG_COMPRESSED))
+    }
+
+    /// Get the (decompressed) body size, once fully received.
+    fn body_size(&self, header: &ImageHeader) -> Result<usize, LoadError> {
+        if !header.compressed() {
+            return Ok(header.body_size as usize);
+        }
+
+        #[cfg(feature = "compression")]
+        return self
+            .decompressor
+            .finish()
+            .map_err(|_| LoadError::Decompression);
+        #[cfg(not(feature = "compression"))]
+        Err(LoadError::UnsupportedFlags(IMAGE_FLAG_COMPRESSED))
+    }
+
+    /// Finish loading, verifying the signature.
+    ///
+    /// Returns:
+    /// - `Ok(&[u8])`: Success, the (decrypted and decompressed) transpiled image, at the start of the buffer.
+    /// - `Err(LoadError)`: Image truncated, corrupted compressed body, invalid signature (the body is zeroed)
+    ///   or unresolved app imports.
+    pub fn finish(self) -> Result<&'l [u8], LoadError> {
+        let header = match self.header {
+            Some(header) if self.received == IMAGE_HEADER_SIZE + header.payload_size() => header,
+            _ => return Err(LoadError::Truncated),
+        };
+        let body_size = self.body_size(&header)?;
+
+        let start = Self::signature_start(&header, self.buffer.len());
+        let (body, signature) = self.buffer.split_at_mut(start);
+        if let Some(verifier) = self.verifier {
+            if !verifier.verify(&signature[..header.signature_size as usize]) {
+                body.fill(0);
+                return Err(LoadError::InvalidSignature);
+            }
+        }
+
+        let body = &mut body[..body_size];
+        if let Some(exports) = self.exports {
+            resolve_imports(body, exports).map_err(LoadError::Link)?;
+        }
+
+        Ok(body)
+    }
+
+    /// Load a whole image container (check [`ImageLoader::write`] and [`ImageLoader::finish`]).
+    ///
+    /// Arguments:
+    /// - `image`: Image container.
+    ///
+    /// Returns:
+    /// - `Ok(&[u8])`: Success, the (decrypted and decompressed) transpiled image, at the start of the buffer.
+    /// - `Err(LoadError)`: Invalid image or signature.
+    pub fn load(mut self, image: &[u8]) -> Result<&'l [u8], LoadError> {
+        self.write(image)?;
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Body: ebreak, c.nop, c.nop (already transpiled)
+    const BODY: [u8; 8] = [0x1f, 0x00, 0x10, 0x00, 0x01, 0x00, 0x01, 0x00];
+
+    /// Toy verifier: the signature is the wrapping sum of the signed bytes.
+    struct Sum(u8);
+
+    impl ImageVerifier for Sum {
+        fn update(&mut self, data: &[u8]) {
+            self.0 = data
+                .iter()
+                .fold(self.0, |sum, byte| sum.wrapping_add(*byte));
+        }
+
+        fn verify(&mut self, signature: &[u8]) -> bool {
+            signature == [self.0]
+        }
+    }
+
+    /// Toy decryptor: XOR with the body offset.
+    struct Xor;
+
+    impl ImageDecryptor for Xor {
+        fn decrypt(&mut self, offset: usize, data: &mut [u8]) -> Result<(), LoadError> {
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte ^= (offset + i) as u8;
+            }
+            Ok(())
+        }
+    }
+
+    /// Build a signed container, encrypting the body if requested.
+    fn container(encrypted: bool) -> [u8; IMAGE_HEADER_SIZE + 9] {
+        let header = ImageHeader {
+            flags: if encrypted { IMAGE_FLAG_ENCRYPTED } else { 0 },
+            body_size: BODY.len() as u32,
+            signature_size: 1,
+        }
+        .to_bytes();
+        let mut body = BODY;
+        if encrypted {
+            Xor.decrypt(0, &mut body).unwrap();
+        }
+
+        let mut signer = Sum(0);
+        signer.update(&header);
+        signer.update(&body);
+
+        let mut image = [0; IMAGE_HEADER_SIZE + 9];
+        image[..IMAGE_HEADER_SIZE].copy_from_slice(&header);
+        image[IMAGE_HEADER_SIZE..IMAGE_HEADER_SIZE + 8].copy_from_slice(&body);
+        image[IMAGE_HEADER_SIZE + 8] = signer.0;
+        image
+    }
+
+    #[test]
+    fn test_header() {
+        let header = ImageHeader {
+            flags: IMAGE_FLAG_ENCRYPTED,
+            body_size: 100,
+            signature_size: 64,
+        };
+        assert_eq!(ImageHeader::parse(&header.to_bytes()), Ok(header));
+        assert!(header.encrypted());
+        assert_eq!(header.payload_size(), 164);
+
+        let mut bytes = header.to_bytes();
+        bytes[4] = 0b100;
+        assert_eq!(
+            ImageHeader::parse(&bytes),
+            Err(LoadError::UnsupportedFlags(0b100))
+        );
+        bytes[4] = IMAGE_FLAG_COMPRESSED as u8;
+        assert_eq!(
+            ImageHeader::parse(&bytes).is_ok(),
+            cfg!(feature = "compression")
+        );
+        bytes[0] = 0;
+        assert_eq!(ImageHeader::parse(&bytes), Err(LoadError::InvalidMagic));
+    }
+
+    #[test]
+    fn test_load_signed() {
+        let image = container(false);
+        let mut buffer = [0; 16];
+        let mut verifier = Sum(0);
+        let loader = ImageLoader::new(&mut buffer).verifier(&mut verifier);
+        assert_eq!(loader.load(&image), Ok(&BODY[..]));
+
+        // Tampered body
+        let mut tampered = image;
+        tampered[IMAGE_HEADER_SIZE] ^= 0x80;
+        let mut verifier = Sum(0);
+        let loader = ImageLoader::new(&mut buffer).verifier(&mut verifier);
+        assert_eq!(loader.load(&tampered), Err(LoadError::InvalidSignature));
+        assert_eq!(buffer[..BODY.len()], [0; 8]);
+    }
+
+    #[test]
+    fn test_load_encrypted_streaming() {
+        let image = container(true);
+        for chunk_size in 1..image.len() {
+            let mut buffer = [0; 9];
+            let mut verifier = Sum(0);
+            let mut decryptor = Xor;
+            let mut loader = ImageLoader::new(&mut buffer)
+                .verifier(&mut verifier)
+                .decryptor(&mut decryptor);
+            for chunk in image.chunks(chunk_size) {
+                loader.write(chunk).unwrap();
+            }
+            assert!(loader.header().unwrap().encrypted());
+            assert_eq!(loader.finish(), Ok(&BODY[..]));
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let image = container(true);
+        let mut buffer = [0; 16];
+        assert_eq!(
+            ImageLoader::new(&mut buffer).load(&image),
+            Err(LoadError::NoDecryptor)
+        );
+        assert_eq!(
+            ImageLoader::new(&mut buffer[..8]).load(&image),
+            Err(LoadError::BufferTooSmall(9))
+        );
+
+        let mut decryptor = Xor;
+        let loader = ImageLoader::new(&mut buffer).decryptor(&mut decryptor);
+        assert_eq!(
+            loader.load(&image[..image.len() - 1]),
+            Err(LoadError::Truncated)
+        );
+
+        let mut decryptor = Xor;
+        let mut loader = ImageLoader::new(&mut buffer).decryptor(&mut decryptor);
+        loader.write(&image).unwrap();
+        assert_eq!(loader.write(&[0]), Err(LoadError::TrailingData));
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_load_compressed() {
+        use crate::interpreter::compression::{compress, max_compressed_size};
+
+        let code = BODY.repeat(16);
+        let mut body = [0; max_compressed_size(128)];
+        let size = compress(&code, &mut body).unwrap();
+        let body = &mut body[..size];
+        Xor.decrypt(0, body).unwrap();
+
+        let header = ImageHeader {
+            flags: IMAGE_FLAG_ENCRYPTED | IMAGE_FLAG_COMPRESSED,
+            body_size: size as u32,
+            signature_size: 1,
+        }
+        .to_bytes();
+        let mut signer = Sum(0);
+        signer.update(&header);
+        signer.update(body);
+        let mut image = header.to_vec();
+        image.extend_from_slice(body);
+        image.push(signer.0);
+        assert!(image.len() < code.len());
+
+        for chunk_size in [1, 5, 64, image.len()] {
+            let mut buffer = [0; 129];
+            let mut verifier = Sum(0);
+            let mut decryptor = Xor;
+            let mut loader = ImageLoader::new(&mut buffer)
+                .verifier(&mut verifier)
+                .decryptor(&mut decryptor);
+            for chunk in image.chunks(chunk_size) {
+                loader.write(chunk).unwrap();
+            }
+            assert!(loader.header().unwrap().compressed());
+            assert_eq!(loader.finish(), Ok(&code[..]));
+        }
+
+        // Decompressed body and signature don't fit
+        let mut buffer = [0; 128];
+        let mut decryptor = Xor;
+        let loader = ImageLoader::new(&mut buffer).decryptor(&mut decryptor);
+        assert_eq!(loader.load(&image), Err(LoadError::BufferTooSmall(129)));
+
+        // Tampered signature
+        let mut tampered = image.clone();
+        *tampered.last_mut().unwrap() ^= 0x80;
+        let mut buffer = [0; 129];
+        let mut verifier = Sum(0);
+        let mut decryptor = Xor;
+        let loader = ImageLoader::new(&mut buffer)
+            .verifier(&mut verifier)
+            .decryptor(&mut decryptor);
+        assert_eq!(loader.load(&tampered), Err(LoadError::InvalidSignature));
+        assert_eq!(buffer[..128], [0; 128]);
+
+        // Corrupted compressed body (decompressed size too small)
+        let mut corrupted = image;
+        corrupted[IMAGE_HEADER_SIZE] ^= 0x81;
+        let mut buffer = [0; 129];
+        let mut decryptor = Xor;
+        let loader = ImageLoader::new(&mut buffer).decryptor(&mut decryptor);
+        assert_eq!(loader.load(&corrupted), Err(LoadError::Decompression));
+    }
+
+    #[test]
+    fn test_load_app() {
+        use crate::interpreter::jump_table::ExportTable;
+        use crate::protocol::{
+            link_table_size, symbol_hash, write_link_table, EXPORT_TABLE_MAGIC, IMPORT_TABLE_MAGIC,
+        };
+
+        let mut runtime = [0; link_table_size(1)];
+        write_link_table(
+            EXPORT_TABLE_MAGIC,
+            &[(symbol_hash("add"), 0x10)],
+            &mut runtime,
+        );
+        let exports = ExportTable::parse(&runtime).unwrap();
+
+        // App image: ebreak, import table (slot at offset 8)
+        let mut body = [0; 4 + link_table_size(1)];
+        body[..4].copy_from_slice(&BODY[..4]);
+        write_link_table(
+            IMPORT_TABLE_MAGIC,
+            &[(symbol_hash("add"), 0)],
+            &mut body[4..],
+        );
+        let header = ImageHeader {
+            flags: 0,
+            body_size: body.len() as u32,
+            signature_size: 0,
+        };
+        let mut image = header.to_bytes().to_vec();
+        image.extend_from_slice(&body);
+
+        let mut buffer = [0; 32];
+        let loader = ImageLoader::new(&mut buffer).exports(&exports);
+        let app = loader.load(&image).unwrap();
+        assert_eq!(app[8..12], 0x10u32.to_le_bytes());
+
+        // Unresolved import
+        let mut runtime = [0; link_table_size(0)];
+        write_link_table(EXPORT_TABLE_MAGIC, &[], &mut runtime);
+        let exports = ExportTable::parse(&runtime).unwrap();
+        let loader = ImageLoader::new(&mut buffer).exports(&exports);
+        assert_eq!(
+            loader.load(&image),
+            Err(LoadError::Link(LinkError::UnresolvedImport {
+                index: 0,
+                hash: symbol_hash("add")
+            }))
+        );
+    }
+}
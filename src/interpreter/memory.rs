@@ -1,7 +1,9 @@
 //! Memory Module
 //!
 //! This module implements the memory interface for the Embive interpreter.
+mod endianness;
 mod memory_type;
+mod region;
 
 use core::{fmt::Debug, ops::Range};
 
@@ -9,11 +11,15 @@ use crate::interpreter::utils::unlikely;
 
 use super::error::Error;
 
+#[doc(inline)]
+pub use endianness::MemoryEndianness;
 #[doc(inline)]
 pub use memory_type::MemoryType;
+#[doc(inline)]
+pub use region::{Permissions, Region, RegionError, RegionMemory, RegionMemoryBuilder};
 
 /// RAM address offset for default memory implementations.
-pub const RAM_OFFSET: u32 = 0x80000000;
+pub const RAM_OFFSET: u32 = crate::protocol::RAM_OFFSET;
 
 /// A helper function to check if a slice range is valid.
 ///
@@ -21,12 +27,18 @@ pub const RAM_OFFSET: u32 = 0x80000000;
 /// - `slice`: The slice to check.
 /// - `start`: The start index of the range.
 /// - `len`: The length of the range.
+/// - `address`: The memory address being accessed (for error reporting).
 ///
 /// Returns:
 /// - `Ok(Range<usize>)`: The valid range.
 /// - `Err(Error)`: An error occurred. Ex.: Memory address is out of bounds.
 #[inline(always)]
-fn checked_slice_range(slice: &[u8], start: usize, len: usize) -> Result<Range<usize>, Error> {
+fn checked_slice_range(
+    slice: &[u8],
+    start: usize,
+    len: usize,
+    address: u32,
+) -> Result<Range<usize>, Error> {
     // Check for overflow when calculating the end index.
     let end = start
         .checked_add(len)
@@ -34,7 +46,7 @@ fn checked_slice_range(slice: &[u8], start: usize, len: usize) -> Result<Range<u
 
     // Check bounds, start is always <= end here.
     if unlikely(end > slice.len()) {
-        return Err(Error::InvalidMemoryAddress(end as u32));
+        return Err(Error::InvalidMemoryAddress { address, len });
     }
 
     Ok(start..end)
@@ -45,10 +57,14 @@ fn checked_slice_range(slice: &[u8], start: usize, len: usize) -> Result<Range<u
 /// This trait implements the memory interface for the Embive interpreter.
 /// It should support loading bytes from the code (0x00000000) region, as well as loading and storing to the RAM ([`RAM_OFFSET`]).
 /// RISC-V is little-endian, bytes should be loaded / stored as that.
+///
+/// Only the byte accesses ([`Memory::load_bytes`], [`Memory::mut_bytes`] and [`Memory::store_bytes`]) are required.
+/// Typed accesses (`load_u8/16/32`, `store_u8/16/32`) and bulk copies (`copy_from_guest`, `copy_to_guest`)
+/// have default implementations on top of them, which can be overridden for performance.
 pub trait Memory {
     /// Load `len` bytes from memory address.
     ///
-    /// RISC-V is little-endian, always use `to_le_bytes()` and `from_le_bytes()`.
+    /// RISC-V is little-endian, always convert values with [`MemoryEndianness`].
     ///
     /// Arguments:
     /// - `address`: Memory address to get (code or RAM).
@@ -61,7 +77,7 @@ pub trait Memory {
 
     /// Get mutable reference to `len` bytes from memory address.
     ///
-    /// RISC-V is little-endian, always use `to_le_bytes()` and `from_le_bytes()`.
+    /// RISC-V is little-endian, always convert values with [`MemoryEndianness`].
     ///
     /// Arguments:
     /// - `address`: Memory address to get (only RAM).
@@ -74,7 +90,7 @@ pub trait Memory {
 
     /// Store `len` bytes to memory address.
     ///
-    /// RISC-V is little-endian, always use `to_le_bytes()` and `from_le_bytes()`.
+    /// RISC-V is little-endian, always convert values with [`MemoryEndianness`].
     ///
     /// Arguments:
     /// - `address`: The memory address to store (only RAM).
@@ -84,6 +100,189 @@ pub trait Memory {
     /// - `Ok(())`: Bytes were stored successfully.
     /// - `Err(Error)`: An error occurred. Ex.: Memory address is out of bounds.
     fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error>;
+
+    /// Load a byte from memory address.
+    ///
+    /// Typed accesses are used by the interpreter (instruction fetch, loads and stores).
+    /// The default implementation uses [`Memory::load_bytes`], override it to avoid the slice length checks.
+    ///
+    /// Arguments:
+    /// - `address`: Memory address to load (code or RAM).
+    ///
+    /// Returns:
+    /// - `Ok(u8)`: Loaded value.
+    /// - `Err(Error)`: An error occurred. Ex.: Memory address is out of bounds.
+    #[inline]
+    fn load_u8(&mut self, address: u32) -> Result<u8, Error> {
+        load_array(self, address).map(u8::from_guest_bytes)
+    }
+
+    /// Load a half-word (little-endian) from memory address (check [`Memory::load_u8`]).
+    #[inline]
+    fn load_u16(&mut self, address: u32) -> Result<u16, Error> {
+        load_array(self, address).map(u16::from_guest_bytes)
+    }
+
+    /// Load a word (little-endian) from memory address (check [`Memory::load_u8`]).
+    #[inline]
+    fn load_u32(&mut self, address: u32) -> Result<u32, Error> {
+        load_array(self, address).map(u32::from_guest_bytes)
+    }
+
+    /// Store a byte to memory address.
+    ///
+    /// The default implementation uses [`Memory::mut_bytes`], override it to avoid the slice length checks.
+    ///
+    /// Arguments:
+    /// - `address`: Memory address to store (only RAM).
+    /// - `value`: Value to store.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Value was stored successfully.
+    /// - `Err(Error)`: An error occurred. Ex.: Memory address is out of bounds.
+    #[inline]
+    fn store_u8(&mut self, address: u32, value: u8) -> Result<(), Error> {
+        store_array(self, address, value.to_guest_bytes())
+    }
+
+    /// Store a half-word (little-endian) to memory address (check [`Memory::store_u8`]).
+    #[inline]
+    fn store_u16(&mut self, address: u32, value: u16) -> Result<(), Error> {
+        store_array(self, address, value.to_guest_bytes())
+    }
+
+    /// Store a word (little-endian) to memory address (check [`Memory::store_u8`]).
+    #[inline]
+    fn store_u32(&mut self, address: u32, value: u32) -> Result<(), Error> {
+        store_array(self, address, value.to_guest_bytes())
+    }
+
+    /// Copy bytes from guest memory to a host buffer (bulk transfer, e.g. syscall buffers).
+    ///
+    /// Arguments:
+    /// - `address`: Memory address to copy from (code or RAM).
+    /// - `buffer`: Host buffer, filled with `buffer.len()` bytes.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Bytes were copied successfully.
+    /// - `Err(Error)`: An error occurred. Ex.: Memory address is out of bounds.
+    #[inline]
+    fn copy_from_guest(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        let bytes = self.load_bytes(address, buffer.len())?;
+        if unlikely(bytes.len() != buffer.len()) {
+            return Err(Error::InvalidMemoryAccessLength(buffer.len()));
+        }
+        buffer.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Copy bytes from a host buffer to guest memory (bulk transfer, e.g. syscall buffers).
+    ///
+    /// Arguments:
+    /// - `address`: Memory address to copy to (only RAM).
+    /// - `data`: Bytes to copy.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Bytes were copied successfully.
+    /// - `Err(Error)`: An error occurred. Ex.: Memory address is out of bounds.
+    #[inline]
+    fn copy_to_guest(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.store_bytes(address, data)
+    }
+
+    /// Take the extra cycles (stalls) of the typed accesses since the last call, e.g. cache misses
+    /// (check [`super::cache::CacheMemory`]).
+    ///
+    /// Called by the interpreter after each executed instruction (`timing` feature only), charging the stalls on top
+    /// of its cost (virtual time, check the `timing` module). The default implementation has no stalls.
+    ///
+    /// Returns the number of stall cycles.
+    #[inline(always)]
+    fn take_stall_cycles(&mut self) -> u32 {
+        0
+    }
+
+    /// Synchronize the machine software interrupt pending bit (`mip.MSIP`) with a memory-mapped register,
+    /// e.g. a CLINT `msip` (check `peripherals::clint::ClintMemory`, `peripherals` feature).
+    ///
+    /// Called by the interpreter when either side may have changed (not before each instruction): after word stores
+    /// (including atomics), and after `mip` CSR writes, host raises and clears
+    /// (`Interpreter::raise_software_interrupt`), resets and snapshot restores. The memory gets the current state
+    /// (for loads), and returns the new one (after stores). The default implementation has no such register.
+    ///
+    /// Arguments:
+    /// - `pending`: Current `mip.MSIP`.
+    ///
+    /// Returns the new `mip.MSIP`.
+    #[inline(always)]
+    fn sync_software_interrupt(&mut self, pending: bool) -> bool {
+        pending
+    }
+}
+
+/// Load a fixed-size array through [`Memory::load_bytes`] (default typed loads).
+#[inline(always)]
+fn load_array<M: Memory + ?Sized, const N: usize>(
+    memory: &mut M,
+    address: u32,
+) -> Result<[u8; N], Error> {
+    memory
+        .load_bytes(address, N)?
+        .try_into()
+        .map_err(|_| Error::InvalidMemoryAccessLength(N))
+}
+
+/// Store a fixed-size array through [`Memory::mut_bytes`] (default typed stores).
+#[inline(always)]
+fn store_array<M: Memory + ?Sized, const N: usize>(
+    memory: &mut M,
+    address: u32,
+    bytes: [u8; N],
+) -> Result<(), Error> {
+    let slice = memory.mut_bytes(address, N)?;
+    if unlikely(slice.len() != N) {
+        return Err(Error::InvalidMemoryAccessLength(N));
+    }
+    slice.copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// Typed accesses for code + RAM memory implementations, without slice length checks.
+macro_rules! impl_split_typed_access {
+    () => {
+        #[inline]
+        fn load_u8(&mut self, address: u32) -> Result<u8, Error> {
+            let (code, ram) = self.regions();
+            load_split_array(code, ram, address).map(u8::from_guest_bytes)
+        }
+
+        #[inline]
+        fn load_u16(&mut self, address: u32) -> Result<u16, Error> {
+            let (code, ram) = self.regions();
+            load_split_array(code, ram, address).map(u16::from_guest_bytes)
+        }
+
+        #[inline]
+        fn load_u32(&mut self, address: u32) -> Result<u32, Error> {
+            let (code, ram) = self.regions();
+            load_split_array(code, ram, address).map(u32::from_guest_bytes)
+        }
+
+        #[inline]
+        fn store_u8(&mut self, address: u32, value: u8) -> Result<(), Error> {
+            store_split_array(self.ram_bytes_mut(), address, value.to_guest_bytes())
+        }
+
+        #[inline]
+        fn store_u16(&mut self, address: u32, value: u16) -> Result<(), Error> {
+            store_split_array(self.ram_bytes_mut(), address, value.to_guest_bytes())
+        }
+
+        #[inline]
+        fn store_u32(&mut self, address: u32, value: u32) -> Result<(), Error> {
+            store_split_array(self.ram_bytes_mut(), address, value.to_guest_bytes())
+        }
+    };
 }
 
 /// A simple memory implementation using slices.
@@ -91,6 +290,8 @@ pub trait Memory {
 /// This memory implementation creates a memory space from code and RAM slices.
 ///
 /// Code section is mapped to address `0x00000000` and RAM to [`RAM_OFFSET`].
+/// The code slice is only borrowed immutably, so it can be shared between instances
+/// (check [`SplitMemory`] for owned RAM).
 #[derive(Debug)]
 pub struct SliceMemory<'a> {
     /// RISC-V bytecode.
@@ -108,39 +309,214 @@ impl<'a> SliceMemory<'a> {
     pub fn new(code: &'a [u8], ram: &'a mut [u8]) -> SliceMemory<'a> {
         SliceMemory { code, ram }
     }
+
+    /// Get the code region.
+    pub fn code(&self) -> &'a [u8] {
+        self.code
+    }
+
+    /// Get the RAM region.
+    pub fn ram(&self) -> &[u8] {
+        self.ram
+    }
+
+    /// Get the mutable RAM region.
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        self.ram
+    }
+
+    /// Replace the code buffer (e.g. to load a new image), keeping the RAM.
+    #[cfg(feature = "ffi")]
+    pub(crate) fn set_code(&mut self, code: &'a [u8]) {
+        self.code = code;
+    }
+
+    /// Get the code and RAM regions.
+    #[inline(always)]
+    fn regions(&self) -> (&[u8], &[u8]) {
+        (self.code, self.ram)
+    }
+
+    /// Get the mutable RAM region.
+    #[inline(always)]
+    fn ram_bytes_mut(&mut self) -> &mut [u8] {
+        self.ram
+    }
 }
 
 impl Memory for SliceMemory<'_> {
+    impl_split_typed_access!();
+
     #[inline]
     fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
-        // Check if the address is in RAM or code.
-        if address >= RAM_OFFSET {
-            // Subtract the RAM offset to get the actual address.
-            let ram_address = address.wrapping_sub(RAM_OFFSET) as usize;
-            checked_slice_range(self.ram, ram_address, len).map(|r| &self.ram[r])
-        } else {
-            let code_address = address as usize;
-            checked_slice_range(self.code, code_address, len).map(|r| &self.code[r])
-        }
+        load_split(self.code, self.ram, address, len)
     }
 
     #[inline]
     fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
-        // Subtract the RAM offset to get the actual address.
-        let ram_address = address.wrapping_sub(RAM_OFFSET) as usize;
-        checked_slice_range(self.ram, ram_address, len).map(|r| &mut self.ram[r])
+        mut_split(self.ram, address, len)
     }
 
     #[inline]
     fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        mut_split(self.ram, address, data.len()).map(|bytes| bytes.copy_from_slice(data))
+    }
+}
+
+/// A memory implementation with separately owned code and RAM regions.
+///
+/// The code region only needs shared access, so it can be shared between many interpreter instances
+/// (e.g. `&[u8]`, `&'static [u8]` or `Arc<[u8]>`), while each instance owns its RAM (e.g. `[u8; N]` or `Vec<u8>`).
+///
+/// Code section is mapped to address `0x00000000` and RAM to [`RAM_OFFSET`].
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::SplitMemory, ExitReason, Interpreter, State};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+///
+/// // Same code, independent RAM
+/// let mut memories = [SplitMemory::new(&code[..], [0u8; 64]), SplitMemory::new(&code[..], [0u8; 64])];
+/// for memory in memories.iter_mut() {
+///     let mut interpreter = Interpreter::new(memory, 0);
+///     assert_eq!(interpreter.run(), Ok(State::Halted { reason: ExitReason::Ebreak }));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SplitMemory<C: AsRef<[u8]>, R: AsRef<[u8]> + AsMut<[u8]>> {
+    /// RISC-V bytecode (shared).
+    code: C,
+    /// RAM buffer (owned).
+    ram: R,
+}
+
+impl<C: AsRef<[u8]>, R: AsRef<[u8]> + AsMut<[u8]>> SplitMemory<C, R> {
+    /// Create a new memory space.
+    ///
+    /// Arguments:
+    /// - `code`: Code buffer (shared, e.g. `&[u8]`).
+    /// - `ram`: RAM buffer (owned, e.g. `[u8; N]`).
+    pub fn new(code: C, ram: R) -> Self {
+        SplitMemory { code, ram }
+    }
+
+    /// Get the code region.
+    pub fn code(&self) -> &C {
+        &self.code
+    }
+
+    /// Get the RAM region.
+    pub fn ram(&self) -> &R {
+        &self.ram
+    }
+
+    /// Get the mutable RAM region.
+    pub fn ram_mut(&mut self) -> &mut R {
+        &mut self.ram
+    }
+
+    /// Get the code and RAM regions.
+    #[inline(always)]
+    fn regions(&self) -> (&[u8], &[u8]) {
+        (self.code.as_ref(), self.ram.as_ref())
+    }
+
+    /// Get the mutable RAM region.
+    #[inline(always)]
+    fn ram_bytes_mut(&mut self) -> &mut [u8] {
+        self.ram.as_mut()
+    }
+
+    /// Split the memory into its code and RAM regions.
+    pub fn into_parts(self) -> (C, R) {
+        (self.code, self.ram)
+    }
+}
+
+impl<C: AsRef<[u8]>, R: AsRef<[u8]> + AsMut<[u8]>> Memory for SplitMemory<C, R> {
+    impl_split_typed_access!();
+
+    #[inline]
+    fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+        load_split(self.code.as_ref(), self.ram.as_ref(), address, len)
+    }
+
+    #[inline]
+    fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+        mut_split(self.ram.as_mut(), address, len)
+    }
+
+    #[inline]
+    fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        mut_split(self.ram.as_mut(), address, data.len()).map(|bytes| bytes.copy_from_slice(data))
+    }
+}
+
+/// Load bytes from a code region (`0x00000000`) or a RAM region ([`RAM_OFFSET`]).
+#[inline(always)]
+fn load_split<'a>(
+    code: &'a [u8],
+    ram: &'a [u8],
+    address: u32,
+    len: usize,
+) -> Result<&'a [u8], Error> {
+    // Check if the address is in RAM or code.
+    if address >= RAM_OFFSET {
         // Subtract the RAM offset to get the actual address.
         let ram_address = address.wrapping_sub(RAM_OFFSET) as usize;
-        checked_slice_range(self.ram, ram_address, data.len()).map(|r| {
-            self.ram[r].copy_from_slice(data);
-        })
+        checked_slice_range(ram, ram_address, len, address).map(|r| &ram[r])
+    } else {
+        let code_address = address as usize;
+        checked_slice_range(code, code_address, len, address).map(|r| &code[r])
     }
 }
 
+/// Load a fixed-size array from a code region (`0x00000000`) or a RAM region ([`RAM_OFFSET`]).
+#[inline(always)]
+fn load_split_array<const N: usize>(
+    code: &[u8],
+    ram: &[u8],
+    address: u32,
+) -> Result<[u8; N], Error> {
+    // Check if the address is in RAM or code.
+    let (region, offset) = if address >= RAM_OFFSET {
+        (ram, address.wrapping_sub(RAM_OFFSET))
+    } else {
+        (code, address)
+    };
+
+    region
+        .get(offset as usize..)
+        .and_then(|bytes| bytes.first_chunk::<N>())
+        .copied()
+        .ok_or(Error::InvalidMemoryAddress { address, len: N })
+}
+
+/// Store a fixed-size array to a RAM region ([`RAM_OFFSET`]).
+#[inline(always)]
+fn store_split_array<const N: usize>(
+    ram: &mut [u8],
+    address: u32,
+    bytes: [u8; N],
+) -> Result<(), Error> {
+    // Subtract the RAM offset to get the actual address.
+    let ram_address = address.wrapping_sub(RAM_OFFSET) as usize;
+    *ram.get_mut(ram_address..)
+        .and_then(|slice| slice.first_chunk_mut::<N>())
+        .ok_or(Error::InvalidMemoryAddress { address, len: N })? = bytes;
+    Ok(())
+}
+
+/// Get mutable bytes from a RAM region ([`RAM_OFFSET`]).
+#[inline(always)]
+fn mut_split(ram: &mut [u8], address: u32, len: usize) -> Result<&mut [u8], Error> {
+    // Subtract the RAM offset to get the actual address.
+    let ram_address = address.wrapping_sub(RAM_OFFSET) as usize;
+    checked_slice_range(ram, ram_address, len, address).map(|r| &mut ram[r])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,11 +551,13 @@ mod tests {
         let mut memory = SliceMemory::new(&[], &mut ram);
         let result = memory.load_bytes(0x80000000, 4);
 
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            Error::InvalidMemoryAddress(_)
-        ));
+        assert_eq!(
+            result,
+            Err(Error::InvalidMemoryAddress {
+                address: 0x80000000,
+                len: 4
+            })
+        );
     }
 
     #[test]
@@ -191,7 +569,7 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
-            Error::InvalidMemoryAddress(_)
+            Error::InvalidMemoryAddress { .. }
         ));
     }
 
@@ -214,7 +592,7 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
-            Error::InvalidMemoryAddress(_)
+            Error::InvalidMemoryAddress { .. }
         ));
     }
 
@@ -237,7 +615,7 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
-            Error::InvalidMemoryAddress(_)
+            Error::InvalidMemoryAddress { .. }
         ));
     }
 
@@ -250,10 +628,90 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
-            Error::InvalidMemoryAddress(_)
+            Error::InvalidMemoryAddress { .. }
         ));
     }
 
+    #[test]
+    pub fn split_shared_code() {
+        let code = [0x1, 0x2, 0x3, 0x4];
+        let mut first = SplitMemory::new(&code[..], [0; 4]);
+        let mut second = SplitMemory::new(&code[..], [0; 4]);
+
+        first.store_bytes(0x80000000, &[0x5]).unwrap();
+        second.store_bytes(0x80000000, &[0x6]).unwrap();
+        assert_eq!(first.load_bytes(0x0, 4), Ok(&code[..]));
+        assert_eq!(second.load_bytes(0x0, 4), Ok(&code[..]));
+        assert_eq!(first.ram(), &[0x5, 0, 0, 0]);
+        assert_eq!(second.into_parts().1, [0x6, 0, 0, 0]);
+        assert!(first.store_bytes(0x0, &[0x1]).is_err());
+    }
+
+    #[test]
+    pub fn typed_access() {
+        let code = [0x1, 0x2, 0x3, 0x4];
+        let mut ram = [0; 4];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+
+        assert_eq!(memory.load_u32(0x0), Ok(0x04030201));
+        assert_eq!(memory.load_u16(0x2), Ok(0x0403));
+        assert_eq!(
+            memory.load_u32(0x2),
+            Err(Error::InvalidMemoryAddress {
+                address: 0x2,
+                len: 4
+            })
+        );
+
+        assert_eq!(memory.store_u16(0x80000002, 0xBEEF), Ok(()));
+        assert_eq!(memory.store_u8(0x80000000, 0x1), Ok(()));
+        assert_eq!(memory.load_u32(0x80000000), Ok(0xBEEF0001));
+        assert_eq!(
+            memory.store_u32(0x80000001, 0),
+            Err(Error::InvalidMemoryAddress {
+                address: 0x80000001,
+                len: 4
+            })
+        );
+        assert!(memory.store_u8(0x0, 0).is_err());
+
+        let mut buffer = [0; 3];
+        assert_eq!(memory.copy_from_guest(0x1, &mut buffer), Ok(()));
+        assert_eq!(buffer, [0x2, 0x3, 0x4]);
+        assert_eq!(memory.copy_to_guest(0x80000001, &buffer), Ok(()));
+        assert_eq!(ram, [0x1, 0x2, 0x3, 0x4]);
+    }
+
+    /// Memory with the default typed accesses.
+    struct DefaultMemory<'a>(SliceMemory<'a>);
+
+    impl Memory for DefaultMemory<'_> {
+        fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+            self.0.load_bytes(address, len)
+        }
+
+        fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+            self.0.mut_bytes(address, len)
+        }
+
+        fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+            self.0.store_bytes(address, data)
+        }
+    }
+
+    #[test]
+    pub fn typed_access_default() {
+        let code = [0x1, 0x2, 0x3, 0x4];
+        let mut ram = [0; 4];
+        let mut memory = DefaultMemory(SliceMemory::new(&code, &mut ram));
+
+        assert_eq!(memory.load_u32(0x0), Ok(0x04030201));
+        assert_eq!(memory.store_u16(0x80000002, 0xBEEF), Ok(()));
+        assert_eq!(memory.load_u16(0x80000002), Ok(0xBEEF));
+        assert_eq!(memory.load_u8(0x80000003), Ok(0xBE));
+        assert!(memory.store_u32(0x80000002, 0).is_err());
+    }
+
     #[test]
     pub fn load_out_of_code() {
         let code = [0; 2];
@@ -263,7 +721,7 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
-            Error::InvalidMemoryAddress(_)
+            Error::InvalidMemoryAddress { .. }
         ));
     }
 }
@@ -0,0 +1,57 @@
+//! Memory Endianness Module
+//!
+//! This module defines the MemoryEndianness trait, converting values between the host and the guest byte order.
+
+/// Memory Endianness Trait
+///
+/// RISC-V is little-endian, independent of the host byte order. All conversions between guest memory bytes
+/// and host values go through this trait, so the interpreter works correctly on big-endian hosts.
+///
+/// Default implementation for the following types is provided:
+/// - Integers (u8, u16, u32, u64, u128, i8, i16, i32, i64, i128)
+/// - Floating-point numbers (f32, f64)
+pub trait MemoryEndianness: Sized {
+    /// Guest memory representation (byte array).
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Convert from guest memory bytes (little-endian).
+    fn from_guest_bytes(bytes: Self::Bytes) -> Self;
+
+    /// Convert to guest memory bytes (little-endian).
+    fn to_guest_bytes(self) -> Self::Bytes;
+}
+
+/// Number Memory Endianness Implementation
+macro_rules! impl_memory_endianness_for_number {
+    ($($t:ty),*) => {
+        $(
+            impl MemoryEndianness for $t {
+                type Bytes = [u8; core::mem::size_of::<$t>()];
+
+                #[inline(always)]
+                fn from_guest_bytes(bytes: Self::Bytes) -> Self {
+                    Self::from_le_bytes(bytes)
+                }
+
+                #[inline(always)]
+                fn to_guest_bytes(self) -> Self::Bytes {
+                    self.to_le_bytes()
+                }
+            }
+        )*
+    };
+}
+
+impl_memory_endianness_for_number!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_bytes() {
+        assert_eq!(0x12345678u32.to_guest_bytes(), [0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(i16::from_guest_bytes([0xFE, 0xFF]), -2);
+        assert_eq!(f32::from_guest_bytes(1.5f32.to_guest_bytes()), 1.5);
+    }
+}
@@ -1,7 +1,7 @@
 //! Memory Type Module
 //!
 //! This module defines the MemoryType trait for types that can be loaded from and stored to memory.
-use super::Memory;
+use super::{Memory, MemoryEndianness};
 
 use crate::interpreter::Error;
 
@@ -9,7 +9,8 @@ use crate::interpreter::Error;
 ///
 /// This trait represents types that can be accessed to/from memory directly.
 ///
-/// All types that implement this trait must handle conversion between native and RISC-V format (e.g., endianness).
+/// All types that implement this trait must handle conversion between native and RISC-V format
+/// (e.g., endianness, check [`MemoryEndianness`]).
 ///
 /// Default implementation for the following types is provided:
 /// - Integers (u8, u16, u32, u64, u128, i8, i16, i32, i64, i128)
@@ -44,29 +45,44 @@ macro_rules! impl_memory_type_for_number {
         impl<'a, M: Memory> MemoryType<'a, M> for $t {
             #[inline]
             fn load(memory: &'a mut M, address: u32) -> Result<Self, Error> {
-                let bytes = memory.load_bytes(address, core::mem::size_of::<$t>())?;
-                let array: [u8; core::mem::size_of::<$t>()] = bytes
-                    .try_into()
-                    .map_err(|_| Error::InvalidMemoryAccessLength(core::mem::size_of::<$t>()))?;
-                Ok(Self::from_le_bytes(array))
+                let mut array = <$t as MemoryEndianness>::Bytes::default();
+                memory.copy_from_guest(address, &mut array)?;
+                Ok(Self::from_guest_bytes(array))
             }
 
             #[inline]
             fn store(&self, memory: &'a mut M, address: u32) -> Result<(), Error> {
-                memory.store_bytes(address, &self.to_le_bytes())
+                memory.copy_to_guest(address, &self.to_guest_bytes())
             }
         }
     };
 }
 
-impl_memory_type_for_number!(i8);
-impl_memory_type_for_number!(i16);
-impl_memory_type_for_number!(i32);
+/// Typed Memory Type Implementation (uses the [`Memory`] typed accesses)
+macro_rules! impl_memory_type_for_typed {
+    ($t:ty, $u:ty, $load:ident, $store:ident) => {
+        impl<'a, M: Memory> MemoryType<'a, M> for $t {
+            #[inline]
+            fn load(memory: &'a mut M, address: u32) -> Result<Self, Error> {
+                memory.$load(address).map(|value| value as $t)
+            }
+
+            #[inline]
+            fn store(&self, memory: &'a mut M, address: u32) -> Result<(), Error> {
+                memory.$store(address, *self as $u)
+            }
+        }
+    };
+}
+
+impl_memory_type_for_typed!(i8, u8, load_u8, store_u8);
+impl_memory_type_for_typed!(i16, u16, load_u16, store_u16);
+impl_memory_type_for_typed!(i32, u32, load_u32, store_u32);
 impl_memory_type_for_number!(i64);
 impl_memory_type_for_number!(i128);
-impl_memory_type_for_number!(u8);
-impl_memory_type_for_number!(u16);
-impl_memory_type_for_number!(u32);
+impl_memory_type_for_typed!(u8, u8, load_u8, store_u8);
+impl_memory_type_for_typed!(u16, u16, load_u16, store_u16);
+impl_memory_type_for_typed!(u32, u32, load_u32, store_u32);
 impl_memory_type_for_number!(u64);
 impl_memory_type_for_number!(u128);
 impl_memory_type_for_number!(f32);
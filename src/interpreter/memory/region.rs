@@ -0,0 +1,513 @@
+//! Region Memory Module
+//!
+//! This module defines the RegionMemory type, a memory built from a table of named regions.
+use core::fmt::{self, Display, Formatter};
+
+use super::{checked_slice_range, Error, Memory, RAM_OFFSET};
+use crate::protocol::APP_OFFSET;
+
+/// Access permissions of a memory region (for the interpreted code).
+///
+/// Instruction fetches are loads (check [`crate::interpreter::Config::ram_execution`] to forbid executing RAM).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    /// Loads (and instruction fetches) are allowed.
+    pub read: bool,
+    /// Stores (and atomics) are allowed.
+    pub write: bool,
+}
+
+impl Permissions {
+    /// Read-only (e.g. code, constant tables).
+    pub const READ_ONLY: Permissions = Permissions {
+        read: true,
+        write: false,
+    };
+    /// Read-write (e.g. RAM, shared memory).
+    pub const READ_WRITE: Permissions = Permissions {
+        read: true,
+        write: true,
+    };
+    /// Write-only (e.g. MMIO transmit buffers).
+    pub const WRITE_ONLY: Permissions = Permissions {
+        read: false,
+        write: true,
+    };
+}
+
+/// Bytes backing a region.
+#[derive(Debug)]
+enum Bytes<'a> {
+    /// Shared buffer (always read-only).
+    Shared(&'a [u8]),
+    /// Exclusive buffer.
+    Exclusive(&'a mut [u8]),
+}
+
+/// A named memory region (check [`RegionMemory`]).
+#[derive(Debug)]
+pub struct Region<'a> {
+    name: &'static str,
+    base: u32,
+    permissions: Permissions,
+    bytes: Bytes<'a>,
+}
+
+impl<'a> Region<'a> {
+    /// Create a region from an exclusive buffer.
+    ///
+    /// The host can still access the buffer while the region is in use (check [`RegionMemory::region_mut`]),
+    /// whatever its permissions.
+    ///
+    /// Arguments:
+    /// - `name`: Region name (unique).
+    /// - `base`: Base address.
+    /// - `bytes`: Region buffer (its length is the region size).
+    /// - `permissions`: Access permissions of the interpreted code.
+    pub fn new(
+        name: &'static str,
+        base: u32,
+        bytes: &'a mut [u8],
+        permissions: Permissions,
+    ) -> Self {
+        Region {
+            name,
+            base,
+            permissions,
+            bytes: Bytes::Exclusive(bytes),
+        }
+    }
+
+    /// Create a read-only region from a shared buffer (e.g. code shared between interpreters).
+    ///
+    /// Arguments:
+    /// - `name`: Region name (unique).
+    /// - `base`: Base address.
+    /// - `bytes`: Region buffer (its length is the region size).
+    pub fn shared(name: &'static str, base: u32, bytes: &'a [u8]) -> Self {
+        Region {
+            name,
+            base,
+            permissions: Permissions::READ_ONLY,
+            bytes: Bytes::Shared(bytes),
+        }
+    }
+
+    /// Get the region name.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the base address.
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// Get the region size, in bytes.
+    pub fn size(&self) -> usize {
+        self.bytes().len()
+    }
+
+    /// Get the access permissions of the interpreted code.
+    pub fn permissions(&self) -> Permissions {
+        self.permissions
+    }
+
+    /// Check if an address is inside the region.
+    pub fn contains(&self, address: u32) -> bool {
+        (address.wrapping_sub(self.base) as usize) < self.size()
+    }
+
+    /// Get the region bytes.
+    pub fn bytes(&self) -> &[u8] {
+        match &self.bytes {
+            Bytes::Shared(bytes) => bytes,
+            Bytes::Exclusive(bytes) => bytes,
+        }
+    }
+
+    /// Get the mutable region bytes (`None` for shared regions).
+    pub fn bytes_mut(&mut self) -> Option<&mut [u8]> {
+        match &mut self.bytes {
+            Bytes::Shared(_) => None,
+            Bytes::Exclusive(bytes) => Some(bytes),
+        }
+    }
+
+    /// Get the last address of the region (`None` if empty or past the end of the address space).
+    fn last_address(&self) -> Option<u32> {
+        let size = u32::try_from(self.size()).ok()?;
+        self.base.checked_add(size.checked_sub(1)?)
+    }
+}
+
+/// Region Memory Build Error
+#[derive(Debug, PartialEq)]
+pub enum RegionError {
+    /// More regions than the table capacity. The region name is provided.
+    TableFull(&'static str),
+    /// Region is empty or goes past the end of the address space. The region name is provided.
+    InvalidRegion(&'static str),
+    /// Two regions have the same name. The name is provided.
+    DuplicateName(&'static str),
+    /// Two regions overlap. The region names are provided.
+    Overlap(&'static str, &'static str),
+}
+
+impl core::error::Error for RegionError {}
+
+impl Display for RegionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::TableFull(name) => write!(f, "no room for region `{name}`"),
+            RegionError::InvalidRegion(name) => {
+                write!(f, "region `{name}` is empty or out of the address space")
+            }
+            RegionError::DuplicateName(name) => write!(f, "duplicate region name `{name}`"),
+            RegionError::Overlap(a, b) => write!(f, "regions `{a}` and `{b}` overlap"),
+        }
+    }
+}
+
+/// A memory implementation built from a table of named regions.
+///
+/// Each region has its own base address, size and permissions (check [`Region`]), so layouts with more
+/// than a code and a RAM region can be expressed (e.g. a shared memory or MMIO region polled by the host).
+/// Accesses must be fully inside a single region, with the required permission
+/// ([`Error::InvalidMemoryAddress`] otherwise).
+///
+/// Generics:
+/// - `N`: Maximum number of regions.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::memory::{Memory, Permissions, Region, RegionMemory, RAM_OFFSET};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut ram = [0; 64];
+/// let mut shared = [0; 16];
+///
+/// let mut memory: RegionMemory<'_, 3> = RegionMemory::builder()
+///     .code(&code)
+///     .ram(&mut ram)
+///     .region(Region::new("shared", 0x4000_0000, &mut shared, Permissions::READ_WRITE))
+///     .build()
+///     .unwrap();
+///
+/// memory.store_u32(0x4000_0000, 42).unwrap();
+/// assert_eq!(memory.region("shared").unwrap().bytes()[0], 42);
+/// assert_eq!(memory.region_at(RAM_OFFSET + 8).unwrap().name(), "ram");
+/// assert!(memory.store_u32(0, 0).is_err());
+/// ```
+#[derive(Debug)]
+pub struct RegionMemory<'a, const N: usize> {
+    regions: [Option<Region<'a>>; N],
+}
+
+impl<'a, const N: usize> RegionMemory<'a, N> {
+    /// Create a region memory builder (check [`RegionMemoryBuilder`]).
+    pub fn builder() -> RegionMemoryBuilder<'a, N> {
+        RegionMemoryBuilder {
+            regions: [const { None }; N],
+            len: 0,
+            error: None,
+        }
+    }
+
+    /// Get an iterator over the regions, in ascending address order.
+    pub fn regions(&self) -> impl Iterator<Item = &Region<'a>> {
+        self.regions.iter().map_while(Option::as_ref)
+    }
+
+    /// Get a region by name.
+    pub fn region(&self, name: &str) -> Option<&Region<'a>> {
+        self.regions().find(|region| region.name == name)
+    }
+
+    /// Get a region by name (mutable, e.g. to access its bytes from the host).
+    pub fn region_mut(&mut self, name: &str) -> Option<&mut Region<'a>> {
+        self.regions
+            .iter_mut()
+            .map_while(Option::as_mut)
+            .find(|region| region.name == name)
+    }
+
+    /// Get the region containing an address.
+    pub fn region_at(&self, address: u32) -> Option<&Region<'a>> {
+        self.regions().find(|region| region.contains(address))
+    }
+
+    /// Find the region containing an access, if allowed.
+    ///
+    /// Arguments:
+    /// - `address`: Start address of the access.
+    /// - `len`: Access length, in bytes.
+    /// - `write`: Check the write permission (read permission otherwise).
+    #[inline(always)]
+    fn find(&mut self, address: u32, len: usize, write: bool) -> Result<&mut Region<'a>, Error> {
+        self.regions
+            .iter_mut()
+            .map_while(Option::as_mut)
+            .find(|region| region.contains(address))
+            .filter(|region| match write {
+                true => region.permissions.write,
+                false => region.permissions.read,
+            })
+            .ok_or(Error::InvalidMemoryAddress { address, len })
+    }
+}
+
+impl<const N: usize> Memory for RegionMemory<'_, N> {
+    #[inline]
+    fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+        let region = self.find(address, len, false)?;
+        let bytes = region.bytes();
+        let range = checked_slice_range(bytes, (address - region.base) as usize, len, address)?;
+        Ok(&bytes[range])
+    }
+
+    #[inline]
+    fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+        let region = self.find(address, len, true)?;
+        let start = (address - region.base) as usize;
+
+        // Shared regions are always read-only
+        let bytes = region
+            .bytes_mut()
+            .ok_or(Error::InvalidMemoryAddress { address, len })?;
+        let range = checked_slice_range(bytes, start, len, address)?;
+        Ok(&mut bytes[range])
+    }
+
+    #[inline]
+    fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.mut_bytes(address, data.len())
+            .map(|bytes| bytes.copy_from_slice(data))
+    }
+}
+
+/// Region Memory Builder
+///
+/// Created by [`RegionMemory::builder`]. Validates the region table on [`RegionMemoryBuilder::build`]:
+/// - There must be at most `N` regions.
+/// - Regions must not be empty nor go past the end of the address space.
+/// - Region names must be unique, and regions must not overlap.
+#[derive(Debug)]
+pub struct RegionMemoryBuilder<'a, const N: usize> {
+    regions: [Option<Region<'a>>; N],
+    len: usize,
+    error: Option<RegionError>,
+}
+
+impl<'a, const N: usize> RegionMemoryBuilder<'a, N> {
+    /// Add a region (check [`Region`]).
+    pub fn region(mut self, region: Region<'a>) -> Self {
+        match self.regions.get_mut(self.len) {
+            Some(slot) => {
+                *slot = Some(region);
+                self.len += 1;
+            }
+            None => {
+                self.error
+                    .get_or_insert(RegionError::TableFull(region.name));
+            }
+        }
+        self
+    }
+
+    /// Add a read-only `code` region at address `0x00000000` (same layout as [`super::SliceMemory`]).
+    pub fn code(self, code: &'a [u8]) -> Self {
+        self.region(Region::shared("code", 0, code))
+    }
+
+    /// Add a read-only `app` region at [`APP_OFFSET`] (app image of a two-part guest, check
+    /// [`crate::interpreter::jump_table`]).
+    pub fn app(self, app: &'a [u8]) -> Self {
+        self.region(Region::shared("app", APP_OFFSET, app))
+    }
+
+    /// Add a read-write `ram` region at [`RAM_OFFSET`] (same layout as [`super::SliceMemory`]).
+    pub fn ram(self, ram: &'a mut [u8]) -> Self {
+        self.region(Region::new("ram", RAM_OFFSET, ram, Permissions::READ_WRITE))
+    }
+
+    /// Validate the region table and build the memory.
+    ///
+    /// Returns:
+    /// - `Ok(RegionMemory)`: Success, memory ready to use.
+    /// - `Err(RegionError)`: Invalid region table (check [`RegionError`]).
+    pub fn build(mut self) -> Result<RegionMemory<'a, N>, RegionError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let regions = &mut self.regions[..self.len];
+        for region in regions.iter().flatten() {
+            if region.last_address().is_none() {
+                return Err(RegionError::InvalidRegion(region.name));
+            }
+        }
+
+        // Sort by base address, so overlapping regions are neighbors
+        regions.sort_unstable_by_key(|region| region.as_ref().map(Region::base));
+        for (index, region) in regions.iter().flatten().enumerate() {
+            if let Some(other) = regions[..index]
+                .iter()
+                .flatten()
+                .find(|other| other.name == region.name)
+            {
+                return Err(RegionError::DuplicateName(other.name));
+            }
+        }
+        for pair in regions.windows(2) {
+            if let [Some(previous), Some(next)] = pair {
+                if previous.last_address() >= Some(next.base) {
+                    return Err(RegionError::Overlap(previous.name, next.name));
+                }
+            }
+        }
+
+        Ok(RegionMemory {
+            regions: self.regions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "transpiler")]
+    use crate::interpreter::{ExitReason, Interpreter, State};
+
+    #[test]
+    fn test_build_errors() {
+        let mut a = [0; 8];
+        let mut b = [0; 8];
+
+        let result: Result<RegionMemory<'_, 1>, _> =
+            RegionMemory::builder().code(&[0; 4]).ram(&mut a).build();
+        assert_eq!(result.err(), Some(RegionError::TableFull("ram")));
+
+        let result: Result<RegionMemory<'_, 2>, _> = RegionMemory::builder()
+            .ram(&mut a)
+            .region(Region::new(
+                "mmio",
+                RAM_OFFSET + 4,
+                &mut b,
+                Permissions::READ_WRITE,
+            ))
+            .build();
+        assert_eq!(result.err(), Some(RegionError::Overlap("ram", "mmio")));
+
+        let result: Result<RegionMemory<'_, 2>, _> = RegionMemory::builder()
+            .ram(&mut a)
+            .region(Region::new("ram", 0x100, &mut b, Permissions::READ_WRITE))
+            .build();
+        assert_eq!(result.err(), Some(RegionError::DuplicateName("ram")));
+
+        let result: Result<RegionMemory<'_, 2>, _> = RegionMemory::builder()
+            .region(Region::new(
+                "top",
+                u32::MAX - 3,
+                &mut a,
+                Permissions::READ_WRITE,
+            ))
+            .build();
+        assert_eq!(result.err(), Some(RegionError::InvalidRegion("top")));
+
+        let result: Result<RegionMemory<'_, 2>, _> = RegionMemory::builder()
+            .region(Region::shared("empty", 0, &[]))
+            .build();
+        assert_eq!(result.err(), Some(RegionError::InvalidRegion("empty")));
+    }
+
+    #[test]
+    fn test_permissions() {
+        let code = [1, 2, 3, 4];
+        let mut ram = [0; 8];
+        let mut status = [0x55; 4];
+        let mut tx = [0; 4];
+        let mut memory: RegionMemory<'_, 4> = RegionMemory::builder()
+            .region(Region::new("tx", 0x2000, &mut tx, Permissions::WRITE_ONLY))
+            .region(Region::new(
+                "status",
+                0x1000,
+                &mut status,
+                Permissions::READ_ONLY,
+            ))
+            .ram(&mut ram)
+            .code(&code)
+            .build()
+            .unwrap();
+
+        // Sorted by address
+        let names: Vec<_> = memory.regions().map(Region::name).collect();
+        assert_eq!(names, ["code", "status", "tx", "ram"]);
+
+        assert_eq!(memory.load_u32(0), Ok(0x04030201));
+        assert!(memory.store_u8(0, 0).is_err());
+        assert_eq!(memory.load_u8(0x1003), Ok(0x55));
+        assert!(memory.store_u8(0x1000, 0).is_err());
+        assert_eq!(memory.store_u16(0x2002, 0xAABB), Ok(()));
+        assert!(memory.load_u8(0x2000).is_err());
+        assert_eq!(memory.store_u32(RAM_OFFSET + 4, 7), Ok(()));
+
+        // Out of the regions, or crossing their end
+        assert_eq!(
+            memory.load_u32(0x3000),
+            Err(Error::InvalidMemoryAddress {
+                address: 0x3000,
+                len: 4
+            })
+        );
+        assert!(memory.load_u32(RAM_OFFSET + 6).is_err());
+
+        // The host has full access
+        let status = memory.region_mut("status").unwrap();
+        status.bytes_mut().unwrap()[0] = 0xAA;
+        assert_eq!(memory.load_u8(0x1000), Ok(0xAA));
+        assert_eq!(memory.region("tx").unwrap().bytes(), &[0, 0, 0xBB, 0xAA]);
+        assert_eq!(memory.region("ram").unwrap().bytes()[4], 7);
+        assert!(memory.region_mut("code").unwrap().bytes_mut().is_none());
+        assert_eq!(memory.region_at(0x1002).map(Region::name), Some("status"));
+        assert!(memory.region_at(0x1004).is_none());
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_interpreter() {
+        let mut code = [
+            0x03, 0xa5, 0x05, 0x00, // lw     a0, 0(a1)
+            0x23, 0xa2, 0xa5, 0x00, // sw     a0, 4(a1)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 4];
+        let mut shared = [42, 0, 0, 0, 0, 0, 0, 0];
+        let mut memory: RegionMemory<'_, 3> = RegionMemory::builder()
+            .code(&code)
+            .ram(&mut ram)
+            .region(Region::new(
+                "shared",
+                0x4000_0000,
+                &mut shared,
+                Permissions::READ_WRITE,
+            ))
+            .build()
+            .unwrap();
+
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.inner[11] = 0x4000_0000;
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(
+            memory.region("shared").unwrap().bytes(),
+            &[42, 0, 0, 0, 42, 0, 0, 0]
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Memory Management Unit Module
+//!
+//! Simplified Sv32 address translation (`mmu` feature), so guests built for virtual memory (e.g. small
+//! kernels isolating their tasks) can run:
+//! - Translation is enabled by writing `satp` with MODE = 1 ([`SATP_MODE_SV32`]) and the physical page number
+//!   of the root page table. ASID is hardwired to 0.
+//! - Translation doesn't depend on the privilege mode: once enabled, every instruction fetch, load, store and
+//!   atomic of the interpreted code is translated. In user mode (check [`super::privilege`]), pages must have
+//!   the `U` bit set, machine mode can access all pages. The `G` bit is ignored.
+//! - Two-level page tables with 4 KiB pages and 4 MiB superpages (Sv32 entry format). `A` and `D` bits are
+//!   neither checked nor updated. Physical addresses are 32-bit (the 2 highest physical page number bits are dropped).
+//! - Page faults trap to `mtvec`, with `mcause` set to [`INSTRUCTION_PAGE_FAULT`], [`LOAD_PAGE_FAULT`] or
+//!   [`STORE_PAGE_FAULT`] (stores and AMOs), `mtval` to the faulting virtual address and `mepc` to the faulting
+//!   instruction. Accesses spanning two pages must map them to contiguous physical pages.
+//! - Translations are cached in a direct-mapped TLB ([`MMU_TLB_ENTRIES`] entries), flushed by `sfence.vma` and
+//!   `satp` writes. The interpreted code must execute `sfence.vma` after updating its page tables.
+//!
+//! Host accesses (syscall arguments, [`Interpreter::memory`], debugger) use physical addresses, check
+//! [`Interpreter::translate_address`].
+use super::{
+    memory::{Memory, RAM_OFFSET},
+    utils::{likely, unlikely},
+    Error, Interpreter, MemoryAccess,
+};
+use crate::instruction::Instruction;
+
+/// Address translation register (`satp`) address.
+pub(crate) const SATP_ADDR: u16 = 0x180;
+/// `satp` MODE bit (Sv32 translation enabled).
+pub const SATP_MODE_SV32: u32 = 1 << 31;
+/// `satp` PPN field mask (physical page number of the root page table).
+pub const SATP_PPN: u32 = 0x003F_FFFF;
+
+/// Page size, in bytes.
+pub const PAGE_SIZE: u32 = 4096;
+/// Page offset mask.
+const PAGE_MASK: u32 = PAGE_SIZE - 1;
+/// Superpage offset mask (4 MiB).
+const SUPERPAGE_MASK: u32 = (1 << 22) - 1;
+
+/// Page table entry: valid.
+pub const PTE_V: u32 = 1 << 0;
+/// Page table entry: readable.
+pub const PTE_R: u32 = 1 << 1;
+/// Page table entry: writable.
+pub const PTE_W: u32 = 1 << 2;
+/// Page table entry: executable.
+pub const PTE_X: u32 = 1 << 3;
+/// Page table entry: accessible in user mode.
+pub const PTE_U: u32 = 1 << 4;
+/// Page table entry: physical page number shift.
+pub const PTE_PPN_SHIFT: u32 = 10;
+
+/// Instruction page fault (`mcause` exception code).
+pub const INSTRUCTION_PAGE_FAULT: u32 = 12;
+/// Load page fault (`mcause` exception code).
+pub const LOAD_PAGE_FAULT: u32 = 13;
+/// Store/AMO page fault (`mcause` exception code).
+pub const STORE_PAGE_FAULT: u32 = 15;
+
+/// Number of TLB entries (direct-mapped, indexed by the virtual page number).
+pub const MMU_TLB_ENTRIES: usize = 8;
+
+/// Cached translation (kept small, the interpreter is embedded in fixed-size storage, e.g. the C API).
+#[derive(Debug, Default, Clone, Copy)]
+struct TlbEntry {
+    /// Virtual page address and permissions (`R`, `W`, `X` and `U` bits, none if empty).
+    tag: u32,
+    /// Physical page address.
+    page: u32,
+}
+
+/// Address translation state (translation cache).
+#[derive(Debug, Default)]
+pub(crate) struct Mmu {
+    /// Translation cache.
+    tlb: [TlbEntry; MMU_TLB_ENTRIES],
+}
+
+impl Mmu {
+    /// Flush all cached translations.
+    pub(crate) fn flush(&mut self) {
+        self.tlb = Default::default();
+    }
+
+    /// Translate a virtual address (`satp.MODE` must be Sv32).
+    ///
+    /// Arguments:
+    /// - `memory`: Physical memory (page tables).
+    /// - `satp`: Address translation register.
+    /// - `address`: Virtual address.
+    /// - `len`: Access width, in bytes.
+    /// - `permission`: Required permissions (`R`, `W` or `X` bit, and `U` bit in user mode).
+    /// - `code`: Page fault exception code.
+    ///
+    /// Returns:
+    /// - `Ok(u32)`: The physical address.
+    /// - `Err(Error)`: Page fault ([`Error::PageFault`]) or page table out of bounds.
+    #[inline(never)]
+    fn translate<M: Memory>(
+        &mut self,
+        memory: &mut M,
+        satp: u32,
+        address: u32,
+        len: u32,
+        permission: u32,
+        code: u32,
+    ) -> Result<u32, Error> {
+        let physical = self.lookup(memory, satp, address, permission, code)?;
+
+        // Accesses spanning two pages must be physically contiguous
+        let next = (address | PAGE_MASK).wrapping_add(1);
+        if unlikely(address & PAGE_MASK > PAGE_SIZE - len)
+            && self.lookup(memory, satp, next, permission, code)?
+                != (physical | PAGE_MASK).wrapping_add(1)
+        {
+            return Err(Error::PageFault {
+                code,
+                address: next,
+            });
+        }
+
+        Ok(physical)
+    }
+
+    /// Translate a virtual address through the TLB, walking the page tables on a miss.
+    fn lookup<M: Memory>(
+        &mut self,
+        memory: &mut M,
+        satp: u32,
+        address: u32,
+        permission: u32,
+        code: u32,
+    ) -> Result<u32, Error> {
+        let index = (address >> 12) as usize % MMU_TLB_ENTRIES;
+        let entry = &mut self.tlb[index];
+
+        if entry.tag & !PAGE_MASK != address & !PAGE_MASK || entry.tag & PAGE_MASK == 0 {
+            let (page, permissions) =
+                walk(memory, satp, address)?.ok_or(Error::PageFault { code, address })?;
+            *entry = TlbEntry {
+                tag: (address & !PAGE_MASK) | permissions,
+                page,
+            };
+        }
+
+        if unlikely(entry.tag & permission != permission) {
+            return Err(Error::PageFault { code, address });
+        }
+
+        Ok(entry.page | (address & PAGE_MASK))
+    }
+}
+
+/// Walk the page tables.
+///
+/// Arguments:
+/// - `memory`: Physical memory (page tables).
+/// - `satp`: Address translation register.
+/// - `address`: Virtual address.
+///
+/// Returns:
+/// - `Ok(Some((u32, u32)))`: The physical page address (4 KiB) and its permissions.
+/// - `Ok(None)`: No valid mapping (page fault).
+/// - `Err(Error)`: Page table out of bounds.
+fn walk<M: Memory>(memory: &mut M, satp: u32, address: u32) -> Result<Option<(u32, u32)>, Error> {
+    let mut table = (satp & SATP_PPN) << 12;
+    let mut shift = 22;
+
+    loop {
+        let index = (address >> shift) & 0x3FF;
+        let pte = memory.load_u32(table.wrapping_add(index * 4))?;
+
+        // Invalid entry (or reserved write-only encoding)
+        if pte & PTE_V == 0 || (pte & PTE_R == 0 && pte & PTE_W != 0) {
+            return Ok(None);
+        }
+
+        let page = (pte >> PTE_PPN_SHIFT) << 12;
+        let permissions = pte & (PTE_R | PTE_W | PTE_X | PTE_U);
+
+        if permissions & (PTE_R | PTE_X) != 0 {
+            // Leaf, superpages must be aligned
+            return Ok(match shift {
+                22 if page & SUPERPAGE_MASK != 0 => None,
+                22 => Some((page | (address & SUPERPAGE_MASK & !PAGE_MASK), permissions)),
+                _ => Some((page, permissions)),
+            });
+        }
+
+        if shift == 12 {
+            // Pointer in a leaf table
+            return Ok(None);
+        }
+
+        table = page;
+        shift = 12;
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Translate a virtual address of the interpreted code to a physical address (check [`crate::interpreter::mmu`]).
+    ///
+    /// Host accesses (e.g. syscall arguments) use physical addresses, so pointers received from a guest running
+    /// with translation enabled should be translated first. Buffers spanning multiple pages should be translated
+    /// page by page. Returns the address unchanged if translation is disabled.
+    ///
+    /// Arguments:
+    /// - `address`: Virtual address.
+    /// - `access`: Kind of access (loads need the `R` permission, stores and atomics the `W` permission).
+    ///
+    /// Returns:
+    /// - `Ok(u32)`: The physical address.
+    /// - `Err(Error)`: No valid mapping ([`Error::PageFault`]) or page table out of bounds.
+    pub fn translate_address(&mut self, address: u32, access: MemoryAccess) -> Result<u32, Error> {
+        self.translate_virtual(address, 1, access)
+    }
+
+    /// Flush the translation cache (same as `sfence.vma`).
+    ///
+    /// Needed after the host updates the page tables of the interpreted code.
+    pub fn flush_tlb(&mut self) {
+        self.mmu.flush();
+    }
+
+    /// Translate the virtual address of a data access (identity if translation is disabled).
+    ///
+    /// Arguments:
+    /// - `address`: Virtual address.
+    /// - `len`: Access width, in bytes.
+    /// - `access`: Kind of access (load reservations are loads).
+    #[inline(always)]
+    pub(crate) fn translate_virtual(
+        &mut self,
+        address: u32,
+        len: u32,
+        access: MemoryAccess,
+    ) -> Result<u32, Error> {
+        let satp = self.registers.control_status.satp();
+        if likely(satp & SATP_MODE_SV32 == 0) {
+            return Ok(address);
+        }
+
+        let (permission, code) = match access {
+            MemoryAccess::Load => (PTE_R, LOAD_PAGE_FAULT),
+            MemoryAccess::Store | MemoryAccess::Atomic => (PTE_W, STORE_PAGE_FAULT),
+        };
+        let permission = permission | self.user_permission();
+        self.mmu
+            .translate(self.memory, satp, address, len, permission, code)
+    }
+
+    /// Fetch the next instruction with translation enabled (check [`Interpreter::fetch`]).
+    ///
+    /// Arguments:
+    /// - `word`: Always fetch 4 bytes, instead of finding the size from the instruction.
+    #[inline(never)]
+    pub(crate) fn fetch_translated(&mut self, word: bool) -> Result<Instruction, Error> {
+        let pc = self.program_counter;
+        let satp = self.registers.control_status.satp();
+
+        let physical = self.fetch_address(satp, pc)?;
+        let data = if likely(pc & PAGE_MASK <= PAGE_SIZE - 4) {
+            self.memory.load_u32(physical).and_then(|data| {
+                #[cfg(feature = "pmp")]
+                if word || Instruction::from(data).size() != 2 {
+                    self.check_fetch(pc.wrapping_add(2), physical.wrapping_add(2))?;
+                }
+
+                Ok(data)
+            })
+        } else {
+            // Last half-word of the page, compressed instructions don't need the next page
+            self.memory.load_u16(physical).and_then(|low| {
+                if !word && Instruction::from(low as u32).size() == 2 {
+                    return Ok(low as u32);
+                }
+
+                let physical = self.fetch_address(satp, pc.wrapping_add(2))?;
+                Ok(low as u32 | (self.memory.load_u16(physical)? as u32) << 16)
+            })
+        };
+
+        data.map(Instruction::from).map_err(|error| match error {
+            Error::InvalidMemoryAddress { .. } => Error::InvalidProgramCounter(pc),
+            error => error,
+        })
+    }
+
+    /// Translate an instruction parcel (2 bytes) fetch address, checking RAM execution
+    /// (check [`crate::interpreter::Config::ram_execution`]) and memory protection (`pmp` feature).
+    pub(crate) fn fetch_address(&mut self, satp: u32, address: u32) -> Result<u32, Error> {
+        let permission = PTE_X | self.user_permission();
+        let physical = self.mmu.translate(
+            self.memory,
+            satp,
+            address,
+            2,
+            permission,
+            INSTRUCTION_PAGE_FAULT,
+        )?;
+        if unlikely(!self.config.ram_execution && physical >= RAM_OFFSET) {
+            return Err(Error::ExecuteFault(self.program_counter));
+        }
+        #[cfg(feature = "pmp")]
+        self.check_fetch(address, physical)?;
+
+        Ok(physical)
+    }
+
+    /// Get the permission needed by the current privilege mode (`U` bit in user mode).
+    #[inline(always)]
+    fn user_permission(&self) -> u32 {
+        match self.registers.control_status.user_mode() {
+            true => PTE_U,
+            false => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::SliceMemory,
+        registers::{CPURegister, CSOperation},
+        ExitReason, State,
+    };
+
+    /// Root page table address.
+    const ROOT: u32 = RAM_OFFSET;
+    /// Leaf page table address.
+    const LEAF: u32 = RAM_OFFSET + PAGE_SIZE;
+    /// Read/write data page (virtual `0x0040_0000`).
+    const DATA: u32 = RAM_OFFSET + 2 * PAGE_SIZE;
+    /// Read-only data page (virtual `0x0040_1000`).
+    const READ_ONLY: u32 = RAM_OFFSET + 3 * PAGE_SIZE;
+
+    fn pte(address: u32, flags: u32) -> u32 {
+        (address >> 12) << PTE_PPN_SHIFT | flags | PTE_V
+    }
+
+    /// Code identity mapped (superpage), data pages mapped at `0x0040_0000`.
+    fn page_tables(memory: &mut SliceMemory<'_>) {
+        memory.store_u32(ROOT, pte(0, PTE_R | PTE_X)).unwrap();
+        memory.store_u32(ROOT + 4, pte(LEAF, 0)).unwrap();
+        memory.store_u32(LEAF, pte(DATA, PTE_R | PTE_W)).unwrap();
+        memory.store_u32(LEAF + 4, pte(READ_ONLY, PTE_R)).unwrap();
+    }
+
+    fn enable(interpreter: &mut Interpreter<'_, SliceMemory<'_>>) {
+        let satp = SATP_MODE_SV32 | (ROOT >> 12);
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(satp)), 0x180)
+            .unwrap();
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_translated_run() {
+        let mut code = [
+            0x73, 0x90, 0x02, 0x18, // csrw satp, t0
+            0x73, 0x00, 0x00, 0x12, // sfence.vma
+            0x03, 0xa5, 0x45, 0x00, // lw   a0, 4(a1)
+            0x23, 0xa4, 0xa5, 0x00, // sw   a0, 8(a1)
+            0x23, 0x20, 0xa6, 0x00, // sw   a0, 0(a2) (read-only)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+            0xf3, 0x26, 0x20, 0x34, // csrr a3, mcause
+            0x73, 0x27, 0x30, 0x34, // csrr a4, mtval
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+
+        let mut ram = [0; 4 * PAGE_SIZE as usize];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        page_tables(&mut memory);
+        memory.store_u32(DATA + 4, 0x1234_5678).unwrap();
+
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let cpu = &mut interpreter.registers.cpu;
+        cpu.inner[CPURegister::T0 as usize] = (SATP_MODE_SV32 | (ROOT >> 12)) as i32;
+        cpu.inner[CPURegister::A1 as usize] = 0x0040_0000;
+        cpu.inner[CPURegister::A2 as usize] = 0x0040_1000;
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(24)), 0x305)
+            .unwrap();
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.program_counter, 36);
+
+        // Load and store through the mapping
+        let cpu = &interpreter.registers.cpu;
+        assert_eq!(cpu.get(CPURegister::A0), Ok(0x1234_5678));
+        assert_eq!(interpreter.memory.load_u32(DATA + 8), Ok(0x1234_5678));
+
+        // Store to the read-only page trapped
+        assert_eq!(cpu.get(CPURegister::A3), Ok(STORE_PAGE_FAULT as i32));
+        assert_eq!(cpu.get(CPURegister::A4), Ok(0x0040_1000));
+        assert_eq!(
+            interpreter.registers.control_status.operation(None, 0x341),
+            Ok(16)
+        );
+        assert_eq!(interpreter.memory.load_u32(READ_ONLY), Ok(0));
+    }
+
+    #[test]
+    fn test_instruction_page_fault() {
+        let code = [0; 4];
+        let mut ram = [0; 4 * PAGE_SIZE as usize];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        page_tables(&mut memory);
+
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        enable(&mut interpreter);
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0x100)), 0x305)
+            .unwrap();
+
+        // Data page is not executable, direct fetches report the fault
+        interpreter.program_counter = 0x0040_0000;
+        assert_eq!(
+            interpreter.fetch(),
+            Err(Error::PageFault {
+                code: INSTRUCTION_PAGE_FAULT,
+                address: 0x0040_0000
+            })
+        );
+
+        // Running traps to the handler
+        assert_eq!(interpreter.step(), Ok(State::Running));
+        assert_eq!(interpreter.program_counter, 0x100);
+        let control_status = &mut interpreter.registers.control_status;
+        assert_eq!(
+            control_status.operation(None, 0x342),
+            Ok(INSTRUCTION_PAGE_FAULT)
+        );
+        assert_eq!(control_status.operation(None, 0x341), Ok(0x0040_0000));
+        assert_eq!(control_status.operation(None, 0x343), Ok(0x0040_0000));
+    }
+
+    #[test]
+    fn test_translate_address() {
+        let code = [0; 4];
+        let mut ram = [0; 4 * PAGE_SIZE as usize];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        page_tables(&mut memory);
+
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        assert_eq!(
+            interpreter.translate_address(0x0040_0010, MemoryAccess::Store),
+            Ok(0x0040_0010)
+        );
+
+        enable(&mut interpreter);
+        assert_eq!(
+            interpreter.translate_address(0x0040_0010, MemoryAccess::Store),
+            Ok(DATA + 0x10)
+        );
+        assert_eq!(
+            interpreter.translate_address(0x0012_3456, MemoryAccess::Load),
+            Ok(0x0012_3456)
+        );
+        assert_eq!(
+            interpreter.translate_address(0x0040_1000, MemoryAccess::Atomic),
+            Err(Error::PageFault {
+                code: STORE_PAGE_FAULT,
+                address: 0x0040_1000
+            })
+        );
+        assert_eq!(
+            interpreter.translate_address(0x0040_2000, MemoryAccess::Load),
+            Err(Error::PageFault {
+                code: LOAD_PAGE_FAULT,
+                address: 0x0040_2000
+            })
+        );
+
+        // Accesses spanning two pages must be physically contiguous
+        assert_eq!(
+            interpreter.translate(0x0040_0ffe, 4, MemoryAccess::Load),
+            Ok(DATA + 0xffe)
+        );
+        interpreter
+            .memory
+            .store_u32(LEAF + 4, pte(DATA, PTE_R))
+            .unwrap();
+        interpreter.flush_tlb();
+        assert_eq!(
+            interpreter.translate(0x0040_0ffe, 4, MemoryAccess::Load),
+            Err(Error::PageFault {
+                code: LOAD_PAGE_FAULT,
+                address: 0x0040_1000
+            })
+        );
+
+        // Cached until flushed
+        interpreter.memory.store_u32(LEAF, 0).unwrap();
+        assert_eq!(
+            interpreter.translate_address(0x0040_0000, MemoryAccess::Load),
+            Ok(DATA)
+        );
+        interpreter.flush_tlb();
+        assert!(interpreter
+            .translate_address(0x0040_0000, MemoryAccess::Load)
+            .is_err());
+
+        // Misaligned superpages are invalid
+        interpreter
+            .memory
+            .store_u32(ROOT, pte(PAGE_SIZE, PTE_R))
+            .unwrap();
+        interpreter.flush_tlb();
+        assert!(interpreter
+            .translate_address(0x0000_0000, MemoryAccess::Load)
+            .is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! State Observer Module
+//!
+//! Host notification of interpreter state transitions (e.g. for monitoring and metrics),
+//! without wrapping every call to [`Interpreter::run`] (`state-observer` feature).
+use super::{memory::Memory, Error, Interpreter, State};
+
+/// A state transition, reported to the state observer (check [`StateObserver`]).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct StateTransition<'e> {
+    /// Previous state ([`State::Running`] after an error).
+    pub from: State,
+    /// New state, or the error that stopped the interpreter.
+    pub to: Result<State, &'e Error>,
+}
+
+/// State observer function.
+///
+/// Called by [`Interpreter::run`] (and everything built on it, e.g. [`super::Runner`] and [`super::Scheduler`])
+/// on every state transition:
+/// - When resuming from a stopped state (e.g. [`State::Called`] to [`State::Running`]).
+/// - When stopping (e.g. [`State::Running`] to [`State::Called`], [`State::Waiting`] or [`State::Halted`]).
+/// - On errors.
+///
+/// Yielding at the instruction limit is not a transition ([`State::Running`] to [`State::Running`]).
+///
+/// Arguments:
+/// - `&Interpreter`: The interpreter (program counter, registers, pending syscall, etc.).
+/// - `StateTransition`: The state transition.
+pub type StateObserver<M> = fn(&Interpreter<'_, M>, StateTransition<'_>);
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Set the state observer (check [`StateObserver`]).
+    ///
+    /// Arguments:
+    /// - `observer`: State observer (`None` disables notifications).
+    pub fn set_state_observer(&mut self, observer: Option<StateObserver<M>>) {
+        self.state_observer = observer;
+    }
+
+    /// Report a transition to the given state, if it changed.
+    ///
+    /// Arguments:
+    /// - `to`: New state, or the error that stopped the interpreter.
+    #[inline]
+    pub(crate) fn observe_state(&mut self, to: Result<State, &Error>) {
+        let state = *to.as_ref().unwrap_or(&State::Running);
+        if to.is_ok() && state == self.observed_state {
+            return;
+        }
+
+        let from = core::mem::replace(&mut self.observed_state, state);
+        if let Some(observer) = self.state_observer {
+            observer(self, StateTransition { from, to });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, num::NonZeroI32};
+
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, ExitReason};
+
+    std::thread_local! {
+        static TRANSITIONS: RefCell<Vec<(State, Option<State>, u32)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn observer(interpreter: &Interpreter<'_, SliceMemory<'_>>, transition: StateTransition<'_>) {
+        TRANSITIONS.with_borrow_mut(|transitions| {
+            transitions.push((
+                transition.from,
+                transition.to.ok(),
+                interpreter.program_counter,
+            ))
+        });
+    }
+
+    #[test]
+    fn test_state_observer() {
+        let code = [
+            0x1f, 0x00, 0x00, 0x00, // ecall (already transpiled)
+            0x1f, 0x00, 0x10, 0x00, // ebreak (already transpiled)
+            0x00, 0x00, 0x00, 0x00, // invalid
+        ];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.set_state_observer(Some(observer));
+
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter
+            .syscall(&mut |_, _, _| Ok::<_, Error>(Ok::<i32, NonZeroI32>(0)))
+            .unwrap();
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        interpreter.program_counter = 8;
+        assert!(interpreter.run().is_err());
+
+        let transitions = TRANSITIONS.take();
+        assert_eq!(transitions.len(), 5);
+        assert_eq!(transitions[0], (State::Running, Some(State::Called), 4));
+        assert_eq!(transitions[1], (State::Called, Some(State::Running), 4));
+        assert_eq!(
+            transitions[2],
+            (
+                State::Running,
+                Some(State::Halted {
+                    reason: ExitReason::Ebreak
+                }),
+                8
+            )
+        );
+        assert_eq!(
+            transitions[3],
+            (
+                State::Halted {
+                    reason: ExitReason::Ebreak
+                },
+                Some(State::Running),
+                8
+            )
+        );
+        assert_eq!(transitions[4], (State::Running, None, 8));
+    }
+
+    #[test]
+    fn test_yield_not_observed() {
+        // c.j 0, padding (already transpiled)
+        let code = [0x0f, 0x00, 0x00, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 10);
+        interpreter.set_state_observer(Some(observer));
+
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert!(TRANSITIONS.take().is_empty());
+    }
+}
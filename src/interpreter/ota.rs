@@ -0,0 +1,363 @@
+//! OTA Module
+//!
+//! A/B guest image management for over-the-air updates: two image slots (active and standby), with
+//! validity flags and rollback when an updated image fails on its first run.
+//!
+//! Update flow:
+//! 1. [`SlotTable::begin_update`]: get the standby slot and write the new image to it (e.g. with
+//!    [`crate::interpreter::loader::ImageLoader`]).
+//! 2. [`SlotTable::finish_update`]: mark it as pending.
+//! 3. [`SlotTable::boot`] (at every start): a pending image becomes active, on trial.
+//! 4. [`SlotTable::report`] (with every [`Interpreter::run`](super::Interpreter::run) result): a trial image
+//!    is confirmed when it halts, or rolled back when it fails (interpreter error or guest panic).
+//!
+//! A trial image still running at the next [`SlotTable::boot`] (e.g. the host was reset by a watchdog) is
+//! also rolled back. Long-running guests should call [`SlotTable::confirm`] once healthy.
+//! The table must be persisted (check [`SlotTable::to_bytes`]) whenever it changes.
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::{ota::{Slot, SlotStatus, SlotTable}, Error, State};
+//!
+//! let mut table = SlotTable::new(Slot::A);
+//!
+//! // Download an update to the standby slot
+//! assert_eq!(table.begin_update(), Slot::B);
+//! table.finish_update();
+//!
+//! // Next start: run the update, which fails
+//! assert_eq!(table.boot(), Ok(Slot::B));
+//! let result = Err(Error::InvalidInstruction(0));
+//! assert_eq!(table.report(&result), Ok(Some(Slot::A)));
+//! assert_eq!(table.status(Slot::B), SlotStatus::Invalid);
+//! ```
+use core::fmt::{self, Display, Formatter};
+
+use super::{snapshot::crc32_update, Error, ExitReason, State};
+
+/// Slot table magic.
+const SLOT_TABLE_MAGIC: [u8; 4] = *b"EMBA";
+
+/// Slot table size (check [`SlotTable::to_bytes`]), in bytes.
+pub const SLOT_TABLE_SIZE: usize = 12;
+
+/// Guest image slot.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    /// First slot.
+    A = 0,
+    /// Second slot.
+    B = 1,
+}
+
+impl Slot {
+    /// Get the other slot.
+    pub fn other(self) -> Slot {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+}
+
+/// Slot status.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotStatus {
+    /// No image (or being written).
+    Empty = 0,
+    /// Image installed, not run yet.
+    Pending = 1,
+    /// Image active, on its first run (not confirmed yet).
+    Trial = 2,
+    /// Image confirmed.
+    Valid = 3,
+    /// Image failed on its first run.
+    Invalid = 4,
+}
+
+impl SlotStatus {
+    /// Decode a slot status.
+    fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => SlotStatus::Empty,
+            1 => SlotStatus::Pending,
+            2 => SlotStatus::Trial,
+            3 => SlotStatus::Valid,
+            4 => SlotStatus::Invalid,
+            _ => return None,
+        })
+    }
+}
+
+/// Slot Table Error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotError {
+    /// Persisted table is invalid (magic, checksum or value mismatch).
+    InvalidTable,
+    /// No valid image to roll back to.
+    NoValidSlot,
+}
+
+impl core::error::Error for SlotError {}
+
+impl Display for SlotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// A/B Slot Table
+///
+/// Tracks the active slot and the status of both slots (check [`crate::interpreter::ota`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotTable {
+    active: Slot,
+    status: [SlotStatus; 2],
+}
+
+impl SlotTable {
+    /// Create a new table, with a valid image (e.g. factory image) in `active` and an empty standby slot.
+    ///
+    /// Arguments:
+    /// - `active`: Slot of the initial image.
+    pub fn new(active: Slot) -> Self {
+        let mut status = [SlotStatus::Empty; 2];
+        status[active as usize] = SlotStatus::Valid;
+        SlotTable { active, status }
+    }
+
+    /// Get the active slot (the one to run).
+    pub fn active(&self) -> Slot {
+        self.active
+    }
+
+    /// Get the standby slot (the one to update).
+    pub fn standby(&self) -> Slot {
+        self.active.other()
+    }
+
+    /// Get the status of a slot.
+    pub fn status(&self, slot: Slot) -> SlotStatus {
+        self.status[slot as usize]
+    }
+
+    /// Start an update: the standby slot is marked as empty, to be written with the new image.
+    ///
+    /// Returns the standby slot.
+    pub fn begin_update(&mut self) -> Slot {
+        let slot = self.standby();
+        self.status[slot as usize] = SlotStatus::Empty;
+        slot
+    }
+
+    /// Finish an update: the standby slot image is marked as pending (run on the next [`SlotTable::boot`]).
+    pub fn finish_update(&mut self) {
+        self.status[self.standby() as usize] = SlotStatus::Pending;
+    }
+
+    /// Select the slot to run, at startup.
+    ///
+    /// A pending standby image becomes active, on trial. An active image still on trial (not confirmed
+    /// before the host restarted) is rolled back.
+    ///
+    /// Returns:
+    /// - `Ok(Slot)`: Slot to run.
+    /// - `Err(SlotError)`: No valid image to run ([`SlotError::NoValidSlot`]).
+    pub fn boot(&mut self) -> Result<Slot, SlotError> {
+        if self.status(self.active) == SlotStatus::Trial {
+            self.rollback()?;
+        }
+
+        let standby = self.standby();
+        if self.status(standby) == SlotStatus::Pending {
+            self.active = standby;
+            self.status[standby as usize] = SlotStatus::Trial;
+        }
+
+        match self.status(self.active) {
+            SlotStatus::Valid | SlotStatus::Trial => Ok(self.active),
+            _ => Err(SlotError::NoValidSlot),
+        }
+    }
+
+    /// Confirm the active image (if on trial), so it is kept on failures.
+    pub fn confirm(&mut self) {
+        if self.status(self.active) == SlotStatus::Trial {
+            self.status[self.active as usize] = SlotStatus::Valid;
+        }
+    }
+
+    /// Mark the active image as invalid and switch back to the other slot.
+    ///
+    /// Returns:
+    /// - `Ok(Slot)`: Slot to run (the other one).
+    /// - `Err(SlotError)`: The other slot has no valid image ([`SlotError::NoValidSlot`]).
+    pub fn rollback(&mut self) -> Result<Slot, SlotError> {
+        self.status[self.active as usize] = SlotStatus::Invalid;
+        self.active = self.active.other();
+        match self.status(self.active) {
+            SlotStatus::Valid => Ok(self.active),
+            _ => Err(SlotError::NoValidSlot),
+        }
+    }
+
+    /// Report an interpreter run result.
+    ///
+    /// Only images on trial are affected: they are confirmed when the guest halts successfully ([`State::Halted`] with
+    /// `ebreak` or exit code `0`), and rolled back on failure (an interpreter error, [`State::Panicked`], a non-zero
+    /// exit code or an exhausted instruction budget). Other states don't change the table.
+    ///
+    /// Arguments:
+    /// - `result`: Interpreter run result (check [`Interpreter::run`](super::Interpreter::run)).
+    ///
+    /// Returns:
+    /// - `Ok(Some(Slot))`: Image rolled back, restart the interpreter with this slot image.
+    /// - `Ok(None)`: Keep running.
+    /// - `Err(SlotError)`: Image failed and the other slot has no valid image ([`SlotError::NoValidSlot`]).
+    pub fn report(&mut self, result: &Result<State, Error>) -> Result<Option<Slot>, SlotError> {
+        if self.status(self.active) != SlotStatus::Trial {
+            return Ok(None);
+        }
+
+        match result {
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak | ExitReason::ExitSyscall(0),
+            }) => {
+                self.confirm();
+                Ok(None)
+            }
+            Ok(State::Halted { .. } | State::Panicked { .. }) | Err(_) => self.rollback().map(Some),
+            Ok(_) => Ok(None),
+        }
+    }
+
+    /// Encode the table, to be persisted (magic, active slot, slot status and CRC-32).
+    pub fn to_bytes(&self) -> [u8; SLOT_TABLE_SIZE] {
+        let mut bytes = [0; SLOT_TABLE_SIZE];
+        bytes[..4].copy_from_slice(&SLOT_TABLE_MAGIC);
+        bytes[4] = self.active as u8;
+        bytes[5] = self.status[0] as u8;
+        bytes[6] = self.status[1] as u8;
+        let crc = !crc32_update(!0, &bytes[..8]);
+        bytes[8..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a persisted table (check [`SlotTable::to_bytes`]).
+    ///
+    /// Arguments:
+    /// - `bytes`: Persisted table.
+    ///
+    /// Returns:
+    /// - `Ok(SlotTable)`: Success, decoded table.
+    /// - `Err(SlotError)`: Invalid table ([`SlotError::InvalidTable`], e.g. never written).
+    pub fn from_bytes(bytes: &[u8; SLOT_TABLE_SIZE]) -> Result<Self, SlotError> {
+        let crc = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        if bytes[..4] != SLOT_TABLE_MAGIC || crc != !crc32_update(!0, &bytes[..8]) {
+            return Err(SlotError::InvalidTable);
+        }
+
+        let active = match bytes[4] {
+            0 => Slot::A,
+            1 => Slot::B,
+            _ => return Err(SlotError::InvalidTable),
+        };
+        match (SlotStatus::from_u8(bytes[5]), SlotStatus::from_u8(bytes[6])) {
+            (Some(a), Some(b)) => Ok(SlotTable {
+                active,
+                status: [a, b],
+            }),
+            _ => Err(SlotError::InvalidTable),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_confirmed() {
+        let mut table = SlotTable::new(Slot::A);
+        assert_eq!(table.boot(), Ok(Slot::A));
+
+        assert_eq!(table.begin_update(), Slot::B);
+        assert_eq!(table.status(Slot::B), SlotStatus::Empty);
+        // Not finished (e.g. download interrupted), keep running the current image
+        assert_eq!(table.boot(), Ok(Slot::A));
+
+        table.finish_update();
+        assert_eq!(table.boot(), Ok(Slot::B));
+        assert_eq!(table.status(Slot::B), SlotStatus::Trial);
+        assert_eq!(table.report(&Ok(State::Waiting)), Ok(None));
+        assert_eq!(
+            table.report(&Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })),
+            Ok(None)
+        );
+        assert_eq!(table.status(Slot::B), SlotStatus::Valid);
+
+        // Confirmed images aren't rolled back
+        assert_eq!(table.report(&Err(Error::InvalidInstruction(0))), Ok(None));
+        assert_eq!(table.boot(), Ok(Slot::B));
+        assert_eq!(table.standby(), Slot::A);
+    }
+
+    #[test]
+    fn test_update_rollback() {
+        let mut table = SlotTable::new(Slot::B);
+        table.begin_update();
+        table.finish_update();
+        assert_eq!(table.boot(), Ok(Slot::A));
+        let panicked = Ok(State::Panicked { msg_ptr: 0, len: 0 });
+        assert_eq!(table.report(&panicked), Ok(Some(Slot::B)));
+        assert_eq!(table.status(Slot::A), SlotStatus::Invalid);
+        assert_eq!(table.boot(), Ok(Slot::B));
+
+        // Reset while on trial
+        table.begin_update();
+        table.finish_update();
+        assert_eq!(table.boot(), Ok(Slot::A));
+        assert_eq!(table.boot(), Ok(Slot::B));
+        assert_eq!(table.status(Slot::A), SlotStatus::Invalid);
+
+        // Non-zero exit code
+        table.begin_update();
+        table.finish_update();
+        assert_eq!(table.boot(), Ok(Slot::A));
+        let failed = Ok(State::Halted {
+            reason: ExitReason::ExitSyscall(1),
+        });
+        assert_eq!(table.report(&failed), Ok(Some(Slot::B)));
+
+        // No valid image left
+        let mut table = SlotTable::new(Slot::A);
+        assert_eq!(table.rollback(), Err(SlotError::NoValidSlot));
+        assert_eq!(table.boot(), Err(SlotError::NoValidSlot));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let mut table = SlotTable::new(Slot::A);
+        table.begin_update();
+        table.finish_update();
+        table.boot().unwrap();
+
+        let bytes = table.to_bytes();
+        assert_eq!(SlotTable::from_bytes(&bytes), Ok(table));
+
+        let mut corrupted = bytes;
+        corrupted[5] ^= 1;
+        assert_eq!(
+            SlotTable::from_bytes(&corrupted),
+            Err(SlotError::InvalidTable)
+        );
+        assert_eq!(
+            SlotTable::from_bytes(&[0xFF; SLOT_TABLE_SIZE]),
+            Err(SlotError::InvalidTable)
+        );
+    }
+}
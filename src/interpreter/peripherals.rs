@@ -0,0 +1,446 @@
+//! Peripherals Module
+//!
+//! Ready-made emulated peripherals (UART, GPIO and timer), exposed to the interpreted code through
+//! a documented syscall set. Useful for running firmware written against a thin HAL inside Embive
+//! for host-side integration tests.
+//!
+//! Syscalls (number in `a7`, arguments in `a0` to `a6`, check [`super::Interpreter::syscall`]):
+//!
+//! | Syscall              | Number   | Arguments                 | Return value                     |
+//! |----------------------|----------|---------------------------|----------------------------------|
+//! | [`UART_WRITE`]       | `-0x100` | `a0`: address, `a1`: len  | Bytes written (TX buffer space)  |
+//! | [`UART_READ`]        | `-0x101` | `a0`: address, `a1`: len  | Bytes read (RX buffer data)      |
+//! | [`GPIO_WRITE`]       | `-0x110` | `a0`: mask, `a1`: value   | New output state                 |
+//! | [`GPIO_READ`]        | `-0x111` | -                         | Input state                      |
+//! | [`TIMER_NOW`]        | `-0x120` | -                         | Current tick count (low 32 bits) |
+//! | [`TIMER_SET_ALARM`]  | `-0x121` | `a0`: ticks from now      | 0                                |
+//!
+//! Errors are returned through `a0` (check [`ERROR_INVALID_ADDRESS`]).
+//!
+//! For console-style streaming without a syscall per transfer, check the ring buffer [`console`].
+//! For a CLINT-compatible software interrupt register (`msip`), check [`clint`].
+pub mod clint;
+pub mod console;
+
+use core::num::NonZeroI32;
+
+use super::{memory::Memory, SYSCALL_ARGS};
+use crate::protocol;
+
+/// UART write syscall number (guest to host).
+pub const UART_WRITE: i32 = protocol::UART_WRITE;
+/// UART read syscall number (host to guest).
+pub const UART_READ: i32 = protocol::UART_READ;
+/// GPIO write syscall number (set outputs).
+pub const GPIO_WRITE: i32 = protocol::GPIO_WRITE;
+/// GPIO read syscall number (get inputs).
+pub const GPIO_READ: i32 = protocol::GPIO_READ;
+/// Timer current tick count syscall number.
+pub const TIMER_NOW: i32 = protocol::TIMER_NOW;
+/// Timer alarm syscall number.
+pub const TIMER_SET_ALARM: i32 = protocol::TIMER_SET_ALARM;
+
+/// Syscall error: invalid memory address (buffer out of bounds).
+pub const ERROR_INVALID_ADDRESS: i32 = protocol::ERROR_INVALID_ADDRESS;
+
+/// Fixed-capacity byte ring buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingBuffer<const N: usize> {
+    buffer: [u8; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RingBuffer<N> {
+    /// Create a new, empty, ring buffer.
+    pub const fn new() -> Self {
+        RingBuffer {
+            buffer: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Number of bytes in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check if the buffer is full.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Free space in the buffer, in bytes.
+    pub fn free(&self) -> usize {
+        N - self.len
+    }
+
+    /// Push a byte to the buffer.
+    ///
+    /// Returns:
+    /// - `true`: The byte was pushed.
+    /// - `false`: The buffer is full.
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.is_full() {
+            return false;
+        }
+
+        self.buffer[(self.head + self.len) % N] = byte;
+        self.len += 1;
+        true
+    }
+
+    /// Pop a byte from the buffer.
+    ///
+    /// Returns:
+    /// - `Some(u8)`: The oldest byte in the buffer.
+    /// - `None`: The buffer is empty.
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let byte = self.buffer[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    /// Push as many bytes as possible to the buffer.
+    ///
+    /// Returns:
+    /// - `usize`: Number of bytes pushed.
+    pub fn push_slice(&mut self, data: &[u8]) -> usize {
+        let count = data.len().min(self.free());
+        for &byte in &data[..count] {
+            self.push(byte);
+        }
+        count
+    }
+
+    /// Pop as many bytes as possible from the buffer.
+    ///
+    /// Returns:
+    /// - `usize`: Number of bytes popped.
+    pub fn pop_slice(&mut self, data: &mut [u8]) -> usize {
+        data.iter_mut()
+            .zip(core::iter::from_fn(|| self.pop()))
+            .map(|(byte, value)| *byte = value)
+            .count()
+    }
+}
+
+/// Emulated UART.
+///
+/// Generics:
+/// - `RX`: Receive buffer size (host to guest).
+/// - `TX`: Transmit buffer size (guest to host).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Uart<const RX: usize, const TX: usize> {
+    rx: RingBuffer<RX>,
+    tx: RingBuffer<TX>,
+}
+
+impl<const RX: usize, const TX: usize> Uart<RX, TX> {
+    /// Write data to the guest (receive buffer).
+    ///
+    /// Returns:
+    /// - `usize`: Number of bytes written (limited by the receive buffer space).
+    pub fn host_write(&mut self, data: &[u8]) -> usize {
+        self.rx.push_slice(data)
+    }
+
+    /// Read data from the guest (transmit buffer).
+    ///
+    /// Returns:
+    /// - `usize`: Number of bytes read.
+    pub fn host_read(&mut self, data: &mut [u8]) -> usize {
+        self.tx.pop_slice(data)
+    }
+
+    /// Number of bytes pending to be read by the guest.
+    pub fn rx_pending(&self) -> usize {
+        self.rx.len()
+    }
+
+    /// Number of bytes pending to be read by the host.
+    pub fn tx_pending(&self) -> usize {
+        self.tx.len()
+    }
+}
+
+/// Emulated GPIO bank (32 pins).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Gpio {
+    /// Output state (set by the guest).
+    pub output: u32,
+    /// Input state (set by the host).
+    pub input: u32,
+}
+
+/// Emulated timer.
+///
+/// The tick count is advanced by the host (check [`Timer::advance`]), or follows the
+/// interpreter virtual time for reproducible simulations (check [`Timer::sync`]).
+/// When the alarm expires, the host should trigger an interrupt (check [`Timer::take_alarm`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timer {
+    now: u64,
+    alarm: Option<u64>,
+}
+
+impl Timer {
+    /// Advance the timer.
+    ///
+    /// Arguments:
+    /// - `ticks`: Number of ticks to advance.
+    pub fn advance(&mut self, ticks: u64) {
+        self.now = self.now.wrapping_add(ticks);
+    }
+
+    /// Synchronize the timer with a time source (e.g. [`super::Interpreter::time`]).
+    ///
+    /// The tick count never goes backwards, older values are ignored.
+    ///
+    /// Arguments:
+    /// - `now`: Current tick count of the time source.
+    pub fn sync(&mut self, now: u64) {
+        self.now = self.now.max(now);
+    }
+
+    /// Current tick count.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Alarm deadline (tick count), if set.
+    pub fn alarm(&self) -> Option<u64> {
+        self.alarm
+    }
+
+    /// Check and clear an expired alarm.
+    ///
+    /// Returns:
+    /// - `true`: The alarm expired (and was cleared), an interrupt should be triggered.
+    /// - `false`: No alarm or not yet expired.
+    pub fn take_alarm(&mut self) -> bool {
+        match self.alarm {
+            Some(deadline) if self.now >= deadline => {
+                self.alarm = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Emulated peripherals bundle (UART, GPIO and timer).
+///
+/// Generics:
+/// - `RX`: UART receive buffer size (host to guest).
+/// - `TX`: UART transmit buffer size (guest to host).
+///
+/// Example:
+/// ```
+/// use core::num::NonZeroI32;
+/// use embive::interpreter::{memory::Memory, peripherals::Peripherals, Error, SYSCALL_ARGS};
+///
+/// fn syscall<M: Memory>(
+///     peripherals: &mut Peripherals<64, 64>,
+///     nr: i32,
+///     args: &[i32; SYSCALL_ARGS],
+///     memory: &mut M,
+/// ) -> Result<Result<i32, NonZeroI32>, Error> {
+///     // Try the peripherals first, then the host-specific syscalls
+///     if let Some(result) = peripherals.syscall(nr, args, memory) {
+///         return Ok(result);
+///     }
+///
+///     Ok(Err(NonZeroI32::new(-1).unwrap())) // Not implemented
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Peripherals<const RX: usize, const TX: usize> {
+    /// UART
+    pub uart: Uart<RX, TX>,
+    /// GPIO bank
+    pub gpio: Gpio,
+    /// Timer
+    pub timer: Timer,
+}
+
+impl<const RX: usize, const TX: usize> Peripherals<RX, TX> {
+    /// Handle a peripheral syscall.
+    ///
+    /// Arguments:
+    /// - `nr`: Syscall number.
+    /// - `args`: Syscall arguments.
+    /// - `memory`: System memory (code + RAM).
+    ///
+    /// Returns:
+    /// - `Some(Result<i32, NonZeroI32>)`: The syscall was handled, result to be returned to the guest.
+    /// - `None`: Not a peripheral syscall.
+    pub fn syscall<M: Memory>(
+        &mut self,
+        nr: i32,
+        args: &[i32; SYSCALL_ARGS],
+        memory: &mut M,
+    ) -> Option<Result<i32, NonZeroI32>> {
+        Some(match nr {
+            UART_WRITE => {
+                let len = (args[1] as u32 as usize).min(self.uart.tx.free());
+                match memory.load_bytes(args[0] as u32, len) {
+                    Ok(data) => Ok(self.uart.tx.push_slice(data) as i32),
+                    Err(_) => Err(INVALID_ADDRESS),
+                }
+            }
+            UART_READ => {
+                let len = (args[1] as u32 as usize).min(self.uart.rx.len());
+                match memory.mut_bytes(args[0] as u32, len) {
+                    Ok(data) => Ok(self.uart.rx.pop_slice(data) as i32),
+                    Err(_) => Err(INVALID_ADDRESS),
+                }
+            }
+            GPIO_WRITE => {
+                let mask = args[0] as u32;
+                self.gpio.output = (self.gpio.output & !mask) | (args[1] as u32 & mask);
+                Ok(self.gpio.output as i32)
+            }
+            GPIO_READ => Ok(self.gpio.input as i32),
+            TIMER_NOW => Ok(self.timer.now as u32 as i32),
+            TIMER_SET_ALARM => {
+                self.timer.alarm = Some(self.timer.now.wrapping_add(args[0] as u32 as u64));
+                Ok(0)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Invalid address error code (checked to be non-zero at compile time).
+const INVALID_ADDRESS: NonZeroI32 = match NonZeroI32::new(ERROR_INVALID_ADDRESS) {
+    Some(code) => code,
+    None => panic!("error codes must be non-zero"),
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::{SliceMemory, RAM_OFFSET};
+
+    fn args(a0: i32, a1: i32) -> [i32; SYSCALL_ARGS] {
+        let mut args = [0; SYSCALL_ARGS];
+        args[0] = a0;
+        args[1] = a1;
+        args
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut buffer = RingBuffer::<4>::new();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.push_slice(&[1, 2, 3, 4, 5]), 4);
+        assert!(buffer.is_full());
+        assert!(!buffer.push(6));
+
+        assert_eq!(buffer.pop(), Some(1));
+        assert!(buffer.push(6));
+
+        let mut data = [0; 8];
+        assert_eq!(buffer.pop_slice(&mut data), 4);
+        assert_eq!(data[..4], [2, 3, 4, 6]);
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn test_uart() {
+        let mut ram = *b"hello\0\0\0";
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let mut peripherals = Peripherals::<4, 4>::default();
+
+        // Guest writes (limited by TX buffer)
+        let result = peripherals.syscall(UART_WRITE, &args(RAM_OFFSET as i32, 5), &mut memory);
+        assert_eq!(result, Some(Ok(4)));
+        let mut data = [0; 8];
+        assert_eq!(peripherals.uart.host_read(&mut data), 4);
+        assert_eq!(&data[..4], b"hell");
+
+        // Guest reads (limited by RX data)
+        assert_eq!(peripherals.uart.host_write(b"ok"), 2);
+        let result = peripherals.syscall(UART_READ, &args(RAM_OFFSET as i32 + 5, 3), &mut memory);
+        assert_eq!(result, Some(Ok(2)));
+        assert_eq!(peripherals.uart.rx_pending(), 0);
+        assert_eq!(&ram[5..], b"ok\0");
+    }
+
+    #[test]
+    fn test_uart_invalid_address() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut peripherals = Peripherals::<4, 4>::default();
+
+        let result = peripherals.syscall(UART_WRITE, &args(RAM_OFFSET as i32, 1), &mut memory);
+        assert_eq!(result, Some(Err(INVALID_ADDRESS)));
+        assert_eq!(peripherals.uart.tx_pending(), 0);
+    }
+
+    #[test]
+    fn test_gpio() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut peripherals = Peripherals::<4, 4>::default();
+
+        let result = peripherals.syscall(GPIO_WRITE, &args(0b1111, 0b0101), &mut memory);
+        assert_eq!(result, Some(Ok(0b0101)));
+        let result = peripherals.syscall(GPIO_WRITE, &args(0b0011, 0b0010), &mut memory);
+        assert_eq!(result, Some(Ok(0b0110)));
+        assert_eq!(peripherals.gpio.output, 0b0110);
+
+        peripherals.gpio.input = 0xF0;
+        let result = peripherals.syscall(GPIO_READ, &args(0, 0), &mut memory);
+        assert_eq!(result, Some(Ok(0xF0)));
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut peripherals = Peripherals::<4, 4>::default();
+
+        peripherals.timer.advance(10);
+        let result = peripherals.syscall(TIMER_NOW, &args(0, 0), &mut memory);
+        assert_eq!(result, Some(Ok(10)));
+
+        let result = peripherals.syscall(TIMER_SET_ALARM, &args(5, 0), &mut memory);
+        assert_eq!(result, Some(Ok(0)));
+        assert_eq!(peripherals.timer.alarm(), Some(15));
+
+        peripherals.timer.advance(4);
+        assert!(!peripherals.timer.take_alarm());
+        peripherals.timer.advance(1);
+        assert!(peripherals.timer.take_alarm());
+        assert!(!peripherals.timer.take_alarm());
+
+        peripherals.timer.sync(20);
+        assert_eq!(peripherals.timer.now(), 20);
+        peripherals.timer.sync(12);
+        assert_eq!(peripherals.timer.now(), 20);
+    }
+
+    #[test]
+    fn test_unknown_syscall() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut peripherals = Peripherals::<4, 4>::default();
+
+        assert_eq!(peripherals.syscall(1, &args(0, 0), &mut memory), None);
+    }
+}
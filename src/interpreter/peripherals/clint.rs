@@ -0,0 +1,267 @@
+//! CLINT Module
+//!
+//! CLINT-compatible machine software interrupt register (`msip`, hart 0), so RTOS ports (e.g. FreeRTOS, RIOT)
+//! triggering their context switches through it run unmodified. The register mirrors `mip.MSIP`:
+//! - Guest: word stores to [`CLINT_MSIP`] set (bit 0 set) or clear the software interrupt, word loads read it.
+//! - Host: [`crate::interpreter::Interpreter::raise_software_interrupt`] and
+//!   [`crate::interpreter::Interpreter::clear_software_interrupt`], as usual.
+//!
+//! Stores take effect before the next instruction, like a `mip` CSR write. The register is only synchronized when
+//! either side is written (check [`Memory::sync_software_interrupt`]), so `mip` writes bypassing
+//! [`crate::interpreter::Interpreter::csr_operation`] (e.g. through `registers.control_status`) are only seen by loads
+//! after the next synchronization. Other CLINT registers (`mtime`, `mtimecmp`) aren't implemented, check
+//! [`super::Timer`].
+use crate::interpreter::{memory::Memory, Error};
+
+/// CLINT `msip` register address (hart 0), at the standard CLINT base.
+pub const CLINT_MSIP: u32 = crate::protocol::CLINT_MSIP;
+
+/// CLINT Memory
+///
+/// Wraps a [`Memory`], mapping a CLINT `msip` register (check the [module documentation](self)).
+/// Only word accesses (`lw`, `sw`, `c.lw`, `c.sw`) reach the register, other accesses go to the wrapped memory.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{
+///     memory::SliceMemory,
+///     peripherals::clint::{ClintMemory, CLINT_MSIP},
+///     Interpreter,
+/// };
+///
+/// // Code: li a0, 1; sw a0, 0(a1); ebreak (already transpiled)
+/// let code = [0x1d, 0x28, 0x10, 0x00, 0x9b, 0xab, 0x05, 0x00, 0x1f, 0x00, 0x10, 0x00];
+/// let mut memory = ClintMemory::new(SliceMemory::new(&code, &mut []), CLINT_MSIP);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+/// interpreter.registers.cpu.set_a1(CLINT_MSIP as i32);
+///
+/// // Software interrupt raised by the guest (`mip.MSIP`, taken once enabled)
+/// interpreter.run().unwrap();
+/// let mip = interpreter.registers.control_status.operation(None, 0x344).unwrap();
+/// assert_eq!(mip & 0x8, 0x8);
+/// ```
+#[derive(Debug)]
+pub struct ClintMemory<M: Memory> {
+    memory: M,
+    /// Address of the `msip` register.
+    address: u32,
+    /// Current `mip.MSIP`, as of the last synchronization.
+    pending: bool,
+    /// Value stored by the guest, not yet synchronized.
+    written: Option<bool>,
+}
+
+impl<M: Memory> ClintMemory<M> {
+    /// Create a new CLINT memory.
+    ///
+    /// Arguments:
+    /// - `memory`: The wrapped memory.
+    /// - `address`: Address of the `msip` register (usually [`CLINT_MSIP`]), must not overlap the wrapped memory.
+    pub fn new(memory: M, address: u32) -> Self {
+        ClintMemory {
+            memory,
+            address,
+            pending: false,
+            written: None,
+        }
+    }
+
+    /// Get the address of the `msip` register.
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    /// Get the wrapped memory.
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Get the wrapped memory (mutable).
+    pub fn memory_mut(&mut self) -> &mut M {
+        &mut self.memory
+    }
+
+    /// Consume the CLINT memory, returning the wrapped memory.
+    pub fn into_inner(self) -> M {
+        self.memory
+    }
+}
+
+impl<M: Memory> Memory for ClintMemory<M> {
+    #[inline]
+    fn load_bytes(&mut self, address: u32, len: usize) -> Result<&[u8], Error> {
+        self.memory.load_bytes(address, len)
+    }
+
+    #[inline]
+    fn mut_bytes(&mut self, address: u32, len: usize) -> Result<&mut [u8], Error> {
+        self.memory.mut_bytes(address, len)
+    }
+
+    #[inline]
+    fn store_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.memory.store_bytes(address, data)
+    }
+
+    #[inline]
+    fn load_u8(&mut self, address: u32) -> Result<u8, Error> {
+        self.memory.load_u8(address)
+    }
+
+    #[inline]
+    fn load_u16(&mut self, address: u32) -> Result<u16, Error> {
+        self.memory.load_u16(address)
+    }
+
+    #[inline]
+    fn load_u32(&mut self, address: u32) -> Result<u32, Error> {
+        if address == self.address {
+            return Ok(self.written.unwrap_or(self.pending) as u32);
+        }
+
+        self.memory.load_u32(address)
+    }
+
+    #[inline]
+    fn store_u8(&mut self, address: u32, value: u8) -> Result<(), Error> {
+        self.memory.store_u8(address, value)
+    }
+
+    #[inline]
+    fn store_u16(&mut self, address: u32, value: u16) -> Result<(), Error> {
+        self.memory.store_u16(address, value)
+    }
+
+    #[inline]
+    fn store_u32(&mut self, address: u32, value: u32) -> Result<(), Error> {
+        if address == self.address {
+            self.written = Some(value & 1 != 0);
+            return Ok(());
+        }
+
+        self.memory.store_u32(address, value)
+    }
+
+    #[inline]
+    fn copy_from_guest(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        self.memory.copy_from_guest(address, buffer)
+    }
+
+    #[inline]
+    fn copy_to_guest(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.memory.copy_to_guest(address, data)
+    }
+
+    #[inline]
+    fn take_stall_cycles(&mut self) -> u32 {
+        self.memory.take_stall_cycles()
+    }
+
+    #[inline]
+    fn sync_software_interrupt(&mut self, pending: bool) -> bool {
+        let pending = self
+            .written
+            .take()
+            .unwrap_or(self.memory.sync_software_interrupt(pending));
+        self.pending = pending;
+        pending
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::{
+            memory::SliceMemory, registers::CSOperation, ExitReason, Interpreter, State,
+        },
+        protocol::SOFTWARE_INTERRUPT_MCAUSE,
+        transpiler::transpile_raw,
+    };
+
+    #[test]
+    fn test_msip() {
+        let mut code = [
+            0x13, 0x05, 0x10, 0x00, // 0: li   a0, 1
+            0x23, 0xa0, 0xa5, 0x00, // 4: sw   a0, 0(a1) (raise)
+            0x13, 0x00, 0x00, 0x00, // 8: nop
+            0x73, 0x00, 0x10, 0x00, // 12: ebreak
+            0x03, 0xa6, 0x05, 0x00, // 16: lw   a2, 0(a1) (handler)
+            0x23, 0xa0, 0x05, 0x00, // 20: sw   zero, 0(a1) (clear)
+            0x73, 0x00, 0x20, 0x30, // 24: mret
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = ClintMemory::new(SliceMemory::new(&code, &mut []), CLINT_MSIP);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_a1(CLINT_MSIP as i32);
+
+        // Handler at 16, software interrupts enabled
+        let control_status = &mut interpreter.registers.control_status;
+        control_status
+            .operation(Some(CSOperation::Write(16)), 0x305)
+            .unwrap();
+        control_status
+            .operation(Some(CSOperation::Write(0x8)), 0x304)
+            .unwrap();
+        control_status
+            .operation(Some(CSOperation::Write(0x8)), 0x300)
+            .unwrap();
+
+        // Taken right after the store, cleared by the handler
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        let control_status = &mut interpreter.registers.control_status;
+        assert_eq!(
+            control_status.operation(None, 0x342),
+            Ok(SOFTWARE_INTERRUPT_MCAUSE)
+        );
+        assert_eq!(control_status.operation(None, 0x341), Ok(8));
+        assert!(!control_status.mip_software);
+        assert_eq!(interpreter.registers.cpu.a2(), 1);
+
+        // Raised by the host (disabled): visible to guest loads, other addresses unaffected
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0)), 0x304)
+            .unwrap();
+        interpreter.raise_software_interrupt(0);
+        interpreter.program_counter = 12;
+        assert_eq!(
+            interpreter.step(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(memory.load_u32(CLINT_MSIP), Ok(1));
+        assert_eq!(
+            memory.load_u32(0),
+            Ok(u32::from_le_bytes(code[..4].try_into().unwrap()))
+        );
+    }
+    #[test]
+    fn test_msip_csr_write() {
+        let mut code = [0x13, 0x00, 0x00, 0x00]; // nop
+        transpile_raw(&mut code).unwrap();
+        let mut memory = ClintMemory::new(SliceMemory::new(&code, &mut []), CLINT_MSIP);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // Visible to loads right after the CSR write, without executing anything
+        interpreter
+            .csr_operation(Some(CSOperation::Write(0x8)), 0x344)
+            .unwrap();
+        assert_eq!(interpreter.memory.load_u32(CLINT_MSIP), Ok(1));
+        interpreter.clear_software_interrupt();
+        assert_eq!(interpreter.memory.load_u32(CLINT_MSIP), Ok(0));
+
+        // Not synchronized by instructions without stores (host store, bypassing the interpreter)
+        interpreter.memory.store_u32(CLINT_MSIP, 1).unwrap();
+        interpreter.step().unwrap();
+        assert!(!interpreter.registers.control_status.mip_software);
+        assert_eq!(interpreter.memory.load_u32(CLINT_MSIP), Ok(1));
+    }
+}
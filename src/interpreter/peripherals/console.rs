@@ -0,0 +1,332 @@
+//! Console Module
+//!
+//! Console-style streaming (stdin/stdout) through ring buffers in guest RAM, without one syscall per byte:
+//! the host writes stdin and reads stdout directly from the guest memory, and the guest does the same on its side.
+//!
+//! Each ring is a header followed by `capacity` data bytes (all values are little-endian `u32`):
+//! - `head` (offset `0`): Total number of bytes written (only updated by the producer).
+//! - `tail` (offset `4`): Total number of bytes read (only updated by the consumer).
+//! - Data (offset [`RING_HEADER_SIZE`]): Byte `n` of the stream is at `n % capacity`.
+//!
+//! The console places the stdin ring at its base address, followed by the stdout ring (check [`Console::size`]).
+//! The guest waits for stdin data with `wfi`, the host triggers an interrupt ([`CONSOLE_INTERRUPT`]) after
+//! writing to it (check [`Console::write_stdin`]).
+use crate::interpreter::{memory::Memory, Error, Interpreter};
+
+/// Size of a ring header (`head` and `tail`), in bytes.
+pub const RING_HEADER_SIZE: u32 = crate::protocol::RING_HEADER_SIZE;
+
+/// Interrupt value (`mtval`) of a console stdin interrupt (data available).
+pub const CONSOLE_INTERRUPT: i32 = crate::protocol::CONSOLE_INTERRUPT;
+
+/// Ring buffer in guest memory (check the [module documentation](self)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ring {
+    /// Ring address (header start).
+    pub address: u32,
+    /// Data capacity, in bytes.
+    pub capacity: u32,
+}
+
+impl Ring {
+    /// Get the ring size (header and data), in bytes.
+    pub const fn size(&self) -> u32 {
+        RING_HEADER_SIZE + self.capacity
+    }
+
+    /// Load the ring indexes (`head`, `tail`).
+    fn indexes<M: Memory>(&self, memory: &mut M) -> Result<(u32, u32), Error> {
+        Ok((
+            memory.load_u32(self.address)?,
+            memory.load_u32(self.address + 4)?,
+        ))
+    }
+
+    /// Reset the ring (empty, indexes set to 0).
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    pub fn reset<M: Memory>(&self, memory: &mut M) -> Result<(), Error> {
+        memory.store_u32(self.address, 0)?;
+        memory.store_u32(self.address + 4, 0)
+    }
+
+    /// Get the number of bytes available to read.
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    pub fn len<M: Memory>(&self, memory: &mut M) -> Result<u32, Error> {
+        let (head, tail) = self.indexes(memory)?;
+        Ok(head.wrapping_sub(tail).min(self.capacity))
+    }
+
+    /// Check if the ring is empty.
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    pub fn is_empty<M: Memory>(&self, memory: &mut M) -> Result<bool, Error> {
+        self.len(memory).map(|len| len == 0)
+    }
+
+    /// Write as many bytes as possible to the ring (producer side).
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    /// - `data`: Bytes to write.
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, number of bytes written (limited by the free space).
+    /// - `Err(Error)`: The ring is out of bounds.
+    pub fn write<M: Memory>(&self, memory: &mut M, data: &[u8]) -> Result<usize, Error> {
+        let (head, tail) = self.indexes(memory)?;
+        let free = self.capacity.saturating_sub(head.wrapping_sub(tail));
+        let count = (data.len() as u32).min(free);
+
+        let mut written = 0;
+        while written < count {
+            let offset = head.wrapping_add(written) % self.capacity;
+            let chunk = (count - written).min(self.capacity - offset);
+            let start = written as usize;
+            memory.copy_to_guest(
+                self.address + RING_HEADER_SIZE + offset,
+                &data[start..start + chunk as usize],
+            )?;
+            written += chunk;
+        }
+
+        memory.store_u32(self.address, head.wrapping_add(count))?;
+        Ok(count as usize)
+    }
+
+    /// Read as many bytes as possible from the ring (consumer side).
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    /// - `buffer`: Output buffer.
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, number of bytes read (limited by the available data).
+    /// - `Err(Error)`: The ring is out of bounds.
+    pub fn read<M: Memory>(&self, memory: &mut M, buffer: &mut [u8]) -> Result<usize, Error> {
+        let (head, tail) = self.indexes(memory)?;
+        let available = head.wrapping_sub(tail).min(self.capacity);
+        let count = (buffer.len() as u32).min(available);
+
+        let mut read = 0;
+        while read < count {
+            let offset = tail.wrapping_add(read) % self.capacity;
+            let chunk = (count - read).min(self.capacity - offset);
+            let start = read as usize;
+            memory.copy_from_guest(
+                self.address + RING_HEADER_SIZE + offset,
+                &mut buffer[start..start + chunk as usize],
+            )?;
+            read += chunk;
+        }
+
+        memory.store_u32(self.address + 4, tail.wrapping_add(count))?;
+        Ok(count as usize)
+    }
+}
+
+/// Ring Buffer Console
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{
+///     memory::{SliceMemory, RAM_OFFSET},
+///     peripherals::console::Console,
+///     Interpreter,
+/// };
+///
+/// let mut ram = [0; 64];
+/// let mut memory = SliceMemory::new(&[], &mut ram);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// // Rings with 16 bytes of capacity at the start of RAM
+/// let console = Console::new(RAM_OFFSET, 16);
+/// console.reset(interpreter.memory).unwrap();
+///
+/// // Guest has not enabled interrupts, it will poll the stdin ring
+/// assert_eq!(console.write_stdin(&mut interpreter, b"hello"), Ok(5));
+/// assert_eq!(console.stdin().len(interpreter.memory), Ok(5));
+///
+/// // Nothing written by the guest yet
+/// let mut buffer = [0; 16];
+/// assert_eq!(console.read_stdout(interpreter.memory, &mut buffer), Ok(0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Console {
+    stdin: Ring,
+    stdout: Ring,
+}
+
+impl Console {
+    /// Create a new console.
+    ///
+    /// Arguments:
+    /// - `address`: Console address in guest RAM (reserved area of [`Console::size`] bytes, word-aligned).
+    /// - `capacity`: Data capacity of each ring, in bytes.
+    pub const fn new(address: u32, capacity: u32) -> Self {
+        let stdin = Ring { address, capacity };
+        Console {
+            stdin,
+            stdout: Ring {
+                address: address + stdin.size(),
+                capacity,
+            },
+        }
+    }
+
+    /// Get the console size (both rings), in bytes.
+    pub const fn size(&self) -> u32 {
+        self.stdin.size() + self.stdout.size()
+    }
+
+    /// Get the stdin ring (host to guest).
+    pub fn stdin(&self) -> Ring {
+        self.stdin
+    }
+
+    /// Get the stdout ring (guest to host).
+    pub fn stdout(&self) -> Ring {
+        self.stdout
+    }
+
+    /// Reset both rings (e.g. before starting the guest).
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    pub fn reset<M: Memory>(&self, memory: &mut M) -> Result<(), Error> {
+        self.stdin.reset(memory)?;
+        self.stdout.reset(memory)
+    }
+
+    /// Write to the guest stdin, triggering an interrupt ([`CONSOLE_INTERRUPT`]) if any data was written.
+    ///
+    /// The interrupt is skipped if not enabled by the interpreted code (it can still poll the ring).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The guest interpreter.
+    /// - `data`: Bytes to write.
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, number of bytes written (limited by the free space).
+    /// - `Err(Error)`: The ring is out of bounds.
+    pub fn write_stdin<M: Memory>(
+        &self,
+        interpreter: &mut Interpreter<'_, M>,
+        data: &[u8],
+    ) -> Result<usize, Error> {
+        let written = self.stdin.write(interpreter.memory, data)?;
+        if written > 0 {
+            match interpreter.interrupt(CONSOLE_INTERRUPT) {
+                Ok(()) | Err(Error::InterruptNotEnabled) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Read from the guest stdout.
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    /// - `buffer`: Output buffer.
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, number of bytes read.
+    /// - `Err(Error)`: The ring is out of bounds.
+    pub fn read_stdout<M: Memory>(
+        &self,
+        memory: &mut M,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error> {
+        self.stdout.read(memory, buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        registers::CSOperation,
+    };
+
+    #[test]
+    fn test_ring_wrap() {
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let ring = Ring {
+            address: RAM_OFFSET,
+            capacity: 4,
+        };
+        assert_eq!(ring.size(), 12);
+
+        let mut buffer = [0; 8];
+        assert_eq!(ring.write(&mut memory, b"abc"), Ok(3));
+        assert_eq!(ring.read(&mut memory, &mut buffer[..2]), Ok(2));
+        assert_eq!(&buffer[..2], b"ab");
+
+        // Wraps around the end of the data
+        assert_eq!(ring.write(&mut memory, b"defgh"), Ok(3));
+        assert_eq!(ring.len(&mut memory), Ok(4));
+        assert_eq!(ring.read(&mut memory, &mut buffer), Ok(4));
+        assert_eq!(&buffer[..4], b"cdef");
+        assert_eq!(ring.is_empty(&mut memory), Ok(true));
+
+        // Header: head = 6, tail = 6
+        assert_eq!(ram[..8], [6, 0, 0, 0, 6, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_console() {
+        let mut ram = [0; 64];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let console = Console::new(RAM_OFFSET, 8);
+        assert_eq!(console.size(), 32);
+        assert_eq!(console.stdout().address, RAM_OFFSET + 16);
+
+        // Guest writes to stdout
+        console.stdout().write(&mut memory, b"ok\n").unwrap();
+        let mut buffer = [0; 8];
+        assert_eq!(console.read_stdout(&mut memory, &mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], b"ok\n");
+
+        // Out of bounds rings
+        let console = Console::new(RAM_OFFSET + 64, 8);
+        assert!(console.reset(&mut memory).is_err());
+    }
+
+    #[test]
+    fn test_stdin_interrupt() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let console = Console::new(RAM_OFFSET, 8);
+
+        // Enable interrupts (mstatus.MIE, mie bit 16) with handler at 0x100
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(8)), 0x300)
+            .unwrap();
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(1 << 16)), 0x304)
+            .unwrap();
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0x100)), 0x305)
+            .unwrap();
+
+        assert_eq!(console.write_stdin(&mut interpreter, b""), Ok(0));
+        assert_eq!(interpreter.program_counter, 0);
+        assert_eq!(console.write_stdin(&mut interpreter, b"x"), Ok(1));
+        assert_eq!(interpreter.program_counter, 0x100);
+    }
+}
@@ -0,0 +1,148 @@
+//! Syscall Permissions Module
+//!
+//! Per-interpreter allowlist of host syscall numbers, with optional quotas (check
+//! [`Interpreter::set_syscall_permissions`]). Allows running guests with different trust levels against one shared
+//! syscall handler: syscalls outside of the allowlist (or over quota) never reach the host, the interpreted code gets
+//! [`super::SYSCALL_ERROR_PERMISSION_DENIED`] instead.
+//!
+//! Syscalls handled by the interpreter (e.g. [`super::EXIT_SYSCALL`], [`super::RANDOM_SYSCALL`]) aren't restricted.
+use super::{memory::Memory, Interpreter};
+
+/// Permission to issue a host syscall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallPermission {
+    /// Syscall number (`a7`).
+    pub nr: i32,
+    /// Remaining calls, decremented on each call (`None` for no quota).
+    pub quota: Option<u32>,
+}
+
+impl SyscallPermission {
+    /// Allow a syscall, without quota.
+    ///
+    /// Arguments:
+    /// - `nr`: Syscall number.
+    pub const fn new(nr: i32) -> Self {
+        SyscallPermission { nr, quota: None }
+    }
+
+    /// Allow a syscall, up to `quota` times (refill by updating [`SyscallPermission::quota`]).
+    ///
+    /// Arguments:
+    /// - `nr`: Syscall number.
+    /// - `quota`: Number of calls allowed.
+    pub const fn with_quota(nr: i32, quota: u32) -> Self {
+        SyscallPermission {
+            nr,
+            quota: Some(quota),
+        }
+    }
+}
+
+impl<'a, M: Memory> Interpreter<'a, M> {
+    /// Set the syscall permissions, an allowlist of host syscalls. Kept on reset (quotas aren't refilled).
+    ///
+    /// Host syscalls outside of the allowlist (or over quota) aren't returned to the host ([`super::State::Called`]),
+    /// the interpreted code gets [`super::SYSCALL_ERROR_PERMISSION_DENIED`] (`a0`) instead, and `a1` is cleared.
+    /// Syscalls handled by the interpreter (e.g. [`super::EXIT_SYSCALL`]) aren't restricted.
+    ///
+    /// Arguments:
+    /// - `permissions`: Allowed host syscalls (`None` allows all of them). If a syscall number is listed more than
+    ///   once, the first entry with calls left is used.
+    pub fn set_syscall_permissions(&mut self, permissions: Option<&'a mut [SyscallPermission]>) {
+        self.syscall_permissions = permissions;
+    }
+
+    /// Get the syscall permissions (e.g. to check the remaining quotas).
+    pub fn syscall_permissions(&self) -> Option<&[SyscallPermission]> {
+        self.syscall_permissions.as_deref()
+    }
+
+    /// Get the syscall permissions (mutable, e.g. to refill the quotas).
+    pub fn syscall_permissions_mut(&mut self) -> Option<&mut [SyscallPermission]> {
+        self.syscall_permissions.as_deref_mut()
+    }
+
+    /// Check if a host syscall is permitted, consuming its quota.
+    ///
+    /// Arguments:
+    /// - `nr`: Syscall number.
+    #[cold]
+    pub(crate) fn syscall_permitted(&mut self, nr: i32) -> bool {
+        let Some(permissions) = self.syscall_permissions.as_deref_mut() else {
+            return true;
+        };
+
+        permissions
+            .iter_mut()
+            .filter(|permission| permission.nr == nr)
+            .any(|permission| match &mut permission.quota {
+                None => true,
+                Some(0) => false,
+                Some(quota) => {
+                    *quota -= 1;
+                    true
+                }
+            })
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::{memory::SliceMemory, State, SYSCALL_ERROR_PERMISSION_DENIED, YIELD_SYSCALL},
+        transpiler::transpile_raw,
+    };
+
+    #[test]
+    fn test_permissions() {
+        let mut code = [
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x6f, 0xf0, 0xdf, 0xff, // j 0
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut permissions = [
+            SyscallPermission::new(1),
+            SyscallPermission::with_quota(2, 1),
+        ];
+        let mut interpreter = Interpreter::builder(&mut memory)
+            .syscall_permissions(&mut permissions)
+            .build()
+            .unwrap();
+
+        // Allowed
+        interpreter.registers.cpu.set_a7(1);
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter.complete_syscall(Ok(7)).unwrap();
+
+        // Allowed once
+        interpreter.registers.cpu.set_a7(2);
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter.complete_syscall(Ok(7)).unwrap();
+        assert_eq!(interpreter.syscall_permissions().unwrap()[1].quota, Some(0));
+
+        // Denied (over quota, or not listed), the guest continues
+        for nr in [2, 3] {
+            interpreter.registers.cpu.set_a7(nr);
+            interpreter.instruction_limit = 2;
+            assert_eq!(interpreter.run(), Ok(State::Running));
+            assert_eq!(
+                interpreter.registers.cpu.a0(),
+                SYSCALL_ERROR_PERMISSION_DENIED
+            );
+            assert_eq!(interpreter.registers.cpu.a1(), 0);
+        }
+
+        // Interpreter syscalls aren't restricted
+        interpreter.registers.cpu.set_a7(YIELD_SYSCALL);
+        assert_eq!(interpreter.run(), Ok(State::Yielded));
+
+        // Quota refilled
+        interpreter.syscall_permissions_mut().unwrap()[1].quota = Some(1);
+        interpreter.registers.cpu.set_a7(2);
+        interpreter.instruction_limit = 0;
+        assert_eq!(interpreter.run(), Ok(State::Called));
+    }
+}
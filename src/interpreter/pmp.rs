@@ -0,0 +1,500 @@
+//! Physical Memory Protection Module
+//!
+//! Standard PMP CSRs (`pmp` feature), so privileged-spec-aware guests (e.g. an RTOS isolating its tasks)
+//! can program their own memory protections:
+//! - [`PMP_ENTRIES`] entries, configured through `pmpcfg0..=pmpcfg3` and `pmpaddr0..=pmpaddr15`. The other
+//!   PMP CSRs (`pmpcfg4..=pmpcfg15`, `pmpaddr16..=pmpaddr63`) are read-only zero.
+//! - Address matching modes OFF, TOR, NA4 and NAPOT, with a 4-byte granularity. Entries are checked in order,
+//!   the first one matching any byte of an access decides: it must cover the whole access and allow it.
+//! - User mode (check [`super::privilege`]) accesses need a matching entry with the `R`, `W` or `X` permission.
+//!   Machine mode accesses are only checked against locked entries ([`PMP_L`]), locked entries (and the
+//!   address of the previous entry, for TOR) can't be written until reset.
+//! - Violations trap to `mtvec`, with `mcause` set to [`super::privilege::INSTRUCTION_ACCESS_FAULT`],
+//!   [`super::privilege::LOAD_ACCESS_FAULT`] or [`super::privilege::STORE_ACCESS_FAULT`] (stores and AMOs) and
+//!   `mtval` to the faulting (virtual) address.
+//! - Checks apply to physical addresses, after translation (`mmu` feature). Page table walks and host accesses
+//!   (syscall arguments, [`super::Interpreter::memory`], debugger) aren't checked. `mstatus.MPRV` isn't supported.
+use super::{
+    memory::Memory,
+    privilege::{INSTRUCTION_ACCESS_FAULT, LOAD_ACCESS_FAULT, STORE_ACCESS_FAULT},
+    utils::likely,
+    Error, Interpreter, MemoryAccess,
+};
+
+/// First PMP configuration register (`pmpcfg0`) address.
+pub(crate) const PMPCFG0_ADDR: u16 = 0x3A0;
+/// First PMP address register (`pmpaddr0`) address.
+pub(crate) const PMPADDR0_ADDR: u16 = 0x3B0;
+/// Last PMP address register (`pmpaddr63`) address.
+pub(crate) const PMPADDR63_ADDR: u16 = 0x3EF;
+
+/// Number of implemented PMP entries.
+pub const PMP_ENTRIES: usize = 16;
+
+/// PMP configuration: readable.
+pub const PMP_R: u8 = 1 << 0;
+/// PMP configuration: writable.
+pub const PMP_W: u8 = 1 << 1;
+/// PMP configuration: executable.
+pub const PMP_X: u8 = 1 << 2;
+/// PMP configuration: address matching mode mask (`A` field).
+pub const PMP_A: u8 = 0b11 << 3;
+/// PMP configuration: top of range address matching (`A` field).
+pub const PMP_TOR: u8 = 1 << 3;
+/// PMP configuration: naturally aligned four-byte region (`A` field).
+pub const PMP_NA4: u8 = 2 << 3;
+/// PMP configuration: naturally aligned power-of-two region (`A` field).
+pub const PMP_NAPOT: u8 = 3 << 3;
+/// PMP configuration: locked (also enforced in machine mode).
+pub const PMP_L: u8 = 1 << 7;
+
+/// Number of words in the PMP snapshot (configurations and addresses, check [`Pmp::snapshot`]).
+pub(crate) const PMP_SNAPSHOT_WORDS: usize = PMP_ENTRIES / 4 + PMP_ENTRIES;
+
+/// PMP configuration writable bits (reserved bits are read-only zero).
+const PMP_MASK: u8 = PMP_L | PMP_A | PMP_X | PMP_W | PMP_R;
+
+/// Physical memory protection state.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub(crate) struct Pmp {
+    /// Entry configurations (`pmpcfg` bytes).
+    cfg: [u8; PMP_ENTRIES],
+    /// Entry addresses (`pmpaddr`, physical address bits 33:2).
+    addr: [u32; PMP_ENTRIES],
+    /// Active locked entries (checked in machine mode), one bit per entry.
+    locked: u16,
+}
+
+impl Pmp {
+    /// Read a PMP CSR (`pmpcfg0..=pmpcfg15` and `pmpaddr0..=pmpaddr63`, unimplemented ones are zero).
+    ///
+    /// Arguments:
+    /// - `addr`: The address of the register.
+    pub(crate) fn read(&self, addr: u16) -> u32 {
+        if addr < PMPADDR0_ADDR {
+            let base = (addr - PMPCFG0_ADDR) as usize * 4;
+            return match self.cfg.get(base..base + 4) {
+                Some(cfg) => u32::from_le_bytes([cfg[0], cfg[1], cfg[2], cfg[3]]),
+                None => 0,
+            };
+        }
+
+        let index = (addr - PMPADDR0_ADDR) as usize;
+        self.addr.get(index).copied().unwrap_or(0)
+    }
+
+    /// Write a PMP CSR (WARL, check [`Pmp::read`]). Writes to locked entries are ignored.
+    ///
+    /// Arguments:
+    /// - `addr`: The address of the register.
+    /// - `value`: The value to write.
+    pub(crate) fn write(&mut self, addr: u16, value: u32) {
+        if addr < PMPADDR0_ADDR {
+            let base = (addr - PMPCFG0_ADDR) as usize * 4;
+            if base < PMP_ENTRIES {
+                for (index, cfg) in (base..).zip(value.to_le_bytes()) {
+                    self.set_cfg(index, cfg);
+                }
+            }
+            return;
+        }
+
+        let index = (addr - PMPADDR0_ADDR) as usize;
+        if index < PMP_ENTRIES && !self.addr_locked(index) {
+            self.addr[index] = value;
+        }
+    }
+
+    /// Get the PMP state for a snapshot (`pmpcfg` registers, then `pmpaddr` registers).
+    pub(crate) fn snapshot(&self) -> [u32; PMP_SNAPSHOT_WORDS] {
+        let mut words = [0; PMP_SNAPSHOT_WORDS];
+        for (index, word) in words.iter_mut().enumerate() {
+            *word = match index < PMP_ENTRIES / 4 {
+                true => self.read(PMPCFG0_ADDR + index as u16),
+                false => self.addr[index - PMP_ENTRIES / 4],
+            };
+        }
+        words
+    }
+
+    /// Restore the PMP state from a snapshot (check [`Pmp::snapshot`]).
+    ///
+    /// Arguments:
+    /// - `words`: PMP state.
+    pub(crate) fn restore_snapshot(&mut self, words: &[u32]) {
+        let (cfg, addr) = words.split_at(PMP_ENTRIES / 4);
+        for (bytes, word) in self.cfg.chunks_exact_mut(4).zip(cfg) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        self.addr.copy_from_slice(addr);
+        self.update_locked();
+    }
+
+    /// Write an entry configuration (WARL, ignored if locked).
+    fn set_cfg(&mut self, index: usize, cfg: u8) {
+        if self.cfg[index] & PMP_L != 0 {
+            return;
+        }
+
+        let mut cfg = cfg & PMP_MASK;
+        if cfg & (PMP_R | PMP_W) == PMP_W {
+            // Reserved write-only encoding
+            cfg &= !PMP_W;
+        }
+        self.cfg[index] = cfg;
+        self.update_locked();
+    }
+
+    /// Check if an entry address can't be written (locked entry, or locked TOR next entry).
+    fn addr_locked(&self, index: usize) -> bool {
+        self.cfg[index] & PMP_L != 0
+            || self
+                .cfg
+                .get(index + 1)
+                .is_some_and(|&cfg| cfg & PMP_L != 0 && cfg & PMP_A == PMP_TOR)
+    }
+
+    /// Update the active locked entries (after a configuration change).
+    fn update_locked(&mut self) {
+        self.locked = self
+            .cfg
+            .iter()
+            .enumerate()
+            .filter(|(_, &cfg)| cfg & PMP_L != 0 && cfg & PMP_A != 0)
+            .fold(0, |locked, (index, _)| locked | 1 << index);
+    }
+
+    /// Check if accesses must be checked (user mode, or active locked entries).
+    #[inline(always)]
+    pub(crate) fn enforced(&self, user: bool) -> bool {
+        user || self.locked != 0
+    }
+
+    /// Get the byte range of an entry (`None` if off).
+    fn range(&self, index: usize) -> Option<(u64, u64)> {
+        let addr = self.addr[index] as u64;
+        match self.cfg[index] & PMP_A {
+            PMP_TOR => {
+                let start = match index {
+                    0 => 0,
+                    _ => (self.addr[index - 1] as u64) << 2,
+                };
+                Some((start, addr << 2))
+            }
+            PMP_NA4 => Some((addr << 2, (addr + 1) << 2)),
+            PMP_NAPOT => {
+                // Trailing ones encode the size (8 bytes and up)
+                let mask = addr ^ (addr + 1);
+                let start = addr & !mask;
+                Some((start << 2, (start + mask + 1) << 2))
+            }
+            _ => None,
+        }
+    }
+
+    /// Check a physical access.
+    ///
+    /// Arguments:
+    /// - `user`: The access is done in user mode.
+    /// - `address`: Physical address.
+    /// - `len`: Access width, in bytes.
+    /// - `permission`: Required permission ([`PMP_R`], [`PMP_W`] or [`PMP_X`]).
+    ///
+    /// Returns `true` if the access is allowed.
+    #[inline(never)]
+    pub(crate) fn check(&self, user: bool, address: u32, len: u32, permission: u8) -> bool {
+        let start = address as u64;
+        let end = start + len as u64;
+
+        for (index, &cfg) in self.cfg.iter().enumerate() {
+            let Some((low, high)) = self.range(index) else {
+                continue;
+            };
+            if start >= high || end <= low {
+                continue;
+            }
+
+            // First matching entry must cover the whole access
+            return start >= low
+                && end <= high
+                && ((!user && cfg & PMP_L == 0) || cfg & permission != 0);
+        }
+
+        // No match: machine mode is allowed, user mode isn't (entries are implemented)
+        !user
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Check a data access against the physical memory protection (check [`crate::interpreter::pmp`]).
+    ///
+    /// Arguments:
+    /// - `address`: Virtual address (reported on faults).
+    /// - `physical`: Physical address.
+    /// - `len`: Access width, in bytes.
+    /// - `access`: Kind of access.
+    #[inline(always)]
+    pub(crate) fn check_access(
+        &self,
+        address: u32,
+        physical: u32,
+        len: u32,
+        access: MemoryAccess,
+    ) -> Result<(), Error> {
+        let (permission, code) = match access {
+            MemoryAccess::Load => (PMP_R, LOAD_ACCESS_FAULT),
+            MemoryAccess::Store | MemoryAccess::Atomic => (PMP_W, STORE_ACCESS_FAULT),
+        };
+        self.check_pmp(address, physical, len, permission, code)
+    }
+
+    /// Check an instruction parcel (2 bytes) fetch against the physical memory protection.
+    ///
+    /// Arguments:
+    /// - `address`: Virtual address (reported on faults).
+    /// - `physical`: Physical address.
+    #[inline(always)]
+    pub(crate) fn check_fetch(&self, address: u32, physical: u32) -> Result<(), Error> {
+        self.check_pmp(address, physical, 2, PMP_X, INSTRUCTION_ACCESS_FAULT)
+    }
+
+    /// Check an access against the physical memory protection.
+    #[inline(always)]
+    fn check_pmp(
+        &self,
+        address: u32,
+        physical: u32,
+        len: u32,
+        permission: u8,
+        code: u32,
+    ) -> Result<(), Error> {
+        let control_status = &self.registers.control_status;
+        let user = control_status.user_mode();
+        let pmp = control_status.pmp();
+        if likely(!pmp.enforced(user)) || pmp.check(user, physical, len, permission) {
+            return Ok(());
+        }
+
+        Err(Error::AccessFault { code, address })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        registers::{CPURegister, CSOperation},
+        Config, ExitReason, State,
+    };
+
+    #[test]
+    fn test_ranges() {
+        let mut pmp = Pmp::default();
+        pmp.write(PMPADDR0_ADDR, 0x100 >> 2);
+        pmp.write(PMPADDR0_ADDR + 1, 0x200 >> 2);
+        pmp.write(PMPADDR0_ADDR + 2, (0x1000 >> 2) | 0b111); // 64 bytes
+        pmp.write(PMPADDR0_ADDR + 3, u32::MAX); // All memory
+        pmp.write(
+            PMPCFG0_ADDR,
+            u32::from_le_bytes([
+                PMP_NA4 | PMP_R,
+                PMP_TOR | PMP_R | PMP_W,
+                PMP_NAPOT | PMP_X,
+                PMP_NAPOT,
+            ]),
+        );
+
+        // NA4
+        assert!(pmp.check(true, 0x100, 4, PMP_R));
+        assert!(!pmp.check(true, 0x100, 4, PMP_W));
+        // TOR (from the previous address), partial matches are denied
+        assert!(pmp.check(true, 0x1FC, 4, PMP_W));
+        assert!(!pmp.check(true, 0x1FE, 4, PMP_W));
+        // NAPOT
+        assert!(pmp.check(true, 0x1000, 2, PMP_X));
+        assert!(pmp.check(true, 0x103C, 4, PMP_X));
+        assert!(!pmp.check(true, 0x1040, 4, PMP_X));
+        // Lowest entry has priority
+        assert!(!pmp.check(true, 0x100, 4, PMP_X));
+        // Machine mode only checks locked entries
+        assert!(pmp.check(false, 0x100, 4, PMP_X));
+        assert!(!pmp.enforced(false));
+        assert!(pmp.enforced(true));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let pmp = Pmp::default();
+
+        // User mode needs a matching entry, machine mode doesn't
+        assert!(!pmp.check(true, RAM_OFFSET, 4, PMP_R));
+        assert!(pmp.check(false, RAM_OFFSET, 4, PMP_R));
+    }
+
+    #[test]
+    fn test_warl() {
+        let mut pmp = Pmp::default();
+
+        // Reserved bits are zero, write-only is reserved
+        pmp.write(PMPCFG0_ADDR, 0x6060_FF02);
+        assert_eq!(pmp.read(PMPCFG0_ADDR), 0x0000_9F00);
+
+        // Unimplemented registers
+        pmp.write(PMPCFG0_ADDR + 4, u32::MAX);
+        pmp.write(PMPADDR0_ADDR + 16, u32::MAX);
+        assert_eq!(pmp.read(PMPCFG0_ADDR + 4), 0);
+        assert_eq!(pmp.read(PMPADDR63_ADDR), 0);
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut pmp = Pmp::default();
+        pmp.write(PMPADDR0_ADDR, 0x100 >> 2);
+        pmp.write(PMPADDR0_ADDR + 1, 0x200 >> 2);
+        pmp.write(PMPCFG0_ADDR, ((PMP_L | PMP_TOR | PMP_R) as u32) << 8);
+        assert!(pmp.enforced(false));
+
+        // Locked entry, and previous address (TOR)
+        pmp.write(PMPCFG0_ADDR, 0x1F1F);
+        pmp.write(PMPADDR0_ADDR, 0);
+        pmp.write(PMPADDR0_ADDR + 1, 0);
+        assert_eq!(pmp.read(PMPCFG0_ADDR), 0x891F);
+        assert_eq!(pmp.read(PMPADDR0_ADDR), 0x100 >> 2);
+        assert_eq!(pmp.read(PMPADDR0_ADDR + 1), 0x200 >> 2);
+
+        // Enforced in machine mode
+        assert!(!pmp.check(false, 0x1FC, 4, PMP_W));
+        assert!(pmp.check(false, 0x1FC, 4, PMP_R));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut pmp = Pmp::default();
+        pmp.write(PMPADDR0_ADDR + 5, 0x1234);
+        pmp.write(PMPCFG0_ADDR + 1, 0x8B00);
+
+        let mut restored = Pmp::default();
+        restored.restore_snapshot(&pmp.snapshot());
+        assert_eq!(restored, pmp);
+        assert!(restored.enforced(false));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_machine_locked() {
+        let mut code = [
+            0x23, 0x20, 0xb5, 0x00, // sw a1, 0(a0)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_a0(RAM_OFFSET as i32);
+        interpreter.registers.cpu.set_a1(42);
+
+        // Read-only locked word
+        let control_status = &mut interpreter.registers.control_status;
+        for (value, addr) in [
+            (4, 0x305),
+            (RAM_OFFSET >> 2, PMPADDR0_ADDR),
+            ((PMP_L | PMP_NA4 | PMP_R) as u32, PMPCFG0_ADDR),
+        ] {
+            control_status
+                .operation(Some(CSOperation::Write(value)), addr)
+                .unwrap();
+        }
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        let control_status = &mut interpreter.registers.control_status;
+        assert_eq!(
+            control_status.operation(None, 0x342),
+            Ok(STORE_ACCESS_FAULT)
+        );
+        assert_eq!(control_status.operation(None, 0x341), Ok(0));
+        assert_eq!(control_status.operation(None, 0x343), Ok(RAM_OFFSET));
+        assert_eq!(ram, [0; 16]);
+    }
+
+    /// Run user code at `entry` (with `a1` set) until it traps.
+    ///
+    /// Returns `mcause`, `mepc` and `mtval`.
+    #[cfg(feature = "transpiler")]
+    fn user_trap(entry: u32, a1: u32) -> (u32, u32, u32) {
+        let mut code = [
+            0x73, 0x00, 0x20, 0x30, // mret (enter user mode)
+            0x73, 0x00, 0x10, 0x00, // ebreak (trap handler)
+            0x03, 0xa5, 0x05, 0x00, // lw a0, 0(a1)
+            0x23, 0xa0, 0xa5, 0x00, // sw a0, 0(a1)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 128];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let config = Config::default().with_user_mode(true);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        interpreter
+            .registers
+            .cpu
+            .set(CPURegister::A1, a1 as i32)
+            .unwrap();
+
+        // Code [0, 16) is executable, first 64 bytes of RAM are read-only
+        let control_status = &mut interpreter.registers.control_status;
+        for (value, addr) in [
+            (4, 0x305),
+            (entry, 0x341),
+            (16 >> 2, PMPADDR0_ADDR),
+            ((RAM_OFFSET >> 2) | 0b111, PMPADDR0_ADDR + 1),
+            (
+                u32::from_le_bytes([PMP_TOR | PMP_X | PMP_R, PMP_NAPOT | PMP_R, 0, 0]),
+                PMPCFG0_ADDR,
+            ),
+        ] {
+            control_status
+                .operation(Some(CSOperation::Write(value)), addr)
+                .unwrap();
+        }
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.program_counter, 8);
+
+        let control_status = &mut interpreter.registers.control_status;
+        (
+            control_status.operation(None, 0x342).unwrap(),
+            control_status.operation(None, 0x341).unwrap(),
+            control_status.operation(None, 0x343).unwrap(),
+        )
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_user_access() {
+        // Load allowed, store denied
+        assert_eq!(
+            user_trap(8, RAM_OFFSET),
+            (STORE_ACCESS_FAULT, 12, RAM_OFFSET)
+        );
+        // Load outside of the region
+        assert_eq!(
+            user_trap(8, RAM_OFFSET + 64),
+            (LOAD_ACCESS_FAULT, 8, RAM_OFFSET + 64)
+        );
+        // Fetch outside of the executable region
+        assert_eq!(
+            user_trap(16, RAM_OFFSET),
+            (INSTRUCTION_ACCESS_FAULT, 16, 16)
+        );
+    }
+}
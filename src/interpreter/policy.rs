@@ -0,0 +1,186 @@
+//! Instruction Policy Module
+//!
+//! Host-configured instruction classes denied at runtime (check [`super::Config::instruction_policy`]), even when
+//! compiled in and enabled, e.g. for restricted plugin tiers. Enforced when dispatching each instruction: executing a
+//! denied instruction fails with [`super::Error::InstructionDenied`] (also in user mode, it doesn't trap).
+
+/// Number of policy classes (check [`PolicyClass`]).
+pub const POLICY_CLASSES: usize = 12;
+
+/// Restricted instruction class. Other instructions (integer computation, loads, stores, branches and jumps)
+/// are always allowed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PolicyClass {
+    /// Multiplications (`mul`, `mulh`, `mulhsu`, `mulhu`).
+    Multiply = 0,
+    /// Divisions and remainders (`div`, `divu`, `rem`, `remu`).
+    Divide = 1,
+    /// Atomics (`lr.w`, `sc.w`, AMOs).
+    Atomic = 2,
+    /// CSR reads without writes (`csrrs`/`csrrc` with `x0` or a zero immediate).
+    CsrRead = 3,
+    /// CSR writes (`csrrw`, `csrrs`, `csrrc` and their immediate versions).
+    CsrWrite = 4,
+    /// Environment calls (`ecall`, syscalls).
+    Ecall = 5,
+    /// Breakpoints (`ebreak`, `c.ebreak`).
+    Ebreak = 6,
+    /// Wait for interrupt (`wfi`).
+    Wfi = 7,
+    /// Trap return (`mret`).
+    Mret = 8,
+    /// Instruction fence (`fence.i`).
+    FenceI = 9,
+    /// Address translation fence (`sfence.vma`).
+    SfenceVma = 10,
+    /// Custom instructions (check [`super::CustomInstruction`]).
+    Custom = 11,
+}
+
+/// Embive Instruction Policy
+///
+/// Set of denied instruction classes. Allows everything by default.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{Config, PolicyClass, InstructionPolicy};
+///
+/// // Deny list: no atomics nor CSR writes
+/// let policy = InstructionPolicy::new()
+///     .deny(PolicyClass::Atomic)
+///     .deny(PolicyClass::CsrWrite);
+/// assert!(policy.denies(PolicyClass::Atomic));
+///
+/// // Allow list: only syscalls and CSR reads (besides unrestricted instructions)
+/// let policy = InstructionPolicy::deny_all()
+///     .allow(PolicyClass::Ecall)
+///     .allow(PolicyClass::CsrRead);
+/// assert!(policy.denies(PolicyClass::Wfi));
+///
+/// let config = Config::default().with_instruction_policy(policy);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct InstructionPolicy {
+    /// Denied classes, one bit per [`PolicyClass`].
+    denied: u16,
+}
+
+impl InstructionPolicy {
+    /// Create a new policy, allowing all instructions.
+    pub const fn new() -> Self {
+        InstructionPolicy { denied: 0 }
+    }
+
+    /// Create a new policy, denying all restricted classes (build allow lists with [`InstructionPolicy::allow`]).
+    pub const fn deny_all() -> Self {
+        InstructionPolicy {
+            denied: (1 << POLICY_CLASSES) - 1,
+        }
+    }
+
+    /// Deny an instruction class.
+    pub const fn deny(mut self, class: PolicyClass) -> Self {
+        self.denied |= 1 << class as u16;
+        self
+    }
+
+    /// Allow an instruction class.
+    pub const fn allow(mut self, class: PolicyClass) -> Self {
+        self.denied &= !(1 << class as u16);
+        self
+    }
+
+    /// Check if an instruction class is denied.
+    #[inline(always)]
+    pub const fn denies(&self, class: PolicyClass) -> bool {
+        self.denied & (1 << class as u16) != 0
+    }
+
+    /// Check if all instructions are allowed.
+    #[inline(always)]
+    pub const fn allows_all(&self) -> bool {
+        self.denied == 0
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::{
+            memory::SliceMemory, registers::CSOperation, Config, Error, ExitReason, Interpreter,
+            State,
+        },
+        transpiler::transpile_raw,
+    };
+
+    #[test]
+    fn test_policy() {
+        let policy = InstructionPolicy::new();
+        assert!(policy.allows_all());
+        assert!(!policy.denies(PolicyClass::Custom));
+
+        let policy = policy.deny(PolicyClass::Custom).deny(PolicyClass::Wfi);
+        assert!(policy.denies(PolicyClass::Custom));
+        assert!(!policy.allow(PolicyClass::Wfi).denies(PolicyClass::Wfi));
+
+        let policy = InstructionPolicy::deny_all().allow(PolicyClass::Multiply);
+        assert!(policy.denies(PolicyClass::Custom));
+        assert!(!policy.denies(PolicyClass::Multiply));
+    }
+
+    #[test]
+    fn test_denied() {
+        let mut code = [
+            0x73, 0x25, 0x00, 0x30, // csrr   a0, mstatus
+            0x2f, 0xa5, 0xb5, 0x00, // amoadd.w a0, a1, (a1)
+            0x73, 0x10, 0x05, 0x30, // csrw   mstatus, a0
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let policy = InstructionPolicy::new()
+            .deny(PolicyClass::Atomic)
+            .deny(PolicyClass::CsrWrite);
+        let config = Config::default()
+            .with_instruction_policy(policy)
+            .with_user_mode(true);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        // CSR reads and breakpoints allowed
+        assert_eq!(interpreter.step(), Ok(State::Running));
+        interpreter.program_counter = 12;
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Atomics and CSR writes denied, also in user mode (no trap)
+        let control_status = &mut interpreter.registers.control_status;
+        control_status
+            .operation(Some(CSOperation::Write(0)), 0x300)
+            .unwrap();
+        control_status.trap_return();
+        assert!(control_status.user_mode());
+        interpreter.program_counter = 4;
+        assert_eq!(
+            interpreter.run(),
+            Err(Error::InstructionDenied {
+                pc: 4,
+                class: PolicyClass::Atomic
+            })
+        );
+        assert_eq!(interpreter.program_counter, 4);
+
+        interpreter.program_counter = 8;
+        assert_eq!(
+            interpreter.run(),
+            Err(Error::InstructionDenied {
+                pc: 8,
+                class: PolicyClass::CsrWrite
+            })
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Privilege Module
+//!
+//! Machine and user privilege modes (check [`super::Config::user_mode`]), so a small kernel can run
+//! user tasks inside the interpreter. Supervisor mode (and trap delegation) is not implemented:
+//! every trap is taken in machine mode. `medeleg` and `mideleg` read as zero and ignore writes, so guests
+//! probing for delegation don't trap.
+//!
+//! In user mode:
+//! - `ecall` traps to machine mode ([`ECALL_FROM_USER`]), host syscalls are only issued from machine mode.
+//! - Machine-level CSRs (address bits 9:8 set), `mret`, `wfi` and `sfence.vma` are illegal ([`ILLEGAL_INSTRUCTION`]).
+//! - Invalid or illegal instructions, out of bounds accesses and program counters trap to machine mode
+//!   ([`ILLEGAL_INSTRUCTION`], [`LOAD_ACCESS_FAULT`], [`STORE_ACCESS_FAULT`], [`INSTRUCTION_ACCESS_FAULT`]),
+//!   instead of stopping the interpreter.
+//! - Trap values (`mtval`) are precise: access faults report the faulting address, and illegal instructions the
+//!   original RISC-V instruction bits (only the low 16 bits for compressed instructions), read back from the
+//!   source image (check [`super::Interpreter::set_source_image`], `0` without it). `ecall` reports `0`.
+//! - Machine interrupts are always enabled (`mstatus.MIE` only applies to machine mode).
+//! - With the `mmu` feature, pages must have the `U` bit set.
+//! - With the `pmp` feature, accesses must be allowed by a physical memory protection entry.
+//!
+//! `ebreak` stops the interpreter in both modes, unless configured to take a breakpoint exception
+//! ([`super::EbreakMode::Trap`], check [`super::Config::ebreak`]).
+use super::{memory::Memory, EbreakMode, Error, ExitReason, Interpreter, MemoryAccess, State};
+
+/// Instruction access fault (`mcause` exception code).
+pub const INSTRUCTION_ACCESS_FAULT: u32 = 1;
+/// Illegal instruction (`mcause` exception code).
+pub const ILLEGAL_INSTRUCTION: u32 = 2;
+/// Breakpoint (`mcause` exception code, check [`super::EbreakMode::Trap`]).
+pub const BREAKPOINT: u32 = 3;
+/// Load access fault (`mcause` exception code).
+pub const LOAD_ACCESS_FAULT: u32 = 5;
+/// Store/AMO access fault (`mcause` exception code).
+pub const STORE_ACCESS_FAULT: u32 = 7;
+/// Environment call from user mode (`mcause` exception code).
+pub const ECALL_FROM_USER: u32 = 8;
+
+/// Privilege mode of the interpreted code.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Privilege {
+    /// User mode (U-mode).
+    User,
+    /// Machine mode (M-mode).
+    Machine,
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Get the current privilege mode (always [`Privilege::Machine`] if user mode is disabled).
+    pub fn privilege(&self) -> Privilege {
+        match self.registers.control_status.user_mode() {
+            true => Privilege::User,
+            false => Privilege::Machine,
+        }
+    }
+
+    /// Take an exception trap to machine mode, at the current instruction.
+    ///
+    /// Arguments:
+    /// - `code`: Exception code (`mcause`).
+    /// - `value`: Trap value (`mtval`).
+    #[cold]
+    pub(crate) fn exception(&mut self, code: u32, value: i32) -> Result<State, Error> {
+        // Traps invalidate the memory reservation
+        self.registers
+            .control_status
+            .exception_entry(&mut self.program_counter, code, value);
+        self.memory_reservation = None;
+
+        Ok(State::Running)
+    }
+
+    /// Execute an `ebreak` instruction (check [`EbreakMode`]).
+    ///
+    /// Arguments:
+    /// - `size`: Instruction size, in bytes.
+    #[cold]
+    pub(crate) fn ebreak(&mut self, size: u32) -> Result<State, Error> {
+        let state = match self.config.ebreak {
+            EbreakMode::Halt => State::Halted {
+                reason: ExitReason::Ebreak,
+            },
+            EbreakMode::Break => State::Breakpoint,
+            EbreakMode::Trap => return self.exception(BREAKPOINT, self.program_counter as i32),
+        };
+
+        // Go to next instruction
+        self.program_counter = self.program_counter.wrapping_add(size);
+        Ok(state)
+    }
+
+    /// Get the original bits of the current instruction, for illegal instruction traps (`mtval`).
+    ///
+    /// Returns:
+    /// - `u32`: Instruction bits from the source image (low 16 bits for compressed instructions), `0` if unknown.
+    #[cold]
+    fn instruction_bits(&mut self) -> u32 {
+        let Some(image) = self.source_image else {
+            return 0;
+        };
+
+        // Physical address of the instruction
+        #[cfg(feature = "mmu")]
+        let address = {
+            let satp = self.registers.control_status.satp();
+            if satp & super::mmu::SATP_MODE_SV32 != 0 {
+                match self.fetch_address(satp, self.program_counter) {
+                    Ok(address) => address,
+                    Err(_) => return 0,
+                }
+            } else {
+                self.program_counter
+            }
+        };
+        #[cfg(not(feature = "mmu"))]
+        let address = self.program_counter;
+
+        let address = address as usize;
+        let bits = |len: usize| {
+            let bytes = image.get(address..address.checked_add(len)?)?;
+            Some(
+                bytes
+                    .iter()
+                    .rev()
+                    .fold(0, |bits, &byte| bits << 8 | byte as u32),
+            )
+        };
+        match bits(2) {
+            // Compressed instruction (lowest 2 bits aren't `11`)
+            Some(bits) if bits & 0b11 != 0b11 => bits,
+            Some(_) => bits(4).unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    /// Deliver an error of the current instruction to the interpreted code as an exception trap, if possible
+    /// (page faults, memory protection violations, and errors in user mode or with [`super::Config::machine_traps`]).
+    /// Other errors are returned.
+    ///
+    /// Arguments:
+    /// - `error`: Error of the current instruction.
+    #[cold]
+    pub(crate) fn trap_error(&mut self, error: Error) -> Result<State, Error> {
+        #[cfg(feature = "mmu")]
+        if let Error::PageFault { code, address } = error {
+            return self.exception(code, address as i32);
+        }
+
+        #[cfg(feature = "pmp")]
+        if let Error::AccessFault { code, address } = error {
+            return self.exception(code, address as i32);
+        }
+
+        let control_status = &self.registers.control_status;
+        if !control_status.user_mode()
+            && (!self.config.machine_traps || self.program_counter == control_status.mtvec())
+        {
+            // Machine mode, unless trapping (the trap handler entry never traps again)
+            return Err(error);
+        }
+
+        match error {
+            Error::InvalidInstruction(_)
+            | Error::IllegalInstruction(_)
+            | Error::InvalidCSRegister(_) => {
+                let bits = self.instruction_bits();
+                self.exception(ILLEGAL_INSTRUCTION, bits as i32)
+            }
+            Error::InvalidProgramCounter(pc) | Error::ExecuteFault(pc) => {
+                self.exception(INSTRUCTION_ACCESS_FAULT, pc as i32)
+            }
+            Error::MemoryFault {
+                access, address, ..
+            } => match access {
+                MemoryAccess::Load => self.exception(LOAD_ACCESS_FAULT, address as i32),
+                MemoryAccess::Store | MemoryAccess::Atomic => {
+                    self.exception(STORE_ACCESS_FAULT, address as i32)
+                }
+            },
+            error => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, registers::CSOperation};
+
+    #[cfg(feature = "transpiler")]
+    use crate::interpreter::{registers::CPURegister, Config, ExitReason, EMBIVE_INTERRUPT_CODE};
+
+    /// Set up the trap handler (`mtvec`) and user entry (`mepc`).
+    ///
+    /// With the `pmp` feature, user mode is given access to all memory.
+    #[cfg(feature = "transpiler")]
+    fn setup(control_status: &mut crate::interpreter::registers::CSRegisters, entry: u32) {
+        control_status
+            .operation(Some(CSOperation::Write(4)), 0x305)
+            .unwrap();
+        control_status
+            .operation(Some(CSOperation::Write(entry)), 0x341)
+            .unwrap();
+
+        #[cfg(feature = "pmp")]
+        {
+            // pmpaddr0 (NAPOT, all memory), pmpcfg0 (NAPOT | X | W | R)
+            control_status
+                .operation(Some(CSOperation::Write(u32::MAX)), 0x3B0)
+                .unwrap();
+            control_status
+                .operation(Some(CSOperation::Write(0x1F)), 0x3A0)
+                .unwrap();
+        }
+    }
+
+    /// Original RISC-V code of the user traps tests (check [`code`]).
+    #[cfg(feature = "transpiler")]
+    const SOURCE: [u8; 40] = [
+        0x73, 0x00, 0x20, 0x30, // mret (enter user mode)
+        0x73, 0x00, 0x10, 0x00, // ebreak (trap handler)
+        0x13, 0x05, 0x50, 0x00, // li   a0, 5
+        0x73, 0x00, 0x00, 0x00, // ecall
+        0x73, 0x25, 0x00, 0x30, // csrr a0, mstatus
+        0xb7, 0x05, 0x00, 0x40, // lui  a1, 0x40000
+        0x03, 0xa5, 0x05, 0x00, // lw   a0, 0(a1)
+        0x73, 0x00, 0x20, 0x30, // mret
+        0x6f, 0x00, 0x00, 0x00, // j    . (loop)
+        0x73, 0x00, 0x10, 0x00, // ebreak
+    ];
+
+    #[cfg(feature = "transpiler")]
+    fn code() -> [u8; 40] {
+        let mut code = SOURCE;
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    /// Run the user code at `entry` until it traps (handler halts).
+    ///
+    /// Arguments:
+    /// - `entry`: User code entry.
+    /// - `source`: Set the source image (original instruction bits in `mtval`).
+    ///
+    /// Returns `mcause`, `mepc` and `mtval`.
+    #[cfg(feature = "transpiler")]
+    fn trap(entry: u32, source: bool) -> (u32, u32, u32) {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_user_mode(true);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        setup(&mut interpreter.registers.control_status, entry);
+        if source {
+            interpreter.set_source_image(Some(&SOURCE));
+        }
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.program_counter, 8);
+        assert_eq!(interpreter.privilege(), Privilege::Machine);
+
+        // Trapped from user mode (MPP = 0)
+        let control_status = &mut interpreter.registers.control_status;
+        assert_eq!(control_status.operation(None, 0x300).unwrap() & 0x1800, 0);
+        (
+            control_status.operation(None, 0x342).unwrap(),
+            control_status.operation(None, 0x341).unwrap(),
+            control_status.operation(None, 0x343).unwrap(),
+        )
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_user_traps() {
+        assert_eq!(trap(8, true), (ECALL_FROM_USER, 12, 0));
+        assert_eq!(trap(16, true), (ILLEGAL_INSTRUCTION, 16, 0x3000_2573));
+        assert_eq!(trap(20, true), (LOAD_ACCESS_FAULT, 24, 0x4000_0000));
+        assert_eq!(trap(28, true), (ILLEGAL_INSTRUCTION, 28, 0x3020_0073));
+        assert_eq!(trap(0x100, true), (INSTRUCTION_ACCESS_FAULT, 0x100, 0x100));
+
+        // Without the source image, illegal instructions report 0
+        assert_eq!(trap(16, false), (ILLEGAL_INSTRUCTION, 16, 0));
+        assert_eq!(trap(28, false), (ILLEGAL_INSTRUCTION, 28, 0));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_user_interrupt() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_user_mode(true);
+        let mut interpreter = Interpreter::with_config(&mut memory, 10, config);
+        setup(&mut interpreter.registers.control_status, 32);
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(1 << EMBIVE_INTERRUPT_CODE)), 0x304)
+            .unwrap();
+
+        // Machine mode, mstatus.MIE is clear
+        assert_eq!(interpreter.interrupt(0), Err(Error::InterruptNotEnabled));
+
+        // Always enabled in user mode
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.privilege(), Privilege::User);
+        assert_eq!(interpreter.interrupt(7), Ok(()));
+        assert_eq!(interpreter.privilege(), Privilege::Machine);
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A0), Ok(0));
+    }
+
+    #[test]
+    fn test_machine_only() {
+        // mret stays in machine mode, errors are returned
+        let code = [
+            0x1f, 0x00, 0x40, 0x00, // mret (already transpiled)
+            0x00, 0x00, 0x00, 0x00, // invalid
+        ];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(4)), 0x341)
+            .unwrap();
+
+        assert_eq!(interpreter.step(), Ok(State::Running));
+        assert_eq!(interpreter.privilege(), Privilege::Machine);
+        assert_eq!(interpreter.step(), Err(Error::IllegalInstruction(4)));
+    }
+}
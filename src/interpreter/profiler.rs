@@ -0,0 +1,613 @@
+//! Profiler Module
+//!
+//! Function-level profiling of guest code, without debug information or host timers: calls (`jal`/`jalr`,
+//! `c.jal`/`c.jalr` linking `ra` or `t0`) push a frame to a shadow call stack, returns (indirect jumps to the
+//! return address of a frame) pop it. Each executed instruction is counted to the function on top of the stack,
+//! building per-function instruction counts and a call graph in user-supplied tables (check [`Profiler`]).
+//!
+//! Functions are identified by their entry address (e.g. symbolized with the ELF symbols, through
+//! `transpiler::pc_map`). Tail calls (`j`, `jr` without linking) aren't calls, the callee is counted to the
+//! caller. Trap handlers are counted to the interrupted function.
+use core::fmt::{self, Write};
+
+use super::{memory::Memory, Error, ExitReason, Interpreter, State};
+use crate::instruction::embive::{
+    CEbreakJalrAdd, CJrMv, Decoded, Instruction, Jal, Jalr, TypeCR, TypeI, TypeJ,
+};
+
+/// Function profile (check [`Profiler::functions`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FunctionProfile {
+    /// Function entry address.
+    pub address: u32,
+    /// Number of calls (`0` for the function the profiling started in).
+    pub calls: u64,
+    /// Instructions executed by the function itself.
+    pub self_instructions: u64,
+    /// Instructions executed by the function and its callees (recursive calls counted once).
+    pub total_instructions: u64,
+}
+
+/// Call graph edge (check [`Profiler::edges`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CallEdge {
+    /// Entry address of the calling function.
+    pub caller: u32,
+    /// Entry address of the called function.
+    pub callee: u32,
+    /// Number of calls.
+    pub calls: u64,
+}
+
+/// Shadow call stack frame (check [`Profiler::new`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CallFrame {
+    /// Function entry address.
+    function: u32,
+    /// Index of the function profile (`None` if the table was full).
+    index: Option<usize>,
+    /// Address the function returns to.
+    return_address: u32,
+    /// Instructions executed before the call.
+    start: u64,
+}
+
+impl CallFrame {
+    /// Empty frame.
+    pub const EMPTY: Self = CallFrame {
+        function: 0,
+        index: None,
+        return_address: 0,
+        start: 0,
+    };
+
+    /// Get the function entry address.
+    pub fn function(&self) -> u32 {
+        self.function
+    }
+
+    /// Get the address the function returns to.
+    pub fn return_address(&self) -> u32 {
+        self.return_address
+    }
+}
+
+/// Control flow of an instruction.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Flow {
+    /// Call, linking the return address.
+    Call,
+    /// Indirect jump (possibly a return).
+    Jump,
+    /// Anything else.
+    Other,
+}
+
+impl Flow {
+    /// Get the control flow of an instruction.
+    fn of(instruction: Instruction) -> Self {
+        // Link registers: `ra` (x1) and `t0` (x5, alternate)
+        let link = |rd: u8| rd == 1 || rd == 5;
+
+        match instruction.decode() {
+            Some(Decoded::Jal(Jal(TypeJ { rd, .. }))) if link(rd) => Flow::Call,
+            Some(Decoded::Jalr(Jalr(TypeI { rd_rs2, .. }))) if link(rd_rs2) => Flow::Call,
+            Some(Decoded::Jalr(_)) => Flow::Jump,
+            Some(Decoded::CJal(_)) => Flow::Call,
+            Some(Decoded::CEbreakJalrAdd(CEbreakJalrAdd(TypeCR { rd_rs1, rs2: 0 })))
+                if rd_rs1 != 0 =>
+            {
+                Flow::Call
+            }
+            Some(Decoded::CJrMv(CJrMv(TypeCR { rs2: 0, .. }))) => Flow::Jump,
+            _ => Flow::Other,
+        }
+    }
+}
+
+/// Embive Profiler
+///
+/// Runs an interpreter, keeping a shadow call stack to count the instructions executed by each function and
+/// the calls between them, in user-supplied tables:
+/// - Functions: one [`FunctionProfile`] per called function, in order of the first call.
+/// - Edges: one [`CallEdge`] per caller and callee pair, in order of the first call.
+/// - Shadow call stack: one [`CallFrame`] per active call (at least one, for the function the profiling
+///   starts in). Deeper calls are counted to the deepest tracked function.
+///
+/// Calls not fully recorded (tables or stack full) are counted by [`Profiler::dropped`].
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{
+///     memory::SliceMemory,
+///     profiler::{CallEdge, CallFrame, FunctionProfile, Profiler},
+///     ExitReason, Interpreter, State,
+/// };
+///
+/// // Code: jal ra, 6; ebreak; ret (already transpiled)
+/// let code = [0x99, 0x30, 0x00, 0x00, 0x15, 0x00, 0x34, 0x00, 0x00, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// let mut functions = [FunctionProfile::default(); 8];
+/// let mut edges = [CallEdge::default(); 8];
+/// let mut stack = [CallFrame::EMPTY; 8];
+/// let mut profiler = Profiler::new(&mut functions, &mut edges, &mut stack);
+/// assert_eq!(profiler.run(&mut interpreter), Ok(State::Halted { reason: ExitReason::Ebreak }));
+///
+/// // The function at 0 called the function at 6 once (1 instruction, `ret`)
+/// assert_eq!(profiler.edges(), [CallEdge { caller: 0, callee: 6, calls: 1 }]);
+/// let callee = profiler.function(6).unwrap();
+/// assert_eq!((callee.calls, callee.self_instructions), (1, 1));
+/// assert_eq!(profiler.function(0).unwrap().total_instructions, 3);
+/// ```
+#[derive(Debug)]
+pub struct Profiler<'p> {
+    functions: &'p mut [FunctionProfile],
+    function_count: usize,
+    edges: &'p mut [CallEdge],
+    edge_count: usize,
+    stack: &'p mut [CallFrame],
+    depth: usize,
+    instructions: u64,
+    dropped: u64,
+}
+
+impl<'p> Profiler<'p> {
+    /// Create a new profiler.
+    ///
+    /// Arguments:
+    /// - `functions`: Function profiles table (overwritten).
+    /// - `edges`: Call graph edges table (overwritten).
+    /// - `stack`: Shadow call stack (overwritten), its length is the maximum call depth tracked.
+    pub fn new(
+        functions: &'p mut [FunctionProfile],
+        edges: &'p mut [CallEdge],
+        stack: &'p mut [CallFrame],
+    ) -> Self {
+        Profiler {
+            functions,
+            function_count: 0,
+            edges,
+            edge_count: 0,
+            stack,
+            depth: 0,
+            instructions: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Get the function profiles, in order of the first call. Totals include the active calls.
+    pub fn functions(&self) -> impl Iterator<Item = FunctionProfile> + '_ {
+        self.functions[..self.function_count]
+            .iter()
+            .enumerate()
+            .map(|(index, function)| FunctionProfile {
+                total_instructions: function.total_instructions + self.active(index),
+                ..*function
+            })
+    }
+
+    /// Get the profile of a function.
+    ///
+    /// Arguments:
+    /// - `address`: Function entry address.
+    pub fn function(&self, address: u32) -> Option<FunctionProfile> {
+        self.functions()
+            .find(|function| function.address == address)
+    }
+
+    /// Get the call graph edges, in order of the first call.
+    pub fn edges(&self) -> &[CallEdge] {
+        &self.edges[..self.edge_count]
+    }
+
+    /// Get the shadow call stack, outermost call first.
+    pub fn stack(&self) -> &[CallFrame] {
+        &self.stack[..self.depth]
+    }
+
+    /// Get the number of instructions profiled.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Get the number of calls not fully recorded (function or edge table full, or shadow call stack full).
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Clear the profile and the shadow call stack (e.g. before profiling another entry point).
+    pub fn reset(&mut self) {
+        self.function_count = 0;
+        self.edge_count = 0;
+        self.depth = 0;
+        self.instructions = 0;
+        self.dropped = 0;
+    }
+
+    /// Export the call graph in the Graphviz DOT format (e.g. `dot -Tsvg`), one node per function
+    /// (calls, self and total instructions) and one edge per caller and callee pair (calls).
+    ///
+    /// Arguments:
+    /// - `sink`: The output sink.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, call graph written.
+    /// - `Err(fmt::Error)`: Failed to write to the sink.
+    pub fn write<W: Write>(&self, sink: &mut W) -> fmt::Result {
+        writeln!(sink, "digraph calls {{")?;
+        for function in self.functions() {
+            writeln!(
+                sink,
+                "  \"0x{:08x}\" [label=\"0x{:08x}\\ncalls {}, self {}, total {}\"];",
+                function.address,
+                function.address,
+                function.calls,
+                function.self_instructions,
+                function.total_instructions
+            )?;
+        }
+        for edge in self.edges() {
+            writeln!(
+                sink,
+                "  \"0x{:08x}\" -> \"0x{:08x}\" [label=\"{}\"];",
+                edge.caller, edge.callee, edge.calls
+            )?;
+        }
+        writeln!(sink, "}}")
+    }
+
+    /// Step through a single instruction, profiling it.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(Error)`: Failed to execute the instruction.
+    pub fn step<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+    ) -> Result<State, Error> {
+        // Count the trap handler instruction, if an interrupt is taken
+        interpreter.poll_interrupts();
+
+        let pc = interpreter.program_counter;
+        if self.depth == 0 {
+            // Function the profiling starts in (never returns)
+            self.push(pc, u32::MAX, false);
+        }
+
+        let instruction = interpreter.fetch()?;
+        let state = interpreter.step()?;
+
+        // Counted to the function on top of the stack (calls to the caller, returns to the callee)
+        self.instructions += 1;
+        if let Some(function) = self.top_index().map(|index| &mut self.functions[index]) {
+            function.self_instructions += 1;
+        }
+
+        let (target, next) = (
+            interpreter.program_counter,
+            pc.wrapping_add(instruction.size()),
+        );
+        match Flow::of(instruction) {
+            // Calls to the next instruction only read the program counter (e.g. `jal ra, 4`)
+            Flow::Call if target != next => self.push(target, next, true),
+            Flow::Jump => self.pop(target),
+            _ => {}
+        }
+
+        Ok(state)
+    }
+
+    /// Run the interpreter, profiling every executed instruction (check [`Interpreter::run`]).
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(Error)`: Failed to execute.
+    pub fn run<M: Memory>(&mut self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
+        let mut count = 0;
+
+        loop {
+            if interpreter.remaining_instructions() == Some(0) {
+                // Halt when the instruction budget is exhausted
+                return Ok(State::Halted {
+                    reason: ExitReason::InstructionLimit,
+                });
+            }
+
+            let state = self.step(interpreter)?;
+            if state != State::Running {
+                return Ok(state);
+            }
+
+            count += 1;
+            if interpreter.instruction_limit > 0 && count >= interpreter.instruction_limit {
+                // Yield after the instruction limit (still running)
+                return Ok(State::Running);
+            }
+        }
+    }
+
+    /// Get the function profile index of the top frame, if recorded.
+    fn top_index(&self) -> Option<usize> {
+        self.depth
+            .checked_sub(1)
+            .and_then(|top| self.stack[top].index)
+    }
+
+    /// Get the instructions executed by the outermost active call of a function (`0` if not active).
+    fn active(&self, index: usize) -> u64 {
+        self.stack()
+            .iter()
+            .find(|frame| frame.index == Some(index))
+            .map_or(0, |frame| self.instructions - frame.start)
+    }
+
+    /// Record a call, pushing its frame.
+    ///
+    /// Arguments:
+    /// - `function`: Function entry address.
+    /// - `return_address`: Address the function returns to.
+    /// - `called`: Called by the guest code (not the function the profiling starts in).
+    fn push(&mut self, function: u32, return_address: u32, called: bool) {
+        let caller = self
+            .depth
+            .checked_sub(1)
+            .map(|top| self.stack[top].function);
+        let index = self.function_index(function);
+        let mut recorded = index.is_some();
+
+        if let Some(index) = index {
+            self.functions[index].calls += called as u64;
+        }
+        if let Some(caller) = caller {
+            recorded &= self.add_edge(caller, function);
+        }
+
+        match self.stack.get_mut(self.depth) {
+            Some(frame) => {
+                *frame = CallFrame {
+                    function,
+                    index,
+                    return_address,
+                    start: self.instructions,
+                };
+                self.depth += 1;
+            }
+            None => recorded = false,
+        }
+
+        self.dropped += !recorded as u64;
+    }
+
+    /// Pop the frames up to the one returning to an address, if any (e.g. returns, or `longjmp`).
+    ///
+    /// Arguments:
+    /// - `target`: Jump target.
+    fn pop(&mut self, target: u32) {
+        let Some(depth) = self
+            .stack()
+            .iter()
+            .rposition(|frame| frame.return_address == target)
+        else {
+            return;
+        };
+
+        while self.depth > depth {
+            self.depth -= 1;
+            let frame = self.stack[self.depth];
+
+            // Recursive calls are counted once, by the outermost call
+            if let Some(index) = frame.index {
+                if self.active(index) == 0 {
+                    self.functions[index].total_instructions += self.instructions - frame.start;
+                }
+            }
+        }
+    }
+
+    /// Get the function profile index of a function, adding it if new.
+    ///
+    /// Arguments:
+    /// - `address`: Function entry address.
+    fn function_index(&mut self, address: u32) -> Option<usize> {
+        let functions = &mut self.functions[..];
+        if let Some(index) = functions[..self.function_count]
+            .iter()
+            .position(|function| function.address == address)
+        {
+            return Some(index);
+        }
+
+        let function = functions.get_mut(self.function_count)?;
+        *function = FunctionProfile {
+            address,
+            ..Default::default()
+        };
+        self.function_count += 1;
+        Some(self.function_count - 1)
+    }
+
+    /// Count a call between two functions.
+    ///
+    /// Arguments:
+    /// - `caller`: Entry address of the calling function.
+    /// - `callee`: Entry address of the called function.
+    ///
+    /// Returns:
+    /// - `true`: Call counted.
+    /// - `false`: Edge table full.
+    fn add_edge(&mut self, caller: u32, callee: u32) -> bool {
+        let edges = &mut self.edges[..];
+        if let Some(edge) = edges[..self.edge_count]
+            .iter_mut()
+            .find(|edge| edge.caller == caller && edge.callee == callee)
+        {
+            edge.calls += 1;
+            return true;
+        }
+
+        let Some(edge) = edges.get_mut(self.edge_count) else {
+            return false;
+        };
+        *edge = CallEdge {
+            caller,
+            callee,
+            calls: 1,
+        };
+        self.edge_count += 1;
+        true
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::memory::{SliceMemory, RAM_OFFSET},
+        transpiler::transpile_raw,
+    };
+
+    const ROOT: u32 = 0x00;
+    const REC: u32 = 0x14;
+    const LEAF: u32 = 0x34;
+
+    fn code() -> [u8; 56] {
+        let mut code = [
+            0x13, 0x05, 0x30, 0x00, // 0x00: li   a0, 3
+            0xef, 0x00, 0x00, 0x01, // 0x04: jal  ra, rec
+            0xef, 0x00, 0xc0, 0x02, // 0x08: jal  ra, leaf
+            0x73, 0x00, 0x10, 0x00, // 0x0c: ebreak
+            0x13, 0x00, 0x00, 0x00, // 0x10: nop
+            0x13, 0x01, 0xc1, 0xff, // 0x14: rec: addi sp, sp, -4
+            0x23, 0x20, 0x11, 0x00, // 0x18: sw   ra, 0(sp)
+            0x13, 0x05, 0xf5, 0xff, // 0x1c: addi a0, a0, -1
+            0x63, 0x04, 0x05, 0x00, // 0x20: beqz a0, 0x28
+            0xef, 0xf0, 0x1f, 0xff, // 0x24: jal  ra, rec
+            0x83, 0x20, 0x01, 0x00, // 0x28: lw   ra, 0(sp)
+            0x13, 0x01, 0x41, 0x00, // 0x2c: addi sp, sp, 4
+            0x67, 0x80, 0x00, 0x00, // 0x30: ret
+            0x67, 0x80, 0x00, 0x00, // 0x34: leaf: ret
+        ];
+        transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[test]
+    fn test_profiler() {
+        let code = code();
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_sp(RAM_OFFSET as i32 + 16);
+
+        let mut functions = [FunctionProfile::default(); 4];
+        let mut edges = [CallEdge::default(); 4];
+        let mut stack = [CallFrame::EMPTY; 8];
+        let mut profiler = Profiler::new(&mut functions, &mut edges, &mut stack);
+
+        // Stopped inside the recursion: active calls included in the totals
+        interpreter.instruction_limit = 15;
+        assert_eq!(profiler.run(&mut interpreter), Ok(State::Running));
+        let frames: Vec<_> = profiler.stack().iter().map(|f| f.function()).collect();
+        assert_eq!(frames, [ROOT, REC, REC, REC]);
+        assert_eq!(profiler.stack()[2].return_address(), 0x28);
+        assert_eq!(profiler.function(REC).unwrap().total_instructions, 13);
+
+        interpreter.instruction_limit = 0;
+        assert_eq!(
+            profiler.run(&mut interpreter),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(profiler.instructions(), 28);
+        assert_eq!(profiler.dropped(), 0);
+        assert_eq!(profiler.stack().len(), 1);
+
+        // Recursive calls counted once in the total
+        let profiles: Vec<_> = profiler.functions().collect();
+        assert_eq!(
+            profiles,
+            [
+                FunctionProfile {
+                    address: ROOT,
+                    calls: 0,
+                    self_instructions: 4,
+                    total_instructions: 28
+                },
+                FunctionProfile {
+                    address: REC,
+                    calls: 3,
+                    self_instructions: 23,
+                    total_instructions: 23
+                },
+                FunctionProfile {
+                    address: LEAF,
+                    calls: 1,
+                    self_instructions: 1,
+                    total_instructions: 1
+                },
+            ]
+        );
+        assert_eq!(
+            profiler.edges(),
+            [
+                CallEdge {
+                    caller: ROOT,
+                    callee: REC,
+                    calls: 1
+                },
+                CallEdge {
+                    caller: REC,
+                    callee: REC,
+                    calls: 2
+                },
+                CallEdge {
+                    caller: ROOT,
+                    callee: LEAF,
+                    calls: 1
+                },
+            ]
+        );
+
+        let mut output = String::new();
+        profiler.write(&mut output).unwrap();
+        assert!(output.starts_with("digraph calls {\n"));
+        assert!(output
+            .contains("  \"0x00000014\" [label=\"0x00000014\\ncalls 3, self 23, total 23\"];\n"));
+        assert!(output.ends_with("  \"0x00000014\" -> \"0x00000014\" [label=\"2\"];\n  \"0x00000000\" -> \"0x00000034\" [label=\"1\"];\n}\n"));
+
+        profiler.reset();
+        assert_eq!(profiler.functions().count(), 0);
+        assert_eq!(profiler.instructions(), 0);
+    }
+
+    #[test]
+    fn test_profiler_full() {
+        let code = code();
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_sp(RAM_OFFSET as i32 + 16);
+
+        let mut functions = [FunctionProfile::default(); 2];
+        let mut edges = [CallEdge::default(); 1];
+        let mut stack = [CallFrame::EMPTY; 2];
+        let mut profiler = Profiler::new(&mut functions, &mut edges, &mut stack);
+        assert_eq!(
+            profiler.run(&mut interpreter),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Nested calls counted to the deepest tracked call, `leaf` not recorded
+        assert_eq!(profiler.dropped(), 3);
+        let rec = profiler.function(REC).unwrap();
+        assert_eq!((rec.calls, rec.self_instructions), (3, 23));
+        assert_eq!(profiler.function(LEAF), None);
+        assert_eq!(profiler.edges().len(), 1);
+        assert_eq!(profiler.function(ROOT).unwrap().total_instructions, 28);
+    }
+}
@@ -0,0 +1,138 @@
+//! Random Module
+//!
+//! Entropy source for the interpreted code (check [`super::RANDOM_SYSCALL`]), backed by a host RNG provider
+//! or by a deterministic generator for reproducible runs (check [`super::Config::rng_seed`]).
+use super::{memory::Memory, Interpreter};
+
+/// RNG provider function, filling a buffer with random bytes.
+///
+/// Should be a cryptographically secure generator (e.g. the host OS entropy source), as the interpreted code
+/// expects `getrandom` semantics.
+pub type RngProvider = fn(&mut [u8]);
+
+/// Random syscall error: no entropy source (no RNG provider nor deterministic seed).
+pub const RANDOM_ERROR_UNAVAILABLE: i32 = crate::protocol::RANDOM_ERROR_UNAVAILABLE;
+
+/// Random syscall error: invalid memory address (buffer out of bounds).
+pub const RANDOM_ERROR_INVALID_ADDRESS: i32 = crate::protocol::RANDOM_ERROR_INVALID_ADDRESS;
+
+/// Deterministic generator step (SplitMix64). Not cryptographically secure.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Set the RNG provider (entropy source of [`super::RANDOM_SYSCALL`]).
+    ///
+    /// Ignored if a deterministic seed is configured (check [`super::Config::rng_seed`]).
+    ///
+    /// Arguments:
+    /// - `provider`: RNG provider (`None` makes [`super::RANDOM_SYSCALL`] fail with [`RANDOM_ERROR_UNAVAILABLE`]).
+    pub fn set_rng_provider(&mut self, provider: Option<RngProvider>) {
+        self.rng_provider = provider;
+    }
+
+    /// Reset the deterministic generator to the configured seed.
+    pub(crate) fn reset_rng(&mut self) {
+        self.rng_state = self.config.rng_seed.unwrap_or_default();
+    }
+
+    /// Handle [`super::RANDOM_SYSCALL`]: fill the buffer (`a0`, length `a1`), returning the error (`a0`) and length (`a1`).
+    pub(crate) fn random_syscall(&mut self) {
+        let address = self.registers.cpu.a0() as u32;
+        let len = self.registers.cpu.a1() as u32;
+
+        let error = match self.memory.mut_bytes(address, len as usize) {
+            Ok(buffer) => match (self.config.rng_seed, self.rng_provider) {
+                (Some(_), _) => {
+                    for chunk in buffer.chunks_mut(8) {
+                        let bytes = splitmix64(&mut self.rng_state).to_le_bytes();
+                        chunk.copy_from_slice(&bytes[..chunk.len()]);
+                    }
+                    0
+                }
+                (None, Some(provider)) => {
+                    provider(buffer);
+                    0
+                }
+                (None, None) => RANDOM_ERROR_UNAVAILABLE,
+            },
+            Err(_) => RANDOM_ERROR_INVALID_ADDRESS,
+        };
+
+        self.registers.cpu.set_a0(error);
+        self.registers
+            .cpu
+            .set_a1(if error == 0 { len as i32 } else { 0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        Config, ExitReason, State, RANDOM_SYSCALL,
+    };
+
+    /// Code: ecall, ebreak (already transpiled)
+    const CODE: [u8; 8] = [0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x10, 0x00];
+
+    fn random(interpreter: &mut Interpreter<'_, SliceMemory<'_>>, address: u32, len: i32) -> i32 {
+        interpreter.reset();
+        interpreter.registers.cpu.set_a7(RANDOM_SYSCALL);
+        interpreter.registers.cpu.set_a0(address as i32);
+        interpreter.registers.cpu.set_a1(len);
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        interpreter.registers.cpu.a0()
+    }
+
+    #[test]
+    fn test_random_provider() {
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(
+            random(&mut interpreter, RAM_OFFSET, 4),
+            RANDOM_ERROR_UNAVAILABLE
+        );
+
+        interpreter.set_rng_provider(Some(|buffer| buffer.fill(0xAA)));
+        assert_eq!(random(&mut interpreter, RAM_OFFSET + 2, 4), 0);
+        assert_eq!(interpreter.registers.cpu.a1(), 4);
+        assert_eq!(
+            random(&mut interpreter, RAM_OFFSET + 12, 8),
+            RANDOM_ERROR_INVALID_ADDRESS
+        );
+
+        assert_eq!(ram[..8], [0, 0, 0xAA, 0xAA, 0xAA, 0xAA, 0, 0]);
+    }
+
+    #[test]
+    fn test_random_deterministic() {
+        let mut ram = [0; 16];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let config = Config::default().with_rng_seed(Some(42));
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        // Same seed, same sequence after a reset (replay)
+        assert_eq!(random(&mut interpreter, RAM_OFFSET, 11), 0);
+        let first = interpreter.memory.load_bytes(RAM_OFFSET, 11).unwrap();
+        let first: [u8; 11] = first.try_into().unwrap();
+        assert_ne!(first, [0; 11]);
+
+        assert_eq!(random(&mut interpreter, RAM_OFFSET, 11), 0);
+        let second = interpreter.memory.load_bytes(RAM_OFFSET, 11).unwrap();
+        assert_eq!(first, second);
+    }
+}
@@ -6,7 +6,13 @@ mod cpu;
 pub use cpu::{CPURegister, CPURegisters};
 
 #[doc(inline)]
-pub use control_status::{CSOperation, CSRegisters};
+pub use control_status::{
+    CSOperation, CSRegisters, CustomCSRHandler, MISA_A, MISA_C, MISA_I, MISA_M, MISA_U,
+};
+
+pub(crate) use control_status::{
+    is_custom_csr, CustomCSRHandlerRef, CSR_SNAPSHOT_WORDS, MIMPID_DEFAULT, MIP_ADDR,
+};
 
 /// Embive Registers
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
@@ -1,10 +1,18 @@
 //! Control and Status Register Module
-use crate::interpreter::{error::Error, EMBIVE_INTERRUPT_CODE};
+#[cfg(feature = "mmu")]
+use crate::interpreter::mmu::SATP_ADDR;
+#[cfg(feature = "pmp")]
+use crate::interpreter::pmp::{Pmp, PMPADDR63_ADDR, PMPCFG0_ADDR, PMP_SNAPSHOT_WORDS};
+use crate::interpreter::{error::Error, MachineIds, EMBIVE_INTERRUPT_CODE};
 
 /// Machine Status Register
 const MSTATUS_ADDR: u16 = 0x300;
 /// ISA and extensions supported.
 const MISA_ADDR: u16 = 0x301;
+/// Machine Exception Delegation
+const MEDELEG_ADDR: u16 = 0x302;
+/// Machine Interrupt Delegation
+const MIDELEG_ADDR: u16 = 0x303;
 /// Machine Interrupt Enable
 const MIE_ADDR: u16 = 0x304;
 /// Machine Trap Vector
@@ -22,15 +30,37 @@ const MCAUSE_ADDR: u16 = 0x342;
 /// Machine Trap Value
 const MTVAL_ADDR: u16 = 0x343;
 /// Machine Interrupt Pending
-const MIP_ADDR: u16 = 0x344;
+pub(crate) const MIP_ADDR: u16 = 0x344;
 /// Machine High Performance Event 31 High
 const MHPMEVENT31H_ADDR: u16 = 0x33F;
+/// Custom Machine Read/Write Registers (start)
+const CUSTOM_MRW_START_ADDR: u16 = 0x7C0;
+/// Custom Machine Read/Write Registers (end)
+const CUSTOM_MRW_END_ADDR: u16 = 0x7FF;
+/// Custom Machine Read/Write Registers 2 (start)
+const CUSTOM_MRW2_START_ADDR: u16 = 0xBC0;
+/// Custom Machine Read/Write Registers 2 (end)
+const CUSTOM_MRW2_END_ADDR: u16 = 0xBFF;
+/// Custom Machine Read-Only Registers (start)
+const CUSTOM_MRO_START_ADDR: u16 = 0xFC0;
+/// Custom Machine Read-Only Registers (end)
+const CUSTOM_MRO_END_ADDR: u16 = 0xFFF;
 /// Machine cycle counter.
 const MCYCLE_ADDR: u16 = 0xB00;
 /// Machine High Performance Counter 31 High
 const MHPMCOUNTER31H_ADDR: u16 = 0xB9F;
+/// Timer (virtual time, low word)
+const TIME_ADDR: u16 = 0xC01;
+/// Timer (virtual time, high word)
+const TIMEH_ADDR: u16 = 0xC81;
 /// Vendor ID
 const MVENDORID_ADDR: u16 = 0xF11;
+/// Architecture ID
+const MARCHID_ADDR: u16 = 0xF12;
+/// Implementation ID
+const MIMPID_ADDR: u16 = 0xF13;
+/// Hardware thread ID
+const MHARTID_ADDR: u16 = 0xF14;
 /// Pointer to configuration data structure
 const MCONFIGPTR_ADDR: u16 = 0xF15;
 
@@ -38,12 +68,25 @@ const MCONFIGPTR_ADDR: u16 = 0xF15;
 const MXLEN: u32 = 32;
 /// MXL for MXLEN = 32
 const MXL_32: u32 = 0b01;
-/// MISA A Extension
-const MISA_A: u32 = 1 << 0;
-/// MISA I Extension
-const MISA_I: u32 = 1 << 8;
-/// MISA M Extension
-const MISA_M: u32 = 1 << 12;
+/// MISA A Extension (Atomic)
+pub const MISA_A: u32 = 1 << 0;
+/// MISA C Extension (Compressed)
+pub const MISA_C: u32 = 1 << 2;
+/// MISA I Extension (Base Integer)
+pub const MISA_I: u32 = 1 << 8;
+/// MISA M Extension (Multiply/Divide)
+pub const MISA_M: u32 = 1 << 12;
+/// MISA U Extension (User mode, check [`crate::interpreter::Config::user_mode`])
+pub const MISA_U: u32 = 1 << 20;
+/// MISA extensions supported by the interpreter
+const MISA_SUPPORTED: u32 = MISA_I | MISA_M | MISA_A | MISA_C | MISA_U;
+/// MISA extensions reported by default
+const MISA_DEFAULT: u32 = MISA_I | MISA_M | MISA_A | MISA_C;
+
+/// Default implementation ID (crate version: `major << 16 | minor << 8 | patch`)
+pub(crate) const MIMPID_DEFAULT: u32 = (parse_version(env!("CARGO_PKG_VERSION_MAJOR")) << 16)
+    | (parse_version(env!("CARGO_PKG_VERSION_MINOR")) << 8)
+    | parse_version(env!("CARGO_PKG_VERSION_PATCH"));
 
 /// MTVEC mode bits
 const MTVEC_MODE: u32 = 0b11;
@@ -52,20 +95,38 @@ const MTVEC_MODE: u32 = 0b11;
 const MEPC_BIT0: u32 = 0b1;
 
 /// MSTATUS MIE bit
-const MSTATUS_MIE: u8 = 0b1 << 3;
+const MSTATUS_MIE: u16 = 0b1 << 3;
 /// MSTATUS MPIE bit
-const MSTATUS_MPIE: u8 = 0b1 << 7;
+const MSTATUS_MPIE: u16 = 0b1 << 7;
+/// MSTATUS MPP field (previous privilege mode, `0b11` for machine mode and `0b00` for user mode)
+const MSTATUS_MPP: u16 = 0b11 << 11;
 /// MSTATUS write mask
-const MSTATUS_MASK: u8 = MSTATUS_MIE | MSTATUS_MPIE;
+const MSTATUS_MASK: u16 = MSTATUS_MIE | MSTATUS_MPIE;
 
 /// MCAUSE for Embive Custom Interrupt
 const MCAUSE_MEI_CODE: u32 = EMBIVE_INTERRUPT_CODE;
 /// MCAUSE interrupt bit
 const MCAUSE_INTERRUPT: u32 = 0b1 << 31;
 
+/// SATP write mask (MODE and PPN, ASID is hardwired to 0)
+#[cfg(feature = "mmu")]
+const SATP_MASK: u32 = 0x803F_FFFF;
+
 /// MIx (MIE and MIP) write mask for Embive Custom Interrupt
 const MI_E_P_MASK: u32 = 0b1 << EMBIVE_INTERRUPT_CODE;
 
+/// MCAUSE code for Machine Software Interrupt
+const MCAUSE_MSI_CODE: u32 = crate::protocol::SOFTWARE_INTERRUPT_CODE;
+/// MIx (MIE and MIP) write mask for Machine Software Interrupt (MSIE and MSIP)
+const MI_SOFTWARE_MASK: u32 = 0b1 << MCAUSE_MSI_CODE;
+
+/// Number of words in a machine state snapshot (check [`CSRegisters::snapshot`])
+#[cfg(not(feature = "pmp"))]
+pub(crate) const CSR_SNAPSHOT_WORDS: usize = 13;
+/// Number of words in a machine state snapshot (check [`CSRegisters::snapshot`]), including the PMP state
+#[cfg(feature = "pmp")]
+pub(crate) const CSR_SNAPSHOT_WORDS: usize = 13 + PMP_SNAPSHOT_WORDS;
+
 /// Control and Status Operation
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum CSOperation {
@@ -77,28 +138,96 @@ pub enum CSOperation {
     Clear(u32),
 }
 
-const fn get_misa() -> u32 {
-    (MXL_32 << (MXLEN - 2)) | MISA_I | MISA_M | MISA_A
+/// Custom CSR handler (check [`crate::interpreter::Interpreter::set_custom_csr_handler`]).
+///
+/// Called when the interpreted code accesses a custom CSR (`0x7C0..=0x7FF`, `0xBC0..=0xBFF` or `0xFC0..=0xFFF`).
+/// Implemented for closures (`FnMut(u16, Option<CSOperation>) -> Result<u32, Error>`), which can hold host state.
+pub trait CustomCSRHandler {
+    /// Execute a custom CSR operation.
+    ///
+    /// Arguments:
+    /// - `addr`: The address of the register.
+    /// - `op`: The operation to execute (`None` means only read the register).
+    ///
+    /// Returns:
+    /// - `Ok(u32)`: The register value prior to the operation.
+    /// - `Err(Error)`: The register is not supported (usually [`Error::InvalidCSRegister`]).
+    fn operation(&mut self, addr: u16, op: Option<CSOperation>) -> Result<u32, Error>;
+}
+
+impl<F: FnMut(u16, Option<CSOperation>) -> Result<u32, Error>> CustomCSRHandler for F {
+    fn operation(&mut self, addr: u16, op: Option<CSOperation>) -> Result<u32, Error> {
+        self(addr, op)
+    }
+}
+
+/// Custom CSR handler reference (check [`crate::interpreter::Interpreter::set_custom_csr_handler`]).
+#[derive(Default)]
+pub(crate) struct CustomCSRHandlerRef<'a>(pub(crate) Option<&'a mut dyn CustomCSRHandler>);
+
+impl core::fmt::Debug for CustomCSRHandlerRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(CustomCSRHandler)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+/// Check if a CSR address is in a custom range (`0x7C0..=0x7FF`, `0xBC0..=0xBFF` or `0xFC0..=0xFFF`).
+#[inline(always)]
+pub(crate) const fn is_custom_csr(addr: u16) -> bool {
+    matches!(
+        addr,
+        CUSTOM_MRW_START_ADDR..=CUSTOM_MRW_END_ADDR
+            | CUSTOM_MRW2_START_ADDR..=CUSTOM_MRW2_END_ADDR
+            | CUSTOM_MRO_START_ADDR..=CUSTOM_MRO_END_ADDR
+    )
+}
+
+const fn get_misa(extensions: u32) -> u32 {
+    (MXL_32 << (MXLEN - 2)) | (extensions & MISA_SUPPORTED) | MISA_I
+}
+
+/// Parse a version number (decimal string) at compile time.
+const fn parse_version(version: &str) -> u32 {
+    let bytes = version.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    value
 }
 
 /// Control and Status Registers
 /// Supported CSRs:
-/// - MSTATUS (MIE, MPIE)
-/// - MISA
-/// - MIE (bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`])
+/// - MSTATUS (MIE, MPIE and MPP if user mode is enabled)
+/// - MISA (configurable extensions, check [`CSRegisters::set_misa_extensions`])
+/// - MIE (bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`] and MSIE)
 /// - MTVEC (Direct mode only)
 /// - MSCRATCH
 /// - MEPC
 /// - MCAUSE
 /// - MTVAL
-/// - MIP (bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`])
+/// - MIP (bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`] and MSIP)
+/// - MVENDORID, MARCHID, MIMPID, MHARTID (read-only, check [`crate::interpreter::Config::machine_ids`])
+/// - TIME, TIMEH (read-only virtual time, check [`CSRegisters::set_instructions_per_tick`])
+/// - SATP (MODE and PPN, `mmu` feature only, check [`crate::interpreter::mmu`])
+/// - PMPCFG0..PMPCFG15, PMPADDR0..PMPADDR63 (`pmp` feature only, check [`crate::interpreter::pmp`])
+///
+/// Host-defined CSRs (handled by the interpreter, check [`crate::interpreter::Interpreter::set_custom_csr_handler`]):
+/// - Custom read/write (`0x7C0..=0x7FF` and `0xBC0..=0xBFF`)
+/// - Custom read-only (`0xFC0..=0xFFF`), writes are illegal instructions (trap before reaching the handler)
 ///
 /// Ignored CSRs (read-only as 0):
+/// - MEDELEG, MIDELEG (no supervisor mode, traps are never delegated)
 /// - MSTATUSH
 /// - MCOUNTINHIBIT..MHPMEVENT31
 /// - MCYCLE..MHPMCOUNTER31
-/// - MVENDORID..MCONFIGPTR
-#[derive(Debug, Default, PartialEq, Copy, Clone)]
+/// - MCONFIGPTR
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub struct CSRegisters {
     /// Machine Trap Vector
     mtvec: u32,
@@ -114,8 +243,67 @@ pub struct CSRegisters {
     mie_embive: bool,
     /// Machine Interrupt Pending (bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`])
     mip_embive: bool,
-    /// Machine Status Register (MIE, MPIE)
-    mstatus: u8,
+    /// Machine Software Interrupt Enable (`mie.MSIE`)
+    mie_software: bool,
+    /// Machine Software Interrupt Pending (`mip.MSIP`)
+    pub(crate) mip_software: bool,
+    /// Machine Software Interrupt value (passed through `mtval`)
+    software_value: i32,
+    /// Machine Status Register (MIE, MPIE, MPP)
+    mstatus: u16,
+    /// Current privilege mode is user mode (machine mode otherwise)
+    user: bool,
+    /// ISA and extensions supported
+    misa: u32,
+    /// Vendor ID
+    mvendorid: u32,
+    /// Architecture ID
+    marchid: u32,
+    /// Implementation ID
+    mimpid: u32,
+    /// Hardware thread ID
+    mhartid: u32,
+    /// Instructions retired
+    instret: u64,
+    /// Cycles elapsed (virtual time source, check the `timing` module, `timing` feature)
+    cycles: u64,
+    /// Instructions per virtual time tick (0 means virtual time is disabled)
+    instructions_per_tick: u32,
+    /// Supervisor Address Translation and Protection (`mmu` feature only)
+    satp: u32,
+    /// Physical Memory Protection
+    #[cfg(feature = "pmp")]
+    pmp: Pmp,
+}
+
+impl Default for CSRegisters {
+    fn default() -> Self {
+        CSRegisters {
+            mtvec: 0,
+            mscratch: 0,
+            mepc: 0,
+            mcause: 0,
+            mtval: 0,
+            mie_embive: false,
+            mip_embive: false,
+            mie_software: false,
+            mip_software: false,
+            software_value: 0,
+            mstatus: 0,
+            user: false,
+            misa: get_misa(MISA_DEFAULT),
+            mvendorid: 0, // Non-commercial implementation
+            marchid: 0,   // Not assigned
+            mimpid: MIMPID_DEFAULT,
+            mhartid: 0,
+            instret: 0,
+            cycles: 0,
+            instructions_per_tick: 0,
+            satp: 0,
+            #[cfg(feature = "pmp")]
+            pmp: Default::default(),
+        }
+    }
 }
 
 impl CSRegisters {
@@ -132,15 +320,38 @@ impl CSRegisters {
     #[inline]
     pub fn operation(&mut self, op: Option<CSOperation>, addr: u16) -> Result<u32, Error> {
         match addr {
+            #[cfg(feature = "mmu")]
+            SATP_ADDR => {
+                let ret = self.satp;
+                self.satp = execute_operation(op, ret) & SATP_MASK;
+                Ok(ret)
+            }
+            #[cfg(feature = "pmp")]
+            PMPCFG0_ADDR..=PMPADDR63_ADDR => {
+                let ret = self.pmp.read(addr);
+                if op.is_some() {
+                    self.pmp.write(addr, execute_operation(op, ret));
+                }
+                Ok(ret)
+            }
             MSTATUS_ADDR => {
                 let ret = self.mstatus as u32;
-                self.mstatus = (execute_operation(op, ret) as u8) & MSTATUS_MASK;
+                let value = execute_operation(op, ret) as u16;
+                self.mstatus = value & MSTATUS_MASK;
+                if self.misa & MISA_U != 0 && value & MSTATUS_MPP != 0 {
+                    // MPP is WARL, only machine and user modes are supported
+                    self.mstatus |= MSTATUS_MPP;
+                }
                 Ok(ret)
             }
-            MISA_ADDR => Ok(get_misa()), // ISA and extensions supported
+            MISA_ADDR => Ok(self.misa), // ISA and extensions supported (WARL, writes ignored)
+            MEDELEG_ADDR | MIDELEG_ADDR => Ok(0), // No supervisor mode, nothing to delegate
             MIE_ADDR => {
-                let ret = (self.mie_embive as u32) << EMBIVE_INTERRUPT_CODE;
-                self.mie_embive = (execute_operation(op, ret) & MI_E_P_MASK) != 0;
+                let ret = ((self.mie_embive as u32) << EMBIVE_INTERRUPT_CODE)
+                    | ((self.mie_software as u32) << MCAUSE_MSI_CODE);
+                let value = execute_operation(op, ret);
+                self.mie_embive = (value & MI_E_P_MASK) != 0;
+                self.mie_software = (value & MI_SOFTWARE_MASK) != 0;
                 Ok(ret)
             }
             MTVEC_ADDR => {
@@ -173,47 +384,281 @@ impl CSRegisters {
                 Ok(ret)
             }
             MIP_ADDR => {
-                let ret = (self.mip_embive as u32) << EMBIVE_INTERRUPT_CODE;
-                self.mip_embive = (execute_operation(op, ret) & MI_E_P_MASK) != 0;
+                let ret = ((self.mip_embive as u32) << EMBIVE_INTERRUPT_CODE)
+                    | ((self.mip_software as u32) << MCAUSE_MSI_CODE);
+                let value = execute_operation(op, ret);
+                self.mip_embive = (value & MI_E_P_MASK) != 0;
+                self.mip_software = (value & MI_SOFTWARE_MASK) != 0;
                 Ok(ret)
             }
             MCYCLE_ADDR..=MHPMCOUNTER31H_ADDR => Ok(0), // Ignore counters
-            MVENDORID_ADDR..=MCONFIGPTR_ADDR => Ok(0),  // IDs are always 0
+            MVENDORID_ADDR => Ok(self.mvendorid),
+            MARCHID_ADDR => Ok(self.marchid),
+            MIMPID_ADDR => Ok(self.mimpid),
+            MHARTID_ADDR => Ok(self.mhartid),
+            MCONFIGPTR_ADDR => Ok(0), // No configuration data structure
+            TIME_ADDR | TIMEH_ADDR => match self.time() {
+                // Read-only, writes are ignored
+                Some(time) if addr == TIME_ADDR => Ok(time as u32),
+                Some(time) => Ok((time >> 32) as u32),
+                None => Err(Error::InvalidCSRegister(addr)),
+            },
             _ => Err(Error::InvalidCSRegister(addr)),
         }
     }
 
+    /// Get the `misa` CSR value.
+    #[inline(always)]
+    pub(crate) fn misa(&self) -> u32 {
+        self.misa
+    }
+
+    /// Set the extensions reported by the `misa` CSR.
+    ///
+    /// The base integer extension ([`MISA_I`]) is always reported, and unsupported extensions are ignored.
+    /// This only affects what the interpreted code sees, check [`crate::interpreter::Config`] for disabling the execution of extensions.
+    /// The exception is [`MISA_U`], which implements user mode (`mstatus.MPP`, check [`crate::interpreter::Config::user_mode`]).
+    ///
+    /// Arguments:
+    /// - `extensions`: Extension bits ([`MISA_A`], [`MISA_C`], [`MISA_M`], [`MISA_U`]).
+    pub fn set_misa_extensions(&mut self, extensions: u32) {
+        self.misa = get_misa(extensions);
+        if self.misa & MISA_U == 0 {
+            // Machine mode only
+            self.user = false;
+            self.mstatus &= !MSTATUS_MPP;
+        }
+    }
+
+    /// Set the machine identification CSRs (read-only to the interpreted code).
+    ///
+    /// Arguments:
+    /// - `ids`: Machine IDs (from [`crate::interpreter::Config::machine_ids`]).
+    pub(crate) fn set_machine_ids(&mut self, ids: MachineIds) {
+        self.mvendorid = ids.vendor;
+        self.marchid = ids.architecture;
+        self.mimpid = ids.implementation;
+        self.mhartid = ids.hart;
+    }
+
+    /// Set the virtual time ratio, in instructions per tick.
+    ///
+    /// Virtual time is advanced by the number of executed instructions (or timing model cycles, check the `timing`
+    /// module, `timing` feature), so it is reproducible across hosts regardless of wall-clock speed.
+    /// It is exposed to the interpreted code through the `time`/`timeh` CSRs.
+    ///
+    /// Arguments:
+    /// - `instructions_per_tick`: Instructions (cycles) per tick (0 disables virtual time, `time` CSRs are invalid).
+    pub fn set_instructions_per_tick(&mut self, instructions_per_tick: u32) {
+        self.instructions_per_tick = instructions_per_tick;
+    }
+
+    /// Get the number of instructions retired (executed) since the last reset.
+    pub fn instructions_retired(&self) -> u64 {
+        self.instret
+    }
+
+    /// Get the number of cycles elapsed since the last reset (instructions retired without a timing model,
+    /// check the `timing` module, `timing` feature).
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Get the current virtual time.
+    ///
+    /// Returns:
+    /// - `Some(u64)`: Ticks since the last reset (cycles / instructions per tick).
+    /// - `None`: Virtual time is disabled.
+    pub fn time(&self) -> Option<u64> {
+        match self.instructions_per_tick {
+            0 => None,
+            ipt => Some(self.cycles / ipt as u64),
+        }
+    }
+
+    /// Count a retired instruction, costing 1 cycle (advance virtual time).
+    #[inline(always)]
+    pub(crate) fn retire(&mut self) {
+        self.instret = self.instret.wrapping_add(1);
+        self.cycles = self.cycles.wrapping_add(1);
+    }
+
+    /// Count a retired instruction, with its timing model cost (advance virtual time).
+    ///
+    /// Arguments:
+    /// - `cost`: Instruction cost, in cycles.
+    #[cfg(feature = "timing")]
+    pub(crate) fn retire_cost(&mut self, cost: u32) {
+        self.instret = self.instret.wrapping_add(1);
+        self.cycles = self.cycles.wrapping_add(cost as u64);
+    }
+
+    /// Count multiple retired instructions, costing 1 cycle each (advance virtual time).
+    pub(crate) fn retire_many(&mut self, count: u64) {
+        self.instret = self.instret.wrapping_add(count);
+        self.cycles = self.cycles.wrapping_add(count);
+    }
+
+    /// Get the machine state for a snapshot (check [`crate::interpreter::snapshot`]).
+    ///
+    /// Returns the trap CSRs, interrupt flags, software interrupt value, instructions retired, `satp`, cycles, the PMP state
+    /// (`pmp` feature only) and `misa`. Host settings (machine IDs, virtual time ratio) aren't included.
+    pub(crate) fn snapshot(&self) -> [u32; CSR_SNAPSHOT_WORDS] {
+        let flags = self.mie_embive as u32
+            | (self.mip_embive as u32) << 1
+            | (self.mie_software as u32) << 2
+            | (self.mip_software as u32) << 3
+            | (self.user as u32) << 4
+            | (self.mstatus as u32) << 8;
+        let mut words = [0; CSR_SNAPSHOT_WORDS];
+        words[..12].copy_from_slice(&[
+            self.mtvec,
+            self.mscratch,
+            self.mepc,
+            self.mcause,
+            self.mtval as u32,
+            flags,
+            self.software_value as u32,
+            self.instret as u32,
+            (self.instret >> 32) as u32,
+            self.satp,
+            self.cycles as u32,
+            (self.cycles >> 32) as u32,
+        ]);
+        #[cfg(feature = "pmp")]
+        words[12..12 + PMP_SNAPSHOT_WORDS].copy_from_slice(&self.pmp.snapshot());
+        words[CSR_SNAPSHOT_WORDS - 1] = self.misa;
+        words
+    }
+
+    /// Restore the machine state saved before a host call (check [`crate::interpreter::Interpreter::call`]).
+    ///
+    /// The counters (instructions retired and cycles) are kept, they include the called function.
+    ///
+    /// Arguments:
+    /// - `saved`: Machine state before the call.
+    pub(crate) fn restore_call_state(&mut self, saved: &CSRegisters) {
+        *self = CSRegisters {
+            instret: self.instret,
+            cycles: self.cycles,
+            ..*saved
+        };
+    }
+
+    /// Restore the machine state from a snapshot (check [`CSRegisters::snapshot`]).
+    ///
+    /// `misa` is kept, it is defined by the host configuration.
+    ///
+    /// Arguments:
+    /// - `words`: Machine state.
+    pub(crate) fn restore_snapshot(&mut self, words: &[u32; CSR_SNAPSHOT_WORDS]) {
+        let flags = words[5];
+        self.mtvec = words[0];
+        self.mscratch = words[1];
+        self.mepc = words[2];
+        self.mcause = words[3];
+        self.mtval = words[4] as i32;
+        self.mie_embive = flags & 1 != 0;
+        self.mip_embive = flags & (1 << 1) != 0;
+        self.mie_software = flags & (1 << 2) != 0;
+        self.mip_software = flags & (1 << 3) != 0;
+        self.user = flags & (1 << 4) != 0;
+        self.mstatus = (flags >> 8) as u16;
+        self.software_value = words[6] as i32;
+        self.instret = words[7] as u64 | (words[8] as u64) << 32;
+        self.satp = words[9];
+        self.cycles = words[10] as u64 | (words[11] as u64) << 32;
+        #[cfg(feature = "pmp")]
+        self.pmp
+            .restore_snapshot(&words[12..12 + PMP_SNAPSHOT_WORDS]);
+    }
+
     /// Set the interrupt pending flag.
     /// Set `mip` bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`] to 1.
     ///
     /// Arguments:
-    /// - `mtval`: The trap value.
     #[inline(always)]
     pub(crate) fn set_interrupt(&mut self) {
         // Set interrupt pending flag
         self.mip_embive = true;
     }
 
+    /// Set the software interrupt pending flag.
+    /// Set `mip.MSIP` to 1, the interrupt is taken when enabled (check [`CSRegisters::take_software_interrupt`]).
+    ///
+    /// Arguments:
+    /// - `value`: The trap value (`mtval`).
+    pub(crate) fn set_software_interrupt(&mut self, value: i32) {
+        self.mip_software = true;
+        self.software_value = value;
+    }
+
+    /// Take a pending software interrupt, if enabled (`mip.MSIP`, `mie.MSIE` and `mstatus.MIE` are set).
+    ///
+    /// `mip.MSIP` stays set until cleared (by the interpreted code or the host).
+    ///
+    /// Arguments:
+    /// - `pc`: Mutable reference to the program counter.
+    ///
+    /// Returns:
+    /// - `true`: Interrupt taken, program counter updated to the trap handler.
+    /// - `false`: Interrupt not pending or not enabled.
+    #[cold]
+    pub(crate) fn take_software_interrupt(&mut self, pc: &mut u32) -> bool {
+        if self.software_interrupt_ready() {
+            self.trap_entry(pc, MCAUSE_MSI_CODE, self.software_value);
+            return true;
+        }
+
+        false
+    }
+
+    /// Check if a software interrupt is pending and enabled (check [`CSRegisters::take_software_interrupt`]).
+    #[inline(always)]
+    pub(crate) fn software_interrupt_ready(&self) -> bool {
+        self.mip_software && self.mie_software && (self.user || (self.mstatus & MSTATUS_MIE) != 0)
+    }
+
+    /// Trigger the Embive interrupt trap (check [`CSRegisters::trap_entry`]).
+    ///
+    /// Arguments:
+    /// - `pc`: Mutable reference to the program counter.
+    /// - `value`: Trap value (`mtval`).
+    #[inline(always)]
+    pub(crate) fn interrupt_entry(&mut self, pc: &mut u32, value: i32) {
+        self.trap_entry(pc, MCAUSE_MEI_CODE, value);
+    }
+
     /// Check if interrupt is enabled.
-    /// Returns true if `mie` bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`] and `mstatus.MIE` are set.
+    /// Returns true if `mie` bit [`crate::interpreter::EMBIVE_INTERRUPT_CODE`] and `mstatus.MIE` are set
+    /// (machine interrupts are always enabled in user mode).
     #[inline(always)]
     pub(crate) fn interrupt_enabled(&self) -> bool {
-        self.mie_embive && (self.mstatus & MSTATUS_MIE) != 0
+        self.mie_embive && (self.user || (self.mstatus & MSTATUS_MIE) != 0)
+    }
+
+    /// Check if the current privilege mode is user mode (machine mode otherwise).
+    #[inline(always)]
+    pub(crate) fn user_mode(&self) -> bool {
+        self.user
     }
 
     /// Trap Entry.
     /// This function triggers an interrupt trap.
     /// What it does:
     /// - Copy `mstatus.MIE` to `mstatus.MPIE` and then clear `mstatus.MIE`.
-    /// - Set `mcause.MEI` to 1
-    /// - Set `mcause.code` to 11
+    /// - Copy the privilege mode to `mstatus.MPP` (if user mode is enabled) and switch to machine mode.
+    /// - Set the `mcause` interrupt bit to 1
+    /// - Set `mcause.code` to the received code
     /// - Copy the received program counter to `mepc`.
     /// - Copy the received value to `mtval`.
     /// - Update the program counter to the value in `mtvec`.
     ///
     /// Arguments:
     /// - `pc`: Mutable reference to the program counter.
-    pub(crate) fn trap_entry(&mut self, pc: &mut u32, value: i32) {
+    /// - `code`: Interrupt code (`mcause.code`).
+    /// - `value`: Trap value (`mtval`).
+    pub(crate) fn trap_entry(&mut self, pc: &mut u32, code: u32, value: i32) {
         // Copy MIE to MPIE
         if (self.mstatus & MSTATUS_MIE) != 0 {
             self.mstatus |= MSTATUS_MPIE;
@@ -224,8 +669,17 @@ impl CSRegisters {
         // Clear MIE
         self.mstatus &= !MSTATUS_MIE;
 
+        // Copy the privilege mode to MPP, trap to machine mode
+        if self.misa & MISA_U != 0 {
+            self.mstatus |= MSTATUS_MPP;
+            if self.user {
+                self.mstatus &= !MSTATUS_MPP;
+            }
+        }
+        self.user = false;
+
         // Set mcause
-        self.mcause = MCAUSE_INTERRUPT | MCAUSE_MEI_CODE;
+        self.mcause = MCAUSE_INTERRUPT | code;
 
         // Copy PC to MEPC
         self.mepc = *pc;
@@ -237,10 +691,50 @@ impl CSRegisters {
         *pc = self.mtvec & !MTVEC_MODE;
     }
 
+    /// Exception Entry.
+    /// Same as [`CSRegisters::trap_entry`], with the `mcause` interrupt bit cleared (synchronous exception).
+    ///
+    /// Arguments:
+    /// - `pc`: Mutable reference to the program counter (faulting instruction).
+    /// - `code`: Exception code (`mcause.code`).
+    /// - `value`: Trap value (`mtval`).
+    #[cold]
+    pub(crate) fn exception_entry(&mut self, pc: &mut u32, code: u32, value: i32) {
+        self.trap_entry(pc, code, value);
+        self.mcause = code;
+    }
+
+    /// Get the physical memory protection state.
+    #[cfg(feature = "pmp")]
+    #[inline(always)]
+    pub(crate) fn pmp(&self) -> &Pmp {
+        &self.pmp
+    }
+
+    /// Get the address translation register (`satp`).
+    #[cfg(feature = "mmu")]
+    #[inline(always)]
+    pub(crate) fn satp(&self) -> u32 {
+        self.satp
+    }
+
+    /// Get the trap handler address (`mtvec`).
+    #[inline(always)]
+    pub(crate) fn mtvec(&self) -> u32 {
+        self.mtvec & !MTVEC_MODE
+    }
+
+    /// Get the trap return address (`mepc`).
+    #[inline(always)]
+    pub(crate) fn mepc(&self) -> u32 {
+        self.mepc
+    }
+
     /// Trap Return.
     /// This function returns from an interrupt.
     /// What it does:
     /// - Restore `mstatus.MIE` from `mstatus.MPIE`.
+    /// - Restore the privilege mode from `mstatus.MPP` and set it to user mode (if user mode is enabled).
     /// - Return the program counter from `mepc`.
     ///
     /// Returns:
@@ -253,6 +747,12 @@ impl CSRegisters {
             self.mstatus &= !MSTATUS_MIE;
         }
 
+        // Restore the privilege mode, MPP is set to the least privileged mode
+        if self.misa & MISA_U != 0 {
+            self.user = self.mstatus & MSTATUS_MPP == 0;
+            self.mstatus &= !MSTATUS_MPP;
+        }
+
         // Return the PC
         self.mepc
     }
@@ -286,15 +786,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mstatus_user() {
+        let mut cs = CSRegisters::default();
+        cs.set_misa_extensions(MISA_U);
+
+        // MPP is WARL: only machine (0b11) and user (0b00) are legal
+        cs.operation(Some(CSOperation::Write(0x1000)), MSTATUS_ADDR)
+            .unwrap();
+        assert_eq!(cs.operation(None, MSTATUS_ADDR), Ok(0x1800));
+        cs.operation(Some(CSOperation::Write(0x0008)), MSTATUS_ADDR)
+            .unwrap();
+        assert_eq!(cs.operation(None, MSTATUS_ADDR), Ok(0x0008));
+
+        // Returning to user mode
+        cs.trap_return();
+        assert!(cs.user_mode());
+
+        // Trap from user mode (MPP = 0), return to machine mode
+        let mut pc = 0;
+        cs.exception_entry(&mut pc, 8, 0);
+        assert!(!cs.user_mode());
+        cs.operation(Some(CSOperation::Write(0x1800)), MSTATUS_ADDR)
+            .unwrap();
+        cs.trap_return();
+        assert!(!cs.user_mode());
+    }
+
     #[test]
     fn test_misa() {
         let mut cs = CSRegisters::default();
 
+        let misa = 0x4000_1105; // RV32IMAC
         assert_eq!(
             cs.operation(Some(CSOperation::Write(0x1898)), MISA_ADDR),
-            Ok(get_misa())
+            Ok(misa)
+        );
+        assert_eq!(cs.operation(None, MISA_ADDR), Ok(misa));
+
+        // Disable M and C extensions
+        cs.set_misa_extensions(MISA_A);
+        assert_eq!(cs.operation(None, MISA_ADDR), Ok(0x4000_0101));
+
+        // I extension is always reported, unsupported extensions are ignored
+        cs.set_misa_extensions(1 << 5);
+        assert_eq!(cs.operation(None, MISA_ADDR), Ok(0x4000_0100));
+    }
+
+    #[test]
+    fn test_trap_delegation() {
+        let mut cs = CSRegisters::default();
+
+        // Read-only zero, writes are ignored
+        assert_eq!(
+            cs.operation(Some(CSOperation::Write(0xFFFF)), MEDELEG_ADDR),
+            Ok(0)
+        );
+        assert_eq!(cs.operation(None, MEDELEG_ADDR), Ok(0));
+        assert_eq!(
+            cs.operation(Some(CSOperation::Set(0x222)), MIDELEG_ADDR),
+            Ok(0)
+        );
+        assert_eq!(cs.operation(None, MIDELEG_ADDR), Ok(0));
+    }
+
+    #[test]
+    fn test_machine_ids() {
+        let mut cs = CSRegisters::default();
+
+        assert_eq!(cs.operation(None, MVENDORID_ADDR), Ok(0));
+        assert_eq!(cs.operation(None, MARCHID_ADDR), Ok(0));
+        assert_eq!(cs.operation(None, MIMPID_ADDR), Ok(MIMPID_DEFAULT));
+        assert_eq!(cs.operation(None, MHARTID_ADDR), Ok(0));
+        assert_eq!(cs.operation(None, MCONFIGPTR_ADDR), Ok(0));
+
+        cs.set_machine_ids(MachineIds {
+            vendor: 1,
+            architecture: 2,
+            implementation: 3,
+            hart: 4,
+        });
+        assert_eq!(
+            cs.operation(Some(CSOperation::Write(0xFF)), MVENDORID_ADDR),
+            Ok(1)
+        );
+        assert_eq!(cs.operation(None, MVENDORID_ADDR), Ok(1));
+        assert_eq!(cs.operation(None, MARCHID_ADDR), Ok(2));
+        assert_eq!(cs.operation(None, MIMPID_ADDR), Ok(3));
+        assert_eq!(cs.operation(None, MHARTID_ADDR), Ok(4));
+    }
+
+    #[test]
+    fn test_mimpid_default() {
+        assert_eq!(parse_version("0"), 0);
+        assert_eq!(parse_version("12"), 12);
+        assert_eq!(
+            MIMPID_DEFAULT,
+            (parse_version(env!("CARGO_PKG_VERSION_MAJOR")) << 16)
+                | (parse_version(env!("CARGO_PKG_VERSION_MINOR")) << 8)
+                | parse_version(env!("CARGO_PKG_VERSION_PATCH"))
         );
-        assert_eq!(cs.operation(None, MISA_ADDR), Ok(get_misa()));
     }
 
     #[test]
@@ -324,10 +915,18 @@ mod tests {
         let mut cs = CSRegisters::default();
 
         assert_eq!(
-            cs.operation(Some(CSOperation::Write(0xFFFF)), MSCRATCH_ADDR),
+            cs.operation(Some(CSOperation::Write(0xFFFF_FFFF)), MSCRATCH_ADDR),
             Ok(0)
         );
-        assert_eq!(cs.operation(None, MSCRATCH_ADDR), Ok(0xFFFF));
+        assert_eq!(
+            cs.operation(Some(CSOperation::Clear(0xFFFF_0000)), MSCRATCH_ADDR),
+            Ok(0xFFFF_FFFF)
+        );
+        assert_eq!(
+            cs.operation(Some(CSOperation::Set(0x1000_0000)), MSCRATCH_ADDR),
+            Ok(0xFFFF)
+        );
+        assert_eq!(cs.operation(None, MSCRATCH_ADDR), Ok(0x1000_FFFF));
     }
 
     #[test]
@@ -362,4 +961,60 @@ mod tests {
         cs.set_interrupt();
         assert_eq!(cs.operation(None, MIP_ADDR), Ok(MI_E_P_MASK));
     }
+
+    #[test]
+    fn test_software_interrupt() {
+        let mut cs = CSRegisters::default();
+        let mut pc = 0x10;
+        cs.mtvec = 0x100;
+
+        cs.set_software_interrupt(7);
+        assert_eq!(cs.operation(None, MIP_ADDR), Ok(MI_SOFTWARE_MASK));
+
+        // Not enabled
+        assert!(!cs.take_software_interrupt(&mut pc));
+        assert_eq!(pc, 0x10);
+
+        cs.operation(Some(CSOperation::Set(MI_SOFTWARE_MASK)), MIE_ADDR)
+            .unwrap();
+        cs.operation(Some(CSOperation::Set(MSTATUS_MIE as u32)), MSTATUS_ADDR)
+            .unwrap();
+        assert!(cs.take_software_interrupt(&mut pc));
+        assert_eq!(pc, 0x100);
+        assert_eq!(cs.mepc, 0x10);
+        assert_eq!(cs.mcause, MCAUSE_INTERRUPT | MCAUSE_MSI_CODE);
+        assert_eq!(cs.mtval, 7);
+
+        // Cleared by the interpreted code
+        cs.operation(Some(CSOperation::Clear(MI_SOFTWARE_MASK)), MIP_ADDR)
+            .unwrap();
+        assert!(!cs.mip_software);
+    }
+
+    #[test]
+    fn test_time() {
+        let mut cs = CSRegisters::default();
+
+        // Disabled by default
+        assert_eq!(cs.time(), None);
+        assert_eq!(
+            cs.operation(None, TIME_ADDR),
+            Err(Error::InvalidCSRegister(TIME_ADDR))
+        );
+
+        cs.set_instructions_per_tick(2);
+        for _ in 0..5 {
+            cs.retire();
+        }
+        assert_eq!(cs.instructions_retired(), 5);
+        assert_eq!(cs.time(), Some(2));
+        assert_eq!(cs.operation(Some(CSOperation::Write(10)), TIME_ADDR), Ok(2));
+        assert_eq!(cs.operation(None, TIME_ADDR), Ok(2));
+        assert_eq!(cs.operation(None, TIMEH_ADDR), Ok(0));
+
+        cs.cycles = 2 << 32;
+        cs.set_instructions_per_tick(1);
+        assert_eq!(cs.operation(None, TIME_ADDR), Ok(0));
+        assert_eq!(cs.operation(None, TIMEH_ADDR), Ok(2));
+    }
 }
@@ -1,12 +1,14 @@
 //! CPU Register Module
-use crate::interpreter::{utils::unlikely, Error};
+use crate::interpreter::{utils::unlikely, Error, SYSCALL_ARGS};
 
 /// Number of registers available
 pub const CPU_REGISTER_COUNT: u8 = 32;
 
 /// CPU Register Enum
+///
+/// Can be used to index [`CPURegisters`] (e.g. `registers.get(CPURegister::A0)`).
 #[repr(u8)]
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum CPURegister {
     /// x0 register, hardwired to 0 (read-only).
     Zero = 0,
@@ -74,6 +76,32 @@ pub enum CPURegister {
     T6 = 31,
 }
 
+impl From<CPURegister> for u8 {
+    #[inline(always)]
+    fn from(register: CPURegister) -> Self {
+        register as u8
+    }
+}
+
+/// Generate named getters and setters for CPU registers.
+macro_rules! named_registers {
+    ($($get:ident, $set:ident => $register:ident;)*) => {
+        $(
+            #[doc = concat!("Get the `", stringify!($get), "` register (check [`CPURegister::", stringify!($register), "`]).")]
+            #[inline(always)]
+            pub fn $get(&self) -> i32 {
+                self.inner[CPURegister::$register as usize]
+            }
+
+            #[doc = concat!("Set the `", stringify!($get), "` register (check [`CPURegister::", stringify!($register), "`]).")]
+            #[inline(always)]
+            pub fn $set(&mut self, value: i32) {
+                self.inner[CPURegister::$register as usize] = value;
+            }
+        )*
+    };
+}
+
 /// CPU Registers
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
 pub struct CPURegisters {
@@ -84,13 +112,14 @@ impl CPURegisters {
     /// Get a CPU register.
     ///
     /// Arguments:
-    /// - `index`: The index of the register (from [`CPURegister::Zero`] to [`CPURegister::T6`]).
+    /// - `index`: The register ([`CPURegister`]) or its index (from `0` to `31`).
     ///
     /// Returns:
     /// - `Ok(i32)`: The value of the register.
     /// - `Err(Error)`: The register index is out of bounds.
     #[inline]
-    pub fn get(&self, index: u8) -> Result<i32, Error> {
+    pub fn get(&self, index: impl Into<u8>) -> Result<i32, Error> {
+        let index = index.into();
         if unlikely(index >= CPU_REGISTER_COUNT) {
             return Err(Error::InvalidCPURegister(index));
         }
@@ -98,23 +127,92 @@ impl CPURegisters {
         Ok(self.inner[index as usize])
     }
 
+    /// Set a CPU register.
+    ///
+    /// Writes to register `0` ([`CPURegister::Zero`]) are discarded:
+    /// its slot is used as a write sink, and cleared right after every write.
+    ///
+    /// Arguments:
+    /// - `index`: The register ([`CPURegister`]) or its index (from `0` to `31`).
+    /// - `value`: The value to be written.
+    ///
+    /// Returns:
+    /// - `Ok(())`: The register was written (or the write was discarded).
+    /// - `Err(Error)`: The register index is out of bounds.
+    #[inline]
+    pub fn set(&mut self, index: impl Into<u8>, value: i32) -> Result<(), Error> {
+        let index = index.into();
+        if unlikely(index >= CPU_REGISTER_COUNT) {
+            return Err(Error::InvalidCPURegister(index));
+        }
+
+        self.inner[index as usize] = value;
+        self.inner[CPURegister::Zero as usize] = 0;
+        Ok(())
+    }
+
+    /// Get the syscall argument registers (`a0` to `a6`).
+    #[inline(always)]
+    pub(crate) fn syscall_args(&self) -> &[i32; SYSCALL_ARGS] {
+        // x0-x9, then a0-a6, then a7 and x18-x31 (checked at compile time)
+        let [_, _, _, _, _, _, _, _, _, _, args @ .., _, _, _, _, _, _, _, _, _, _, _, _, _, _, _] =
+            &self.inner;
+        args
+    }
+
     /// Get a mutable reference to a CPU register.
     ///
     /// Arguments:
-    /// - `index`: The index of the register (from [`CPURegister::Zero`] to [`CPURegister::T6`]).
-    ///     - Register `0` [`CPURegister::Zero`] should be read-only, we ignore it for performance reasons.
+    /// - `index`: The register ([`CPURegister`]) or its index (from `0` to `31`).
+    ///     - Register `0` [`CPURegister::Zero`] should be read-only, writing to it through this
+    ///       reference breaks the interpreter (use [`CPURegisters::set`] instead).
     ///
     /// Returns:
     /// - `Ok(&mut i32)`: Mutable reference to the register.
     /// - `Err(Error)`: The register index is out of bounds.
     #[inline]
-    pub fn get_mut(&mut self, index: u8) -> Result<&mut i32, Error> {
+    pub fn get_mut(&mut self, index: impl Into<u8>) -> Result<&mut i32, Error> {
+        let index = index.into();
         if unlikely(index >= CPU_REGISTER_COUNT) {
             return Err(Error::InvalidCPURegister(index));
         }
 
         Ok(&mut self.inner[index as usize])
     }
+
+    named_registers! {
+        ra, set_ra => RA;
+        sp, set_sp => SP;
+        gp, set_gp => GP;
+        tp, set_tp => TP;
+        t0, set_t0 => T0;
+        t1, set_t1 => T1;
+        t2, set_t2 => T2;
+        s0, set_s0 => S0;
+        s1, set_s1 => S1;
+        a0, set_a0 => A0;
+        a1, set_a1 => A1;
+        a2, set_a2 => A2;
+        a3, set_a3 => A3;
+        a4, set_a4 => A4;
+        a5, set_a5 => A5;
+        a6, set_a6 => A6;
+        a7, set_a7 => A7;
+        s2, set_s2 => S2;
+        s3, set_s3 => S3;
+        s4, set_s4 => S4;
+        s5, set_s5 => S5;
+        s6, set_s6 => S6;
+        s7, set_s7 => S7;
+        s8, set_s8 => S8;
+        s9, set_s9 => S9;
+        s10, set_s10 => S10;
+        s11, set_s11 => S11;
+        t3, set_t3 => T3;
+        t4, set_t4 => T4;
+        t5, set_t5 => T5;
+        t6, set_t6 => T6;
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +229,38 @@ mod tests {
         assert_eq!(registers.get_mut(CPU_REGISTER_COUNT - 1).map(|x| *x), Ok(0));
     }
 
+    #[test]
+    fn get_cpu_register_typed() {
+        let mut registers = CPURegisters::default();
+
+        registers.set_a0(10);
+        registers.set_sp(-4);
+        assert_eq!(registers.get(CPURegister::A0), Ok(10));
+        assert_eq!(registers.get(10), Ok(10));
+        assert_eq!(registers.sp(), -4);
+
+        *registers.get_mut(CPURegister::T6).unwrap() = 6;
+        assert_eq!(registers.t6(), 6);
+    }
+
+    #[test]
+    fn set_cpu_register() {
+        let mut registers = CPURegisters::default();
+
+        assert_eq!(registers.set(CPURegister::A0, 10), Ok(()));
+        assert_eq!(registers.a0(), 10);
+
+        // Writes to the zero register are discarded
+        assert_eq!(registers.set(CPURegister::Zero, 10), Ok(()));
+        assert_eq!(registers.get(CPURegister::Zero), Ok(0));
+        assert_eq!(registers.a0(), 10);
+
+        assert!(matches!(
+            registers.set(CPU_REGISTER_COUNT, 10),
+            Err(Error::InvalidCPURegister(_))
+        ));
+    }
+
     #[test]
     fn get_cpu_register_out_of_bounds() {
         let mut registers = CPURegisters::default();
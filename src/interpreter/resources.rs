@@ -0,0 +1,289 @@
+//! Resource Usage Module
+//!
+//! Resource usage accounting, per run and over the interpreter lifetime, for fair multi-tenant hosting:
+//! bytes stored by the interpreted code, syscalls issued (`ecall`) and interrupts taken (check
+//! [`Interpreter::resource_usage`]).
+//!
+//! Hard limits (check [`super::Config::resource_limits`]) stop the interpreted code right before it exceeds one:
+//! running returns [`super::State::Halted`] with [`super::ExitReason::ResourceLimit`], like the instruction budget.
+//! The store or `ecall` isn't executed (the program counter still points to it), and pending interrupts stay pending.
+//!
+//! Only compiled with the `resource-limits` feature, so stores, `ecall` and interrupts skip the accounting by default.
+use super::{memory::Memory, utils::unlikely, ExitReason, Interpreter, State};
+
+/// Accounted resource.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resource {
+    /// Bytes stored by the interpreted code (stores, AMOs and successful `sc.w`).
+    BytesWritten,
+    /// Syscalls issued by the interpreted code (`ecall` in machine mode, including the interpreter syscalls).
+    Syscalls,
+    /// Interrupts taken (direct, queued and software interrupts).
+    Interrupts,
+}
+
+/// Resource usage (check [`Interpreter::resource_usage`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Bytes stored by the interpreted code.
+    pub bytes_written: u64,
+    /// Syscalls issued by the interpreted code.
+    pub syscalls: u64,
+    /// Interrupts taken.
+    pub interrupts: u64,
+}
+
+impl ResourceUsage {
+    /// Get the usage of a resource.
+    pub const fn get(&self, resource: Resource) -> u64 {
+        match resource {
+            Resource::BytesWritten => self.bytes_written,
+            Resource::Syscalls => self.syscalls,
+            Resource::Interrupts => self.interrupts,
+        }
+    }
+
+    /// Add to the usage of a resource.
+    #[inline(always)]
+    fn add(&mut self, resource: Resource, amount: u64) {
+        let usage = match resource {
+            Resource::BytesWritten => &mut self.bytes_written,
+            Resource::Syscalls => &mut self.syscalls,
+            Resource::Interrupts => &mut self.interrupts,
+        };
+        *usage = usage.saturating_add(amount);
+    }
+}
+
+/// Embive Resource Limits
+///
+/// Hard limits on the lifetime resource usage (since creation, the last reset or
+/// [`Interpreter::reset_resource_usage`]). `0` disables a limit. No limits by default.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{Config, ResourceLimits};
+///
+/// // Up to 64 KiB written and 100 syscalls
+/// let limits = ResourceLimits::new()
+///     .with_bytes_written(64 * 1024)
+///     .with_syscalls(100);
+/// let config = Config::default().with_resource_limits(limits);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Maximum number of bytes stored by the interpreted code.
+    pub bytes_written: u64,
+    /// Maximum number of syscalls issued by the interpreted code.
+    pub syscalls: u64,
+    /// Maximum number of interrupts taken.
+    pub interrupts: u64,
+}
+
+impl ResourceLimits {
+    /// Create new resource limits, all disabled.
+    pub const fn new() -> Self {
+        ResourceLimits {
+            bytes_written: 0,
+            syscalls: 0,
+            interrupts: 0,
+        }
+    }
+
+    /// Set the maximum number of bytes stored by the interpreted code (`0` disables it).
+    pub const fn with_bytes_written(mut self, limit: u64) -> Self {
+        self.bytes_written = limit;
+        self
+    }
+
+    /// Set the maximum number of syscalls issued by the interpreted code (`0` disables it).
+    pub const fn with_syscalls(mut self, limit: u64) -> Self {
+        self.syscalls = limit;
+        self
+    }
+
+    /// Set the maximum number of interrupts taken (`0` disables it).
+    pub const fn with_interrupts(mut self, limit: u64) -> Self {
+        self.interrupts = limit;
+        self
+    }
+
+    /// Get the limit of a resource (`0` if disabled).
+    pub const fn get(&self, resource: Resource) -> u64 {
+        match resource {
+            Resource::BytesWritten => self.bytes_written,
+            Resource::Syscalls => self.syscalls,
+            Resource::Interrupts => self.interrupts,
+        }
+    }
+}
+
+/// Resource usage tracking.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct ResourceTracker {
+    /// Usage during the current (or last) run.
+    run: ResourceUsage,
+    /// Usage since creation, the last reset or [`Interpreter::reset_resource_usage`].
+    lifetime: ResourceUsage,
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Get the resource usage since creation, the last reset or [`Interpreter::reset_resource_usage`]
+    /// (checked against [`super::Config::resource_limits`]).
+    pub fn resource_usage(&self) -> &ResourceUsage {
+        &self.resources.lifetime
+    }
+
+    /// Get the resource usage of the current (or last) [`Interpreter::run`] call.
+    /// Interrupts injected between runs count towards the last run.
+    pub fn run_resource_usage(&self) -> &ResourceUsage {
+        &self.resources.run
+    }
+
+    /// Clear the lifetime resource usage (e.g. at the start of a new accounting period), lifting reached limits.
+    pub fn reset_resource_usage(&mut self) {
+        self.resources.lifetime = ResourceUsage::default();
+    }
+
+    /// Clear the resource usage of the run (at the start of each run).
+    #[inline(always)]
+    pub(crate) fn reset_run_resource_usage(&mut self) {
+        self.resources.run = ResourceUsage::default();
+    }
+
+    /// Account the usage of a resource, unless it would exceed its limit.
+    ///
+    /// Arguments:
+    /// - `resource`: Used resource.
+    /// - `amount`: Amount used.
+    ///
+    /// Returns:
+    /// - `None`: Usage accounted.
+    /// - `Some(State)`: Limit reached, usage not accounted (the state to stop with).
+    #[inline(always)]
+    pub(crate) fn use_resource(&mut self, resource: Resource, amount: u64) -> Option<State> {
+        let limit = self.config.resource_limits.get(resource);
+        if unlikely(limit > 0)
+            && self.resources.lifetime.get(resource).saturating_add(amount) > limit
+        {
+            return Some(State::Halted {
+                reason: ExitReason::ResourceLimit(resource),
+            });
+        }
+
+        self.resources.run.add(resource, amount);
+        self.resources.lifetime.add(resource, amount);
+        None
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::{
+            memory::{SliceMemory, RAM_OFFSET},
+            registers::CSOperation,
+            Config, Error, EMBIVE_INTERRUPT_CODE,
+        },
+        transpiler::transpile_raw,
+    };
+
+    #[test]
+    fn test_usage() {
+        let mut code = [
+            0x23, 0x00, 0xb6, 0x00, // 0: sb a1, 0(a2)
+            0x23, 0x22, 0xb6, 0x00, // 4: sw a1, 4(a2)
+            0x73, 0x00, 0x00, 0x00, // 8: ecall
+            0x6f, 0xf0, 0x5f, 0xff, // 12: j 0
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 8];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        *interpreter.registers.cpu.get_mut(12).unwrap() = RAM_OFFSET as i32;
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter.complete_syscall(Ok(0)).unwrap();
+        let usage = ResourceUsage {
+            bytes_written: 5,
+            syscalls: 1,
+            interrupts: 0,
+        };
+        assert_eq!(interpreter.run_resource_usage(), &usage);
+        assert_eq!(interpreter.resource_usage(), &usage);
+
+        // Per run usage cleared on each run
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        assert_eq!(interpreter.run_resource_usage(), &usage);
+        assert_eq!(interpreter.resource_usage().bytes_written, 10);
+        assert_eq!(interpreter.resource_usage().get(Resource::Syscalls), 2);
+
+        interpreter.reset_resource_usage();
+        assert_eq!(interpreter.resource_usage(), &ResourceUsage::default());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut code = [
+            0x23, 0x22, 0xb6, 0x00, // 0: sw a1, 4(a2)
+            0x73, 0x00, 0x00, 0x00, // 4: ecall
+            0x6f, 0xf0, 0x9f, 0xff, // 8: j 0
+        ];
+        transpile_raw(&mut code).unwrap();
+        let mut ram = [0; 8];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let limits = ResourceLimits::new()
+            .with_bytes_written(6)
+            .with_syscalls(3)
+            .with_interrupts(1);
+        let config = Config::default().with_resource_limits(limits);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        *interpreter.registers.cpu.get_mut(12).unwrap() = RAM_OFFSET as i32;
+
+        // Second store would exceed the limit: not executed
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter.complete_syscall(Ok(0)).unwrap();
+        let halted = Ok(State::Halted {
+            reason: ExitReason::ResourceLimit(Resource::BytesWritten),
+        });
+        assert_eq!(interpreter.run(), halted);
+        assert_eq!(interpreter.program_counter, 0);
+        assert_eq!(interpreter.resource_usage().bytes_written, 4);
+        assert_eq!(interpreter.run(), halted);
+
+        // Interrupts: one taken, then halted (still pending)
+        let control_status = &mut interpreter.registers.control_status;
+        control_status
+            .operation(Some(CSOperation::Write(8)), 0x305)
+            .unwrap();
+        control_status
+            .operation(
+                Some(CSOperation::Write((1 << EMBIVE_INTERRUPT_CODE) | 0x8)),
+                0x304,
+            )
+            .unwrap();
+        control_status
+            .operation(Some(CSOperation::Write(0x8)), 0x300)
+            .unwrap();
+        interpreter.reset_resource_usage();
+        interpreter.interrupt(1).unwrap();
+        assert_eq!(interpreter.resource_usage().interrupts, 1);
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0x8)), 0x300)
+            .unwrap();
+        assert_eq!(
+            interpreter.interrupt(2),
+            Err(Error::ResourceLimit(Resource::Interrupts))
+        );
+        interpreter.raise_software_interrupt(3);
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::ResourceLimit(Resource::Interrupts)
+            })
+        );
+        assert!(interpreter.registers.control_status.mip_software);
+    }
+}
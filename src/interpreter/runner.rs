@@ -0,0 +1,353 @@
+//! Runner Module
+//!
+//! Higher-level interface that owns the interpreter loop, dispatching each [`State`] to host callbacks.
+use core::{fmt, num::NonZeroI32};
+
+use super::{memory::Memory, Error, Interpreter, State, SYSCALL_ARGS};
+
+/// Syscall handler used by the [`Runner`] (check [`Interpreter::syscall`]).
+pub type SyscallHandler<'r, M> =
+    dyn FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<i32, NonZeroI32>, Error> + 'r;
+
+/// Hook handler used by the [`Runner`], called with the hook id (check [`Interpreter::add_hook`]).
+#[cfg(feature = "hooks")]
+pub type HookHandler<'r, 'a, M> = dyn FnMut(u32, &mut Interpreter<'a, M>) + 'r;
+
+/// Embive Runner
+///
+/// Drives an [`Interpreter`], replacing the usual `loop { match interpreter.run() { ... } }` boilerplate:
+/// - [`State::Called`]: The syscall handler is called (error [`Error::NoSyscallFunction`] if not set).
+/// - [`State::Waiting`]: The interrupt source is polled, triggering an interrupt if it returns a value.
+///   Otherwise, the idle callback is called and execution continues after the `wfi` instruction.
+/// - [`State::Running`] / [`State::Yielded`]: The instruction limit was reached or the guest yielded,
+///   the yield callback is called.
+/// - [`State::Breakpoint`]: The breakpoint callback is called. Otherwise, execution stops.
+/// - [`State::Hooked`]: The hook callback is called (`hooks` feature). Otherwise, execution stops.
+/// - [`State::SyscallPending`]: A syscall was deferred (check [`Interpreter::defer_syscall`]), the yield callback
+///   is called, so the host can complete it.
+/// - [`State::Halted`] / [`State::Panicked`]: Execution finished.
+///
+/// Example:
+/// ```
+/// use core::num::NonZeroI32;
+/// use embive::interpreter::{memory::SliceMemory, Error, ExitReason, Interpreter, Runner, State, SYSCALL_ARGS};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// let mut syscall = |_nr: i32, _args: &[i32; SYSCALL_ARGS], _memory: &mut SliceMemory<'_>| {
+///     Ok::<Result<i32, NonZeroI32>, Error>(Ok(0))
+/// };
+/// let mut interrupt = || Some(0);
+///
+/// let state = Runner::new(&mut interpreter)
+///     .syscall(&mut syscall)
+///     .interrupt_source(&mut interrupt)
+///     .run_to_completion()
+///     .unwrap();
+/// assert_eq!(state, State::Halted { reason: ExitReason::Ebreak });
+/// ```
+pub struct Runner<'r, 'a, M: Memory> {
+    interpreter: &'r mut Interpreter<'a, M>,
+    syscall: Option<&'r mut SyscallHandler<'r, M>>,
+    interrupt_source: Option<&'r mut (dyn FnMut() -> Option<i32> + 'r)>,
+    on_idle: Option<&'r mut (dyn FnMut() + 'r)>,
+    on_yield: Option<&'r mut (dyn FnMut() + 'r)>,
+    on_breakpoint: Option<&'r mut (dyn FnMut() + 'r)>,
+    #[cfg(feature = "hooks")]
+    on_hook: Option<&'r mut HookHandler<'r, 'a, M>>,
+}
+
+impl<M: Memory> fmt::Debug for Runner<'_, '_, M>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Runner");
+        debug
+            .field("interpreter", &self.interpreter)
+            .field("syscall", &self.syscall.is_some())
+            .field("interrupt_source", &self.interrupt_source.is_some())
+            .field("on_idle", &self.on_idle.is_some())
+            .field("on_yield", &self.on_yield.is_some())
+            .field("on_breakpoint", &self.on_breakpoint.is_some());
+        #[cfg(feature = "hooks")]
+        debug.field("on_hook", &self.on_hook.is_some());
+        debug.finish()
+    }
+}
+
+impl<'r, 'a, M: Memory> Runner<'r, 'a, M> {
+    /// Create a new runner for an interpreter, without any callbacks.
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter to drive.
+    pub fn new(interpreter: &'r mut Interpreter<'a, M>) -> Self {
+        Runner {
+            interpreter,
+            syscall: None,
+            interrupt_source: None,
+            on_idle: None,
+            on_yield: None,
+            on_breakpoint: None,
+            #[cfg(feature = "hooks")]
+            on_hook: None,
+        }
+    }
+
+    /// Set the syscall handler, called on [`State::Called`].
+    pub fn syscall(mut self, handler: &'r mut SyscallHandler<'r, M>) -> Self {
+        self.syscall = Some(handler);
+        self
+    }
+
+    /// Set the interrupt source, polled on [`State::Waiting`].
+    ///
+    /// The source returns `Some(value)` to trigger an interrupt (value passed through `mtval`),
+    /// or `None` if no interrupt is available.
+    pub fn interrupt_source(mut self, source: &'r mut (dyn FnMut() -> Option<i32> + 'r)) -> Self {
+        self.interrupt_source = Some(source);
+        self
+    }
+
+    /// Set the idle callback, called on [`State::Waiting`] when no interrupt is available.
+    pub fn on_idle(mut self, callback: &'r mut (dyn FnMut() + 'r)) -> Self {
+        self.on_idle = Some(callback);
+        self
+    }
+
+    /// Set the yield callback, called on [`State::Running`] (instruction limit reached), [`State::Yielded`]
+    /// and [`State::SyscallPending`].
+    pub fn on_yield(mut self, callback: &'r mut (dyn FnMut() + 'r)) -> Self {
+        self.on_yield = Some(callback);
+        self
+    }
+
+    /// Set the breakpoint callback, called on [`State::Breakpoint`] (check [`super::EbreakMode::Break`]).
+    /// Execution continues after the callback, otherwise [`Runner::run_to_completion`] stops at breakpoints.
+    pub fn on_breakpoint(mut self, callback: &'r mut (dyn FnMut() + 'r)) -> Self {
+        self.on_breakpoint = Some(callback);
+        self
+    }
+
+    /// Set the hook callback, called on [`State::Hooked`] with the hook id (check [`Interpreter::add_hook`]).
+    /// The callback can redirect the execution (e.g. [`Interpreter::return_from_hook`]), which continues after it.
+    /// Otherwise, [`Runner::run_to_completion`] stops at hooks.
+    #[cfg(feature = "hooks")]
+    pub fn on_hook(mut self, callback: &'r mut HookHandler<'r, 'a, M>) -> Self {
+        self.on_hook = Some(callback);
+        self
+    }
+
+    /// Get the interpreter being driven.
+    pub fn interpreter(&mut self) -> &mut Interpreter<'a, M> {
+        self.interpreter
+    }
+
+    /// Run the interpreter once (check [`Interpreter::run`]) and dispatch the resulting state.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, state returned by the interpreter (already handled).
+    /// - `Err(Error)`: Failed to run or to handle the state.
+    pub fn poll(&mut self) -> Result<State, Error> {
+        let state = self.interpreter.run()?;
+
+        match state {
+            State::Running | State::Yielded | State::SyscallPending => {
+                if let Some(callback) = self.on_yield.as_mut() {
+                    callback();
+                }
+            }
+            State::Called => match self.syscall.as_mut() {
+                Some(handler) => self.interpreter.syscall(handler)?,
+                None => return Err(Error::NoSyscallFunction),
+            },
+            State::Waiting => match self.interrupt_source.as_mut().and_then(|source| source()) {
+                Some(value) => self.interpreter.interrupt(value)?,
+                None => {
+                    if let Some(callback) = self.on_idle.as_mut() {
+                        callback();
+                    }
+                }
+            },
+            State::Breakpoint => {
+                if let Some(callback) = self.on_breakpoint.as_mut() {
+                    callback();
+                }
+            }
+            #[cfg(feature = "hooks")]
+            State::Hooked(id) => {
+                if let Some(callback) = self.on_hook.as_mut() {
+                    callback(id, self.interpreter);
+                }
+            }
+            #[cfg(not(feature = "hooks"))]
+            State::Hooked(_) => {}
+            State::Halted { .. } | State::Panicked { .. } => {}
+        }
+
+        Ok(state)
+    }
+
+    /// Run the interpreter until the code halts or panics.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: The final state ([`State::Halted`], [`State::Panicked`], or [`State::Breakpoint`] /
+    ///   [`State::Hooked`] without a breakpoint / hook callback).
+    /// - `Err(Error)`: Failed to run or to handle a state.
+    pub fn run_to_completion(&mut self) -> Result<State, Error> {
+        loop {
+            let state = self.poll()?;
+
+            match state {
+                State::Halted { .. } | State::Panicked { .. } => return Ok(state),
+                State::Breakpoint if self.on_breakpoint.is_none() => return Ok(state),
+                #[cfg(feature = "hooks")]
+                State::Hooked(_) if self.on_hook.is_none() => return Ok(state),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, Config, EbreakMode};
+
+    #[cfg(feature = "transpiler")]
+    use crate::{
+        interpreter::{registers::CPURegister, ExitReason},
+        transpiler::transpile_raw,
+    };
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_run_to_completion() {
+        let mut code = [
+            0x93, 0x00, 0x80, 0x00, // li   ra, 8
+            0xf3, 0x90, 0x00, 0x30, // csrrw ra, mstatus, ra
+            0x93, 0x00, 0x00, 0x80, // li   ra, -2048
+            0xf3, 0x90, 0x40, 0x30, // csrrw ra, mie, ra
+            0x93, 0x00, 0x00, 0x03, // li   ra, 48
+            0xf3, 0x90, 0x50, 0x30, // csrrw ra, mtvec, ra
+            0x93, 0x08, 0x10, 0x00, // li   a7, 1
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x73, 0x00, 0x50, 0x10, // wfi
+            0x73, 0x00, 0x50, 0x10, // wfi
+            0x73, 0x00, 0x10, 0x00, // ebreak
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x13, 0x01, 0x11, 0x00, // addi sp, sp, 1
+            0x73, 0x00, 0x20, 0x30, // mret
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 2);
+
+        let mut syscalls = 0;
+        let mut interrupts = 0;
+        let mut idles = 0;
+        let mut yields = 0;
+
+        let mut syscall = |nr: i32, _args: &[i32; SYSCALL_ARGS], _memory: &mut SliceMemory<'_>| {
+            syscalls += 1;
+            Ok(Ok(nr + 1))
+        };
+        // Only one interrupt is available
+        let mut interrupt = || {
+            interrupts += 1;
+            (interrupts == 1).then_some(0)
+        };
+        let mut idle = || idles += 1;
+        let mut on_yield = || yields += 1;
+
+        let state = Runner::new(&mut interpreter)
+            .syscall(&mut syscall)
+            .interrupt_source(&mut interrupt)
+            .on_idle(&mut idle)
+            .on_yield(&mut on_yield)
+            .run_to_completion()
+            .unwrap();
+        assert_eq!(
+            state,
+            State::Halted {
+                reason: ExitReason::Ebreak
+            }
+        );
+        assert_eq!(syscalls, 1);
+        assert_eq!(interrupts, 2);
+        assert_eq!(idles, 1);
+        assert!(yields > 0);
+
+        // Syscall result and interrupt handler were executed
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A1), Ok(2));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::SP), Ok(1));
+    }
+
+    #[test]
+    fn test_on_breakpoint() {
+        // Code: ebreak, ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x10, 0x00, 0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_ebreak(EbreakMode::Break);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        // Without a callback, execution stops at the breakpoint
+        let state = Runner::new(&mut interpreter).run_to_completion();
+        assert_eq!(state, Ok(State::Breakpoint));
+        assert_eq!(interpreter.program_counter, 4);
+
+        let mut breakpoints = 0;
+        let mut on_breakpoint = || breakpoints += 1;
+        let state = Runner::new(&mut interpreter)
+            .on_breakpoint(&mut on_breakpoint)
+            .poll();
+        assert_eq!(state, Ok(State::Breakpoint));
+        assert_eq!(breakpoints, 1);
+        assert_eq!(interpreter.program_counter, 8);
+    }
+
+    #[cfg(feature = "hooks")]
+    #[test]
+    fn test_on_hook() {
+        // Code: ebreak, ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x10, 0x00, 0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.add_hook(0, 3).unwrap();
+
+        // Without a callback, execution stops at the hook
+        let state = Runner::new(&mut interpreter).run_to_completion();
+        assert_eq!(state, Ok(State::Hooked(3)));
+        assert_eq!(interpreter.program_counter, 0);
+
+        // Skip the hooked instruction
+        let mut hooks = 0;
+        let mut on_hook = |id, interpreter: &mut Interpreter<'_, SliceMemory<'_>>| {
+            assert_eq!(id, 3);
+            interpreter.program_counter = 4;
+            hooks += 1;
+        };
+        interpreter.reset();
+        let state = Runner::new(&mut interpreter)
+            .on_hook(&mut on_hook)
+            .run_to_completion();
+        assert!(matches!(state, Ok(State::Halted { .. })));
+        assert_eq!(hooks, 1);
+        assert_eq!(interpreter.program_counter, 8);
+    }
+
+    #[test]
+    fn test_no_syscall_function() {
+        // Code: ecall (already transpiled)
+        let code = [0x1f, 0x00, 0x00, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let result = Runner::new(&mut interpreter).poll();
+        assert_eq!(result, Err(Error::NoSyscallFunction));
+    }
+}
@@ -0,0 +1,269 @@
+//! Scheduler Module
+//!
+//! Cooperative scheduler for hosts running multiple guests (e.g. plugins), each with its own interpreter.
+use super::{memory::Memory, Error, Interpreter, State};
+
+/// Scheduler Task: an interpreter and its scheduling parameters (check [`Scheduler`]).
+///
+/// The instruction quantum of the task is the interpreter instruction limit
+/// (check [`Interpreter::set_instruction_limit`]).
+#[derive(Debug)]
+pub struct Task<'a, M: Memory> {
+    /// Task interpreter.
+    pub interpreter: Interpreter<'a, M>,
+    /// Task priority, higher priority tasks always run first. Default: `0`.
+    pub priority: u8,
+    state: State,
+}
+
+impl<'a, M: Memory> Task<'a, M> {
+    /// Create a new task, with the lowest priority (`0`).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The task interpreter. Its instruction limit is the task quantum
+    ///   (0 means no limit, so the task runs until it stops by itself).
+    pub fn new(interpreter: Interpreter<'a, M>) -> Self {
+        Task {
+            interpreter,
+            priority: 0,
+            state: State::Running,
+        }
+    }
+
+    /// Set the task priority (check [`Task::priority`]).
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Get the last state returned by the task interpreter.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Check if the task finished ([`State::Halted`] or [`State::Panicked`]).
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, State::Halted { .. } | State::Panicked { .. })
+    }
+}
+
+/// Embive Scheduler
+///
+/// Owns `N` tasks and runs them cooperatively. Every [`Scheduler::poll`] runs a single task for (at most) its
+/// instruction quantum and returns the resulting state, which the host handles as usual (e.g. syscalls on
+/// [`State::Called`], interrupts on [`State::Waiting`]) through [`Scheduler::interpreter`].
+///
+/// The highest priority unfinished task is selected, round-robin among tasks of the same priority.
+/// Finished tasks ([`State::Halted`] or [`State::Panicked`]) are not scheduled again until reset
+/// (check [`Scheduler::reset`]).
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::SliceMemory, ExitReason, Interpreter, Scheduler, State, Task};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut first = SliceMemory::new(&code, &mut []);
+/// let mut second = SliceMemory::new(&code, &mut []);
+///
+/// let mut scheduler = Scheduler::new([
+///     Task::new(Interpreter::new(&mut first, 100)),
+///     Task::new(Interpreter::new(&mut second, 100)).with_priority(1),
+/// ]);
+///
+/// // Higher priority first
+/// assert_eq!(scheduler.poll(), Ok(Some((1, State::Halted { reason: ExitReason::Ebreak }))));
+/// assert_eq!(scheduler.poll(), Ok(Some((0, State::Halted { reason: ExitReason::Ebreak }))));
+/// assert_eq!(scheduler.poll(), Ok(None));
+/// ```
+#[derive(Debug)]
+pub struct Scheduler<'a, M: Memory, const N: usize> {
+    tasks: [Task<'a, M>; N],
+    last: usize,
+}
+
+impl<'a, M: Memory, const N: usize> Scheduler<'a, M, N> {
+    /// Create a new scheduler.
+    ///
+    /// Arguments:
+    /// - `tasks`: The tasks to schedule.
+    pub fn new(tasks: [Task<'a, M>; N]) -> Self {
+        Scheduler {
+            tasks,
+            last: N.saturating_sub(1),
+        }
+    }
+
+    /// Get a task.
+    ///
+    /// Arguments:
+    /// - `index`: Task index.
+    pub fn task(&self, index: usize) -> Option<&Task<'a, M>> {
+        self.tasks.get(index)
+    }
+
+    /// Get a task interpreter (e.g. to handle a syscall or trigger an interrupt).
+    ///
+    /// Arguments:
+    /// - `index`: Task index.
+    pub fn interpreter(&mut self, index: usize) -> Option<&mut Interpreter<'a, M>> {
+        self.tasks.get_mut(index).map(|task| &mut task.interpreter)
+    }
+
+    /// Get the last state of every task.
+    pub fn states(&self) -> [State; N] {
+        core::array::from_fn(|index| self.tasks[index].state)
+    }
+
+    /// Check if all tasks finished.
+    pub fn is_finished(&self) -> bool {
+        self.tasks.iter().all(Task::is_finished)
+    }
+
+    /// Reset a task (check [`Interpreter::reset`]), scheduling it again.
+    ///
+    /// Arguments:
+    /// - `index`: Task index.
+    pub fn reset(&mut self, index: usize) {
+        if let Some(task) = self.tasks.get_mut(index) {
+            task.interpreter.reset();
+            task.state = State::Running;
+        }
+    }
+
+    /// Select the next task to run.
+    fn next(&self) -> Option<usize> {
+        let priority = self
+            .tasks
+            .iter()
+            .filter(|task| !task.is_finished())
+            .map(|task| task.priority)
+            .max()?;
+
+        // Round-robin, starting after the last task run
+        (1..=N)
+            .map(|offset| (self.last + offset) % N)
+            .find(|&index| {
+                let task = &self.tasks[index];
+                !task.is_finished() && task.priority == priority
+            })
+    }
+
+    /// Run the next task once (check [`Interpreter::run`]).
+    ///
+    /// Returns:
+    /// - `Ok(Some((usize, State)))`: Success, index of the task run and its state (to be handled by the host).
+    /// - `Ok(None)`: All tasks finished.
+    /// - `Err((usize, Error))`: Failed to run a task (index of the task and error).
+    pub fn poll(&mut self) -> Result<Option<(usize, State)>, (usize, Error)> {
+        let Some(index) = self.next() else {
+            return Ok(None);
+        };
+        self.last = index;
+
+        let task = &mut self.tasks[index];
+        task.state = task.interpreter.run().map_err(|e| (index, e))?;
+
+        Ok(Some((index, task.state)))
+    }
+}
+
+#[cfg(all(test, feature = "transpiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::{memory::SliceMemory, ExitReason},
+        transpiler::transpile_raw,
+    };
+
+    fn code() -> [u8; 20] {
+        let mut code = [
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let code = code();
+        let mut first = SliceMemory::new(&code, &mut []);
+        let mut second = SliceMemory::new(&code, &mut []);
+        let mut scheduler = Scheduler::new([
+            Task::new(Interpreter::new(&mut first, 2)),
+            Task::new(Interpreter::new(&mut second, 3)),
+        ]);
+
+        assert_eq!(scheduler.poll(), Ok(Some((0, State::Running))));
+        assert_eq!(scheduler.poll(), Ok(Some((1, State::Running))));
+        assert_eq!(scheduler.poll(), Ok(Some((0, State::Running))));
+        assert_eq!(scheduler.states(), [State::Running, State::Running]);
+        assert_eq!(
+            scheduler.poll(),
+            Ok(Some((
+                1,
+                State::Halted {
+                    reason: ExitReason::Ebreak
+                }
+            )))
+        );
+        assert_eq!(
+            scheduler.poll(),
+            Ok(Some((
+                0,
+                State::Halted {
+                    reason: ExitReason::Ebreak
+                }
+            )))
+        );
+        assert_eq!(scheduler.poll(), Ok(None));
+        assert!(scheduler.is_finished());
+
+        assert_eq!(scheduler.interpreter(0).unwrap().registers.cpu.a0(), 4);
+        assert_eq!(scheduler.interpreter(1).unwrap().registers.cpu.a0(), 4);
+
+        scheduler.reset(1);
+        assert_eq!(scheduler.poll(), Ok(Some((1, State::Running))));
+    }
+
+    #[test]
+    fn test_priority() {
+        let code = code();
+        let mut first = SliceMemory::new(&code, &mut []);
+        let mut second = SliceMemory::new(&code, &mut []);
+        let mut scheduler = Scheduler::new([
+            Task::new(Interpreter::new(&mut first, 2)),
+            Task::new(Interpreter::new(&mut second, 2)).with_priority(1),
+        ]);
+        assert_eq!(scheduler.task(1).unwrap().priority, 1);
+
+        // Low priority task only runs after the high priority one finished
+        assert_eq!(scheduler.poll(), Ok(Some((1, State::Running))));
+        assert_eq!(scheduler.poll(), Ok(Some((1, State::Running))));
+        assert_eq!(
+            scheduler.poll(),
+            Ok(Some((
+                1,
+                State::Halted {
+                    reason: ExitReason::Ebreak
+                }
+            )))
+        );
+        assert_eq!(scheduler.poll(), Ok(Some((0, State::Running))));
+        assert_eq!(scheduler.poll(), Ok(Some((0, State::Running))));
+        assert_eq!(
+            scheduler.poll(),
+            Ok(Some((
+                0,
+                State::Halted {
+                    reason: ExitReason::Ebreak
+                }
+            )))
+        );
+        assert_eq!(scheduler.poll(), Ok(None));
+    }
+}
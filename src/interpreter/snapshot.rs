@@ -0,0 +1,674 @@
+//! Snapshot Module
+//!
+//! Persistent interpreter state, so a guest can be resumed exactly where it stopped (e.g. after a power cycle).
+//! Snapshots are versioned and checksummed, and are written/read in fixed-size chunks (e.g. flash pages)
+//! through user-supplied storage ([`SnapshotWriter`] and [`SnapshotReader`]).
+//!
+//! Format (little-endian 32-bit words, so every field is word-aligned):
+//! - Header: magic ([`SNAPSHOT_MAGIC`]), version ([`SNAPSHOT_VERSION`]), state size and RAM size, in bytes.
+//! - State: program counter, CPU registers, machine state (CSRs) and pending interpreter state
+//!   (syscall, sleep, memory reservation, TLS base, instruction debt and random number generator).
+//! - RAM contents.
+//! - CRC-32 (IEEE) of all the previous bytes.
+//!
+//! The last chunk is padded with `0xFF` (erased flash value). Host settings ([`super::Config`], handlers,
+//! machine IDs and virtual time ratio) aren't part of the snapshot: the resuming interpreter must be
+//! created with the same configuration and code.
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::{
+//!     memory::{Memory, SliceMemory, RAM_OFFSET},
+//!     snapshot::snapshot_size,
+//!     Interpreter,
+//! };
+//!
+//! // Code: ebreak (already transpiled)
+//! let code = [0x1f, 0x00, 0x10, 0x00];
+//! let mut ram = [0; 64];
+//! let mut memory = SliceMemory::new(&code, &mut ram);
+//! let mut interpreter = Interpreter::new(&mut memory, 0);
+//! interpreter.registers.cpu.set_a0(42);
+//! interpreter.memory.store_u32(RAM_OFFSET, 0xCAFE).unwrap();
+//!
+//! // Save to a flash-like storage, in 32-byte pages
+//! let mut flash = [0xFF; 512];
+//! let mut page = [0; 32];
+//! let chunks = interpreter.save_snapshot(&mut flash[..], 64, &mut page).unwrap();
+//! assert_eq!(chunks, snapshot_size(64).div_ceil(32));
+//!
+//! // After a power cycle
+//! let mut ram = [0; 64];
+//! let mut memory = SliceMemory::new(&code, &mut ram);
+//! let mut interpreter = Interpreter::new(&mut memory, 0);
+//! interpreter.resume_from(&mut &flash[..], 64, &mut page).unwrap();
+//! assert_eq!(interpreter.registers.cpu.a0(), 42);
+//! assert_eq!(interpreter.memory.load_u32(RAM_OFFSET), Ok(0xCAFE));
+//! ```
+use core::fmt::{self, Display, Formatter};
+
+use super::{
+    memory::{Memory, RAM_OFFSET},
+    registers::CSR_SNAPSHOT_WORDS,
+    Error, Interpreter,
+};
+
+/// Snapshot magic (`"EMBS"`).
+pub const SNAPSHOT_MAGIC: u32 = u32::from_le_bytes(*b"EMBS");
+
+/// Snapshot format version (incremented on incompatible changes).
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Header size (magic, version, state size and RAM size), in bytes.
+const HEADER_SIZE: usize = 16;
+
+/// Number of CPU registers.
+const CPU_WORDS: usize = 32;
+
+/// Number of state words (program counter, CPU registers, machine state, flags, sleep timeout,
+/// memory reservation, TLS base, instruction debt and random number generator state).
+const STATE_WORDS: usize = 1 + CPU_WORDS + CSR_SNAPSHOT_WORDS + 1 + 2 + 2 + 1 + 2 + 2;
+
+/// State size, in bytes.
+const STATE_SIZE: usize = STATE_WORDS * 4;
+
+/// Checksum size, in bytes.
+const CHECKSUM_SIZE: usize = 4;
+
+/// State flag: syscall pending.
+const FLAG_SYSCALL_PENDING: u32 = 1 << 0;
+/// State flag: sleep timeout set.
+const FLAG_WAIT_TIMEOUT: u32 = 1 << 1;
+/// State flag: memory reservation set.
+const FLAG_MEMORY_RESERVATION: u32 = 1 << 2;
+/// State flag: TLS base set.
+const FLAG_TLS_BASE: u32 = 1 << 3;
+/// State flag: pending syscall deferred.
+const FLAG_SYSCALL_DEFERRED: u32 = 1 << 4;
+
+/// Padding byte of the last chunk (erased flash value).
+const PADDING: u8 = 0xFF;
+
+/// Get the size of a snapshot, in bytes (without the padding of the last chunk).
+///
+/// Arguments:
+/// - `ram_size`: RAM size, in bytes.
+pub const fn snapshot_size(ram_size: usize) -> usize {
+    HEADER_SIZE + STATE_SIZE + ram_size + CHECKSUM_SIZE
+}
+
+/// Snapshot Error
+#[derive(Debug, PartialEq)]
+pub enum SnapshotError {
+    /// Failed to access the interpreter memory.
+    Interpreter(Error),
+    /// Failed to read from or write to the snapshot storage.
+    Storage,
+    /// Chunk buffer is empty or its size isn't a multiple of 4 bytes. The size is provided.
+    InvalidChunkSize(usize),
+    /// Not a snapshot (magic mismatch).
+    InvalidMagic,
+    /// Unsupported snapshot format version. The version is provided.
+    UnsupportedVersion(u32),
+    /// Snapshot RAM size doesn't match the requested one. The snapshot RAM size is provided.
+    RamSizeMismatch(u32),
+    /// Snapshot was taken with different extensions enabled (`misa`). The snapshot `misa` is provided.
+    ConfigMismatch(u32),
+    /// Checksum mismatch (corrupted or incomplete snapshot).
+    Checksum,
+}
+
+impl From<Error> for SnapshotError {
+    fn from(error: Error) -> Self {
+        SnapshotError::Interpreter(error)
+    }
+}
+
+impl core::error::Error for SnapshotError {}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Snapshot storage, written in fixed-size chunks.
+///
+/// Chunks are written in order, each with the size of the chunk buffer (check [`Interpreter::save_snapshot`]).
+pub trait SnapshotWriter {
+    /// Start writing a snapshot (wear-leveling hook, e.g. select and erase the next flash slot).
+    ///
+    /// Arguments:
+    /// - `chunks`: Number of chunks to be written.
+    fn begin(&mut self, chunks: usize) -> Result<(), SnapshotError> {
+        let _ = chunks;
+        Ok(())
+    }
+
+    /// Write a chunk.
+    ///
+    /// Arguments:
+    /// - `index`: Chunk index (from `0`).
+    /// - `chunk`: Chunk data.
+    fn write_chunk(&mut self, index: usize, chunk: &[u8]) -> Result<(), SnapshotError>;
+
+    /// Finish writing a snapshot (wear-leveling hook, e.g. mark the slot as the active one).
+    fn finish(&mut self) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
+
+/// Snapshot storage, read in fixed-size chunks.
+///
+/// Chunks may be read more than once (check [`Interpreter::resume_from`]).
+pub trait SnapshotReader {
+    /// Read a chunk.
+    ///
+    /// Arguments:
+    /// - `index`: Chunk index (from `0`).
+    /// - `chunk`: Chunk buffer, to be filled.
+    fn read_chunk(&mut self, index: usize, chunk: &mut [u8]) -> Result<(), SnapshotError>;
+}
+
+/// Contiguous storage (e.g. memory-mapped flash), chunk `index` is at `index * chunk.len()`.
+impl SnapshotWriter for [u8] {
+    fn write_chunk(&mut self, index: usize, chunk: &[u8]) -> Result<(), SnapshotError> {
+        self.get_mut(index * chunk.len()..(index + 1) * chunk.len())
+            .ok_or(SnapshotError::Storage)?
+            .copy_from_slice(chunk);
+        Ok(())
+    }
+}
+
+/// Contiguous storage (e.g. memory-mapped flash), chunk `index` is at `index * chunk.len()`.
+impl SnapshotReader for &[u8] {
+    fn read_chunk(&mut self, index: usize, chunk: &mut [u8]) -> Result<(), SnapshotError> {
+        let len = chunk.len();
+        chunk.copy_from_slice(
+            self.get(index * len..(index + 1) * len)
+                .ok_or(SnapshotError::Storage)?,
+        );
+        Ok(())
+    }
+}
+
+/// Update a CRC-32 (IEEE, reflected) with `data`.
+///
+/// Arguments:
+/// - `crc`: Current CRC (`!0` initially, inverted when done).
+/// - `data`: Data to checksum.
+pub(crate) fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    crc
+}
+
+/// Check the chunk buffer size.
+fn check_chunk_size(buffer: &[u8]) -> Result<(), SnapshotError> {
+    match buffer.len() {
+        len if len == 0 || len % 4 != 0 => Err(SnapshotError::InvalidChunkSize(len)),
+        _ => Ok(()),
+    }
+}
+
+/// Streams bytes to a [`SnapshotWriter`], one chunk at a time.
+struct ChunkWriter<'c, W: SnapshotWriter + ?Sized> {
+    writer: &'c mut W,
+    buffer: &'c mut [u8],
+    len: usize,
+    index: usize,
+    crc: u32,
+}
+
+impl<W: SnapshotWriter + ?Sized> ChunkWriter<'_, W> {
+    /// Write bytes (without updating the checksum).
+    fn write(&mut self, mut data: &[u8]) -> Result<(), SnapshotError> {
+        while !data.is_empty() {
+            let count = data.len().min(self.buffer.len() - self.len);
+            self.buffer[self.len..self.len + count].copy_from_slice(&data[..count]);
+            self.len += count;
+            data = &data[count..];
+
+            if self.len == self.buffer.len() {
+                self.writer.write_chunk(self.index, self.buffer)?;
+                self.index += 1;
+                self.len = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write checksummed bytes.
+    fn push(&mut self, data: &[u8]) -> Result<(), SnapshotError> {
+        self.crc = crc32_update(self.crc, data);
+        self.write(data)
+    }
+
+    /// Write checksummed words.
+    fn push_words(&mut self, words: &[u32]) -> Result<(), SnapshotError> {
+        words
+            .iter()
+            .try_for_each(|word| self.push(&word.to_le_bytes()))
+    }
+
+    /// Write the checksum and pad the last chunk, returning the number of chunks written.
+    fn finish(mut self) -> Result<usize, SnapshotError> {
+        self.write(&(!self.crc).to_le_bytes())?;
+        if self.len > 0 {
+            self.buffer[self.len..].fill(PADDING);
+            self.writer.write_chunk(self.index, self.buffer)?;
+            self.index += 1;
+        }
+        Ok(self.index)
+    }
+}
+
+/// Streams bytes from a [`SnapshotReader`], one chunk at a time.
+struct ChunkReader<'c, R: SnapshotReader + ?Sized> {
+    reader: &'c mut R,
+    buffer: &'c mut [u8],
+    position: usize,
+    index: usize,
+    crc: u32,
+}
+
+impl<'c, R: SnapshotReader + ?Sized> ChunkReader<'c, R> {
+    fn new(reader: &'c mut R, buffer: &'c mut [u8]) -> Self {
+        let position = buffer.len();
+        Self {
+            reader,
+            buffer,
+            position,
+            index: 0,
+            crc: !0,
+        }
+    }
+
+    /// Read bytes (without updating the checksum).
+    fn read(&mut self, mut data: &mut [u8]) -> Result<(), SnapshotError> {
+        while !data.is_empty() {
+            if self.position == self.buffer.len() {
+                self.reader.read_chunk(self.index, self.buffer)?;
+                self.index += 1;
+                self.position = 0;
+            }
+
+            let count = data.len().min(self.buffer.len() - self.position);
+            data[..count].copy_from_slice(&self.buffer[self.position..self.position + count]);
+            self.position += count;
+            data = &mut data[count..];
+        }
+        Ok(())
+    }
+
+    /// Read checksummed bytes.
+    fn pull(&mut self, data: &mut [u8]) -> Result<(), SnapshotError> {
+        self.read(data)?;
+        self.crc = crc32_update(self.crc, data);
+        Ok(())
+    }
+
+    /// Read checksummed words.
+    fn pull_words(&mut self, words: &mut [u32]) -> Result<(), SnapshotError> {
+        for word in words {
+            let mut bytes = [0; 4];
+            self.pull(&mut bytes)?;
+            *word = u32::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    /// Read and check the checksum.
+    fn finish(mut self) -> Result<(), SnapshotError> {
+        let mut checksum = [0; CHECKSUM_SIZE];
+        self.read(&mut checksum)?;
+        match u32::from_le_bytes(checksum) == !self.crc {
+            true => Ok(()),
+            false => Err(SnapshotError::Checksum),
+        }
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Save the interpreter state and RAM to a snapshot (check [`crate::interpreter::snapshot`]).
+    ///
+    /// Snapshots can be taken whenever the interpreter is stopped (e.g. [`super::State::Waiting`] before
+    /// a deep sleep), including with a pending syscall.
+    ///
+    /// Arguments:
+    /// - `writer`: Snapshot storage.
+    /// - `ram_size`: RAM size to save, in bytes (from [`RAM_OFFSET`]).
+    /// - `buffer`: Chunk buffer, its size (a multiple of 4 bytes) is the chunk size (e.g. flash page size).
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, number of chunks written.
+    /// - `Err(SnapshotError)`: Invalid chunk size, RAM size or storage error.
+    pub fn save_snapshot<W: SnapshotWriter + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        ram_size: usize,
+        buffer: &mut [u8],
+    ) -> Result<usize, SnapshotError> {
+        check_chunk_size(buffer)?;
+        let ram = self.memory.load_bytes(RAM_OFFSET, ram_size)?;
+
+        writer.begin(snapshot_size(ram_size).div_ceil(buffer.len()))?;
+        let mut chunks = ChunkWriter {
+            writer: &mut *writer,
+            buffer,
+            len: 0,
+            index: 0,
+            crc: !0,
+        };
+        chunks.push_words(&[
+            SNAPSHOT_MAGIC,
+            SNAPSHOT_VERSION,
+            STATE_SIZE as u32,
+            ram_size as u32,
+        ])?;
+        chunks.push_words(&state_words(
+            self.program_counter,
+            &self.registers.cpu.inner,
+            &self.registers.control_status.snapshot(),
+            self.syscall_pending,
+            self.syscall_deferred,
+            self.wait_timeout,
+            self.memory_reservation,
+            self.tls_base,
+            self.instruction_debt,
+            self.rng_state,
+        ))?;
+        chunks.push(ram)?;
+        let count = chunks.finish()?;
+
+        writer.finish()?;
+        Ok(count)
+    }
+
+    /// Resume from a snapshot (check [`Interpreter::save_snapshot`]), restoring the interpreter state and RAM.
+    ///
+    /// The snapshot is fully checked (header, configuration and checksum) before restoring anything,
+    /// so the interpreter is left untouched on error. Chunks are read twice (check, then restore).
+    ///
+    /// Arguments:
+    /// - `reader`: Snapshot storage.
+    /// - `ram_size`: RAM size to restore, in bytes (must match the snapshot).
+    /// - `buffer`: Chunk buffer, with the same size used to save the snapshot.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, the interpreter can continue running.
+    /// - `Err(SnapshotError)`: Invalid or corrupted snapshot, invalid RAM size or storage error.
+    pub fn resume_from<R: SnapshotReader + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        ram_size: usize,
+        buffer: &mut [u8],
+    ) -> Result<(), SnapshotError> {
+        check_chunk_size(buffer)?;
+        self.memory.load_bytes(RAM_OFFSET, ram_size)?;
+
+        // Check the snapshot
+        let mut chunks = ChunkReader::new(&mut *reader, &mut *buffer);
+        let mut header = [0; HEADER_SIZE / 4];
+        chunks.pull_words(&mut header)?;
+        match header {
+            [magic, ..] if magic != SNAPSHOT_MAGIC => return Err(SnapshotError::InvalidMagic),
+            [_, version, state_size, _]
+                if version != SNAPSHOT_VERSION || state_size != STATE_SIZE as u32 =>
+            {
+                return Err(SnapshotError::UnsupportedVersion(version))
+            }
+            [_, _, _, size] if size as usize != ram_size => {
+                return Err(SnapshotError::RamSizeMismatch(size))
+            }
+            _ => {}
+        }
+
+        let mut state = [0; STATE_WORDS];
+        chunks.pull_words(&mut state)?;
+        let mut csr = [0; CSR_SNAPSHOT_WORDS];
+        csr.copy_from_slice(&state[1 + CPU_WORDS..1 + CPU_WORDS + CSR_SNAPSHOT_WORDS]);
+        let misa = csr[CSR_SNAPSHOT_WORDS - 1];
+        if misa != self.registers.control_status.misa() {
+            return Err(SnapshotError::ConfigMismatch(misa));
+        }
+
+        let mut scratch = [0; 64];
+        for offset in (0..ram_size).step_by(scratch.len()) {
+            let len = scratch.len().min(ram_size - offset);
+            chunks.pull(&mut scratch[..len])?;
+        }
+        chunks.finish()?;
+
+        // Restore the RAM (header and state were already read)
+        let mut chunks = ChunkReader::new(reader, buffer);
+        let mut skipped = [0; HEADER_SIZE + STATE_SIZE];
+        chunks.read(&mut skipped)?;
+        chunks.read(self.memory.mut_bytes(RAM_OFFSET, ram_size)?)?;
+
+        // Restore the state
+        let rest = &state[1 + CPU_WORDS + CSR_SNAPSHOT_WORDS..];
+        self.program_counter = state[0];
+        for (register, &value) in self.registers.cpu.inner.iter_mut().zip(&state[1..]) {
+            *register = value as i32;
+        }
+        self.registers.control_status.restore_snapshot(&csr);
+        self.sync_software_interrupt();
+        #[cfg(feature = "mmu")]
+        self.mmu.flush();
+
+        let flags = rest[0];
+        let wide = |index: usize| rest[index] as u64 | (rest[index + 1] as u64) << 32;
+        self.syscall_pending = flags & FLAG_SYSCALL_PENDING != 0;
+        self.syscall_deferred = flags & FLAG_SYSCALL_DEFERRED != 0;
+        self.wait_timeout = (flags & FLAG_WAIT_TIMEOUT != 0).then(|| wide(1));
+        self.memory_reservation =
+            (flags & FLAG_MEMORY_RESERVATION != 0).then_some((rest[3], rest[4] as i32));
+        self.tls_base = (flags & FLAG_TLS_BASE != 0).then_some(rest[5]);
+        self.instruction_debt = wide(6);
+        self.rng_state = wide(8);
+        Ok(())
+    }
+}
+
+/// Encode the interpreter state as snapshot words.
+#[allow(clippy::too_many_arguments)]
+fn state_words(
+    program_counter: u32,
+    cpu: &[i32],
+    csr: &[u32; CSR_SNAPSHOT_WORDS],
+    syscall_pending: bool,
+    syscall_deferred: bool,
+    wait_timeout: Option<u64>,
+    memory_reservation: Option<(u32, i32)>,
+    tls_base: Option<u32>,
+    instruction_debt: u64,
+    rng_state: u64,
+) -> [u32; STATE_WORDS] {
+    let mut flags = 0;
+    if syscall_pending {
+        flags |= FLAG_SYSCALL_PENDING;
+    }
+    if syscall_deferred {
+        flags |= FLAG_SYSCALL_DEFERRED;
+    }
+    if wait_timeout.is_some() {
+        flags |= FLAG_WAIT_TIMEOUT;
+    }
+    if memory_reservation.is_some() {
+        flags |= FLAG_MEMORY_RESERVATION;
+    }
+    if tls_base.is_some() {
+        flags |= FLAG_TLS_BASE;
+    }
+    let wait_timeout = wait_timeout.unwrap_or(0);
+    let (reservation_address, reservation_value) = memory_reservation.unwrap_or((0, 0));
+
+    let mut words = [0; STATE_WORDS];
+    words[0] = program_counter;
+    for (word, &value) in words[1..].iter_mut().zip(cpu) {
+        *word = value as u32;
+    }
+    words[1 + CPU_WORDS..1 + CPU_WORDS + CSR_SNAPSHOT_WORDS].copy_from_slice(csr);
+    words[1 + CPU_WORDS + CSR_SNAPSHOT_WORDS..].copy_from_slice(&[
+        flags,
+        wait_timeout as u32,
+        (wait_timeout >> 32) as u32,
+        reservation_address,
+        reservation_value as u32,
+        tls_base.unwrap_or(0),
+        instruction_debt as u32,
+        (instruction_debt >> 32) as u32,
+        rng_state as u32,
+        (rng_state >> 32) as u32,
+    ]);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, Config, State};
+
+    /// Code: ecall, wfi, c.j -4, padding (already transpiled)
+    const CODE: [u8; 12] = [
+        0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x30, 0x00, 0xcf, 0xff, 0x00, 0x00,
+    ];
+
+    /// Wear-leveling storage: two slots, the finished one becomes active.
+    struct Slots {
+        slots: [[u8; 512]; 2],
+        active: usize,
+        chunks: usize,
+    }
+
+    impl SnapshotWriter for Slots {
+        fn begin(&mut self, chunks: usize) -> Result<(), SnapshotError> {
+            self.chunks = chunks;
+            self.slots[1 - self.active].fill(0xFF);
+            Ok(())
+        }
+
+        fn write_chunk(&mut self, index: usize, chunk: &[u8]) -> Result<(), SnapshotError> {
+            self.slots[1 - self.active].write_chunk(index, chunk)
+        }
+
+        fn finish(&mut self) -> Result<(), SnapshotError> {
+            self.active = 1 - self.active;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_save_resume() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_a0(-5);
+        interpreter
+            .memory
+            .store_u32(RAM_OFFSET + 28, 0x1234_5678)
+            .unwrap();
+        interpreter.tls_base = Some(RAM_OFFSET + 16);
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        interpreter.memory_reservation = Some((RAM_OFFSET, 7));
+        interpreter.consume_instructions(3);
+
+        let mut slots = Slots {
+            slots: [[0xFF; 512]; 2],
+            active: 0,
+            chunks: 0,
+        };
+        let mut chunk = [0; 20];
+        let chunks = interpreter
+            .save_snapshot(&mut slots, 32, &mut chunk)
+            .unwrap();
+        assert_eq!(chunks, snapshot_size(32).div_ceil(20));
+        assert_eq!(slots.chunks, chunks);
+        assert_eq!(slots.active, 1);
+        assert_eq!(slots.slots[1][snapshot_size(32)..chunks * 20], [0xFF; 4]);
+
+        let mut resumed_ram = [0; 32];
+        let mut resumed_memory = SliceMemory::new(&CODE, &mut resumed_ram);
+        let mut resumed = Interpreter::new(&mut resumed_memory, 0);
+        resumed
+            .resume_from(&mut &slots.slots[1][..], 32, &mut chunk)
+            .unwrap();
+        assert_eq!(resumed.program_counter, interpreter.program_counter);
+        assert_eq!(resumed.registers, interpreter.registers);
+        assert_eq!(resumed.memory_reservation, Some((RAM_OFFSET, 7)));
+        assert_eq!(resumed.tls_base, Some(RAM_OFFSET + 16));
+        assert_eq!(resumed.instruction_debt, 3);
+        assert_eq!(resumed.rng_state, interpreter.rng_state);
+        assert_eq!(resumed.pending_syscall(), interpreter.pending_syscall());
+        assert_eq!(resumed.memory.load_u32(RAM_OFFSET + 28), Ok(0x1234_5678));
+
+        // Continues where it stopped
+        resumed.complete_syscall(Ok(0)).unwrap();
+        resumed.instruction_limit = 4;
+        assert_eq!(resumed.run(), Ok(State::Waiting));
+    }
+
+    #[test]
+    fn test_resume_errors() {
+        let mut ram = [0; 32];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let mut flash = [0xFF; 512];
+        let mut chunk = [0; 16];
+        assert_eq!(
+            interpreter.save_snapshot(&mut flash[..], 32, &mut [0; 6]),
+            Err(SnapshotError::InvalidChunkSize(6))
+        );
+        assert_eq!(
+            interpreter.save_snapshot(&mut flash[..64], 32, &mut chunk),
+            Err(SnapshotError::Storage)
+        );
+        assert_eq!(
+            interpreter.resume_from(&mut &[0xFF; 512][..], 32, &mut chunk),
+            Err(SnapshotError::InvalidMagic)
+        );
+        interpreter
+            .save_snapshot(&mut flash[..], 32, &mut chunk)
+            .unwrap();
+        assert_eq!(
+            interpreter.resume_from(&mut &flash[..], 16, &mut chunk),
+            Err(SnapshotError::RamSizeMismatch(32))
+        );
+
+        // Corrupted snapshots leave the interpreter untouched
+        let mut corrupted = flash;
+        corrupted[HEADER_SIZE + STATE_SIZE + 4] ^= 1;
+        interpreter.registers.cpu.set_a0(1);
+        assert_eq!(
+            interpreter.resume_from(&mut &corrupted[..], 32, &mut chunk),
+            Err(SnapshotError::Checksum)
+        );
+        assert_eq!(interpreter.registers.cpu.a0(), 1);
+
+        let mut corrupted = flash;
+        corrupted[4] = 4;
+        assert_eq!(
+            interpreter.resume_from(&mut &corrupted[..], 32, &mut chunk),
+            Err(SnapshotError::UnsupportedVersion(4))
+        );
+
+        // Different configuration
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let config = Config::default().with_m_extension(false);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        assert!(matches!(
+            interpreter.resume_from(&mut &flash[..], 32, &mut chunk),
+            Err(SnapshotError::ConfigMismatch(_))
+        ));
+    }
+}
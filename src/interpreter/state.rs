@@ -8,8 +8,59 @@ pub enum State {
     Running,
     /// Interpreter was called (syscall). Optionally call [`super::Interpreter::syscall`] to handle the syscall and then [`super::Interpreter::run`] to continue running.
     Called,
+    /// Syscall deferred by the host (check [`super::Interpreter::defer_syscall`]). Call
+    /// [`super::Interpreter::complete_syscall`] and then [`super::Interpreter::run`] to continue running
+    /// (running before completing the syscall returns this state again).
+    SyscallPending,
     /// Interpreter waiting interrupt. Optionally call [`super::Interpreter::interrupt`] to trigger an interrupt and then [`super::Interpreter::run`] to continue running.
+    /// If the interpreted code requested a timeout ([`super::SLEEP_SYSCALL`]), it is available through [`super::Interpreter::wait_timeout`].
     Waiting,
+    /// Interpreted code yielded voluntarily (syscall [`super::YIELD_SYSCALL`]), as opposed to [`State::Running`]
+    /// at the instruction limit (preemption). Call [`super::Interpreter::run`] to continue running.
+    Yielded,
     /// Interpreter halted. Call [`super::Interpreter::reset`] and then [`super::Interpreter::run`] to run again.
-    Halted,
+    Halted {
+        /// Why the interpreted code stopped.
+        reason: ExitReason,
+    },
+    /// Interpreter stopped at a breakpoint (`ebreak` with [`super::EbreakMode::Break`]), the program counter
+    /// points to the next instruction. Call [`super::Interpreter::run`] to continue running.
+    Breakpoint,
+    /// Interpreter stopped at a host hook (`hooks` feature, check `Interpreter::add_hook`), before executing the
+    /// hooked instruction. The hook id is provided. Call [`super::Interpreter::run`] to continue running (optionally
+    /// after redirecting the execution, e.g. `Interpreter::return_from_hook`). Never returned without the feature.
+    Hooked(u32),
+    /// Interpreted code panicked (syscall [`super::PANIC_SYSCALL`]). Optionally call [`super::Interpreter::panic_message`]
+    /// to decode the panic message, then call [`super::Interpreter::reset`] and [`super::Interpreter::run`] to run again.
+    Panicked {
+        /// Address of the panic message (`a0`).
+        msg_ptr: u32,
+        /// Length of the panic message, in bytes (`a1`).
+        len: u32,
+    },
+}
+
+/// Reason of a halt ([`State::Halted`]).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExitReason {
+    /// The interpreted code executed `ebreak` (with [`super::EbreakMode::Halt`]). Results, if any, are in the
+    /// registers (e.g. `a0`).
+    Ebreak,
+    /// The interpreted code exited with a code (syscall [`super::EXIT_SYSCALL`], `a0`).
+    ExitSyscall(i32),
+    /// The instruction budget was exhausted (check [`super::Config::max_instructions`]).
+    InstructionLimit,
+    /// A resource limit was reached (check [`super::Config::resource_limits`]).
+    #[cfg(feature = "resource-limits")]
+    ResourceLimit(super::Resource),
+}
+
+impl ExitReason {
+    /// Get the exit code, if the interpreted code exited through [`super::EXIT_SYSCALL`].
+    pub fn code(&self) -> Option<i32> {
+        match *self {
+            ExitReason::ExitSyscall(code) => Some(code),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,205 @@
+//! Stepping Module
+//!
+//! Instruction boundary helpers for debuggers and steppers.
+//! Transpiled code mixes compressed (16-bit) and regular (32-bit) instructions, so not every
+//! 2-byte aligned address is the start of an instruction.
+use super::{
+    memory::{Memory, RAM_OFFSET},
+    Error, Interpreter,
+};
+use crate::instruction::{
+    embive::{
+        Branch, CBeqz, CBnez, CEbreakJalrAdd, CJal, CJrMv, InstructionImpl, Jal, Jalr,
+        SystemMiscMem, CJ,
+    },
+    Instruction,
+};
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Get the possible program counters after executing the current instruction.
+    ///
+    /// Conditional branches have two candidates (next instruction and branch target), other instructions
+    /// have a single one. Indirect jumps (`jalr`, `c.jr`, `c.jalr`, `mret`) are resolved with the current
+    /// register values. Traps and interrupts taken by the instruction are not considered.
+    ///
+    /// Returns:
+    /// - `Ok([Option<u32>; 2])`: Success, the candidate program counters (first is always set).
+    /// - `Err(Error)`: Failed to fetch the current instruction.
+    pub fn next_pc_candidates(&mut self) -> Result<[Option<u32>; 2], Error> {
+        let pc = self.program_counter;
+        let data = self.fetch()?;
+        let next = pc.wrapping_add(data.size());
+
+        let inst = u32::from(data);
+        let opcode = (inst & 0x1F) as u8;
+
+        let target = if opcode == Branch::opcode() {
+            return Ok([
+                Some(next),
+                Some(pc.wrapping_add_signed(Branch::decode(inst).0.imm)),
+            ]);
+        } else if opcode == CBeqz::opcode() || opcode == CBnez::opcode() {
+            return Ok([
+                Some(next),
+                Some(pc.wrapping_add_signed(CBeqz::decode(inst).0.imm)),
+            ]);
+        } else if opcode == Jal::opcode() {
+            pc.wrapping_add_signed(Jal::decode(inst).0.imm)
+        } else if opcode == CJ::opcode() || opcode == CJal::opcode() {
+            pc.wrapping_add_signed(CJ::decode(inst).0.imm)
+        } else if opcode == Jalr::opcode() {
+            let jalr = Jalr::decode(inst).0;
+            (self.registers.cpu.get(jalr.rs1)? as u32).wrapping_add_signed(jalr.imm)
+        } else if opcode == CJrMv::opcode() || opcode == CEbreakJalrAdd::opcode() {
+            // c.jr / c.jalr (c.ebreak has no source register)
+            let cr = CJrMv::decode(inst).0;
+            if cr.rs2 == 0 && (opcode == CJrMv::opcode() || cr.rd_rs1 != 0) {
+                self.registers.cpu.get(cr.rd_rs1)? as u32
+            } else {
+                next
+            }
+        } else if opcode == SystemMiscMem::opcode() {
+            let system = SystemMiscMem::decode(inst).0;
+            if system.func == SystemMiscMem::MISC_FUNC && system.imm == SystemMiscMem::MRET_IMM {
+                self.registers.control_status.mepc()
+            } else {
+                next
+            }
+        } else {
+            next
+        };
+
+        Ok([Some(target), None])
+    }
+
+    /// Find the start of the instruction containing an address.
+    ///
+    /// Instructions are decoded sequentially from the start of the memory region (code at `0`, or RAM at
+    /// [`RAM_OFFSET`]), so the code is expected to be contiguous (as produced by the transpiler).
+    ///
+    /// Arguments:
+    /// - `address`: Address inside the instruction.
+    ///
+    /// Returns:
+    /// - `Ok(u32)`: Success, start address of the instruction.
+    /// - `Err(Error)`: Address is outside of the memory ([`Error::InvalidProgramCounter`]).
+    pub fn instruction_start(&mut self, address: u32) -> Result<u32, Error> {
+        let mut pc = if address >= RAM_OFFSET { RAM_OFFSET } else { 0 };
+
+        loop {
+            // The instruction size can be found from its first byte
+            let size = self
+                .memory
+                .load_u8(pc)
+                .map(|byte| Instruction::from(byte as u32).size())
+                .map_err(|_| Error::InvalidProgramCounter(address))?;
+
+            let next = pc.wrapping_add(size);
+            if next > address {
+                return Ok(pc);
+            }
+            pc = next;
+        }
+    }
+
+    /// Check if an address is the start of an instruction (e.g. before setting a breakpoint).
+    ///
+    /// Arguments:
+    /// - `address`: Address to check.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Address is the start of an instruction.
+    /// - `Err(Error)`: Address is in the middle of an instruction ([`Error::NotInstructionStart`])
+    ///   or outside of the memory ([`Error::InvalidProgramCounter`]).
+    pub fn check_instruction_start(&mut self, address: u32) -> Result<(), Error> {
+        match self.instruction_start(address)? {
+            start if start == address => Ok(()),
+            start => Err(Error::NotInstructionStart { address, start }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::SliceMemory;
+
+    #[cfg(feature = "transpiler")]
+    use crate::transpiler::transpile_raw;
+
+    #[cfg(feature = "transpiler")]
+    fn code() -> [u8; 24] {
+        let mut code = [
+            0x13, 0x05, 0xa0, 0x00, // li    a0, 10
+            0x01, 0xc5, // c.beqz a0, 8
+            0x63, 0x04, 0xb5, 0x00, // beq   a0, a1, 8
+            0x82, 0x80, // c.jr   ra
+            0xe7, 0x80, 0x45, 0x00, // jalr  ra, 4(a1)
+            0x73, 0x00, 0x20, 0x30, // mret
+            0x02, 0x90, // c.ebreak
+            0x00, 0x00, // padding
+        ];
+        transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_next_pc_candidates() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_ra(0x40);
+        interpreter.registers.cpu.set_a1(0x20);
+
+        let mut candidates = |pc| {
+            interpreter.program_counter = pc;
+            interpreter.next_pc_candidates()
+        };
+
+        assert_eq!(candidates(0), Ok([Some(4), None]));
+        assert_eq!(candidates(4), Ok([Some(6), Some(12)]));
+        assert_eq!(candidates(6), Ok([Some(10), Some(14)]));
+        assert_eq!(candidates(10), Ok([Some(0x40), None]));
+        assert_eq!(candidates(12), Ok([Some(0x24), None]));
+        assert_eq!(candidates(16), Ok([Some(0), None]));
+        assert_eq!(candidates(20), Ok([Some(22), None]));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_instruction_start() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(interpreter.check_instruction_start(4), Ok(()));
+        assert_eq!(interpreter.check_instruction_start(10), Ok(()));
+        assert_eq!(interpreter.instruction_start(13), Ok(12));
+        assert_eq!(
+            interpreter.check_instruction_start(8),
+            Err(Error::NotInstructionStart {
+                address: 8,
+                start: 6
+            })
+        );
+        assert_eq!(
+            interpreter.check_instruction_start(24),
+            Err(Error::InvalidProgramCounter(24))
+        );
+    }
+
+    #[test]
+    fn test_instruction_start_ram() {
+        let mut ram = [0x1F, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let mut memory = SliceMemory::new(&[], &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        // 4-byte instruction, followed by a 2-byte instruction
+        assert_eq!(
+            interpreter.instruction_start(RAM_OFFSET + 2),
+            Ok(RAM_OFFSET)
+        );
+        assert_eq!(interpreter.check_instruction_start(RAM_OFFSET + 4), Ok(()));
+    }
+}
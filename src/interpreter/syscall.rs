@@ -0,0 +1,245 @@
+//! Syscall Module
+#[cfg(feature = "syscall-flood")]
+use super::utils::unlikely;
+use super::{memory::Memory, Error, Interpreter, SYSCALL_ARGS};
+
+/// Number of register arguments (`a0` to `a5`) in the extended syscall convention (check [`extended_syscall_args`]).
+pub const EXTENDED_SYSCALL_REGISTER_ARGS: usize = crate::protocol::EXTENDED_SYSCALL_REGISTER_ARGS;
+
+/// Syscall return value.
+///
+/// Returned to the interpreted code through:
+/// - `a1`: 32-bit values ([`SyscallRet::I32`] and [`SyscallRet::U32`]).
+/// - `a1` (low) and `a2` (high): 64-bit values ([`SyscallRet::I64`] and [`SyscallRet::U64`]).
+///
+/// Syscall functions can return any type that converts into [`SyscallRet`] (e.g. `i32`, `u64`).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyscallRet {
+    /// Signed 32-bit value (`a1`).
+    I32(i32),
+    /// Unsigned 32-bit value (`a1`).
+    U32(u32),
+    /// Signed 64-bit value (`a1` low, `a2` high).
+    I64(i64),
+    /// Unsigned 64-bit value (`a1` low, `a2` high).
+    U64(u64),
+}
+
+impl SyscallRet {
+    /// Get the register values (`a1`, and `a2` for 64-bit values).
+    #[inline(always)]
+    pub(crate) fn registers(self) -> (i32, Option<i32>) {
+        match self {
+            SyscallRet::I32(value) => (value, None),
+            SyscallRet::U32(value) => (value as i32, None),
+            SyscallRet::I64(value) => (value as i32, Some((value >> 32) as i32)),
+            SyscallRet::U64(value) => (value as i32, Some((value >> 32) as i32)),
+        }
+    }
+}
+
+impl From<i32> for SyscallRet {
+    fn from(value: i32) -> Self {
+        SyscallRet::I32(value)
+    }
+}
+
+impl From<u32> for SyscallRet {
+    fn from(value: u32) -> Self {
+        SyscallRet::U32(value)
+    }
+}
+
+impl From<i64> for SyscallRet {
+    fn from(value: i64) -> Self {
+        SyscallRet::I64(value)
+    }
+}
+
+impl From<u64> for SyscallRet {
+    fn from(value: u64) -> Self {
+        SyscallRet::U64(value)
+    }
+}
+
+impl<M: Memory> Interpreter<'_, M> {
+    /// Get the number of syscalls (`ecall`) issued since the last call to [`Interpreter::run`] (`syscall-flood` feature),
+    /// including the ones handled by the interpreter (e.g. [`super::RANDOM_SYSCALL`]).
+    #[cfg(feature = "syscall-flood")]
+    pub fn syscalls_in_run(&self) -> u32 {
+        self.syscalls_in_run
+    }
+
+    /// Get the number of consecutive syscalls issued less than [`super::Config::syscall_min_interval`]
+    /// instructions apart (check [`super::Config::syscall_burst_limit`]).
+    #[cfg(feature = "syscall-flood")]
+    pub fn syscall_burst(&self) -> u32 {
+        self.syscall_burst
+    }
+
+    /// Count a syscall (`ecall`), checking for a syscall flood.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, syscall counted.
+    /// - `Err(Error)`: Syscall burst limit exceeded ([`Error::SyscallFlood`]). The burst is cleared,
+    ///   so running again executes the syscall (after the host throttled the guest).
+    #[cfg(feature = "syscall-flood")]
+    #[inline(always)]
+    pub(crate) fn count_syscall(&mut self) -> Result<(), Error> {
+        self.syscalls_in_run = self.syscalls_in_run.saturating_add(1);
+
+        let limit = self.config.syscall_burst_limit;
+        if unlikely(limit > 0) {
+            let retired = self.registers.control_status.instructions_retired();
+            let interval = retired.wrapping_sub(self.last_syscall);
+            self.last_syscall = retired;
+
+            if interval < self.config.syscall_min_interval as u64 {
+                self.syscall_burst = self.syscall_burst.saturating_add(1);
+            } else {
+                self.syscall_burst = 1;
+            }
+
+            if self.syscall_burst > limit {
+                self.syscall_burst = 0;
+                self.syscalls_in_run -= 1;
+                return Err(Error::SyscallFlood(self.program_counter));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the arguments of a syscall with more than [`SYSCALL_ARGS`] arguments (extended convention).
+///
+/// The first [`EXTENDED_SYSCALL_REGISTER_ARGS`] arguments are passed in `a0` to `a5`, and the remaining ones
+/// are spilled to a guest memory block (array of 32-bit words), whose address is passed in `a6`.
+/// Syscalls with up to [`SYSCALL_ARGS`] arguments are read from the registers only (regular convention).
+///
+/// Guest side (Rust):
+/// ```ignore
+/// // 9 arguments: a0 to a5, and a block with the remaining 3
+/// let extra: [i32; 3] = [arg6, arg7, arg8];
+/// syscall(nr, [arg0, arg1, arg2, arg3, arg4, arg5, extra.as_ptr() as i32]);
+/// ```
+///
+/// Arguments:
+/// - `args`: Syscall arguments, as received by the syscall function (`a0` to `a6`).
+/// - `memory`: Guest memory, as received by the syscall function.
+///
+/// Returns:
+/// - `Ok([i32; N])`: Success, all `N` arguments.
+/// - `Err(Error)`: The memory block (`a6`) is out of bounds.
+///
+/// Example:
+/// ```
+/// use core::num::NonZeroI32;
+/// use embive::interpreter::{extended_syscall_args, memory::Memory, Error, SYSCALL_ARGS};
+///
+/// fn syscall<M: Memory>(nr: i32, args: &[i32; SYSCALL_ARGS], memory: &mut M) -> Result<Result<i32, NonZeroI32>, Error> {
+///     Ok(match nr {
+///         // Sum of 9 arguments
+///         1 => Ok(extended_syscall_args::<_, 9>(args, memory)?.iter().sum()),
+///         _ => Err(NonZeroI32::new(1).unwrap()),
+///     })
+/// }
+/// ```
+pub fn extended_syscall_args<M: Memory, const N: usize>(
+    args: &[i32; SYSCALL_ARGS],
+    memory: &mut M,
+) -> Result<[i32; N], Error> {
+    let mut values = [0; N];
+
+    if N <= SYSCALL_ARGS {
+        values.copy_from_slice(&args[..N]);
+        return Ok(values);
+    }
+
+    values[..EXTENDED_SYSCALL_REGISTER_ARGS]
+        .copy_from_slice(&args[..EXTENDED_SYSCALL_REGISTER_ARGS]);
+
+    // Remaining arguments are in the memory block
+    let block = args[EXTENDED_SYSCALL_REGISTER_ARGS] as u32;
+    for (i, value) in values[EXTENDED_SYSCALL_REGISTER_ARGS..]
+        .iter_mut()
+        .enumerate()
+    {
+        *value = memory.load_u32(block.wrapping_add(i as u32 * 4))? as i32;
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::{SliceMemory, RAM_OFFSET};
+
+    #[cfg(all(feature = "transpiler", feature = "syscall-flood"))]
+    use crate::interpreter::{Config, State};
+
+    #[test]
+    fn test_registers() {
+        assert_eq!(SyscallRet::from(-1i32).registers(), (-1, None));
+        assert_eq!(SyscallRet::from(u32::MAX).registers(), (-1, None));
+        assert_eq!(
+            SyscallRet::from(0x1234_5678_9abc_def0u64).registers(),
+            (0x9abc_def0u32 as i32, Some(0x1234_5678))
+        );
+        assert_eq!(SyscallRet::from(-2i64).registers(), (-2, Some(-1)));
+    }
+
+    #[test]
+    fn test_extended_syscall_args() {
+        let mut ram = [0; 12];
+        ram[..4].copy_from_slice(&7i32.to_le_bytes());
+        ram[4..8].copy_from_slice(&8i32.to_le_bytes());
+        ram[8..].copy_from_slice(&(-9i32).to_le_bytes());
+        let mut memory = SliceMemory::new(&[], &mut ram);
+
+        let args = [1, 2, 3, 4, 5, 6, RAM_OFFSET as i32];
+        assert_eq!(
+            extended_syscall_args::<_, 9>(&args, &mut memory),
+            Ok([1, 2, 3, 4, 5, 6, 7, 8, -9])
+        );
+        // Regular convention
+        assert_eq!(
+            extended_syscall_args::<_, 3>(&args, &mut memory),
+            Ok([1, 2, 3])
+        );
+        // Block out of bounds
+        assert!(extended_syscall_args::<_, 10>(&args, &mut memory).is_err());
+    }
+
+    #[cfg(all(feature = "transpiler", feature = "syscall-flood"))]
+    #[test]
+    fn test_syscall_flood() {
+        let mut code = [
+            0x93, 0x08, 0xc0, 0xff, // li   a7, -4 (random)
+            0x93, 0x05, 0x00, 0x00, // li   a1, 0
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x6f, 0xf0, 0xdf, 0xff, // j    -4
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_syscall_flood_limit(10, 4);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+
+        assert_eq!(interpreter.run(), Err(Error::SyscallFlood(8)));
+        assert_eq!(interpreter.syscalls_in_run(), 10);
+        assert_eq!(interpreter.syscall_burst(), 0);
+
+        // Running again executes the syscall (e.g. after throttling the guest)
+        interpreter.set_instruction_limit(3);
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.syscalls_in_run(), 2);
+        assert_eq!(interpreter.syscall_burst(), 2);
+
+        // Syscalls far enough apart are not a burst
+        interpreter.set_config(Config::default().with_syscall_flood_limit(1, 2));
+        interpreter.set_instruction_limit(100);
+        assert_eq!(interpreter.run(), Ok(State::Running));
+        assert_eq!(interpreter.syscalls_in_run(), 50);
+    }
+}
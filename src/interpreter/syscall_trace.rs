@@ -0,0 +1,459 @@
+//! Syscall Trace Module
+//!
+//! Opt-in syscall tracing, to debug the guest/host interaction without instrumenting the syscall handler.
+//! [`SyscallTracer`] wraps [`Interpreter::syscall`], recording every syscall ([`SyscallRecord`]) and writing it to
+//! a user-supplied [`core::fmt::Write`] sink, in a `strace`-like format:
+//!
+//! ```text
+//! [120] write(1, "hello\n", 6) = 6
+//! [152] read(0, "abc", 64) = 3 <10>
+//! [170] syscall_7(1, 2, 0, 0, 0, 0, 0) = -1 (error 38)
+//! ```
+//!
+//! Lines start with the instructions retired when the syscall was made, and end with the instructions charged by
+//! the handler (check [`Interpreter::syscall_with_cost`]), if any. Arguments are decoded with the registered
+//! signatures ([`SyscallSignature`]), guest buffers being printed as escaped strings.
+//! Reserved syscalls ([`crate::protocol::SYSCALLS`]) are named, other syscalls are printed with all their arguments.
+use core::fmt::{self, Display, Formatter, Write};
+use core::num::NonZeroI32;
+
+use super::{memory::Memory, Error, Interpreter, SyscallRet, SYSCALL_ARGS};
+use crate::protocol::{self, UART_READ, UART_WRITE};
+
+/// Default maximum number of buffer bytes printed (check [`SyscallTracer::max_buffer_len`]).
+const MAX_BUFFER_LEN: usize = 32;
+
+/// Syscall argument format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgFormat {
+    /// Signed decimal integer.
+    Int,
+    /// Hexadecimal (e.g. addresses, flags).
+    Hex,
+    /// Guest buffer address, read by the syscall. Its length is the argument at the given index.
+    Buffer(usize),
+    /// Guest buffer address, written by the syscall. Its length is the argument at the given index,
+    /// limited to the returned value (e.g. bytes read).
+    OutBuffer(usize),
+}
+
+/// Syscall signature, used to decode the arguments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyscallSignature {
+    /// Syscall number (`a7`).
+    pub number: i32,
+    /// Syscall name.
+    pub name: &'static str,
+    /// Argument formats (`a0` onwards).
+    pub args: &'static [ArgFormat],
+}
+
+/// Signatures of the Linux stdio syscalls (`read`, `write`, `exit`) and of the UART syscalls
+/// (check [`crate::protocol::UART_WRITE`]).
+pub const STDIO_SIGNATURES: [SyscallSignature; 5] = [
+    SyscallSignature {
+        number: 63,
+        name: "read",
+        args: &[ArgFormat::Int, ArgFormat::OutBuffer(2), ArgFormat::Int],
+    },
+    SyscallSignature {
+        number: 64,
+        name: "write",
+        args: &[ArgFormat::Int, ArgFormat::Buffer(2), ArgFormat::Int],
+    },
+    SyscallSignature {
+        number: 93,
+        name: "exit",
+        args: &[ArgFormat::Int],
+    },
+    SyscallSignature {
+        number: UART_WRITE,
+        name: "uart_write",
+        args: &[ArgFormat::Buffer(1), ArgFormat::Int],
+    },
+    SyscallSignature {
+        number: UART_READ,
+        name: "uart_read",
+        args: &[ArgFormat::OutBuffer(1), ArgFormat::Int],
+    },
+];
+
+/// Syscall record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyscallRecord {
+    /// Syscall number (`a7`).
+    pub number: i32,
+    /// Arguments (`a0` to `a6`).
+    pub args: [i32; SYSCALL_ARGS],
+    /// Instructions retired when the syscall was made.
+    pub timestamp: u64,
+    /// Syscall result, returned to the interpreted code.
+    pub result: Result<SyscallRet, NonZeroI32>,
+    /// Instructions charged by the syscall handler (check [`Interpreter::syscall_with_cost`]).
+    pub cost: u64,
+}
+
+/// Syscall Trace Error
+#[derive(Debug, PartialEq)]
+pub enum SyscallTraceError<E> {
+    /// No syscall is pending.
+    Interpreter(Error),
+    /// The syscall handler failed.
+    Handler(E),
+    /// Failed to write to the trace sink.
+    Sink,
+}
+
+impl<E: fmt::Debug> core::error::Error for SyscallTraceError<E> {}
+
+impl<E: fmt::Debug> Display for SyscallTraceError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Embive Syscall Tracer
+///
+/// Handles syscalls through [`Interpreter::syscall`], writing each one to a sink (check [`crate::interpreter::syscall_trace`]).
+///
+/// Example:
+/// ```
+/// use core::num::NonZeroI32;
+/// use embive::interpreter::{
+///     memory::SliceMemory, syscall_trace::SyscallTracer, Error, Interpreter, State, SYSCALL_ARGS,
+/// };
+///
+/// // Code: ecall, ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x10, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+/// interpreter.registers.cpu.set_a7(93); // exit(3)
+/// interpreter.registers.cpu.set_a0(3);
+/// assert_eq!(interpreter.run(), Ok(State::Called));
+///
+/// let mut output = String::new();
+/// let mut syscall = |_nr: i32, _args: &[i32; SYSCALL_ARGS], _memory: &mut SliceMemory<'_>| {
+///     Ok::<Result<i32, NonZeroI32>, Error>(Ok(0))
+/// };
+/// SyscallTracer::new(&mut output).syscall(&mut interpreter, &mut syscall).unwrap();
+/// assert_eq!(output, "[1] exit(3) = 0\n");
+/// ```
+#[derive(Debug)]
+pub struct SyscallTracer<'t, W: Write> {
+    sink: &'t mut W,
+    signatures: &'t [SyscallSignature],
+    max_buffer_len: usize,
+}
+
+impl<'t, W: Write> SyscallTracer<'t, W> {
+    /// Create a new syscall tracer, with the [`STDIO_SIGNATURES`].
+    ///
+    /// Arguments:
+    /// - `sink`: Trace output sink.
+    pub fn new(sink: &'t mut W) -> Self {
+        SyscallTracer {
+            sink,
+            signatures: &STDIO_SIGNATURES,
+            max_buffer_len: MAX_BUFFER_LEN,
+        }
+    }
+
+    /// Set the syscall signatures, used to decode the arguments (e.g. host-defined syscalls).
+    pub fn signatures(mut self, signatures: &'t [SyscallSignature]) -> Self {
+        self.signatures = signatures;
+        self
+    }
+
+    /// Set the maximum number of buffer bytes printed (longer buffers are truncated, followed by `...`).
+    pub fn max_buffer_len(mut self, len: usize) -> Self {
+        self.max_buffer_len = len;
+        self
+    }
+
+    /// Handle the pending syscall (check [`Interpreter::syscall`]), tracing it.
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter, stopped with [`super::State::Called`].
+    /// - `function`: System call function (check [`Interpreter::syscall`]).
+    ///
+    /// Returns:
+    /// - `Ok(SyscallRecord)`: Success, syscall record.
+    /// - `Err(SyscallTraceError)`: No syscall pending, handler error or failed to write the trace.
+    pub fn syscall<M, F, E, R>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        function: &mut F,
+    ) -> Result<SyscallRecord, SyscallTraceError<E>>
+    where
+        M: Memory,
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<Result<R, NonZeroI32>, E>,
+        R: Into<SyscallRet>,
+    {
+        self.syscall_with_cost(interpreter, &mut |nr, args, memory| {
+            function(nr, args, memory).map(|result| (result, 0))
+        })
+    }
+
+    /// Handle the pending syscall, consuming instruction budget (check [`Interpreter::syscall_with_cost`]), tracing it.
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter, stopped with [`super::State::Called`].
+    /// - `function`: System call function (check [`Interpreter::syscall_with_cost`]).
+    ///
+    /// Returns:
+    /// - `Ok(SyscallRecord)`: Success, syscall record.
+    /// - `Err(SyscallTraceError)`: No syscall pending, handler error or failed to write the trace.
+    pub fn syscall_with_cost<M, F, E, R>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        function: &mut F,
+    ) -> Result<SyscallRecord, SyscallTraceError<E>>
+    where
+        M: Memory,
+        F: FnMut(i32, &[i32; SYSCALL_ARGS], &mut M) -> Result<(Result<R, NonZeroI32>, u32), E>,
+        R: Into<SyscallRet>,
+    {
+        let (number, args) = interpreter
+            .pending_syscall()
+            .ok_or(SyscallTraceError::Interpreter(Error::NoPendingSyscall))?;
+        let timestamp = interpreter.registers.control_status.instructions_retired();
+
+        let mut record = None;
+        interpreter
+            .syscall_with_cost(&mut |nr, args, memory| {
+                let (result, cost) = function(nr, args, memory)?;
+                let result = result.map(Into::into);
+                record = Some((result, cost));
+                Ok((result, cost))
+            })
+            .map_err(SyscallTraceError::Handler)?;
+
+        let Some((result, cost)) = record else {
+            return Err(SyscallTraceError::Interpreter(Error::NoPendingSyscall));
+        };
+        let record = SyscallRecord {
+            number,
+            args,
+            timestamp,
+            result,
+            cost: cost as u64,
+        };
+        self.write(&record, interpreter.memory)
+            .map_err(|_| SyscallTraceError::Sink)?;
+        Ok(record)
+    }
+
+    /// Write a syscall record to the sink.
+    ///
+    /// Arguments:
+    /// - `record`: Syscall record.
+    /// - `memory`: Guest memory, to decode buffers.
+    pub fn write<M: Memory>(&mut self, record: &SyscallRecord, memory: &mut M) -> fmt::Result {
+        let signature = self
+            .signatures
+            .iter()
+            .find(|signature| signature.number == record.number);
+        let (name, count) = match (signature, protocol::syscall_spec(record.number)) {
+            (Some(signature), _) => (signature.name, signature.args.len()),
+            (None, Some(spec)) => (spec.name, spec.args.len()),
+            (None, None) => ("", SYSCALL_ARGS),
+        };
+
+        write!(self.sink, "[{}] ", record.timestamp)?;
+        match name {
+            "" => write!(self.sink, "syscall_{}(", record.number)?,
+            name => write!(self.sink, "{name}(")?,
+        }
+        for (i, &arg) in record.args.iter().take(count).enumerate() {
+            if i > 0 {
+                self.sink.write_str(", ")?;
+            }
+            let format = signature
+                .and_then(|signature| signature.args.get(i).copied())
+                .unwrap_or(ArgFormat::Int);
+            self.write_arg(format, arg, record, memory)?;
+        }
+        self.sink.write_str(") = ")?;
+
+        match record.result {
+            Ok(SyscallRet::I32(value)) => write!(self.sink, "{value}")?,
+            Ok(SyscallRet::U32(value)) => write!(self.sink, "{value}")?,
+            Ok(SyscallRet::I64(value)) => write!(self.sink, "{value}")?,
+            Ok(SyscallRet::U64(value)) => write!(self.sink, "{value}")?,
+            Err(code) => write!(self.sink, "-1 (error {code})")?,
+        }
+        if record.cost > 0 {
+            write!(self.sink, " <{}>", record.cost)?;
+        }
+        self.sink.write_char('\n')
+    }
+
+    /// Write a syscall argument.
+    fn write_arg<M: Memory>(
+        &mut self,
+        format: ArgFormat,
+        arg: i32,
+        record: &SyscallRecord,
+        memory: &mut M,
+    ) -> fmt::Result {
+        let len = |index: usize| record.args.get(index).map_or(0, |&len| len as u32 as usize);
+        let len = match format {
+            ArgFormat::Int => return write!(self.sink, "{arg}"),
+            ArgFormat::Hex => return write!(self.sink, "{:#x}", arg as u32),
+            ArgFormat::Buffer(index) => len(index),
+            ArgFormat::OutBuffer(index) => match record.result {
+                Ok(result) => len(index).min(result.registers().0.max(0) as usize),
+                Err(_) => 0,
+            },
+        };
+
+        let shown = len.min(self.max_buffer_len);
+        match memory.load_bytes(arg as u32, shown) {
+            Ok(bytes) => {
+                self.sink.write_char('"')?;
+                for &byte in bytes {
+                    match byte {
+                        b'\n' => self.sink.write_str("\\n")?,
+                        b'\r' => self.sink.write_str("\\r")?,
+                        b'\t' => self.sink.write_str("\\t")?,
+                        b'"' => self.sink.write_str("\\\"")?,
+                        b'\\' => self.sink.write_str("\\\\")?,
+                        0x20..=0x7E => self.sink.write_char(byte as char)?,
+                        _ => write!(self.sink, "\\x{byte:02x}")?,
+                    }
+                }
+                self.sink.write_char('"')?;
+                if shown < len {
+                    self.sink.write_str("...")?;
+                }
+                Ok(())
+            }
+            // Invalid buffer, print its address
+            Err(_) => write!(self.sink, "{:#x}", arg as u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        State, LOG_SYSCALL,
+    };
+
+    /// Code: ecall, c.j -2 (already transpiled)
+    const CODE: [u8; 8] = [0x1f, 0x00, 0x00, 0x00, 0xcf, 0xff, 0x00, 0x00];
+
+    /// Trace a syscall made with `args` (`a7`, then `a0` onwards), returning the trace output.
+    fn trace<F>(ram: &mut [u8], number: i32, args: &[i32], function: &mut F) -> String
+    where
+        F: FnMut(
+            i32,
+            &[i32; SYSCALL_ARGS],
+            &mut SliceMemory<'_>,
+        ) -> Result<(Result<i32, NonZeroI32>, u32), ()>,
+    {
+        let mut memory = SliceMemory::new(&CODE, ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_a7(number);
+        for (i, &arg) in args.iter().enumerate() {
+            interpreter.registers.cpu.set(10 + i as u8, arg).unwrap();
+        }
+        assert_eq!(interpreter.run(), Ok(State::Called));
+
+        let mut output = String::new();
+        let signatures = [
+            STDIO_SIGNATURES[0],
+            STDIO_SIGNATURES[1],
+            SyscallSignature {
+                number: 1,
+                name: "ioctl",
+                args: &[ArgFormat::Hex, ArgFormat::Buffer(9)],
+            },
+        ];
+        let mut tracer = SyscallTracer::new(&mut output)
+            .signatures(&signatures)
+            .max_buffer_len(8);
+        let record = tracer
+            .syscall_with_cost(&mut interpreter, function)
+            .unwrap();
+        assert_eq!(record.number, number);
+        assert_eq!(record.timestamp, 1);
+        output
+    }
+
+    #[test]
+    fn test_trace_buffers() {
+        let mut ram = *b"hi\n\"\x01 long buffer";
+        let address = RAM_OFFSET as i32;
+        let output = trace(&mut ram, 64, &[1, address, 5], &mut |_, _, _| {
+            Ok((Ok(5), 0))
+        });
+        assert_eq!(output, "[1] write(1, \"hi\\n\\\"\\x01\", 5) = 5\n");
+
+        // Truncated, charged
+        let output = trace(&mut ram, 64, &[1, address, 16], &mut |_, _, _| {
+            Ok((Ok(16), 3))
+        });
+        assert_eq!(
+            output,
+            "[1] write(1, \"hi\\n\\\"\\x01 lo\"..., 16) = 16 <3>\n"
+        );
+
+        // Buffer written by the syscall, limited to the result
+        let output = trace(&mut ram, 63, &[0, address, 64], &mut |_, _, memory| {
+            memory.store_bytes(RAM_OFFSET, b"abc").unwrap();
+            Ok((Ok(3), 0))
+        });
+        assert_eq!(output, "[1] read(0, \"abc\", 64) = 3\n");
+
+        // Invalid buffer
+        let output = trace(&mut ram, 64, &[1, 0x100, 4], &mut |_, _, _| Ok((Ok(4), 0)));
+        assert_eq!(output, "[1] write(1, 0x100, 4) = 4\n");
+
+        // Length argument out of range
+        let output = trace(&mut ram, 1, &[-1, address], &mut |_, _, _| Ok((Ok(0), 0)));
+        assert_eq!(output, "[1] ioctl(0xffffffff, \"\") = 0\n");
+    }
+
+    #[test]
+    fn test_trace_names() {
+        let mut ram = [0; 4];
+        let output = trace(&mut ram, 7, &[1, 2], &mut |_, _, _| {
+            Ok((Err(NonZeroI32::new(38).unwrap()), 0))
+        });
+        assert_eq!(
+            output,
+            "[1] syscall_7(1, 2, 0, 0, 0, 0, 0) = -1 (error 38)\n"
+        );
+
+        let output = trace(&mut ram, LOG_SYSCALL, &[4, 2], &mut |_, _, _| {
+            Ok((Ok(0), 0))
+        });
+        assert_eq!(output, "[1] log(4, 2) = 0\n");
+    }
+
+    #[test]
+    fn test_trace_errors() {
+        let mut ram = [0; 4];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let mut output = String::new();
+        let mut tracer = SyscallTracer::new(&mut output);
+        let mut function = |_: i32, _: &[i32; SYSCALL_ARGS], _: &mut SliceMemory<'_>| {
+            Err::<Result<i32, NonZeroI32>, _>(())
+        };
+        assert_eq!(
+            tracer.syscall(&mut interpreter, &mut function),
+            Err(SyscallTraceError::Interpreter(Error::NoPendingSyscall))
+        );
+
+        assert_eq!(interpreter.run(), Ok(State::Called));
+        assert_eq!(
+            tracer.syscall(&mut interpreter, &mut function),
+            Err(SyscallTraceError::Handler(()))
+        );
+        assert!(output.is_empty());
+    }
+}
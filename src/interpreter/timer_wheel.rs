@@ -0,0 +1,347 @@
+//! Timer Wheel Module
+//!
+//! Host-side timers measured in instructions retired (check
+//! [`super::registers::CSRegisters::instructions_retired`]), injecting interrupts at exact points of the guest
+//! execution. Unlike wall-clock timers, runs are reproducible: the same program, with the same timers,
+//! always gets its interrupts at the same instructions (useful to test guest schedulers and timeouts).
+//!
+//! Expired timers are queued with [`Interpreter::queue_interrupt`], so they are delivered as soon as the
+//! guest enables interrupts (`interrupt-queue` feature).
+use super::{memory::Memory, Error, Interpreter, State};
+
+/// A scheduled timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timer {
+    /// Instructions retired when the timer expires.
+    pub deadline: u64,
+    /// Period, in instructions, of a periodic timer (`0` for one-shot timers).
+    pub period: u64,
+    /// Value passed to the interrupt handler (`mtval`).
+    pub value: i32,
+    /// Interrupt priority (check [`Interpreter::queue_interrupt`]).
+    pub priority: u8,
+}
+
+/// Embive Timer Wheel
+///
+/// Holds up to `N` timers and wraps [`Interpreter::run`], stopping the interpreter exactly at each deadline
+/// (by temporarily lowering [`Interpreter::instruction_limit`]) to queue the timer interrupt:
+/// - [`Interpreter::instruction_limit`] is still honored: [`State::Running`] is returned once the
+///   interpreter executed that many instructions, as with [`Interpreter::run`].
+/// - [`State::Waiting`]: no instructions retire while the guest waits for an interrupt, so the next timers
+///   are fired right away (skipping the idle time) and the state is returned. Running again delivers them.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::SliceMemory, timer_wheel::TimerWheel, Interpreter};
+///
+/// // Code: c.j 0 (loop), c.nop (already transpiled)
+/// let code = [0x0f, 0x00, 0x03, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 100);
+///
+/// // Tick every 10 instructions
+/// let mut timers = TimerWheel::<4>::new();
+/// timers.schedule_periodic(10, 10, 1, 0).unwrap();
+///
+/// // Guest doesn't enable interrupts, ticks stay queued (coalesced)
+/// timers.run(&mut interpreter).unwrap();
+/// assert_eq!(interpreter.registers.control_status.instructions_retired(), 100);
+/// assert!(interpreter.queued_interrupts().eq([(1, 0)]));
+/// assert_eq!(timers.next_deadline(), Some(110));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerWheel<const N: usize> {
+    timers: [Option<Timer>; N],
+}
+
+impl<const N: usize> Default for TimerWheel<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> TimerWheel<N> {
+    /// Create a new timer wheel, without timers.
+    pub const fn new() -> Self {
+        TimerWheel { timers: [None; N] }
+    }
+
+    /// Add a timer.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, timer scheduled.
+    /// - `Err(Error)`: All `N` timers are in use ([`Error::TimerWheelFull`]).
+    fn insert(&mut self, timer: Timer) -> Result<(), Error> {
+        let slot = self
+            .timers
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Error::TimerWheelFull)?;
+        *slot = Some(timer);
+        Ok(())
+    }
+
+    /// Schedule a one-shot timer.
+    ///
+    /// Arguments:
+    /// - `deadline`: Instructions retired when the timer expires (already expired timers fire on the next run).
+    /// - `value`: Value passed to the interrupt handler (`mtval`).
+    /// - `priority`: Interrupt priority (check [`Interpreter::queue_interrupt`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, timer scheduled.
+    /// - `Err(Error)`: All `N` timers are in use ([`Error::TimerWheelFull`]).
+    pub fn schedule(&mut self, deadline: u64, value: i32, priority: u8) -> Result<(), Error> {
+        self.insert(Timer {
+            deadline,
+            period: 0,
+            value,
+            priority,
+        })
+    }
+
+    /// Schedule a periodic timer.
+    ///
+    /// Arguments:
+    /// - `deadline`: Instructions retired when the timer first expires.
+    /// - `period`: Instructions between expirations (`0` for a one-shot timer).
+    /// - `value`: Value passed to the interrupt handler (`mtval`).
+    /// - `priority`: Interrupt priority (check [`Interpreter::queue_interrupt`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, timer scheduled.
+    /// - `Err(Error)`: All `N` timers are in use ([`Error::TimerWheelFull`]).
+    pub fn schedule_periodic(
+        &mut self,
+        deadline: u64,
+        period: u64,
+        value: i32,
+        priority: u8,
+    ) -> Result<(), Error> {
+        self.insert(Timer {
+            deadline,
+            period,
+            value,
+            priority,
+        })
+    }
+
+    /// Schedule a one-shot timer relative to the current instruction count.
+    ///
+    /// Arguments:
+    /// - `interpreter`: Interpreter whose instructions retired are counted.
+    /// - `delay`: Instructions until the timer expires.
+    /// - `value`: Value passed to the interrupt handler (`mtval`).
+    /// - `priority`: Interrupt priority (check [`Interpreter::queue_interrupt`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, timer scheduled.
+    /// - `Err(Error)`: All `N` timers are in use ([`Error::TimerWheelFull`]).
+    pub fn schedule_in<M: Memory>(
+        &mut self,
+        interpreter: &Interpreter<'_, M>,
+        delay: u64,
+        value: i32,
+        priority: u8,
+    ) -> Result<(), Error> {
+        let now = interpreter.registers.control_status.instructions_retired();
+        self.schedule(now.saturating_add(delay), value, priority)
+    }
+
+    /// Cancel all timers with the given interrupt value.
+    ///
+    /// Returns:
+    /// - `bool`: Whether a timer was cancelled.
+    pub fn cancel(&mut self, value: i32) -> bool {
+        let mut cancelled = false;
+        for slot in self.timers.iter_mut() {
+            if slot.is_some_and(|timer| timer.value == value) {
+                *slot = None;
+                cancelled = true;
+            }
+        }
+        cancelled
+    }
+
+    /// Cancel all timers.
+    pub fn clear(&mut self) {
+        self.timers = [None; N];
+    }
+
+    /// Get the scheduled timers (in no particular order).
+    pub fn timers(&self) -> impl Iterator<Item = &Timer> + '_ {
+        self.timers.iter().flatten()
+    }
+
+    /// Get the earliest deadline, if any timer is scheduled.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.timers().map(|timer| timer.deadline).min()
+    }
+
+    /// Queue the interrupts of the timers expired at `now`, rescheduling periodic timers.
+    fn fire<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        now: u64,
+    ) -> Result<(), Error> {
+        for slot in self.timers.iter_mut() {
+            let Some(timer) = slot else {
+                continue;
+            };
+            if timer.deadline > now {
+                continue;
+            }
+
+            interpreter.queue_interrupt(timer.value, timer.priority)?;
+            match (now - timer.deadline).checked_div(timer.period) {
+                // Missed periods are coalesced
+                Some(missed) => {
+                    timer.deadline = timer
+                        .deadline
+                        .saturating_add(timer.period.saturating_mul(missed + 1))
+                }
+                // One-shot
+                None => *slot = None,
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue the interrupts of the expired timers (check [`Interpreter::queue_interrupt`]).
+    /// Only needed when running the interpreter without [`TimerWheel::run`].
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, expired timers queued (or none expired).
+    /// - `Err(Error)`: The interrupt queue is full ([`Error::InterruptQueueFull`]), timers are kept.
+    pub fn poll<M: Memory>(&mut self, interpreter: &mut Interpreter<'_, M>) -> Result<(), Error> {
+        let now = interpreter.registers.control_status.instructions_retired();
+        self.fire(interpreter, now)
+    }
+
+    /// Run the interpreter (check [`Interpreter::run`]), injecting timer interrupts at their deadlines.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(Error)`: Failed to execute, or the interrupt queue is full ([`Error::InterruptQueueFull`]).
+    pub fn run<M: Memory>(&mut self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
+        let limit = interpreter.instruction_limit;
+        let start = interpreter.registers.control_status.instructions_retired();
+
+        loop {
+            self.poll(interpreter)?;
+
+            let now = interpreter.registers.control_status.instructions_retired();
+            let host_left = match limit {
+                0 => u64::MAX,
+                limit => (limit as u64).saturating_sub(now - start),
+            };
+            if host_left == 0 {
+                // Instruction limit reached (still running)
+                return Ok(State::Running);
+            }
+
+            // Stop at the next deadline (already expired timers were fired)
+            let gap = self
+                .next_deadline()
+                .map_or(u64::MAX, |deadline| deadline - now);
+            let capped = gap.min(host_left);
+            interpreter.instruction_limit = match capped {
+                u64::MAX => limit,
+                capped => capped.min(u32::MAX as u64) as u32,
+            };
+            let state = interpreter.run();
+            interpreter.instruction_limit = limit;
+
+            match state? {
+                // Deadline or instruction limit reached
+                State::Running => continue,
+                // Skip the idle time, firing the next timers
+                State::Waiting => {
+                    if let Some(deadline) = self.next_deadline() {
+                        self.fire(interpreter, deadline)?;
+                    }
+                    return Ok(State::Waiting);
+                }
+                state => return Ok(state),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "transpiler")]
+    use crate::interpreter::{
+        memory::SliceMemory,
+        registers::{CPURegister, CSOperation},
+    };
+
+    #[test]
+    fn test_schedule() {
+        let mut timers = TimerWheel::<2>::new();
+        timers.schedule(30, 1, 0).unwrap();
+        timers.schedule_periodic(20, 5, 2, 1).unwrap();
+        assert_eq!(timers.schedule(10, 3, 0), Err(Error::TimerWheelFull));
+        assert_eq!(timers.next_deadline(), Some(20));
+        assert_eq!(timers.timers().count(), 2);
+
+        assert!(timers.cancel(2));
+        assert!(!timers.cancel(2));
+        assert_eq!(timers.next_deadline(), Some(30));
+
+        timers.clear();
+        assert_eq!(timers.next_deadline(), None);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_run() {
+        let mut code = [
+            0x93, 0x00, 0x80, 0x00, // li   ra, 8
+            0x73, 0x90, 0x00, 0x30, // csrw mstatus, ra (MIE)
+            0x93, 0x00, 0x00, 0x80, // li   ra, -2048
+            0x73, 0x90, 0x40, 0x30, // csrw mie, ra (Embive interrupt)
+            0x6f, 0x00, 0x00, 0x00, // j    . (loop)
+            0x13, 0x06, 0x16, 0x00, // addi a2, a2, 1 (handler)
+            0x73, 0x00, 0x20, 0x30, // mret
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 50);
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0x14)), 0x305)
+            .unwrap();
+
+        let mut timers = TimerWheel::<2>::new();
+        timers.schedule(7, 1, 0).unwrap();
+        timers.schedule_periodic(20, 10, 2, 0).unwrap();
+
+        // Host instruction limit is still honored
+        assert_eq!(timers.run(&mut interpreter), Ok(State::Running));
+        let control_status = &mut interpreter.registers.control_status;
+        assert_eq!(control_status.instructions_retired(), 50);
+        assert_eq!(control_status.operation(None, 0x343), Ok(2));
+
+        // Interrupts at 7, 20, 30 and 40 were handled, 50 is queued
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A2), Ok(4));
+        assert!(interpreter.queued_interrupts().eq([(2, 0)]));
+        assert_eq!(timers.next_deadline(), Some(60));
+
+        // Same instructions, same interrupts
+        interpreter.reset();
+        interpreter
+            .registers
+            .control_status
+            .operation(Some(CSOperation::Write(0x14)), 0x305)
+            .unwrap();
+        let mut timers = TimerWheel::<2>::new();
+        timers.schedule(7, 1, 0).unwrap();
+        timers.schedule_periodic(20, 10, 2, 0).unwrap();
+        assert_eq!(timers.run(&mut interpreter), Ok(State::Running));
+        assert_eq!(interpreter.registers.cpu.get(CPURegister::A2), Ok(4));
+    }
+}
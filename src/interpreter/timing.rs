@@ -0,0 +1,358 @@
+//! Timing Model Module
+//!
+//! Approximate the timing of a real core (e.g. multi-cycle division, memory wait states), for more realistic
+//! simulations (`timing` feature, so the default interpreter loop doesn't pay for it). A [`TimingModel`] set with [`Interpreter::set_timing_model`] is consulted for every retired
+//! instruction, returning its cost in cycles. Cycles drive the virtual time (`time` CSR, [`Interpreter::time`],
+//! check [`super::Config::instructions_per_tick`]) instead of the instruction count.
+//!
+//! Memories can add stall cycles on top of the instruction cost (e.g. cache misses, check
+//! [`super::cache::CacheMemory`] and [`super::memory::Memory::take_stall_cycles`]), with or without a timing model.
+//!
+//! Without a timing model, every instruction costs 1 cycle. Instruction limits ([`Interpreter::run`]) and
+//! `instret` still count instructions. Instructions consumed by the host ([`Interpreter::consume_instructions`])
+//! cost 1 cycle each.
+//!
+//! Example:
+//! ```
+//! use embive::interpreter::{
+//!     memory::SliceMemory,
+//!     timing::{CostTable, InstructionKind},
+//!     Config, ExitReason, Interpreter, State,
+//! };
+//!
+//! // Code: div a0, a0, a1; ebreak (already transpiled)
+//! let code = [0x1e, 0x07, 0x94, 0x5a, 0x1f, 0x00, 0x10, 0x00];
+//! let mut memory = SliceMemory::new(&code, &mut []);
+//! let config = Config::default().with_instructions_per_tick(1);
+//! let mut costs = CostTable::new().with_cost(InstructionKind::Divide, 34);
+//! let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+//! interpreter.set_timing_model(Some(&mut costs));
+//!
+//! assert_eq!(interpreter.run(), Ok(State::Halted { reason: ExitReason::Ebreak }));
+//! assert_eq!(interpreter.time(), Some(35));
+//! ```
+use super::{
+    decode_execute::{data_address, instruction_kind},
+    memory::Memory,
+    Interpreter,
+};
+use crate::instruction::Instruction;
+
+/// Number of instruction kinds (check [`InstructionKind`]).
+pub const INSTRUCTION_KINDS: usize = 9;
+
+/// Instruction kind, for timing purposes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InstructionKind {
+    /// Integer computation (`add`, `addi`, `lui`, `c.mv`, ...).
+    Alu = 0,
+    /// Loads (`lw`, `c.lwsp`, ...).
+    Load = 1,
+    /// Stores (`sw`, `c.swsp`, ...).
+    Store = 2,
+    /// Conditional branches (`beq`, `c.beqz`, ...).
+    Branch = 3,
+    /// Jumps (`jal`, `jalr`, `c.j`, `c.jr`, ...).
+    Jump = 4,
+    /// Multiplications (`mul`, `mulh`, `mulhsu`, `mulhu`).
+    Multiply = 5,
+    /// Divisions and remainders (`div`, `divu`, `rem`, `remu`).
+    Divide = 6,
+    /// Atomics (`lr.w`, `sc.w`, AMOs).
+    Atomic = 7,
+    /// System, CSR, fence and custom instructions.
+    System = 8,
+}
+
+/// Retired instruction, as seen by a [`TimingModel`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RetiredInstruction {
+    /// Program counter of the instruction.
+    pub pc: u32,
+    /// Retired instruction (Embive format).
+    pub instruction: Instruction,
+    /// Instruction kind.
+    pub kind: InstructionKind,
+    /// Program counter after the instruction.
+    pub next_pc: u32,
+    /// Data address of loads, stores and atomics (virtual address, before translation).
+    pub data_address: Option<u32>,
+}
+
+impl RetiredInstruction {
+    /// Check if the control flow changed (taken branch, jump or trap), e.g. to charge a pipeline flush.
+    pub fn taken(&self) -> bool {
+        self.next_pc != self.pc.wrapping_add(self.instruction.size())
+    }
+}
+
+/// Timing model, consulted for every retired instruction (check [`crate::interpreter::timing`]).
+pub trait TimingModel {
+    /// Get the cost of a retired instruction.
+    ///
+    /// Arguments:
+    /// - `retired`: The retired instruction.
+    ///
+    /// Returns the cost, in cycles (0 is allowed, e.g. for fused instructions).
+    fn cost(&mut self, retired: &RetiredInstruction) -> u32;
+}
+
+/// Fixed cost per instruction kind (1 cycle by default), with an optional taken branch penalty.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CostTable {
+    /// Cost of each instruction kind, in cycles.
+    costs: [u32; INSTRUCTION_KINDS],
+    /// Extra cost of taken branches, in cycles.
+    taken_penalty: u32,
+}
+
+impl Default for CostTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CostTable {
+    /// Create a new cost table (1 cycle per instruction, no taken branch penalty).
+    pub const fn new() -> Self {
+        CostTable {
+            costs: [1; INSTRUCTION_KINDS],
+            taken_penalty: 0,
+        }
+    }
+
+    /// Set the cost of an instruction kind.
+    ///
+    /// Arguments:
+    /// - `kind`: Instruction kind.
+    /// - `cost`: Cost, in cycles.
+    pub const fn with_cost(mut self, kind: InstructionKind, cost: u32) -> Self {
+        self.costs[kind as usize] = cost;
+        self
+    }
+
+    /// Set the extra cost of taken conditional branches (check [`RetiredInstruction::taken`]).
+    ///
+    /// Arguments:
+    /// - `penalty`: Extra cost, in cycles.
+    pub const fn with_taken_penalty(mut self, penalty: u32) -> Self {
+        self.taken_penalty = penalty;
+        self
+    }
+}
+
+impl TimingModel for CostTable {
+    fn cost(&mut self, retired: &RetiredInstruction) -> u32 {
+        let cost = self.costs[retired.kind as usize];
+        match retired.kind == InstructionKind::Branch && retired.taken() {
+            true => cost.saturating_add(self.taken_penalty),
+            false => cost,
+        }
+    }
+}
+
+/// Timing model reference (check [`Interpreter::set_timing_model`]).
+#[derive(Default)]
+pub(crate) struct TimingModelRef<'a>(Option<&'a mut dyn TimingModel>);
+
+impl core::fmt::Debug for TimingModelRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(TimingModel)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl TimingModelRef<'_> {
+    /// Check if a timing model is set.
+    #[inline(always)]
+    pub(crate) fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl<'a, M: Memory> Interpreter<'a, M> {
+    /// Set the timing model (check [`crate::interpreter::timing`]).
+    ///
+    /// Arguments:
+    /// - `model`: Timing model (`None` costs 1 cycle per instruction).
+    pub fn set_timing_model(&mut self, model: Option<&'a mut dyn TimingModel>) {
+        self.timing_model = TimingModelRef(model);
+    }
+
+    /// Get the data address of an instruction about to be executed, for the timing model.
+    ///
+    /// Arguments:
+    /// - `instruction`: The instruction (at the program counter).
+    #[inline(never)]
+    pub(crate) fn timing_data_address(&self, instruction: Instruction) -> Option<u32> {
+        data_address(self, instruction)
+    }
+
+    /// Retire an executed instruction, charging its timing model cost.
+    ///
+    /// Arguments:
+    /// - `pc`: Program counter of the instruction.
+    /// - `instruction`: The instruction.
+    /// - `data_address`: Data address (check [`Interpreter::timing_data_address`]).
+    /// - `stall`: Memory stall cycles (check [`super::memory::Memory::take_stall_cycles`]).
+    #[inline(never)]
+    pub(crate) fn retire_timed(
+        &mut self,
+        pc: u32,
+        instruction: Instruction,
+        data_address: Option<u32>,
+        stall: u32,
+    ) {
+        let retired = RetiredInstruction {
+            pc,
+            instruction,
+            kind: instruction_kind(instruction),
+            next_pc: self.program_counter,
+            data_address,
+        };
+        let cost = match &mut self.timing_model.0 {
+            Some(model) => model.cost(&retired),
+            None => 1,
+        };
+        self.registers
+            .control_status
+            .retire_cost(cost.saturating_add(stall));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, ExitReason, State};
+
+    #[cfg(feature = "transpiler")]
+    use crate::interpreter::{memory::RAM_OFFSET, Config};
+
+    /// Timing model recording the retired instructions.
+    #[cfg(feature = "transpiler")]
+    #[derive(Default)]
+    struct Recorder {
+        retired: [Option<RetiredInstruction>; 8],
+        count: usize,
+    }
+
+    #[cfg(feature = "transpiler")]
+    impl TimingModel for Recorder {
+        fn cost(&mut self, retired: &RetiredInstruction) -> u32 {
+            self.retired[self.count] = Some(*retired);
+            self.count += 1;
+            match retired.data_address {
+                // RAM wait state
+                Some(address) if address >= RAM_OFFSET => 3,
+                _ => 1,
+            }
+        }
+    }
+
+    #[cfg(feature = "transpiler")]
+    fn code() -> [u8; 22] {
+        let mut code = [
+            0x93, 0x05, 0x30, 0x00, // li   a1, 3
+            0x33, 0x85, 0xb5, 0x02, // mul  a0, a1, a1
+            0x23, 0x22, 0xa6, 0x00, // sw   a0, 4(a2)
+            0x63, 0x04, 0xb5, 0x00, // beq  a0, a1, 8 (not taken)
+            0x85, 0x45, // c.li a1, 1
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_retired() {
+        let code = code();
+        let mut ram = [0; 8];
+        let mut memory = SliceMemory::new(&code, &mut ram);
+        let mut recorder = Recorder::default();
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.registers.cpu.set_a2(RAM_OFFSET as i32);
+        interpreter.set_timing_model(Some(&mut recorder));
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        // Store costs 3 cycles
+        let control_status = &interpreter.registers.control_status;
+        assert_eq!(control_status.instructions_retired(), 6);
+        assert_eq!(control_status.cycles(), 8);
+
+        let kinds = recorder.retired.map(|retired| retired.map(|r| r.kind));
+        assert_eq!(
+            kinds[..6],
+            [
+                Some(InstructionKind::Alu),
+                Some(InstructionKind::Multiply),
+                Some(InstructionKind::Store),
+                Some(InstructionKind::Branch),
+                Some(InstructionKind::Alu),
+                Some(InstructionKind::System),
+            ]
+        );
+        let store = recorder.retired[2].unwrap();
+        assert_eq!(store.data_address, Some(RAM_OFFSET + 4));
+        assert_eq!((store.pc, store.next_pc), (8, 12));
+        assert!(!recorder.retired[3].unwrap().taken());
+        assert_eq!(recorder.retired[4].unwrap().next_pc, 18);
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_cost_table() {
+        let mut code = [
+            0x63, 0x04, 0x00, 0x00, // beq  zero, zero, 8 (taken)
+            0x73, 0x00, 0x10, 0x00, // ebreak (skipped)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let config = Config::default().with_instructions_per_tick(2);
+        let mut costs = CostTable::new()
+            .with_cost(InstructionKind::Branch, 2)
+            .with_cost(InstructionKind::System, 0)
+            .with_taken_penalty(3);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        interpreter.set_timing_model(Some(&mut costs));
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.control_status.cycles(), 5);
+        assert_eq!(interpreter.time(), Some(2));
+
+        // Host consumed instructions cost 1 cycle each
+        interpreter.consume_instructions(3);
+        assert_eq!(interpreter.time(), Some(4));
+    }
+
+    #[test]
+    fn test_default_cost() {
+        // Code: ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        let control_status = &interpreter.registers.control_status;
+        assert_eq!(control_status.cycles(), 1);
+        assert_eq!(control_status.instructions_retired(), 1);
+    }
+}
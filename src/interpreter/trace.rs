@@ -0,0 +1,355 @@
+//! Trace Module
+//!
+//! Instruction tracing, with encoders for standard textual formats (check [`TraceFormat`]).
+//! Traces are written to a user-supplied [`core::fmt::Write`] sink, one line per executed instruction.
+use core::fmt::{self, Display, Formatter, Write};
+
+use super::{memory::Memory, Error, ExitReason, Interpreter, State};
+use crate::instruction::Instruction;
+
+/// Executed instruction record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceRecord {
+    /// Program counter of the instruction.
+    pub pc: u32,
+    /// Executed instruction (Embive format).
+    pub instruction: Instruction,
+    /// CPU register written by the instruction (index, new value), if any.
+    ///
+    /// Only writes that change the register value are detected.
+    pub register_write: Option<(u8, i32)>,
+    /// Interpreter state after the instruction.
+    pub state: State,
+}
+
+impl TraceRecord {
+    /// Instruction size, in bytes (2 for compressed instructions, 8 for load-immediates, 4 otherwise).
+    pub fn size(&self) -> u32 {
+        self.instruction.size()
+    }
+
+    /// Raw instruction (Embive format), truncated to its size (first word only for load-immediates).
+    pub fn raw(&self) -> u32 {
+        let raw = u32::from(self.instruction);
+        if self.size() == 2 {
+            raw & 0xFFFF
+        } else {
+            raw
+        }
+    }
+}
+
+/// Trace output format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// Spike commit log format (`--log-commits`), for diffing against spike:
+    /// `core   0: 3 0x<pc> (0x<instruction>) x<rd> 0x<value>`.
+    ///
+    /// The raw instruction is in the Embive format (not RISC-V), compare the program counter and register writes.
+    Spike,
+    /// JSON lines, one object per instruction:
+    /// `{"pc":"0x<pc>","insn":"0x<instruction>","disasm":"<instruction>","rd":<rd>,"rd_value":"0x<value>"}`.
+    ///
+    /// The `rd` and `rd_value` fields are only present if a register was written.
+    JsonLines,
+}
+
+impl TraceFormat {
+    /// Encode a trace record (single line, including the line terminator).
+    ///
+    /// Arguments:
+    /// - `record`: The trace record to encode.
+    /// - `sink`: The output sink.
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, record written.
+    /// - `Err(fmt::Error)`: Failed to write to the sink.
+    pub fn write<W: Write>(&self, record: &TraceRecord, sink: &mut W) -> fmt::Result {
+        let width = record.size().min(4) as usize * 2;
+
+        match self {
+            TraceFormat::Spike => {
+                write!(
+                    sink,
+                    "core   0: 3 0x{:08x} (0x{:0width$x})",
+                    record.pc,
+                    record.raw()
+                )?;
+                if let Some((rd, value)) = record.register_write {
+                    write!(sink, " x{rd:<2} 0x{:08x}", value as u32)?;
+                }
+            }
+            TraceFormat::JsonLines => {
+                write!(
+                    sink,
+                    "{{\"pc\":\"0x{:08x}\",\"insn\":\"0x{:0width$x}\",\"disasm\":\"",
+                    record.pc,
+                    record.raw()
+                )?;
+                write!(JsonEscape(sink), "{:?}", record.instruction)?;
+                sink.write_char('"')?;
+                if let Some((rd, value)) = record.register_write {
+                    write!(sink, ",\"rd\":{rd},\"rd_value\":\"0x{:08x}\"", value as u32)?;
+                }
+                sink.write_char('}')?;
+            }
+        }
+
+        sink.write_char('\n')
+    }
+}
+
+/// JSON string escaping adapter.
+struct JsonEscape<'w, W: Write>(&'w mut W);
+
+impl<W: Write> Write for JsonEscape<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '"' => self.0.write_str("\\\"")?,
+                '\\' => self.0.write_str("\\\\")?,
+                c if (c as u32) < 0x20 => write!(self.0, "\\u{:04x}", c as u32)?,
+                c => self.0.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trace Error
+#[derive(Debug, PartialEq)]
+pub enum TraceError {
+    /// Failed to execute the instruction.
+    Interpreter(Error),
+    /// Failed to write to the trace sink.
+    Sink,
+}
+
+impl From<Error> for TraceError {
+    fn from(error: Error) -> Self {
+        TraceError::Interpreter(error)
+    }
+}
+
+impl From<fmt::Error> for TraceError {
+    fn from(_: fmt::Error) -> Self {
+        TraceError::Sink
+    }
+}
+
+impl core::error::Error for TraceError {}
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Step through a single instruction, recording it (tracing hook).
+///
+/// Arguments:
+/// - `interpreter`: The interpreter to step.
+///
+/// Returns:
+/// - `Ok(TraceRecord)`: Success, executed instruction record.
+/// - `Err(Error)`: Failed to fetch or execute the instruction.
+pub fn step<M: Memory>(interpreter: &mut Interpreter<'_, M>) -> Result<TraceRecord, Error> {
+    // Record the trap handler instruction, if an interrupt is taken
+    interpreter.poll_interrupts();
+
+    let pc = interpreter.program_counter;
+    let instruction = interpreter.fetch()?;
+    let registers = interpreter.registers.cpu.inner;
+
+    let state = interpreter.step()?;
+
+    // Register x0 is hardwired to 0
+    let register_write = (1..registers.len()).find_map(|i| {
+        let value = interpreter.registers.cpu.inner[i];
+        (value != registers[i]).then_some((i as u8, value))
+    });
+
+    Ok(TraceRecord {
+        pc,
+        instruction,
+        register_write,
+        state,
+    })
+}
+
+/// Embive Tracer
+///
+/// Runs an interpreter, writing every executed instruction to a sink.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::SliceMemory, trace::{TraceFormat, Tracer}, ExitReason, Interpreter, State};
+///
+/// // Code: ebreak (already transpiled)
+/// let code = [0x1f, 0x00, 0x10, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// let mut output = String::new();
+/// let state = Tracer::new(TraceFormat::Spike, &mut output).run(&mut interpreter).unwrap();
+/// assert_eq!(state, State::Halted { reason: ExitReason::Ebreak });
+/// assert_eq!(output, "core   0: 3 0x00000000 (0x0010001f)\n");
+/// ```
+#[derive(Debug)]
+pub struct Tracer<'t, W: Write> {
+    format: TraceFormat,
+    sink: &'t mut W,
+}
+
+impl<'t, W: Write> Tracer<'t, W> {
+    /// Create a new tracer.
+    ///
+    /// Arguments:
+    /// - `format`: Trace output format.
+    /// - `sink`: Trace output sink.
+    pub fn new(format: TraceFormat, sink: &'t mut W) -> Self {
+        Tracer { format, sink }
+    }
+
+    /// Step through a single instruction, writing it to the sink.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(TraceError)`: Failed to execute or to write the trace.
+    pub fn step<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+    ) -> Result<State, TraceError> {
+        let record = step(interpreter)?;
+        self.format.write(&record, self.sink)?;
+        Ok(record.state)
+    }
+
+    /// Run the interpreter, writing every executed instruction to the sink (check [`Interpreter::run`]).
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(TraceError)`: Failed to execute or to write the trace.
+    pub fn run<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+    ) -> Result<State, TraceError> {
+        let mut count = 0;
+
+        loop {
+            if interpreter.remaining_instructions() == Some(0) {
+                // Halt when the instruction budget is exhausted
+                return Ok(State::Halted {
+                    reason: ExitReason::InstructionLimit,
+                });
+            }
+
+            let state = self.step(interpreter)?;
+            if state != State::Running {
+                return Ok(state);
+            }
+
+            count += 1;
+            if interpreter.instruction_limit > 0 && count >= interpreter.instruction_limit {
+                // Yield after the instruction limit (still running)
+                return Ok(State::Running);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::memory::SliceMemory;
+
+    #[cfg(feature = "transpiler")]
+    use crate::transpiler::transpile_raw;
+
+    #[cfg(feature = "transpiler")]
+    fn code() -> [u8; 10] {
+        let mut code = [
+            0x13, 0x05, 0x10, 0x00, // li   a0, 1
+            0x05, 0x05, // addi a0, a0, 1 (compressed)
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_spike() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let mut output = String::new();
+        let state = Tracer::new(TraceFormat::Spike, &mut output)
+            .run(&mut interpreter)
+            .unwrap();
+        assert_eq!(
+            state,
+            State::Halted {
+                reason: ExitReason::Ebreak
+            }
+        );
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("core   0: 3 0x00000000 (0x"));
+        assert!(lines[0].ends_with(") x10 0x00000001"));
+        assert!(lines[1].starts_with("core   0: 3 0x00000004 (0x"));
+        assert!(lines[1].ends_with(") x10 0x00000002"));
+        assert_eq!(lines[1].find(')'), Some(30)); // 16-bit instruction
+        assert!(lines[2].starts_with("core   0: 3 0x00000006 (0x"));
+    }
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_json_lines() {
+        let code = code();
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 1);
+
+        let mut output = String::new();
+        let mut tracer = Tracer::new(TraceFormat::JsonLines, &mut output);
+        assert_eq!(tracer.run(&mut interpreter), Ok(State::Running));
+        assert_eq!(tracer.run(&mut interpreter), Ok(State::Running));
+        assert_eq!(
+            tracer.run(&mut interpreter),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("{\"pc\":\"0x00000000\",\"insn\":\"0x"));
+        assert!(lines[0].contains("\"disasm\":\"OpImm("));
+        assert!(lines[0].ends_with(",\"rd\":10,\"rd_value\":\"0x00000001\"}"));
+        assert!(lines[2].contains("\"disasm\":\"SystemMiscMem("));
+        assert!(lines[2].ends_with("\"}"));
+    }
+
+    #[test]
+    fn test_json_escape() {
+        let mut output = String::new();
+        write!(JsonEscape(&mut output), "a\"b\\c\t").unwrap();
+        assert_eq!(output, "a\\\"b\\\\c\\u0009");
+    }
+
+    #[test]
+    fn test_step_error() {
+        let mut memory = SliceMemory::new(&[], &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let mut output = String::new();
+        let result = Tracer::new(TraceFormat::Spike, &mut output).step(&mut interpreter);
+        assert_eq!(
+            result,
+            Err(TraceError::Interpreter(Error::InvalidProgramCounter(0)))
+        );
+        assert!(output.is_empty());
+    }
+}
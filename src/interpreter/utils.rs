@@ -13,6 +13,19 @@ pub fn likely(b: bool) -> bool {
     b
 }
 
+/// Decode the valid UTF-8 prefix of a byte slice (truncated at the first invalid byte).
+#[inline]
+pub fn utf8_prefix(bytes: &[u8]) -> &str {
+    match core::str::from_utf8(bytes) {
+        Ok(string) => string,
+        // The slice is valid UTF-8 up to this index, so the fallback is never used
+        Err(e) => bytes
+            .get(..e.valid_up_to())
+            .and_then(|valid| core::str::from_utf8(valid).ok())
+            .unwrap_or_default(),
+    }
+}
+
 /// A hint that the branch is unlikely to be taken.
 #[inline]
 pub fn unlikely(b: bool) -> bool {
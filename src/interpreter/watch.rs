@@ -0,0 +1,342 @@
+//! Watch Module
+//!
+//! Host-registered watch variables: guest memory values (e.g. `static` counters) checked after each run,
+//! reporting the ones that changed and their new values. Cheap "live variable" views (e.g. for dashboards),
+//! without tracing every memory access.
+//!
+//! Values are compared after the run, so a value changed and then restored within a run isn't reported.
+//! To stop right after the instruction changing a value (e.g. while debugging), use [`WatchList::run_stepping`].
+use super::{memory::Memory, Error, Interpreter, State};
+
+/// Maximum size of a watched value, in bytes.
+pub const WATCH_MAX_SIZE: usize = 8;
+
+/// A watched value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watch {
+    /// Host-defined identifier.
+    pub id: u32,
+    /// Guest address.
+    pub address: u32,
+    /// Size, in bytes (up to [`WATCH_MAX_SIZE`]).
+    pub size: usize,
+    /// Last value read (little-endian, zero-extended).
+    pub value: u64,
+}
+
+/// A watched value change, reported to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchChange {
+    /// Watch identifier.
+    pub id: u32,
+    /// Guest address.
+    pub address: u32,
+    /// Previous value.
+    pub old: u64,
+    /// New value.
+    pub new: u64,
+}
+
+/// Read a watched value from memory.
+fn read<M: Memory>(memory: &mut M, address: u32, size: usize) -> Result<u64, Error> {
+    let mut bytes = [0; WATCH_MAX_SIZE];
+    memory.copy_from_guest(address, &mut bytes[..size])?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Embive Watch List
+///
+/// Holds up to `N` watched values. Check for changes with [`WatchList::poll`], or run the interpreter
+/// through [`WatchList::run`] (after each run) or [`WatchList::run_stepping`] (after each instruction).
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::{SliceMemory, RAM_OFFSET}, watch::WatchList, Interpreter};
+///
+/// // Code: li a0, 42; lui a1, 0x80000; sw a0, 0(a1); sw a0, 4(a1); ebreak (already transpiled)
+/// let code = [
+///     0x1d, 0x28, 0xa0, 0x02, 0x9c, 0x05, 0x00, 0x80, 0x9b, 0xab, 0x05, 0x00, 0x9b, 0xab, 0x45, 0x00,
+///     0x1f, 0x00, 0x10, 0x00,
+/// ];
+/// let mut ram = [0; 8];
+/// let mut memory = SliceMemory::new(&code, &mut ram);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// let mut watches = WatchList::<4>::new();
+/// watches.add(interpreter.memory, 1, RAM_OFFSET, 4).unwrap();
+/// watches.add(interpreter.memory, 2, RAM_OFFSET + 4, 2).unwrap();
+///
+/// let mut changed = Vec::new();
+/// watches.run(&mut interpreter, |change| changed.push((change.id, change.new))).unwrap();
+/// assert_eq!(changed, [(1, 42), (2, 42)]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchList<const N: usize> {
+    watches: [Option<Watch>; N],
+}
+
+impl<const N: usize> Default for WatchList<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> WatchList<N> {
+    /// Create a new watch list, without watches.
+    pub const fn new() -> Self {
+        WatchList { watches: [None; N] }
+    }
+
+    /// Watch a guest value, reading its current value.
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    /// - `id`: Host-defined identifier (reported on changes).
+    /// - `address`: Guest address (code or RAM).
+    /// - `size`: Value size, in bytes (`1` to [`WATCH_MAX_SIZE`]).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, value watched.
+    /// - `Err(Error)`: All `N` watches are in use ([`Error::WatchListFull`]), invalid size or address.
+    pub fn add<M: Memory>(
+        &mut self,
+        memory: &mut M,
+        id: u32,
+        address: u32,
+        size: usize,
+    ) -> Result<(), Error> {
+        if !(1..=WATCH_MAX_SIZE).contains(&size) {
+            return Err(Error::InvalidMemoryAccessLength(size));
+        }
+        let slot = self
+            .watches
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Error::WatchListFull)?;
+
+        *slot = Some(Watch {
+            id,
+            address,
+            size,
+            value: read(memory, address, size)?,
+        });
+        Ok(())
+    }
+
+    /// Stop watching values.
+    ///
+    /// Arguments:
+    /// - `id`: Watch identifier.
+    ///
+    /// Returns `true` if any watch was removed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let mut removed = false;
+        for slot in self.watches.iter_mut() {
+            if slot.is_some_and(|watch| watch.id == id) {
+                *slot = None;
+                removed = true;
+            }
+        }
+        removed
+    }
+
+    /// Remove all watches.
+    pub fn clear(&mut self) {
+        self.watches = [None; N];
+    }
+
+    /// Get the watches (with their last values read).
+    pub fn watches(&self) -> impl Iterator<Item = &Watch> {
+        self.watches.iter().flatten()
+    }
+
+    /// Check the watched values, reporting the ones that changed since the last check.
+    ///
+    /// Arguments:
+    /// - `memory`: Guest memory.
+    /// - `changed`: Called for each changed value.
+    ///
+    /// Returns:
+    /// - `Ok(usize)`: Success, number of changed values.
+    /// - `Err(Error)`: Failed to read a watched value (e.g. memory remapped).
+    pub fn poll<M: Memory>(
+        &mut self,
+        memory: &mut M,
+        mut changed: impl FnMut(WatchChange),
+    ) -> Result<usize, Error> {
+        let mut count = 0;
+        for watch in self.watches.iter_mut().flatten() {
+            let value = read(memory, watch.address, watch.size)?;
+            if value != watch.value {
+                changed(WatchChange {
+                    id: watch.id,
+                    address: watch.address,
+                    old: watch.value,
+                    new: value,
+                });
+                watch.value = value;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Run the interpreter (check [`Interpreter::run`]), then report the changed values (check [`WatchList::poll`]).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    /// - `changed`: Called for each changed value.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`Interpreter::run`]).
+    /// - `Err(Error)`: Failed to run, or to read a watched value.
+    pub fn run<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        changed: impl FnMut(WatchChange),
+    ) -> Result<State, Error> {
+        let result = interpreter.run();
+        self.poll(interpreter.memory, changed)?;
+        result
+    }
+
+    /// Run the interpreter one instruction at a time, stopping right after an instruction changes a watched value
+    /// (debug mode, much slower than [`WatchList::run`]).
+    ///
+    /// [`Interpreter::instruction_limit`] is still honored: [`State::Running`] is returned once the interpreter
+    /// executed that many instructions, or right after a change (the program counter is after the instruction).
+    ///
+    /// Arguments:
+    /// - `interpreter`: The interpreter.
+    /// - `changed`: Called for each changed value.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`Interpreter::run`]).
+    /// - `Err(Error)`: Failed to run, or to read a watched value.
+    pub fn run_stepping<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        mut changed: impl FnMut(WatchChange),
+    ) -> Result<State, Error> {
+        let limit = interpreter.instruction_limit;
+        interpreter.instruction_limit = 1;
+
+        let mut executed = 0;
+        let result = loop {
+            let result = interpreter.run();
+            executed += 1;
+            match self.poll(interpreter.memory, &mut changed) {
+                Err(error) => break Err(error),
+                Ok(0) if result == Ok(State::Running) && (limit == 0 || executed < limit) => {}
+                Ok(_) => break result,
+            }
+        };
+
+        interpreter.instruction_limit = limit;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        ExitReason,
+    };
+
+    /// Code: li a0, 42; lui a1, 0x80000; sw a0, 0(a1); sw a0, 4(a1); ebreak (already transpiled)
+    const CODE: [u8; 20] = [
+        0x1d, 0x28, 0xa0, 0x02, 0x9c, 0x05, 0x00, 0x80, 0x9b, 0xab, 0x05, 0x00, 0x9b, 0xab, 0x45,
+        0x00, 0x1f, 0x00, 0x10, 0x00,
+    ];
+
+    #[test]
+    fn test_watches() {
+        let mut ram = [0; 8];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut watches = WatchList::<2>::new();
+
+        assert_eq!(
+            watches.add(&mut memory, 0, RAM_OFFSET, 0),
+            Err(Error::InvalidMemoryAccessLength(0))
+        );
+        assert_eq!(
+            watches.add(&mut memory, 0, RAM_OFFSET, 9),
+            Err(Error::InvalidMemoryAccessLength(9))
+        );
+        assert!(watches.add(&mut memory, 0, RAM_OFFSET + 8, 1).is_err());
+        watches.add(&mut memory, 1, 0, 2).unwrap();
+        watches.add(&mut memory, 2, RAM_OFFSET, 8).unwrap();
+        assert_eq!(
+            watches.add(&mut memory, 3, RAM_OFFSET, 4),
+            Err(Error::WatchListFull)
+        );
+        assert_eq!(watches.watches().next().unwrap().value, 0x281d);
+
+        // No changes
+        assert_eq!(watches.poll(&mut memory, |_| panic!()), Ok(0));
+
+        memory.store_u32(RAM_OFFSET + 4, 0x1234).unwrap();
+        let mut changes = Vec::new();
+        assert_eq!(
+            watches.poll(&mut memory, |change| changes.push(change)),
+            Ok(1)
+        );
+        assert_eq!(
+            changes,
+            [WatchChange {
+                id: 2,
+                address: RAM_OFFSET,
+                old: 0,
+                new: 0x1234 << 32
+            }]
+        );
+
+        assert!(watches.remove(2));
+        assert!(!watches.remove(2));
+        assert_eq!(watches.watches().count(), 1);
+        watches.clear();
+        assert_eq!(watches.watches().count(), 0);
+    }
+
+    #[test]
+    fn test_run_stepping() {
+        let mut ram = [0; 8];
+        let mut memory = SliceMemory::new(&CODE, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        let mut watches = WatchList::<2>::new();
+        watches.add(interpreter.memory, 1, RAM_OFFSET, 4).unwrap();
+        watches
+            .add(interpreter.memory, 2, RAM_OFFSET + 4, 4)
+            .unwrap();
+
+        // Stops after each store
+        let mut changes = Vec::new();
+        for pc in [12, 16] {
+            assert_eq!(
+                watches.run_stepping(&mut interpreter, |change| changes.push(change.id)),
+                Ok(State::Running)
+            );
+            assert_eq!(interpreter.program_counter, pc);
+        }
+        assert_eq!(changes, [1, 2]);
+        assert_eq!(
+            watches.run_stepping(&mut interpreter, |_| panic!()),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.instruction_limit, 0);
+
+        // Instruction limit honored
+        interpreter.reset();
+        interpreter.instruction_limit = 2;
+        assert_eq!(
+            watches.run_stepping(&mut interpreter, |_| panic!()),
+            Ok(State::Running)
+        );
+        assert_eq!(interpreter.program_counter, 8);
+        assert_eq!(interpreter.instruction_limit, 2);
+    }
+}
@@ -0,0 +1,307 @@
+//! Watchdog Module
+//!
+//! Host-side supervisor for guests that stop making progress: a guest can wait for an interrupt forever
+//! (`wfi`) or a syscall handler can take too long to complete. The watchdog tracks how long the interpreter
+//! has been in [`State::Waiting`] or in [`State::Called`] (until the syscall is completed), measured in
+//! instructions retired or in host time (user-supplied clock).
+use core::fmt;
+
+use super::{memory::Memory, Error, Interpreter, State};
+
+/// Embive Watchdog
+///
+/// Wraps [`Interpreter::run`] and [`Interpreter::interrupt`], tracking the current waiting or called period:
+/// - [`State::Waiting`]: Starts when the interpreter first returns [`State::Waiting`], and lasts until an interrupt
+///   is triggered ([`Watchdog::interrupt`]) or the interpreter returns another state. Consecutive `wfi` instructions
+///   are part of the same period.
+/// - [`State::Called`]: Starts when the interpreter returns [`State::Called`], and lasts until the syscall is completed
+///   (check [`Interpreter::pending_syscall`]), including deferred syscalls ([`State::SyscallPending`]).
+///
+/// When a period exceeds its timeout, the timeout callback is called (and a new period starts).
+/// Otherwise, [`Error::WatchdogTimeout`] is returned.
+///
+/// By default, time is measured in instructions retired, so only [`State::Waiting`] periods advance (the guest keeps
+/// executing `wfi`). Set a host clock ([`Watchdog::clock`]) to also supervise syscalls, calling [`Watchdog::check`]
+/// while handling them asynchronously.
+///
+/// Example:
+/// ```
+/// use embive::interpreter::{memory::SliceMemory, watchdog::Watchdog, Error, Interpreter, State};
+///
+/// // Code: wfi, c.j -4, padding (already transpiled)
+/// let code = [0x1f, 0x00, 0x30, 0x00, 0xcf, 0xff, 0x00, 0x00];
+/// let mut memory = SliceMemory::new(&code, &mut []);
+/// let mut interpreter = Interpreter::new(&mut memory, 0);
+///
+/// // Guest never gets an interrupt
+/// let mut watchdog = Watchdog::new().waiting_timeout(100);
+/// let error = loop {
+///     match watchdog.run(&mut interpreter) {
+///         Ok(_) => {}
+///         Err(error) => break error,
+///     }
+/// };
+/// assert_eq!(error, Error::WatchdogTimeout(State::Waiting));
+/// ```
+pub struct Watchdog<'w> {
+    waiting_timeout: Option<u64>,
+    called_timeout: Option<u64>,
+    clock: Option<&'w mut (dyn FnMut() -> u64 + 'w)>,
+    on_timeout: Option<&'w mut (dyn FnMut(State, u64) + 'w)>,
+    period: Option<(State, u64)>,
+}
+
+impl fmt::Debug for Watchdog<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("waiting_timeout", &self.waiting_timeout)
+            .field("called_timeout", &self.called_timeout)
+            .field("clock", &self.clock.is_some())
+            .field("on_timeout", &self.on_timeout.is_some())
+            .field("period", &self.period)
+            .finish()
+    }
+}
+
+impl Default for Watchdog<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'w> Watchdog<'w> {
+    /// Create a new watchdog, without any timeouts (measuring time in instructions retired).
+    pub fn new() -> Self {
+        Watchdog {
+            waiting_timeout: None,
+            called_timeout: None,
+            clock: None,
+            on_timeout: None,
+            period: None,
+        }
+    }
+
+    /// Set the maximum duration of a [`State::Waiting`] period.
+    ///
+    /// Arguments:
+    /// - `timeout`: Timeout, in instructions retired or clock ticks (check [`Watchdog::clock`]).
+    pub fn waiting_timeout(mut self, timeout: u64) -> Self {
+        self.waiting_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum duration of a [`State::Called`] period (until the syscall is completed).
+    ///
+    /// Arguments:
+    /// - `timeout`: Timeout, in instructions retired or clock ticks (check [`Watchdog::clock`]).
+    pub fn called_timeout(mut self, timeout: u64) -> Self {
+        self.called_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the host clock, returning the current time in ticks (any monotonic unit, e.g. milliseconds).
+    pub fn clock(mut self, clock: &'w mut (dyn FnMut() -> u64 + 'w)) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Set the timeout callback, called with the timed out state and the elapsed time.
+    /// Without a callback, timeouts result in [`Error::WatchdogTimeout`].
+    pub fn on_timeout(mut self, callback: &'w mut (dyn FnMut(State, u64) + 'w)) -> Self {
+        self.on_timeout = Some(callback);
+        self
+    }
+
+    /// Get the current period: the watched state and the time it started, if any.
+    pub fn period(&self) -> Option<(State, u64)> {
+        self.period
+    }
+
+    /// Get the current time (clock ticks or instructions retired).
+    fn now<M: Memory>(&mut self, interpreter: &Interpreter<'_, M>) -> u64 {
+        match self.clock.as_mut() {
+            Some(clock) => clock(),
+            None => interpreter.registers.control_status.instructions_retired(),
+        }
+    }
+
+    /// Check the current period for a timeout.
+    ///
+    /// A [`State::Called`] period ends once the syscall is completed (after this check, so slow handlers are detected).
+    ///
+    /// Returns:
+    /// - `Ok(())`: No timeout, or the timeout callback was called.
+    /// - `Err(Error)`: The period timed out ([`Error::WatchdogTimeout`]).
+    pub fn check<M: Memory>(&mut self, interpreter: &Interpreter<'_, M>) -> Result<(), Error> {
+        let Some((state, start)) = self.period else {
+            return Ok(());
+        };
+
+        let timeout = match state {
+            State::Waiting => self.waiting_timeout,
+            _ => self.called_timeout,
+        };
+        let now = self.now(interpreter);
+        let elapsed = now.saturating_sub(start);
+
+        // Syscall completed, end the period
+        if state == State::Called && interpreter.pending_syscall().is_none() {
+            self.period = None;
+        }
+
+        match timeout {
+            Some(timeout) if elapsed >= timeout => match self.on_timeout.as_mut() {
+                Some(callback) => {
+                    callback(state, elapsed);
+                    if self.period.is_some() {
+                        self.period = Some((state, now));
+                    }
+                    Ok(())
+                }
+                None => {
+                    self.period = None;
+                    Err(Error::WatchdogTimeout(state))
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Run the interpreter (check [`Interpreter::run`]), tracking the returned state.
+    ///
+    /// Returns:
+    /// - `Ok(State)`: Success, current state (check [`State`]).
+    /// - `Err(Error)`: Failed to execute, or a period timed out ([`Error::WatchdogTimeout`]).
+    pub fn run<M: Memory>(&mut self, interpreter: &mut Interpreter<'_, M>) -> Result<State, Error> {
+        self.check(interpreter)?;
+
+        let state = interpreter.run()?;
+        self.period = match (state, self.period) {
+            // Still waiting, keep the period start
+            (State::Waiting, Some((State::Waiting, start))) => Some((State::Waiting, start)),
+            // Deferred syscall, still not completed
+            (State::SyscallPending, Some((State::Called, start))) => Some((State::Called, start)),
+            (State::Waiting | State::Called, _) => Some((state, self.now(interpreter))),
+            _ => None,
+        };
+
+        self.check(interpreter)?;
+        Ok(state)
+    }
+
+    /// Trigger an interrupt (check [`Interpreter::interrupt`]), ending the [`State::Waiting`] period.
+    ///
+    /// Arguments:
+    /// - `value`: Value passed to the interrupt handler (`mtval`).
+    ///
+    /// Returns:
+    /// - `Ok(())`: Success, interrupt triggered.
+    /// - `Err(Error)`: Interrupt not enabled by the interpreted code.
+    pub fn interrupt<M: Memory>(
+        &mut self,
+        interpreter: &mut Interpreter<'_, M>,
+        value: i32,
+    ) -> Result<(), Error> {
+        interpreter.interrupt(value)?;
+        if matches!(self.period, Some((State::Waiting, _))) {
+            self.period = None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+    use crate::interpreter::{memory::SliceMemory, ExitReason, SYSCALL_ARGS};
+
+    #[cfg(feature = "transpiler")]
+    use crate::transpiler::transpile_raw;
+
+    #[cfg(feature = "transpiler")]
+    #[test]
+    fn test_waiting_timeout() {
+        let mut code = [
+            0x93, 0x00, 0x80, 0x00, // li    ra, 8
+            0xf3, 0x90, 0x00, 0x30, // csrrw ra, mstatus, ra
+            0x93, 0x00, 0x00, 0x80, // li    ra, -2048
+            0xf3, 0x90, 0x40, 0x30, // csrrw ra, mie, ra
+            0x73, 0x00, 0x50, 0x10, // wfi
+            0xf5, 0xbf, // c.j   -4
+            0x00, 0x00, // padding
+        ];
+        transpile_raw(&mut code).unwrap();
+
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let timeouts = Cell::new(0);
+        let mut on_timeout = |state, elapsed| {
+            assert_eq!(state, State::Waiting);
+            assert_eq!(elapsed, 6);
+            timeouts.set(timeouts.get() + 1);
+        };
+        let mut watchdog = Watchdog::new()
+            .waiting_timeout(6)
+            .on_timeout(&mut on_timeout);
+
+        // Period starts at the first wfi (5 instructions retired)
+        assert_eq!(watchdog.run(&mut interpreter), Ok(State::Waiting));
+        assert_eq!(watchdog.period(), Some((State::Waiting, 5)));
+
+        // Each loop iteration retires 2 instructions
+        for _ in 0..3 {
+            assert_eq!(watchdog.run(&mut interpreter), Ok(State::Waiting));
+        }
+        assert_eq!(timeouts.get(), 1);
+        assert_eq!(watchdog.period(), Some((State::Waiting, 11)));
+
+        // Interrupt ends the period
+        watchdog.interrupt(&mut interpreter, 0).unwrap();
+        assert_eq!(watchdog.period(), None);
+    }
+
+    #[test]
+    fn test_called_timeout() {
+        // Code: ecall, ebreak (already transpiled)
+        let code = [0x1f, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x10, 0x00];
+        let mut memory = SliceMemory::new(&code, &mut []);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+
+        let time = Cell::new(0);
+        let mut clock = || time.get();
+        let mut watchdog = Watchdog::new().called_timeout(10).clock(&mut clock);
+
+        assert_eq!(watchdog.run(&mut interpreter), Ok(State::Called));
+        assert_eq!(watchdog.period(), Some((State::Called, 0)));
+
+        // Syscall still pending (deferred)
+        interpreter.defer_syscall().unwrap();
+        time.set(5);
+        assert_eq!(watchdog.run(&mut interpreter), Ok(State::SyscallPending));
+        assert_eq!(watchdog.period(), Some((State::Called, 0)));
+        time.set(10);
+        assert_eq!(
+            watchdog.check(&interpreter),
+            Err(Error::WatchdogTimeout(State::Called))
+        );
+
+        // Fast syscall
+        interpreter.reset();
+        assert_eq!(watchdog.run(&mut interpreter), Ok(State::Called));
+        let mut syscall = |_nr: i32, _args: &[i32; SYSCALL_ARGS], _memory: &mut SliceMemory<'_>| {
+            Ok::<_, Error>(Ok(0))
+        };
+        interpreter.syscall(&mut syscall).unwrap();
+        time.set(15);
+        assert_eq!(
+            watchdog.run(&mut interpreter),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(watchdog.period(), None);
+    }
+}
@@ -11,11 +11,21 @@
 
 #[cfg(all(feature = "alloc", feature = "transpiler"))]
 extern crate alloc;
+#[cfg(any(feature = "std", feature = "test-utils", feature = "guest-build"))]
+extern crate std;
 
+#[cfg(feature = "ffi")]
+#[allow(unsafe_code)]
+pub mod ffi;
 mod format;
+#[cfg(feature = "guest-build")]
+pub mod guest_build;
 pub mod instruction;
 #[cfg(feature = "interpreter")]
 pub mod interpreter;
+pub mod protocol;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 #[cfg(feature = "transpiler")]
 pub mod transpiler;
 
@@ -30,9 +40,9 @@ mod tests {
     use crate::{
         interpreter::{
             memory::{SliceMemory, RAM_OFFSET},
-            Error, Interpreter, State, SYSCALL_ARGS,
+            Error, Interpreter, Runner, State, SYSCALL_ARGS,
         },
-        transpiler::transpile_elf,
+        transpiler::{transpile_elf_with_config, Config},
     };
 
     const RAM_SIZE: usize = 32 * 1024;
@@ -64,7 +74,7 @@ mod tests {
         Ok(Ok(0))
     }
 
-    fn execute_bin_test(test: DirEntry) {
+    fn execute_bin_test(test: DirEntry, config: &Config<'_>) {
         let code = &[];
 
         println!("\nRunning: {}", test.file_name().to_string_lossy());
@@ -72,7 +82,7 @@ mod tests {
         // Load binary into RAM
         let mut ram = [0; RAM_SIZE];
         let test_elf = std::fs::read(test.path()).expect("Failed to read test file");
-        transpile_elf(&test_elf, &mut ram).expect("Failed to transpile");
+        transpile_elf_with_config(&test_elf, &mut ram, config).expect("Failed to transpile");
 
         let mut memory = SliceMemory::new(code, &mut ram);
 
@@ -85,16 +95,18 @@ mod tests {
         // Get syscall counter prior to running
         let prev_syscall_counter = SYSCALL_COUNTER.with(|c| *c.borrow());
 
-        // Run it
-        loop {
-            match interpreter.run().unwrap() {
-                State::Running => {}
-                State::Called => {
-                    interpreter.syscall(&mut syscall).unwrap();
-                }
-                State::Waiting => {}
-                State::Halted => break,
-            }
+        // Run it until the test exits
+        let mut syscall_fn = syscall;
+        let state = Runner::new(&mut interpreter)
+            .syscall(&mut syscall_fn)
+            .run_to_completion()
+            .unwrap();
+
+        if let State::Panicked { msg_ptr, len } = state {
+            panic!(
+                "Guest panicked: {}",
+                interpreter.panic_message(msg_ptr, len).unwrap()
+            );
         }
 
         // Get syscall counter after running
@@ -119,7 +131,27 @@ mod tests {
         let mut tested_files = 0;
         for test in tests {
             let test = test.expect("Failed to get test");
-            execute_bin_test(test);
+            execute_bin_test(test, &Config::default());
+            tested_files += 1;
+        }
+        assert_eq!(tested_files, RV32UI_TESTS);
+    }
+
+    #[test]
+    fn rv32ui_load_immediate_bin_tests() {
+        // Get all tests
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("tests/riscv");
+        dir.push("rv32ui");
+
+        let tests = read_dir(dir).expect("Failed to read directory");
+
+        // Iterate over RV32UI tests, with `lui` + `addi` pairs fused
+        let config = Config::default().with_load_immediate(true);
+        let mut tested_files = 0;
+        for test in tests {
+            let test = test.expect("Failed to get test");
+            execute_bin_test(test, &config);
             tested_files += 1;
         }
         assert_eq!(tested_files, RV32UI_TESTS);
@@ -138,7 +170,7 @@ mod tests {
         let mut tested_files = 0;
         for test in tests {
             let test = test.expect("Failed to get test");
-            execute_bin_test(test);
+            execute_bin_test(test, &Config::default());
             tested_files += 1;
         }
         assert_eq!(tested_files, RV32UM_TESTS);
@@ -157,7 +189,7 @@ mod tests {
         let mut tested_files = 0;
         for test in tests {
             let test = test.expect("Failed to get test");
-            execute_bin_test(test);
+            execute_bin_test(test, &Config::default());
             tested_files += 1;
         }
         assert_eq!(tested_files, RV32UA_TESTS);
@@ -176,7 +208,7 @@ mod tests {
         let mut tested_files = 0;
         for test in tests {
             let test = test.expect("Failed to get test");
-            execute_bin_test(test);
+            execute_bin_test(test, &Config::default());
             tested_files += 1;
         }
         assert_eq!(tested_files, RV32UC_TESTS);
@@ -0,0 +1,475 @@
+//! Guest/Host Protocol Module
+//!
+//! Conventions shared by the interpreted code (guest) and the host: syscall numbers, interrupt CSRs,
+//! panic/exit protocols and memory layout. Always available (no feature needed), so guest-side crates can
+//! depend on the same definitions as the interpreter (`default-features = false`), instead of copying them.
+//!
+//! Syscalls (`ecall`):
+//! - Number in `a7`, arguments in `a0` to `a6` ([`SYSCALL_ARGS`]). More arguments follow the extended convention
+//!   (`a0` to `a5` in registers, pointer to the remaining ones in `a6`, check [`EXTENDED_SYSCALL_REGISTER_ARGS`]).
+//! - Result: error code in `a0` (`0` on success), value in `a1` (`a1` low and `a2` high for 64-bit values).
+//! - Negative numbers are reserved for Embive ([`SYSCALLS`]), the others are host-defined.
+//!
+//! Interrupts (`mtvec` in direct mode, enabled by `mstatus.MIE`):
+//! - Host interrupt: enabled by `mie` bit [`INTERRUPT_CODE`], `mcause` is [`INTERRUPT_MCAUSE`].
+//! - Software interrupt: enabled by `mie.MSIE` (bit [`SOFTWARE_INTERRUPT_CODE`]), `mcause` is [`SOFTWARE_INTERRUPT_MCAUSE`].
+//!   Raised through `mip.MSIP`, or the memory-mapped [`CLINT_MSIP`] register when provided by the host.
+//! - The host-provided value is passed through `mtval` (e.g. [`CONSOLE_INTERRUPT`]). Return with `mret`.
+//! - `wfi` waits for the next interrupt ([`SLEEP_SYSCALL`] with a timeout).
+//! - [`YIELD_SYSCALL`] gives control back to the host voluntarily (cooperative scheduling).
+//!
+//! Exit and panic:
+//! - [`EXIT_SYSCALL`] halts the guest with an exit code (`a0`), surfaced to the host as the halt reason.
+//! - `ebreak` halts the guest without an exit code (results are read by the host from the registers, e.g. `a0`).
+//! - [`PANIC_SYSCALL`] reports a panic message (address in `a0`, length in `a1`), the guest doesn't resume.
+//!
+//! Memory: code starts at address `0` (read-only), RAM at [`RAM_OFFSET`].
+//!
+//! ABI versioning:
+//! - Transpiled images can carry the ABI version they were built for, in a trailer appended after the image
+//!   ([`ABI_MAGIC`] and the version, check [`image_abi_version`]). Hosts reject images outside
+//!   [`MIN_ABI_VERSION`]`..=`[`ABI_VERSION`] before running them (check [`abi_compatible`]).
+//! - At runtime, the guest queries the host capabilities with [`CAPABILITIES_SYSCALL`].
+//!
+//! Two-part guests (resident runtime and swappable app):
+//! - The runtime image (e.g. standard library, helpers) is at address `0`, the app image at [`APP_OFFSET`].
+//! - The runtime exports functions through an export table, the app calls them through the slots of its
+//!   import table (`lw` of the slot, then `jalr`), filled by the host when loading the app.
+//! - Both tables are trailers of their image (before the ABI version trailer, if any): entries (name hash,
+//!   check [`symbol_hash`], and address or slot, little-endian `u32`s), entry count (little-endian `u32`) and
+//!   magic ([`EXPORT_TABLE_MAGIC`] or [`IMPORT_TABLE_MAGIC`]). Check [`write_link_table`].
+//!
+//! Example (guest side):
+//! ```
+//! use embive::protocol::{syscall_spec, SyscallHandling, RANDOM_SYSCALL};
+//!
+//! let spec = syscall_spec(RANDOM_SYSCALL).unwrap();
+//! assert_eq!(spec.name, "random");
+//! assert_eq!(spec.handling, SyscallHandling::Interpreter);
+//! ```
+
+/// Number of syscall arguments (`a0` to `a6`).
+pub const SYSCALL_ARGS: usize = 7;
+
+/// Number of register arguments (`a0` to `a5`) in the extended syscall convention.
+pub const EXTENDED_SYSCALL_REGISTER_ARGS: usize = SYSCALL_ARGS - 1;
+
+/// Panic syscall number (`a0`: message address, `a1`: message length). Doesn't return.
+pub const PANIC_SYSCALL: i32 = -1;
+
+/// Sleep syscall number (`a0`: timeout low, `a1`: timeout high, in host-defined ticks). Returns `0`.
+pub const SLEEP_SYSCALL: i32 = -2;
+
+/// Log syscall number (`a0`: records address, `a1`: records length in bytes, check [`LOG_RECORD_HEADER_SIZE`]).
+pub const LOG_SYSCALL: i32 = -3;
+
+/// Random syscall number (`a0`: buffer address, `a1`: buffer length). Returns the number of bytes written.
+pub const RANDOM_SYSCALL: i32 = -4;
+
+/// Random syscall error: no entropy source.
+pub const RANDOM_ERROR_UNAVAILABLE: i32 = 1;
+
+/// Random syscall error: invalid memory address (buffer out of bounds).
+pub const RANDOM_ERROR_INVALID_ADDRESS: i32 = 2;
+
+/// Size of a log record header, in bytes.
+///
+/// Record format (little-endian, no alignment): level (1 byte, `1` error to `5` trace), module ID (2 bytes,
+/// guest-defined), message length (2 bytes), message (UTF-8 bytes).
+pub const LOG_RECORD_HEADER_SIZE: usize = 5;
+
+/// Peripherals: UART write syscall number (`a0`: address, `a1`: length). Returns the bytes written.
+pub const UART_WRITE: i32 = -0x100;
+
+/// Peripherals: UART read syscall number (`a0`: address, `a1`: length). Returns the bytes read.
+pub const UART_READ: i32 = -0x101;
+
+/// Peripherals: GPIO write syscall number (`a0`: mask, `a1`: value). Returns the new output state.
+pub const GPIO_WRITE: i32 = -0x110;
+
+/// Peripherals: GPIO read syscall number. Returns the input state.
+pub const GPIO_READ: i32 = -0x111;
+
+/// Peripherals: timer syscall number, current tick count. Returns the low 32 bits.
+pub const TIMER_NOW: i32 = -0x120;
+
+/// Peripherals: timer alarm syscall number (`a0`: ticks from now). Returns `0`.
+pub const TIMER_SET_ALARM: i32 = -0x121;
+
+/// Peripherals syscall error: invalid memory address (buffer out of bounds).
+pub const ERROR_INVALID_ADDRESS: i32 = 1;
+
+/// Console: interrupt value (`mtval`) of a stdin interrupt (data available).
+pub const CONSOLE_INTERRUPT: i32 = -0x130;
+
+/// Console: size of a ring header (`head` and `tail`, little-endian `u32`), in bytes.
+pub const RING_HEADER_SIZE: u32 = 8;
+
+/// Host interrupt code (`mie`/`mip` bit and `mcause` code).
+pub const INTERRUPT_CODE: u32 = 16;
+
+/// `mcause` value of a host interrupt.
+pub const INTERRUPT_MCAUSE: u32 = (1 << 31) | INTERRUPT_CODE;
+
+/// Machine software interrupt code (`mie`/`mip` bit and `mcause` code).
+pub const SOFTWARE_INTERRUPT_CODE: u32 = 3;
+
+/// `mcause` value of a machine software interrupt.
+pub const SOFTWARE_INTERRUPT_MCAUSE: u32 = (1 << 31) | SOFTWARE_INTERRUPT_CODE;
+
+/// Peripherals: CLINT-compatible `msip` register address (hart 0, bit 0 mirrors `mip.MSIP`), at the standard
+/// CLINT base (`0x0200_0000`).
+pub const CLINT_MSIP: u32 = 0x0200_0000;
+
+/// RAM start address (code starts at address `0`).
+pub const RAM_OFFSET: u32 = 0x8000_0000;
+
+/// Return address of functions called by the host (set in `ra`), misaligned so it can't be a function address.
+pub const CALL_RETURN_ADDRESS: u32 = 0xFFFF_FFFF;
+
+/// Program counter after returning to [`CALL_RETURN_ADDRESS`] (jumps clear bit 0 of the target), host calls stop
+/// when it is reached.
+///
+/// Reserved: the last halfword of the address space must not hold code, as any jump to it ends the host call.
+pub const CALL_RETURN_PC: u32 = CALL_RETURN_ADDRESS & !1;
+
+/// Capabilities syscall number (`a0`: buffer address, `a1`: buffer length).
+/// Returns the capabilities size in bytes, even if the buffer is smaller (it is filled up to its length).
+///
+/// Capabilities (little-endian `u32` words):
+/// - Offset `0`: Host ABI version ([`ABI_VERSION`]).
+/// - Offset `4`: Enabled extensions (`misa` bits).
+/// - Offset `8`: Code size, in bytes (`0` if unknown).
+/// - Offset `12`: RAM size, in bytes (`0` if unknown).
+/// - Offset `16`: Number of host syscalls.
+/// - Offset [`CAPABILITIES_HEADER_SIZE`]: Host syscall numbers (`i32`, reserved syscalls aren't listed).
+pub const CAPABILITIES_SYSCALL: i32 = -5;
+
+/// Size of the capabilities header (before the syscall numbers), in bytes.
+pub const CAPABILITIES_HEADER_SIZE: usize = 20;
+
+/// Capabilities syscall error: invalid memory address (buffer out of bounds).
+pub const CAPABILITIES_ERROR_INVALID_ADDRESS: i32 = 1;
+
+/// Yield syscall number (no arguments), a voluntary yield to the host (e.g. to other guests).
+/// Returns the host backpressure (`0` if the host is idle, higher values ask the guest to yield more often).
+pub const YIELD_SYSCALL: i32 = -6;
+
+/// Exit syscall number, halting the guest with an exit code (`a0`, `0` on success). The guest doesn't resume.
+pub const EXIT_SYSCALL: i32 = -7;
+
+/// Host syscall error (`a0`): syscall not permitted for this guest, or over quota (`-EPERM`).
+/// Negative, so it doesn't clash with host-defined (positive) error codes.
+pub const SYSCALL_ERROR_PERMISSION_DENIED: i32 = -1;
+
+/// Guest/host ABI version (syscall numbers and conventions of this module).
+///
+/// Incremented on incompatible changes (e.g. a reserved syscall removed or renumbered).
+pub const ABI_VERSION: u32 = 1;
+
+/// Oldest image ABI version supported by the host.
+pub const MIN_ABI_VERSION: u32 = 1;
+
+/// Magic bytes of the ABI version trailer.
+pub const ABI_MAGIC: [u8; 4] = *b"EMBV";
+
+/// Size of the ABI version trailer ([`ABI_MAGIC`] and the little-endian `u32` version), in bytes.
+pub const ABI_TRAILER_SIZE: usize = 8;
+
+/// Get the ABI version trailer of this version, appended to transpiled images.
+pub const fn abi_trailer() -> [u8; ABI_TRAILER_SIZE] {
+    let version = ABI_VERSION.to_le_bytes();
+    [
+        ABI_MAGIC[0],
+        ABI_MAGIC[1],
+        ABI_MAGIC[2],
+        ABI_MAGIC[3],
+        version[0],
+        version[1],
+        version[2],
+        version[3],
+    ]
+}
+
+/// Get the ABI version of a transpiled image, from its trailer.
+///
+/// Arguments:
+/// - `image`: The transpiled image (code).
+///
+/// Returns:
+/// - `Some(u32)`: The image ABI version.
+/// - `None`: The image has no ABI version trailer (unversioned).
+pub fn image_abi_version(image: &[u8]) -> Option<u32> {
+    match image.split_last_chunk::<ABI_TRAILER_SIZE>()? {
+        (_, [m0, m1, m2, m3, v0, v1, v2, v3]) if [*m0, *m1, *m2, *m3] == ABI_MAGIC => {
+            Some(u32::from_le_bytes([*v0, *v1, *v2, *v3]))
+        }
+        _ => None,
+    }
+}
+
+/// Check if an image ABI version is supported by the host ([`MIN_ABI_VERSION`]`..=`[`ABI_VERSION`]).
+pub const fn abi_compatible(version: u32) -> bool {
+    version >= MIN_ABI_VERSION && version <= ABI_VERSION
+}
+
+/// App image start address, in two-part guests (the runtime image starts at address `0`).
+pub const APP_OFFSET: u32 = 0x4000_0000;
+
+/// Magic bytes of the export table trailer (runtime image).
+pub const EXPORT_TABLE_MAGIC: [u8; 4] = *b"EMBX";
+
+/// Magic bytes of the import table trailer (app image).
+pub const IMPORT_TABLE_MAGIC: [u8; 4] = *b"EMBM";
+
+/// Size of a link table entry (name hash and address or slot), in bytes.
+pub const LINK_ENTRY_SIZE: usize = 8;
+
+/// Hash a symbol name, for export and import tables (32-bit FNV-1a).
+///
+/// Arguments:
+/// - `name`: The symbol name.
+///
+/// Returns the name hash.
+pub const fn symbol_hash(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    let mut hash = 0x811C_9DC5u32;
+    let mut index = 0;
+    while index < bytes.len() {
+        hash = (hash ^ bytes[index] as u32).wrapping_mul(0x0100_0193);
+        index += 1;
+    }
+    hash
+}
+
+/// Get the size of a link table trailer (entries, count and magic), in bytes.
+pub const fn link_table_size(entries: usize) -> usize {
+    entries * LINK_ENTRY_SIZE + 8
+}
+
+/// Write a link table trailer (e.g. appended to a runtime image when packaging it).
+///
+/// Arguments:
+/// - `magic`: Table magic ([`EXPORT_TABLE_MAGIC`] or [`IMPORT_TABLE_MAGIC`]).
+/// - `entries`: Table entries (name hash and address, or `0` for import slots).
+/// - `output`: Output buffer (check [`link_table_size`]).
+///
+/// Returns:
+/// - `Some(usize)`: The table size, in bytes.
+/// - `None`: The output buffer is too small.
+pub fn write_link_table(
+    magic: [u8; 4],
+    entries: &[(u32, u32)],
+    output: &mut [u8],
+) -> Option<usize> {
+    let size = link_table_size(entries.len());
+    let output = output.get_mut(..size)?;
+    let (table, trailer) = output.split_at_mut(size - 8);
+    for ((hash, value), entry) in entries.iter().zip(table.chunks_exact_mut(LINK_ENTRY_SIZE)) {
+        entry[..4].copy_from_slice(&hash.to_le_bytes());
+        entry[4..].copy_from_slice(&value.to_le_bytes());
+    }
+    trailer[..4].copy_from_slice(&(entries.len() as u32).to_le_bytes());
+    trailer[4..].copy_from_slice(&magic);
+    Some(size)
+}
+
+/// Syscall handling (who handles a reserved syscall).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SyscallHandling {
+    /// Handled by the interpreter, possibly stopping with a dedicated state (e.g. panic, sleep).
+    Interpreter,
+    /// Handled by the host (e.g. log forwarding).
+    Host,
+    /// Handled by the emulated peripherals (`peripherals` feature).
+    Peripherals,
+}
+
+/// Reserved Syscall Specification
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SyscallSpec {
+    /// Syscall name.
+    pub name: &'static str,
+    /// Syscall number (`a7`).
+    pub number: i32,
+    /// Arguments (`a0` onwards).
+    pub args: &'static [&'static str],
+    /// Return value (`a1`), if the syscall returns.
+    pub returns: Option<&'static str>,
+    /// Who handles the syscall.
+    pub handling: SyscallHandling,
+}
+
+/// Reserved syscalls (machine-readable table of the constants above).
+pub const SYSCALLS: [SyscallSpec; 13] = [
+    SyscallSpec {
+        name: "panic",
+        number: PANIC_SYSCALL,
+        args: &["msg_ptr", "len"],
+        returns: None,
+        handling: SyscallHandling::Interpreter,
+    },
+    SyscallSpec {
+        name: "sleep",
+        number: SLEEP_SYSCALL,
+        args: &["timeout_low", "timeout_high"],
+        returns: Some("0"),
+        handling: SyscallHandling::Interpreter,
+    },
+    SyscallSpec {
+        name: "log",
+        number: LOG_SYSCALL,
+        args: &["records_ptr", "len"],
+        returns: Some("0"),
+        handling: SyscallHandling::Host,
+    },
+    SyscallSpec {
+        name: "random",
+        number: RANDOM_SYSCALL,
+        args: &["buf_ptr", "len"],
+        returns: Some("bytes_written"),
+        handling: SyscallHandling::Interpreter,
+    },
+    SyscallSpec {
+        name: "capabilities",
+        number: CAPABILITIES_SYSCALL,
+        args: &["buf_ptr", "len"],
+        returns: Some("capabilities_size"),
+        handling: SyscallHandling::Interpreter,
+    },
+    SyscallSpec {
+        name: "yield",
+        number: YIELD_SYSCALL,
+        args: &[],
+        returns: Some("backpressure"),
+        handling: SyscallHandling::Interpreter,
+    },
+    SyscallSpec {
+        name: "exit",
+        number: EXIT_SYSCALL,
+        args: &["code"],
+        returns: None,
+        handling: SyscallHandling::Interpreter,
+    },
+    SyscallSpec {
+        name: "uart_write",
+        number: UART_WRITE,
+        args: &["buf_ptr", "len"],
+        returns: Some("bytes_written"),
+        handling: SyscallHandling::Peripherals,
+    },
+    SyscallSpec {
+        name: "uart_read",
+        number: UART_READ,
+        args: &["buf_ptr", "len"],
+        returns: Some("bytes_read"),
+        handling: SyscallHandling::Peripherals,
+    },
+    SyscallSpec {
+        name: "gpio_write",
+        number: GPIO_WRITE,
+        args: &["mask", "value"],
+        returns: Some("output_state"),
+        handling: SyscallHandling::Peripherals,
+    },
+    SyscallSpec {
+        name: "gpio_read",
+        number: GPIO_READ,
+        args: &[],
+        returns: Some("input_state"),
+        handling: SyscallHandling::Peripherals,
+    },
+    SyscallSpec {
+        name: "timer_now",
+        number: TIMER_NOW,
+        args: &[],
+        returns: Some("ticks_low"),
+        handling: SyscallHandling::Peripherals,
+    },
+    SyscallSpec {
+        name: "timer_set_alarm",
+        number: TIMER_SET_ALARM,
+        args: &["ticks"],
+        returns: Some("0"),
+        handling: SyscallHandling::Peripherals,
+    },
+];
+
+/// Get the specification of a reserved syscall.
+///
+/// Arguments:
+/// - `number`: The syscall number.
+///
+/// Returns:
+/// - `Some(&SyscallSpec)`: The syscall specification.
+/// - `None`: Not a reserved syscall (host-defined, or unknown).
+pub fn syscall_spec(number: i32) -> Option<&'static SyscallSpec> {
+    SYSCALLS.iter().find(|spec| spec.number == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syscalls() {
+        for (i, spec) in SYSCALLS.iter().enumerate() {
+            // Reserved numbers, no duplicates
+            assert!(spec.number < 0, "{}", spec.name);
+            assert!(spec.args.len() <= SYSCALL_ARGS, "{}", spec.name);
+            assert!(
+                SYSCALLS[i + 1..]
+                    .iter()
+                    .all(|other| other.number != spec.number && other.name != spec.name),
+                "{}",
+                spec.name
+            );
+            assert_eq!(syscall_spec(spec.number), Some(spec));
+        }
+
+        assert_eq!(syscall_spec(0), None);
+        assert_eq!(syscall_spec(93), None);
+    }
+
+    #[test]
+    fn test_image_abi_version() {
+        let mut image = [0x13, 0x00, 0x00, 0x00].to_vec();
+        assert_eq!(image_abi_version(&image), None);
+        assert_eq!(image_abi_version(&[]), None);
+
+        image.extend_from_slice(&abi_trailer());
+        assert_eq!(image_abi_version(&image), Some(ABI_VERSION));
+        assert_eq!(image_abi_version(&abi_trailer()), Some(ABI_VERSION));
+
+        assert!(abi_compatible(ABI_VERSION));
+        assert!(!abi_compatible(ABI_VERSION + 1));
+        assert!(!abi_compatible(MIN_ABI_VERSION - 1));
+    }
+
+    #[test]
+    fn test_link_table() {
+        assert_eq!(symbol_hash(""), 0x811C_9DC5);
+        assert_eq!(symbol_hash("a"), 0xE40C_292C);
+
+        let mut table = [0; 24];
+        assert_eq!(
+            write_link_table(
+                EXPORT_TABLE_MAGIC,
+                &[(1, 0x10), (2, 0x20)],
+                &mut table[..23]
+            ),
+            None
+        );
+        assert_eq!(
+            write_link_table(EXPORT_TABLE_MAGIC, &[(1, 0x10), (2, 0x20)], &mut table),
+            Some(link_table_size(2))
+        );
+        assert_eq!(
+            table,
+            [
+                1, 0, 0, 0, 0x10, 0, 0, 0, 2, 0, 0, 0, 0x20, 0, 0, 0, 2, 0, 0, 0, b'E', b'M', b'B',
+                b'X'
+            ]
+        );
+    }
+}
@@ -0,0 +1,613 @@
+//! Test Utilities Module
+//!
+//! Helpers for writing integration tests for guest firmware: run a guest image until it halts,
+//! then assert on the final register values, memory ranges and syscall sequence.
+//! Failed assertions panic with a diff of the expected and actual values.
+//!
+//! Example:
+//! ```
+//! use embive::{
+//!     interpreter::{memory::Memory, registers::CPURegister},
+//!     test_utils::GuestTest,
+//! };
+//!
+//! let elf = include_bytes!("../tests/app.elf");
+//!
+//! let run = GuestTest::from_elf(elf, 4096)
+//!     .unwrap()
+//!     .syscall(|nr, args, memory| {
+//!         Ok(match nr {
+//!             // Add two numbers
+//!             1 => Ok(args[0] + args[1]),
+//!             // Load from RAM
+//!             2 => Ok(memory.load_u32(args[0] as u32)? as i32),
+//!             _ => Err(2.try_into().unwrap()),
+//!         })
+//!     })
+//!     .interrupt_on_wait(Some(10))
+//!     .run();
+//!
+//! run.assert_halted();
+//! run.assert_registers(&[(CPURegister::A0, 0), (CPURegister::A1, 30)]);
+//! run.assert_syscalls(&[2, 1]);
+//! ```
+//!
+//! The riscv-tests runner is also available, to verify changes against the upstream suites (check [`run_riscv_suite`]).
+//!
+//! Guest runs can be perturbed with seeded random preemption, interrupts and syscall delays (check [`Chaos`]).
+mod chaos;
+mod riscv_tests;
+
+use core::{fmt::Write, num::NonZeroI32};
+use std::{boxed::Box, string::String, vec, vec::Vec};
+
+use crate::{
+    interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        registers::{CPURegister, CPURegisters},
+        Config, Error, ExitReason, Interpreter, State, SYSCALL_ARGS,
+    },
+    transpiler::{self, transpile_elf_vec},
+};
+
+#[doc(inline)]
+pub use chaos::{
+    Chaos, ChaosRng, DEFAULT_CHAOS_INTERRUPT_RATE, DEFAULT_CHAOS_MAX_FUEL,
+    DEFAULT_CHAOS_MAX_SYSCALL_DELAY,
+};
+#[doc(inline)]
+pub use riscv_tests::{
+    run_riscv_suite, run_riscv_test, RiscvTestError, RISCV_TEST_EXIT_SYSCALL, RISCV_TEST_RAM_SIZE,
+};
+
+/// Default instruction budget of a guest test run (check [`GuestTest::max_instructions`]).
+pub const DEFAULT_MAX_INSTRUCTIONS: u32 = 10_000_000;
+
+/// Syscall handler of a guest test (check [`crate::interpreter::Interpreter::syscall`]).
+pub type TestSyscallHandler = Box<
+    dyn FnMut(
+        i32,
+        &[i32; SYSCALL_ARGS],
+        &mut SliceMemory<'_>,
+    ) -> Result<Result<i32, NonZeroI32>, Error>,
+>;
+
+/// Syscall done by the guest during a test run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyscallRecord {
+    /// Syscall number (`a7`).
+    pub nr: i32,
+    /// Syscall arguments (`a0` to `a6`).
+    pub args: [i32; SYSCALL_ARGS],
+    /// Syscall result (value or error code).
+    pub result: Result<i32, NonZeroI32>,
+}
+
+/// Guest Test Builder
+///
+/// Owns the guest code and RAM, running the guest until it halts (check [`GuestTest::run`]).
+/// Syscalls succeed with `0` by default, `wfi` instructions continue execution (or trigger an interrupt,
+/// check [`GuestTest::interrupt_on_wait`]).
+pub struct GuestTest {
+    code: Vec<u8>,
+    ram: Vec<u8>,
+    program_counter: u32,
+    stack_pointer: Option<u32>,
+    max_instructions: u32,
+    interrupt: Option<i32>,
+    syscall: Option<TestSyscallHandler>,
+    chaos: Option<Chaos>,
+}
+
+impl core::fmt::Debug for GuestTest {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GuestTest")
+            .field("code", &self.code.len())
+            .field("ram", &self.ram.len())
+            .field("program_counter", &self.program_counter)
+            .field("stack_pointer", &self.stack_pointer)
+            .field("max_instructions", &self.max_instructions)
+            .field("interrupt", &self.interrupt)
+            .field("syscall", &self.syscall.is_some())
+            .field("chaos", &self.chaos)
+            .finish()
+    }
+}
+
+impl GuestTest {
+    /// Create a guest test from transpiled code.
+    ///
+    /// Arguments:
+    /// - `code`: Transpiled code (Embive format).
+    /// - `ram_size`: Size of the guest RAM, in bytes (zero-initialized).
+    pub fn new(code: Vec<u8>, ram_size: usize) -> Self {
+        GuestTest {
+            code,
+            ram: vec![0; ram_size],
+            program_counter: 0,
+            stack_pointer: None,
+            max_instructions: DEFAULT_MAX_INSTRUCTIONS,
+            interrupt: None,
+            syscall: None,
+            chaos: None,
+        }
+    }
+
+    /// Create a guest test from a RISC-V ELF image (transpiled to the Embive format).
+    ///
+    /// Arguments:
+    /// - `elf`: The RISC-V ELF file.
+    /// - `ram_size`: Size of the guest RAM, in bytes (zero-initialized).
+    ///
+    /// Returns:
+    /// - `Ok(GuestTest)`: Success, guest test ready to run.
+    /// - `Err(transpiler::Error)`: Failed to transpile the ELF.
+    pub fn from_elf(elf: &[u8], ram_size: usize) -> Result<Self, transpiler::Error> {
+        Ok(Self::new(transpile_elf_vec(elf)?, ram_size))
+    }
+
+    /// Set the entry point (initial program counter). Default: `0`.
+    pub fn program_counter(mut self, program_counter: u32) -> Self {
+        self.program_counter = program_counter;
+        self
+    }
+
+    /// Set the initial stack pointer (`sp`). Default: not set (0).
+    pub fn stack_pointer(mut self, stack_pointer: u32) -> Self {
+        self.stack_pointer = Some(stack_pointer);
+        self
+    }
+
+    /// Set the instruction budget, the run fails if the guest doesn't halt before it (`0` for no budget).
+    /// Default: [`DEFAULT_MAX_INSTRUCTIONS`].
+    pub fn max_instructions(mut self, max_instructions: u32) -> Self {
+        self.max_instructions = max_instructions;
+        self
+    }
+
+    /// Trigger an interrupt with the given value when the guest waits (`wfi`). Default: `None` (continue).
+    pub fn interrupt_on_wait(mut self, value: Option<i32>) -> Self {
+        self.interrupt = value;
+        self
+    }
+
+    /// Set the syscall handler (check [`crate::interpreter::Interpreter::syscall`]). Default: always `Ok(0)`.
+    pub fn syscall<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(
+                i32,
+                &[i32; SYSCALL_ARGS],
+                &mut SliceMemory<'_>,
+            ) -> Result<Result<i32, NonZeroI32>, Error>
+            + 'static,
+    {
+        self.syscall = Some(Box::new(handler));
+        self
+    }
+
+    /// Perturb the run with seeded random preemption, interrupts and syscall delays (check [`Chaos`]).
+    /// Default: `None` (the guest runs undisturbed).
+    pub fn chaos(mut self, chaos: Chaos) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Run the guest until it halts (`ebreak` or exit syscall) or panics.
+    ///
+    /// Panics if the interpreter fails (e.g. invalid instruction) or the instruction budget is exhausted.
+    ///
+    /// Returns:
+    /// - `GuestRun`: The final guest state, to assert on.
+    #[track_caller]
+    pub fn run(mut self) -> GuestRun {
+        let mut syscalls = Vec::new();
+        let mut memory = SliceMemory::new(&self.code, &mut self.ram);
+        let config = Config::default().with_max_instructions(self.max_instructions as u64);
+        let mut interpreter = Interpreter::with_config(&mut memory, 0, config);
+        interpreter.program_counter = self.program_counter;
+        if let Some(sp) = self.stack_pointer {
+            interpreter.registers.cpu.set_sp(sp as i32);
+        }
+
+        let mut syscall = |nr: i32, args: &[i32; SYSCALL_ARGS], memory: &mut SliceMemory<'_>| {
+            let result = match self.syscall.as_mut() {
+                Some(handler) => handler(nr, args, memory)?,
+                None => Ok(0),
+            };
+            syscalls.push(SyscallRecord {
+                nr,
+                args: *args,
+                result,
+            });
+            Ok::<_, Error>(result)
+        };
+
+        // Failures of chaos runs report the seed, to replay them
+        let seed = match self.chaos.as_ref().and_then(Chaos::seed) {
+            Some(seed) => std::format!(" (chaos seed {seed:#x})"),
+            None => String::new(),
+        };
+
+        let state = loop {
+            // Preempt at random points, within the instruction budget
+            if let Some(chaos) = self.chaos.as_mut() {
+                let remaining = interpreter.remaining_instructions().unwrap_or(u64::MAX);
+                let fuel = chaos.fuel(remaining.min(u32::MAX as u64) as u32);
+                interpreter.set_instruction_limit(fuel);
+            }
+
+            let state = interpreter.run().and_then(|state| match state {
+                State::Called => {
+                    interpreter.syscall(&mut syscall)?;
+                    if let Some(chaos) = self.chaos.as_mut() {
+                        interpreter.consume_instructions(chaos.syscall_delay());
+                    }
+                    Ok(state)
+                }
+                State::Waiting => match self.interrupt {
+                    Some(value) => interpreter.interrupt(value).map(|_| state),
+                    None => Ok(state),
+                },
+                // Preempted, inject an interrupt (ignored if disabled by the guest)
+                State::Running => match self.chaos.as_mut().and_then(Chaos::interrupt) {
+                    Some(value) => match interpreter.interrupt(value) {
+                        Err(Error::InterruptNotEnabled) => Ok(state),
+                        result => result.map(|_| state),
+                    },
+                    None => Ok(state),
+                },
+                _ => Ok(state),
+            });
+
+            match state {
+                Ok(
+                    State::Running
+                    | State::Called
+                    | State::Waiting
+                    | State::Yielded
+                    | State::Breakpoint
+                    | State::Hooked(_),
+                ) => {}
+                Ok(State::Halted {
+                    reason: ExitReason::InstructionLimit,
+                }) => panic!(
+                    "guest did not halt after {} instructions (pc: {:#010x}){seed}",
+                    self.max_instructions, interpreter.program_counter
+                ),
+                Ok(state) => break state,
+                Err(error) => panic!("guest failed: {error}{seed}"),
+            }
+        };
+
+        let program_counter = interpreter.program_counter;
+        let registers = interpreter.registers.cpu;
+
+        GuestRun {
+            code: self.code,
+            ram: self.ram,
+            state,
+            program_counter,
+            registers,
+            syscalls,
+        }
+    }
+}
+
+/// Final guest state of a test run (check [`GuestTest::run`]).
+#[derive(Debug)]
+pub struct GuestRun {
+    code: Vec<u8>,
+    ram: Vec<u8>,
+    state: State,
+    program_counter: u32,
+    registers: CPURegisters,
+    syscalls: Vec<SyscallRecord>,
+}
+
+impl GuestRun {
+    /// Get the final interpreter state ([`State::Halted`] or [`State::Panicked`]).
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Get the final program counter.
+    pub fn program_counter(&self) -> u32 {
+        self.program_counter
+    }
+
+    /// Get the final CPU registers.
+    pub fn registers(&self) -> &CPURegisters {
+        &self.registers
+    }
+
+    /// Get the syscalls done by the guest, in order.
+    pub fn syscalls(&self) -> &[SyscallRecord] {
+        &self.syscalls
+    }
+
+    /// Get the final guest memory (code and RAM).
+    pub fn memory(&mut self) -> SliceMemory<'_> {
+        SliceMemory::new(&self.code, &mut self.ram)
+    }
+
+    /// Assert that the guest halted (`ebreak` or exit syscall), showing the panic message otherwise.
+    #[track_caller]
+    pub fn assert_halted(&self) {
+        if let State::Panicked { msg_ptr, len } = self.state {
+            let message = self
+                .bytes(msg_ptr, len as usize)
+                .map(String::from_utf8_lossy)
+                .unwrap_or_default();
+            panic!("guest panicked: {message}");
+        }
+    }
+
+    /// Assert that the guest exited with an exit code (check [`crate::interpreter::EXIT_SYSCALL`]).
+    ///
+    /// Arguments:
+    /// - `expected`: Expected exit code.
+    #[track_caller]
+    pub fn assert_exit_code(&self, expected: i32) {
+        self.assert_halted();
+        match self.state {
+            State::Halted {
+                reason: ExitReason::ExitSyscall(code),
+            } if code == expected => {}
+            State::Halted {
+                reason: ExitReason::ExitSyscall(code),
+            } => panic!("exit code mismatch: expected {expected}, actual {code}"),
+            state => panic!("guest did not exit (expected exit code {expected}): {state:?}"),
+        }
+    }
+
+    /// Assert the final values of CPU registers.
+    ///
+    /// Arguments:
+    /// - `expected`: Registers and their expected values.
+    #[track_caller]
+    pub fn assert_registers(&self, expected: &[(CPURegister, i32)]) {
+        let mut diff = String::new();
+        for &(register, value) in expected {
+            let actual = self.registers.inner[register as usize];
+            if actual != value {
+                // Writing to a String never fails
+                let _ = writeln!(
+                    diff,
+                    "  {:<6} expected {value:#010x} ({value}), actual {actual:#010x} ({actual})",
+                    std::format!("{register:?}").to_lowercase()
+                );
+            }
+        }
+
+        if !diff.is_empty() {
+            panic!("register mismatch:\n{diff}");
+        }
+    }
+
+    /// Assert the final values of all CPU registers (e.g. against a golden state), listing the differing ones.
+    ///
+    /// Arguments:
+    /// - `expected`: Expected CPU registers.
+    #[track_caller]
+    pub fn assert_cpu_registers(&self, expected: &CPURegisters) {
+        let mut diff = String::new();
+        for change in expected.diff(&self.registers) {
+            // Writing to a String never fails
+            let _ = writeln!(diff, "  {change}");
+        }
+
+        if !diff.is_empty() {
+            panic!("register mismatch (expected -> actual):\n{diff}");
+        }
+    }
+
+    /// Assert the final contents of a memory range (code or RAM).
+    ///
+    /// Arguments:
+    /// - `address`: Start address of the range.
+    /// - `expected`: Expected bytes.
+    #[track_caller]
+    pub fn assert_memory(&self, address: u32, expected: &[u8]) {
+        let actual = match self.bytes(address, expected.len()) {
+            Some(actual) => actual,
+            None => panic!(
+                "memory range {address:#010x}..{:#010x} is out of bounds",
+                address as usize + expected.len()
+            ),
+        };
+
+        // Hex dump of the differing rows (16 bytes each)
+        let mut diff = String::new();
+        for (row, (expected, actual)) in expected.chunks(16).zip(actual.chunks(16)).enumerate() {
+            if expected == actual {
+                continue;
+            }
+
+            let mut marks = String::new();
+            for (e, a) in expected.iter().zip(actual) {
+                marks.push_str(if e == a { "   " } else { "^^ " });
+            }
+
+            // Writing to a String never fails
+            let _ = writeln!(
+                diff,
+                "  {:#010x}  expected {}\n              actual   {}\n                       {}",
+                address as usize + row * 16,
+                hex(expected),
+                hex(actual),
+                marks.trim_end()
+            );
+        }
+
+        if !diff.is_empty() {
+            panic!("memory mismatch:\n{diff}");
+        }
+    }
+
+    /// Assert the sequence of syscall numbers done by the guest (check [`GuestRun::syscalls`] for arguments).
+    ///
+    /// Arguments:
+    /// - `expected`: Expected syscall numbers, in order.
+    #[track_caller]
+    pub fn assert_syscalls(&self, expected: &[i32]) {
+        let actual: Vec<i32> = self.syscalls.iter().map(|syscall| syscall.nr).collect();
+        if actual == expected {
+            return;
+        }
+
+        let mut diff = String::new();
+        for i in 0..expected.len().max(actual.len()) {
+            // Writing to a String never fails
+            let _ = match (expected.get(i), actual.get(i)) {
+                (Some(e), Some(a)) if e == a => writeln!(diff, "  #{i}: {a}"),
+                (Some(e), Some(a)) => writeln!(diff, "! #{i}: expected {e}, actual {a}"),
+                (Some(e), None) => writeln!(diff, "- #{i}: expected {e}, missing"),
+                (None, Some(a)) => writeln!(diff, "+ #{i}: unexpected {a}"),
+                (None, None) => Ok(()),
+            };
+        }
+
+        panic!("syscall sequence mismatch:\n{diff}");
+    }
+
+    /// Get a memory range (code or RAM), if in bounds.
+    fn bytes(&self, address: u32, len: usize) -> Option<&[u8]> {
+        let (region, offset) = if address >= RAM_OFFSET {
+            (&self.ram, (address - RAM_OFFSET) as usize)
+        } else {
+            (&self.code, address as usize)
+        };
+        region.get(offset..offset.checked_add(len)?)
+    }
+}
+
+/// Format bytes as space-separated hex.
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for byte in bytes {
+        // Writing to a String never fails
+        let _ = write!(out, "{byte:02x} ");
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Code: li a0, 42; sw a0, 0(sp); ecall; ebreak (already transpiled)
+    fn code() -> Vec<u8> {
+        let mut code = vec![
+            0x13, 0x05, 0xa0, 0x02, // li    a0, 42
+            0x23, 0x20, 0xa1, 0x00, // sw    a0, 0(sp)
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x73, 0x00, 0x10, 0x00, // ebreak
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        code
+    }
+
+    fn run() -> GuestRun {
+        GuestTest::new(code(), 16)
+            .stack_pointer(RAM_OFFSET + 4)
+            .syscall(|_, args, _| Ok(Ok(args[0] + 1)))
+            .run()
+    }
+
+    #[test]
+    fn test_guest_run() {
+        let run = run();
+
+        run.assert_halted();
+        run.assert_registers(&[(CPURegister::A0, 0), (CPURegister::A1, 43)]);
+        run.assert_memory(RAM_OFFSET + 4, &[42, 0, 0, 0]);
+        run.assert_syscalls(&[0]);
+        assert_eq!(run.syscalls()[0].result, Ok(43));
+        assert_eq!(run.program_counter(), 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "a1     expected 0x0000002a (42), actual 0x0000002b (43)")]
+    fn test_register_mismatch() {
+        run().assert_registers(&[(CPURegister::A1, 42)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a1: 0x0000002a (42) -> 0x0000002b (43)")]
+    fn test_cpu_registers_mismatch() {
+        let run = run();
+        let mut expected = *run.registers();
+        run.assert_cpu_registers(&expected);
+
+        expected.set(CPURegister::A1, 42).unwrap();
+        run.assert_cpu_registers(&expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "^^")]
+    fn test_memory_mismatch() {
+        run().assert_memory(RAM_OFFSET + 4, &[43, 0, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "+ #1: unexpected")]
+    fn test_syscall_mismatch() {
+        let run = run();
+        let mut syscalls = run.syscalls.clone();
+        syscalls.push(syscalls[0]);
+        GuestRun { syscalls, ..run }.assert_syscalls(&[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "exit code mismatch: expected 0, actual 3")]
+    fn test_exit_code() {
+        let mut code = vec![
+            0x93, 0x08, 0x90, 0xff, // li    a7, -7 (exit)
+            0x13, 0x05, 0x30, 0x00, // li    a0, 3
+            0x73, 0x00, 0x00, 0x00, // ecall
+        ];
+        crate::transpiler::transpile_raw(&mut code).unwrap();
+        let run = GuestTest::new(code, 0).run();
+        run.assert_exit_code(3);
+        assert_eq!(run.syscalls(), []);
+
+        run.assert_exit_code(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "did not halt")]
+    fn test_max_instructions() {
+        GuestTest::new(code(), 16)
+            .stack_pointer(RAM_OFFSET + 4)
+            .max_instructions(2)
+            .run();
+    }
+
+    #[test]
+    fn test_chaos_run() {
+        for seed in 0..16 {
+            let run = GuestTest::new(code(), 16)
+                .stack_pointer(RAM_OFFSET + 4)
+                .syscall(|_, args, _| Ok(Ok(args[0] + 1)))
+                .chaos(Chaos::new(seed).max_fuel(2))
+                .run();
+
+            run.assert_halted();
+            run.assert_registers(&[(CPURegister::A0, 0), (CPURegister::A1, 43)]);
+            run.assert_memory(RAM_OFFSET + 4, &[42, 0, 0, 0]);
+            run.assert_syscalls(&[0]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "(chaos seed 0x2a)")]
+    fn test_chaos_seed_reported() {
+        GuestTest::new(code(), 16)
+            .stack_pointer(RAM_OFFSET + 4)
+            .syscall(|_, args, _| Ok(Ok(args[0] + 1)))
+            .max_instructions(2)
+            .chaos(Chaos::new(42).max_fuel(1))
+            .run();
+    }
+}
@@ -0,0 +1,181 @@
+//! Chaos Testing Module
+//!
+//! Seeded perturbations of a guest test run (check [`crate::test_utils::GuestTest::chaos`]), to shake out
+//! concurrency bugs in guest code (e.g. RTOS interrupt handling) deterministically:
+//! - Limited fuel: the guest is preempted after a random number of instructions.
+//! - Interrupts: injected at random preemption points (when enabled by the guest).
+//! - Syscall delays: syscalls take a random number of instructions (virtual time advances).
+//!
+//! Failures report the seed, so a run can be replayed with [`Chaos::new`].
+use std::boxed::Box;
+
+use crate::interpreter::random::splitmix64;
+
+/// Chaos random source, returning a new random value on each call.
+pub type ChaosRng = Box<dyn FnMut() -> u64>;
+
+/// Default maximum fuel (instructions per slice, check [`Chaos::max_fuel`]).
+pub const DEFAULT_CHAOS_MAX_FUEL: u32 = 64;
+
+/// Default interrupt rate (check [`Chaos::interrupt_rate`]).
+pub const DEFAULT_CHAOS_INTERRUPT_RATE: u32 = 8;
+
+/// Default maximum syscall delay, in instructions (check [`Chaos::max_syscall_delay`]).
+pub const DEFAULT_CHAOS_MAX_SYSCALL_DELAY: u32 = 256;
+
+/// Chaos Testing Configuration
+///
+/// Random perturbations applied by [`crate::test_utils::GuestTest::run`]. The same seed (or random source)
+/// always produces the same perturbations.
+pub struct Chaos {
+    rng: ChaosRng,
+    seed: Option<u64>,
+    max_fuel: u32,
+    interrupt_rate: u32,
+    interrupt_value: i32,
+    max_syscall_delay: u32,
+}
+
+impl core::fmt::Debug for Chaos {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Chaos")
+            .field("seed", &self.seed)
+            .field("max_fuel", &self.max_fuel)
+            .field("interrupt_rate", &self.interrupt_rate)
+            .field("interrupt_value", &self.interrupt_value)
+            .field("max_syscall_delay", &self.max_syscall_delay)
+            .finish()
+    }
+}
+
+impl Chaos {
+    /// Create a chaos configuration driven by a seed (SplitMix64 generator).
+    ///
+    /// Arguments:
+    /// - `seed`: Generator seed, reported on failures to replay the run.
+    pub fn new(seed: u64) -> Self {
+        let mut state = seed;
+        let mut chaos = Self::with_rng(Box::new(move || splitmix64(&mut state)));
+        chaos.seed = Some(seed);
+        chaos
+    }
+
+    /// Create a chaos configuration driven by a custom random source (e.g. a fuzzer input).
+    ///
+    /// Arguments:
+    /// - `rng`: Random source, should be deterministic for reproducible runs.
+    pub fn with_rng(rng: ChaosRng) -> Self {
+        Chaos {
+            rng,
+            seed: None,
+            max_fuel: DEFAULT_CHAOS_MAX_FUEL,
+            interrupt_rate: DEFAULT_CHAOS_INTERRUPT_RATE,
+            interrupt_value: 0,
+            max_syscall_delay: DEFAULT_CHAOS_MAX_SYSCALL_DELAY,
+        }
+    }
+
+    /// Set the maximum fuel: the guest is preempted after `1..=max_fuel` instructions (`0` disables preemption).
+    /// Default: [`DEFAULT_CHAOS_MAX_FUEL`].
+    pub fn max_fuel(mut self, max_fuel: u32) -> Self {
+        self.max_fuel = max_fuel;
+        self
+    }
+
+    /// Set the interrupt rate: an interrupt is injected at one in `rate` preemption points, on average
+    /// (`0` disables interrupts). Default: [`DEFAULT_CHAOS_INTERRUPT_RATE`].
+    pub fn interrupt_rate(mut self, rate: u32) -> Self {
+        self.interrupt_rate = rate;
+        self
+    }
+
+    /// Set the value passed to injected interrupts (`mtval`). Default: `0`.
+    pub fn interrupt_value(mut self, value: i32) -> Self {
+        self.interrupt_value = value;
+        self
+    }
+
+    /// Set the maximum syscall delay: syscalls take `0..=max_delay` instructions (`0` disables delays).
+    /// Default: [`DEFAULT_CHAOS_MAX_SYSCALL_DELAY`].
+    pub fn max_syscall_delay(mut self, max_delay: u32) -> Self {
+        self.max_syscall_delay = max_delay;
+        self
+    }
+
+    /// Get the seed (`None` with a custom random source).
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Get a random value in `0..bound` (`bound` must not be `0`).
+    fn below(&mut self, bound: u32) -> u32 {
+        ((self.rng)() % bound as u64) as u32
+    }
+
+    /// Get the fuel of the next slice, at most `remaining`.
+    pub(crate) fn fuel(&mut self, remaining: u32) -> u32 {
+        match self.max_fuel {
+            0 => remaining,
+            max_fuel => remaining.min(1 + self.below(max_fuel)),
+        }
+    }
+
+    /// Get the value of an interrupt to inject at a preemption point, if any.
+    pub(crate) fn interrupt(&mut self) -> Option<i32> {
+        (self.interrupt_rate > 0 && self.below(self.interrupt_rate) == 0)
+            .then_some(self.interrupt_value)
+    }
+
+    /// Get the delay of a syscall, in instructions.
+    pub(crate) fn syscall_delay(&mut self) -> u32 {
+        match self.max_syscall_delay {
+            0 => 0,
+            max_delay => self.below(max_delay.saturating_add(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(chaos: &mut Chaos) -> Vec<(u32, Option<i32>, u32)> {
+        (0..64)
+            .map(|_| {
+                (
+                    chaos.fuel(u32::MAX),
+                    chaos.interrupt(),
+                    chaos.syscall_delay(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_seed() {
+        assert_eq!(sample(&mut Chaos::new(7)), sample(&mut Chaos::new(7)));
+        assert_ne!(sample(&mut Chaos::new(7)), sample(&mut Chaos::new(8)));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut chaos = Chaos::new(1)
+            .max_fuel(4)
+            .interrupt_value(3)
+            .max_syscall_delay(2);
+        for (fuel, interrupt, delay) in sample(&mut chaos) {
+            assert!((1..=4).contains(&fuel));
+            assert!(matches!(interrupt, None | Some(3)));
+            assert!(delay <= 2);
+        }
+        assert_eq!(chaos.fuel(0), 0);
+
+        let mut chaos = Chaos::new(1)
+            .max_fuel(0)
+            .interrupt_rate(0)
+            .max_syscall_delay(0);
+        assert!(sample(&mut chaos)
+            .iter()
+            .all(|&sample| sample == (u32::MAX, None, 0)));
+    }
+}
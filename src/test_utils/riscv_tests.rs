@@ -0,0 +1,214 @@
+//! riscv-tests Runner Module
+//!
+//! Runs [riscv-tests](https://github.com/riscv-software-src/riscv-tests) binaries (as built by
+//! [embive-tests](https://github.com/embive/embive-tests)), so forks can verify their changes against
+//! the same suites as upstream (`rv32ui`, `rv32um`, `rv32ua`, `rv32uc`).
+//!
+//! The machine-mode binaries (`tests/riscv/rv32mi`) are not the upstream `rv32mi` suite: they are hand-written
+//! smoke tests in the same format (sources in `tests/riscv/rv32mi/src`), covering the traps and CSRs Embive
+//! implements. Unlike upstream, machine-mode `ecall`s are host syscalls (Embive's syscall interface).
+//!
+//! Each test is loaded into RAM and executed from [`RAM_OFFSET`]. The result is reported through
+//! the exit syscall ([`RISCV_TEST_EXIT_SYSCALL`]): `a0` is `0` on success, otherwise the failed test
+//! number is `a0 >> 1`.
+//!
+//! Tests run with [`riscv_test_config`]: user mode, machine-mode exception traps and `ebreak` traps are enabled, as
+//! expected by the machine-mode smoke tests (traps to `mtvec`). The run stops at the exit syscall.
+use core::{
+    cell::Cell,
+    fmt::{Display, Formatter},
+    num::NonZeroI32,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    string::String,
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    interpreter::{
+        memory::{SliceMemory, RAM_OFFSET},
+        Config, EbreakMode, Error, Interpreter, Runner, State, SYSCALL_ARGS,
+    },
+    transpiler::{self, transpile_elf},
+};
+
+/// Guest RAM size of a riscv-tests run, in bytes.
+pub const RISCV_TEST_RAM_SIZE: usize = 32 * 1024;
+
+/// Exit syscall number used by the riscv-tests environment.
+pub const RISCV_TEST_EXIT_SYSCALL: i32 = 93;
+
+/// riscv-tests Runner Error
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RiscvTestError {
+    /// Failed to read a test file or directory.
+    Io(io::Error),
+    /// Failed to transpile the test.
+    Transpiler(transpiler::Error),
+    /// Interpreter error while running the test.
+    Interpreter(Error),
+    /// Test case failed. The test case number is provided.
+    Failed(i32),
+    /// Guest panicked. The panic message is provided.
+    Panicked(String),
+    /// Test halted without calling the exit syscall.
+    NoExit,
+    /// Test called an unknown syscall. The syscall number is provided.
+    UnknownSyscall(i32),
+}
+
+impl core::error::Error for RiscvTestError {}
+
+impl Display for RiscvTestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RiscvTestError::Io(e) => write!(f, "failed to read test: {e}"),
+            RiscvTestError::Transpiler(e) => write!(f, "failed to transpile test: {e}"),
+            RiscvTestError::Interpreter(e) => write!(f, "failed to run test: {e}"),
+            RiscvTestError::Failed(test) => write!(f, "test case {test} failed"),
+            RiscvTestError::Panicked(msg) => write!(f, "guest panicked: {msg}"),
+            RiscvTestError::NoExit => write!(f, "test halted without calling the exit syscall"),
+            RiscvTestError::UnknownSyscall(nr) => write!(f, "unknown syscall {nr}"),
+        }
+    }
+}
+
+impl From<io::Error> for RiscvTestError {
+    fn from(e: io::Error) -> Self {
+        RiscvTestError::Io(e)
+    }
+}
+
+impl From<transpiler::Error> for RiscvTestError {
+    fn from(e: transpiler::Error) -> Self {
+        RiscvTestError::Transpiler(e)
+    }
+}
+
+impl From<Error> for RiscvTestError {
+    fn from(e: Error) -> Self {
+        RiscvTestError::Interpreter(e)
+    }
+}
+
+/// Get the interpreter configuration of riscv-tests runs (check [`run_riscv_test`]).
+///
+/// All extensions, user mode, machine-mode exception traps ([`Config::machine_traps`]) and `ebreak` traps
+/// ([`EbreakMode::Trap`]) are enabled.
+pub const fn riscv_test_config() -> Config {
+    Config::new()
+        .with_user_mode(true)
+        .with_machine_traps(true)
+        .with_ebreak(EbreakMode::Trap)
+}
+
+/// Run a single riscv-tests binary.
+///
+/// Arguments:
+/// - `elf`: The test ELF file.
+///
+/// Returns:
+/// - `Ok(())`: All test cases passed.
+/// - `Err(RiscvTestError)`: The test failed (check [`RiscvTestError`]).
+pub fn run_riscv_test(elf: &[u8]) -> Result<(), RiscvTestError> {
+    // Load binary into RAM
+    let mut ram = vec![0; RISCV_TEST_RAM_SIZE];
+    transpile_elf(elf, &mut ram)?;
+
+    let mut memory = SliceMemory::new(&[], &mut ram);
+    let mut interpreter = Interpreter::with_config(&mut memory, 0, riscv_test_config());
+
+    // Set program counter to RAM (code start)
+    interpreter.program_counter = RAM_OFFSET;
+
+    let exit = Cell::new(None);
+    let unknown = Cell::new(None);
+    let mut syscall = |nr: i32, args: &[i32; SYSCALL_ARGS], _memory: &mut SliceMemory<'_>| {
+        if nr == RISCV_TEST_EXIT_SYSCALL {
+            exit.set(exit.get().or(Some(args[0])));
+        } else {
+            unknown.set(unknown.get().or(Some(nr)));
+        }
+        Ok::<Result<i32, NonZeroI32>, Error>(Ok(0))
+    };
+
+    // Stop at the exit syscall (`ebreak` traps to `mtvec` instead of halting)
+    let mut runner = Runner::new(&mut interpreter).syscall(&mut syscall);
+    let state = loop {
+        let state = runner.poll()?;
+        if exit.get().is_some() || matches!(state, State::Halted { .. } | State::Panicked { .. }) {
+            break state;
+        }
+    };
+
+    if let State::Panicked { msg_ptr, len } = state {
+        let msg = interpreter.panic_message(msg_ptr, len)?;
+        return Err(RiscvTestError::Panicked(msg.into()));
+    }
+
+    match (unknown.get(), exit.get()) {
+        (Some(nr), _) => Err(RiscvTestError::UnknownSyscall(nr)),
+        (None, Some(0)) => Ok(()),
+        (None, Some(code)) => Err(RiscvTestError::Failed(code >> 1)),
+        (None, None) => Err(RiscvTestError::NoExit),
+    }
+}
+
+/// Run all riscv-tests binaries (`*.elf`) of a directory (e.g. `rv32ui`), in name order.
+///
+/// Arguments:
+/// - `dir`: The suite directory.
+///
+/// Returns:
+/// - `Ok(usize)`: All tests passed, returns the number of tests run.
+/// - `Err((PathBuf, RiscvTestError))`: A test failed (path of the test and error).
+pub fn run_riscv_suite<P: AsRef<Path>>(dir: P) -> Result<usize, (PathBuf, RiscvTestError)> {
+    let dir = dir.as_ref();
+    let mut tests = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| (dir.to_path_buf(), e.into()))?;
+    tests.retain(|path| path.extension().is_some_and(|ext| ext == "elf"));
+    tests.sort();
+
+    for test in &tests {
+        fs::read(test)
+            .map_err(RiscvTestError::from)
+            .and_then(|elf| run_riscv_test(&elf))
+            .map_err(|e| (test.clone(), e))?;
+    }
+
+    Ok(tests.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_riscv_test() {
+        let elf = include_bytes!("../../tests/riscv/rv32ui/simple.elf");
+        assert!(run_riscv_test(elf).is_ok());
+    }
+
+    #[test]
+    fn test_run_riscv_suite() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("tests/riscv/rv32uc");
+        assert_eq!(run_riscv_suite(dir).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_run_riscv_suite_machine() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("tests/riscv/rv32mi");
+        assert_eq!(run_riscv_suite(dir).unwrap(), 6);
+    }
+}
@@ -11,8 +11,37 @@
 //!         - Write the section data to the output buffer (handling the alignment and address translation)
 //!         - If the section has the flag `Execinstr`:
 //!            - Convert the RISC-V instructions to Embive instructions
+//!
+//! Unsupported 32-bit instructions (e.g. vendor-specific encodings) can be mapped to custom Embive
+//! instructions or syscall shims through a handler (check [`Config::with_custom_handler`]).
+//!
+//! Thread-local storage (`PT_TLS` segment) is not allocated by the transpiler, as each interpreter
+//! instance needs its own copy. Use [`tls_segment`] to locate the TLS initialization image and
+//! [`crate::interpreter::Interpreter::init_tls`] to set up a TLS block before running the code.
+//!
+//! Symbol addresses (e.g. exported functions) can be looked up with [`symbol`], so hosts don't need
+//! to hardcode addresses (check [`crate::interpreter::Interpreter::call`]).
+//!
+//! Transpiled addresses can be translated back to the original ELF addresses with the side table
+//! returned by [`pc_map`] (e.g. for breakpoints, symbolization and traces).
+//!
+//! Relocatable objects (e.g. separately compiled plugin modules) can be linked into a single image with
+//! [`link_objects`] (`alloc` feature).
+//!
+//! The instructions used by a binary (e.g. to enforce a "no atomics" policy) are reported by [`instruction_usage`].
+//!
+//! Images can be checked against the device memory (check [`Config::with_memory_map`]), described by a table of
+//! [`MemoryRegion`] or parsed from the `MEMORY` command of a linker script ([`memory_map`]).
+mod config;
 mod convert;
+mod custom;
 mod error;
+#[cfg(feature = "alloc")]
+mod link;
+mod load_immediate;
+mod memory_map;
+mod strict;
+mod usage;
 
 use core::ops::DerefMut;
 
@@ -20,17 +49,107 @@ use core::ops::DerefMut;
 use alloc::vec::Vec;
 
 use elf::{
-    abi::{EM_RISCV, SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS},
+    abi::{EM_RISCV, PT_TLS, SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS},
     endian::LittleEndian,
     file::Class,
+    parse::ParsingTable,
+    section::SectionHeader,
+    segment::ProgramHeader,
     ElfBytes,
 };
 
+#[doc(inline)]
+pub use config::Config;
+#[doc(inline)]
+pub use custom::{CustomInstruction, CustomInstructionHandler, CUSTOM_INSTRUCTIONS};
 #[doc(inline)]
 pub use error::Error;
+#[cfg(feature = "alloc")]
+#[doc(inline)]
+pub use link::{link_objects, LinkedImage, LINK_RAM_ADDRESS};
+#[cfg(feature = "alloc")]
+#[doc(inline)]
+pub use memory_map::memory_map_vec;
+#[doc(inline)]
+pub use memory_map::{memory_map, MemoryRegion};
+#[doc(inline)]
+pub use strict::StrictViolation;
+#[doc(inline)]
+pub use usage::{InstructionClass, InstructionUsage, INSTRUCTION_CLASSES};
 
+use crate::protocol::{abi_trailer, ABI_TRAILER_SIZE};
 use convert::convert;
 
+/// Thread-local storage (TLS) segment of an ELF file.
+///
+/// The TLS block is composed of an initialization image (`.tdata`), followed by zero-initialized
+/// data (`.tbss`). The initialization image is part of the transpiled binary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TlsSegment {
+    /// Address of the TLS initialization image (`.tdata`), relative to the start of the transpiled binary.
+    pub template_address: u32,
+    /// Size of the TLS initialization image, in bytes.
+    pub template_size: u32,
+    /// Total size of the TLS block (`.tdata` + `.tbss`), in bytes.
+    pub size: u32,
+    /// Required alignment of the TLS block, in bytes.
+    pub align: u32,
+}
+
+/// Mapping between a transpiled code section and its original RISC-V ELF addresses (check [`pc_map`]).
+///
+/// Example:
+/// ```
+/// use embive::transpiler::PcMapping;
+///
+/// let map = [
+///     PcMapping { address: 0x0, original_address: 0x2000_0000, size: 0x100 },
+///     PcMapping { address: 0x100, original_address: 0x2000_0200, size: 0x80 },
+/// ];
+///
+/// let original = map.iter().find_map(|m| m.original_address(0x110));
+/// assert_eq!(original, Some(0x2000_0210));
+/// let address = map.iter().find_map(|m| m.address(0x2000_0010));
+/// assert_eq!(address, Some(0x10));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PcMapping {
+    /// Start address of the section in the transpiled binary (interpreter program counter).
+    pub address: u32,
+    /// Start address of the section in the original ELF (virtual address, as in map files).
+    pub original_address: u32,
+    /// Size of the section, in bytes.
+    pub size: u32,
+}
+
+impl PcMapping {
+    /// Translate a transpiled address (e.g. program counter) to the original ELF address.
+    ///
+    /// # Arguments
+    /// - `address`: Address in the transpiled binary.
+    ///
+    /// # Returns
+    /// - `Some(u32)`: The original ELF address.
+    /// - `None`: The address is outside of this section.
+    pub fn original_address(&self, address: u32) -> Option<u32> {
+        let offset = address.checked_sub(self.address)?;
+        (offset < self.size).then(|| self.original_address + offset)
+    }
+
+    /// Translate an original ELF address (e.g. from a map file) to the transpiled address.
+    ///
+    /// # Arguments
+    /// - `original_address`: Address in the original ELF.
+    ///
+    /// # Returns
+    /// - `Some(u32)`: The address in the transpiled binary.
+    /// - `None`: The address is outside of this section.
+    pub fn address(&self, original_address: u32) -> Option<u32> {
+        let offset = original_address.checked_sub(self.original_address)?;
+        (offset < self.size).then(|| self.address + offset)
+    }
+}
+
 /// Transpile raw RISC-V instructions to Embive instructions.
 ///
 /// # Arguments
@@ -39,7 +158,21 @@ use convert::convert;
 /// # Returns
 /// - `Ok(bool)`: Transpilation was successful, returns if the code buffer needs padding.
 /// - `Err(Error)`: An error occurred during the transpilation.
+#[cfg(test)]
 pub(crate) fn transpile_raw(code: &mut [u8]) -> Result<bool, Error> {
+    transpile_raw_with_config(code, &Config::default())
+}
+
+/// Transpile raw RISC-V instructions to Embive instructions, with a custom configuration.
+///
+/// # Arguments
+/// - `code`: The raw RISC-V instructions.
+/// - `config`: The transpiler configuration.
+///
+/// # Returns
+/// - `Ok(bool)`: Transpilation was successful, returns if the code buffer needs padding.
+/// - `Err(Error)`: An error occurred during the transpilation.
+fn transpile_raw_with_config(code: &mut [u8], config: &Config<'_>) -> Result<bool, Error> {
     let code_size = code.len();
     let mut needs_padding = false;
 
@@ -55,8 +188,39 @@ pub(crate) fn transpile_raw(code: &mut [u8]) -> Result<bool, Error> {
             u32::from_le_bytes(code[i..i + 4].try_into().unwrap())
         };
 
+        // Reject reserved, hint and unsupported encodings
+        if config.strict {
+            strict::check(raw).map_err(|violation| Error::StrictViolationAt {
+                section: None,
+                offset: i,
+                instruction: if raw & 0b11 == 0b11 {
+                    raw
+                } else {
+                    raw & 0xFFFF
+                },
+                violation,
+            })?;
+        }
+
         // Convert the RISC-V instruction to Embive instruction
-        let instruction = convert(raw)?;
+        let instruction = match (convert(raw), config.custom_handler) {
+            // Custom 32-bit instruction
+            (Err(Error::InvalidInstruction(data)), Some(handler)) if data & 0b11 == 0b11 => {
+                match handler(data) {
+                    Some(custom) => custom.encode(data),
+                    None => Err(Error::InvalidInstruction(data)),
+                }
+            }
+            (result, _) => result,
+        }
+        .map_err(|e| match e {
+            Error::InvalidInstruction(instruction) => Error::InvalidInstructionAt {
+                section: None,
+                offset: i,
+                instruction,
+            },
+            e => e,
+        })?;
         let inst_bytes = instruction.data.to_le_bytes();
         let inst_size = instruction.size as usize;
 
@@ -67,6 +231,11 @@ pub(crate) fn transpile_raw(code: &mut [u8]) -> Result<bool, Error> {
         i += inst_size;
     }
 
+    // Fuse `lui` + `addi` pairs into load-immediates
+    if config.load_immediate {
+        load_immediate::fuse(code);
+    }
+
     Ok(needs_padding)
 }
 
@@ -76,11 +245,17 @@ pub(crate) fn transpile_raw(code: &mut [u8]) -> Result<bool, Error> {
 /// - `elf`: The ELF to transpile.
 /// - `output`: The output buffer to write the Embive binary format.
 /// - `append_fn`: Function to append data to the output buffer.
+/// - `config`: The transpiler configuration.
 ///
 /// # Returns
 /// - `Ok(usize)`: Transpilation was successful, returns the size of the binary.
 /// - `Err(Error)`: An error occurred during the transpilation.
-fn elf_transpiler_impl<O, F>(elf: &[u8], output: &mut O, append_fn: F) -> Result<usize, Error>
+fn elf_transpiler_impl<O, F>(
+    elf: &[u8],
+    output: &mut O,
+    append_fn: F,
+    config: &Config<'_>,
+) -> Result<usize, Error>
 where
     O: DerefMut<Target = [u8]>,
     F: Fn(&mut O, usize, &[u8]) -> Result<(), Error>,
@@ -95,6 +270,11 @@ where
         return Err(Error::InvalidPlatform);
     }
 
+    // Check the segments against the device memory
+    if let Some(regions) = config.memory_map {
+        memory_map::check_segments(&segments, regions)?;
+    }
+
     let entry = elf_bytes.ehdr.e_entry as u32;
     let mut binary_size = 0;
     let mut needs_padding = false;
@@ -146,10 +326,34 @@ where
                         }
                         append_fn(output, offset, data)?;
 
-                        // If the section has the flag `Execinstr`
-                        if (section.sh_flags as u32 & SHF_EXECINSTR) != 0 {
+                        // If the section has the flag `Execinstr` (kept as is for the source image)
+                        if (section.sh_flags as u32 & SHF_EXECINSTR) != 0 && !config.source_image {
                             // Convert the RISC-V instructions to Embive instructions
-                            needs_padding = transpile_raw(&mut output[offset..end_offset])?;
+                            needs_padding =
+                                transpile_raw_with_config(&mut output[offset..end_offset], config)
+                                    .map_err(|e| match e {
+                                        Error::InvalidInstructionAt {
+                                            offset,
+                                            instruction,
+                                            ..
+                                        } => Error::InvalidInstructionAt {
+                                            section: Some(i),
+                                            offset,
+                                            instruction,
+                                        },
+                                        Error::StrictViolationAt {
+                                            offset,
+                                            instruction,
+                                            violation,
+                                            ..
+                                        } => Error::StrictViolationAt {
+                                            section: Some(i),
+                                            offset,
+                                            instruction,
+                                            violation,
+                                        },
+                                        e => e,
+                                    })?;
                         }
 
                         break 'segment;
@@ -168,6 +372,12 @@ where
         binary_size += 2;
     }
 
+    // Add the ABI version trailer if needed
+    if config.abi_trailer {
+        append_fn(output, binary_size, &abi_trailer())?;
+        binary_size += ABI_TRAILER_SIZE;
+    }
+
     Ok::<usize, Error>(binary_size)
 }
 
@@ -181,15 +391,43 @@ where
 /// # Returns
 /// - `Ok(usize)`: Transpilation was successful, returns the size of the binary.
 /// - `Err(Error)`: An error occurred during the transpilation.
-pub fn transpile_elf(elf: &[u8], mut output: &mut [u8]) -> Result<usize, Error> {
-    elf_transpiler_impl(elf, &mut output, |output, offset, data| {
-        // Copy the data to the output buffer
-        output
-            .get_mut(offset..offset + data.len())
-            .ok_or(Error::BufferTooSmall)?
-            .copy_from_slice(data);
-        Ok(())
-    })
+pub fn transpile_elf(elf: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+    transpile_elf_with_config(elf, output, &Config::default())
+}
+
+/// Parse RISC-V ELF, extracting the binary data and converting the instructions to the Embive format.
+/// Same as [`transpile_elf`], with a custom configuration (check [`Config`]).
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+/// - `output`: The output buffer to write the Embive binary format.
+/// - `config`: The transpiler configuration.
+///
+/// # Returns
+/// - `Ok(usize)`: Transpilation was successful, returns the size of the binary.
+/// - `Err(Error)`: An error occurred during the transpilation.
+pub fn transpile_elf_with_config(
+    elf: &[u8],
+    mut output: &mut [u8],
+    config: &Config<'_>,
+) -> Result<usize, Error> {
+    elf_transpiler_impl(
+        elf,
+        &mut output,
+        |output, offset, data| {
+            // Copy the data to the output buffer
+            let available = output.len();
+            output
+                .get_mut(offset..offset + data.len())
+                .ok_or(Error::BufferTooSmall {
+                    required: offset + data.len(),
+                    available,
+                })?
+                .copy_from_slice(data);
+            Ok(())
+        },
+        config,
+    )
 }
 
 /// Parse RISC-V ELF, extracting the binary data and converting the instructions to the Embive format.
@@ -203,21 +441,256 @@ pub fn transpile_elf(elf: &[u8], mut output: &mut [u8]) -> Result<usize, Error>
 /// - `Err(Error)`: An error occurred during the transpilation.
 #[cfg(feature = "alloc")]
 pub fn transpile_elf_vec(elf: &[u8]) -> Result<Vec<u8>, Error> {
+    transpile_elf_vec_with_config(elf, &Config::default())
+}
+
+/// Parse RISC-V ELF, extracting the binary data and converting the instructions to the Embive format.
+/// Same as [`transpile_elf_vec`], with a custom configuration (check [`Config`]).
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+/// - `config`: The transpiler configuration.
+///
+/// # Returns
+/// - `Ok(Vec<u8>)`: Transpilation was successful, returns the transpiled binary.
+/// - `Err(Error)`: An error occurred during the transpilation.
+#[cfg(feature = "alloc")]
+pub fn transpile_elf_vec_with_config(elf: &[u8], config: &Config<'_>) -> Result<Vec<u8>, Error> {
     let mut output = Vec::new();
     let out_ptr = &mut output;
 
-    elf_transpiler_impl(elf, out_ptr, |output, _offset, data| {
-        // Append the data to the output buffer
-        output.extend_from_slice(data);
-        Ok(())
-    })?;
+    elf_transpiler_impl(
+        elf,
+        out_ptr,
+        |output, _offset, data| {
+            // Append the data to the output buffer
+            output.extend_from_slice(data);
+            Ok(())
+        },
+        config,
+    )?;
 
     Ok(output)
 }
 
+/// Get the thread-local storage (TLS) segment of a RISC-V ELF, if any.
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+///
+/// # Returns
+/// - `Ok(Some(TlsSegment))`: The ELF has a TLS segment.
+/// - `Ok(None)`: The ELF doesn't use thread-local storage.
+/// - `Err(Error)`: An error occurred while parsing the ELF.
+pub fn tls_segment(elf: &[u8]) -> Result<Option<TlsSegment>, Error> {
+    let elf_bytes = ElfBytes::<LittleEndian>::minimal_parse(elf)?;
+
+    // Check if the ELF is a RISC-V 32-bit ELF
+    if elf_bytes.ehdr.e_machine != EM_RISCV || elf_bytes.ehdr.class != Class::ELF32 {
+        return Err(Error::InvalidPlatform);
+    }
+
+    let segments = elf_bytes.segments().ok_or(Error::NoProgramHeader)?;
+    let entry = elf_bytes.ehdr.e_entry as u32;
+
+    Ok(segments
+        .iter()
+        // Empty TLS segments are ignored
+        .find(|segment| segment.p_type == PT_TLS && segment.p_memsz > 0)
+        .map(|segment| {
+            // Same alignment rules as the transpiled sections
+            let align = (segment.p_align as u32).max(1);
+            let template_address = (segment.p_paddr as u32 - entry).div_ceil(align) * align;
+
+            TlsSegment {
+                template_address,
+                template_size: segment.p_filesz as u32,
+                size: segment.p_memsz as u32,
+                align,
+            }
+        }))
+}
+
+/// Get the address of a symbol (e.g. an exported function) of a RISC-V ELF, if any.
+///
+/// The static symbol table (`.symtab`) is searched first, then the dynamic symbol table (`.dynsym`).
+/// Code symbols are translated to their address in the transpiled binary (same as the transpiler),
+/// other symbols (e.g. RAM data) keep their link address.
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+/// - `name`: The symbol name (unmangled names need `#[no_mangle]` or `extern "C"`).
+///
+/// # Returns
+/// - `Ok(Some(u32))`: The symbol address.
+/// - `Ok(None)`: The symbol wasn't found (or is undefined).
+/// - `Err(Error)`: An error occurred while parsing the ELF (e.g. [`Error::NoSymbolTable`] for stripped ELFs).
+pub fn symbol(elf: &[u8], name: &str) -> Result<Option<u32>, Error> {
+    let elf_bytes = ElfBytes::<LittleEndian>::minimal_parse(elf)?;
+
+    // Check if the ELF is a RISC-V 32-bit ELF
+    if elf_bytes.ehdr.e_machine != EM_RISCV || elf_bytes.ehdr.class != Class::ELF32 {
+        return Err(Error::InvalidPlatform);
+    }
+
+    let segments = elf_bytes.segments().ok_or(Error::NoProgramHeader)?;
+    let sections = elf_bytes.section_headers().ok_or(Error::NoSectionHeader)?;
+    let entry = elf_bytes.ehdr.e_entry as u32;
+
+    let tables = [elf_bytes.symbol_table()?, elf_bytes.dynamic_symbol_table()?];
+    if tables.iter().all(Option::is_none) {
+        return Err(Error::NoSymbolTable);
+    }
+
+    for (symbols, strings) in tables.into_iter().flatten() {
+        for symbol in symbols.iter() {
+            if symbol.is_undefined() || strings.get(symbol.st_name as usize)? != name {
+                continue;
+            }
+
+            let address = symbol.st_value as u32;
+            return Ok(Some(match sections.get(symbol.st_shndx as usize) {
+                // Code symbol, translate to the transpiled binary address
+                Ok(section) if (section.sh_flags as u32 & SHF_EXECINSTR) != 0 => {
+                    let offset = section_offset(&section, &segments, entry)
+                        .ok_or(Error::NoSegmentForSection(symbol.st_shndx as usize))?;
+                    offset + (address - section.sh_addr as u32)
+                }
+                // Data or absolute symbol
+                _ => address,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the program counter mapping table of a RISC-V ELF, one entry per code section.
+///
+/// Instruction sizes are preserved by the transpiler, so addresses inside a section keep their offset
+/// from the section start. This allows breakpoints, symbolization and traces to be expressed in terms
+/// of the original ELF addresses (check [`PcMapping`]).
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+/// - `function`: Called for each code section mapping (e.g. to store it in a table).
+///
+/// # Returns
+/// - `Ok(())`: Success, all mappings were emitted.
+/// - `Err(Error)`: An error occurred while parsing the ELF.
+pub fn pc_map<F: FnMut(PcMapping)>(elf: &[u8], mut function: F) -> Result<(), Error> {
+    let elf_bytes = ElfBytes::<LittleEndian>::minimal_parse(elf)?;
+
+    // Check if the ELF is a RISC-V 32-bit ELF
+    if elf_bytes.ehdr.e_machine != EM_RISCV || elf_bytes.ehdr.class != Class::ELF32 {
+        return Err(Error::InvalidPlatform);
+    }
+
+    let segments = elf_bytes.segments().ok_or(Error::NoProgramHeader)?;
+    let sections = elf_bytes.section_headers().ok_or(Error::NoSectionHeader)?;
+    let entry = elf_bytes.ehdr.e_entry as u32;
+
+    for (i, section) in sections.iter().enumerate() {
+        // Non-empty code sections (same as the transpiler)
+        if section.sh_type != SHT_PROGBITS
+            || (section.sh_flags as u32 & (SHF_ALLOC | SHF_EXECINSTR)) != SHF_ALLOC | SHF_EXECINSTR
+            || section.sh_size == 0
+        {
+            continue;
+        }
+
+        let address =
+            section_offset(&section, &segments, entry).ok_or(Error::NoSegmentForSection(i))?;
+        function(PcMapping {
+            address,
+            original_address: section.sh_addr as u32,
+            size: section.sh_size as u32,
+        });
+    }
+
+    Ok(())
+}
+
+/// Get the program counter mapping table of a RISC-V ELF, one entry per code section.
+/// Same as [`pc_map`], with the table dynamically allocated and returned as a `Vec<PcMapping>`.
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+///
+/// # Returns
+/// - `Ok(Vec<PcMapping>)`: The mapping table.
+/// - `Err(Error)`: An error occurred while parsing the ELF.
+#[cfg(feature = "alloc")]
+pub fn pc_map_vec(elf: &[u8]) -> Result<Vec<PcMapping>, Error> {
+    let mut map = Vec::new();
+    pc_map(elf, |mapping| map.push(mapping))?;
+    Ok(map)
+}
+
+/// Get the instruction usage of a RISC-V ELF (number of instructions of each class in its code sections).
+///
+/// # Arguments
+/// - `elf`: The RISC-V ELF file.
+///
+/// # Returns
+/// - `Ok(InstructionUsage)`: The instruction usage report.
+/// - `Err(Error)`: An error occurred while parsing the ELF.
+pub fn instruction_usage(elf: &[u8]) -> Result<InstructionUsage, Error> {
+    let elf_bytes = ElfBytes::<LittleEndian>::minimal_parse(elf)?;
+
+    // Check if the ELF is a RISC-V 32-bit ELF
+    if elf_bytes.ehdr.e_machine != EM_RISCV || elf_bytes.ehdr.class != Class::ELF32 {
+        return Err(Error::InvalidPlatform);
+    }
+
+    let sections = elf_bytes.section_headers().ok_or(Error::NoSectionHeader)?;
+    let mut usage = InstructionUsage::default();
+
+    for section in sections.iter() {
+        // Code sections (same as the transpiler)
+        if section.sh_type != SHT_PROGBITS
+            || (section.sh_flags as u32 & (SHF_ALLOC | SHF_EXECINSTR)) != SHF_ALLOC | SHF_EXECINSTR
+        {
+            continue;
+        }
+
+        let (data, compression) = elf_bytes.section_data(&section)?;
+
+        // Compression is not supported
+        if let Some(value) = compression {
+            return Err(Error::UnsupportedCompression(value));
+        }
+
+        usage.add(data);
+    }
+
+    Ok(usage)
+}
+
+/// Get the offset of a section in the transpiled binary (same rules as the transpiler).
+fn section_offset(
+    section: &SectionHeader,
+    segments: &ParsingTable<'_, LittleEndian, ProgramHeader>,
+    entry: u32,
+) -> Option<u32> {
+    let addr = section.sh_addr as u32;
+    segments
+        .iter()
+        .find(|segment| {
+            addr >= segment.p_vaddr as u32
+                && addr + section.sh_size as u32 <= segment.p_vaddr as u32 + segment.p_memsz as u32
+        })
+        .map(|segment| {
+            let paddr = addr - segment.p_vaddr as u32 + segment.p_paddr as u32;
+            let alignment = (section.sh_addralign as u32).max(1);
+            (paddr - entry).div_ceil(alignment) * alignment
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instruction::embive::{Decoded, Instruction, Lui, TypeUL};
 
     #[test]
     fn test_transpile() {
@@ -231,6 +704,375 @@ mod tests {
         assert_eq!(&output[..result.unwrap()], expected);
     }
 
+    #[test]
+    fn test_transpile_buffer_too_small() {
+        let elf = include_bytes!("../tests/test.elf");
+        let mut output = [0; 16];
+
+        let result = transpile_elf(elf, &mut output);
+        assert!(matches!(
+            result,
+            Err(Error::BufferTooSmall { available: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn test_transpile_raw_invalid() {
+        let mut code = [
+            0x13, 0x00, 0x00, 0x00, // nop
+            0xff, 0xff, 0xff, 0xff, // invalid
+        ];
+
+        let result = transpile_raw(&mut code);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidInstructionAt {
+                section: None,
+                offset: 4,
+                instruction: 0xffffffff,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_transpile_raw_custom() {
+        let mut code = [
+            0x0b, 0x85, 0xc5, 0x00, // custom-0 a0, a1, a2
+            0x2b, 0x00, 0x00, 0x00, // custom-1 (syscall shim)
+        ];
+
+        fn handler(inst: u32) -> Option<CustomInstruction> {
+            match inst & 0x7F {
+                0b000_1011 => Some(CustomInstruction::Custom {
+                    id: 1,
+                    rd: ((inst >> 7) & 0x1F) as u8,
+                    rs1: ((inst >> 15) & 0x1F) as u8,
+                    rs2: ((inst >> 20) & 0x1F) as u8,
+                }),
+                0b010_1011 => Some(CustomInstruction::Syscall),
+                _ => None,
+            }
+        }
+
+        // Not supported without a handler
+        let result = transpile_raw(&mut code.clone());
+        assert!(matches!(
+            result,
+            Err(Error::InvalidInstructionAt { offset: 0, .. })
+        ));
+
+        let config = Config::default().with_custom_handler(Some(handler));
+        transpile_raw_with_config(&mut code, &config).unwrap();
+
+        let custom = u32::from_le_bytes(code[..4].try_into().unwrap());
+        let expected = CustomInstruction::Custom {
+            id: 1,
+            rd: 10,
+            rs1: 11,
+            rs2: 12,
+        };
+        assert_eq!(custom, expected.encode(0).unwrap().data);
+
+        let syscall = u32::from_le_bytes(code[4..].try_into().unwrap());
+        assert_eq!(syscall, 0x0000001f); // ecall
+    }
+
+    #[test]
+    fn test_instruction_usage() {
+        let elf = include_bytes!("../tests/test.elf");
+
+        let usage = instruction_usage(elf).expect("Failed to parse ELF");
+        assert_eq!(usage.total(), 112);
+        assert_eq!(usage.count(InstructionClass::Compressed), 44);
+        assert_eq!(usage.count(InstructionClass::Load), 16);
+        assert_eq!(usage.count(InstructionClass::Store), 18);
+        assert_eq!(usage.count(InstructionClass::OpImm), 13);
+        assert_eq!(usage.count(InstructionClass::System), 4);
+        assert_eq!(usage.count(InstructionClass::Csr), 3);
+        assert_eq!(usage.count(InstructionClass::Unknown), 0);
+        assert!(!usage.uses_m() && !usage.uses_a());
+        assert!(usage.uses_c() && usage.uses_zicsr());
+    }
+
+    #[test]
+    fn test_instruction_usage_raw() {
+        let mut usage = InstructionUsage::default();
+        usage.add(&[
+            0x2f, 0x25, 0x05, 0x10, // lr.w a0, (a0)
+            0x33, 0x45, 0xb5, 0x02, // div  a0, a0, a1
+            0x0f, 0x00, 0xf0, 0x0f, // fence
+            0x0b, 0x85, 0xc5, 0x00, // custom-0 a0, a1, a2
+            0x33, 0x05, 0xb5, 0x00, // add  a0, a0, a1
+        ]);
+
+        assert_eq!(usage.count(InstructionClass::Atomic), 1);
+        assert_eq!(usage.count(InstructionClass::MulDiv), 1);
+        assert_eq!(usage.count(InstructionClass::MiscMem), 1);
+        assert_eq!(usage.count(InstructionClass::Unknown), 1);
+        assert_eq!(usage.count(InstructionClass::Op), 1);
+        assert!(usage.uses_a() && usage.uses_m() && !usage.uses_c());
+    }
+
+    #[test]
+    fn test_transpile_abi_trailer() {
+        let elf = include_bytes!("../tests/test.elf");
+        let mut output = [0; 16384];
+
+        let config = Config::default().with_abi_trailer(true);
+        let size = transpile_elf_with_config(elf, &mut output, &config).unwrap();
+        let image = include_bytes!("../tests/test.bin");
+
+        // Image unchanged, followed by the trailer
+        assert_eq!(size, image.len() + ABI_TRAILER_SIZE);
+        assert_eq!(&output[..image.len()], image);
+        assert_eq!(
+            crate::protocol::image_abi_version(&output[..size]),
+            Some(crate::protocol::ABI_VERSION)
+        );
+        assert_eq!(crate::protocol::image_abi_version(image), None);
+    }
+
+    #[test]
+    fn test_transpile_load_immediate() {
+        let mut code = [
+            0x37, 0x55, 0x34, 0x12, // lui  a0, 0x12345
+            0x13, 0x05, 0x85, 0x67, // addi a0, a0, 1656
+            0xb7, 0x55, 0x34, 0x12, // lui  a1, 0x12345
+            0x13, 0x06, 0x86, 0x67, // addi a2, a2, 1656
+            0xb7, 0x56, 0x34, 0x12, // lui  a3, 0x12345
+            0x93, 0x86, 0x86, 0x67, // addi a3, a3, 1656
+            0x6f, 0xf0, 0xdf, 0xff, // j    -4
+        ];
+
+        // Off by default
+        let mut plain = code;
+        transpile_raw(&mut plain).unwrap();
+        let inst = Instruction::from(u32::from_le_bytes(plain[..4].try_into().unwrap()));
+        assert_eq!(inst.size(), 4);
+
+        let config = Config::default().with_load_immediate(true);
+        transpile_raw_with_config(&mut code, &config).unwrap();
+
+        // Only the first pair is fused (different registers, and `addi` jump target)
+        let inst = Instruction::from(u32::from_le_bytes(code[..4].try_into().unwrap()));
+        assert_eq!(inst.size(), 8);
+        assert_eq!(
+            inst.decode(),
+            Some(Decoded::Lui(Lui(TypeUL {
+                rd: 10,
+                imm: 0x1234_5000,
+                li: true,
+            })))
+        );
+        assert_eq!(code[4..8], 0x1234_5678u32.to_le_bytes());
+        assert_eq!(code[8..], plain[8..]);
+    }
+
+    #[test]
+    fn test_transpile_load_immediate_batches() {
+        // More pairs than a batch, the `addi` of the last one is a jump target
+        const PAIRS: usize = 130;
+        let mut code = [0; PAIRS * 8 + 4];
+        for pair in code.chunks_exact_mut(8) {
+            pair[..4].copy_from_slice(&0x1234_5537u32.to_le_bytes()); // lui  a0, 0x12345
+            pair[4..].copy_from_slice(&0x6785_0513u32.to_le_bytes()); // addi a0, a0, 1656
+        }
+        code[PAIRS * 8..].copy_from_slice(&0xffdf_f06fu32.to_le_bytes()); // j -4
+
+        let config = Config::default().with_load_immediate(true);
+        transpile_raw_with_config(&mut code, &config).unwrap();
+
+        for (i, pair) in code.chunks_exact(8).enumerate() {
+            let inst = Instruction::from(u32::from_le_bytes(pair[..4].try_into().unwrap()));
+            assert_eq!(inst.size(), if i < PAIRS - 1 { 8 } else { 4 });
+        }
+    }
+
+    #[test]
+    fn test_transpile_source_image() {
+        let elf = include_bytes!("../tests/test.elf");
+        let mut output = [0; 16384];
+
+        let config = Config::default().with_source_image(true);
+        let size = transpile_elf_with_config(elf, &mut output, &config).unwrap();
+        let image = include_bytes!("../tests/test.bin");
+
+        // Same layout, instructions kept as RISC-V
+        assert_eq!(size, image.len());
+        assert_ne!(&output[..size], image);
+        let len = if output[0] & 0b11 == 0b11 { 4 } else { 2 };
+        transpile_raw(&mut output[..len]).unwrap();
+        assert_eq!(&output[..len], &image[..len]);
+    }
+
+    #[test]
+    fn test_transpile_memory_map() {
+        let elf = include_bytes!("../tests/test.elf");
+        let mut output = [0; 16384];
+
+        let regions = [
+            MemoryRegion::new("FLASH", 0, 0x4000).with_attributes(true, false, true),
+            MemoryRegion::new("RAM", 0x8000_0000, 0x1000).with_attributes(true, true, false),
+        ];
+        let config = Config::default().with_memory_map(Some(&regions));
+        let size = transpile_elf_with_config(elf, &mut output, &config).unwrap();
+        assert_eq!(&output[..size], include_bytes!("../tests/test.bin"));
+
+        // Stack doesn't fit the RAM
+        let regions = [regions[0], MemoryRegion::new("RAM", 0x8000_0000, 0x400)];
+        let config = Config::default().with_memory_map(Some(&regions));
+        let result = transpile_elf_with_config(elf, &mut output, &config);
+        assert!(matches!(
+            result,
+            Err(Error::SegmentOutsideMemory {
+                segment: 4,
+                address: 0x8000_0004,
+                size: 0x80c,
+            })
+        ));
+
+        // Initialized data (load address) doesn't fit the flash
+        let regions = [MemoryRegion::new("FLASH", 0, 0x100), regions[1]];
+        let config = Config::default().with_memory_map(Some(&regions));
+        let result = transpile_elf_with_config(elf, &mut output, &config);
+        assert!(matches!(
+            result,
+            Err(Error::SegmentOutsideMemory {
+                segment: 2,
+                address: 0x168,
+                size: 4,
+            })
+        ));
+
+        // Code in a non-executable region
+        let regions = [
+            MemoryRegion::new("FLASH", 0, 0x4000).with_attributes(true, false, false),
+            MemoryRegion::new("RAM", 0x8000_0000, 0x1000),
+        ];
+        let config = Config::default().with_memory_map(Some(&regions));
+        let result = transpile_elf_with_config(elf, &mut output, &config);
+        assert!(matches!(
+            result,
+            Err(Error::SegmentOutsideMemory { segment: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_transpile_strict() {
+        let elf = include_bytes!("../tests/test.elf");
+        let mut output = [0; 16384];
+
+        // No violations, same output
+        let config = Config::default().with_strict(true);
+        let size = transpile_elf_with_config(elf, &mut output, &config).unwrap();
+        assert_eq!(&output[..size], include_bytes!("../tests/test.bin"));
+
+        let mut code = [
+            0x13, 0x00, 0x00, 0x00, // nop
+            0x01, 0x45, // li   a0, 0
+            0x73, 0x25, 0x00, 0xc0, // rdcycle a0
+        ];
+
+        // Accepted by default (fails at runtime)
+        assert!(transpile_raw(&mut code.clone()).is_ok());
+
+        let result = transpile_raw_with_config(&mut code, &config);
+        assert!(matches!(
+            result,
+            Err(Error::StrictViolationAt {
+                section: None,
+                offset: 6,
+                instruction: 0xc0002573,
+                violation: StrictViolation::UnsupportedCsr(0xc00),
+            })
+        ));
+    }
+
+    #[test]
+    fn test_tls_segment() {
+        let elf = include_bytes!("../tests/tls.elf");
+
+        let result = tls_segment(elf).expect("Failed to parse ELF");
+        assert_eq!(
+            result,
+            Some(TlsSegment {
+                template_address: 0x20,
+                template_size: 4,
+                size: 12,
+                align: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_tls_segment_none() {
+        // ELF with an empty TLS segment
+        let elf = include_bytes!("../tests/test.elf");
+
+        let result = tls_segment(elf).expect("Failed to parse ELF");
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_symbol() {
+        let elf = include_bytes!("../tests/test.elf");
+
+        // Code symbols
+        assert_eq!(symbol(elf, "_entry").unwrap(), Some(0));
+        assert_eq!(symbol(elf, "codeEntry").unwrap(), Some(0xc2));
+        // Data symbol (RAM)
+        assert_eq!(
+            symbol(elf, "GLOBAL_DATA__main_u2").unwrap(),
+            Some(0x8000_0000)
+        );
+        assert_eq!(symbol(elf, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_pc_map() {
+        let elf = include_bytes!("../tests/test.elf");
+
+        let mut map = [None; 2];
+        let mut count = 0;
+        pc_map(elf, |mapping| {
+            map[count] = Some(mapping);
+            count += 1;
+        })
+        .unwrap();
+
+        // Code sections only (.init and .text)
+        assert_eq!(
+            map,
+            [
+                Some(PcMapping {
+                    address: 0,
+                    original_address: 0,
+                    size: 0xc0
+                }),
+                Some(PcMapping {
+                    address: 0xc0,
+                    original_address: 0xc0,
+                    size: 0xa8
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pc_mapping() {
+        let mapping = PcMapping {
+            address: 0x100,
+            original_address: 0x2000_0000,
+            size: 0x10,
+        };
+
+        assert_eq!(mapping.original_address(0x10c), Some(0x2000_000c));
+        assert_eq!(mapping.original_address(0x110), None);
+        assert_eq!(mapping.original_address(0xfc), None);
+        assert_eq!(mapping.address(0x2000_0004), Some(0x104));
+        assert_eq!(mapping.address(0x1fff_fffc), None);
+    }
+
     #[cfg(feature = "alloc")]
     #[test]
     fn test_transpile_vec() {
@@ -0,0 +1,124 @@
+//! Transpiler Configuration Module
+
+use super::{CustomInstructionHandler, MemoryRegion};
+
+/// Embive Transpiler Configuration
+///
+/// Example:
+/// ```
+/// use embive::transpiler::{Config, CustomInstruction};
+///
+/// // Vendor DSP instruction (custom-0 opcode) mapped to custom Embive instruction 0
+/// fn handler(inst: u32) -> Option<CustomInstruction> {
+///     (inst & 0x7F == 0b000_1011).then(|| CustomInstruction::Custom {
+///         id: 0,
+///         rd: ((inst >> 7) & 0x1F) as u8,
+///         rs1: ((inst >> 15) & 0x1F) as u8,
+///         rs2: ((inst >> 20) & 0x1F) as u8,
+///     })
+/// }
+///
+/// let config = Config::default().with_custom_handler(Some(handler));
+/// assert!(config.custom_handler.is_some());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct Config<'a> {
+    /// Handler for unsupported (custom) RISC-V instructions. Default: `None`.
+    pub custom_handler: Option<CustomInstructionHandler>,
+    /// Strict mode, rejecting reserved and hint encodings, and unsupported system instructions and CSRs
+    /// (check [`super::StrictViolation`]). Default: `false`.
+    pub strict: bool,
+    /// Append the ABI version trailer to the image (check [`Config::with_abi_trailer`]). Default: `false`.
+    pub abi_trailer: bool,
+    /// Device memory map, validated against the ELF segments (check [`Config::with_memory_map`]). Default: `None`.
+    pub memory_map: Option<&'a [MemoryRegion<'a>]>,
+    /// Output the source image, keeping the RISC-V instructions (check [`Config::with_source_image`]).
+    /// Default: `false`.
+    pub source_image: bool,
+    /// Fuse `lui` + `addi` pairs into load-immediates (check [`Config::with_load_immediate`]). Default: `false`.
+    pub load_immediate: bool,
+}
+
+impl<'a> Config<'a> {
+    /// Create a new configuration (no custom instruction handler and memory map, strict mode, ABI trailer and
+    /// load-immediates disabled).
+    pub const fn new() -> Self {
+        Config {
+            custom_handler: None,
+            strict: false,
+            abi_trailer: false,
+            memory_map: None,
+            source_image: false,
+            load_immediate: false,
+        }
+    }
+
+    /// Set the custom instruction handler (check [`CustomInstructionHandler`]).
+    pub const fn with_custom_handler(mut self, handler: Option<CustomInstructionHandler>) -> Self {
+        self.custom_handler = handler;
+        self
+    }
+
+    /// Enable or disable the strict mode.
+    ///
+    /// The transpiler fails with [`super::Error::StrictViolationAt`] (instead of emitting code the interpreter could
+    /// reject at runtime, or that is not executed as written) on:
+    /// - Reserved encodings (e.g. `c.lwsp` to `x0`).
+    /// - Hints (e.g. `c.li` to `x0`, `pause`).
+    /// - Unsupported system instructions (e.g. `sret`) and CSRs (e.g. `cycle`).
+    ///
+    /// The defined illegal instructions (`unimp`, `c.unimp`) are accepted, as they are used to trap on purpose.
+    pub const fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Enable or disable the ABI version trailer.
+    ///
+    /// The ABI version of this transpiler ([`crate::protocol::ABI_VERSION`]) is appended after the image,
+    /// so hosts can reject images built for an incompatible ABI before running them
+    /// (check [`crate::protocol::image_abi_version`]). The trailer isn't part of the guest address space layout.
+    pub const fn with_abi_trailer(mut self, abi_trailer: bool) -> Self {
+        self.abi_trailer = abi_trailer;
+        self
+    }
+
+    /// Enable or disable the source image output.
+    ///
+    /// The image has the same layout as the transpiled one, but the instructions are kept as RISC-V (not converted,
+    /// nor checked in strict mode), so the interpreter can report the original bits of illegal instructions
+    /// (check [`crate::interpreter::Interpreter::set_source_image`]).
+    pub const fn with_source_image(mut self, source_image: bool) -> Self {
+        self.source_image = source_image;
+        self
+    }
+
+    /// Enable or disable the load-immediates.
+    ///
+    /// A `lui` followed by an `addi` to the same register (e.g. the `li` pseudo-instruction) is transpiled to a single
+    /// 8-byte Embive instruction: the `lui` (with a load-immediate flag) followed by the full 32-bit immediate, in place
+    /// of the `addi`. The interpreter executes it in one step, saving a dispatch per pair. The image doesn't get
+    /// smaller: addresses and code size are unchanged (the pair is still 8 bytes).
+    ///
+    /// The `addi` is no longer an instruction, so pairs where it is a direct jump or branch target are kept as is.
+    /// Indirect jumps to the `addi` (jump tables, `auipc` + `jalr`, function pointers) and jumps from other sections
+    /// can't be detected: they would execute the immediate as an instruction. That's why this is disabled by default,
+    /// only enable it for code that never jumps between the two instructions (as emitted by compilers for `li`).
+    pub const fn with_load_immediate(mut self, load_immediate: bool) -> Self {
+        self.load_immediate = load_immediate;
+        self
+    }
+
+    /// Set the device memory map (check [`super::memory_map`] to parse it from a linker script).
+    ///
+    /// The loadable ELF segments are checked before transpiling, failing with [`super::Error::SegmentOutsideMemory`]
+    /// instead of producing an image that doesn't fit the device memory:
+    /// - Run address (`p_vaddr`, `p_memsz` bytes): inside a region allowing the segment permissions
+    ///   (writable, executable).
+    /// - Load address (`p_paddr`, `p_filesz` bytes, e.g. initialized data copied to RAM): inside a readable region.
+    pub const fn with_memory_map(mut self, memory_map: Option<&'a [MemoryRegion<'a>]>) -> Self {
+        self.memory_map = memory_map;
+        self
+    }
+}
@@ -88,7 +88,7 @@ macro_rules! embive_raw {
         RawInstruction::new(inst.encode() | <$inst>::opcode() as u32, <$inst>::size())
     }};
 }
-use embive_raw;
+pub(super) use embive_raw;
 
 /// Convert a RISC-V instruction to Embive format.
 ///
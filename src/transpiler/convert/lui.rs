@@ -1,4 +1,4 @@
-use crate::format::{Format, TypeU};
+use crate::format::{Format, TypeUL};
 use crate::instruction::{embive, riscv};
 use crate::transpiler::Error;
 
@@ -6,7 +6,7 @@ use super::{embive_raw, Convert, RawInstruction};
 
 impl Convert for riscv::Lui {
     fn convert(data: u32) -> Result<RawInstruction, Error> {
-        let inst = TypeU::from_riscv(data);
+        let inst = TypeUL::from_riscv(data);
 
         Ok(embive_raw!(embive::Lui, inst))
     }
@@ -8,6 +8,8 @@ pub const ECALL_IMM: i32 = 0b0;
 pub const EBREAK_IMM: i32 = 0b1;
 pub const WFI_IMM: i32 = 0b1_0000_0101;
 pub const MRET_IMM: i32 = 0b11_0000_0010;
+/// `sfence.vma` (funct7, the immediate low bits hold `rs2`)
+pub const SFENCEVMA_FUNCT7: i32 = 0b000_1001;
 
 pub const CSRRW_FUNC: u8 = 0b001;
 pub const CSRRS_FUNC: u8 = 0b010;
@@ -27,6 +29,10 @@ impl Convert for riscv::System {
                 EBREAK_IMM => inst.imm = embive::SystemMiscMem::EBREAK_IMM,
                 WFI_IMM => inst.imm = embive::SystemMiscMem::WFI_IMM,
                 MRET_IMM => inst.imm = embive::SystemMiscMem::MRET_IMM,
+                imm if imm >> 5 == SFENCEVMA_FUNCT7 => {
+                    // Address space (rs2) is ignored, rs1 (virtual address) is kept
+                    inst.imm = embive::SystemMiscMem::SFENCEVMA_IMM
+                }
                 _ => {}
             }
         } else {
@@ -0,0 +1,140 @@
+//! Custom Instruction Module
+//!
+//! Extension point for vendor-specific (custom) RISC-V instructions, check [`super::Config::with_custom_handler`].
+use crate::format::{TypeI, TypeR};
+use crate::instruction::embive;
+
+use super::{
+    convert::{embive_raw, RawInstruction},
+    Error,
+};
+
+/// Number of custom Embive instructions (IDs from `0` to `CUSTOM_INSTRUCTIONS - 1`).
+pub const CUSTOM_INSTRUCTIONS: u16 = 512;
+
+/// Embive replacement for a custom RISC-V instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CustomInstruction {
+    /// Custom Embive instruction, executed by the interpreter custom instruction handler.
+    Custom {
+        /// Custom instruction ID (less than [`CUSTOM_INSTRUCTIONS`]).
+        id: u16,
+        /// Destination register.
+        rd: u8,
+        /// Source register 1.
+        rs1: u8,
+        /// Source register 2.
+        rs2: u8,
+    },
+    /// Syscall shim, the instruction is replaced by an `ecall`
+    /// (the syscall number and arguments are taken from the registers, as usual).
+    Syscall,
+}
+
+/// Custom instruction handler function.
+///
+/// Called when the transpiler finds a 32-bit RISC-V instruction it doesn't support (e.g. vendor-specific encodings).
+/// Compressed (16-bit) instructions are not forwarded, as all Embive replacements are 32-bit.
+///
+/// Arguments:
+/// - `u32`: The RISC-V instruction.
+///
+/// Returns:
+/// - `Some(CustomInstruction)`: The Embive replacement.
+/// - `None`: The instruction is not supported (error [`Error::InvalidInstruction`]).
+pub type CustomInstructionHandler = fn(u32) -> Option<CustomInstruction>;
+
+impl CustomInstruction {
+    /// Encode the replacement as an Embive instruction.
+    ///
+    /// Arguments:
+    /// - `data`: The original RISC-V instruction (for error reporting).
+    ///
+    /// Returns:
+    /// - `Ok(RawInstruction)`: The raw Embive instruction.
+    /// - `Err(Error)`: Invalid custom instruction ID or register.
+    pub(crate) fn encode(self, data: u32) -> Result<RawInstruction, Error> {
+        match self {
+            CustomInstruction::Custom { id, rd, rs1, rs2 } => {
+                if id >= CUSTOM_INSTRUCTIONS || rd > 31 || rs1 > 31 || rs2 > 31 {
+                    return Err(Error::InvalidInstruction(data));
+                }
+
+                let inst = TypeR {
+                    rd,
+                    rs1,
+                    rs2,
+                    func: embive::OpAmo::CUSTOM_FUNC + id,
+                };
+                Ok(embive_raw!(embive::OpAmo, inst))
+            }
+            CustomInstruction::Syscall => {
+                let inst = TypeI {
+                    rd_rs2: 0,
+                    rs1: 0,
+                    imm: embive::SystemMiscMem::ECALL_IMM,
+                    func: embive::SystemMiscMem::MISC_FUNC,
+                };
+                Ok(embive_raw!(embive::SystemMiscMem, inst))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Format;
+
+    #[test]
+    fn test_encode_custom() {
+        let custom = CustomInstruction::Custom {
+            id: 3,
+            rd: 10,
+            rs1: 11,
+            rs2: 12,
+        };
+        let raw = custom.encode(0).unwrap();
+        assert_eq!(raw.data & 0x1F, 30); // OpAmo
+
+        let inst = TypeR::from_embive(raw.data);
+        assert_eq!(
+            inst,
+            TypeR {
+                rd: 10,
+                rs1: 11,
+                rs2: 12,
+                func: embive::OpAmo::CUSTOM_FUNC + 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_encode_invalid() {
+        let custom = CustomInstruction::Custom {
+            id: CUSTOM_INSTRUCTIONS,
+            rd: 0,
+            rs1: 0,
+            rs2: 0,
+        };
+        assert!(matches!(
+            custom.encode(0x1234),
+            Err(Error::InvalidInstruction(0x1234))
+        ));
+
+        let custom = CustomInstruction::Custom {
+            id: 0,
+            rd: 32,
+            rs1: 0,
+            rs2: 0,
+        };
+        assert!(custom.encode(0).is_err());
+    }
+
+    #[test]
+    fn test_encode_syscall() {
+        let raw = CustomInstruction::Syscall.encode(0).unwrap();
+        // ecall (check interpreter tests)
+        assert_eq!(raw.data, 0x0000001f);
+    }
+}
@@ -4,8 +4,11 @@ use core::fmt::{Display, Formatter, Result};
 
 use elf::{compression::CompressionHeader, ParseError};
 
+use super::StrictViolation;
+
 /// Embive Transpiler Error
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Error parsing ELF.
     ErrorParsingELF(ParseError),
@@ -13,6 +16,26 @@ pub enum Error {
     NoSegmentForSection(usize),
     /// Invalid instruction. The instruction is provided.
     InvalidInstruction(u32),
+    /// Invalid instruction found while transpiling code.
+    InvalidInstructionAt {
+        /// ELF section index (`None` when transpiling raw code).
+        section: Option<usize>,
+        /// Offset of the instruction from the start of the section (or raw code), in bytes.
+        offset: usize,
+        /// The RISC-V instruction.
+        instruction: u32,
+    },
+    /// Instruction rejected by the strict mode (check [`super::Config::with_strict`]).
+    StrictViolationAt {
+        /// ELF section index (`None` when transpiling raw code).
+        section: Option<usize>,
+        /// Offset of the instruction from the start of the section (or raw code), in bytes.
+        offset: usize,
+        /// The RISC-V instruction.
+        instruction: u32,
+        /// The violated rule.
+        violation: StrictViolation,
+    },
     /// Invalid instruction size. The size is provided.
     InvalidInstructionSize(usize),
     /// Invalid platform (not a RISC-V 32-bit ELF).
@@ -22,16 +45,158 @@ pub enum Error {
     /// ELF has no program header table.
     NoProgramHeader,
     /// Buffer is too small.
-    BufferTooSmall,
+    BufferTooSmall {
+        /// Required buffer size, in bytes.
+        required: usize,
+        /// Available buffer size, in bytes.
+        available: usize,
+    },
     /// Unsupported ELF Compression
     UnsupportedCompression(CompressionHeader),
+    /// ELF has no symbol table (stripped).
+    NoSymbolTable,
+    /// Object is not a relocatable ELF (linking). The object index is provided.
+    NotRelocatable(usize),
+    /// Undefined symbol (linking).
+    UndefinedSymbol {
+        /// Object index.
+        object: usize,
+        /// Symbol index in the object symbol table.
+        symbol: usize,
+    },
+    /// Global symbol defined more than once (linking).
+    DuplicateSymbol {
+        /// Object index (second definition).
+        object: usize,
+        /// Symbol index in the object symbol table.
+        symbol: usize,
+    },
+    /// Unsupported relocation type (linking).
+    UnsupportedRelocation {
+        /// Object index.
+        object: usize,
+        /// Relocation type.
+        relocation: u32,
+    },
+    /// Invalid memory map (check [`super::memory_map`]). The byte offset of the error is provided.
+    InvalidMemoryMap(usize),
+    /// Loadable ELF segment doesn't fit the memory map (check [`super::Config::with_memory_map`]).
+    SegmentOutsideMemory {
+        /// ELF segment index.
+        segment: usize,
+        /// Segment address (run or load address).
+        address: u32,
+        /// Segment size, in bytes.
+        size: u32,
+    },
+    /// Relocation target out of range, or without a matching high part (linking).
+    RelocationOutOfRange {
+        /// Object index.
+        object: usize,
+        /// Relocated section index.
+        section: usize,
+        /// Offset of the relocation from the start of the section, in bytes.
+        offset: usize,
+    },
 }
 
-impl core::error::Error for Error {}
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::ErrorParsingELF(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{self:?}")
+        match self {
+            Error::ErrorParsingELF(e) => write!(f, "failed to parse ELF: {e}"),
+            Error::NoSegmentForSection(i) => {
+                write!(f, "section {i} is not contained in any ELF segment")
+            }
+            Error::InvalidInstruction(inst) => {
+                write!(f, "invalid or unsupported RISC-V instruction {inst:#010x}")
+            }
+            Error::InvalidInstructionAt {
+                section,
+                offset,
+                instruction,
+            } => {
+                write!(
+                    f,
+                    "invalid or unsupported RISC-V instruction {instruction:#010x} at offset {offset:#x}"
+                )?;
+                if let Some(section) = section {
+                    write!(f, " of section {section}")?;
+                }
+                Ok(())
+            }
+            Error::StrictViolationAt {
+                section,
+                offset,
+                instruction,
+                violation,
+            } => {
+                write!(
+                    f,
+                    "RISC-V instruction {instruction:#010x} at offset {offset:#x}"
+                )?;
+                if let Some(section) = section {
+                    write!(f, " of section {section}")?;
+                }
+                write!(f, " rejected by strict mode ({violation})")
+            }
+            Error::InvalidInstructionSize(size) => {
+                write!(f, "invalid instruction size ({size} bytes)")
+            }
+            Error::InvalidPlatform => write!(f, "not a RISC-V 32-bit ELF"),
+            Error::NoSectionHeader => write!(f, "ELF has no section header table"),
+            Error::NoProgramHeader => write!(f, "ELF has no program header table"),
+            Error::BufferTooSmall {
+                required,
+                available,
+            } => write!(
+                f,
+                "output buffer is too small ({required} bytes required, {available} available)"
+            ),
+            Error::UnsupportedCompression(header) => {
+                write!(f, "unsupported ELF section compression ({header:?})")
+            }
+            Error::NoSymbolTable => write!(f, "ELF has no symbol table (was it stripped?)"),
+            Error::NotRelocatable(object) => {
+                write!(f, "object {object} is not a relocatable ELF")
+            }
+            Error::UndefinedSymbol { object, symbol } => {
+                write!(f, "undefined symbol {symbol} of object {object}")
+            }
+            Error::DuplicateSymbol { object, symbol } => {
+                write!(f, "duplicate definition of symbol {symbol} of object {object}")
+            }
+            Error::UnsupportedRelocation { object, relocation } => {
+                write!(f, "unsupported relocation type {relocation} in object {object}")
+            }
+            Error::InvalidMemoryMap(offset) => {
+                write!(f, "invalid memory map at offset {offset}")
+            }
+            Error::SegmentOutsideMemory {
+                segment,
+                address,
+                size,
+            } => write!(
+                f,
+                "segment {segment} ({size} bytes at {address:#010x}) doesn't fit the memory map"
+            ),
+            Error::RelocationOutOfRange {
+                object,
+                section,
+                offset,
+            } => write!(
+                f,
+                "relocation at offset {offset:#x} of section {section} of object {object} is out of range"
+            ),
+        }
     }
 }
 
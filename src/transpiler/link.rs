@@ -0,0 +1,693 @@
+//! Link Module
+//!
+//! Minimal static linker for relocatable RISC-V objects (`.o`), so separately compiled modules can be
+//! combined into a single Embive image without a full toolchain (check [`link_objects`]).
+//!
+//! How it works:
+//! - Allocated sections of all objects are laid out in order: code, read-only data (code image),
+//!   then writable data and zero-initialized data (RAM image, at [`LINK_RAM_ADDRESS`]).
+//! - Global symbols are resolved between objects (strong definitions override weak ones).
+//! - Relocations are applied (no linker relaxation), then the code sections are transpiled.
+//! - Position-independent code is linked without a GOT: each `auipc` + `lw` pair loading a symbol address from the
+//!   GOT is rewritten to compute it (`auipc` + `addi`), as the final layout is known.
+use alloc::vec::Vec;
+
+use elf::{
+    abi::{
+        EM_RISCV, ET_REL, R_RISCV_32, R_RISCV_32_PCREL, R_RISCV_ADD16, R_RISCV_ADD32, R_RISCV_ADD8,
+        R_RISCV_ALIGN, R_RISCV_BRANCH, R_RISCV_CALL, R_RISCV_CALL_PLT, R_RISCV_GOT_HI20,
+        R_RISCV_HI20, R_RISCV_JAL, R_RISCV_LO12_I, R_RISCV_LO12_S, R_RISCV_PCREL_HI20,
+        R_RISCV_PCREL_LO12_I, R_RISCV_PCREL_LO12_S, R_RISCV_RELAX, R_RISCV_RVC_BRANCH,
+        R_RISCV_RVC_JUMP, R_RISCV_SET16, R_RISCV_SET32, R_RISCV_SET6, R_RISCV_SET8, R_RISCV_SUB16,
+        R_RISCV_SUB32, R_RISCV_SUB6, R_RISCV_SUB8, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHN_ABS,
+        SHN_COMMON, SHT_NOBITS, SHT_RELA, STB_GLOBAL, STB_WEAK,
+    },
+    endian::LittleEndian,
+    file::Class,
+    parse::ParsingTable,
+    section::SectionHeader,
+    string_table::StringTable,
+    symbol::{Symbol, SymbolTable},
+    ElfBytes,
+};
+
+use super::{transpile_raw_with_config, Config, Error};
+use crate::protocol::abi_trailer;
+
+/// `lw` opcode and function (I-type).
+const LW: u32 = 0x2003;
+/// `addi` opcode and function (I-type).
+const ADDI: u32 = 0x0013;
+
+/// Address of the RAM image of a linked image (same as the interpreter RAM offset).
+pub const LINK_RAM_ADDRESS: u32 = 0x8000_0000;
+
+/// Linked Embive image (check [`link_objects`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedImage<'a> {
+    /// Transpiled code and read-only data (interpreter code memory, starting at address `0`).
+    pub code: Vec<u8>,
+    /// Initial RAM image (writable and zero-initialized data), to be copied to [`LINK_RAM_ADDRESS`]
+    /// before running the code.
+    pub data: Vec<u8>,
+    symbols: Vec<(&'a str, u32)>,
+}
+
+impl<'a> LinkedImage<'a> {
+    /// Get the address of a global symbol (e.g. the entry point).
+    ///
+    /// # Arguments
+    /// - `name`: The symbol name.
+    ///
+    /// # Returns
+    /// - `Some(u32)`: The symbol address.
+    /// - `None`: The symbol wasn't found.
+    pub fn symbol(&self, name: &str) -> Option<u32> {
+        self.symbols
+            .iter()
+            .find(|(symbol, _)| *symbol == name)
+            .map(|&(_, address)| address)
+    }
+
+    /// Get all global symbols (name and address).
+    pub fn symbols(&self) -> &[(&'a str, u32)] {
+        &self.symbols
+    }
+}
+
+/// Kind of an allocated section, in layout order.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SectionKind {
+    Code,
+    ReadOnly,
+    Data,
+    Zeroed,
+}
+
+impl SectionKind {
+    /// Get the kind of a section (`None` if not allocated).
+    fn of(section: &SectionHeader) -> Option<Self> {
+        let flags = section.sh_flags as u32;
+        if flags & SHF_ALLOC == 0 {
+            return None;
+        }
+
+        Some(match section.sh_type {
+            SHT_NOBITS => SectionKind::Zeroed,
+            _ if flags & SHF_EXECINSTR != 0 => SectionKind::Code,
+            _ if flags & SHF_WRITE != 0 => SectionKind::Data,
+            _ => SectionKind::ReadOnly,
+        })
+    }
+}
+
+/// Parsed relocatable object.
+struct Object<'a> {
+    elf: ElfBytes<'a, LittleEndian>,
+    sections: ParsingTable<'a, LittleEndian, SectionHeader>,
+    symbols: Option<(SymbolTable<'a, LittleEndian>, StringTable<'a>)>,
+    /// Linked address of each section (`None` if not allocated).
+    addresses: Vec<Option<u32>>,
+}
+
+impl Object<'_> {
+    /// Get the linked address of a symbol defined by this object.
+    fn defined_address(&self, symbol: &Symbol) -> Option<u32> {
+        match symbol.st_shndx {
+            SHN_ABS => Some(symbol.st_value as u32),
+            SHN_COMMON => None,
+            index => self
+                .addresses
+                .get(index as usize)
+                .copied()
+                .flatten()
+                .map(|address| address + symbol.st_value as u32),
+        }
+    }
+}
+
+/// PC-relative high part (`auipc`), for the matching low parts.
+struct HighPart {
+    /// Linked address of the `auipc`.
+    address: u32,
+    /// Offset from the `auipc` to the symbol.
+    offset: u32,
+    /// The symbol address is loaded from the GOT (`GOT_HI20`).
+    got: bool,
+}
+
+/// Relocation to apply.
+struct Relocation {
+    object: usize,
+    section: usize,
+    offset: u64,
+    kind: u32,
+    /// Linked address of the relocated location (P).
+    address: u32,
+    /// Symbol address plus addend (S + A).
+    value: u32,
+}
+
+/// Link relocatable RISC-V objects into a single Embive image, transpiling the code sections.
+///
+/// Supported relocations: `R_RISCV_32`, `32_PCREL`, `BRANCH`, `JAL`, `CALL`, `CALL_PLT`, `HI20`, `LO12_I`,
+/// `LO12_S`, `PCREL_HI20`, `PCREL_LO12_I`, `PCREL_LO12_S`, `GOT_HI20`, `RVC_BRANCH`, `RVC_JUMP`, and the label
+/// differences (`ADD8/16/32`, `SUB6/8/16/32`, `SET6/8/16/32`). `RELAX` and `ALIGN` are ignored, as no relaxation
+/// is done. Common symbols are not supported (compile with `-fno-common`).
+///
+/// Position-independent objects (`-fPIC`) are linked without a GOT: the `lw` loading a symbol address through
+/// `GOT_HI20` is rewritten to an `addi` computing it (other uses of the GOT entry are not supported).
+///
+/// # Arguments
+/// - `objects`: The RISC-V ELF objects, in link order.
+/// - `config`: The transpiler configuration.
+///
+/// # Returns
+/// - `Ok(LinkedImage)`: Linking was successful, returns the image.
+/// - `Err(Error)`: An error occurred while parsing, linking or transpiling the objects.
+pub fn link_objects<'a>(
+    objects: &[&'a [u8]],
+    config: &Config<'_>,
+) -> Result<LinkedImage<'a>, Error> {
+    let mut parsed = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        let elf = ElfBytes::<LittleEndian>::minimal_parse(object)?;
+
+        // Check if the object is a RISC-V 32-bit relocatable ELF
+        if elf.ehdr.e_machine != EM_RISCV || elf.ehdr.class != Class::ELF32 {
+            return Err(Error::InvalidPlatform);
+        }
+        if elf.ehdr.e_type != ET_REL {
+            return Err(Error::NotRelocatable(index));
+        }
+
+        let sections = elf.section_headers().ok_or(Error::NoSectionHeader)?;
+        let symbols = elf.symbol_table()?;
+        parsed.push(Object {
+            elf,
+            sections,
+            symbols,
+            addresses: alloc::vec![None; sections.len()],
+        });
+    }
+
+    // Lay out the sections, by kind
+    let mut code = Vec::new();
+    let mut data = Vec::new();
+    let mut code_sections = Vec::new();
+    for kind in [
+        SectionKind::Code,
+        SectionKind::ReadOnly,
+        SectionKind::Data,
+        SectionKind::Zeroed,
+    ] {
+        for object in parsed.iter_mut() {
+            for (index, section) in object.sections.iter().enumerate() {
+                if SectionKind::of(&section) != Some(kind) || section.sh_size == 0 {
+                    continue;
+                }
+
+                let (buffer, base) = match kind {
+                    SectionKind::Code | SectionKind::ReadOnly => (&mut code, 0),
+                    SectionKind::Data | SectionKind::Zeroed => (&mut data, LINK_RAM_ADDRESS),
+                };
+                let offset = buffer
+                    .len()
+                    .next_multiple_of((section.sh_addralign as usize).max(1));
+                buffer.resize(offset, 0);
+
+                if kind == SectionKind::Zeroed {
+                    buffer.resize(offset + section.sh_size as usize, 0);
+                } else {
+                    let (bytes, compression) = object.elf.section_data(&section)?;
+
+                    // Compression is not supported
+                    if let Some(value) = compression {
+                        return Err(Error::UnsupportedCompression(value));
+                    }
+                    buffer.extend_from_slice(bytes);
+                }
+
+                if kind == SectionKind::Code {
+                    code_sections.push((index, offset, buffer.len()));
+                }
+                object.addresses[index] = Some(base + offset as u32);
+            }
+        }
+    }
+
+    // Collect the global symbols (strong definitions override weak ones)
+    let mut globals: Vec<(&'a str, u32, bool)> = Vec::new();
+    for (index, object) in parsed.iter().enumerate() {
+        let Some((symbols, strings)) = &object.symbols else {
+            continue;
+        };
+
+        for (symbol_index, symbol) in symbols.iter().enumerate() {
+            let bind = symbol.st_bind();
+            if (bind != STB_GLOBAL && bind != STB_WEAK) || symbol.is_undefined() {
+                continue;
+            }
+
+            let name = strings.get(symbol.st_name as usize)?;
+            let address = object
+                .defined_address(&symbol)
+                .ok_or(Error::UndefinedSymbol {
+                    object: index,
+                    symbol: symbol_index,
+                })?;
+            let weak = bind == STB_WEAK;
+
+            match globals.iter_mut().find(|(global, ..)| *global == name) {
+                Some(global) if global.2 && !weak => *global = (name, address, weak),
+                Some(global) if global.2 || weak => {}
+                Some(_) => {
+                    return Err(Error::DuplicateSymbol {
+                        object: index,
+                        symbol: symbol_index,
+                    })
+                }
+                None => globals.push((name, address, weak)),
+            }
+        }
+    }
+
+    // Resolve the relocations
+    let mut relocations = Vec::new();
+    for (index, object) in parsed.iter().enumerate() {
+        for section in object.sections.iter() {
+            let target = section.sh_info as usize;
+            let Some(base) = object.addresses.get(target).copied().flatten() else {
+                continue;
+            };
+            if section.sh_type != SHT_RELA {
+                continue;
+            }
+
+            for rela in object.elf.section_data_as_relas(&section)? {
+                let symbol = resolve(object, index, &globals, rela.r_sym as usize)?;
+                relocations.push(Relocation {
+                    object: index,
+                    section: target,
+                    offset: rela.r_offset,
+                    kind: rela.r_type,
+                    address: base + rela.r_offset as u32,
+                    value: symbol.wrapping_add(rela.r_addend as u32),
+                });
+            }
+        }
+    }
+
+    // PC-relative high parts, by address (for the matching low parts)
+    let high_parts: Vec<HighPart> = relocations
+        .iter()
+        .filter(|r| r.kind == R_RISCV_PCREL_HI20 || r.kind == R_RISCV_GOT_HI20)
+        .map(|r| HighPart {
+            address: r.address,
+            offset: r.value.wrapping_sub(r.address),
+            got: r.kind == R_RISCV_GOT_HI20,
+        })
+        .collect();
+
+    for relocation in relocations.iter() {
+        let buffer = match relocation.address.checked_sub(LINK_RAM_ADDRESS) {
+            Some(offset) => data.get_mut(offset as usize..),
+            None => code.get_mut(relocation.address as usize..),
+        }
+        .ok_or(Error::RelocationOutOfRange {
+            object: relocation.object,
+            section: relocation.section,
+            offset: relocation.offset as usize,
+        })?;
+        apply(buffer, relocation, &high_parts)?;
+    }
+
+    // Transpile the code sections (instruction sizes are preserved)
+    for (index, start, end) in code_sections {
+        transpile_raw_with_config(&mut code[start..end], config).map_err(|e| match e {
+            Error::InvalidInstructionAt {
+                offset,
+                instruction,
+                ..
+            } => Error::InvalidInstructionAt {
+                section: Some(index),
+                offset,
+                instruction,
+            },
+            Error::StrictViolationAt {
+                offset,
+                instruction,
+                violation,
+                ..
+            } => Error::StrictViolationAt {
+                section: Some(index),
+                offset,
+                instruction,
+                violation,
+            },
+            e => e,
+        })?;
+    }
+
+    // Interpreter fetches 4 bytes at a time, even if the last instruction is compressed
+    code.extend_from_slice(&[0, 0]);
+
+    if config.abi_trailer {
+        code.extend_from_slice(&abi_trailer());
+    }
+
+    Ok(LinkedImage {
+        code,
+        data,
+        symbols: globals
+            .into_iter()
+            .map(|(name, address, _)| (name, address))
+            .collect(),
+    })
+}
+
+/// Resolve the linked address of a symbol referenced by an object.
+fn resolve(
+    object: &Object<'_>,
+    index: usize,
+    globals: &[(&str, u32, bool)],
+    symbol_index: usize,
+) -> Result<u32, Error> {
+    let undefined = Error::UndefinedSymbol {
+        object: index,
+        symbol: symbol_index,
+    };
+    let (symbols, strings) = object.symbols.as_ref().ok_or(Error::NoSymbolTable)?;
+    let symbol = symbols.get(symbol_index)?;
+
+    if !symbol.is_undefined() {
+        return object.defined_address(&symbol).ok_or(undefined);
+    }
+
+    let name = strings.get(symbol.st_name as usize)?;
+    match globals.iter().find(|(global, ..)| *global == name) {
+        Some(&(_, address, _)) => Ok(address),
+        // Undefined weak symbols resolve to 0
+        None if symbol.st_bind() == STB_WEAK => Ok(0),
+        None => Err(undefined),
+    }
+}
+
+/// Apply a relocation.
+///
+/// # Arguments
+/// - `buffer`: The image, starting at the relocated location.
+/// - `relocation`: The relocation.
+/// - `high_parts`: PC-relative high parts.
+fn apply(buffer: &mut [u8], relocation: &Relocation, high_parts: &[HighPart]) -> Result<(), Error> {
+    let out_of_range = Error::RelocationOutOfRange {
+        object: relocation.object,
+        section: relocation.section,
+        offset: relocation.offset as usize,
+    };
+    let relative = relocation.value.wrapping_sub(relocation.address) as i32;
+    let in_range = |bits: u32| {
+        let limit = 1i32 << (bits - 1);
+        (-limit..limit).contains(&relative) && relative & 1 == 0
+    };
+
+    match relocation.kind {
+        R_RISCV_32 => patch32(buffer, 0, |_| relocation.value),
+        R_RISCV_32_PCREL => patch32(buffer, 0, |_| relative as u32),
+        R_RISCV_BRANCH if in_range(13) => patch32(buffer, 0, |inst| {
+            (inst & 0x01FF_F07F) | b_imm(relative as u32)
+        }),
+        R_RISCV_JAL if in_range(21) => patch32(buffer, 0, |inst| {
+            (inst & 0x0000_0FFF) | j_imm(relative as u32)
+        }),
+        R_RISCV_CALL | R_RISCV_CALL_PLT => {
+            patch32(buffer, 0, |inst| u_hi(inst, relative as u32))?;
+            patch32(buffer, 4, |inst| i_lo(inst, relative as u32))
+        }
+        R_RISCV_PCREL_HI20 | R_RISCV_GOT_HI20 => {
+            patch32(buffer, 0, |inst| u_hi(inst, relative as u32))
+        }
+        R_RISCV_PCREL_LO12_I | R_RISCV_PCREL_LO12_S => {
+            // The symbol is the `auipc` of the high part
+            let high = high_parts
+                .iter()
+                .find(|high| high.address == relocation.value)
+                .ok_or(out_of_range)?;
+            if high.got {
+                // GOT load (`lw rd, lo(rs1)`), compute the address instead (`addi rd, rs1, lo`)
+                let inst = buffer
+                    .get(..4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+                if relocation.kind != R_RISCV_PCREL_LO12_I
+                    || inst.map_or(true, |inst| inst & 0x707F != LW)
+                {
+                    return Err(Error::UnsupportedRelocation {
+                        object: relocation.object,
+                        relocation: R_RISCV_GOT_HI20,
+                    });
+                }
+                return patch32(buffer, 0, |inst| i_lo((inst & !0x707F) | ADDI, high.offset));
+            }
+
+            match relocation.kind {
+                R_RISCV_PCREL_LO12_I => patch32(buffer, 0, |inst| i_lo(inst, high.offset)),
+                _ => patch32(buffer, 0, |inst| s_lo(inst, high.offset)),
+            }
+        }
+        R_RISCV_HI20 => patch32(buffer, 0, |inst| u_hi(inst, relocation.value)),
+        R_RISCV_LO12_I => patch32(buffer, 0, |inst| i_lo(inst, relocation.value)),
+        R_RISCV_LO12_S => patch32(buffer, 0, |inst| s_lo(inst, relocation.value)),
+        R_RISCV_RVC_BRANCH if in_range(9) => {
+            patch16(buffer, |inst| (inst & 0xE383) | cb_imm(relative as u32))
+        }
+        R_RISCV_RVC_JUMP if in_range(12) => {
+            patch16(buffer, |inst| (inst & 0xE003) | cj_imm(relative as u32))
+        }
+        // Label differences (e.g. jump tables)
+        R_RISCV_ADD8 => patch_data(buffer, 1, |data| data.wrapping_add(relocation.value)),
+        R_RISCV_ADD16 => patch_data(buffer, 2, |data| data.wrapping_add(relocation.value)),
+        R_RISCV_ADD32 => patch_data(buffer, 4, |data| data.wrapping_add(relocation.value)),
+        R_RISCV_SUB6 => patch_data(buffer, 1, |data| {
+            (data & !0x3F) | (data.wrapping_sub(relocation.value) & 0x3F)
+        }),
+        R_RISCV_SUB8 => patch_data(buffer, 1, |data| data.wrapping_sub(relocation.value)),
+        R_RISCV_SUB16 => patch_data(buffer, 2, |data| data.wrapping_sub(relocation.value)),
+        R_RISCV_SUB32 => patch_data(buffer, 4, |data| data.wrapping_sub(relocation.value)),
+        R_RISCV_SET6 => patch_data(buffer, 1, |data| (data & !0x3F) | (relocation.value & 0x3F)),
+        R_RISCV_SET8 => patch_data(buffer, 1, |_| relocation.value),
+        R_RISCV_SET16 => patch_data(buffer, 2, |_| relocation.value),
+        R_RISCV_SET32 => patch_data(buffer, 4, |_| relocation.value),
+        R_RISCV_RELAX | R_RISCV_ALIGN => Ok(()),
+        R_RISCV_BRANCH | R_RISCV_JAL | R_RISCV_RVC_BRANCH | R_RISCV_RVC_JUMP => Err(out_of_range),
+        kind => Err(Error::UnsupportedRelocation {
+            object: relocation.object,
+            relocation: kind,
+        }),
+    }
+}
+
+/// Patch a 32-bit value (little-endian).
+fn patch32(buffer: &mut [u8], offset: usize, f: impl FnOnce(u32) -> u32) -> Result<(), Error> {
+    let size = buffer.len();
+    let bytes = buffer
+        .get_mut(offset..offset + 4)
+        .ok_or(Error::InvalidInstructionSize(size))?;
+    // Unwrap is safe because the slice is 4 bytes
+    let value = f(u32::from_le_bytes((&*bytes).try_into().unwrap()));
+    bytes.copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+/// Patch a 16-bit instruction (little-endian).
+fn patch16(buffer: &mut [u8], f: impl FnOnce(u32) -> u32) -> Result<(), Error> {
+    let size = buffer.len();
+    let bytes = buffer
+        .get_mut(..2)
+        .ok_or(Error::InvalidInstructionSize(size))?;
+    let value = f(u16::from_le_bytes([bytes[0], bytes[1]]) as u32) as u16;
+    bytes.copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+/// Patch a 1, 2 or 4-byte value (little-endian, truncated).
+fn patch_data(buffer: &mut [u8], size: usize, f: impl FnOnce(u32) -> u32) -> Result<(), Error> {
+    let len = buffer.len();
+    let bytes = buffer
+        .get_mut(..size)
+        .ok_or(Error::InvalidInstructionSize(len))?;
+    let mut value = [0; 4];
+    value[..size].copy_from_slice(bytes);
+    let value = f(u32::from_le_bytes(value)).to_le_bytes();
+    bytes.copy_from_slice(&value[..size]);
+    Ok(())
+}
+
+/// Set the U-type immediate to the high part of a value (rounded for the low part sign).
+fn u_hi(inst: u32, value: u32) -> u32 {
+    (inst & 0xFFF) | (value.wrapping_add(0x800) & 0xFFFF_F000)
+}
+
+/// Set the I-type immediate to the low part of a value.
+fn i_lo(inst: u32, value: u32) -> u32 {
+    (inst & 0x000F_FFFF) | ((value & 0xFFF) << 20)
+}
+
+/// Set the S-type immediate to the low part of a value.
+fn s_lo(inst: u32, value: u32) -> u32 {
+    (inst & 0x01FF_F07F) | ((value & 0x1F) << 7) | (((value >> 5) & 0x7F) << 25)
+}
+
+/// Encode a B-type immediate.
+fn b_imm(offset: u32) -> u32 {
+    (((offset >> 12) & 0x1) << 31)
+        | (((offset >> 5) & 0x3F) << 25)
+        | (((offset >> 1) & 0xF) << 8)
+        | (((offset >> 11) & 0x1) << 7)
+}
+
+/// Encode a J-type immediate.
+fn j_imm(offset: u32) -> u32 {
+    (((offset >> 20) & 0x1) << 31)
+        | (((offset >> 1) & 0x3FF) << 21)
+        | (((offset >> 11) & 0x1) << 20)
+        | (offset & 0xF_F000)
+}
+
+/// Encode a CB-type (`c.beqz`, `c.bnez`) immediate.
+fn cb_imm(offset: u32) -> u32 {
+    (((offset >> 8) & 0x1) << 12)
+        | (((offset >> 3) & 0x3) << 10)
+        | (((offset >> 6) & 0x3) << 5)
+        | (((offset >> 1) & 0x3) << 3)
+        | (((offset >> 5) & 0x1) << 2)
+}
+
+/// Encode a CJ-type (`c.j`, `c.jal`) immediate.
+fn cj_imm(offset: u32) -> u32 {
+    (((offset >> 11) & 0x1) << 12)
+        | (((offset >> 4) & 0x1) << 11)
+        | (((offset >> 8) & 0x3) << 9)
+        | (((offset >> 10) & 0x1) << 8)
+        | (((offset >> 6) & 0x1) << 7)
+        | (((offset >> 7) & 0x1) << 6)
+        | (((offset >> 1) & 0x7) << 3)
+        | (((offset >> 5) & 0x1) << 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_immediates() {
+        // beq zero, zero, -8 / jal zero, 2046 / jal ra, -2048 / c.beqz a0, -4 / c.j 40
+        assert_eq!(b_imm(-8i32 as u32) | 0x0000_0063, 0xFE00_0CE3);
+        assert_eq!(j_imm(2046) | 0x0000_006F, 0x7FE0_006F);
+        assert_eq!(j_imm(-2048i32 as u32) | 0x0000_00EF, 0x801F_F0EF);
+        assert_eq!(cb_imm(-4i32 as u32) | 0xC101, 0xDD75);
+        assert_eq!(cj_imm(40) | 0xA001, 0xA025);
+    }
+
+    #[test]
+    fn test_link_objects() {
+        let main = include_bytes!("../../tests/link/main.o");
+        let lib = include_bytes!("../../tests/link/lib.o");
+
+        let image = link_objects(&[main, lib], &Config::default()).unwrap();
+        assert_eq!(image.symbol("_start"), Some(0));
+        assert_eq!(image.symbol("add_one"), Some(0x28));
+        assert_eq!(image.symbol("result"), Some(LINK_RAM_ADDRESS + 8));
+        assert_eq!(image.symbol("missing"), None);
+
+        // increment = 1, table = [add_one], result = 0
+        assert_eq!(image.data, [1, 0, 0, 0, 0x28, 0, 0, 0, 0, 0, 0, 0]);
+
+        // Undefined symbols
+        let result = link_objects(&[main], &Config::default());
+        assert!(matches!(
+            result,
+            Err(Error::UndefinedSymbol { object: 0, .. })
+        ));
+
+        // Duplicate symbols
+        let result = link_objects(&[main, lib, lib], &Config::default());
+        assert!(matches!(
+            result,
+            Err(Error::DuplicateSymbol { object: 2, .. })
+        ));
+
+        // Executables are not relocatable
+        let elf = include_bytes!("../../tests/test.elf");
+        let result = link_objects(&[elf], &Config::default());
+        assert!(matches!(result, Err(Error::NotRelocatable(0))));
+    }
+
+    #[test]
+    fn test_link_pic() {
+        let pic = include_bytes!("../../tests/link/pic.o");
+        let image = link_objects(&[pic], &Config::default()).unwrap();
+        let counter = image.symbol("counter").unwrap();
+        assert_eq!(counter, LINK_RAM_ADDRESS);
+
+        // Label differences: `counter - .`, then `.Lcase1 - .Lcase0` (16 and 8 bits)
+        let offset = image.symbol("counter_offset").unwrap() as usize;
+        let word = u32::from_le_bytes(image.code[offset..offset + 4].try_into().unwrap());
+        assert_eq!(word, counter.wrapping_sub(offset as u32));
+        assert_eq!(image.code[offset + 4..offset + 7], [4, 0, 4]);
+    }
+
+    #[cfg(feature = "interpreter")]
+    #[test]
+    fn test_run_linked_pic() {
+        use crate::interpreter::{memory::SliceMemory, ExitReason, Interpreter, State};
+
+        let pic = include_bytes!("../../tests/link/pic.o");
+        let image = link_objects(&[pic], &Config::default()).unwrap();
+
+        let mut ram = [0; 4];
+        ram.copy_from_slice(&image.data);
+        let mut memory = SliceMemory::new(&image.code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.program_counter = image.symbol("pic_start").unwrap();
+
+        // Counter (through the GOT) incremented, through the jump table
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.cpu.a0(), 42);
+        assert_eq!(ram, [42, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "interpreter")]
+    #[test]
+    fn test_run_linked() {
+        use crate::interpreter::{memory::SliceMemory, ExitReason, Interpreter, State};
+
+        let main = include_bytes!("../../tests/link/main.o");
+        let lib = include_bytes!("../../tests/link/lib.o");
+        let image = link_objects(&[main, lib], &Config::default()).unwrap();
+
+        let mut ram = [0; 16];
+        ram[..image.data.len()].copy_from_slice(&image.data);
+        let mut memory = SliceMemory::new(&image.code, &mut ram);
+        let mut interpreter = Interpreter::new(&mut memory, 0);
+        interpreter.program_counter = image.symbol("_start").unwrap();
+
+        // 41 + increment, stored in result, then doubled
+        assert_eq!(
+            interpreter.run(),
+            Ok(State::Halted {
+                reason: ExitReason::Ebreak
+            })
+        );
+        assert_eq!(interpreter.registers.cpu.a0(), 84);
+        assert_eq!(
+            interpreter.registers.cpu.a3() as u32,
+            image.symbol("add_one").unwrap()
+        );
+        assert_eq!(ram[8..12], [42, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Load-Immediate Module
+//!
+//! Fusion of `lui` + `addi` pairs into 8-byte load-immediates (check [`super::Config::with_load_immediate`]).
+use crate::format::TypeUL;
+use crate::instruction::embive::{Decoded, Instruction, InstructionImpl, Lui, OpImm};
+
+/// Maximum number of pairs checked against a single scan of the direct jump and branch targets.
+const PAIR_BATCH: usize = 128;
+
+/// Fuse the `lui` + `addi` pairs (same register) of transpiled code into load-immediates.
+///
+/// The `lui` gets the load-immediate flag, and the `addi` is replaced by the full 32-bit immediate.
+/// Pairs where the `addi` is a direct jump or branch target are kept as is.
+///
+/// Pairs are collected in batches (sorted by offset), and the code is scanned once per batch for the direct
+/// targets, so code with up to [`PAIR_BATCH`] pairs is scanned only once.
+///
+/// # Arguments
+/// - `code`: The transpiled (Embive) code.
+pub(super) fn fuse(code: &mut [u8]) {
+    let mut pairs = [0u32; PAIR_BATCH];
+    let mut targeted = [false; PAIR_BATCH];
+    let mut offset = 0;
+
+    loop {
+        // Collect the next batch of pairs (offsets of the `lui`)
+        let mut count = 0;
+        while count < PAIR_BATCH {
+            let Some(instruction) = instruction_at(code, offset) else {
+                break;
+            };
+            let mut size = Instruction::from(instruction).size();
+            if pair_at(code, offset).is_some() {
+                pairs[count] = offset as u32;
+                count += 1;
+                size = Lui::LOAD_IMMEDIATE_SIZE;
+            }
+
+            offset += size as usize;
+        }
+
+        if count == 0 {
+            break;
+        }
+
+        // Keep the pairs where the `addi` is a direct target
+        let pairs = &pairs[..count];
+        targeted[..count].fill(false);
+        for_each_direct_target(code, |target| {
+            if let Ok(index) = pairs.binary_search(&target.wrapping_sub(4)) {
+                targeted[index] = true;
+            }
+        });
+
+        for (&pair, &targeted) in pairs.iter().zip(&targeted) {
+            if targeted {
+                continue;
+            }
+
+            let pair = pair as usize;
+            // Unwrap is safe because the pair was found above (fusion doesn't change other pairs)
+            let (rd, imm) = pair_at(code, pair).unwrap();
+            let lui = Lui(TypeUL {
+                rd,
+                imm: imm & !0xFFF,
+                li: true,
+            });
+            code[pair..pair + 4]
+                .copy_from_slice(&(lui.encode() | Lui::opcode() as u32).to_le_bytes());
+            code[pair + 4..pair + 8].copy_from_slice(&imm.to_le_bytes());
+        }
+    }
+}
+
+/// Get the (zero-padded) instruction at an offset, if any.
+fn instruction_at(code: &[u8], offset: usize) -> Option<u32> {
+    let bytes = code.get(offset..)?;
+    if bytes.len() < 2 {
+        return None;
+    }
+
+    let mut word = [0; 4];
+    let len = bytes.len().min(4);
+    word[..len].copy_from_slice(&bytes[..len]);
+    Some(u32::from_le_bytes(word))
+}
+
+/// Check for a `lui` followed by an `addi` to the same register.
+///
+/// # Returns
+/// - `Some((u8, i32))`: The destination register and the loaded immediate.
+/// - `None`: Not a `lui` + `addi` pair.
+fn pair_at(code: &[u8], offset: usize) -> Option<(u8, i32)> {
+    let Decoded::Lui(lui) = Instruction::from(instruction_at(code, offset)?).decode()? else {
+        return None;
+    };
+    // Unwrap is safe because the slice is 4 bytes
+    let next = code.get(offset + 4..offset + 8)?;
+    let Decoded::OpImm(addi) =
+        Instruction::from(u32::from_le_bytes(next.try_into().unwrap())).decode()?
+    else {
+        return None;
+    };
+
+    let (lui, addi) = (lui.0, addi.0);
+    (!lui.li
+        && lui.rd != 0
+        && addi.func == OpImm::ADDI_FUNC
+        && addi.rd_rs2 == lui.rd
+        && addi.rs1 == lui.rd)
+        .then_some((lui.rd, lui.imm.wrapping_add(addi.imm)))
+}
+
+/// Call a function with the target of each direct jump or branch (`jal`, `c.j`, `c.jal`, `beq`, `c.beqz`, ...).
+fn for_each_direct_target(code: &[u8], mut f: impl FnMut(u32)) {
+    let mut offset = 0;
+    while let Some(instruction) = instruction_at(code, offset) {
+        let instruction = Instruction::from(instruction);
+        let imm = match instruction.decode() {
+            Some(Decoded::Branch(branch)) => Some(branch.0.imm),
+            Some(Decoded::Jal(jal)) => Some(jal.0.imm),
+            Some(Decoded::CJ(jump)) => Some(jump.0.imm),
+            Some(Decoded::CJal(jump)) => Some(jump.0.imm),
+            Some(Decoded::CBeqz(branch)) => Some(branch.0.imm),
+            Some(Decoded::CBnez(branch)) => Some(branch.0.imm),
+            _ => None,
+        };
+        if let Some(imm) = imm {
+            f((offset as u32).wrapping_add_signed(imm));
+        }
+
+        offset += instruction.size() as usize;
+    }
+}
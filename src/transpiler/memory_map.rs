@@ -0,0 +1,391 @@
+//! Memory Map Module
+//!
+//! Device memory description, validated against the ELF segments (check [`super::Config::with_memory_map`]),
+//! optionally parsed from the `MEMORY` command of a linker script ([`memory_map`]).
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use elf::{
+    abi::{PF_W, PF_X, PT_LOAD},
+    endian::LittleEndian,
+    parse::ParsingTable,
+    segment::ProgramHeader,
+};
+
+use super::Error;
+
+/// A region of the device memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion<'a> {
+    /// Region name (e.g. `FLASH`).
+    pub name: &'a str,
+    /// Start address.
+    pub origin: u32,
+    /// Length, in bytes.
+    pub length: u32,
+    /// Readable (or loadable, for the load address of initialized data).
+    pub read: bool,
+    /// Writable.
+    pub write: bool,
+    /// Executable.
+    pub execute: bool,
+}
+
+impl<'a> MemoryRegion<'a> {
+    /// Create a new memory region, with all attributes (readable, writable and executable).
+    ///
+    /// Arguments:
+    /// - `name`: Region name.
+    /// - `origin`: Start address.
+    /// - `length`: Length, in bytes.
+    pub const fn new(name: &'a str, origin: u32, length: u32) -> Self {
+        MemoryRegion {
+            name,
+            origin,
+            length,
+            read: true,
+            write: true,
+            execute: true,
+        }
+    }
+
+    /// Set the region attributes.
+    pub const fn with_attributes(mut self, read: bool, write: bool, execute: bool) -> Self {
+        self.read = read;
+        self.write = write;
+        self.execute = execute;
+        self
+    }
+
+    /// Check if an address range (`size` bytes from `address`) is inside the region.
+    pub fn contains(&self, address: u32, size: u32) -> bool {
+        let end = self.origin as u64 + self.length as u64;
+        address >= self.origin && address as u64 + size as u64 <= end
+    }
+}
+
+/// Check that all loadable ELF segments fit the memory map:
+/// - Run address (`p_vaddr`, `p_memsz` bytes) inside a region with the segment permissions (write, execute).
+/// - Load address (`p_paddr`, `p_filesz` bytes) inside a readable region.
+pub(crate) fn check_segments(
+    segments: &ParsingTable<'_, LittleEndian, ProgramHeader>,
+    regions: &[MemoryRegion<'_>],
+) -> Result<(), Error> {
+    let fits = |address: u32, size: u32, write: bool, execute: bool| {
+        regions.iter().any(|region| {
+            region.contains(address, size)
+                && (region.write || !write)
+                && (region.execute || !execute)
+        })
+    };
+
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.p_type != PT_LOAD {
+            continue;
+        }
+
+        let address = segment.p_vaddr as u32;
+        let size = segment.p_memsz as u32;
+        let write = segment.p_flags & PF_W != 0;
+        let execute = segment.p_flags & PF_X != 0;
+        if size > 0 && !fits(address, size, write, execute) {
+            return Err(Error::SegmentOutsideMemory {
+                segment: i,
+                address,
+                size,
+            });
+        }
+
+        let load_address = segment.p_paddr as u32;
+        let load_size = segment.p_filesz as u32;
+        let loadable =
+            |region: &MemoryRegion<'_>| region.read && region.contains(load_address, load_size);
+        if load_size > 0 && !regions.iter().any(loadable) {
+            return Err(Error::SegmentOutsideMemory {
+                segment: i,
+                address: load_address,
+                size: load_size,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Memory map tokenizer (linker script subset).
+struct Tokens<'a> {
+    script: &'a str,
+    offset: usize,
+}
+
+impl<'a> Tokens<'a> {
+    /// Skip whitespace and comments.
+    fn skip(&mut self) -> Result<(), Error> {
+        loop {
+            let rest = &self.script[self.offset..];
+            let trimmed = rest.trim_start();
+            self.offset += rest.len() - trimmed.len();
+
+            if !trimmed.starts_with("/*") {
+                return Ok(());
+            }
+            let end = trimmed
+                .find("*/")
+                .ok_or(Error::InvalidMemoryMap(self.offset))?;
+            self.offset += end + 2;
+        }
+    }
+
+    /// Get the next token (a word or a punctuation character) and its offset.
+    fn next(&mut self) -> Result<Option<(usize, &'a str)>, Error> {
+        self.skip()?;
+        let start = self.offset;
+        let rest = &self.script[start..];
+        let len = match rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.')) {
+            Some(0) => rest.chars().next().map_or(0, char::len_utf8),
+            Some(len) => len,
+            None => rest.len(),
+        };
+        if len == 0 {
+            return Ok(None);
+        }
+        self.offset += len;
+        Ok(rest.get(..len).map(|token| (start, token)))
+    }
+
+    /// Get the next token, failing at the end of the script.
+    fn expect_any(&mut self) -> Result<(usize, &'a str), Error> {
+        self.next()?.ok_or(Error::InvalidMemoryMap(self.offset))
+    }
+
+    /// Consume the expected token.
+    fn expect(&mut self, expected: &str) -> Result<(), Error> {
+        match self.expect_any()? {
+            (_, token) if token == expected => Ok(()),
+            (offset, _) => Err(Error::InvalidMemoryMap(offset)),
+        }
+    }
+
+    /// Parse `<keyword> = <number>` (keyword case-insensitive, any of `keywords`).
+    fn assignment(&mut self, keywords: &[&str]) -> Result<u32, Error> {
+        let (offset, keyword) = self.expect_any()?;
+        if !keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+            return Err(Error::InvalidMemoryMap(offset));
+        }
+        self.expect("=")?;
+        let (offset, number) = self.expect_any()?;
+        parse_number(number).ok_or(Error::InvalidMemoryMap(offset))
+    }
+}
+
+/// Parse a linker script number (decimal, `0x` hexadecimal or octal, with an optional `K`/`M` suffix).
+fn parse_number(token: &str) -> Option<u32> {
+    let (digits, multiplier) = match token.as_bytes().last()? {
+        b'k' | b'K' => (&token[..token.len() - 1], 1024),
+        b'm' | b'M' => (&token[..token.len() - 1], 1024 * 1024),
+        _ => (token, 1),
+    };
+
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u32::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    value.checked_mul(multiplier)
+}
+
+/// Parse the `MEMORY` command of a linker script, e.g.:
+/// ```text
+/// MEMORY
+/// {
+///     FLASH (rx)  : ORIGIN = 0x00000000, LENGTH = 64K
+///     RAM   (rwx) : ORIGIN = 0x80000000, LENGTH = 16K
+/// }
+/// ```
+///
+/// Supported subset: region names, attributes (`r`, `w`, `x`, and `!` to negate the following ones;
+/// other attributes are ignored), `ORIGIN`/`org`/`o` and `LENGTH`/`len`/`l` with decimal, hexadecimal (`0x`) or
+/// octal (leading `0`) numbers and an optional `K` or `M` suffix, and `/* */` comments.
+/// Expressions (e.g. `ORIGIN(RAM)`) are not supported. The rest of the script is ignored.
+///
+/// Example:
+/// ```
+/// use embive::transpiler::memory_map;
+///
+/// let script = "MEMORY { FLASH (rx) : ORIGIN = 0, LENGTH = 64K  RAM (rwx) : ORIGIN = 0x80000000, LENGTH = 16K }";
+/// let mut regions = Vec::new();
+/// memory_map(script, |region| regions.push(region)).unwrap();
+/// assert_eq!(regions[0].name, "FLASH");
+/// assert_eq!(regions[1].length, 16 * 1024);
+/// assert!(!regions[0].write && regions[1].write);
+/// ```
+///
+/// # Arguments
+/// - `script`: The linker script (or only its `MEMORY` command).
+/// - `function`: Called for each memory region (e.g. to store it in a table).
+///
+/// # Returns
+/// - `Ok(())`: Success, all regions were emitted.
+/// - `Err(Error)`: No `MEMORY` command, or invalid syntax ([`Error::InvalidMemoryMap`], with the byte offset).
+pub fn memory_map<'a, F: FnMut(MemoryRegion<'a>)>(
+    script: &'a str,
+    mut function: F,
+) -> Result<(), Error> {
+    let mut tokens = Tokens { script, offset: 0 };
+
+    // Find the `MEMORY` command
+    loop {
+        match tokens.next()? {
+            Some((_, "MEMORY")) => break,
+            Some(_) => {}
+            None => return Err(Error::InvalidMemoryMap(script.len())),
+        }
+    }
+    tokens.expect("{")?;
+
+    loop {
+        let (offset, name) = tokens.expect_any()?;
+        match name {
+            "}" => return Ok(()),
+            name if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.') => {
+            }
+            _ => return Err(Error::InvalidMemoryMap(offset)),
+        }
+        let mut region = MemoryRegion::new(name, 0, 0);
+
+        // Attributes (all by default)
+        let (offset, token) = tokens.expect_any()?;
+        match token {
+            "(" => {
+                let attributes = &script[tokens.offset..];
+                let end = attributes
+                    .find(')')
+                    .ok_or(Error::InvalidMemoryMap(offset))?;
+                let (mut read, mut write, mut execute) = (false, false, false);
+                let mut negate = false;
+                for attribute in attributes[..end].chars() {
+                    match attribute.to_ascii_lowercase() {
+                        '!' => negate = true,
+                        'r' => read = !negate,
+                        'w' => write = !negate,
+                        'x' => execute = !negate,
+                        _ => {}
+                    }
+                }
+                // Only negated attributes: everything else is allowed
+                if negate && !(read || write || execute) {
+                    let negated = |c: char| attributes[..end].contains(c);
+                    (read, write, execute) = (!negated('r'), !negated('w'), !negated('x'));
+                }
+                region = region.with_attributes(read, write, execute);
+                tokens.offset += end + 1;
+                tokens.expect(":")?;
+            }
+            ":" => {}
+            _ => return Err(Error::InvalidMemoryMap(offset)),
+        }
+
+        region.origin = tokens.assignment(&["ORIGIN", "org", "o"])?;
+        tokens.expect(",")?;
+        region.length = tokens.assignment(&["LENGTH", "len", "l"])?;
+        function(region);
+    }
+}
+
+/// Parse the `MEMORY` command of a linker script.
+/// Same as [`memory_map`], with the regions dynamically allocated and returned as a `Vec<MemoryRegion>`.
+///
+/// # Arguments
+/// - `script`: The linker script (or only its `MEMORY` command).
+///
+/// # Returns
+/// - `Ok(Vec<MemoryRegion>)`: The memory regions.
+/// - `Err(Error)`: No `MEMORY` command, or invalid syntax ([`Error::InvalidMemoryMap`], with the byte offset).
+#[cfg(feature = "alloc")]
+pub fn memory_map_vec(script: &str) -> Result<Vec<MemoryRegion<'_>>, Error> {
+    let mut regions = Vec::new();
+    memory_map(script, |region| regions.push(region))?;
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("16"), Some(16));
+        assert_eq!(parse_number("0x8000_0000"), None);
+        assert_eq!(parse_number("0x80000000"), Some(0x8000_0000));
+        assert_eq!(parse_number("010"), Some(8));
+        assert_eq!(parse_number("64K"), Some(64 * 1024));
+        assert_eq!(parse_number("2M"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_number("8192M"), None);
+        assert_eq!(parse_number("K"), None);
+    }
+
+    #[test]
+    fn test_memory_map() {
+        let script = "
+            /* Device memory */
+            MEMORY
+            {
+                FLASH (rx) : ORIGIN = 0x0, LENGTH = 64K
+                RAM (!x) : org = 0x80000000, len = 0x4000 /* 16K */
+                SHARED : o = 0x90000000, l = 256
+            }
+
+            SECTIONS { .text : { *(.text) } > FLASH }
+        ";
+        let mut regions = [MemoryRegion::new("", 0, 0); 3];
+        let mut count = 0;
+        memory_map(script, |region| {
+            regions[count] = region;
+            count += 1;
+        })
+        .unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(
+            regions,
+            [
+                MemoryRegion::new("FLASH", 0, 0x10000).with_attributes(true, false, true),
+                MemoryRegion::new("RAM", 0x8000_0000, 0x4000).with_attributes(true, true, false),
+                MemoryRegion::new("SHARED", 0x9000_0000, 256),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memory_map_invalid() {
+        let result = memory_map("SECTIONS {}", |_| {});
+        assert!(matches!(result, Err(Error::InvalidMemoryMap(11))));
+
+        let result = memory_map("MEMORY { RAM : ORIGIN = 0x80000000 LENGTH = 1K }", |_| {});
+        assert!(matches!(result, Err(Error::InvalidMemoryMap(35))));
+
+        let result = memory_map(
+            "MEMORY { RAM : ORIGIN = ORIGIN(FLASH), LENGTH = 1K }",
+            |_| {},
+        );
+        assert!(matches!(result, Err(Error::InvalidMemoryMap(24))));
+
+        let result = memory_map("MEMORY { /* RAM", |_| {});
+        assert!(matches!(result, Err(Error::InvalidMemoryMap(9))));
+    }
+
+    #[test]
+    fn test_region_contains() {
+        let region = MemoryRegion::new("RAM", 0xFFFF_FF00, 0x100);
+        assert!(region.contains(0xFFFF_FF00, 0x100));
+        assert!(region.contains(0xFFFF_FFFF, 1));
+        assert!(!region.contains(0xFFFF_FFFF, 2));
+        assert!(!region.contains(0xFFFF_FEFF, 1));
+    }
+}
@@ -0,0 +1,231 @@
+//! Strict Mode Module
+//!
+//! Encodings accepted by the transpiler, but rejected in strict mode (check [`super::Config::with_strict`]).
+use core::fmt::{Display, Formatter, Result};
+
+/// Canonical NOP (`addi x0, x0, 0`).
+const NOP: u32 = 0x0000_0013;
+/// Defined illegal instruction (`c.unimp`), used on purpose to trap.
+const C_UNIMP: u32 = 0x0000;
+/// Illegal instruction (`unimp`, `csrrw x0, cycle, x0`), used on purpose to trap.
+const UNIMP: u32 = 0xC000_1073;
+
+/// 32-bit System instructions supported by the interpreter (`ecall`, `ebreak`, `wfi`, `mret`).
+const SYSTEM_SUPPORTED: [u32; 4] = [0x0000_0073, 0x0010_0073, 0x1050_0073, 0x3020_0073];
+/// `sfence.vma` mask (any `rs1` and `rs2`), also supported by the interpreter.
+const SFENCE_VMA_MASK: u32 = 0xFE00_7FFF;
+/// `sfence.vma` encoding (check [`SFENCE_VMA_MASK`]).
+const SFENCE_VMA: u32 = 0x1200_0073;
+/// Address translation register (`satp`), supported with the `mmu` feature.
+#[cfg(feature = "mmu")]
+const SATP: u16 = 0x180;
+/// Memory protection registers (`pmpcfg0..=pmpcfg15`, `pmpaddr0..=pmpaddr63`), supported with the `pmp` feature.
+#[cfg(feature = "pmp")]
+const PMP: (u16, u16) = (0x3A0, 0x3EF);
+
+/// Control and status registers supported by the interpreter (same map as its CSR file).
+/// Custom CSRs (need a handler) and `time`/`timeh` (need a time source) are configured at runtime.
+const CSR_SUPPORTED: [(u16, u16); 10] = [
+    (0x300, 0x305), // mstatus, misa, medeleg, mideleg, mie, mtvec
+    (0x310, 0x310), // mstatush
+    (0x320, 0x344), // mcountinhibit, mhpmevent*, mscratch, mepc, mcause, mtval, mip
+    (0x7C0, 0x7FF), // Custom read/write
+    (0xB00, 0xB9F), // mcycle, minstret, mhpmcounter*
+    (0xBC0, 0xBFF), // Custom read/write
+    (0xC01, 0xC01), // time
+    (0xC81, 0xC81), // timeh
+    (0xF11, 0xF15), // mvendorid, marchid, mimpid, mhartid, mconfigptr
+    (0xFC0, 0xFFF), // Custom read-only
+];
+
+/// Strict mode violation (check [`super::Config::with_strict`]).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StrictViolation {
+    /// Reserved encoding (e.g. `c.addi16sp` with a zero immediate).
+    Reserved,
+    /// Hint encoding (e.g. `c.li` with `rd = x0`), executed as a no-op or not at all.
+    Hint,
+    /// Unsupported system instruction (e.g. `sret`). The instruction is provided.
+    UnsupportedSystem(u32),
+    /// Unsupported control and status register. The register address is provided.
+    UnsupportedCsr(u16),
+}
+
+impl Display for StrictViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            StrictViolation::Reserved => write!(f, "reserved encoding"),
+            StrictViolation::Hint => write!(f, "hint encoding"),
+            StrictViolation::UnsupportedSystem(inst) => {
+                write!(f, "unsupported system instruction {inst:#010x}")
+            }
+            StrictViolation::UnsupportedCsr(addr) => write!(f, "unsupported CSR {addr:#05x}"),
+        }
+    }
+}
+
+/// Check a RISC-V instruction against the strict mode rules.
+///
+/// # Arguments
+/// - `data`: The raw RISC-V instruction (upper half ignored for 16-bit instructions).
+///
+/// # Returns
+/// - `Ok(())`: The instruction is accepted.
+/// - `Err(StrictViolation)`: The instruction is rejected.
+pub(crate) fn check(data: u32) -> core::result::Result<(), StrictViolation> {
+    if data & 0b11 != 0b11 {
+        return check_compressed(data & 0xFFFF);
+    }
+
+    let rd = (data >> 7) & 0x1F;
+    let funct3 = (data >> 12) & 0b111;
+    match data & 0x7F {
+        // OP-IMM, OP, LUI, AUIPC writing to x0 (other than the canonical NOP)
+        0b001_0011 | 0b011_0011 | 0b011_0111 | 0b001_0111 if rd == 0 && data != NOP => {
+            Err(StrictViolation::Hint)
+        }
+        // FENCE without predecessor or successor set (e.g. `pause`)
+        0b000_1111 if funct3 == 0 && ((data >> 20) & 0xF == 0 || (data >> 24) & 0xF == 0) => {
+            Err(StrictViolation::Hint)
+        }
+        0b000_1111 if funct3 > 1 => Err(StrictViolation::Reserved),
+        0b111_0011
+            if funct3 == 0
+                && !SYSTEM_SUPPORTED.contains(&data)
+                && data & SFENCE_VMA_MASK != SFENCE_VMA =>
+        {
+            Err(StrictViolation::UnsupportedSystem(data))
+        }
+        0b111_0011 if funct3 != 0 && data != UNIMP => {
+            let addr = (data >> 20) as u16;
+            #[cfg(feature = "mmu")]
+            if addr == SATP {
+                return Ok(());
+            }
+            #[cfg(feature = "pmp")]
+            if (PMP.0..=PMP.1).contains(&addr) {
+                return Ok(());
+            }
+            match CSR_SUPPORTED
+                .iter()
+                .any(|&(start, end)| (start..=end).contains(&addr))
+            {
+                true => Ok(()),
+                false => Err(StrictViolation::UnsupportedCsr(addr)),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check a 16-bit RISC-V instruction against the strict mode rules.
+fn check_compressed(data: u32) -> core::result::Result<(), StrictViolation> {
+    let funct3 = (data >> 13) & 0b111;
+    let bit12 = (data >> 12) & 0b1;
+    let rd = (data >> 7) & 0x1F;
+    let rs2 = (data >> 2) & 0x1F;
+    // CI-type immediate (bit 12 and bits 6-2)
+    let imm = (bit12 << 5) | rs2;
+
+    let violation = match (data & 0b11, funct3) {
+        // C.ADDI4SPN with a zero immediate
+        (0b00, 0b000) if (data >> 5) & 0xFF == 0 && data != C_UNIMP => StrictViolation::Reserved,
+        // C.NOP with a non-zero immediate, C.ADDI with a zero immediate
+        (0b01, 0b000) if (rd == 0) != (imm == 0) => StrictViolation::Hint,
+        // C.LI to x0
+        (0b01, 0b010) if rd == 0 => StrictViolation::Hint,
+        // C.ADDI16SP and C.LUI with a zero immediate
+        (0b01, 0b011) if imm == 0 => StrictViolation::Reserved,
+        // C.LUI to x0
+        (0b01, 0b011) if rd == 0 => StrictViolation::Hint,
+        // C.SRLI and C.SRAI with shamt[5] set (RV32), or a zero shift amount
+        (0b01, 0b100) if (data >> 11) & 0b1 == 0 && bit12 == 1 => StrictViolation::Reserved,
+        (0b01, 0b100) if (data >> 11) & 0b1 == 0 && rs2 == 0 => StrictViolation::Hint,
+        // C.SLLI with shamt[5] set (RV32), to x0 or with a zero shift amount
+        (0b10, 0b000) if bit12 == 1 => StrictViolation::Reserved,
+        (0b10, 0b000) if rd == 0 || rs2 == 0 => StrictViolation::Hint,
+        // C.LWSP to x0
+        (0b10, 0b010) if rd == 0 => StrictViolation::Reserved,
+        // C.JR with x0
+        (0b10, 0b100) if bit12 == 0 && rs2 == 0 && rd == 0 => StrictViolation::Reserved,
+        // C.MV and C.ADD to x0
+        (0b10, 0b100) if rs2 != 0 && rd == 0 => StrictViolation::Hint,
+        _ => return Ok(()),
+    };
+
+    Err(violation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_compressed() {
+        // Accepted
+        for inst in [
+            0x0000, 0x0001, 0x0505, 0x8105, 0x8901, 0x0506, 0x8082, 0x9002, 0x9502,
+        ] {
+            assert_eq!(check(inst), Ok(()), "{inst:#06x}");
+        }
+
+        let reserved = [
+            0x0008, // c.addi4spn a0, sp, 0
+            0x6101, // c.addi16sp sp, 0
+            0x6501, // c.lui a0, 0
+            0x9101, // c.srli a0, 32
+            0x1502, // c.slli a0, 32
+            0x4002, // c.lwsp zero, 0(sp)
+            0x8002, // c.jr zero
+        ];
+        for inst in reserved {
+            assert_eq!(check(inst), Err(StrictViolation::Reserved), "{inst:#06x}");
+        }
+
+        let hints = [
+            0x0011, // c.nop 4
+            0x0501, // c.addi a0, 0
+            0x4011, // c.li zero, 4
+            0x6005, // c.lui zero, 1
+            0x8101, // c.srli a0, 0
+            0x0006, // c.slli zero, 1
+            0x802a, // c.mv zero, a0
+            0x902a, // c.add zero, a0
+        ];
+        for inst in hints {
+            assert_eq!(check(inst), Err(StrictViolation::Hint), "{inst:#06x}");
+        }
+
+        // Upper half is ignored
+        assert_eq!(check(0x1234_0001), Ok(()));
+    }
+
+    #[test]
+    fn test_check() {
+        // Accepted
+        for inst in [
+            NOP,
+            UNIMP,
+            0x0ff0_000f,
+            0x0000_100f,
+            0x3000_2573,
+            0x0010_0073,
+            0x1205_0073, // sfence.vma a0, zero
+        ] {
+            assert_eq!(check(inst), Ok(()), "{inst:#010x}");
+        }
+
+        assert_eq!(check(0x0015_0013), Err(StrictViolation::Hint)); // addi zero, a0, 1
+        assert_eq!(check(0x0000_1037), Err(StrictViolation::Hint)); // lui zero, 1
+        assert_eq!(check(0x0100_000f), Err(StrictViolation::Hint)); // pause
+        assert_eq!(check(0x0000_200f), Err(StrictViolation::Reserved)); // misc-mem funct3 = 2
+        assert_eq!(
+            check(0x1020_0073), // sret
+            Err(StrictViolation::UnsupportedSystem(0x1020_0073))
+        );
+        assert_eq!(
+            check(0xc000_2573), // rdcycle a0
+            Err(StrictViolation::UnsupportedCsr(0xc00))
+        );
+    }
+}
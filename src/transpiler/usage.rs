@@ -0,0 +1,339 @@
+//! Instruction Usage Module
+
+/// Number of instruction classes (check [`InstructionClass`]).
+pub const INSTRUCTION_CLASSES: usize = 16;
+
+/// RISC-V Instruction Class (major opcode, with the extensions split out).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum InstructionClass {
+    /// Loads (`lb`, `lh`, `lw`, `lbu`, `lhu`).
+    Load = 0,
+    /// Stores (`sb`, `sh`, `sw`).
+    Store = 1,
+    /// Conditional branches (`beq`, `bne`, ...).
+    Branch = 2,
+    /// Jump and link (`jal`).
+    Jal = 3,
+    /// Jump and link register (`jalr`).
+    Jalr = 4,
+    /// Load upper immediate (`lui`).
+    Lui = 5,
+    /// Add upper immediate to PC (`auipc`).
+    Auipc = 6,
+    /// Register-immediate operations (`addi`, `slli`, ...).
+    OpImm = 7,
+    /// Register-register operations (`add`, `sll`, ...).
+    Op = 8,
+    /// Multiplication and division (M extension).
+    MulDiv = 9,
+    /// Atomic memory operations (A extension).
+    Atomic = 10,
+    /// Memory ordering (`fence`, `fence.i`).
+    MiscMem = 11,
+    /// System instructions (`ecall`, `ebreak`, `mret`, `wfi`, `sfence.vma`).
+    System = 12,
+    /// Control and status register instructions (Zicsr extension).
+    Csr = 13,
+    /// 16-bit instructions (C extension).
+    Compressed = 14,
+    /// Unknown opcodes (e.g. vendor-specific instructions, check [`super::Config::with_custom_handler`]).
+    Unknown = 15,
+}
+
+/// Check if a register is one of the registers addressable by most compressed instructions (`x8` to `x15`).
+fn is_compressed_register(register: u32) -> bool {
+    (8..16).contains(&register)
+}
+
+/// Check if a signed immediate fits in `bits` bits.
+fn fits_signed(imm: i32, bits: u32) -> bool {
+    (-(1 << (bits - 1))..(1 << (bits - 1))).contains(&imm)
+}
+
+/// Check if a 32-bit RISC-V instruction has a 16-bit equivalent (C extension, RV32).
+///
+/// Branch and jump offsets are checked as they are, ignoring that compressing the code would shorten them.
+fn is_compressible(inst: u32) -> bool {
+    const SP: u32 = 2;
+    let rd = (inst >> 7) & 0x1F;
+    let rs1 = (inst >> 15) & 0x1F;
+    let rs2 = (inst >> 20) & 0x1F;
+    let funct3 = (inst >> 12) & 0b111;
+    let funct7 = inst >> 25;
+    let imm_i = inst as i32 >> 20;
+    let imm_s = (inst as i32 >> 25) << 5 | rd as i32;
+    // Sign-extended from bit 12 (branches) and 20 (jumps)
+    let imm_b = ((((inst >> 31) << 12)
+        | (((inst >> 7) & 0x1) << 11)
+        | (((inst >> 25) & 0x3F) << 5)
+        | (((inst >> 8) & 0xF) << 1)) as i32)
+        << 19
+        >> 19;
+    let imm_j = ((((inst >> 31) << 20)
+        | (((inst >> 12) & 0xFF) << 12)
+        | (((inst >> 20) & 0x1) << 11)
+        | (((inst >> 21) & 0x3FF) << 1)) as i32)
+        << 11
+        >> 11;
+
+    match inst & 0x7F {
+        // c.lwsp, c.lw
+        0b000_0011 if funct3 == 0b010 => {
+            (rs1 == SP && rd != 0 && imm_i & 0b11 == 0 && (0..256).contains(&imm_i))
+                || (is_compressed_register(rd)
+                    && is_compressed_register(rs1)
+                    && imm_i & 0b11 == 0
+                    && (0..128).contains(&imm_i))
+        }
+        // c.swsp, c.sw
+        0b010_0011 if funct3 == 0b010 => {
+            (rs1 == SP && imm_s & 0b11 == 0 && (0..256).contains(&imm_s))
+                || (is_compressed_register(rs1)
+                    && is_compressed_register(rs2)
+                    && imm_s & 0b11 == 0
+                    && (0..128).contains(&imm_s))
+        }
+        // c.beqz, c.bnez
+        0b110_0011 => {
+            funct3 < 0b010 && rs2 == 0 && is_compressed_register(rs1) && fits_signed(imm_b, 9)
+        }
+        // c.j, c.jal
+        0b110_1111 => (rd == 0 || rd == 1) && fits_signed(imm_j, 12),
+        // c.jr, c.jalr
+        0b110_0111 => (rd == 0 || rd == 1) && rs1 != 0 && imm_i == 0,
+        // c.lui
+        0b011_0111 => rd != 0 && rd != SP && inst >> 12 != 0 && fits_signed(inst as i32 >> 12, 6),
+        0b001_0011 => match funct3 {
+            // c.nop, c.addi, c.li, c.mv, c.addi16sp, c.addi4spn
+            0b000 => {
+                (rd == rs1 && (rd == 0 || imm_i != 0) && fits_signed(imm_i, 6))
+                    || (rd != 0 && rs1 == 0 && fits_signed(imm_i, 6))
+                    || (rd != 0 && rs1 != 0 && imm_i == 0)
+                    || (rd == SP
+                        && rs1 == SP
+                        && imm_i != 0
+                        && imm_i & 0xF == 0
+                        && fits_signed(imm_i, 10))
+                    || (is_compressed_register(rd)
+                        && rs1 == SP
+                        && imm_i != 0
+                        && imm_i & 0b11 == 0
+                        && (0..1024).contains(&imm_i))
+            }
+            // c.slli
+            0b001 => funct7 == 0 && rd == rs1 && rd != 0,
+            // c.srli, c.srai
+            0b101 => funct7 & !0b010_0000 == 0 && rd == rs1 && is_compressed_register(rd),
+            // c.andi
+            0b111 => rd == rs1 && is_compressed_register(rd) && fits_signed(imm_i, 6),
+            _ => false,
+        },
+        0b011_0011 => match (funct7, funct3) {
+            // c.add, c.mv
+            (0, 0b000) => rd != 0 && rs2 != 0 && (rd == rs1 || rs1 == 0 || rs2 == rd),
+            // c.sub
+            (0b010_0000, 0b000) => {
+                rd == rs1 && is_compressed_register(rd) && is_compressed_register(rs2)
+            }
+            // c.xor, c.or, c.and
+            (0, 0b100 | 0b110 | 0b111) => {
+                is_compressed_register(rd)
+                    && is_compressed_register(rs2)
+                    && (rd == rs1 || rd == rs2)
+                    && is_compressed_register(rs1)
+            }
+            _ => false,
+        },
+        // c.ebreak
+        0b111_0011 => inst == 0x0010_0073,
+        _ => false,
+    }
+}
+
+impl InstructionClass {
+    /// Classify a raw RISC-V instruction (16-bit instructions are expected in the lower half).
+    ///
+    /// # Arguments
+    /// - `inst`: The raw RISC-V instruction.
+    pub fn of(inst: u32) -> Self {
+        if inst & 0b11 != 0b11 {
+            return InstructionClass::Compressed;
+        }
+
+        let funct3 = (inst >> 12) & 0b111;
+        match inst & 0x7F {
+            0b000_0011 => InstructionClass::Load,
+            0b010_0011 => InstructionClass::Store,
+            0b110_0011 => InstructionClass::Branch,
+            0b110_1111 => InstructionClass::Jal,
+            0b110_0111 => InstructionClass::Jalr,
+            0b011_0111 => InstructionClass::Lui,
+            0b001_0111 => InstructionClass::Auipc,
+            0b001_0011 => InstructionClass::OpImm,
+            0b011_0011 if inst >> 25 == 0b000_0001 => InstructionClass::MulDiv,
+            0b011_0011 => InstructionClass::Op,
+            0b010_1111 => InstructionClass::Atomic,
+            0b000_1111 => InstructionClass::MiscMem,
+            0b111_0011 if funct3 == 0 => InstructionClass::System,
+            0b111_0011 => InstructionClass::Csr,
+            _ => InstructionClass::Unknown,
+        }
+    }
+}
+
+/// Instruction Usage Report
+///
+/// Number of instructions of each class in the code sections of a binary (check [`super::instruction_usage`]),
+/// e.g. to verify that a binary doesn't use an extension before deploying it.
+///
+/// The transpiler keeps the instruction sizes (compressed instructions stay 16-bit), so image size is reduced by
+/// building the guest with the C extension (e.g. `riscv32imac`), not by the transpiler: code addresses can't change
+/// after linking. [`InstructionUsage::compressible`] estimates the savings for binaries built without it.
+///
+/// Example:
+/// ```
+/// use embive::transpiler::{InstructionClass, InstructionUsage};
+///
+/// let mut usage = InstructionUsage::default();
+/// usage.add(&[
+///     0x33, 0x05, 0xb5, 0x02, // mul a0, a0, a1
+///     0x05, 0x05, // addi a0, a0, 1
+/// ]);
+///
+/// assert_eq!(usage.count(InstructionClass::MulDiv), 1);
+/// assert!(usage.uses_m() && usage.uses_c() && !usage.uses_a());
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct InstructionUsage {
+    counts: [u32; INSTRUCTION_CLASSES],
+    compressible: u32,
+}
+
+impl InstructionUsage {
+    /// Count the instructions of a raw RISC-V code buffer.
+    ///
+    /// # Arguments
+    /// - `code`: The raw RISC-V instructions (a trailing odd byte is ignored).
+    pub fn add(&mut self, code: &[u8]) {
+        let mut i = 0;
+        while i + 2 <= code.len() {
+            // Unwrap is safe because the slice is 2 bytes
+            let low = u16::from_le_bytes(code[i..i + 2].try_into().unwrap()) as u32;
+            let inst = match code.get(i + 2..i + 4) {
+                // Unwrap is safe because the slice is 2 bytes
+                Some(high) if low & 0b11 == 0b11 => {
+                    low | (u16::from_le_bytes(high.try_into().unwrap()) as u32) << 16
+                }
+                _ => low,
+            };
+
+            self.counts[InstructionClass::of(inst) as usize] += 1;
+            if inst & 0b11 == 0b11 && is_compressible(inst) {
+                self.compressible += 1;
+            }
+            i += if inst & 0b11 == 0b11 { 4 } else { 2 };
+        }
+    }
+
+    /// Get the number of instructions of a class.
+    ///
+    /// # Arguments
+    /// - `class`: The instruction class.
+    pub fn count(&self, class: InstructionClass) -> u32 {
+        self.counts[class as usize]
+    }
+
+    /// Get the number of instructions of each class (indexed by [`InstructionClass`]).
+    pub fn counts(&self) -> &[u32; INSTRUCTION_CLASSES] {
+        &self.counts
+    }
+
+    /// Get the number of 32-bit instructions with a 16-bit equivalent (C extension).
+    ///
+    /// Building the guest with the C extension would save up to 2 bytes per compressible instruction
+    /// (an estimate: the compiler may also pick other instructions or registers).
+    pub fn compressible(&self) -> u32 {
+        self.compressible
+    }
+
+    /// Get the total number of instructions.
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Check if the M extension (multiplication and division) is used.
+    pub fn uses_m(&self) -> bool {
+        self.count(InstructionClass::MulDiv) > 0
+    }
+
+    /// Check if the A extension (atomics) is used.
+    pub fn uses_a(&self) -> bool {
+        self.count(InstructionClass::Atomic) > 0
+    }
+
+    /// Check if the C extension (compressed instructions) is used.
+    pub fn uses_c(&self) -> bool {
+        self.count(InstructionClass::Compressed) > 0
+    }
+
+    /// Check if the Zicsr extension (control and status registers) is used.
+    pub fn uses_zicsr(&self) -> bool {
+        self.count(InstructionClass::Csr) > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressible() {
+        let compressible = [
+            0x0001_2503, // lw   a0, 0(sp)
+            0x00a1_2e23, // sw   a0, 28(sp)
+            0x0004_a403, // lw   s0, 0(s1)
+            0x0004_0463, // beqz s0, 8
+            0x0080_006f, // j    8
+            0x0000_8067, // ret
+            0x0000_1537, // lui  a0, 1
+            0x0015_0513, // addi a0, a0, 1
+            0x0010_0513, // li   a0, 1
+            0x0005_8513, // mv   a0, a1
+            0xff01_0113, // addi sp, sp, -16
+            0x0081_0413, // addi s0, sp, 8
+            0x0015_1513, // slli a0, a0, 1
+            0x4014_5413, // srai s0, s0, 1
+            0x00b5_0533, // add  a0, a0, a1
+            0x4094_0433, // sub  s0, s0, s1
+            0x0084_f433, // and  s0, s1, s0
+            0x0010_0073, // ebreak
+        ];
+        let incompressible = [
+            0x1001_2503, // lw   a0, 256(sp)
+            0x0005_a283, // lw   t0, 0(a1)
+            0x00b5_0463, // beq  a0, a1, 8
+            0x0080_02ef, // jal  t0, 8
+            0x0002_0537, // lui  a0, 0x20
+            0x0015_8513, // addi a0, a1, 1
+            0x40b2_82b3, // sub  t0, t0, a1
+            0x02b5_0533, // mul  a0, a0, a1
+            0x0000_0073, // ecall
+        ];
+
+        for inst in compressible {
+            assert!(is_compressible(inst), "{inst:#010x}");
+        }
+        for inst in incompressible {
+            assert!(!is_compressible(inst), "{inst:#010x}");
+        }
+
+        let mut usage = InstructionUsage::default();
+        usage.add(&[
+            0x13, 0x05, 0x15, 0x00, // addi a0, a0, 1
+            0x05, 0x05, // c.addi a0, 1
+            0x33, 0x05, 0xb5, 0x02, // mul  a0, a0, a1
+        ]);
+        assert_eq!(usage.compressible(), 1);
+    }
+}
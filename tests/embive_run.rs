@@ -0,0 +1,74 @@
+//! `embive-run` binary tests.
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Run `embive-run` with `args`, writing `stdin` to the process.
+fn embive_run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_embive-run"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run embive-run");
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdio() {
+    let output = embive_run(&["tests/stdio.elf"], b"hello");
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(output.stdout, b"hello");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "err\nembive-run: exit status 5\n"
+    );
+}
+
+#[test]
+fn test_app() {
+    let output = embive_run(&["--ram", "16K", "tests/app.elf"], b"");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_limit() {
+    let output = embive_run(&["--limit", "5", "tests/stdio.elf"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("instruction limit reached"));
+}
+
+#[test]
+fn test_trace() {
+    let output = embive_run(&["--trace=json", "tests/stdio.elf"], b"abc");
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr
+        .lines()
+        .any(|line| line.starts_with(r#"{"pc":"0x00000000""#)));
+}
+
+#[test]
+fn test_invalid_arguments() {
+    assert_eq!(embive_run(&[], b"").status.code(), Some(2));
+    assert_eq!(
+        embive_run(&["--ram", "huge", "tests/stdio.elf"], b"")
+            .status
+            .code(),
+        Some(2)
+    );
+    assert_eq!(
+        embive_run(&["tests/missing.elf"], b"").status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_strace() {
+    let output = embive_run(&["--strace", "tests/stdio.elf"], b"hey");
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("] read(0, \"hey\", 64) = 3\n"));
+    assert!(stderr.contains("] exit(3) = 0\n"));
+}
@@ -0,0 +1,19 @@
+# Library object: functions used by main.o.
+# Build: llvm-mc -triple=riscv32 -mattr=+m,+a,+c,-relax -filetype=obj lib.s -o lib.o
+    .text
+    .globl add_one
+add_one:
+.Lincrement:
+    auipc t0, %pcrel_hi(increment)
+    lw t0, %pcrel_lo(.Lincrement)(t0)
+    add a0, a0, t0
+    ret
+
+    .globl double
+double:
+    slli a0, a0, 1
+    ret
+
+    .globl finish
+finish:
+    ebreak
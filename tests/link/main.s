@@ -0,0 +1,27 @@
+# Main object: calls into lib.o, which reads `increment` (defined here).
+# Build: llvm-mc -triple=riscv32 -mattr=+m,+a,+c,-relax -filetype=obj main.s -o main.o
+    .text
+    .globl _start
+_start:
+    li a0, 41
+    call add_one
+.Lresult:
+    auipc a1, %pcrel_hi(result)
+    sw a0, %pcrel_lo(.Lresult)(a1)
+    lui a2, %hi(table)
+    lw a3, %lo(table)(a2)
+    c.jal double
+    bnez a0, finish
+    j finish
+
+    .data
+    .globl increment
+increment:
+    .word 1
+table:
+    .word add_one
+
+    .bss
+    .globl result
+result:
+    .word 0
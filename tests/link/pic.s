@@ -0,0 +1,37 @@
+# Position-independent object: global data through the GOT, jump table and label differences.
+# Build: llvm-mc -triple=riscv32 -mattr=+m,+a,+c,-relax -filetype=obj pic.s -o pic.o
+    .text
+    .globl pic_start
+pic_start:
+.Lgot:
+    auipc a1, %got_pcrel_hi(counter)
+    lw a1, %pcrel_lo(.Lgot)(a1)
+    lw a0, 0(a1)
+.Ltable_address:
+    auipc a2, %pcrel_hi(.Ltable)
+    addi a2, a2, %pcrel_lo(.Ltable_address)
+    lw a3, 4(a2)
+    add a3, a3, a2
+    jr a3
+.Lcase0:
+    li a0, 0
+    ebreak
+.Lcase1:
+    addi a0, a0, 1
+    sw a0, 0(a1)
+    ebreak
+
+    .section .rodata
+.Ltable:
+    .word .Lcase0 - .Ltable
+    .word .Lcase1 - .Ltable
+    .globl counter_offset
+counter_offset:
+    .word counter - .
+    .half .Lcase1 - .Lcase0
+    .byte .Lcase1 - .Lcase0
+
+    .data
+    .globl counter
+counter:
+    .word 41
@@ -0,0 +1,9 @@
+#!/bin/sh
+# Build the rv32mi tests (from this directory): llvm-mc and ld.lld (or `rust-lld -flavor gnu`).
+set -e
+LD=${LD:-ld.lld}
+for test in csr illegal ma_fetch mcsr sbreak scall; do
+    llvm-mc -triple=riscv32 -mattr=+m,+a,+c,-relax -filetype=obj "$test.s" -o "$test.o"
+    $LD -T link.ld "$test.o" -o "../$test.elf"
+    rm "$test.o"
+done
@@ -0,0 +1,100 @@
+# CSR instructions (read/write, set and clear, immediates), WARL fields and access faults.
+    .include "env.s"
+    RVTEST_CODE_BEGIN
+
+    li gp, 2
+    csrwi mscratch, 3
+    csrr a0, mscratch
+    li a1, 3
+    bne a0, a1, fail
+
+    li gp, 3
+    csrrci a0, mscratch, 1
+    li a1, 3
+    bne a0, a1, fail
+
+    li gp, 4
+    csrrsi a0, mscratch, 4
+    li a1, 2
+    bne a0, a1, fail
+
+    li gp, 5
+    csrrwi a0, mscratch, 2
+    li a1, 6
+    bne a0, a1, fail
+
+    li gp, 6
+    li a1, 0xbad1dea
+    csrrw a0, mscratch, a1
+    li a1, 2
+    bne a0, a1, fail
+
+    li gp, 7
+    li a1, 0x0001dea
+    csrrc a0, mscratch, a1
+    li a1, 0xbad1dea
+    bne a0, a1, fail
+
+    li gp, 8
+    li a1, 0x000beef
+    csrrs a0, mscratch, a1
+    li a1, 0xbad0000
+    bne a0, a1, fail
+
+    li gp, 9
+    csrr a0, mscratch
+    li a1, 0xbadbeef
+    bne a0, a1, fail
+
+    # mepc bit 0 is always clear (16-bit instruction alignment)
+    li gp, 10
+    li a1, 3
+    csrw mepc, a1
+    csrr a0, mepc
+    li a1, 2
+    bne a0, a1, fail
+
+    # mtvec only supports the direct mode
+    li gp, 11
+    csrr s1, mtvec
+    ori a1, s1, 1
+    csrw mtvec, a1
+    csrr a0, mtvec
+    csrw mtvec, s1
+    bne a0, s1, fail
+
+    # Unimplemented CSRs are illegal (no supervisor mode)
+    li gp, 12
+bad12:
+    csrr a0, sscratch
+    j fail
+after12:
+
+    # Machine CSRs are illegal in user mode
+    li gp, 13
+    RVTEST_ENTER_USER bad13
+bad13:
+    csrr a0, mscratch
+    j fail
+
+mtvec_handler:
+    li t0, 12
+    beq gp, t0, trap12
+    li t0, 13
+    beq gp, t0, trap13
+    j fail
+
+trap12:
+    RVTEST_CHECK_TRAP 2, bad12
+    RVTEST_RETURN after12
+
+trap13:
+    RVTEST_CHECK_TRAP 2, bad13
+    # Trapped from user mode (MPP = 0)
+    csrr t0, mstatus
+    li t1, 0x1800
+    and t0, t0, t1
+    bnez t0, fail
+    j pass
+
+    RVTEST_CODE_END
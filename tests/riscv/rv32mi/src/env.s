@@ -0,0 +1,72 @@
+# Machine-mode test environment (riscv-tests `p` environment, adapted to Embive).
+#
+# Tests run in machine mode from `_start` (user mode has access to all memory), the test number is kept in `gp`. Traps jump to the test `mtvec_handler`.
+# The result is reported through the exit syscall (`a7` = 93): `a0` is `0` on success, `(gp << 1) | 1` on failure.
+
+.macro RVTEST_CODE_BEGIN
+    .section .text.init, "ax"
+    .globl _start
+_start:
+    # Give user mode access to all memory, if PMP is implemented (skipped through the trap otherwise)
+    la t0, 1f
+    csrw mtvec, t0
+    li t0, -1
+    csrw pmpaddr0, t0
+    li t0, 0x1F
+    csrw pmpcfg0, t0
+    .balign 4
+1:
+    la t0, trap_vector
+    csrw mtvec, t0
+    li gp, 0
+    j reset_vector
+    .balign 4
+trap_vector:
+    j mtvec_handler
+reset_vector:
+.endm
+
+.macro RVTEST_CODE_END
+pass:
+    fence
+    li a7, 93
+    li a0, 0
+    ecall
+fail:
+    fence
+1:  beqz gp, 1b
+    slli gp, gp, 1
+    ori gp, gp, 1
+    li a7, 93
+    mv a0, gp
+    ecall
+    unimp
+.endm
+
+# Enter user mode (`mret` with `mstatus.MPP` clear) at `label`.
+.macro RVTEST_ENTER_USER label
+    la t0, \label
+    csrw mepc, t0
+    li t0, 0x1800
+    csrc mstatus, t0
+    mret
+.endm
+
+# Check an exception trap: `mcause` is `cause` and `mepc` is `label`, fails otherwise.
+.macro RVTEST_CHECK_TRAP cause, label
+    csrr t0, mcause
+    li t1, \cause
+    bne t0, t1, fail
+    csrr t0, mepc
+    la t1, \label
+    bne t0, t1, fail
+.endm
+
+# Return from the trap handler to `label` (machine mode).
+.macro RVTEST_RETURN label
+    la t0, \label
+    csrw mepc, t0
+    li t0, 0x1800
+    csrs mstatus, t0
+    mret
+.endm
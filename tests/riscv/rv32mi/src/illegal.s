@@ -0,0 +1,62 @@
+# Illegal instructions trap (mcause 2), mtval is 0 (the original instruction bits aren't kept).
+    .include "env.s"
+    RVTEST_CODE_BEGIN
+
+    # Defined illegal instruction (c.unimp)
+    li gp, 2
+bad2:
+    .word 0
+    j fail
+after2:
+
+    # Write to a read-only CSR (unimp, csrrw zero, cycle, zero)
+    li gp, 3
+bad3:
+    unimp
+    j fail
+after3:
+
+    # Privileged instructions are illegal in user mode
+    li gp, 4
+    RVTEST_ENTER_USER bad4
+bad4:
+    mret
+    j fail
+
+    li gp, 5
+    RVTEST_ENTER_USER bad5
+bad5:
+    wfi
+    j fail
+
+mtvec_handler:
+    csrr t0, mtval
+    bnez t0, fail
+    li t0, 2
+    beq gp, t0, trap2
+    li t0, 3
+    beq gp, t0, trap3
+    li t0, 4
+    beq gp, t0, trap4
+    li t0, 5
+    beq gp, t0, trap5
+    j fail
+
+trap2:
+    RVTEST_CHECK_TRAP 2, bad2
+    RVTEST_RETURN after2
+
+trap3:
+    RVTEST_CHECK_TRAP 2, bad3
+    RVTEST_RETURN after3
+
+trap4:
+    RVTEST_CHECK_TRAP 2, bad4
+    li gp, 5
+    RVTEST_ENTER_USER bad5
+
+trap5:
+    RVTEST_CHECK_TRAP 2, bad5
+    j pass
+
+    RVTEST_CODE_END
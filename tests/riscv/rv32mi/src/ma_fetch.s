@@ -0,0 +1,38 @@
+# Instruction fetches are 16-bit aligned (C extension): jumps to halfword boundaries don't trap,
+# and jalr clears bit 0 of the target.
+    .include "env.s"
+    RVTEST_CODE_BEGIN
+
+    li gp, 2
+    la t1, target2
+    addi t1, t1, 1
+    jalr zero, 0(t1)
+    j fail
+target2:
+
+    li gp, 3
+    la t1, target3
+    jr t1
+    j fail
+    .balign 4
+    c.nop
+target3:
+
+    # The return address is the next instruction
+    li gp, 4
+    la t1, target4
+    jalr t0, 0(t1)
+return4:
+    j fail
+    .balign 4
+    c.nop
+target4:
+    la t1, return4
+    bne t0, t1, fail
+
+    j pass
+
+mtvec_handler:
+    j fail
+
+    RVTEST_CODE_END
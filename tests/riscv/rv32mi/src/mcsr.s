@@ -0,0 +1,49 @@
+# Machine information CSRs: readable, and read-only.
+    .include "env.s"
+    RVTEST_CODE_BEGIN
+
+    # mhartid is 0 (single hart)
+    li gp, 2
+    csrr a0, mhartid
+    bnez a0, fail
+
+    # misa reports RV32 (MXL = 1)
+    li gp, 3
+    csrr a0, misa
+    srli a0, a0, 30
+    li a1, 1
+    bne a0, a1, fail
+
+    # misa reports the base integer ISA (I)
+    li gp, 4
+    csrr a0, misa
+    andi a0, a0, 1 << 8
+    beqz a0, fail
+
+    # Machine IDs are readable
+    li gp, 5
+    csrr a0, mimpid
+    csrr a0, marchid
+    csrr a0, mvendorid
+
+    # Writing a read-only CSR is illegal
+    li gp, 6
+bad6:
+    csrw mvendorid, zero
+    j fail
+after6:
+
+    # Setting no bits of a read-only CSR is a read (no write)
+    li gp, 7
+    csrrs a0, mhartid, zero
+    bnez a0, fail
+
+    j pass
+
+mtvec_handler:
+    li t0, 6
+    bne gp, t0, fail
+    RVTEST_CHECK_TRAP 2, bad6
+    RVTEST_RETURN after6
+
+    RVTEST_CODE_END
@@ -0,0 +1,43 @@
+# ebreak traps (mcause 3), mtval is the ebreak address.
+    .include "env.s"
+    RVTEST_CODE_BEGIN
+
+    li gp, 2
+bad2:
+    .option push
+    .option norvc
+    ebreak
+    .option pop
+    j fail
+after2:
+
+    li gp, 3
+bad3:
+    c.ebreak
+    j fail
+after3:
+
+    j pass
+
+mtvec_handler:
+    li t0, 2
+    beq gp, t0, trap2
+    li t0, 3
+    beq gp, t0, trap3
+    j fail
+
+trap2:
+    RVTEST_CHECK_TRAP 3, bad2
+    csrr t0, mtval
+    la t1, bad2
+    bne t0, t1, fail
+    RVTEST_RETURN after2
+
+trap3:
+    RVTEST_CHECK_TRAP 3, bad3
+    csrr t0, mtval
+    la t1, bad3
+    bne t0, t1, fail
+    RVTEST_RETURN after3
+
+    RVTEST_CODE_END
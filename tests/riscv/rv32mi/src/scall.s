@@ -0,0 +1,19 @@
+# ecall from user mode traps (mcause 8), machine-mode ecalls are host syscalls.
+    .include "env.s"
+    RVTEST_CODE_BEGIN
+
+    li gp, 2
+    RVTEST_ENTER_USER bad2
+bad2:
+    ecall
+    j fail
+
+mtvec_handler:
+    li t0, 2
+    bne gp, t0, fail
+    RVTEST_CHECK_TRAP 8, bad2
+    csrr t0, mtval
+    bnez t0, fail
+    j pass
+
+    RVTEST_CODE_END
@@ -0,0 +1,45 @@
+# Stdio guest (`embive-run` test): echoes stdin to stdout, writes a message to stderr and exits with
+# the number of bytes read.
+# Build: llvm-mc -triple=riscv32 -mattr=+m,+a,+c -filetype=obj stdio.s -o stdio.o
+#        ld.lld --image-base=0 -Ttext=0 -Tbss=0x80000000 stdio.o -o stdio.elf
+    .text
+    .globl _start
+_start:
+    # read(0, buffer, 64)
+    li a0, 0
+    lui a1, %hi(buffer)
+    addi a1, a1, %lo(buffer)
+    li a2, 64
+    li a7, 63
+    ecall
+    mv s0, a1
+
+    # write(1, buffer, length)
+    li a0, 1
+    lui a1, %hi(buffer)
+    addi a1, a1, %lo(buffer)
+    mv a2, s0
+    li a7, 64
+    ecall
+
+    # write(2, "err\n", 4)
+    li t0, 0x0a727265
+    lui a1, %hi(message)
+    addi a1, a1, %lo(message)
+    sw t0, 0(a1)
+    li a0, 2
+    li a2, 4
+    li a7, 64
+    ecall
+
+    # exit(length)
+    mv a0, s0
+    li a7, 93
+    ecall
+    ebreak
+
+    .bss
+buffer:
+    .space 64
+message:
+    .space 4